@@ -0,0 +1,71 @@
+/*
+ * C interface of the Shogi-Aho-AI engine (src/ffi.rs).
+ *
+ * Build the library with `cargo build --release --lib --no-default-features
+ * --features ffi` and link against target/release/libshogi_aho_ai.so
+ * (shogi_aho_ai.dll / libshogi_aho_ai.dylib).
+ *
+ * Players are 1 (Player1, moves first) and 2. Squares count from the top
+ * left (x to the right, y downwards), Player2's side of the board.
+ */
+#ifndef SHOGI_AHO_AI_H
+#define SHOGI_AHO_AI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque board handle */
+typedef struct AhoBoard AhoBoard;
+
+/* AhoMove.piece when a normal move does not promote */
+#define AHO_NO_PIECE 0xFF
+
+/*
+ * drop:  0 for a move of a piece on the board, 1 for a drop from the hand
+ *        (from_x/from_y unused)
+ * piece: promoted kind of a promotion, dropped kind of a drop, else
+ *        AHO_NO_PIECE. Kinds: 0-7 S_King, S_Rook, S_Bishop, S_Gold,
+ *        S_Silver, S_Knight, S_Lance, S_Pawn; 8-13 S_ProRook, S_ProBishop,
+ *        S_ProSilver, S_ProKnight, S_ProLance, S_ProPawn; 14-19 C_King,
+ *        C_Queen, C_Rook, C_Bishop, C_Knight, C_Pawn; 20+ pieces.toml pieces
+ */
+typedef struct AhoMove {
+    uint8_t drop;
+    uint8_t from_x;
+    uint8_t from_y;
+    uint8_t to_x;
+    uint8_t to_y;
+    uint8_t piece;
+} AhoMove;
+
+/* New board of a named setup ("ShogiOnly", "ChessOnly", "Fair", ...), or NULL */
+AhoBoard *aho_board_new(const char *setup);
+
+/* Free a board; NULL is ignored */
+void aho_board_free(AhoBoard *board);
+
+/*
+ * Write up to `capacity` legal moves of `player` to `out` and return the total
+ * number (capacity 0 sizes the buffer). -1 for a NULL board or unknown player.
+ */
+ptrdiff_t aho_legal_moves(const AhoBoard *board, int player, AhoMove *out, size_t capacity);
+
+/* Play `move` for `player`: 0, or -1 if it is not legal (board unchanged) */
+int aho_apply_move(AhoBoard *board, int player, const AhoMove *move);
+
+/*
+ * Search the best move for `player` within `time_limit_ms`: 1 with the move in
+ * `out`, 0 if `player` has no legal move, -1 on bad arguments. A depth still
+ * running when the limit passes is abandoned; the deepest finished one counts.
+ */
+int aho_search(const AhoBoard *board, int player, uint32_t time_limit_ms, AhoMove *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SHOGI_AHO_AI_H */
@@ -13,6 +13,10 @@ import subprocess
 from pathlib import Path
 from typing import List, Tuple, Optional
 import argparse
+import struct
+
+# Must match FEATURE_VERSION in src/ml/features.rs
+FEATURE_VERSION = 1
 
 def extract_features_from_kifu(kifu_path: str, binary_path: str) -> Optional[List[dict]]:
     """
@@ -44,6 +48,133 @@ def extract_features_from_kifu(kifu_path: str, binary_path: str) -> Optional[Lis
         return None
 
 
+def load_selfplay_dataset(path: str) -> List[dict]:
+    """
+    Load a binary dataset written by `selfplay --export-dataset`
+    (selfplay_kifu/{Board}/{RunID}/dataset.bin or the deduplicated
+    dataset_dedup.bin). Layout is documented in src/selfplay/dataset.rs.
+
+    Returns:
+        List of dicts with {game_num, move_number, player, outcome, weight, score, depth, mirrored, features}
+        (one per position; a position recorded twice by a resumed run keeps the last copy).
+        `weight` is the number of positions merged into the record (1 for version 1 files).
+        `mirrored` marks the left-right reflections written by `selfplay --mirror` (version 3).
+    """
+    with open(path, 'rb') as f:
+        data = f.read()
+
+    if data[:4] != b'AHOD':
+        raise ValueError(f"{path} is not a selfplay dataset")
+    version, feature_size = struct.unpack_from('<HI', data, 4)
+    if version == 1:
+        record_header = struct.Struct('<IHBbiB6sH')
+    elif version == 2:
+        record_header = struct.Struct('<IHBQfIiB6sH')
+    elif version == 3:
+        record_header = struct.Struct('<IHBQfIiBB6sH')
+    else:
+        raise ValueError(f"Unsupported dataset version {version}")
+
+    records = {}
+    offset = 10
+    while offset + record_header.size <= len(data):
+        fields = record_header.unpack_from(data, offset)
+        if version == 1:
+            game_num, move_number, player, outcome, score, depth, _move, count = fields
+            weight, mirrored = 1, 0
+        elif version == 2:
+            game_num, move_number, player, _hash, outcome, weight, score, depth, _move, count = fields
+            mirrored = 0
+        else:
+            game_num, move_number, player, _hash, outcome, weight, score, depth, mirrored, _move, count = fields
+        end = offset + record_header.size + count * 6
+        if end > len(data):
+            break  # Record cut off by an interrupted run
+        features = np.zeros(feature_size, dtype=np.float32)
+        for i in range(count):
+            index, value = struct.unpack_from('<Hf', data, offset + record_header.size + i * 6)
+            features[index] = value
+        records[(game_num, move_number, mirrored)] = {
+            'game_num': game_num,
+            'move_number': move_number,
+            'player': player,
+            'outcome': float(outcome),
+            'weight': weight,
+            'score': score,
+            'depth': depth,
+            'mirrored': bool(mirrored),
+            'features': features,
+        }
+        offset = end
+
+    return list(records.values())
+
+
+def prepare_dataset_from_selfplay(kifu_dir: str, output_path: str, boards: List[str]):
+    """
+    Prepare HDF5 dataset from the binary datasets exported during selfplay.
+    Unlike kifu-based preparation, outcomes are the real game results.
+    Runs with a deduplicated dataset_dedup.bin use it instead of dataset.bin.
+    """
+    dataset_files = []
+    for board in boards:
+        for raw_path in glob.glob(f"{kifu_dir}/{board}/*/dataset.bin"):
+            dedup_path = Path(raw_path).with_name('dataset_dedup.bin')
+            dataset_files.append(str(dedup_path) if dedup_path.exists() else raw_path)
+
+    print(f"Found {len(dataset_files)} selfplay datasets for boards: {', '.join(boards)}")
+    if not dataset_files:
+        print("No dataset.bin found! Run SelfPlay with --export-dataset first.")
+        return
+
+    all_features = []
+    all_outcomes = []
+    all_scores = []
+    all_weights = []
+    all_augmented_flags = []
+    total_games = 0
+
+    for file_path in dataset_files:
+        board_setup = Path(file_path).parent.parent.name
+        records = load_selfplay_dataset(file_path)
+        total_games += len({r['game_num'] for r in records})
+        for record in records:
+            for aug_idx, aug_features in enumerate(augment_position(record['features'], board_setup)):
+                all_features.append(aug_features)
+                all_outcomes.append(record['outcome'])
+                all_scores.append(record['score'])
+                all_weights.append(record['weight'])
+                all_augmented_flags.append(aug_idx > 0)
+
+    if not all_features:
+        print("Warning: No training data found in the selfplay datasets.")
+        return
+
+    features_array = np.array(all_features, dtype=np.float32)
+    print(f"\nDataset Summary:")
+    print(f"  Games: {total_games}")
+    print(f"  Total Samples: {len(all_features)}")
+    print(f"  Feature Shape: {features_array.shape}")
+
+    Path(output_path).parent.mkdir(parents=True, exist_ok=True)
+    with h5py.File(output_path, 'w') as f:
+        f.create_dataset('features', data=features_array, compression='gzip')
+        f.create_dataset('moves', data=np.zeros(len(all_features), dtype=np.int32), compression='gzip')
+        f.create_dataset('outcomes', data=np.array(all_outcomes, dtype=np.float32), compression='gzip')
+        f.create_dataset('scores', data=np.array(all_scores, dtype=np.int32), compression='gzip')
+        f.create_dataset('weights', data=np.array(all_weights, dtype=np.float32), compression='gzip')
+        f.create_dataset('augmented', data=np.array(all_augmented_flags, dtype=np.bool_), compression='gzip')
+
+        f.attrs['version'] = '0.2.0'
+        f.attrs['feature_version'] = FEATURE_VERSION
+        f.attrs['num_games'] = total_games
+        f.attrs['num_samples'] = len(all_features)
+        f.attrs['augmentation_enabled'] = True
+
+    print(f"\nDataset saved to {output_path}")
+    return len(all_features)
+
+
 def encode_move_to_index(move_data: dict) -> int:
     """
     Encode a move as action index.
@@ -247,6 +378,7 @@ def prepare_dataset(kifu_dir: str, output_path: str, boards: List[str], binary_p
         
         # Store metadata
         f.attrs['version'] = '0.2.0'
+        f.attrs['feature_version'] = FEATURE_VERSION
         f.attrs['num_games'] = total_games_processed
         f.attrs['num_samples'] = len(all_features)
         f.attrs['augmentation_enabled'] = True
@@ -297,6 +429,8 @@ if __name__ == '__main__':
     parser.add_argument('--boards', default='Fair', help='Comma-separated board types (e.g., Fair,ShogiOnly) or "all"')
     parser.add_argument('--binary', default='target/release/extract_features', help='Path to extract_features binary')
     parser.add_argument('--version', default='0.1.0', help='Dataset version')
+    parser.add_argument('--from-selfplay-dataset', action='store_true',
+                        help='Read dataset.bin files exported by selfplay --export-dataset instead of kifu JSON')
     
     args = parser.parse_args()
     
@@ -326,6 +460,14 @@ if __name__ == '__main__':
     output_dir = Path(output_path).parent
     output_dir.mkdir(parents=True, exist_ok=True)
     
+    if args.from_selfplay_dataset:
+        print(f"Dataset version: {args.version}")
+        print(f"Output path: {output_path}")
+        num_samples = prepare_dataset_from_selfplay(args.kifu_dir, output_path, boards)
+        if not args.output:
+            generate_readme(str(Path(output_path).parent), args.version, boards, num_samples if num_samples else 0)
+        exit(0)
+
     # Ensure binary exists
     binary_path = Path(args.binary)
     if not binary_path.exists():
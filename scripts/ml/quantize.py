@@ -0,0 +1,71 @@
+import argparse
+import os
+import onnx
+from onnx import helper, numpy_helper
+from onnxruntime.quantization import QuantType, quantize_dynamic
+
+
+def gemm_to_matmul(model):
+    """
+    Rewrite Gemm nodes as MatMul + Add. Dynamic quantization only covers
+    MatMul, and Gemm is what both train.py and the Rust trainer export.
+    """
+    graph = model.graph
+    initializers = {init.name: init for init in graph.initializer}
+    nodes = []
+    for node in graph.node:
+        attrs = {a.name: helper.get_attribute_value(a) for a in node.attribute}
+        weight = initializers.get(node.input[1]) if node.op_type == 'Gemm' else None
+        if (weight is None or attrs.get('transA', 0) or attrs.get('alpha', 1.0) != 1.0
+                or attrs.get('beta', 1.0) != 1.0):
+            nodes.append(node)
+            continue
+
+        if attrs.get('transB', 0):
+            transposed = numpy_helper.from_array(numpy_helper.to_array(weight).T.copy(), weight.name)
+            weight.CopyFrom(transposed)
+        if len(node.input) < 3 or not node.input[2]:
+            nodes.append(helper.make_node('MatMul', node.input[:2], node.output, name=node.name))
+            continue
+        product = f'{node.output[0]}_matmul'
+        nodes.append(helper.make_node('MatMul', node.input[:2], [product], name=f'{node.name}_matmul'))
+        nodes.append(helper.make_node('Add', [product, node.input[2]], node.output, name=f'{node.name}_add'))
+
+    del graph.node[:]
+    graph.node.extend(nodes)
+    return model
+
+
+def quantize(model_path, output_path):
+    """
+    Quantize a model's weights to int8. Activations are quantized at run time,
+    so the model keeps its float board_features/value interface and needs no
+    scale metadata. Custom metadata (lineage, feature version) is carried over.
+    """
+    model = onnx.load(model_path)
+    props = {prop.key: prop.value for prop in model.metadata_props}
+
+    prepared = f'{output_path}.prepared.onnx'
+    onnx.save(gemm_to_matmul(model), prepared)
+    try:
+        quantize_dynamic(prepared, output_path, weight_type=QuantType.QInt8)
+    finally:
+        os.remove(prepared)
+
+    quantized = onnx.load(output_path)
+    props['quantization'] = 'int8-dynamic'
+    helper.set_model_props(quantized, props)
+    onnx.save(quantized, output_path)
+
+    before, after = os.path.getsize(model_path), os.path.getsize(output_path)
+    print(f"{output_path}: {before / 1024:.0f} KiB -> {after / 1024:.0f} KiB")
+
+
+if __name__ == '__main__':
+    parser = argparse.ArgumentParser(description='Quantize a trained model to int8 for CPU inference')
+    parser.add_argument('model', type=str, help='ONNX model to quantize')
+    parser.add_argument('--output', type=str, help='Output path (default: <model>_int8.onnx next to the model)')
+    args = parser.parse_args()
+
+    output = args.output or f"{os.path.splitext(args.model)[0]}_int8.onnx"
+    quantize(args.model, output)
@@ -1,4 +1,7 @@
 import argparse
+import hashlib
+import json
+import os
 import torch
 import torch.nn as nn
 import torch.optim as optim
@@ -8,6 +11,7 @@ import numpy as np
 import onnx
 import time
 from model import ShogiNet
+from prepare_dataset import FEATURE_VERSION
 
 
 class ShogiDataset(Dataset):
@@ -75,7 +79,103 @@ def train_epoch(model, dataloader, criterion_policy, criterion_value, optimizer,
     return total_loss / len(dataloader)
 
 
-def train(data_path, model_path, epochs=10, batch_size=64, lr=0.001, version='0.1.0'):
+def file_sha256(path):
+    """SHA-256 of a file, read in chunks"""
+    digest = hashlib.sha256()
+    with open(path, 'rb') as f:
+        for chunk in iter(lambda: f.read(1 << 20), b''):
+            digest.update(chunk)
+    return digest.hexdigest()
+
+
+def read_lineage(onnx_path):
+    """Custom metadata of an existing ONNX model as a dict"""
+    return {prop.key: prop.value for prop in onnx.load(onnx_path).metadata_props}
+
+
+def stamp_lineage(onnx_path, version, data_path, parent_model=None, generation=None):
+    """
+    Embed model lineage into the ONNX custom metadata, read back in Rust by
+    ModelRegistry::get_model_lineage and recorded in every kifu the model plays.
+
+    generation defaults to the parent's generation + 1 (0 without a parent).
+    """
+    with h5py.File(data_path, 'r') as f:
+        feature_version = int(f.attrs.get('feature_version', FEATURE_VERSION))
+
+    if generation is None:
+        generation = 0
+        if parent_model:
+            parent_generation = read_lineage(parent_model).get('generation')
+            generation = int(parent_generation) + 1 if parent_generation is not None else 1
+
+    lineage = {
+        'version': version,
+        'generation': str(generation),
+        'training_data_hash': file_sha256(data_path),
+        'feature_version': str(feature_version),
+    }
+    if parent_model:
+        lineage['parent_model'] = parent_model
+
+    model = onnx.load(onnx_path)
+    onnx.helper.set_model_props(model, lineage)
+    onnx.save(model, onnx_path)
+    print(f"Lineage: generation {generation}, feature version {feature_version}"
+          + (f", parent {parent_model}" if parent_model else ""))
+    return lineage
+
+
+def stamp_feature_version(onnx_path):
+    """
+    Declare the current FEATURE_VERSION in an existing model's metadata, keeping
+    its other entries. For models exported before the version was recorded:
+    the Rust evaluator refuses models that do not declare it.
+    """
+    props = read_lineage(onnx_path)
+    props['feature_version'] = str(FEATURE_VERSION)
+    model = onnx.load(onnx_path)
+    onnx.helper.set_model_props(model, props)
+    onnx.save(model, onnx_path)
+    print(f"{onnx_path}: feature version {FEATURE_VERSION}")
+
+
+def update_manifest(onnx_path, training_positions, parent_model=None):
+    """
+    Record the model in the manifest.json of the nearest enclosing 'models'
+    directory (read by ModelRegistry and shown in the model selection menu).
+    Models outside a 'models' directory are not recorded.
+    """
+    from datetime import datetime
+
+    path = os.path.abspath(onnx_path)
+    models_dir = os.path.dirname(path)
+    while os.path.basename(models_dir) != 'models':
+        parent = os.path.dirname(models_dir)
+        if parent == models_dir:
+            return
+        models_dir = parent
+
+    manifest_path = os.path.join(models_dir, 'manifest.json')
+    manifest = {'models': {}}
+    if os.path.exists(manifest_path):
+        with open(manifest_path) as f:
+            manifest = json.load(f)
+    key = os.path.relpath(path, models_dir).replace(os.sep, '/')
+    entry = manifest.setdefault('models', {}).setdefault(key, {})
+    entry['training_positions'] = training_positions
+    entry['created_at'] = datetime.now().astimezone().isoformat()
+    if parent_model:
+        entry['parent_model'] = parent_model
+
+    tmp_path = manifest_path + '.tmp'
+    with open(tmp_path, 'w') as f:
+        json.dump(manifest, f, indent=2)
+    os.replace(tmp_path, manifest_path)
+
+
+def train(data_path, model_path, epochs=10, batch_size=64, lr=0.001, version='0.1.0',
+          parent_model=None, generation=None):
     """Main training loop"""
     start_time = time.time()
     device = torch.device('cuda' if torch.cuda.is_available() else 'cpu')
@@ -115,6 +215,8 @@ def train(data_path, model_path, epochs=10, batch_size=64, lr=0.001, version='0.
         dynamic_axes={'board_features': {0: 'batch_size'}}
     )
     print(f"ONNX model exported to {onnx_path}")
+    lineage = stamp_lineage(onnx_path, version, data_path, parent_model, generation)
+    update_manifest(onnx_path, len(dataset), parent_model)
 
     training_time = time.time() - start_time
     
@@ -122,7 +224,8 @@ def train(data_path, model_path, epochs=10, batch_size=64, lr=0.001, version='0.
         'final_loss': final_loss,
         'training_time': training_time,
         'num_samples': len(dataset),
-        'input_size': dataset.input_size
+        'input_size': dataset.input_size,
+        'lineage': lineage,
     }
 
 
@@ -151,6 +254,9 @@ def update_readme(output_dir: str, version: str, data_path: str, epochs: int, ba
 - **Learning Rate**: {lr}
 - **Final Loss**: {stats.get('final_loss', 0.0):.4f}
 - **Training Time**: {stats.get('training_time', 0.0):.1f}s
+- **Generation**: {stats.get('lineage', {}).get('generation', 'N/A')}
+- **Parent Model**: {stats.get('lineage', {}).get('parent_model', 'none')}
+- **Training Data SHA-256**: `{stats.get('lineage', {}).get('training_data_hash', 'N/A')}`
 - **Architecture**:
   - Input: {stats.get('input_size', 'Unknown')} features
   - Hidden: 256 units
@@ -186,8 +292,16 @@ if __name__ == '__main__':
     parser.add_argument('--batch-size', type=int, default=64, help='Batch size')
     parser.add_argument('--version', type=str, default='0.1.0', help='Model version')
     parser.add_argument('--board-type', type=str, default='Fair', help='Board type (Fair, ChessOnly, ShogiOnly, ALL, etc.)')
+    parser.add_argument('--parent-model', type=str, help='ONNX model this one derives from (recorded in the lineage metadata)')
+    parser.add_argument('--generation', type=int, help='Model generation (default: parent generation + 1, or 0)')
+    parser.add_argument('--stamp-feature-version', type=str, metavar='ONNX',
+                        help='Only declare the current feature version in an existing model (trained on the current layout) and exit')
     
     args = parser.parse_args()
+
+    if args.stamp_feature_version:
+        stamp_feature_version(args.stamp_feature_version)
+        raise SystemExit(0)
     
     
     # Determine data path
@@ -227,7 +341,9 @@ if __name__ == '__main__':
         epochs=args.epochs,
         batch_size=args.batch_size,
         lr=0.001,
-        version=args.version
+        version=args.version,
+        parent_model=args.parent_model,
+        generation=args.generation
     )
     
     # Update README if using version-based directory
@@ -0,0 +1,148 @@
+//! Kifu annotation
+//!
+//! Replays a saved game and searches every position to a fixed depth,
+//! reporting the engine's preferred move, score and principal variation next
+//! to the move actually played. Search results are kept in a per-kifu
+//! [`AnalysisCache`] on disk, so re-running at a higher depth starts from the
+//! earlier work and re-running at the same depth is answered from the cache.
+
+use crate::core::{Move, PlayerId};
+use crate::game::replay::ReplayViewer;
+use crate::game::{KifuData, SearchHealth};
+use crate::logic::apply_move;
+use crate::player::ai::analysis_cache::AnalysisCache;
+use crate::player::ai::eval::{eval_breakdown, EvalBreakdown};
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const CACHE_DIR: &str = "analysis_cache";
+
+pub struct AnalysisConfig {
+    pub depth: usize,
+    /// `None` disables the on-disk cache
+    pub cache_path: Option<PathBuf>,
+    /// Attach the handcrafted evaluation's per-piece breakdown to every position
+    pub heatmap: bool,
+}
+
+#[derive(Serialize)]
+pub struct PositionAnalysis {
+    /// 1-based number of the move played from this position
+    pub move_number: usize,
+    pub player: PlayerId,
+    pub played: Move,
+    pub best_move: Option<Move>,
+    /// From Player1's point of view
+    pub score: i32,
+    pub depth: usize,
+    pub pv: Vec<Move>,
+    pub cached: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<SearchHealth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap: Option<EvalBreakdown>,
+}
+
+#[derive(Serialize)]
+pub struct AnalysisReport {
+    pub board_setup: String,
+    pub depth: usize,
+    pub evaluator: String,
+    pub positions: Vec<PositionAnalysis>,
+    /// Positions answered from the cache without searching
+    pub cache_hits: usize,
+    pub nodes: usize,
+}
+
+/// `analysis_cache/{parent dir}_{file stem}.json`, so that kifus with the same
+/// file name from different runs get separate caches
+pub fn default_cache_path(kifu_path: &Path) -> PathBuf {
+    let stem = kifu_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "kifu".to_string());
+    let name = match kifu_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|p| p.to_string_lossy())
+    {
+        Some(parent) => format!("{}_{}.json", parent, stem),
+        None => format!("{}.json", stem),
+    };
+    Path::new(CACHE_DIR).join(name)
+}
+
+pub fn analyze_kifu(kifu: &KifuData, config: &AnalysisConfig) -> anyhow::Result<AnalysisReport> {
+    let mut cache = match &config.cache_path {
+        Some(path) => AnalysisCache::load(path)?,
+        None => AnalysisCache::new(),
+    };
+
+    // One engine per side: the search runs from the engine's own perspective
+    let engines = [
+        AlphaBetaAI::new(
+            PlayerId::Player1,
+            "Analysis",
+            AIStrength::Strong,
+            None,
+            true,
+        ),
+        AlphaBetaAI::new(
+            PlayerId::Player2,
+            "Analysis",
+            AIStrength::Strong,
+            None,
+            true,
+        ),
+    ];
+
+    let (mut board, mut player) = ReplayViewer::initial_position(kifu);
+    let mut positions = Vec::new();
+    let mut cache_hits = 0;
+    let mut nodes = 0;
+
+    for (i, played) in kifu.moves.iter().enumerate() {
+        let engine = &engines[if player == PlayerId::Player1 { 0 } else { 1 }];
+        if let Some(result) = engine.analyze(&board, config.depth, &mut cache) {
+            if result.cached {
+                cache_hits += 1;
+            }
+            nodes += result.nodes;
+            positions.push(PositionAnalysis {
+                move_number: i + 1,
+                player,
+                played: *played,
+                best_move: result.best_move,
+                score: if player == PlayerId::Player1 {
+                    result.score
+                } else {
+                    -result.score
+                },
+                depth: result.depth,
+                pv: result.pv,
+                cached: result.cached,
+                health: result.health,
+                heatmap: config.heatmap.then(|| eval_breakdown(&board)),
+            });
+        }
+        eprint!("\rAnalyzing: {}/{} moves", i + 1, kifu.moves.len());
+
+        board = apply_move(&board, played, player);
+        player = player.opponent();
+    }
+    eprintln!();
+
+    if let Some(path) = &config.cache_path {
+        cache.save(path)?;
+    }
+
+    Ok(AnalysisReport {
+        board_setup: kifu.board_setup.clone(),
+        depth: config.depth,
+        evaluator: engines[0].evaluator_name(),
+        positions,
+        cache_hits,
+        nodes,
+    })
+}
@@ -0,0 +1,145 @@
+//! Engine benchmark
+//!
+//! Searches a fixed suite of positions to a fixed depth and reports nodes,
+//! NPS and transposition table hit rates, so that performance regressions in
+//! `logic` and `player::ai` are measurable between commits.
+
+use crate::core::setup::setup_from_strings;
+use crate::core::{Board, PieceKind, PlayerId};
+use crate::logic::ZobristHasher;
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use crate::selfplay::BoardSetupType;
+use serde::Serialize;
+
+pub struct BenchConfig {
+    pub depth: usize,
+    pub strength: AIStrength,
+}
+
+#[derive(Serialize)]
+pub struct BenchResult {
+    pub name: String,
+    pub nodes: usize,
+    pub time_ms: u128,
+    pub nps: u64,
+    pub tt_probes: usize,
+    pub tt_hits: usize,
+    pub best_move: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BenchReport {
+    pub depth: usize,
+    pub strength: String,
+    pub evaluator: String,
+    pub positions: Vec<BenchResult>,
+    pub total_nodes: usize,
+    pub total_time_ms: u128,
+    pub nps: u64,
+    pub tt_hit_rate: f64,
+}
+
+/// Nodes per second, guarding against sub-millisecond searches
+fn nps(nodes: usize, time_ms: u128) -> u64 {
+    (nodes as u128 * 1000 / time_ms.max(1)) as u64
+}
+
+/// The fixed benchmark suite: (name, board, side to move)
+pub fn bench_positions() -> Vec<(String, Board, PlayerId)> {
+    let mut positions: Vec<(String, Board, PlayerId)> = [
+        BoardSetupType::ShogiOnly,
+        BoardSetupType::ChessOnly,
+        BoardSetupType::StandardMixed,
+        BoardSetupType::ReversedMixed,
+        BoardSetupType::Fair,
+        BoardSetupType::ReversedFair,
+    ]
+    .iter()
+    .map(|setup| (setup.to_string(), setup.create_board(), PlayerId::Player1))
+    .collect();
+
+    // Shogi middlegame with pieces in hand (drop-heavy)
+    let mut drops = setup_from_strings(
+        &[
+            "l n . g k . . n l",
+            ". r . . . g s b .",
+            "p . p p p p . p p",
+            ". p . . . . p . .",
+            ". . . . . . . . .",
+            ". . P . . . . . .",
+            "P P . P P P P P P",
+            ". . G . . . . R .",
+            "L N S . K G S N L",
+        ],
+        true,
+        true,
+        None,
+        None,
+    );
+    drops.add_to_hand(PlayerId::Player1, PieceKind::S_Bishop);
+    drops.add_to_hand(PlayerId::Player2, PieceKind::S_Silver);
+    drops.add_to_hand(PlayerId::Player2, PieceKind::S_Pawn);
+    positions.push(("ShogiDrops".to_string(), drops, PlayerId::Player2));
+
+    // Chess endgame (rook and pawns)
+    let endgame = setup_from_strings(
+        &[
+            ". . . . . . ck .",
+            ". . . . . cp cp .",
+            ". . . . . . . cp",
+            ". . . . . . . .",
+            ". . . . . . . .",
+            ". . . . . . CP .",
+            ". . . . . CP . CP",
+            ". . . CR . . CK .",
+        ],
+        false,
+        false,
+        None,
+        None,
+    );
+    positions.push(("ChessEndgame".to_string(), endgame, PlayerId::Player1));
+
+    positions
+}
+
+pub fn run_bench(config: &BenchConfig) -> BenchReport {
+    let mut results = Vec::new();
+    let mut evaluator = String::new();
+
+    for (name, mut board, player) in bench_positions() {
+        let ai = AlphaBetaAI::new(player, "Bench", config.strength, None, true);
+        evaluator = ai.evaluator_name();
+
+        board.zobrist_hash = ZobristHasher::compute_hash(&board, player);
+        board.history = vec![board.zobrist_hash];
+        let best_move = ai.search_fixed_depth(&board, config.depth);
+        let info = (*ai.last_thinking.borrow()).expect("search always records thinking data");
+
+        results.push(BenchResult {
+            name,
+            nodes: info.nodes,
+            time_ms: info.time_ms,
+            nps: nps(info.nodes, info.time_ms),
+            tt_probes: info.tt_probes,
+            tt_hits: info.tt_hits,
+            best_move: best_move.map(|mv| mv.to_string()),
+        });
+    }
+
+    let total_nodes = results.iter().map(|r| r.nodes).sum();
+    let total_time_ms = results.iter().map(|r| r.time_ms).sum();
+    let total_probes: usize = results.iter().map(|r| r.tt_probes).sum();
+    let total_hits: usize = results.iter().map(|r| r.tt_hits).sum();
+
+    BenchReport {
+        depth: config.depth,
+        strength: format!("{:?}", config.strength),
+        evaluator,
+        positions: results,
+        total_nodes,
+        total_time_ms,
+        nps: nps(total_nodes, total_time_ms),
+        tt_hit_rate: total_hits as f64 / total_probes.max(1) as f64,
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! This binary is called by Python scripts to convert kifu games into training data.
 
-use shogi_aho_ai::core::{Board, PlayerId};
+use shogi_aho_ai::core::PlayerId;
 use shogi_aho_ai::game::KifuData;
 use shogi_aho_ai::logic::apply_move;
 use shogi_aho_ai::ml::features::BoardFeatureExtractor;
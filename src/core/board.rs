@@ -1,4 +1,5 @@
 use super::piece::{Piece, PieceKind};
+use super::ruleset::Ruleset;
 use super::types::{PlayerConfig, PlayerId, Position};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -40,6 +41,15 @@ pub struct Board {
     /// The move that led to this state (used for display/highlighting).
     pub last_move: Option<crate::core::Move>,
 
+    /// Column of a chess pawn that just advanced two squares.
+    /// The opponent may capture it en passant on the next move only.
+    #[serde(default)]
+    pub en_passant: Option<usize>,
+
+    /// Game-wide rules (promotion zone, repetition, move limits).
+    #[serde(default)]
+    pub rules: Ruleset,
+
     /// Zobrist Hash of the current position.
     /// Used for Transposition Table lookups and repetition detection.
     #[serde(skip)]
@@ -61,6 +71,8 @@ impl Board {
             hand: HashMap::new(),
             player_configs: HashMap::new(),
             last_move: None,
+            en_passant: None,
+            rules: Ruleset::default(),
             zobrist_hash: 0,
             history: Vec::new(),
         }
@@ -110,6 +122,17 @@ impl Board {
         self.player_configs.insert(player, config);
     }
 
+    /// Sets the game-wide rules, replacing player configurations the ruleset
+    /// overrides.
+    pub fn set_ruleset(&mut self, rules: Ruleset) {
+        for player in [PlayerId::Player1, PlayerId::Player2] {
+            if let Some(config) = rules.player_config(player) {
+                self.set_player_config(player, config);
+            }
+        }
+        self.rules = rules;
+    }
+
     /// retrieves the configuration for a player. Returns default if not set.
     pub fn get_player_config(&self, player: PlayerId) -> PlayerConfig {
         self.player_configs
@@ -128,6 +151,22 @@ impl Board {
             })
             .map(|(pos, _)| *pos)
     }
+
+    /// The position reflected left-right. Movement rules do not depend on the
+    /// file, so it is as legal and as good for each side as the original.
+    /// The hash and repetition history are not carried over.
+    pub fn mirrored(&self) -> Board {
+        let mut board = Board::new(self.width, self.height);
+        for (pos, piece) in &self.pieces {
+            board.place_piece(Position::new(self.width - 1 - pos.x, pos.y), piece.clone());
+        }
+        board.hand = self.hand.clone();
+        board.player_configs = self.player_configs.clone();
+        board.rules = self.rules.clone();
+        board.last_move = self.last_move.map(|mv| mv.mirrored(self.width));
+        board.en_passant = self.en_passant.map(|x| self.width - 1 - x);
+        board
+    }
 }
 
 #[cfg(test)]
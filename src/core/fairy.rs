@@ -0,0 +1,270 @@
+//! Fairy pieces defined in `pieces.toml` or `pieces.json`
+//!
+//! Each definition becomes a [`PieceKind::Custom`] that move generation,
+//! evaluation, Zobrist hashing and feature extraction understand without
+//! changes to `piece.rs`. Setup strings write them as `@` plus the symbol
+//! (`@A` for Player1, `@a` for Player2, `+@A` promoted).
+//!
+//! Evaluation features are per built-in kind (the NN input layout is fixed),
+//! so each piece names the built-in kind it is treated as there (`like`).
+//!
+//! ```toml
+//! [[piece]]
+//! name = "Chancellor"
+//! symbol = "A"
+//! display_char = "将"
+//! like = "C_Rook"
+//! value = 1400
+//! shogi = false
+//! # As seen by Player1 (forward is -y); mirrored for Player2
+//! moves = [
+//!   { Slide = [0, -1] }, { Slide = [0, 1] }, { Slide = [-1, 0] }, { Slide = [1, 0] },
+//!   { Step = [-1, -2] }, { Step = [1, -2] }, { Step = [-2, -1] }, { Step = [2, -1] },
+//!   { Step = [-2, 1] }, { Step = [2, 1] }, { Step = [-1, 2] }, { Step = [1, 2] },
+//! ]
+//! ```
+
+use super::piece::{MoveStep, PieceKind};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Files looked up in the working directory, in order
+pub const PIECE_FILES: [&str; 2] = ["pieces.toml", "pieces.json"];
+
+/// Custom kinds the Zobrist tables have keys for
+pub const MAX_CUSTOM_PIECES: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PieceDef {
+    pub name: String,
+    /// ASCII letter for setup strings (`@A`) and the ASCII glyph style
+    pub symbol: String,
+    /// Character on the board display
+    pub display_char: char,
+    /// Moves as seen by Player1 (forward is -y); mirrored for Player2
+    pub moves: Vec<MoveStep>,
+    /// Name of the custom piece this one promotes to
+    #[serde(default)]
+    pub promotes_to: Option<String>,
+    /// Material value in centipawns
+    pub value: i32,
+    /// Built-in kind it counts as for NN/NNUE features, piece-square
+    /// tables and king safety
+    pub like: PieceKind,
+    /// Shogi-style piece: promotes in the promotion zone and, when captured,
+    /// goes to the hand
+    #[serde(default = "default_shogi")]
+    pub shogi: bool,
+}
+
+fn default_shogi() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct PieceFile {
+    #[serde(rename = "piece", default)]
+    pieces: Vec<PieceDef>,
+}
+
+static PIECES: OnceCell<Vec<PieceDef>> = OnceCell::new();
+
+/// The registry, loading [`PIECE_FILES`] on first use
+fn registry() -> &'static [PieceDef] {
+    PIECES.get_or_init(load_piece_files)
+}
+
+fn load_piece_files() -> Vec<PieceDef> {
+    let Some(path) = PIECE_FILES.iter().map(Path::new).find(|p| p.exists()) else {
+        return Vec::new();
+    };
+    load(path).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to load {}: {}", path.display(), e);
+        eprintln!("Playing without custom pieces");
+        Vec::new()
+    })
+}
+
+/// The pieces of a piece file (see [`crate::util::load_config`]), checked
+pub fn load(path: &Path) -> anyhow::Result<Vec<PieceDef>> {
+    let file: PieceFile = crate::util::load_config(path)?;
+    validate(&file.pieces)?;
+    Ok(file.pieces)
+}
+
+fn validate(pieces: &[PieceDef]) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        pieces.len() <= MAX_CUSTOM_PIECES,
+        "at most {} custom pieces are supported",
+        MAX_CUSTOM_PIECES
+    );
+    for (i, def) in pieces.iter().enumerate() {
+        anyhow::ensure!(
+            def.symbol.len() == 1 && def.symbol.chars().all(|c| c.is_ascii_alphabetic()),
+            "{}: symbol must be one ASCII letter",
+            def.name
+        );
+        anyhow::ensure!(
+            !matches!(def.like, PieceKind::Custom(_)),
+            "{}: `like` must be a built-in kind",
+            def.name
+        );
+        anyhow::ensure!(
+            pieces[..i]
+                .iter()
+                .all(|other| other.name != def.name
+                    && !other.symbol.eq_ignore_ascii_case(&def.symbol)),
+            "{}: duplicate name or symbol",
+            def.name
+        );
+        if let Some(target) = &def.promotes_to {
+            anyhow::ensure!(
+                pieces.iter().any(|other| &other.name == target),
+                "{}: unknown promotion target {}",
+                def.name,
+                target
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The custom pieces in registry order (`PieceKind::Custom(i)` is the i-th)
+pub fn pieces() -> &'static [PieceDef] {
+    registry()
+}
+
+/// Definition of a custom kind, `None` if this build has no such piece (a
+/// board from a kifu, the network or a library caller with other pieces)
+pub fn def(index: u8) -> Option<&'static PieceDef> {
+    registry().get(index as usize)
+}
+
+/// Custom kind with setup-string symbol `symbol` (case-insensitive)
+pub fn by_symbol(symbol: &str) -> Option<PieceKind> {
+    registry()
+        .iter()
+        .position(|def| def.symbol.eq_ignore_ascii_case(symbol))
+        .map(|i| PieceKind::Custom(i as u8))
+}
+
+fn by_name(name: &str) -> Option<PieceKind> {
+    registry()
+        .iter()
+        .position(|def| def.name == name)
+        .map(|i| PieceKind::Custom(i as u8))
+}
+
+/// The kind custom kind `index` promotes to
+pub fn promoted(index: u8) -> Option<PieceKind> {
+    by_name(def(index)?.promotes_to.as_deref()?)
+}
+
+static PROMOTIONS: Lazy<Vec<Vec<PieceKind>>> = Lazy::new(|| {
+    (0..registry().len() as u8)
+        .map(|i| promoted(i).into_iter().collect())
+        .collect()
+});
+
+/// [`promoted`] as the slice `Piece::promotion_kinds` returns
+pub fn promotion_kinds(index: u8) -> &'static [PieceKind] {
+    PROMOTIONS.get(index as usize).map_or(&[], Vec::as_slice)
+}
+
+/// The custom kind that promotes to custom kind `index`, if any
+pub fn unpromoted(index: u8) -> Option<PieceKind> {
+    let name = &def(index)?.name;
+    registry()
+        .iter()
+        .position(|def| def.promotes_to.as_ref() == Some(name))
+        .map(|i| PieceKind::Custom(i as u8))
+}
+
+/// Registers the test pieces: Wazir (`@W`), a shogi piece promoting to
+/// Chancellor (`@A`), a chess-like piece. Call before the registry is first used.
+#[cfg(test)]
+pub(crate) fn install_test_pieces() {
+    let installed = PIECES.get_or_init(sample_pieces);
+    assert!(
+        installed.len() == 2 && installed[0].name == "Wazir",
+        "custom pieces were loaded before the test pieces were installed"
+    );
+}
+
+#[cfg(test)]
+fn sample_pieces() -> Vec<PieceDef> {
+    let pieces = vec![
+        PieceDef {
+            name: "Wazir".to_string(),
+            symbol: "W".to_string(),
+            display_char: '臣',
+            moves: vec![
+                MoveStep::Step(0, -1),
+                MoveStep::Step(0, 1),
+                MoveStep::Step(-1, 0),
+                MoveStep::Step(1, 0),
+            ],
+            promotes_to: Some("Chancellor".to_string()),
+            value: 300,
+            like: PieceKind::S_Silver,
+            shogi: true,
+        },
+        PieceDef {
+            name: "Chancellor".to_string(),
+            symbol: "A".to_string(),
+            display_char: '将',
+            moves: vec![
+                MoveStep::Slide(0, -1),
+                MoveStep::Slide(0, 1),
+                MoveStep::Slide(-1, 0),
+                MoveStep::Slide(1, 0),
+                MoveStep::Step(-1, -2),
+                MoveStep::Step(1, -2),
+            ],
+            promotes_to: None,
+            value: 1300,
+            like: PieceKind::S_ProRook,
+            shogi: true,
+        },
+    ];
+    validate(&pieces).expect("sample pieces are valid");
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_file() {
+        let file: PieceFile = toml::from_str(
+            "[[piece]]\nname = \"Ferz\"\nsymbol = \"F\"\ndisplay_char = \"F\"\n\
+             like = \"S_Silver\"\nvalue = 200\n\
+             moves = [{ Step = [-1, -1] }, { Step = [1, -1] }, { Step = [-1, 1] }, { Step = [1, 1] }]\n",
+        )
+        .unwrap();
+        assert!(validate(&file.pieces).is_ok());
+        assert!(file.pieces[0].shogi && file.pieces[0].promotes_to.is_none());
+
+        let mut bad = file.pieces.clone();
+        bad[0].promotes_to = Some("Nothing".to_string());
+        assert!(validate(&bad).is_err());
+        let mut bad = file.pieces.clone();
+        bad.push(bad[0].clone());
+        assert!(validate(&bad).is_err());
+
+        install_test_pieces();
+        let wazir = by_symbol("w").unwrap();
+        assert_eq!(wazir, PieceKind::Custom(0));
+        assert_eq!(promoted(0), Some(PieceKind::Custom(1)));
+        assert_eq!(unpromoted(1), Some(wazir));
+
+        // Kinds this build does not define are harmless
+        let unknown = PieceKind::Custom(MAX_CUSTOM_PIECES as u8);
+        assert!(def(MAX_CUSTOM_PIECES as u8).is_none());
+        assert_eq!(unknown.display_char(), '?');
+        assert!(promotion_kinds(MAX_CUSTOM_PIECES as u8).is_empty());
+        assert!(!unknown.is_shogi());
+    }
+}
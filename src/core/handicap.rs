@@ -0,0 +1,185 @@
+//! Handicap (komaochi) and chess odds starts
+//!
+//! The handicap is always given by Player1, the side that moves first, which
+//! matches both conventions: in shogi the stronger player (uwate) removes
+//! pieces and moves first, in chess the odds giver plays White. "Left" and
+//! "queen side" are the low-x side, the left of Player1 looking up the board.
+
+use super::{Board, PieceKind, PlayerId, Position};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Handicap {
+    /// 香落ち: the left lance
+    Lance,
+    /// 角落ち
+    Bishop,
+    /// 飛車落ち
+    Rook,
+    /// 二枚落ち: rook and bishop
+    TwoPiece,
+    /// 四枚落ち: rook, bishop and both lances
+    FourPiece,
+    /// The f-pawn (one file right of the king)
+    PawnOdds,
+    /// The queen-side knight
+    KnightOdds,
+    /// The queen-side rook
+    RookOdds,
+    /// The queen (the queen-side one if there are two)
+    QueenOdds,
+}
+
+impl Handicap {
+    pub const ALL: [Handicap; 9] = [
+        Handicap::Lance,
+        Handicap::Bishop,
+        Handicap::Rook,
+        Handicap::TwoPiece,
+        Handicap::FourPiece,
+        Handicap::PawnOdds,
+        Handicap::KnightOdds,
+        Handicap::RookOdds,
+        Handicap::QueenOdds,
+    ];
+
+    /// Name used on the command line (`--handicap rook`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Handicap::Lance => "lance",
+            Handicap::Bishop => "bishop",
+            Handicap::Rook => "rook",
+            Handicap::TwoPiece => "2-piece",
+            Handicap::FourPiece => "4-piece",
+            Handicap::PawnOdds => "pawn-odds",
+            Handicap::KnightOdds => "knight-odds",
+            Handicap::RookOdds => "rook-odds",
+            Handicap::QueenOdds => "queen-odds",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|h| h.name() == name)
+    }
+
+    /// Description for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            Handicap::Lance => "Lance handicap (香落ち)",
+            Handicap::Bishop => "Bishop handicap (角落ち)",
+            Handicap::Rook => "Rook handicap (飛車落ち)",
+            Handicap::TwoPiece => "2-piece handicap (二枚落ち)",
+            Handicap::FourPiece => "4-piece handicap (四枚落ち)",
+            Handicap::PawnOdds => "Pawn odds (f-pawn)",
+            Handicap::KnightOdds => "Knight odds",
+            Handicap::RookOdds => "Rook odds",
+            Handicap::QueenOdds => "Queen odds",
+        }
+    }
+
+    /// Squares of the pieces Player1 gives up on `board`, or `None` if
+    /// Player1 does not have them (e.g. shogi handicaps for a chess side)
+    fn removed_squares(self, board: &Board) -> Option<Vec<Position>> {
+        let own = |kind: PieceKind| -> Vec<Position> {
+            let mut squares: Vec<Position> = board
+                .pieces
+                .iter()
+                .filter(|(_, piece)| piece.owner == PlayerId::Player1 && piece.kind == kind)
+                .map(|(&pos, _)| pos)
+                .collect();
+            // Low x first, back rank first
+            squares.sort_by_key(|pos| (pos.x, std::cmp::Reverse(pos.y)));
+            squares
+        };
+        let first = |kind: PieceKind| own(kind).first().copied();
+        let squares = match self {
+            Handicap::Lance => vec![first(PieceKind::S_Lance)?],
+            Handicap::Bishop => vec![first(PieceKind::S_Bishop)?],
+            Handicap::Rook => vec![first(PieceKind::S_Rook)?],
+            Handicap::TwoPiece => vec![first(PieceKind::S_Rook)?, first(PieceKind::S_Bishop)?],
+            Handicap::FourPiece => {
+                let lances = own(PieceKind::S_Lance);
+                if lances.len() < 2 {
+                    return None;
+                }
+                vec![
+                    first(PieceKind::S_Rook)?,
+                    first(PieceKind::S_Bishop)?,
+                    lances[0],
+                    lances[lances.len() - 1],
+                ]
+            }
+            Handicap::PawnOdds => {
+                let king = first(PieceKind::C_King)?;
+                vec![own(PieceKind::C_Pawn)
+                    .into_iter()
+                    .find(|pos| pos.x == king.x + 1)?]
+            }
+            Handicap::KnightOdds => vec![first(PieceKind::C_Knight)?],
+            Handicap::RookOdds => vec![first(PieceKind::C_Rook)?],
+            Handicap::QueenOdds => vec![first(PieceKind::C_Queen)?],
+        };
+        Some(squares)
+    }
+
+    /// Whether Player1 has the pieces this handicap removes
+    pub fn applies_to(self, board: &Board) -> bool {
+        self.removed_squares(board).is_some()
+    }
+
+    /// Removes Player1's handicap pieces from `board`
+    pub fn apply(self, board: &mut Board) -> anyhow::Result<()> {
+        let squares = self.removed_squares(board).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} needs pieces Player1 does not have in this setup",
+                self.label()
+            )
+        })?;
+        for pos in squares {
+            board.remove_piece(pos);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::setup::BoardSetupType;
+
+    #[test]
+    fn test_handicaps() {
+        let count = |board: &Board, kind: PieceKind| {
+            board
+                .pieces
+                .values()
+                .filter(|p| p.owner == PlayerId::Player1 && p.kind == kind)
+                .count()
+        };
+
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        Handicap::Lance.apply(&mut board).unwrap();
+        assert_eq!(count(&board, PieceKind::S_Lance), 1);
+        assert!(board.get_piece(Position::new(0, 8)).is_none());
+        assert!(board.get_piece(Position::new(0, 0)).is_some());
+
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        Handicap::FourPiece.apply(&mut board).unwrap();
+        for kind in [PieceKind::S_Lance, PieceKind::S_Rook, PieceKind::S_Bishop] {
+            assert_eq!(count(&board, kind), 0);
+        }
+        assert_eq!(board.pieces.len(), 40 - 4);
+
+        let mut chess = BoardSetupType::ChessOnly.create_board();
+        assert!(!Handicap::Rook.applies_to(&chess));
+        Handicap::PawnOdds.apply(&mut chess).unwrap();
+        assert!(chess.get_piece(Position::new(5, 6)).is_none());
+        Handicap::RookOdds.apply(&mut chess).unwrap();
+        assert!(chess.get_piece(Position::new(0, 7)).is_none());
+        assert!(chess.get_piece(Position::new(7, 7)).is_some());
+
+        for handicap in Handicap::ALL {
+            assert_eq!(Handicap::from_name(handicap.name()), Some(handicap));
+        }
+    }
+}
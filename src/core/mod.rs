@@ -1,6 +1,10 @@
 pub mod board;
+pub mod fairy;
+pub mod handicap;
 pub mod r#move;
+pub mod notation;
 pub mod piece;
+pub mod ruleset;
 pub mod serialization;
 pub mod setup;
 pub mod types;
@@ -8,5 +12,6 @@ pub mod types;
 pub use board::Board;
 pub use piece::{MoveStep, Piece, PieceKind};
 pub use r#move::Move;
+pub use ruleset::Ruleset;
 
 pub use types::{PlayerConfig, PlayerId, Position};
@@ -1,5 +1,6 @@
 use super::piece::PieceKind;
-use super::types::Position;
+use super::types::{PlayerId, Position};
+use super::Board;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -16,6 +17,21 @@ pub enum Move {
     },
 }
 
+impl Move {
+    /// The same move reflected left-right on a board `width` squares wide
+    pub fn mirrored(self, width: usize) -> Move {
+        let flip = |pos: Position| Position::new(width - 1 - pos.x, pos.y);
+        match self {
+            Move::Normal { from, to, promote } => Move::Normal {
+                from: flip(from),
+                to: flip(to),
+                promote,
+            },
+            Move::Drop { kind, to } => Move::Drop { kind, to: flip(to) },
+        }
+    }
+}
+
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -32,3 +48,221 @@ impl fmt::Display for Move {
         }
     }
 }
+
+/// Promotion written after the squares of a typed move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromotionSpec {
+    None,
+    /// `+`: the shogi promotion
+    Promote,
+    /// `q`, `r`, `b` or `n` (optionally `=q`): a chess pawn promotion
+    Chess(char),
+}
+
+/// Reads a square at the start of `s`: USI (`7g`, file counted from the
+/// right, rank letter from the top) or algebraic (`e2`, file letter from the
+/// left, rank counted from the bottom). Returns the square and the rest.
+fn parse_square<'a>(s: &'a str, board: &Board) -> anyhow::Result<(Position, &'a str)> {
+    let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+    let (file, rank, rest) = if digits > 0 {
+        let letter = s[digits..]
+            .chars()
+            .next()
+            .filter(|c| c.is_ascii_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("expected a rank letter after '{}'", &s[..digits]))?;
+        let file: usize = s[..digits].parse()?;
+        let x = board.width.checked_sub(file).filter(|_| file > 0);
+        let y = (letter as u8 - b'a') as usize;
+        (x, Some(y).filter(|&y| y < board.height), &s[digits + 1..])
+    } else {
+        let letter = s
+            .chars()
+            .next()
+            .filter(|c| c.is_ascii_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("expected a square at '{}'", s))?;
+        let digits = s[1..].chars().take_while(|c| c.is_ascii_digit()).count();
+        anyhow::ensure!(digits > 0, "expected a rank number after '{}'", letter);
+        let rank: usize = s[1..1 + digits].parse()?;
+        let x = (letter as u8 - b'a') as usize;
+        let y = board.height.checked_sub(rank).filter(|_| rank > 0);
+        (Some(x).filter(|&x| x < board.width), y, &s[1 + digits..])
+    };
+    match (file, rank) {
+        (Some(x), Some(y)) => Ok((Position::new(x, y), rest)),
+        _ => anyhow::bail!("'{}' is off the board", &s[..s.len() - rest.len()]),
+    }
+}
+
+/// Parses a typed move and checks it against the legal moves of `player`.
+///
+/// Accepts USI (`7g7f`, `8h2b+`, `P*5e`) and algebraic (`e2e4`, `e7e8q`)
+/// notation; `-` between the squares is ignored. The error says why an entry
+/// is not a legal move.
+pub fn parse_move(text: &str, board: &Board, player: PlayerId) -> anyhow::Result<Move> {
+    use crate::logic::{legal_moves, pseudo_legal_moves};
+
+    let text: String = text
+        .chars()
+        .filter(|&c| c != '-' && !c.is_whitespace())
+        .collect();
+    let legal = legal_moves(board, player);
+    let pseudo = pseudo_legal_moves(board, player);
+
+    if let Some((piece, square)) = text.split_once('*') {
+        let (to, rest) = parse_square(square, board)?;
+        anyhow::ensure!(
+            rest.is_empty(),
+            "unexpected '{}' after the drop square",
+            rest
+        );
+        let hand = board.hand.get(&player);
+        let in_hand = |kind: &PieceKind| hand.and_then(|h| h.get(kind)).is_some_and(|&c| c > 0);
+        // A plain letter is a shogi piece unless only the chess piece is in hand
+        let kind = [true, false]
+            .into_iter()
+            .filter_map(|shogi| super::setup::parse_piece_kind(piece, shogi))
+            .find(in_hand)
+            .ok_or_else(|| anyhow::anyhow!("no '{}' in your hand", piece))?;
+        let mv = Move::Drop { kind, to };
+        if legal.contains(&mv) {
+            return Ok(mv);
+        }
+        anyhow::ensure!(board.get_piece(to).is_none(), "{} is occupied", square);
+        anyhow::ensure!(
+            board.get_player_config(player).can_drop,
+            "you cannot drop pieces in this game"
+        );
+        anyhow::ensure!(
+            !crate::logic::rules::is_dead_end(kind, player, to.y, board.height),
+            "a {:?} dropped on {} could never move",
+            kind,
+            square
+        );
+        let nifu = kind == PieceKind::S_Pawn
+            && board
+                .pieces
+                .iter()
+                .any(|(pos, p)| pos.x == to.x && p.owner == player && p.kind == PieceKind::S_Pawn);
+        anyhow::ensure!(!nifu, "you already have a pawn on that file (nifu)");
+        if pseudo.contains(&mv) && kind == PieceKind::S_Pawn {
+            let after = crate::logic::apply_move(board, &mv, player);
+            if !crate::logic::is_in_check(&after, player) {
+                anyhow::bail!("a pawn drop may not give mate (uchifuzume)");
+            }
+        }
+        anyhow::bail!("{} would leave your king in check", text);
+    }
+
+    let (from, rest) = parse_square(&text, board)?;
+    let (to, rest) = parse_square(rest, board)?;
+    let spec = match rest.trim_start_matches('=') {
+        "" => PromotionSpec::None,
+        "+" => PromotionSpec::Promote,
+        r if r.len() == 1 && "qrbn".contains(r.to_ascii_lowercase().as_str()) => {
+            PromotionSpec::Chess(r.chars().next().unwrap_or('q').to_ascii_lowercase())
+        }
+        r => anyhow::bail!("unexpected '{}' after the squares (use + or q/r/b/n)", r),
+    };
+
+    let piece = board
+        .get_piece(from)
+        .ok_or_else(|| anyhow::anyhow!("there is no piece on the first square"))?;
+    anyhow::ensure!(
+        piece.owner == player,
+        "the piece on the first square is not yours"
+    );
+    let reaches =
+        |m: &&Move| matches!(m, Move::Normal { from: f, to: t, .. } if *f == from && *t == to);
+    let candidates: Vec<Move> = legal.iter().filter(reaches).copied().collect();
+    if candidates.is_empty() {
+        anyhow::ensure!(
+            !pseudo.iter().any(|m| reaches(&m)),
+            "that move would leave your king in check"
+        );
+        anyhow::bail!("the {:?} cannot move there", piece.kind);
+    }
+
+    let promote_of = |m: &Move| match m {
+        Move::Normal { promote, .. } => *promote,
+        Move::Drop { .. } => None,
+    };
+    let found = candidates
+        .iter()
+        .copied()
+        .find(|m| match (spec, promote_of(m)) {
+            (PromotionSpec::None, None) => true,
+            (PromotionSpec::Promote, Some(kind)) => {
+                kind.is_shogi() || !kind.display_char().is_ascii()
+            }
+            (PromotionSpec::Chess(c), Some(kind)) => {
+                !kind.is_shogi() && kind.display_char().eq_ignore_ascii_case(&c)
+            }
+            _ => false,
+        });
+    found.ok_or_else(|| match spec {
+        PromotionSpec::None if candidates.len() > 1 => {
+            anyhow::anyhow!("choose a promotion piece (add q, r, b or n)")
+        }
+        PromotionSpec::None => anyhow::anyhow!("this move must promote (add +)"),
+        PromotionSpec::Promote => anyhow::anyhow!("this move cannot promote"),
+        PromotionSpec::Chess(c) => anyhow::anyhow!("this move cannot promote to '{}'", c),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::setup::BoardSetupType;
+
+    #[test]
+    fn test_parse_move() {
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let shogi = BoardSetupType::ShogiOnly.create_board();
+        let pawn_push = Move::Normal {
+            from: Position::new(2, 6),
+            to: Position::new(2, 5),
+            promote: None,
+        };
+        assert_eq!(parse_move("7g7f", &shogi, p1).unwrap(), pawn_push);
+        assert_eq!(parse_move("c3-c4", &shogi, p1).unwrap(), pawn_push);
+        let err = |text: &str, board: &Board, player| {
+            parse_move(text, board, player).unwrap_err().to_string()
+        };
+        assert!(err("7g7e", &shogi, p1).contains("cannot move there"));
+        assert!(err("7c7d", &shogi, p1).contains("not yours"));
+        assert!(err("5e5d", &shogi, p1).contains("no piece"));
+        assert!(err("0a1a", &shogi, p1).contains("off the board"));
+        assert!(err("P*5e", &shogi, p1).contains("no 'P' in your hand"));
+        assert!(err("7g7f+", &shogi, p1).contains("cannot promote"));
+
+        let mut hand = shogi.clone();
+        hand.add_to_hand(p2, PieceKind::S_Pawn);
+        assert!(err("p*5e", &hand, p2).contains("nifu"));
+        hand.remove_piece(Position::new(4, 2));
+        assert_eq!(
+            parse_move("P*5e", &hand, p2).unwrap(),
+            Move::Drop {
+                kind: PieceKind::S_Pawn,
+                to: Position::new(4, 4)
+            }
+        );
+        assert!(err("P*5i", &hand, p2).contains("occupied"));
+
+        let mut chess = BoardSetupType::ChessOnly.create_board();
+        assert!(parse_move("e2e4", &chess, p1).is_ok());
+        chess.pieces.retain(|_, p| p.kind == PieceKind::C_King);
+        chess.place_piece(
+            Position::new(0, 1),
+            crate::core::Piece::new(PieceKind::C_Pawn, p1),
+        );
+        assert!(err("a7a8", &chess, p1).contains("choose a promotion piece"));
+        assert_eq!(
+            parse_move("a7a8=N", &chess, p1).unwrap(),
+            Move::Normal {
+                from: Position::new(0, 1),
+                to: Position::new(0, 0),
+                promote: Some(PieceKind::C_Knight),
+            }
+        );
+    }
+}
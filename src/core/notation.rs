@@ -0,0 +1,293 @@
+//! Human-readable move notation
+//!
+//! Shogi pieces are written the Japanese way (`☗7六歩`, `☖同銀`, `☗5五角打`,
+//! `☗2二角成`), chess pieces in SAN (`e4`, `Nxf3`, `exd6`, `e8=Q+`, `N@e4` for
+//! a drop). Both need the position before the move: which piece moves, which
+//! other pieces could reach the same square, and whether the move gives check.
+
+use super::{Board, Move, PieceKind, PlayerId, Position};
+use crate::logic::{apply_move, en_passant_capture, is_in_check, legal_moves};
+use std::fmt;
+
+/// A move written for `board`, the position before it; see [`Move::notation`]
+pub struct Notation<'a> {
+    mv: Move,
+    board: &'a Board,
+    player: PlayerId,
+}
+
+impl Move {
+    /// This move in shogi notation or SAN, played by `player` on `board`
+    pub fn notation(self, board: &Board, player: PlayerId) -> Notation<'_> {
+        Notation {
+            mv: self,
+            board,
+            player,
+        }
+    }
+}
+
+/// Kanji numeral for a shogi rank (一 to 九, then 十, 十一, ...)
+fn kanji_number(n: usize) -> String {
+    const DIGITS: [char; 10] = ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+    match n {
+        0..=9 => DIGITS[n].to_string(),
+        10 => "十".to_string(),
+        11..=19 => format!("十{}", DIGITS[n - 10]),
+        _ => n.to_string(),
+    }
+}
+
+/// The square as a shogi player reads it: file counted from the right, rank in kanji
+fn shogi_square(board: &Board, pos: Position) -> String {
+    format!("{}{}", board.width - pos.x, kanji_number(pos.y + 1))
+}
+
+/// The square in algebraic notation: file letter from the left, rank from the bottom
+fn algebraic_square(board: &Board, pos: Position) -> String {
+    format!("{}{}", (b'a' + pos.x as u8) as char, board.height - pos.y)
+}
+
+/// SAN letter of a piece (empty for pawns)
+fn san_letter(kind: PieceKind) -> String {
+    match kind {
+        PieceKind::C_Pawn => String::new(),
+        PieceKind::Custom(i) => super::fairy::def(i)
+            .map_or_else(|| "?".to_string(), |def| def.symbol.to_ascii_uppercase()),
+        kind => kind.display_char().to_string(),
+    }
+}
+
+impl Notation<'_> {
+    /// Squares of the player's other `kind` pieces that can also move to `to`
+    fn rivals(&self, kind: PieceKind, from: Option<Position>, to: Position) -> Vec<Position> {
+        let mut rivals: Vec<Position> = legal_moves(self.board, self.player)
+            .into_iter()
+            .filter_map(|m| match m {
+                Move::Normal { from: f, to: t, .. } if t == to && Some(f) != from => Some(f),
+                _ => None,
+            })
+            .filter(|f| self.board.get_piece(*f).is_some_and(|p| p.kind == kind))
+            .collect();
+        rivals.sort_by_key(|p| (p.x, p.y));
+        rivals.dedup();
+        rivals
+    }
+
+    /// 右/左/直 and 上/引/寄 telling apart pieces of the same kind that reach
+    /// the same square, from the mover's point of view
+    fn shogi_disambiguation(&self, kind: PieceKind, from: Position, to: Position) -> String {
+        let rivals = self.rivals(kind, Some(from), to);
+        if rivals.is_empty() {
+            return String::new();
+        }
+        // Forward is -y for Player1; right is +x for Player1
+        let sign = if self.player == PlayerId::Player1 {
+            1
+        } else {
+            -1
+        };
+        let forward = |p: Position| (p.y as i32 - to.y as i32) * sign;
+        let rightward = |p: Position| p.x as i32 * sign;
+        let motion = |p: Position| match forward(p) {
+            d if d > 0 => '上',
+            d if d < 0 => '引',
+            _ => '寄',
+        };
+        if rivals.iter().all(|&r| motion(r) != motion(from)) {
+            return motion(from).to_string();
+        }
+        let golden = !matches!(
+            kind,
+            PieceKind::S_Rook | PieceKind::S_Bishop | PieceKind::S_ProRook | PieceKind::S_ProBishop
+        );
+        let side = if golden && from.x == to.x && forward(from) > 0 {
+            '直'
+        } else if rivals.iter().all(|&r| rightward(r) < rightward(from)) {
+            '右'
+        } else if rivals.iter().all(|&r| rightward(r) > rightward(from)) {
+            '左'
+        } else {
+            // Between the others: the side relative to the destination, then the motion
+            let side = if rightward(from) > rightward(to) {
+                '右'
+            } else {
+                '左'
+            };
+            return format!("{}{}", side, motion(from));
+        };
+        side.to_string()
+    }
+
+    fn fmt_shogi(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mark = match self.player {
+            PlayerId::Player1 => '☗',
+            PlayerId::Player2 => '☖',
+        };
+        match self.mv {
+            Move::Normal { from, to, promote } => {
+                let Some(piece) = self.board.get_piece(from) else {
+                    return write!(f, "{}{}", mark, self.mv);
+                };
+                let recapture = matches!(
+                    self.board.last_move,
+                    Some(Move::Normal { to: t, .. } | Move::Drop { to: t, .. }) if t == to
+                );
+                let square = if recapture {
+                    "同".to_string()
+                } else {
+                    shogi_square(self.board, to)
+                };
+                write!(
+                    f,
+                    "{}{}{}{}",
+                    mark,
+                    square,
+                    piece.kind.display_char(),
+                    self.shogi_disambiguation(piece.kind, from, to)
+                )?;
+                if promote.is_some() {
+                    write!(f, "成")
+                } else if legal_moves(self.board, self.player).iter().any(|m| {
+                    matches!(m, Move::Normal { from: fr, to: t, promote: Some(_) } if *fr == from && *t == to)
+                }) {
+                    write!(f, "不成")
+                } else {
+                    Ok(())
+                }
+            }
+            Move::Drop { kind, to } => {
+                write!(
+                    f,
+                    "{}{}{}",
+                    mark,
+                    shogi_square(self.board, to),
+                    kind.display_char()
+                )?;
+                // 打 only when a piece on the board could make the same move
+                if self.rivals(kind, None, to).is_empty() {
+                    Ok(())
+                } else {
+                    write!(f, "打")
+                }
+            }
+        }
+    }
+
+    fn fmt_san(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mv {
+            Move::Normal { from, to, promote } => {
+                let Some(piece) = self.board.get_piece(from) else {
+                    return write!(f, "{}", self.mv);
+                };
+                let capture = self.board.get_piece(to).is_some()
+                    || en_passant_capture(self.board, &self.mv).is_some();
+                let letter = san_letter(piece.kind);
+                let file = (b'a' + from.x as u8) as char;
+                let rank = self.board.height - from.y;
+                write!(f, "{}", letter)?;
+                if letter.is_empty() {
+                    if capture {
+                        write!(f, "{}", file)?;
+                    }
+                } else {
+                    let rivals = self.rivals(piece.kind, Some(from), to);
+                    if rivals.is_empty() {
+                        // Only this piece reaches the square
+                    } else if rivals.iter().all(|r| r.x != from.x) {
+                        write!(f, "{}", file)?;
+                    } else if rivals.iter().all(|r| r.y != from.y) {
+                        write!(f, "{}", rank)?;
+                    } else {
+                        write!(f, "{}{}", file, rank)?;
+                    }
+                }
+                if capture {
+                    write!(f, "x")?;
+                }
+                write!(f, "{}", algebraic_square(self.board, to))?;
+                if let Some(kind) = promote {
+                    write!(f, "={}", san_letter(kind))?;
+                }
+            }
+            Move::Drop { kind, to } => {
+                let letter = match kind {
+                    PieceKind::C_Pawn => "P".to_string(),
+                    kind => san_letter(kind),
+                };
+                write!(f, "{}@{}", letter, algebraic_square(self.board, to))?;
+            }
+        }
+        let after = apply_move(self.board, &self.mv, self.player);
+        let opponent = self.player.opponent();
+        if is_in_check(&after, opponent) {
+            if legal_moves(&after, opponent).is_empty() {
+                write!(f, "#")?;
+            } else {
+                write!(f, "+")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Notation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.mv {
+            Move::Normal { from, .. } => self.board.get_piece(from).map(|p| p.kind),
+            Move::Drop { kind, .. } => Some(kind),
+        };
+        match kind {
+            Some(kind) if !kind.is_shogi() => self.fmt_san(f),
+            _ => self.fmt_shogi(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::setup::BoardSetupType;
+
+    #[test]
+    fn test_notation() {
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let shogi = BoardSetupType::ShogiOnly.create_board();
+        let mv = |from: (usize, usize), to: (usize, usize)| Move::Normal {
+            from: Position::new(from.0, from.1),
+            to: Position::new(to.0, to.1),
+            promote: None,
+        };
+        let text = |m: Move, board: &Board, player| m.notation(board, player).to_string();
+        assert_eq!(text(mv((2, 6), (2, 5)), &shogi, p1), "☗7六歩");
+        // Both golds can step up to 5八
+        assert_eq!(text(mv((3, 8), (4, 7)), &shogi, p1), "☗5八金左");
+        assert_eq!(text(mv((5, 8), (4, 7)), &shogi, p1), "☗5八金右");
+
+        let mut board = shogi.clone();
+        for (from, to, player) in [((2, 6), (2, 5), p1), ((6, 2), (6, 3), p2)] {
+            board = apply_move(&board, &mv(from, to), player);
+        }
+        let trade = Move::Normal {
+            from: Position::new(1, 7),
+            to: Position::new(7, 1),
+            promote: Some(PieceKind::S_ProBishop),
+        };
+        assert_eq!(text(trade, &board, p1), "☗2二角成");
+        let board = apply_move(&board, &trade, p1);
+        assert_eq!(text(mv((6, 0), (7, 1)), &board, p2), "☖同銀");
+
+        let chess = BoardSetupType::ChessOnly.create_board();
+        assert_eq!(text(mv((4, 6), (4, 4)), &chess, p1), "e4");
+        assert_eq!(text(mv((6, 7), (5, 5)), &chess, p1), "Nf3");
+        let mut board = chess.clone();
+        for (from, to, player) in [
+            ((5, 6), (5, 5), p1),
+            ((4, 1), (4, 3), p2),
+            ((6, 6), (6, 4), p1),
+        ] {
+            board = apply_move(&board, &mv(from, to), player);
+        }
+        assert_eq!(text(mv((3, 0), (7, 4)), &board, p2), "Qh4#");
+    }
+}
@@ -1,9 +1,10 @@
+use super::fairy;
 use super::types::PlayerId;
 use serde::{Deserialize, Serialize};
 
 /// 駒の種類
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum PieceKind {
     // 将棋駒
     S_King,   // 王/玉
@@ -28,9 +29,84 @@ pub enum PieceKind {
     C_Bishop,
     C_Knight,
     C_Pawn,
+    // pieces.toml の駒 (登録順)
+    Custom(u8),
 }
 
 impl PieceKind {
+    /// Built-in kinds in declaration order
+    pub const BUILTIN: [PieceKind; 20] = [
+        PieceKind::S_King,
+        PieceKind::S_Rook,
+        PieceKind::S_Bishop,
+        PieceKind::S_Gold,
+        PieceKind::S_Silver,
+        PieceKind::S_Knight,
+        PieceKind::S_Lance,
+        PieceKind::S_Pawn,
+        PieceKind::S_ProRook,
+        PieceKind::S_ProBishop,
+        PieceKind::S_ProSilver,
+        PieceKind::S_ProKnight,
+        PieceKind::S_ProLance,
+        PieceKind::S_ProPawn,
+        PieceKind::C_King,
+        PieceKind::C_Queen,
+        PieceKind::C_Rook,
+        PieceKind::C_Bishop,
+        PieceKind::C_Knight,
+        PieceKind::C_Pawn,
+    ];
+
+    /// Stable numeric code (dataset files, FFI): built-in kinds by their
+    /// [`PieceKind::BUILTIN`] index, custom kinds after them
+    pub fn code(self) -> u8 {
+        match self {
+            PieceKind::Custom(i) => Self::BUILTIN.len() as u8 + i,
+            _ => Self::BUILTIN.iter().position(|&k| k == self).unwrap_or(0) as u8,
+        }
+    }
+
+    /// Inverse of [`PieceKind::code`]; `None` for codes of unregistered custom pieces
+    pub fn from_code(code: u8) -> Option<PieceKind> {
+        let custom = (code as usize).checked_sub(Self::BUILTIN.len());
+        Self::BUILTIN.get(code as usize).copied().or_else(|| {
+            custom
+                .filter(|&i| i < fairy::pieces().len())
+                .map(|i| PieceKind::Custom(i as u8))
+        })
+    }
+
+    /// 将棋駒かどうか
+    pub fn is_shogi(&self) -> bool {
+        matches!(
+            self,
+            PieceKind::S_King
+                | PieceKind::S_Rook
+                | PieceKind::S_Bishop
+                | PieceKind::S_Gold
+                | PieceKind::S_Silver
+                | PieceKind::S_Knight
+                | PieceKind::S_Lance
+                | PieceKind::S_Pawn
+                | PieceKind::S_ProRook
+                | PieceKind::S_ProBishop
+                | PieceKind::S_ProSilver
+                | PieceKind::S_ProKnight
+                | PieceKind::S_ProLance
+                | PieceKind::S_ProPawn
+        ) || matches!(self, PieceKind::Custom(i) if fairy::def(*i).is_some_and(|def| def.shogi))
+    }
+
+    /// 組み込みの駒はそのまま、pieces.toml の駒は評価で見なす組み込みの駒 (`like`)。
+    /// 定義のない駒は歩と見なす
+    pub fn builtin(&self) -> PieceKind {
+        match self {
+            PieceKind::Custom(i) => fairy::def(*i).map_or(PieceKind::S_Pawn, |def| def.like),
+            _ => *self,
+        }
+    }
+
     pub fn display_char(&self) -> char {
         match self {
             PieceKind::S_King => '王',
@@ -53,12 +129,13 @@ impl PieceKind {
             PieceKind::C_Bishop => 'B',
             PieceKind::C_Knight => 'N',
             PieceKind::C_Pawn => 'P',
+            PieceKind::Custom(i) => fairy::def(*i).map_or('?', |def| def.display_char),
         }
     }
 }
 
 /// 移動の特性
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MoveStep {
     Step(i32, i32),  // 指定した相対座標へ1マス移動
     Slide(i32, i32), // 指定した方向へ障害物があるまで移動
@@ -74,23 +151,7 @@ pub struct Piece {
 
 impl Piece {
     pub fn new(kind: PieceKind, owner: PlayerId) -> Self {
-        let is_shogi = matches!(
-            kind,
-            PieceKind::S_King
-                | PieceKind::S_Rook
-                | PieceKind::S_Bishop
-                | PieceKind::S_Gold
-                | PieceKind::S_Silver
-                | PieceKind::S_Knight
-                | PieceKind::S_Lance
-                | PieceKind::S_Pawn
-                | PieceKind::S_ProRook
-                | PieceKind::S_ProBishop
-                | PieceKind::S_ProSilver
-                | PieceKind::S_ProKnight
-                | PieceKind::S_ProLance
-                | PieceKind::S_ProPawn
-        );
+        let is_shogi = kind.is_shogi();
         Piece {
             kind,
             owner,
@@ -198,43 +259,48 @@ impl Piece {
                 // チェスのPawnは「移動」と「取り」が違うため、エンジンの合法手生成側で特殊処理する
                 vec![]
             }
+            // 定義は先手から見た向きなので、後手は前後を反転する。定義のない駒は動けない
+            PieceKind::Custom(i) => fairy::def(i)
+                .map_or(&[][..], |def| &def.moves)
+                .iter()
+                .map(|step| match *step {
+                    MoveStep::Step(dx, dy) => MoveStep::Step(dx, -forward * dy),
+                    MoveStep::Slide(dx, dy) => MoveStep::Slide(dx, -forward * dy),
+                })
+                .collect(),
         }
     }
 
     pub fn display_char(&self) -> char {
+        self.kind.display_char()
+    }
+
+    /// Kinds a move may promote this piece to: one for shogi pieces, the
+    /// four chess promotions (queen first) for a chess pawn
+    pub fn promotion_kinds(&self) -> &'static [PieceKind] {
         match self.kind {
-            PieceKind::S_King => '王',
-            PieceKind::S_Rook => '飛',
-            PieceKind::S_Bishop => '角',
-            PieceKind::S_Gold => '金',
-            PieceKind::S_Silver => '銀',
-            PieceKind::S_Knight => '桂',
-            PieceKind::S_Lance => '香',
-            PieceKind::S_Pawn => '歩',
-            PieceKind::S_ProRook => '竜',
-            PieceKind::S_ProBishop => '馬',
-            PieceKind::S_ProSilver => '全',
-            PieceKind::S_ProKnight => '圭',
-            PieceKind::S_ProLance => '杏',
-            PieceKind::S_ProPawn => 'と',
-            PieceKind::C_King => 'K',
-            PieceKind::C_Queen => 'Q',
-            PieceKind::C_Rook => 'R',
-            PieceKind::C_Bishop => 'B',
-            PieceKind::C_Knight => 'N',
-            PieceKind::C_Pawn => 'P',
+            PieceKind::S_Rook => &[PieceKind::S_ProRook],
+            PieceKind::S_Bishop => &[PieceKind::S_ProBishop],
+            PieceKind::S_Silver => &[PieceKind::S_ProSilver],
+            PieceKind::S_Knight => &[PieceKind::S_ProKnight],
+            PieceKind::S_Lance => &[PieceKind::S_ProLance],
+            PieceKind::S_Pawn => &[PieceKind::S_ProPawn],
+            PieceKind::C_Pawn => &[
+                PieceKind::C_Queen,
+                PieceKind::C_Rook,
+                PieceKind::C_Bishop,
+                PieceKind::C_Knight,
+            ],
+            PieceKind::Custom(i) => fairy::promotion_kinds(i),
+            _ => &[],
         }
     }
 
+    /// The promoted kind of a shogi piece. `None` for a chess pawn, whose
+    /// move chooses among [`Piece::promotion_kinds`].
     pub fn promotable_kind(&self) -> Option<PieceKind> {
-        match self.kind {
-            PieceKind::S_Rook => Some(PieceKind::S_ProRook),
-            PieceKind::S_Bishop => Some(PieceKind::S_ProBishop),
-            PieceKind::S_Silver => Some(PieceKind::S_ProSilver),
-            PieceKind::S_Knight => Some(PieceKind::S_ProKnight),
-            PieceKind::S_Lance => Some(PieceKind::S_ProLance),
-            PieceKind::S_Pawn => Some(PieceKind::S_ProPawn),
-            PieceKind::C_Pawn => Some(PieceKind::C_Queen), // チェスはQueen固定（簡略化）
+        match self.promotion_kinds() {
+            [kind] => Some(*kind),
             _ => None,
         }
     }
@@ -247,6 +313,7 @@ impl Piece {
             PieceKind::S_ProKnight => PieceKind::S_Knight,
             PieceKind::S_ProLance => PieceKind::S_Lance,
             PieceKind::S_ProPawn => PieceKind::S_Pawn,
+            PieceKind::Custom(i) => fairy::unpromoted(i).unwrap_or(self.kind),
             _ => self.kind,
         }
     }
@@ -0,0 +1,207 @@
+//! Game-wide rules, user-tweakable through `ruleset.toml` or `ruleset.json`
+//!
+//! Every board carries its [`Ruleset`], so games, self-play and the engine
+//! apply the same limits, entering-king declarations and draw adjudication.
+//! Boards built from a setup take the one from the file in the working
+//! directory; without a file they get the defaults, which are the standard
+//! rules. Per-player rules (captures, drops, uchifuzume, stalemate result)
+//! are [`PlayerConfig`]s: `player1` and `player2` replace the ones the setup
+//! chose.
+//!
+//! ```toml
+//! promotion_zone_rows = 3
+//! repetition_limit = 4
+//! max_plies = 512
+//! chess_quiet_plies = 100
+//!
+//! [impasse]
+//! enabled = true
+//! rule = "TwentySeven"
+//!
+//! [adjudication]
+//! stagnation_plies = 60
+//! max_quiet_plies = 200
+//!
+//! [player2]
+//! can_capture = true
+//! can_promote = true
+//! can_drop = false
+//! keep_captured = false
+//! stalemate_draws = true
+//! ```
+
+use super::types::{PlayerConfig, PlayerId};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Files looked up in the working directory, in order
+pub const RULESET_FILES: [&str; 2] = ["ruleset.toml", "ruleset.json"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ruleset {
+    /// Rows of the promotion zone, which is also the camp for entering-king
+    /// declarations
+    pub promotion_zone_rows: usize,
+    /// Occurrences of a position that draw the game (sennichite)
+    pub repetition_limit: usize,
+    /// Plies after which the game is a draw
+    pub max_plies: usize,
+    /// Plies without a capture or pawn move that draw a game with only chess
+    /// pieces (100 is the 50-move rule); 0 disables
+    pub chess_quiet_plies: usize,
+    pub impasse: ImpasseConfig,
+    pub adjudication: AdjudicationConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player1: Option<PlayerConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player2: Option<PlayerConfig>,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            promotion_zone_rows: 3,
+            repetition_limit: 4,
+            max_plies: 512,
+            chess_quiet_plies: 100,
+            impasse: ImpasseConfig::default(),
+            adjudication: AdjudicationConfig::default(),
+            player1: None,
+            player2: None,
+        }
+    }
+}
+
+/// Draw adjudication of stagnating self-play games (no capture, promotion or
+/// pawn move). The score window starts at `draw_score_centipawns` and grows by
+/// `widen_step_centipawns` every `widen_every_plies` quiet plies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdjudicationConfig {
+    pub enabled: bool,
+    /// Quiet plies before adjudication is considered
+    pub stagnation_plies: usize,
+    pub draw_score_centipawns: i32,
+    pub widen_every_plies: usize,
+    pub widen_step_centipawns: i32,
+    pub max_draw_score_centipawns: i32,
+    /// Quiet plies after which the game is drawn whatever the score
+    pub max_quiet_plies: usize,
+}
+
+/// Entering-king declarations (see [`crate::logic::impasse`]). The side to
+/// move declares automatically when that wins, or, once both kings have
+/// entered, when it draws.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImpasseConfig {
+    pub enabled: bool,
+    pub rule: crate::logic::impasse::ImpasseRule,
+}
+
+impl Default for AdjudicationConfig {
+    fn default() -> Self {
+        AdjudicationConfig {
+            enabled: true,
+            stagnation_plies: 60,
+            draw_score_centipawns: 150,
+            widen_every_plies: 20,
+            widen_step_centipawns: 150,
+            max_draw_score_centipawns: 1500,
+            max_quiet_plies: 200,
+        }
+    }
+}
+
+impl Default for ImpasseConfig {
+    fn default() -> Self {
+        ImpasseConfig {
+            enabled: true,
+            rule: crate::logic::impasse::ImpasseRule::TwentySeven,
+        }
+    }
+}
+
+static RULESET: Lazy<Ruleset> = Lazy::new(|| {
+    let Some(path) = RULESET_FILES.iter().map(Path::new).find(|p| p.exists()) else {
+        return Ruleset::default();
+    };
+    Ruleset::load(path).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to load {}: {}", path.display(), e);
+        eprintln!("Using the standard rules");
+        Ruleset::default()
+    })
+});
+
+impl Ruleset {
+    /// The rules in a ruleset file (see [`crate::util::load_config`])
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        crate::util::load_config(path)
+    }
+
+    /// The rules from the ruleset file (loaded once)
+    pub fn get() -> &'static Ruleset {
+        &RULESET
+    }
+
+    /// The setup's config for `player` replaced by this ruleset's, if any
+    pub fn player_config(&self, player: PlayerId) -> Option<PlayerConfig> {
+        match player {
+            PlayerId::Player1 => self.player1,
+            PlayerId::Player2 => self.player2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_ruleset_file() {
+        let rules: Ruleset = toml::from_str(
+            "max_plies = 300\n\n[player2]\ncan_capture = true\ncan_promote = false\n\
+             can_drop = false\nkeep_captured = false\nstalemate_draws = true\n",
+        )
+        .unwrap();
+        assert_eq!(rules.max_plies, 300);
+        assert_eq!(rules.repetition_limit, Ruleset::default().repetition_limit);
+        assert!(rules.player1.is_none());
+        let p2 = rules.player_config(PlayerId::Player2).unwrap();
+        assert!(p2.stalemate_draws && !p2.can_drop && !p2.pawn_drop_mate);
+
+        let rules: Ruleset =
+            toml::from_str("[impasse]\nrule = \"TwentyFour\"\n\n[adjudication]\nenabled = false\n")
+                .unwrap();
+        assert!(rules.impasse.enabled);
+        assert_eq!(
+            rules.impasse.rule,
+            crate::logic::impasse::ImpasseRule::TwentyFour
+        );
+        assert!(!rules.adjudication.enabled);
+        assert_eq!(rules.adjudication.max_quiet_plies, 200);
+
+        let json: Ruleset = serde_json::from_str(r#"{"promotion_zone_rows": 2}"#).unwrap();
+        assert_eq!(json.promotion_zone_rows, 2);
+        assert_eq!(json.max_plies, 512);
+
+        // Configs from before `stalemate_draws` keep the chess and shogi results
+        let old: Ruleset = serde_json::from_str(
+            r#"{"player1": {"can_capture": true, "can_promote": false, "can_drop": false, "keep_captured": false},
+                "player2": {"can_capture": true, "can_promote": true, "can_drop": true, "keep_captured": true}}"#,
+        )
+        .unwrap();
+        assert!(
+            old.player_config(PlayerId::Player1)
+                .unwrap()
+                .stalemate_draws
+        );
+        assert!(
+            !old.player_config(PlayerId::Player2)
+                .unwrap()
+                .stalemate_draws
+        );
+    }
+}
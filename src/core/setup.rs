@@ -1,6 +1,14 @@
-use crate::core::{Board, Piece, PieceKind, PlayerConfig, PlayerId, Position};
+use crate::core::{Board, Piece, PieceKind, PlayerConfig, PlayerId, Position, Ruleset};
+use std::collections::HashMap;
+
+/// 持ち駒行の先頭語 (`"hand P 2G p"` のように駒を並べる。大文字が先手、小文字が後手)
+pub const HAND_PREFIX: &str = "hand";
 
 /// 文字列配列から盤面とプレイヤー設定を初期化する
+///
+/// 盤面の各行は空白区切りの駒表記 (`.` は空きマス、大文字が先手、小文字が後手、
+/// `c` 始まりはチェス駒、`@` 始まりは pieces.toml の駒、`+` 始まりは成り駒)。
+/// `hand` で始まる行は初期の持ち駒で、駒表記の前に枚数を付けられる (`2P`)。
 pub fn setup_from_strings(
     setup: &[&str],
     p1_shogi: bool,
@@ -8,12 +16,13 @@ pub fn setup_from_strings(
     p1_use_hand: Option<bool>,
     p2_use_hand: Option<bool>,
 ) -> Board {
-    let height = setup.len();
-    let width = if height > 0 {
-        setup[0].split_whitespace().count()
-    } else {
-        0
-    };
+    let is_hand_row = |row: &&&str| row.split_whitespace().next() == Some(HAND_PREFIX);
+    let (hand_rows, rows): (Vec<&&str>, Vec<&&str>) = setup.iter().partition(is_hand_row);
+    let height = rows.len();
+    let width = rows
+        .first()
+        .map(|row| row.split_whitespace().count())
+        .unwrap_or(0);
     let mut board = Board::new(width, height);
 
     let mut p1_config = if p1_shogi {
@@ -37,35 +46,335 @@ pub fn setup_from_strings(
         p2_config.keep_captured = use_hand;
     }
     board.set_player_config(PlayerId::Player2, p2_config);
+    board.set_ruleset(Ruleset::get().clone());
 
-    for (y, row) in setup.iter().enumerate() {
+    for (y, row) in rows.iter().enumerate() {
         for (x, s) in row.split_whitespace().enumerate() {
             if s == "." {
                 continue;
             }
+            if let Some(piece) = parse_piece(s, p1_shogi, p2_shogi) {
+                board.place_piece(Position::new(x, y), piece);
+            }
+        }
+    }
 
-            let (kind_str, owner) = if s.chars().next().unwrap().is_uppercase() {
-                (s, PlayerId::Player1)
-            } else {
-                (s, PlayerId::Player2)
-            };
+    for row in hand_rows {
+        for s in row.split_whitespace().skip(1) {
+            if let Some((piece, count)) = parse_hand_token(s, p1_shogi, p2_shogi) {
+                for _ in 0..count {
+                    board.add_to_hand(piece.owner, piece.unpromoted_kind());
+                }
+            }
+        }
+    }
+    board
+}
+
+/// 盤上の 1 マス分の駒表記を駒に変換する。1 文字表記は将棋の駒で、チェス駒は
+/// `c` を付ける ([`setup_to_strings`] の出力と同じ)
+pub fn parse_piece_token(s: &str) -> Option<Piece> {
+    parse_piece(s, true, true)
+}
+
+/// 持ち駒行の 1 語 (`2P` のように枚数を前に付けられる) を駒と枚数に変換する
+pub fn parse_hand_token(s: &str, p1_shogi: bool, p2_shogi: bool) -> Option<(Piece, usize)> {
+    let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+    let count = s[..digits].parse().unwrap_or(1);
+    let piece = parse_piece(&s[digits..], p1_shogi, p2_shogi)?;
+    Some((piece, count))
+}
+
+/// 駒表記を駒に変換する (所有者は大文字/小文字、1文字表記の種類は所有者の将棋/チェス指定で決まる)
+fn parse_piece(s: &str, p1_shogi: bool, p2_shogi: bool) -> Option<Piece> {
+    let (promoted, kind_str) = match s.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let owner = if kind_str
+        .trim_start_matches('@')
+        .chars()
+        .next()?
+        .is_uppercase()
+    {
+        PlayerId::Player1
+    } else {
+        PlayerId::Player2
+    };
+
+    let is_shogi_hint = if owner == PlayerId::Player1 {
+        p1_shogi
+    } else {
+        p2_shogi
+    };
+
+    let mut piece = Piece::new(parse_piece_kind(kind_str, is_shogi_hint)?, owner);
+    if promoted {
+        piece.kind = piece.promotable_kind()?;
+    }
+    Some(piece)
+}
+
+/// Chess960 (フィッシャーランダム) の初期配置にした `setup`
+///
+/// チェスのキングがある段 (後列) のチェス駒 (ポーン以外) を、同じマスの中で
+/// 並べ替える。ビショップ 2 枚は異なる色のマス、キングは 2 つのルークの間に置く。
+/// 同じ筋に同じ駒が並ぶ後列 (チェス同士の対局の両陣) は同じ並びになる。
+/// 同じ `seed` からは常に同じ配置になる。
+pub fn chess960_setup(setup: &[&str], seed: u64) -> Vec<String> {
+    use rand::SeedableRng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut orders: HashMap<(Vec<usize>, Vec<String>), Vec<String>> = HashMap::new();
+    setup
+        .iter()
+        .map(|row| {
+            let mut cells: Vec<String> = row.split_whitespace().map(str::to_string).collect();
+            let is_back_rank = cells.first().map(String::as_str) != Some(HAND_PREFIX)
+                && cells.iter().any(|c| c.eq_ignore_ascii_case("ck"));
+            if !is_back_rank {
+                return row.to_string();
+            }
+            let files: Vec<usize> = (0..cells.len())
+                .filter(|&x| {
+                    matches!(
+                        cells[x].to_lowercase().as_str(),
+                        "cr" | "cn" | "cb" | "cq" | "ck"
+                    )
+                })
+                .collect();
+            let kinds: Vec<String> = files.iter().map(|&x| cells[x].to_lowercase()).collect();
+            let mut key_kinds = kinds.clone();
+            key_kinds.sort();
+            let order = orders
+                .entry((files.clone(), key_kinds))
+                .or_insert_with(|| shuffle_back_rank(&files, &kinds, &mut rng));
+            for (&x, kind) in files.iter().zip(order.iter()) {
+                cells[x] = if cells[x].starts_with('C') {
+                    kind.to_uppercase()
+                } else {
+                    kind.clone()
+                };
+            }
+            cells.join(" ")
+        })
+        .collect()
+}
+
+/// `chess960_seed` があれば [`chess960_setup`] で並べ替えてから [`setup_from_strings`]
+pub fn setup_from_strings_chess960(
+    setup: &[&str],
+    p1_shogi: bool,
+    p2_shogi: bool,
+    chess960_seed: Option<u64>,
+) -> Board {
+    match chess960_seed {
+        Some(seed) => {
+            let rows = chess960_setup(setup, seed);
+            let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+            setup_from_strings(&rows, p1_shogi, p2_shogi, None, None)
+        }
+        None => setup_from_strings(setup, p1_shogi, p2_shogi, None, None),
+    }
+}
+
+/// 後列の駒 `kinds` (筋 `files` の順) のランダムな並び。条件を満たす並びが
+/// 見つからなければ元の並び
+fn shuffle_back_rank(files: &[usize], kinds: &[String], rng: &mut impl rand::Rng) -> Vec<String> {
+    use rand::seq::SliceRandom;
+
+    let valid = |order: &[String]| {
+        let files_of = |kind: &str| -> Vec<usize> {
+            files
+                .iter()
+                .zip(order)
+                .filter(|(_, k)| k.as_str() == kind)
+                .map(|(&x, _)| x)
+                .collect()
+        };
+        let bishops_ok = match files_of("cb").as_slice() {
+            [a, b] => a % 2 != b % 2,
+            _ => true,
+        };
+        let king_ok = match (files_of("cr").as_slice(), files_of("ck").as_slice()) {
+            ([a, b], [king]) => a < king && king < b,
+            _ => true,
+        };
+        bishops_ok && king_ok
+    };
+    (0..1000)
+        .map(|_| {
+            let mut order = kinds.to_vec();
+            order.shuffle(rng);
+            order
+        })
+        .find(|order| valid(order))
+        .unwrap_or_else(|| kinds.to_vec())
+}
+
+/// 駒の表記 ([`setup_from_strings`] の逆)。チェス駒は常に `c` 付きで書く
+fn piece_token(piece: &Piece) -> String {
+    let base = Piece::new(piece.unpromoted_kind(), piece.owner);
+    let mut token = String::new();
+    if base.kind != piece.kind {
+        token.push('+');
+    }
+    if let PieceKind::Custom(i) = base.kind {
+        token.push('@');
+        token.push_str(crate::core::fairy::def(i).map_or("?", |def| def.symbol.as_str()));
+    } else {
+        if !base.kind.is_shogi() {
+            token.push('c');
+        }
+        token.push(kind_letter(base.kind));
+    }
+    if piece.owner == PlayerId::Player1 {
+        token.to_uppercase()
+    } else {
+        token.to_lowercase()
+    }
+}
 
-            let is_shogi_hint = if owner == PlayerId::Player1 {
-                p1_shogi
+/// 成っていない駒の 1 文字表記 ([`parse_piece_kind`] の逆)
+fn kind_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::S_King | PieceKind::C_King => 'K',
+        PieceKind::S_Rook | PieceKind::C_Rook | PieceKind::S_ProRook => 'R',
+        PieceKind::S_Bishop | PieceKind::C_Bishop | PieceKind::S_ProBishop => 'B',
+        PieceKind::S_Gold => 'G',
+        PieceKind::S_Silver | PieceKind::S_ProSilver => 'S',
+        PieceKind::S_Knight | PieceKind::C_Knight | PieceKind::S_ProKnight => 'N',
+        PieceKind::S_Lance | PieceKind::S_ProLance => 'L',
+        PieceKind::S_Pawn | PieceKind::C_Pawn | PieceKind::S_ProPawn => 'P',
+        PieceKind::C_Queen => 'Q',
+        PieceKind::Custom(_) => unreachable!("custom pieces are written with their symbol"),
+    }
+}
+
+/// 盤面を [`setup_from_strings`] の形式に書き出す (持ち駒があれば `hand` 行を付ける)。
+/// 将棋駒を 1 文字で書くため、読み戻すときは同じ将棋/チェス指定を渡す。
+pub fn setup_to_strings(board: &Board) -> Vec<String> {
+    let mut rows: Vec<String> = (0..board.height)
+        .map(|y| {
+            (0..board.width)
+                .map(|x| match board.get_piece(Position::new(x, y)) {
+                    Some(piece) => piece_token(piece),
+                    None => ".".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+
+    let mut hand_tokens = Vec::new();
+    for player in [PlayerId::Player1, PlayerId::Player2] {
+        let Some(hand) = board.hand.get(&player) else {
+            continue;
+        };
+        let mut items: Vec<_> = hand.iter().filter(|(_, &count)| count > 0).collect();
+        items.sort_by_key(|(kind, _)| format!("{:?}", kind));
+        for (&kind, &count) in items {
+            let token = piece_token(&Piece::new(kind, player));
+            hand_tokens.push(if count > 1 {
+                format!("{}{}", count, token)
             } else {
-                p2_shogi
+                token
+            });
+        }
+    }
+    if !hand_tokens.is_empty() {
+        rows.push(format!("{} {}", HAND_PREFIX, hand_tokens.join(" ")));
+    }
+    rows
+}
+
+/// 持ち駒を SFEN に書く順 (飛角金銀桂香歩)
+const SFEN_HAND_ORDER: [PieceKind; 7] = [
+    PieceKind::S_Rook,
+    PieceKind::S_Bishop,
+    PieceKind::S_Gold,
+    PieceKind::S_Silver,
+    PieceKind::S_Knight,
+    PieceKind::S_Lance,
+    PieceKind::S_Pawn,
+];
+
+/// 局面の SFEN (先手 = `b` = 大文字、1 手目として書く)。9x9 の盤に組み込みの
+/// 将棋駒しかない局面だけが書けるので、それ以外は `None`
+pub fn to_sfen(board: &Board, side_to_move: PlayerId) -> Option<String> {
+    let shogi_only = |kind: PieceKind| kind.is_shogi() && !matches!(kind, PieceKind::Custom(_));
+    if (board.width, board.height) != (9, 9)
+        || board.pieces.values().any(|piece| !shogi_only(piece.kind))
+        || board
+            .hand
+            .values()
+            .flatten()
+            .any(|(&kind, &count)| count > 0 && !SFEN_HAND_ORDER.contains(&kind))
+    {
+        return None;
+    }
+
+    let cased = |letters: String, owner: PlayerId| match owner {
+        PlayerId::Player1 => letters.to_uppercase(),
+        PlayerId::Player2 => letters.to_lowercase(),
+    };
+    let mut ranks = Vec::new();
+    for y in 0..board.height {
+        let mut rank = String::new();
+        let mut empty = 0;
+        for x in 0..board.width {
+            let Some(piece) = board.get_piece(Position::new(x, y)) else {
+                empty += 1;
+                continue;
             };
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+                empty = 0;
+            }
+            let mut letters = String::new();
+            if piece.unpromoted_kind() != piece.kind {
+                letters.push('+');
+            }
+            letters.push(kind_letter(piece.kind));
+            rank.push_str(&cased(letters, piece.owner));
+        }
+        if empty > 0 {
+            rank.push_str(&empty.to_string());
+        }
+        ranks.push(rank);
+    }
 
-            let kind = parse_piece_kind(kind_str, is_shogi_hint);
-            if let Some(k) = kind {
-                board.place_piece(Position::new(x, y), Piece::new(k, owner));
+    let mut hand = String::new();
+    for player in [PlayerId::Player1, PlayerId::Player2] {
+        for kind in SFEN_HAND_ORDER {
+            let count = board
+                .hand
+                .get(&player)
+                .and_then(|h| h.get(&kind))
+                .copied()
+                .unwrap_or(0);
+            if count > 1 {
+                hand.push_str(&count.to_string());
+            }
+            if count > 0 {
+                hand.push_str(&cased(kind_letter(kind).to_string(), player));
             }
         }
     }
-    board
+    if hand.is_empty() {
+        hand.push('-');
+    }
+    let side = match side_to_move {
+        PlayerId::Player1 => 'b',
+        PlayerId::Player2 => 'w',
+    };
+    Some(format!("{} {} {} 1", ranks.join("/"), side, hand))
 }
 
-fn parse_piece_kind(s: &str, is_shogi_hint: bool) -> Option<PieceKind> {
+pub(crate) fn parse_piece_kind(s: &str, is_shogi_hint: bool) -> Option<PieceKind> {
+    if let Some(symbol) = s.strip_prefix('@') {
+        return crate::core::fairy::by_symbol(symbol);
+    }
     let lower = s.to_lowercase();
     match lower.as_str() {
         // 成り駒などの2文字表記はそのまま
@@ -186,3 +495,164 @@ pub fn get_reversed_fair_setup() -> Vec<&'static str> {
         "CR CN CB CQ CK G S N L", // P1 starting line: Chess Left, King, Shogi Right
     ]
 }
+
+/// The named starting positions
+#[derive(Clone, Copy, Debug)]
+pub enum BoardSetupType {
+    StandardMixed,
+    ReversedMixed,
+    ShogiOnly,
+    ChessOnly,
+    Fair,
+    ReversedFair,
+}
+
+impl std::fmt::Display for BoardSetupType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            BoardSetupType::StandardMixed => "StandardMixed",
+            BoardSetupType::ReversedMixed => "ReversedMixed",
+            BoardSetupType::ShogiOnly => "ShogiOnly",
+            BoardSetupType::ChessOnly => "ChessOnly",
+            BoardSetupType::Fair => "Fair",
+            BoardSetupType::ReversedFair => "ReversedFair",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl BoardSetupType {
+    /// Parse the name printed by `Display` (e.g. "ShogiOnly")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "StandardMixed" => Some(BoardSetupType::StandardMixed),
+            "ReversedMixed" => Some(BoardSetupType::ReversedMixed),
+            "ShogiOnly" => Some(BoardSetupType::ShogiOnly),
+            "ChessOnly" => Some(BoardSetupType::ChessOnly),
+            "Fair" => Some(BoardSetupType::Fair),
+            "ReversedFair" => Some(BoardSetupType::ReversedFair),
+            _ => None,
+        }
+    }
+
+    pub fn create_board(&self) -> Board {
+        self.create_board_with(None, None)
+            .expect("a setup without handicap always builds")
+    }
+
+    /// The setup, with its chess back ranks shuffled when a Chess960 seed is
+    /// given (see [`chess960_setup`]) and then the
+    /// handicap, if any, removed
+    pub fn create_board_with(
+        &self,
+        chess960_seed: Option<u64>,
+        handicap: Option<crate::core::handicap::Handicap>,
+    ) -> anyhow::Result<Board> {
+        let (map, p1_shogi, p2_shogi) = match self {
+            BoardSetupType::StandardMixed => (get_standard_mixed_setup(), true, true),
+            BoardSetupType::ReversedMixed => (get_reversed_mixed_setup(), true, true),
+            BoardSetupType::ShogiOnly => (get_shogi_setup(), true, true),
+            BoardSetupType::ChessOnly => (get_chess_setup(), false, false),
+            BoardSetupType::Fair => (get_fair_setup(), true, true),
+            BoardSetupType::ReversedFair => (get_reversed_fair_setup(), false, false),
+        };
+        let mut board = setup_from_strings_chess960(&map, p1_shogi, p2_shogi, chess960_seed);
+        if let Some(handicap) = handicap {
+            handicap.apply(&mut board)?;
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_hands_and_round_trip() {
+        let setup = [". . k", ". +P .", "K . .", "hand 2P G", "hand b cp"];
+        let board = setup_from_strings(&setup, true, true, None, None);
+        assert_eq!((board.width, board.height), (3, 3));
+        assert_eq!(
+            board.get_piece(Position::new(1, 1)).map(|p| p.kind),
+            Some(PieceKind::S_ProPawn)
+        );
+        let hand = |player: PlayerId, kind: PieceKind| {
+            board.hand.get(&player).and_then(|h| h.get(&kind)).copied()
+        };
+        assert_eq!(hand(PlayerId::Player1, PieceKind::S_Pawn), Some(2));
+        assert_eq!(hand(PlayerId::Player1, PieceKind::S_Gold), Some(1));
+        assert_eq!(hand(PlayerId::Player2, PieceKind::S_Bishop), Some(1));
+        assert_eq!(hand(PlayerId::Player2, PieceKind::C_Pawn), Some(1));
+
+        let hash = |b: &Board| crate::logic::ZobristHasher::compute_hash(b, PlayerId::Player1);
+        for (map, p1_shogi, p2_shogi) in [
+            (setup.to_vec(), true, true),
+            (get_fair_setup(), true, true),
+            (get_reversed_mixed_setup(), false, true),
+            (get_chess_setup(), false, false),
+        ] {
+            let board = setup_from_strings(&map, p1_shogi, p2_shogi, None, None);
+            let exported = setup_to_strings(&board);
+            let rows: Vec<&str> = exported.iter().map(String::as_str).collect();
+            let restored = setup_from_strings(&rows, p1_shogi, p2_shogi, None, None);
+            assert_eq!(hash(&restored), hash(&board), "{:?}", exported);
+        }
+    }
+
+    #[test]
+    fn test_sfen() {
+        let board = setup_from_strings(&get_shogi_setup(), true, true, None, None);
+        assert_eq!(
+            to_sfen(&board, PlayerId::Player1).as_deref(),
+            Some("lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1")
+        );
+
+        let mut rows = vec![". . . . . . . . ."; 9];
+        rows[0] = ". . . . k . . . .";
+        rows[4] = ". . +p . . . . . +R";
+        rows[8] = ". . . . K . . . .";
+        rows.push("hand 2P b G");
+        let board = setup_from_strings(&rows, true, true, None, None);
+        assert_eq!(
+            to_sfen(&board, PlayerId::Player2).as_deref(),
+            Some("4k4/9/9/9/2+p5+R/9/9/9/4K4 w G2Pb 1")
+        );
+
+        // Chess pieces and other board sizes have no SFEN
+        let board = setup_from_strings(&get_fair_setup(), true, true, None, None);
+        assert_eq!(to_sfen(&board, PlayerId::Player1), None);
+        let board = setup_from_strings(&[". k .", ". . .", ". K ."], true, true, None, None);
+        assert_eq!(to_sfen(&board, PlayerId::Player1), None);
+    }
+
+    #[test]
+    fn test_chess960_back_ranks() {
+        let chess = get_chess_setup();
+        let mut shuffled_any = false;
+        for seed in 0..20 {
+            let rows = chess960_setup(&chess, seed);
+            assert_eq!(rows, chess960_setup(&chess, seed));
+            assert_eq!(rows[0], rows[7].to_lowercase(), "both sides mirror");
+            assert_eq!(rows[1..7], chess[1..7]);
+            let back: Vec<&str> = rows[7].split_whitespace().collect();
+            let files = |kind: &str| -> Vec<usize> {
+                (0..back.len()).filter(|&x| back[x] == kind).collect()
+            };
+            let (bishops, rooks, king) = (files("CB"), files("CR"), files("CK")[0]);
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            assert!(rooks[0] < king && king < rooks[1]);
+            shuffled_any |= rows[7] != chess[7];
+        }
+        assert!(shuffled_any);
+
+        // Mixed setups only move the chess pieces
+        let fair = get_fair_setup();
+        let rows = chess960_setup(&fair, 3);
+        let row: Vec<&str> = rows[0].split_whitespace().collect();
+        assert_eq!(row[5..], ["g", "s", "n", "l"]);
+        let mut chess_pieces = row[..5].to_vec();
+        chess_pieces.sort();
+        assert_eq!(chess_pieces, ["cb", "ck", "cn", "cq", "cr"]);
+    }
+}
@@ -20,11 +20,44 @@ impl PlayerId {
 
 /// プレイヤーの能力設定
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "StoredPlayerConfig")]
 pub struct PlayerConfig {
     pub can_capture: bool,   // 駒を取れるか
     pub can_promote: bool,   // 成れるか
     pub can_drop: bool,      // 持ち駒を打てるか
     pub keep_captured: bool, // 取った駒を持ち駒にするか (将棋 = true, チェス = false)
+    /// 歩を打って詰ませてよいか (将棋では打ち歩詰めで反則)
+    #[serde(default)]
+    pub pawn_drop_mate: bool,
+    /// 手詰まり（王手されずに指す手がない）を引き分けにするか (チェス = true, 将棋 = 負け)
+    pub stalemate_draws: bool,
+}
+
+/// 保存された [`PlayerConfig`]。`stalemate_draws` のない古い設定では、
+/// 持ち駒を使わないチェスの設定なら引き分け、それ以外は負けとする
+#[derive(Deserialize)]
+struct StoredPlayerConfig {
+    can_capture: bool,
+    can_promote: bool,
+    can_drop: bool,
+    keep_captured: bool,
+    #[serde(default)]
+    pawn_drop_mate: bool,
+    stalemate_draws: Option<bool>,
+}
+
+impl From<StoredPlayerConfig> for PlayerConfig {
+    fn from(stored: StoredPlayerConfig) -> Self {
+        let chess = !stored.can_drop && !stored.keep_captured;
+        PlayerConfig {
+            can_capture: stored.can_capture,
+            can_promote: stored.can_promote,
+            can_drop: stored.can_drop,
+            keep_captured: stored.keep_captured,
+            pawn_drop_mate: stored.pawn_drop_mate,
+            stalemate_draws: stored.stalemate_draws.unwrap_or(chess),
+        }
+    }
 }
 
 impl Default for PlayerConfig {
@@ -34,6 +67,8 @@ impl Default for PlayerConfig {
             can_promote: true,
             can_drop: true,
             keep_captured: true,
+            pawn_drop_mate: false,
+            stalemate_draws: false,
         }
     }
 }
@@ -49,6 +84,8 @@ impl PlayerConfig {
             can_promote: false, // チェスのPawnプロモーションは駒個別の能力として扱う方が汎用的
             can_drop: false,
             keep_captured: false,
+            pawn_drop_mate: false,
+            stalemate_draws: true,
         }
     }
 }
@@ -0,0 +1,281 @@
+//! Crash report bundles
+//!
+//! A panic, or an internal inconsistency reported with [`report`], writes
+//! `crash_reports/<timestamp>/` with what a bug report needs, and prints its
+//! path on one line:
+//! - `report.txt`: engine version, build features, the reason and a backtrace
+//! - `board.txt`: the last rendered board and its status line
+//! - `position.txt`: the current game position as setup-string rows (see
+//!   [`crate::core::setup::setup_to_strings`]) with the side to move
+//! - `log.txt`: the most recent game events and status messages
+//! - `ai_config.json`: the configuration in effect
+//!
+//! The game loop and the renderer keep the context up to date with
+//! [`record_position`], [`record_render`] and [`log`].
+
+use crate::core::{Board, PlayerId};
+use crate::ui::glyphs::{piece_label, GlyphStyle};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const CRASH_DIR: &str = "crash_reports";
+
+/// Log lines kept for `log.txt`
+const LOG_CAPACITY: usize = 200;
+
+#[derive(Default)]
+struct CrashContext {
+    /// Last rendered board and its status message
+    rendered: Option<(Board, Option<String>)>,
+    /// Current game position: board, side to move, setup name
+    position: Option<(Board, PlayerId, String)>,
+    log: VecDeque<String>,
+}
+
+static CONTEXT: Lazy<Mutex<CrashContext>> = Lazy::new(|| Mutex::new(CrashContext::default()));
+
+/// Run `f` on the context unless it is locked (a panic while it was held
+/// must not deadlock the panic hook)
+fn with_context<T>(f: impl FnOnce(&mut CrashContext) -> T) -> Option<T> {
+    match CONTEXT.try_lock() {
+        Ok(mut context) => Some(f(&mut context)),
+        Err(std::sync::TryLockError::Poisoned(poisoned)) => Some(f(&mut poisoned.into_inner())),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Append a line to the log tail
+pub fn log(line: impl Into<String>) {
+    let line = line.into();
+    with_context(|context| {
+        if context.log.len() == LOG_CAPACITY {
+            context.log.pop_front();
+        }
+        let timestamp = chrono::Local::now().format("%H:%M:%S%.3f");
+        context.log.push_back(format!("{} {}", timestamp, line));
+    });
+}
+
+/// Remember the board just drawn; a changed status message is also logged
+pub fn record_render(board: &Board, status: Option<&str>) {
+    let changed = with_context(|context| {
+        let changed = context
+            .rendered
+            .as_ref()
+            .is_none_or(|(_, previous)| previous.as_deref() != status);
+        context.rendered = Some((board.clone(), status.map(str::to_string)));
+        changed
+    });
+    if let (Some(true), Some(status)) = (changed, status) {
+        log(format!("status: {}", status));
+    }
+}
+
+/// Remember the position of the game being played
+pub fn record_position(board: &Board, side_to_move: PlayerId, setup: &str) {
+    with_context(|context| {
+        context.position = Some((board.clone(), side_to_move, setup.to_string()));
+    });
+}
+
+/// Forget the game position once the game is over
+pub fn clear_position() {
+    with_context(|context| context.position = None);
+}
+
+/// Write a bundle for panics, then run the default hook. The terminal leaves
+/// raw mode and the alternate screen first, so the message and the bundle
+/// path stay visible.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = crossterm::terminal::disable_raw_mode();
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::terminal::LeaveAlternateScreen);
+        default_hook(info);
+
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        match write_bundle(
+            Path::new(CRASH_DIR),
+            &format!("panic: {}", info),
+            &backtrace,
+        ) {
+            Ok(dir) => eprintln!("Crash report written to {}", dir.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+    }));
+}
+
+/// Report an internal inconsistency that the program survives: write a
+/// bundle and print its path. Returns the bundle directory.
+pub fn report(reason: &str) -> Option<PathBuf> {
+    log(format!("inconsistency: {}", reason));
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    match write_bundle(Path::new(CRASH_DIR), reason, &backtrace) {
+        Ok(dir) => {
+            eprintln!(
+                "[!] Internal inconsistency: {}. Crash report written to {}\r",
+                reason,
+                dir.display()
+            );
+            Some(dir)
+        }
+        Err(e) => {
+            eprintln!(
+                "[!] Internal inconsistency: {} (failed to write crash report: {})\r",
+                reason, e
+            );
+            None
+        }
+    }
+}
+
+/// Plain-text board: `^` marks Player1's pieces, `v` Player2's
+fn board_text(board: &Board) -> String {
+    let mut text = String::new();
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let cell = match board.get_piece(crate::core::Position::new(x, y)) {
+                Some(piece) => {
+                    let owner = if piece.owner == PlayerId::Player1 {
+                        '^'
+                    } else {
+                        'v'
+                    };
+                    format!("{}{}", owner, piece_label(piece.kind, GlyphStyle::Romaji))
+                }
+                None => "  . ".to_string(),
+            };
+            text.push_str(&cell);
+        }
+        text.push('\n');
+    }
+    for (player, hand) in &board.hand {
+        let pieces: Vec<String> = hand
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .map(|(kind, count)| format!("{:?}x{}", kind, count))
+            .collect();
+        if !pieces.is_empty() {
+            let _ = writeln!(text, "{:?} hand: {}", player, pieces.join(" "));
+        }
+    }
+    text
+}
+
+fn create_bundle_dir(base: &Path) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(base)?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    // Several crashes in one second (parallel games) get their own directories
+    for attempt in 1.. {
+        let name = if attempt == 1 {
+            timestamp.clone()
+        } else {
+            format!("{}_{}", timestamp, attempt)
+        };
+        let dir = base.join(name);
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    unreachable!()
+}
+
+/// Write a bundle under `base` and return its directory
+pub fn write_bundle(base: &Path, reason: &str, backtrace: &str) -> anyhow::Result<PathBuf> {
+    let dir = create_bundle_dir(base)?;
+    let (rendered, position, log) = with_context(|context| {
+        (
+            context.rendered.clone(),
+            context.position.clone(),
+            context.log.iter().cloned().collect::<Vec<_>>(),
+        )
+    })
+    .unwrap_or_default();
+
+    let mut report = String::new();
+    writeln!(
+        report,
+        "{} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(
+        report,
+        "ml feature: {}",
+        if crate::ml::ML_ENABLED {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    )?;
+    writeln!(
+        report,
+        "platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    writeln!(report, "time: {}", chrono::Local::now().to_rfc3339())?;
+    writeln!(report, "\n{}\n\n{}", reason, backtrace)?;
+    std::fs::write(dir.join("report.txt"), report)?;
+
+    if let Some((board, status)) = rendered {
+        let status = status.map(|s| format!("{}\n\n", s)).unwrap_or_default();
+        std::fs::write(dir.join("board.txt"), status + &board_text(&board))?;
+    }
+    if let Some((board, side_to_move, setup)) = position {
+        let mut text = format!("setup: {}\nside to move: {:?}\n", setup, side_to_move);
+        for row in crate::core::setup::setup_to_strings(&board) {
+            text.push_str(&row);
+            text.push('\n');
+        }
+        std::fs::write(dir.join("position.txt"), text)?;
+    }
+    std::fs::write(dir.join("log.txt"), log.join("\n") + "\n")?;
+    crate::util::atomic_write_json(
+        &dir.join("ai_config.json"),
+        crate::player::ai::config::AIConfig::get(),
+        true,
+    )?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selfplay::BoardSetupType;
+
+    #[test]
+    fn test_bundle_contents() {
+        let board = BoardSetupType::ShogiOnly.create_board();
+        record_position(&board, PlayerId::Player2, "ShogiOnly");
+        record_render(&board, Some("Player2's turn"));
+        log("crash test marker");
+
+        let base = std::env::temp_dir().join(format!("aho_crash_{}", std::process::id()));
+        let first = write_bundle(&base, "test reason", "no backtrace").unwrap();
+        let second = write_bundle(&base, "test reason", "no backtrace").unwrap();
+        let read = |name: &str| std::fs::read_to_string(first.join(name)).unwrap();
+        let (report, position, log_tail, board_txt) = (
+            read("report.txt"),
+            read("position.txt"),
+            read("log.txt"),
+            read("board.txt"),
+        );
+        let config_written = first.join("ai_config.json").exists();
+        std::fs::remove_dir_all(&base).ok();
+
+        assert_ne!(first, second);
+        assert!(report.contains(env!("CARGO_PKG_VERSION")) && report.contains("test reason"));
+        assert!(log_tail.contains("crash test marker"));
+        assert!(config_written);
+        assert!(board_txt.starts_with("Player2's turn"));
+        assert!(position.starts_with("setup: ShogiOnly\nside to move: Player2\n"));
+        let rows: Vec<String> = position.lines().skip(2).map(str::to_string).collect();
+        assert_eq!(rows, crate::core::setup::setup_to_strings(&board));
+    }
+}
@@ -0,0 +1,374 @@
+//! Demo (attract) mode
+//!
+//! Runs unattended until a key is pressed: each round either replays a
+//! random archived kifu or plays a fresh Light-vs-Light game, one move per
+//! `move_delay`, with the evaluation and the last move under the board.
+//! Useful for showcasing the engine and for soak-testing the renderer.
+
+use crate::core::{Board, Move, PlayerId};
+use crate::game::replay::ReplayViewer;
+use crate::game::KifuData;
+use crate::logic::{apply_move, is_checkmate, legal_moves};
+use crate::player::ai::config::AIConfig;
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use crate::player::PlayerController;
+use crate::selfplay::BoardSetupType;
+use crate::ui::display::{render_board, DisplayState};
+use crossterm::event::{self, Event};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const SETUPS: [BoardSetupType; 6] = [
+    BoardSetupType::StandardMixed,
+    BoardSetupType::ReversedMixed,
+    BoardSetupType::ShogiOnly,
+    BoardSetupType::ChessOnly,
+    BoardSetupType::Fair,
+    BoardSetupType::ReversedFair,
+];
+
+/// Archived files tried before falling back to a live game (some JSON files
+/// under the kifu directories are run manifests or reports)
+const KIFU_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemoSource {
+    /// Replays and live games, chosen at random each round
+    Mixed,
+    Replay,
+    Live,
+}
+
+#[derive(Debug, Clone)]
+pub struct DemoConfig {
+    /// Pause after each move
+    pub move_delay: Duration,
+    /// Pause on the final position before the next round
+    pub result_delay: Duration,
+    pub source: DemoSource,
+    /// Directories searched for archived kifu
+    pub kifu_dirs: Vec<PathBuf>,
+    /// Live games are adjourned after this many plies
+    pub max_plies: usize,
+    /// Stop after this many rounds (`None`: until a key is pressed)
+    pub rounds: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+impl Default for DemoConfig {
+    fn default() -> Self {
+        Self {
+            move_delay: Duration::from_millis(800),
+            result_delay: Duration::from_secs(3),
+            source: DemoSource::Mixed,
+            kifu_dirs: vec![
+                PathBuf::from("kifu"),
+                PathBuf::from("selfplay_results"),
+                PathBuf::from("selfplay_kifu"),
+            ],
+            max_plies: 200,
+            rounds: None,
+            seed: None,
+        }
+    }
+}
+
+/// One frame of the demo: the position plus the lines printed under it
+struct Frame<'a> {
+    round: usize,
+    title: &'a str,
+    board: &'a Board,
+    ply: usize,
+    last_move: Option<Move>,
+    /// `last_move` in notation
+    move_text: Option<String>,
+    /// Player1's perspective
+    eval: Option<(i32, &'static str)>,
+    status: Option<String>,
+}
+
+impl Frame<'_> {
+    fn render(&self) {
+        let state = DisplayState {
+            perspective: PlayerId::Player1,
+            last_move: self.last_move,
+            show_cursor: false,
+            status_msg: self.status.clone(),
+            ..Default::default()
+        };
+        render_board(self.board, &state);
+
+        print!("\r\n=== Demo (round {}) ===\r\n", self.round);
+        print!("{}\r\n", self.title);
+        match &self.move_text {
+            Some(text) => print!("Move {}: {}\r\n", self.ply, text),
+            None => print!("Start position\r\n"),
+        }
+        if let Some((score, source)) = self.eval {
+            print!("Eval: {:+} ({}, Player1's view)\r\n", score, source);
+        }
+        print!("Press any key to exit\r\n");
+    }
+}
+
+/// Run rounds until a key is pressed (or `config.rounds` are done)
+pub fn run(config: &DemoConfig) -> anyhow::Result<()> {
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let kifu = if config.source == DemoSource::Live {
+        Vec::new()
+    } else {
+        crate::ui::kifu_selector::kifu_paths(&config.kifu_dirs)?
+    };
+    if config.source == DemoSource::Replay && kifu.is_empty() {
+        anyhow::bail!("No archived kifu found in {:?}", config.kifu_dirs);
+    }
+
+    let mut round = 1;
+    while config.rounds.is_none_or(|rounds| round <= rounds) {
+        let replay = match config.source {
+            DemoSource::Replay => true,
+            DemoSource::Live => false,
+            DemoSource::Mixed => !kifu.is_empty() && rng.gen_bool(0.5),
+        };
+        let archived = if replay {
+            pick_kifu(&kifu, &mut rng)
+        } else {
+            None
+        };
+        let stopped = match archived {
+            Some((path, data)) => replay_kifu(config, round, &path, &data)?,
+            None => play_live(config, round, *SETUPS.choose(&mut rng).unwrap())?,
+        };
+        if stopped {
+            break;
+        }
+        round += 1;
+    }
+    Ok(())
+}
+
+/// A random archived game with at least one move
+fn pick_kifu(paths: &[PathBuf], rng: &mut StdRng) -> Option<(PathBuf, KifuData)> {
+    (0..KIFU_ATTEMPTS).find_map(|_| {
+        let path = paths.choose(rng)?;
+        let data = crate::util::read_json_or_skip::<KifuData>(path).ok()??;
+        (!data.moves.is_empty()).then(|| (path.clone(), data))
+    })
+}
+
+fn replay_kifu(
+    config: &DemoConfig,
+    round: usize,
+    path: &Path,
+    kifu: &KifuData,
+) -> anyhow::Result<bool> {
+    let title = format!(
+        "Replay: {} vs {} ({}) - {}",
+        kifu.player1_name,
+        kifu.player2_name,
+        kifu.board_setup,
+        path.display()
+    );
+    let (boards, unavailable) = ReplayViewer::replay_boards(kifu);
+    for (ply, board) in boards.iter().enumerate() {
+        let last = ply + 1 == boards.len();
+        let status = if unavailable[ply] {
+            Some("Move list does not replay here; showing the last valid position".to_string())
+        } else if last {
+            Some(format!("End of game ({} moves)", kifu.moves.len()))
+        } else {
+            None
+        };
+        Frame {
+            round,
+            title: &title,
+            board,
+            ply,
+            last_move: ply.checked_sub(1).map(|i| kifu.moves[i]),
+            move_text: ply.checked_sub(1).map(|i| {
+                let mover = if i.is_multiple_of(2) {
+                    PlayerId::Player1
+                } else {
+                    PlayerId::Player2
+                };
+                kifu.moves[i].notation(&boards[i], mover).to_string()
+            }),
+            eval: Some((crate::player::ai::eval::evaluate(board), "static")),
+            status,
+        }
+        .render();
+        let delay = if last {
+            config.result_delay
+        } else {
+            config.move_delay
+        };
+        if key_pressed_within(delay)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn play_live(config: &DemoConfig, round: usize, setup: BoardSetupType) -> anyhow::Result<bool> {
+    // Vary the openings even when the configured randomness is off, or every
+    // game on a setup would be the same
+    let randomness = crate::player::ai::config::OpeningRandomnessConfig {
+        enabled: true,
+        ..AIConfig::get().opening_randomness.clone()
+    };
+    let engines = [PlayerId::Player1, PlayerId::Player2].map(|player| {
+        AlphaBetaAI::new(player, "Demo", AIStrength::Light, None, true)
+            .with_opening_randomness(&randomness)
+    });
+    let title = format!(
+        "Live: Light vs Light on {} ({})",
+        setup,
+        engines[0].evaluator_name()
+    );
+
+    let mut board = setup.create_board();
+    let mut player = PlayerId::Player1;
+    let mut last_move = None;
+    let mut move_text = None;
+    let mut eval = None;
+    for ply in 0.. {
+        let repetition = board
+            .history
+            .iter()
+            .filter(|&&h| h == board.zobrist_hash)
+            .count()
+            >= board.rules.repetition_limit;
+        let moves = legal_moves(&board, player);
+        let result = if repetition {
+            Some("Sennichite (Repetition) - Draw".to_string())
+        } else if moves.is_empty() {
+            Some(match crate::logic::rules::no_move_winner(&board, player) {
+                None => "Stalemate - Draw".to_string(),
+                Some(winner) if is_checkmate(&board, player) => {
+                    format!("Checkmate! {:?} wins", winner)
+                }
+                Some(winner) => format!("No more moves! {:?} wins", winner),
+            })
+        } else if ply >= config.max_plies {
+            Some(format!("Adjourned after {} plies", ply))
+        } else {
+            None
+        };
+
+        let frame = Frame {
+            round,
+            title: &title,
+            board: &board,
+            ply,
+            last_move,
+            move_text: move_text.clone(),
+            eval,
+            status: result
+                .clone()
+                .or_else(|| Some(format!("{:?} is thinking...", player))),
+        };
+        frame.render();
+        if result.is_some() {
+            return key_pressed_within(config.result_delay);
+        }
+
+        let engine = match player {
+            PlayerId::Player1 => &engines[0],
+            PlayerId::Player2 => &engines[1],
+        };
+        let started = Instant::now();
+        let Some(mv) = engine.choose_move(&board, &moves) else {
+            break;
+        };
+        if let Some(info) = *engine.last_thinking.borrow() {
+            let score = if player == PlayerId::Player1 {
+                info.score
+            } else {
+                -info.score
+            };
+            eval = Some((score, "search"));
+        }
+        move_text = Some(mv.notation(&board, player).to_string());
+        board = apply_move(&board, &mv, player);
+        player = player.opponent();
+        last_move = Some(mv);
+
+        // Thinking time counts towards the pause, so fast and slow moves look alike
+        if key_pressed_within(config.move_delay.saturating_sub(started.elapsed()))? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Wait for `duration`, returning early (with `true`) on any key press
+fn key_pressed_within(duration: Duration) -> anyhow::Result<bool> {
+    let deadline = Instant::now() + duration;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(remaining.min(Duration::from_millis(100)))? {
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            continue;
+        }
+        if let Event::Key(_) = event::read()? {
+            return Ok(true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_kifu_skips_non_kifu_and_empty_games() {
+        let dir = std::env::temp_dir().join(format!("aho_demo_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("run.json");
+        std::fs::write(&manifest, r#"{"games": 3}"#).unwrap();
+
+        let mut kifu = KifuData {
+            board_setup: "ShogiOnly".to_string(),
+            chess960_seed: None,
+            handicap: None,
+            thinking_data: None,
+            player1_name: "A".to_string(),
+            player2_name: "B".to_string(),
+            moves: Vec::new(),
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots: Vec::new(),
+            result: None,
+            variations: Vec::new(),
+        };
+        let empty = dir.join("empty.json");
+        crate::util::atomic_write_json(&empty, &kifu, false).unwrap();
+        let board = BoardSetupType::ShogiOnly.create_board();
+        kifu.moves = vec![legal_moves(&board, PlayerId::Player1)[0]];
+        let game = dir.join("game.json");
+        crate::util::atomic_write_json(&game, &kifu, false).unwrap();
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let paths = [manifest, empty.clone()];
+        assert!(pick_kifu(&paths, &mut rng).is_none());
+        let paths = [paths[0].clone(), empty, game.clone()];
+        let picks: Vec<PathBuf> = (0..10)
+            .filter_map(|_| pick_kifu(&paths, &mut rng).map(|(path, _)| path))
+            .collect();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(!picks.is_empty());
+        assert!(picks.iter().all(|path| *path == game));
+    }
+}
@@ -0,0 +1,236 @@
+//! C bindings (`ffi` feature)
+//!
+//! A board is an opaque handle from [`aho_board_new`], freed with
+//! [`aho_board_free`]. Moves are [`AhoMove`] structs. Normal moves have the
+//! same six bytes as in dataset files; drops do not, since dataset files put
+//! the dropped kind in the second byte and [`AhoMove`] in `piece`, the last.
+//! Players are 1 (Player1, moves first) and 2.
+//! The declarations for C are in `include/shogi_aho_ai.h`.
+
+use crate::core::{Board, Move, PieceKind, PlayerId, Position};
+use std::ffi::{c_char, c_int, CStr};
+use std::time::Duration;
+
+/// No piece in [`AhoMove::piece`]: a normal move that does not promote
+pub const AHO_NO_PIECE: u8 = 0xFF;
+
+/// A move. `drop` is 0 for a move of a piece on the board and 1 for a drop
+/// from the hand (`from_x`/`from_y` unused). `piece` is the promoted kind of
+/// a promotion, the dropped kind of a drop, or [`AHO_NO_PIECE`]; kinds are
+/// [`PieceKind::code`]s. Squares count from the top left, Player2's side.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AhoMove {
+    pub drop: u8,
+    pub from_x: u8,
+    pub from_y: u8,
+    pub to_x: u8,
+    pub to_y: u8,
+    pub piece: u8,
+}
+
+impl From<Move> for AhoMove {
+    fn from(mv: Move) -> Self {
+        match mv {
+            Move::Normal { from, to, promote } => AhoMove {
+                drop: 0,
+                from_x: from.x as u8,
+                from_y: from.y as u8,
+                to_x: to.x as u8,
+                to_y: to.y as u8,
+                piece: promote.map(PieceKind::code).unwrap_or(AHO_NO_PIECE),
+            },
+            Move::Drop { kind, to } => AhoMove {
+                drop: 1,
+                from_x: 0,
+                from_y: 0,
+                to_x: to.x as u8,
+                to_y: to.y as u8,
+                piece: kind.code(),
+            },
+        }
+    }
+}
+
+impl AhoMove {
+    fn to_move(self) -> Option<Move> {
+        let to = Position::new(self.to_x as usize, self.to_y as usize);
+        match self.drop {
+            0 => Some(Move::Normal {
+                from: Position::new(self.from_x as usize, self.from_y as usize),
+                to,
+                promote: match self.piece {
+                    AHO_NO_PIECE => None,
+                    code => Some(PieceKind::from_code(code)?),
+                },
+            }),
+            1 => Some(Move::Drop {
+                kind: PieceKind::from_code(self.piece)?,
+                to,
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn player(player: c_int) -> Option<PlayerId> {
+    match player {
+        1 => Some(PlayerId::Player1),
+        2 => Some(PlayerId::Player2),
+        _ => None,
+    }
+}
+
+/// New board of a named setup ("ShogiOnly", "ChessOnly", "Fair", ...), or
+/// null for an unknown name
+///
+/// # Safety
+/// `setup` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn aho_board_new(setup: *const c_char) -> *mut Board {
+    if setup.is_null() {
+        return std::ptr::null_mut();
+    }
+    let name = CStr::from_ptr(setup).to_string_lossy();
+    match crate::BoardSetupType::from_name(&name) {
+        Some(setup) => Box::into_raw(Box::new(setup.create_board())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Free a board from [`aho_board_new`]; null is ignored
+///
+/// # Safety
+/// `board` must come from [`aho_board_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aho_board_free(board: *mut Board) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Write up to `capacity` legal moves of `player` to `out` and return how
+/// many there are in total (call with `capacity` 0 to size the buffer).
+/// Returns -1 for a null board or an unknown player.
+///
+/// # Safety
+/// `board` must be a live board; `out` must have room for `capacity` moves.
+#[no_mangle]
+pub unsafe extern "C" fn aho_legal_moves(
+    board: *const Board,
+    player_id: c_int,
+    out: *mut AhoMove,
+    capacity: usize,
+) -> isize {
+    let (Some(board), Some(player)) = (board.as_ref(), player(player_id)) else {
+        return -1;
+    };
+    let moves = crate::logic::legal_moves(board, player);
+    if !out.is_null() {
+        for (i, &mv) in moves.iter().take(capacity).enumerate() {
+            *out.add(i) = mv.into();
+        }
+    }
+    moves.len() as isize
+}
+
+/// Play `mv` for `player` on `board`. Returns 0, or -1 if the move is not
+/// legal (the board is then unchanged).
+///
+/// # Safety
+/// `board` must be a live board and `mv` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn aho_apply_move(
+    board: *mut Board,
+    player_id: c_int,
+    mv: *const AhoMove,
+) -> c_int {
+    let (Some(board), Some(player), Some(mv)) = (board.as_mut(), player(player_id), mv.as_ref())
+    else {
+        return -1;
+    };
+    match mv.to_move() {
+        Some(mv) if crate::logic::legal_moves(board, player).contains(&mv) => {
+            *board = crate::logic::apply_move(board, &mv, player);
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Search the best move for `player` within `time_limit_ms` milliseconds,
+/// with the evaluator and depth limit (`search.max_depth_strong`) configured
+/// in `ai_config.json`. A depth still running when the limit passes is
+/// abandoned and the move of the deepest finished one is returned. Returns 1
+/// and writes the move to `out`, 0 if `player` has no legal move, -1 on bad
+/// arguments.
+///
+/// # Safety
+/// `board` must be a live board and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn aho_search(
+    board: *const Board,
+    player_id: c_int,
+    time_limit_ms: u32,
+    out: *mut AhoMove,
+) -> c_int {
+    let (Some(board), Some(player)) = (board.as_ref(), player(player_id)) else {
+        return -1;
+    };
+    if out.is_null() {
+        return -1;
+    }
+    let strength = crate::AIStrength::Strong;
+    let engine = crate::AlphaBetaAI::new(player, "Engine", strength, None, true);
+    let max_depth = crate::player::ai::config::AIConfig::get()
+        .search
+        .max_depth(strength) as usize;
+    match engine.search_with_limits(
+        board,
+        max_depth,
+        Duration::from_millis(time_limit_ms as u64),
+    ) {
+        Some(mv) => {
+            *out = mv.into();
+            1
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_api() {
+        unsafe {
+            assert!(aho_board_new(c"NoSuchSetup".as_ptr()).is_null());
+            let board = aho_board_new(c"ShogiOnly".as_ptr());
+            assert!(!board.is_null());
+
+            let count = aho_legal_moves(board, 1, std::ptr::null_mut(), 0);
+            assert_eq!(count, 30);
+            let mut moves = vec![
+                AhoMove::from(Move::Drop {
+                    kind: PieceKind::S_Pawn,
+                    to: Position::new(0, 0),
+                });
+                count as usize
+            ];
+            aho_legal_moves(board, 1, moves.as_mut_ptr(), moves.len());
+            assert_eq!(aho_legal_moves(board, 3, moves.as_mut_ptr(), 0), -1);
+
+            assert_eq!(aho_apply_move(board, 1, &moves[0]), 0);
+            // The same move again is not legal for the same side
+            assert_eq!(aho_apply_move(board, 1, &moves[0]), -1);
+
+            let mut best = moves[0];
+            let start = std::time::Instant::now();
+            assert_eq!(aho_search(board, 2, 200, &mut best), 1);
+            assert!(start.elapsed() < Duration::from_millis(200 + 300));
+            assert_eq!(aho_apply_move(board, 2, &best), 0);
+            aho_board_free(board);
+        }
+    }
+}
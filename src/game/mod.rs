@@ -1,9 +1,15 @@
 use crate::core::{Board, Move, PlayerId};
-use crate::logic::{apply_move, legal_moves};
+use crate::logic::apply_move;
+#[cfg(feature = "cli")]
+use crate::logic::legal_moves;
+#[cfg(feature = "cli")]
 use crate::player::PlayerController;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "cli")]
 pub mod replay;
+#[cfg(feature = "cli")]
+pub mod verify;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ThinkingInfo {
@@ -13,11 +19,89 @@ pub struct ThinkingInfo {
     pub score: i32,
     pub nodes: usize,
     pub time_ms: u128,
+    /// Depth limit after adaptive adjustment (0 for terminal positions)
+    #[serde(default)]
+    pub max_depth: usize,
+    /// Adaptive depth profile used for the search (e.g. "Endgame", "DropHeavy")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<SearchHealth>,
+}
+
+/// Search-quality indicators of one search, so that regressions in pruning
+/// or move ordering show up in the recorded games
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SearchHealth {
+    /// Occupied transposition table entries per mille (sampled)
+    pub hashfull: u16,
+    /// Share of nodes spent in quiescence search
+    pub qsearch_share: f32,
+    /// Share of expanded nodes that ended in a beta cutoff
+    pub cut_rate: f32,
+    /// Moves searched per expanded node
+    pub branching_factor: f32,
+}
+
+impl std::fmt::Display for SearchHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hash={:.1}% qs={:.0}% cut={:.0}% bf={:.2}",
+            self.hashfull as f32 / 10.0,
+            self.qsearch_share * 100.0,
+            self.cut_rate * 100.0,
+            self.branching_factor
+        )
+    }
+}
+
+/// One point of an evaluation curve (Player1's perspective)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct EvalSample {
+    pub move_number: usize,
+    pub score: i32,
+}
+
+/// A large evaluation swing between two consecutive searched moves
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct CriticalMoment {
+    pub move_number: usize,
+    pub score_before: i32,
+    pub score_after: i32,
+}
+
+/// Swing of the evaluation (centipawns) between two consecutive searched
+/// moves that makes the later one a critical moment
+pub const CRITICAL_SWING: i32 = 2000;
+
+/// Indices into `scores` (one per searched move, from Player1's side) where
+/// the evaluation swung by more than [`CRITICAL_SWING`]
+pub fn critical_swings(scores: &[i32]) -> Vec<usize> {
+    (1..scores.len())
+        .filter(|&i| (scores[i] - scores[i - 1]).abs() > CRITICAL_SWING)
+        .collect()
+}
+
+/// Evaluation curve of a finished game, possibly downsampled.
+/// Critical moments are always kept in full.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EvalTrajectory {
+    /// Every `sample_interval`-th searched move is kept (plus the last one)
+    pub sample_interval: usize,
+    pub samples: Vec<EvalSample>,
+    pub critical_moments: Vec<CriticalMoment>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct KifuData {
     pub board_setup: String,
+    /// Seed of the Chess960 back ranks (see [`crate::core::setup::chess960_setup`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chess960_seed: Option<u64>,
+    /// Pieces Player1 gave up at the start (see [`crate::core::handicap`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handicap: Option<crate::core::handicap::Handicap>,
     pub player1_name: String,
     pub player2_name: String,
     pub moves: Vec<Move>,
@@ -29,6 +113,98 @@ pub struct KifuData {
     pub model_path: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model_version: Option<String>,
+    /// Full provenance of the model (generation, training data, parent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_lineage: Option<crate::ml::model_registry::ModelLineage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eval_trajectory: Option<EvalTrajectory>,
+    /// Full positions every few plies (see [`KifuSnapshot`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snapshots: Vec<KifuSnapshot>,
+    /// How the game ended, if it was played to a finish
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<KifuResult>,
+    /// Lines explored in the replay viewer instead of the recorded moves
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variations: Vec<KifuVariation>,
+}
+
+/// A line played from a position of the game instead of the recorded move
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KifuVariation {
+    /// Plies of the recorded game before the variation
+    pub ply: usize,
+    pub moves: Vec<Move>,
+}
+
+/// The end of a recorded game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KifuResult {
+    /// `None` for a draw
+    pub winner: Option<PlayerId>,
+    /// What ended it, in English (`Checkmate`, `Resignation`, `Repetition`, ...)
+    pub reason: String,
+}
+
+/// Full game state at one point of a kifu. Lets a move list that no longer
+/// replays (corruption, rule changes) resume from the nearest snapshot, and
+/// lets external tools read positions without implementing move application.
+/// Repetition history is not stored.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KifuSnapshot {
+    /// Plies played before this position
+    pub ply: usize,
+    pub side_to_move: PlayerId,
+    pub board: Board,
+}
+
+impl KifuSnapshot {
+    /// Board ready for move application (Zobrist hash recomputed)
+    pub fn restore(&self) -> Board {
+        let mut board = self.board.clone();
+        board.zobrist_hash = crate::logic::ZobristHasher::compute_hash(&board, self.side_to_move);
+        board.history = vec![board.zobrist_hash];
+        board
+    }
+}
+
+/// Snapshots at ply 0 and every `interval` plies of `moves` played from
+/// `initial`. With `interval` 0 only a ply 0 snapshot, and only when `initial`
+/// has pieces in hand, which a setup name cannot reproduce.
+pub fn snapshots_every(initial: &Board, moves: &[Move], interval: usize) -> Vec<KifuSnapshot> {
+    if interval == 0 {
+        let has_hand = initial
+            .hand
+            .values()
+            .flat_map(|h| h.values())
+            .any(|&c| c > 0);
+        return if has_hand {
+            vec![KifuSnapshot {
+                ply: 0,
+                side_to_move: PlayerId::Player1,
+                board: initial.clone(),
+            }]
+        } else {
+            Vec::new()
+        };
+    }
+    let mut snapshots = Vec::new();
+    let mut board = initial.clone();
+    let mut player = PlayerId::Player1;
+    for ply in 0..=moves.len() {
+        if ply % interval == 0 {
+            snapshots.push(KifuSnapshot {
+                ply,
+                side_to_move: player,
+                board: board.clone(),
+            });
+        }
+        if let Some(mv) = moves.get(ply) {
+            board = apply_move(&board, mv, player);
+            player = player.opponent();
+        }
+    }
+    snapshots
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,17 +213,37 @@ pub enum PerspectiveMode {
     AutoFlip,
 }
 
+pub use crate::logic::rules::RuleEnding;
+
+/// A game in progress, played in the terminal UI or by self-play
+#[cfg(feature = "cli")]
 pub struct Game {
     pub board: Board,
     pub current_player: PlayerId,
     pub board_sync_rx: Option<std::sync::mpsc::Receiver<(Board, PlayerId)>>,
     pub perspective_mode: PerspectiveMode,
     pub history: Vec<Move>,
+    /// Position before the first move, for the kifu
+    pub initial_board: Board,
     pub board_setup: String,
+    /// Chess960 seed the setup was shuffled with, for the kifu
+    pub chess960_seed: Option<u64>,
+    /// Handicap Player1 gave, for the kifu
+    pub handicap: Option<crate::core::handicap::Handicap>,
     pub player1_name: String,
     pub player2_name: String,
+    /// Plies since the last capture, promotion or pawn move
+    pub stagnation: crate::selfplay::adjudication::StagnationTracker,
+    /// Moves taken back with [`Game::undo`], the next one to replay last
+    redo: Vec<Move>,
+    /// Started from a position the setup name does not describe, so the kifu
+    /// always records it (see [`Game::from_position`])
+    start_snapshot: bool,
+    /// Set once the game is decided (see [`Game::end`])
+    pub result: Option<KifuResult>,
 }
 
+#[cfg(feature = "cli")]
 impl Game {
     pub fn new(mut board: Board) -> Self {
         // Initialize hash and history
@@ -56,14 +252,21 @@ impl Game {
         board.history = vec![hash];
 
         Game {
+            initial_board: board.clone(),
             board,
             current_player: PlayerId::Player1,
             board_sync_rx: None,
             perspective_mode: PerspectiveMode::AutoFlip,
             history: Vec::new(),
             board_setup: "Unknown".to_string(),
+            chess960_seed: None,
+            handicap: None,
             player1_name: "Player1".to_string(),
             player2_name: "Player2".to_string(),
+            stagnation: Default::default(),
+            redo: Vec::new(),
+            start_snapshot: false,
+            result: None,
         }
     }
 
@@ -74,21 +277,153 @@ impl Game {
         board.history = vec![hash];
 
         Game {
+            initial_board: board.clone(),
             board,
             current_player: PlayerId::Player1,
             board_sync_rx: None,
             perspective_mode: PerspectiveMode::AutoFlip,
             history: Vec::new(),
             board_setup,
+            chess960_seed: None,
+            handicap: None,
             player1_name: "Player1".to_string(),
             player2_name: "Player2".to_string(),
+            stagnation: Default::default(),
+            redo: Vec::new(),
+            start_snapshot: false,
+            result: None,
+        }
+    }
+
+    /// A game from an arbitrary position (the board editor's), `side_to_move`
+    /// to play first. `board_setup` names the setup it was edited from; the
+    /// position itself is kept in the kifu as its ply 0 snapshot.
+    pub fn from_position(mut board: Board, board_setup: String, side_to_move: PlayerId) -> Self {
+        let hash = crate::logic::ZobristHasher::compute_hash(&board, side_to_move);
+        board.zobrist_hash = hash;
+        board.history = vec![hash];
+        board.last_move = None;
+
+        let mut game = Game::with_setup(board.clone(), board_setup);
+        game.initial_board = board.clone();
+        game.board = board;
+        game.current_player = side_to_move;
+        game.start_snapshot = true;
+        game
+    }
+
+    /// Plays `mv` for the side to move
+    pub fn make_move(&mut self, mv: Move) {
+        self.stagnation.record(&self.board, &mv);
+        self.board = apply_move(&self.board, &mv, self.current_player);
+        self.history.push(mv);
+        self.current_player = self.current_player.opponent();
+    }
+
+    /// Records how the game ended, for the kifu
+    pub fn end(&mut self, winner: Option<PlayerId>, reason: &str) {
+        self.result = Some(KifuResult {
+            winner,
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Side that played the first move
+    fn first_player(&self) -> PlayerId {
+        if self.history.len().is_multiple_of(2) {
+            self.current_player
+        } else {
+            self.current_player.opponent()
         }
     }
 
+    /// Takes back the last `plies` moves, replaying the rest from the initial
+    /// position so the board, its repetition hashes and the stagnation count
+    /// are as they were. `false` (and nothing changes) if there are fewer.
+    /// Positions are unchanged, so an engine's transposition table stays valid.
+    pub fn undo(&mut self, plies: usize) -> bool {
+        if plies == 0 || plies > self.history.len() {
+            return false;
+        }
+        let first = self.first_player();
+        let undone = self.history.split_off(self.history.len() - plies);
+        self.redo.extend(undone.into_iter().rev());
+
+        let moves = std::mem::take(&mut self.history);
+        self.board = self.initial_board.clone();
+        self.current_player = first;
+        self.stagnation = Default::default();
+        for mv in moves {
+            self.make_move(mv);
+        }
+        true
+    }
+
+    /// Plays again the last `plies` moves taken back with [`Game::undo`].
+    /// `false` (and nothing changes) if fewer were taken back.
+    pub fn redo(&mut self, plies: usize) -> bool {
+        if plies == 0 || plies > self.redo.len() {
+            return false;
+        }
+        for _ in 0..plies {
+            let mv = self.redo.pop().expect("checked above");
+            self.make_move(mv);
+        }
+        true
+    }
+
+    /// Every move so far in notation
+    pub fn notation_list(&self) -> Vec<String> {
+        let mut board = self.initial_board.clone();
+        let mut player = self.first_player();
+        self.history
+            .iter()
+            .map(|mv| {
+                let text = mv.notation(&board, player).to_string();
+                board = apply_move(&board, mv, player);
+                player = player.opponent();
+                text
+            })
+            .collect()
+    }
+
+    /// The last move in notation, for logs (replays the game up to it)
+    pub fn last_move_notation(&self) -> Option<String> {
+        let (&last, earlier) = self.history.split_last()?;
+        let mut player = if earlier.len().is_multiple_of(2) {
+            self.current_player.opponent()
+        } else {
+            self.current_player
+        };
+        let mut board = self.initial_board.clone();
+        for mv in earlier {
+            board = apply_move(&board, mv, player);
+            player = player.opponent();
+        }
+        Some(last.notation(&board, player).to_string())
+    }
+
+    /// Whether a rule ends the game before the side to move plays (see
+    /// [`crate::logic::rules::rule_ending`])
+    pub fn rule_ending(&self) -> Option<RuleEnding> {
+        crate::logic::rules::rule_ending(
+            &self.board,
+            self.current_player,
+            self.history.len(),
+            self.stagnation.quiet_plies(),
+        )
+    }
+
     pub fn play<F>(&mut self, p1: &dyn PlayerController, p2: &dyn PlayerController, mut on_move: F)
     where
         F: FnMut(&crate::core::Move),
     {
+        use crate::i18n::{player_name, tr};
+
+        // Notation of every move, listed beside the board
+        let mut move_list = self.notation_list();
+        // The last engine search, for the other side's evaluation bar
+        let mut engine: Option<crate::player::EngineView> = None;
         loop {
             // 外部（ネットワーク等）からの盤面・手番更新があれば反映
             if let Some(ref rx) = self.board_sync_rx {
@@ -98,13 +433,17 @@ impl Game {
                 }
             }
 
+            crate::crash::record_position(&self.board, self.current_player, &self.board_setup);
+
             // 現状をまず描画 (リモートプレイヤーも待機画面が見えるように)
             let mut state = crate::ui::display::DisplayState::new();
             state.perspective = match self.perspective_mode {
                 PerspectiveMode::Fixed(p) => p,
                 PerspectiveMode::AutoFlip => self.current_player,
             };
-            state.last_move = self.board.last_move.clone();
+            state.last_move = self.board.last_move;
+            state.move_list = move_list.clone();
+            state.blindfold = p1.blindfold() || p2.blindfold();
 
             // AI vs AIの場合はカーソルを表示しない
             let current_controller = match self.current_player {
@@ -114,27 +453,23 @@ impl Game {
             state.show_cursor =
                 current_controller.is_local() && !current_controller.name().contains("AI");
 
-            state.status_msg = Some(format!(
-                "{}'s turn ({:?})",
+            state.status_msg = Some(crate::trf!(
+                "{}'s turn ({})",
+                "{}の手番 ({})",
                 if self.current_player == PlayerId::Player1 {
                     p1.name()
                 } else {
                     p2.name()
                 },
-                self.current_player
+                player_name(self.current_player)
             ));
             crate::ui::display::render_board(&self.board, &state);
 
-            // 千日手判定
-            let hash_count = self
-                .board
-                .history
-                .iter()
-                .filter(|&&h| h == self.board.zobrist_hash)
-                .count();
-            if hash_count >= 4 {
+            if let Some(ending) = self.rule_ending() {
+                self.end(ending.winner(self.current_player), ending.reason());
                 let state = crate::ui::display::DisplayState {
-                    status_msg: Some("Sennichite (Repetition) - Draw!".to_string()),
+                    status_msg: Some(ending.message(self.current_player)),
+                    move_list,
                     ..Default::default()
                 };
                 crate::ui::display::render_board(&self.board, &state);
@@ -146,14 +481,31 @@ impl Game {
             let moves = legal_moves(&self.board, self.current_player);
 
             if moves.is_empty() {
+                let winner = crate::logic::rules::no_move_winner(&self.board, self.current_player);
+                let checkmate = crate::logic::is_checkmate(&self.board, self.current_player);
+                self.end(
+                    winner,
+                    match winner {
+                        None => "Stalemate",
+                        Some(_) if checkmate => "Checkmate",
+                        Some(_) => "No moves",
+                    },
+                );
                 let state = crate::ui::display::DisplayState {
-                    status_msg: Some(
-                        if crate::logic::is_checkmate(&self.board, self.current_player) {
-                            format!("Checkmate! {:?} wins!", self.current_player.opponent())
-                        } else {
-                            format!("No more moves! {:?} wins!", self.current_player.opponent())
-                        },
-                    ),
+                    status_msg: Some(match winner {
+                        None => tr("Stalemate - Draw!", "ステイルメイト - 引き分け").to_string(),
+                        Some(winner) if checkmate => crate::trf!(
+                            "Checkmate! {} wins!",
+                            "詰み! {}の勝ち",
+                            player_name(winner)
+                        ),
+                        Some(winner) => crate::trf!(
+                            "No more moves! {} wins!",
+                            "指せる手がありません! {}の勝ち",
+                            player_name(winner)
+                        ),
+                    }),
+                    move_list,
                     ..Default::default()
                 };
                 crate::ui::display::render_board(&self.board, &state);
@@ -168,13 +520,15 @@ impl Game {
 
             if controller.name().contains("AI") {
                 let check_msg = if crate::logic::is_in_check(&self.board, self.current_player) {
-                    " (CHECK)"
+                    tr(" (CHECK)", " (王手)")
                 } else {
                     ""
                 };
-                state.status_msg = Some(format!(
-                    "AI ({:?}) is thinking{}...",
-                    self.current_player, check_msg
+                state.status_msg = Some(crate::trf!(
+                    "AI ({}) is thinking{}...",
+                    "AI ({}) が考えています{}...",
+                    player_name(self.current_player),
+                    check_msg
                 ));
                 crate::ui::display::render_board(&self.board, &state);
 
@@ -187,13 +541,15 @@ impl Game {
                         ))
                     {
                         if key.code == crossterm::event::KeyCode::Char('q') {
-                            println!("Interrupted by user.");
+                            println!("{}", tr("Interrupted by user.", "中断しました。"));
                             break;
                         }
                     }
                 }
             }
 
+            controller.show_history(&move_list);
+            controller.show_engine(engine.as_ref());
             if let Some(mv) = controller.choose_move(&self.board, &moves) {
                 // 移動適用前の最終同期チェック
                 if let Some(ref rx) = self.board_sync_rx {
@@ -212,32 +568,133 @@ impl Game {
                     continue;
                 }
 
+                // Against the current board: a sync may have replaced it meanwhile
+                if !legal_moves(&self.board, self.current_player).contains(&mv) {
+                    crate::crash::report(&format!(
+                        "{} ({:?}) chose {}, which is not a legal move",
+                        controller.name(),
+                        self.current_player,
+                        mv
+                    ));
+                    break;
+                }
+
                 // ローカルで移動を適用
                 if controller.is_local() {
                     on_move(&mv);
                 }
-                self.board = apply_move(&self.board, &mv, self.current_player);
-                self.history.push(mv.clone());
-                self.current_player = self.current_player.opponent();
+                let notation = mv.notation(&self.board, self.current_player).to_string();
+                crate::crash::log(format!(
+                    "ply {}: {:?} plays {} ({})",
+                    self.history.len() + 1,
+                    self.current_player,
+                    notation,
+                    mv
+                ));
+                move_list.push(notation);
+                // A new move instead of the ones taken back
+                self.redo.clear();
+                if let Some(search) = controller.last_search(&self.board) {
+                    engine = Some(crate::player::EngineView::new(
+                        controller.name(),
+                        &search,
+                        &self.board,
+                        self.current_player,
+                    ));
+                }
+                self.make_move(mv);
+            } else if let Some(take_back) = controller.take_back() {
+                // Back to (or on to) the requester's next turn: their move
+                // and the reply
+                let done = self.board_sync_rx.is_none()
+                    && match take_back {
+                        crate::player::TakeBack::Undo => self.undo(2),
+                        crate::player::TakeBack::Redo => self.redo(2),
+                    };
+                if done {
+                    move_list = self.notation_list();
+                    engine = None;
+                }
+            } else if controller.timed_out() {
+                self.end(Some(self.current_player.opponent()), "Time forfeit");
+                let state = crate::ui::display::DisplayState {
+                    perspective: state.perspective,
+                    status_msg: Some(crate::trf!(
+                        "Time forfeit! {} ran out of time, {} wins!",
+                        "時間切れ! {}の持ち時間がなくなり、{}の勝ち",
+                        player_name(self.current_player),
+                        player_name(self.current_player.opponent())
+                    )),
+                    move_list,
+                    ..Default::default()
+                };
+                crate::ui::display::render_board(&self.board, &state);
+                std::thread::sleep(std::time::Duration::from_secs(5));
+                break;
             } else {
+                self.end(Some(self.current_player.opponent()), "Resignation");
                 println!(
-                    "Player resigned. {:?} wins!\r",
-                    self.current_player.opponent()
+                    "{}\r",
+                    crate::trf!(
+                        "Player resigned. {} wins!",
+                        "投了しました。{}の勝ち",
+                        player_name(self.current_player.opponent())
+                    )
                 );
                 break;
             }
         }
 
+        crate::crash::clear_position();
         self.ask_save_kifu();
     }
 
+    /// The game so far as a kifu
+    pub fn kifu(&self) -> KifuData {
+        let config = crate::player::ai::config::AIConfig::get();
+        let model_lineage = config.evaluation.nn_model_path.as_ref().and_then(|path| {
+            crate::ml::model_registry::ModelRegistry::get_model_lineage(std::path::Path::new(path))
+        });
+        let snapshots = if self.start_snapshot {
+            vec![KifuSnapshot {
+                ply: 0,
+                side_to_move: self.first_player(),
+                board: self.initial_board.clone(),
+            }]
+        } else {
+            snapshots_every(&self.initial_board, &self.history, 0)
+        };
+
+        KifuData {
+            board_setup: self.board_setup.clone(),
+            chess960_seed: self.chess960_seed,
+            handicap: self.handicap,
+            thinking_data: None,
+            player1_name: self.player1_name.clone(),
+            player2_name: self.player2_name.clone(),
+            moves: self.history.clone(),
+            evaluator: config.evaluation.evaluator_type.clone(),
+            model_path: config.evaluation.nn_model_path.clone(),
+            model_version: model_lineage.as_ref().and_then(|l| l.version.clone()),
+            model_lineage,
+            eval_trajectory: None,
+            snapshots,
+            result: self.result.clone(),
+            variations: Vec::new(),
+        }
+    }
+
     fn ask_save_kifu(&self) {
+        use crate::i18n::tr;
         use std::io::Write;
 
         // 端末設定を一度戻す（入力のため）
         let _ = crossterm::terminal::disable_raw_mode();
 
-        print!("\r\nSave game record? (y/N) > ");
+        print!(
+            "\r\n{}",
+            tr("Save game record? (y/N) > ", "棋譜を保存しますか? (y/N) > ")
+        );
         let _ = std::io::stdout().flush();
 
         let mut input = String::new();
@@ -247,13 +704,27 @@ impl Game {
             // Create kifu directory if it doesn't exist
             let kifu_dir = "kifu";
             if let Err(e) = std::fs::create_dir_all(kifu_dir) {
-                println!("Failed to create kifu directory: {}", e);
+                println!(
+                    "{}: {}",
+                    tr(
+                        "Failed to create kifu directory",
+                        "棋譜のディレクトリを作れませんでした"
+                    ),
+                    e
+                );
                 std::thread::sleep(std::time::Duration::from_secs(2));
                 return;
             }
 
             let default_name = "game.json";
-            print!("Filename (default: {}) > ", default_name);
+            print!(
+                "{}",
+                crate::trf!(
+                    "Filename (default: {}) > ",
+                    "ファイル名 (既定: {}) > ",
+                    default_name
+                )
+            );
             let _ = std::io::stdout().flush();
 
             let mut filename_input = String::new();
@@ -272,35 +743,22 @@ impl Game {
             // Save to kifu directory
             let filepath = std::path::Path::new(kifu_dir).join(&filename);
 
-            match std::fs::File::create(&filepath) {
-                Ok(file) => {
-                    let config = crate::player::ai::config::AIConfig::get();
-                    let model_version = if let Some(ref path) = config.evaluation.nn_model_path {
-                        crate::ml::model_registry::ModelRegistry::get_model_version(
-                            std::path::Path::new(path),
-                        )
-                    } else {
-                        None
-                    };
-
-                    let kifu_data = KifuData {
-                        board_setup: self.board_setup.clone(),
-                        thinking_data: None,
-                        player1_name: self.player1_name.clone(),
-                        player2_name: self.player2_name.clone(),
-                        moves: self.history.clone(),
-                        evaluator: config.evaluation.evaluator_type.clone(),
-                        model_path: config.evaluation.nn_model_path.clone(),
-                        model_version,
-                    };
-                    // Minified JSON (not pretty) to keep it lightweight
-                    if let Err(e) = serde_json::to_writer(file, &kifu_data) {
-                        println!("Failed to write kifu: {}", e);
-                    } else {
-                        println!("Kifu saved to {}", filepath.display());
-                    }
-                }
-                Err(e) => println!("Failed to create file: {}", e),
+            // Minified JSON (not pretty) to keep it lightweight
+            if let Err(e) = crate::util::atomic_write_json(&filepath, &self.kifu(), false) {
+                println!(
+                    "{}: {}",
+                    tr("Failed to write kifu", "棋譜を書き込めませんでした"),
+                    e
+                );
+            } else {
+                println!(
+                    "{}",
+                    crate::trf!(
+                        "Kifu saved to {}",
+                        "棋譜を {} に保存しました",
+                        filepath.display()
+                    )
+                );
             }
             // Wait user to see message
             std::thread::sleep(std::time::Duration::from_secs(2));
@@ -310,3 +768,101 @@ impl Game {
         let _ = crossterm::terminal::enable_raw_mode();
     }
 }
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+    use crate::core::setup::BoardSetupType;
+    use crate::core::Position;
+
+    #[test]
+    fn test_move_limits() {
+        // A knight move: no capture or pawn move
+        let quiet = Move::Normal {
+            from: Position::new(1, 7),
+            to: Position::new(2, 5),
+            promote: None,
+        };
+        let limits = crate::core::Ruleset {
+            max_plies: 40,
+            chess_quiet_plies: 20,
+            ..Default::default()
+        };
+        let game = |setup: BoardSetupType| {
+            let mut board = setup.create_board();
+            board.set_ruleset(limits.clone());
+            Game::new(board)
+        };
+
+        let mut chess = game(BoardSetupType::ChessOnly);
+        let mut shogi = game(BoardSetupType::ShogiOnly);
+        assert_eq!(chess.rule_ending(), None);
+        for _ in 0..limits.chess_quiet_plies {
+            chess.stagnation.record(&chess.board, &quiet);
+            shogi.stagnation.record(&chess.board, &quiet);
+        }
+        assert_eq!(
+            chess.rule_ending(),
+            Some(RuleEnding::QuietPlies(limits.chess_quiet_plies))
+        );
+        // The quiet-move rule is chess only
+        assert_eq!(shogi.rule_ending(), None);
+
+        shogi.history = vec![quiet; limits.max_plies];
+        let ending = shogi.rule_ending();
+        assert_eq!(ending, Some(RuleEnding::MoveLimit(limits.max_plies)));
+        assert_eq!(ending.unwrap().winner(PlayerId::Player1), None);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::new(BoardSetupType::ShogiOnly.create_board());
+        let start = game.board.zobrist_hash;
+        let mut hashes = Vec::new();
+        for _ in 0..4 {
+            let mv = legal_moves(&game.board, game.current_player)[0];
+            game.make_move(mv);
+            hashes.push(game.board.zobrist_hash);
+        }
+        let played = game.history.clone();
+
+        assert!(!game.undo(5));
+        assert!(game.undo(2));
+        assert_eq!(game.history, played[..2]);
+        assert_eq!(game.board.zobrist_hash, hashes[1]);
+        assert_eq!(game.current_player, PlayerId::Player1);
+        assert!(game.undo(2));
+        assert_eq!(game.board.zobrist_hash, start);
+        assert_eq!(game.board.history, vec![start]);
+
+        assert!(game.redo(2));
+        assert_eq!(game.board.zobrist_hash, hashes[1]);
+        assert!(game.redo(2));
+        assert_eq!(game.history, played);
+        assert_eq!(game.notation_list().len(), 4);
+        assert!(!game.redo(1));
+    }
+
+    #[test]
+    fn test_from_position_records_start() {
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        board.remove_piece(Position::new(0, 0));
+        let mut game = Game::from_position(board.clone(), "ShogiOnly".into(), PlayerId::Player2);
+        let mv = legal_moves(&game.board, PlayerId::Player2)[0];
+        game.make_move(mv);
+        assert!(game.undo(1));
+        assert_eq!(game.current_player, PlayerId::Player2);
+        game.make_move(mv);
+
+        let kifu = game.kifu();
+        assert_eq!(kifu.snapshots.len(), 1);
+        assert_eq!(kifu.snapshots[0].side_to_move, PlayerId::Player2);
+        let (start, first) = crate::game::replay::ReplayViewer::initial_position(&kifu);
+        assert_eq!(first, PlayerId::Player2);
+        assert_eq!(start.pieces.len(), board.pieces.len());
+        assert!(start.get_piece(Position::new(0, 0)).is_none());
+        let (boards, unavailable) = crate::game::replay::ReplayViewer::replay_boards(&kifu);
+        assert_eq!(boards.len(), 2);
+        assert!(!unavailable[1]);
+    }
+}
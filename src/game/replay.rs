@@ -1,77 +1,233 @@
-use crate::core::{Board, Move, PlayerId};
-use crate::logic::apply_move;
-use crate::ui::display::{render_board, DisplayState};
-use crossterm::event::{self, Event, KeyCode};
+use crate::core::{Board, Move, PlayerId, Position};
+use crate::i18n::{player_name, tr};
+use crate::logic::{apply_move, legal_moves};
+use crate::ui::display::{click_target, hand_items, render_board, ClickTarget, DisplayState};
+use crossterm::event::{self, Event, KeyCode, MouseButton, MouseEventKind};
 use crossterm::{execute, terminal};
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Auto-play speeds, from fastest to slowest
+const AUTOPLAY_DELAYS: [Duration; 6] = [
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(1500),
+    Duration::from_secs(2),
+    Duration::from_secs(3),
+    Duration::from_secs(5),
+];
+
+/// Steps through the moves on its own (space to play or pause)
+struct AutoPlay {
+    playing: bool,
+    /// Index into [`AUTOPLAY_DELAYS`]
+    speed: usize,
+    /// When the last move was shown, by either auto-play or the arrows
+    last_step: Instant,
+}
+
+impl AutoPlay {
+    fn new() -> Self {
+        Self {
+            playing: false,
+            speed: 1,
+            last_step: Instant::now(),
+        }
+    }
+
+    fn delay(&self) -> Duration {
+        AUTOPLAY_DELAYS[self.speed]
+    }
+
+    fn faster(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    fn slower(&mut self) {
+        self.speed = (self.speed + 1).min(AUTOPLAY_DELAYS.len() - 1);
+    }
+
+    /// A move was shown at `now`: the next automatic one waits a full delay
+    fn stepped(&mut self, now: Instant) {
+        self.last_step = now;
+    }
+
+    /// Whether the next move is due
+    fn due(&self, now: Instant) -> bool {
+        self.playing && now.duration_since(self.last_step) >= self.delay()
+    }
+}
 
 pub struct ReplayViewer {
     kifu: crate::game::KifuData,
     history: Vec<Move>,
     boards: Vec<Board>,
+    /// Plies whose position could not be replayed (see [`ReplayViewer::replay_boards`])
+    unavailable: Vec<bool>,
     current_index: usize,
+    /// Shade squares by the handcrafted evaluation (toggled with `h`)
+    show_heatmap: bool,
+    /// Clicked piece on the board or in hand, whose moves are highlighted
+    inspected: Option<ClickTarget>,
+    autoplay: AutoPlay,
+    /// Large evaluation swings, by the move they followed (see [`crate::game::critical_swings`])
+    critical: Vec<crate::game::CriticalMoment>,
+    /// Move number being typed after `g` or a digit
+    typed: Option<String>,
+    /// File the kifu was read from, where saved variations are written
+    path: Option<std::path::PathBuf>,
+    /// Result of the last variation save
+    message: Option<String>,
 }
 
 impl ReplayViewer {
     pub fn new(kifu_data: crate::game::KifuData) -> Self {
-        // Reconstruct initial board from board_setup string
-        let board = Self::board_from_setup(&kifu_data.board_setup);
-
         // Pre-calculate all board states
-        let mut boards = Vec::new();
-        let mut current_board = board;
-        let mut current_player = PlayerId::Player1;
-
-        boards.push(current_board.clone());
-
-        for mv in &kifu_data.moves {
-            current_board = apply_move(&current_board, mv, current_player);
-            boards.push(current_board.clone());
-            current_player = current_player.opponent();
-        }
+        let (boards, unavailable) = Self::replay_boards(&kifu_data);
+        let critical = Self::critical_moments(&kifu_data);
 
         Self {
             kifu: kifu_data.clone(),
             history: kifu_data.moves,
             boards,
+            unavailable,
             current_index: 0,
+            show_heatmap: false,
+            inspected: None,
+            autoplay: AutoPlay::new(),
+            critical,
+            typed: None,
+            path: None,
+            message: None,
         }
     }
 
-    fn board_from_setup(setup: &str) -> Board {
-        use crate::core::setup;
-
-        match setup {
-            "StandardMixed" => {
-                let map = setup::get_standard_mixed_setup();
-                setup::setup_from_strings(&map, true, false, None, None)
+    /// From the searches in `thinking_data`, else the ones the downsampled
+    /// evaluation trajectory kept. Sorted by move number.
+    fn critical_moments(kifu: &crate::game::KifuData) -> Vec<crate::game::CriticalMoment> {
+        match &kifu.thinking_data {
+            Some(thinking) if !thinking.is_empty() => {
+                let scores: Vec<i32> = thinking.iter().map(|t| t.score).collect();
+                crate::game::critical_swings(&scores)
+                    .into_iter()
+                    .map(|i| crate::game::CriticalMoment {
+                        move_number: thinking[i].move_number,
+                        score_before: scores[i - 1],
+                        score_after: scores[i],
+                    })
+                    .collect()
             }
-            "ReversedMixed" => {
-                let map = setup::get_reversed_mixed_setup();
-                setup::setup_from_strings(&map, false, true, None, None)
-            }
-            "ShogiOnly" => {
-                let map = setup::get_shogi_setup();
-                setup::setup_from_strings(&map, true, true, None, None)
-            }
-            "ChessOnly" => {
-                let map = setup::get_chess_setup();
-                setup::setup_from_strings(&map, false, false, None, None)
-            }
-            "Fair" => {
-                let map = setup::get_fair_setup();
-                setup::setup_from_strings(&map, true, true, None, None)
-            }
-            "ReversedFair" => {
-                let map = setup::get_reversed_fair_setup();
-                setup::setup_from_strings(&map, false, false, None, None)
+            _ => kifu
+                .eval_trajectory
+                .as_ref()
+                .map(|t| t.critical_moments.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Shows the position after `ply` moves (clamped to the game)
+    fn jump(&mut self, ply: usize) {
+        self.current_index = ply.min(self.history.len());
+        self.inspected = None;
+        self.autoplay.stepped(Instant::now());
+    }
+
+    /// Position right after the next critical move, if any
+    fn next_critical(&self) -> Option<usize> {
+        self.critical
+            .iter()
+            .map(|c| c.move_number)
+            .find(|&ply| ply > self.current_index)
+    }
+
+    /// Position right after the previous critical move, if any
+    fn previous_critical(&self) -> Option<usize> {
+        self.critical
+            .iter()
+            .map(|c| c.move_number)
+            .rfind(|&ply| ply < self.current_index)
+    }
+
+    /// Board after every ply, starting from the setup (or the ply 0 snapshot).
+    /// Snapshots are authoritative and the replay resyncs to each one. After a
+    /// move that is not legal, the following plies are unavailable (the last
+    /// good board is repeated) until the next snapshot.
+    pub fn replay_boards(kifu: &crate::game::KifuData) -> (Vec<Board>, Vec<bool>) {
+        let snapshot_at = |ply: usize| kifu.snapshots.iter().find(|s| s.ply == ply);
+        let (mut board, mut player) = Self::initial_position(kifu);
+
+        let mut boards = vec![board.clone()];
+        let mut unavailable = vec![false];
+        let mut broken = false;
+        for (i, mv) in kifu.moves.iter().enumerate() {
+            if !broken && legal_moves(&board, player).contains(mv) {
+                board = apply_move(&board, mv, player);
+            } else {
+                broken = true;
             }
-            _ => {
-                // Default to Fair if unknown
-                let map = setup::get_fair_setup();
-                setup::setup_from_strings(&map, true, true, None, None)
+            player = player.opponent();
+            if let Some(snapshot) = snapshot_at(i + 1) {
+                board = snapshot.restore();
+                player = snapshot.side_to_move;
+                broken = false;
             }
+            boards.push(board.clone());
+            unavailable.push(broken);
+        }
+        (boards, unavailable)
+    }
+
+    /// Position before the first move: the ply 0 snapshot (the only way a
+    /// kifu records starting hands), else the named setup
+    pub fn initial_position(kifu: &crate::game::KifuData) -> (Board, PlayerId) {
+        match kifu.snapshots.iter().find(|s| s.ply == 0) {
+            Some(snapshot) => (snapshot.restore(), snapshot.side_to_move),
+            None => (
+                Self::board_from_setup(&kifu.board_setup, kifu.chess960_seed, kifu.handicap),
+                PlayerId::Player1,
+            ),
+        }
+    }
+
+    /// Board of a named setup, with the kifu's Chess960 shuffle and handicap
+    /// if any
+    pub fn board_from_setup(
+        setup: &str,
+        chess960_seed: Option<u64>,
+        handicap: Option<crate::core::handicap::Handicap>,
+    ) -> Board {
+        use crate::core::setup;
+
+        let (map, p1_shogi, p2_shogi) = match setup {
+            "StandardMixed" => (setup::get_standard_mixed_setup(), true, false),
+            "ReversedMixed" => (setup::get_reversed_mixed_setup(), false, true),
+            "ShogiOnly" => (setup::get_shogi_setup(), true, true),
+            "ChessOnly" => (setup::get_chess_setup(), false, false),
+            "Fair" => (setup::get_fair_setup(), true, true),
+            "ReversedFair" => (setup::get_reversed_fair_setup(), false, false),
+            // Default to Fair if unknown
+            _ => (setup::get_fair_setup(), true, true),
+        };
+        let mut board = setup::setup_from_strings_chess960(&map, p1_shogi, p2_shogi, chess960_seed);
+        if let Some(handicap) = handicap {
+            // A handicap the setup cannot take leaves the board as it is
+            handicap.apply(&mut board).ok();
+        }
+        board
+    }
+
+    /// Side that played move `ply` (0-based)
+    fn mover(&self, ply: usize) -> PlayerId {
+        let first = self
+            .kifu
+            .snapshots
+            .iter()
+            .find(|s| s.ply == 0)
+            .map_or(PlayerId::Player1, |s| s.side_to_move);
+        if ply.is_multiple_of(2) {
+            first
+        } else {
+            first.opponent()
         }
     }
 
@@ -82,15 +238,175 @@ impl ReplayViewer {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         let kifu_data: crate::game::KifuData = serde_json::from_reader(reader)?;
-        Ok(Self::new(kifu_data))
+        let mut viewer = Self::new(kifu_data);
+        viewer.path = Some(path.to_path_buf());
+        Ok(viewer)
+    }
+
+    /// Squares `target` can move or be dropped to in `board`, for its owner
+    fn inspect(board: &Board, target: ClickTarget) -> Option<(Option<Position>, Vec<Position>)> {
+        let (selected, player, kind) = match target {
+            ClickTarget::Square(pos) => {
+                let piece = board.get_piece(pos)?;
+                (Some(pos), piece.owner, None)
+            }
+            ClickTarget::Hand(player, index) => {
+                let (kind, _) = *hand_items(board, player)?.get(index)?;
+                (None, player, Some(kind))
+            }
+        };
+        let targets = legal_moves(board, player)
+            .into_iter()
+            .filter_map(|mv| match (mv, kind) {
+                (Move::Normal { from, to, .. }, None) if Some(from) == selected => Some(to),
+                (Move::Drop { kind: k, to }, Some(kind)) if k == kind => Some(to),
+                _ => None,
+            })
+            .collect();
+        Some((selected, targets))
+    }
+
+    /// `moves` in notation, played from `board` by `first` and then alternately
+    fn line_notation(board: &Board, first: PlayerId, moves: &[Move]) -> Vec<String> {
+        let mut board = board.clone();
+        let mut player = first;
+        moves
+            .iter()
+            .map(|mv| {
+                let text = mv.notation(&board, player).to_string();
+                board = apply_move(&board, mv, player);
+                player = player.opponent();
+                text
+            })
+            .collect()
+    }
+
+    /// Keeps `moves` from the position after `ply` moves as a variation of the
+    /// kifu, and rewrites the kifu file if it came from one. `false` if the
+    /// same variation was already saved.
+    fn save_variation(&mut self, ply: usize, moves: Vec<Move>) -> anyhow::Result<bool> {
+        let variation = crate::game::KifuVariation { ply, moves };
+        if self.kifu.variations.contains(&variation) {
+            return Ok(false);
+        }
+        self.kifu.variations.push(variation);
+        if let Some(path) = &self.path {
+            crate::util::atomic_write_json(path, &self.kifu, false)?;
+        }
+        Ok(true)
+    }
+
+    /// Leaves the recorded line at the shown position: moves are played with
+    /// the cursor or by the AI, and taken back, until the main line is resumed
+    fn explore(&mut self) -> anyhow::Result<()> {
+        use crate::player::ai::alpha_beta::{AIStrength, AlphaBetaAI};
+        use crate::player::PlayerController;
+
+        let ply = self.current_index;
+        let first = self.mover(ply);
+        let mut boards = vec![self.boards[ply].clone()];
+        let mut moves: Vec<Move> = Vec::new();
+        let mut message: Option<String> = None;
+        loop {
+            let board = boards
+                .last()
+                .expect("starts with the branch position")
+                .clone();
+            let player = if moves.len().is_multiple_of(2) {
+                first
+            } else {
+                first.opponent()
+            };
+            let legal = legal_moves(&board, player);
+            let notation = Self::line_notation(&boards[0], first, &moves);
+
+            let turn = if legal.is_empty() {
+                tr("No legal moves", "指せる手がありません").to_string()
+            } else {
+                crate::trf!("{} to move", "{}の手番", player_name(player))
+            };
+            let mut status = crate::trf!(
+                "Variation from move {} ({} moves) | {}",
+                "{} 手目からの変化 ({} 手) | {}",
+                ply,
+                moves.len(),
+                turn
+            );
+            if let Some(message) = &message {
+                status = format!("{} | {}", status, message);
+            }
+            let state = DisplayState {
+                perspective: PlayerId::Player1,
+                last_move: moves.last().copied(),
+                status_msg: Some(status),
+                move_list: notation.clone(),
+                ..Default::default()
+            };
+            execute!(
+                io::stdout(),
+                terminal::Clear(terminal::ClearType::All),
+                crossterm::cursor::MoveTo(0, 0)
+            )?;
+            render_board(&board, &state);
+            print!(
+                "{}\r\n",
+                tr(
+                    "[m/Enter] Play a move | [a] AI move | [←/Backspace] Take back | [s] Save | [Esc/q] Back to the game",
+                    "[m/Enter] 指す | [a] AI が指す | [←/Backspace] 1 手戻す | [s] 保存 | [Esc/q] 本譜に戻る"
+                )
+            );
+
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            message = None;
+            let chosen = match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => break,
+                KeyCode::Enter | KeyCode::Char('m') if !legal.is_empty() => {
+                    // The same move input as in a game; resigning there just cancels
+                    let mover = crate::player::tui::TuiController::new(player, player_name(player));
+                    mover.show_history(&notation);
+                    mover.choose_move(&board, &legal)
+                }
+                KeyCode::Char('a') if !legal.is_empty() => {
+                    let engine = AlphaBetaAI::new(player, "AI", AIStrength::Light, None, true);
+                    engine.choose_move(&board, &legal)
+                }
+                KeyCode::Left | KeyCode::Backspace => {
+                    if moves.pop().is_some() {
+                        boards.pop();
+                    }
+                    None
+                }
+                KeyCode::Char('s') if !moves.is_empty() => {
+                    message = Some(match self.save_variation(ply, moves.clone()) {
+                        Ok(true) => tr("Variation saved", "変化を保存しました").to_string(),
+                        Ok(false) => tr("Already saved", "保存済みです").to_string(),
+                        Err(e) => crate::trf!("Save failed: {}", "保存できません: {}", e),
+                    });
+                    None
+                }
+                _ => None,
+            };
+            if let Some(mv) = chosen {
+                message = None;
+                boards.push(apply_move(&board, &mv, player));
+                moves.push(mv);
+            }
+        }
+        Ok(())
     }
 
     pub fn run(&mut self) -> anyhow::Result<()> {
+        let _mouse = crate::ui::MouseCapture::enable();
         loop {
             // Get current board state
             let board = &self.boards[self.current_index];
             let last_move = if self.current_index > 0 {
-                Some(self.history[self.current_index - 1].clone())
+                Some(self.history[self.current_index - 1])
             } else {
                 None
             };
@@ -103,14 +419,29 @@ impl ReplayViewer {
             )?;
 
             // Render board
-            let state = DisplayState {
+            let mut state = DisplayState {
                 perspective: PlayerId::Player1,
                 last_move,
-                status_msg: None,
+                status_msg: self.unavailable[self.current_index].then(|| {
+                    "Move list does not replay here; showing the last valid position".to_string()
+                }),
+                heatmap: self
+                    .show_heatmap
+                    .then(|| crate::player::ai::eval::eval_breakdown(board).positional_map()),
                 ..Default::default()
             };
+            if let Some((selected, highlights)) = self
+                .inspected
+                .and_then(|target| Self::inspect(board, target))
+            {
+                state.selected = selected;
+                state.highlights = highlights;
+            }
 
             render_board(board, &state);
+            if self.show_heatmap {
+                print!("{}\r\n", crate::ui::display::heatmap_legend());
+            }
 
             // Display game info AFTER board
             print!("\r\n");
@@ -131,44 +462,409 @@ impl ReplayViewer {
             if let Some(ref version) = self.kifu.model_version {
                 print!("Version: {}\r\n", version);
             }
+            if let Some(ref lineage) = self.kifu.model_lineage {
+                if let Some(generation) = lineage.generation {
+                    print!("Generation: {}\r\n", generation);
+                }
+                if let Some(ref parent) = lineage.parent_model {
+                    print!("Parent: {}\r\n", parent);
+                }
+            }
 
             // Display winner
             let total_moves = self.kifu.moves.len();
             if total_moves > 0 {
                 let (winner_name, winner_id) = if total_moves % 2 == 1 {
-                    (&self.kifu.player1_name, "Player1")
+                    (&self.kifu.player1_name, PlayerId::Player1)
                 } else {
-                    (&self.kifu.player2_name, "Player2")
+                    (&self.kifu.player2_name, PlayerId::Player2)
                 };
-                print!("Winner: {} ({})\r\n", winner_name, winner_id);
+                print!(
+                    "{}: {} ({})\r\n",
+                    tr("Winner", "勝者"),
+                    winner_name,
+                    player_name(winner_id)
+                );
+            }
+
+            if let Some(mv) = last_move.filter(|_| !self.unavailable[self.current_index]) {
+                let before = &self.boards[self.current_index - 1];
+                print!(
+                    "{}: {}\r\n",
+                    tr("Last move", "直前の手"),
+                    mv.notation(before, self.mover(self.current_index - 1))
+                );
+            }
+
+            if let Some(moment) = self
+                .critical
+                .iter()
+                .find(|c| c.move_number == self.current_index)
+            {
+                print!(
+                    "{}\r\n",
+                    crate::trf!(
+                        "Critical moment: evaluation {:+} -> {:+}",
+                        "分岐点: 評価値 {:+} -> {:+}",
+                        moment.score_before,
+                        moment.score_after
+                    )
+                );
             }
 
             println!(
-                "\rMove {}/{} | [←/→] Navigate | [q] Quit",
-                self.current_index + 1,
-                total_moves
+                "\r{}",
+                crate::trf!(
+                    "Move {}/{} | [←/→/Wheel] Navigate | [Click] Show moves | [h] Heatmap | [q] Quit",
+                    "{}/{} 手目 | [←/→/ホイール] 移動 | [クリック] 駒の動き | [h] ヒートマップ | [q] 終了",
+                    self.current_index,
+                    total_moves
+                )
+            );
+            for variation in self
+                .kifu
+                .variations
+                .iter()
+                .filter(|v| v.ply == self.current_index && !self.unavailable[v.ply])
+            {
+                let line = Self::line_notation(
+                    &self.boards[variation.ply],
+                    self.mover(variation.ply),
+                    &variation.moves,
+                );
+                print!("{}: {}\r\n", tr("Variation", "変化"), line.join(" "));
+            }
+            if let Some(message) = self.message.take() {
+                print!("{}\r\n", message);
+            }
+
+            if let Some(text) = &self.typed {
+                println!(
+                    "\r{}",
+                    crate::trf!(
+                        "Go to move: {}_ | [Enter] Jump | [Esc] Cancel",
+                        "移動先の手数: {}_ | [Enter] 移動 | [Esc] やめる",
+                        text
+                    )
+                );
+            } else {
+                println!(
+                    "\r{}",
+                    crate::trf!(
+                        "[g/0-9] Go to move | [Home/End] Start/End | [[/]] Critical moments ({}) | [v] Explore a variation",
+                        "[g/0-9] 手数を指定 | [Home/End] 初手/最終手 | [[/]] 分岐点 ({} 箇所) | [v] 変化を調べる",
+                        self.critical.len()
+                    )
+                );
+            }
+            let delay = self.autoplay.delay().as_secs_f32();
+            println!(
+                "\r{}",
+                if self.autoplay.playing {
+                    crate::trf!(
+                        "Auto-play {:.1}s/move | [Space] Pause | [+/-] Speed",
+                        "自動再生 1 手 {:.1} 秒 | [Space] 一時停止 | [+/-] 速さ",
+                        delay
+                    )
+                } else {
+                    crate::trf!(
+                        "[Space] Auto-play ({:.1}s/move) | [+/-] Speed",
+                        "[Space] 自動再生 (1 手 {:.1} 秒) | [+/-] 速さ",
+                        delay
+                    )
+                }
             );
 
             // Input handling
             if event::poll(Duration::from_millis(100))? {
-                if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => break,
-                        KeyCode::Right | KeyCode::Char('n') => {
-                            if self.current_index < self.history.len() {
-                                self.current_index += 1;
-                            }
+                let code = match event::read()? {
+                    Event::Key(key) => key.code,
+                    Event::Mouse(mouse) => match mouse.kind {
+                        MouseEventKind::ScrollDown => KeyCode::Right,
+                        MouseEventKind::ScrollUp => KeyCode::Left,
+                        MouseEventKind::Down(MouseButton::Left) => {
+                            let target = click_target(board, &state, mouse.column, mouse.row);
+                            // A second click on the same piece puts it back
+                            self.inspected = target.filter(|&t| {
+                                self.inspected != Some(t) && Self::inspect(board, t).is_some()
+                            });
+                            continue;
                         }
-                        KeyCode::Left | KeyCode::Char('p') => {
-                            if self.current_index > 0 {
-                                self.current_index -= 1;
+                        _ => continue,
+                    },
+                    // Redrawn at once, in the layout that fits the new size
+                    Event::Resize(..) => continue,
+                    _ => continue,
+                };
+                if let Some(text) = self.typed.as_mut() {
+                    // 手数の入力
+                    match code {
+                        KeyCode::Char(c) if c.is_ascii_digit() => text.push(c),
+                        KeyCode::Backspace => {
+                            text.pop();
+                        }
+                        KeyCode::Esc => self.typed = None,
+                        KeyCode::Enter => {
+                            if let Ok(ply) = self.typed.take().unwrap_or_default().parse() {
+                                self.jump(ply);
                             }
                         }
                         _ => {}
                     }
+                    continue;
+                }
+                match code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('g') => self.typed = Some(String::new()),
+                    KeyCode::Char('v') if self.unavailable[self.current_index] => {
+                        self.message = Some(
+                            tr(
+                                "This position could not be replayed",
+                                "この局面は再生できませんでした",
+                            )
+                            .to_string(),
+                        );
+                    }
+                    KeyCode::Char('v') => {
+                        self.autoplay.playing = false;
+                        self.inspected = None;
+                        self.explore()?;
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() => self.typed = Some(c.to_string()),
+                    KeyCode::Home => self.jump(0),
+                    KeyCode::End => self.jump(self.history.len()),
+                    KeyCode::Char(']') => {
+                        if let Some(ply) = self.next_critical() {
+                            self.jump(ply);
+                        }
+                    }
+                    KeyCode::Char('[') => {
+                        if let Some(ply) = self.previous_critical() {
+                            self.jump(ply);
+                        }
+                    }
+                    KeyCode::Char('h') => self.show_heatmap = !self.show_heatmap,
+                    KeyCode::Right | KeyCode::Char('n')
+                        if self.current_index < self.history.len() =>
+                    {
+                        self.current_index += 1;
+                        self.inspected = None;
+                        self.autoplay.stepped(Instant::now());
+                    }
+                    KeyCode::Left | KeyCode::Char('p') if self.current_index > 0 => {
+                        self.current_index -= 1;
+                        self.inspected = None;
+                        self.autoplay.stepped(Instant::now());
+                    }
+                    KeyCode::Char(' ') => {
+                        self.autoplay.playing = !self.autoplay.playing;
+                        // Played again from the start once the end is reached
+                        if self.autoplay.playing && self.current_index == self.history.len() {
+                            self.current_index = 0;
+                            self.inspected = None;
+                        }
+                        self.autoplay.stepped(Instant::now());
+                    }
+                    KeyCode::Char('+') | KeyCode::Up => self.autoplay.faster(),
+                    KeyCode::Char('-') | KeyCode::Down => self.autoplay.slower(),
+                    _ => {}
+                }
+            }
+
+            let now = Instant::now();
+            if self.autoplay.due(now) {
+                if self.current_index < self.history.len() {
+                    self.current_index += 1;
+                    self.inspected = None;
+                    self.autoplay.stepped(now);
+                }
+                if self.current_index == self.history.len() {
+                    self.autoplay.playing = false;
                 }
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{snapshots_every, KifuData};
+
+    #[test]
+    fn test_jump_to_critical_moments() {
+        let mut board = ReplayViewer::board_from_setup("ShogiOnly", None, None);
+        let mut player = PlayerId::Player1;
+        let mut moves = Vec::new();
+        for _ in 0..8 {
+            let mv = legal_moves(&board, player)[0];
+            board = apply_move(&board, &mv, player);
+            player = player.opponent();
+            moves.push(mv);
+        }
+        let scores = [10, 20, 2500, 2400, 2300, -100, -50, -60];
+        let thinking = scores
+            .iter()
+            .enumerate()
+            .map(|(i, &score)| crate::game::ThinkingInfo {
+                move_number: i + 1,
+                player: String::new(),
+                depth: 4,
+                score,
+                nodes: 0,
+                time_ms: 0,
+                max_depth: 4,
+                depth_profile: None,
+                health: None,
+            })
+            .collect();
+        let mut viewer = ReplayViewer::new(KifuData {
+            board_setup: "ShogiOnly".to_string(),
+            chess960_seed: None,
+            handicap: None,
+            player1_name: String::new(),
+            player2_name: String::new(),
+            moves,
+            thinking_data: Some(thinking),
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots: Vec::new(),
+            result: None,
+            variations: Vec::new(),
+        });
+
+        // Swings after moves 3 (+2480) and 6 (-2400)
+        let plies: Vec<usize> = viewer.critical.iter().map(|c| c.move_number).collect();
+        assert_eq!(plies, vec![3, 6]);
+        assert_eq!(viewer.next_critical(), Some(3));
+        viewer.jump(3);
+        assert_eq!(viewer.next_critical(), Some(6));
+        assert_eq!(viewer.previous_critical(), None);
+        viewer.jump(100);
+        assert_eq!(viewer.current_index, 8);
+        assert_eq!(viewer.previous_critical(), Some(6));
+    }
+
+    #[test]
+    fn test_saved_variation_is_written_back() {
+        let kifu = KifuData {
+            board_setup: "ShogiOnly".to_string(),
+            chess960_seed: None,
+            handicap: None,
+            player1_name: String::new(),
+            player2_name: String::new(),
+            moves: Vec::new(),
+            thinking_data: None,
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots: Vec::new(),
+            result: None,
+            variations: Vec::new(),
+        };
+        let path = std::env::temp_dir().join(format!("aho_variation_{}.json", std::process::id()));
+        crate::util::atomic_write_json(&path, &kifu, false).unwrap();
+        let mut viewer = ReplayViewer::from_kifu_path(&path).unwrap();
+
+        let board = viewer.boards[0].clone();
+        let first = legal_moves(&board, PlayerId::Player1)[0];
+        let after = apply_move(&board, &first, PlayerId::Player1);
+        let reply = legal_moves(&after, PlayerId::Player2)[0];
+        let line = vec![first, reply];
+        assert_eq!(
+            ReplayViewer::line_notation(&board, PlayerId::Player1, &line).len(),
+            2
+        );
+
+        assert!(viewer.save_variation(0, line.clone()).unwrap());
+        assert!(!viewer.save_variation(0, line.clone()).unwrap());
+        let saved = ReplayViewer::from_kifu_path(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            saved.kifu.variations,
+            vec![crate::game::KifuVariation {
+                ply: 0,
+                moves: line
+            }]
+        );
+    }
+
+    #[test]
+    fn test_autoplay_timing() {
+        let mut autoplay = AutoPlay::new();
+        let start = autoplay.last_step;
+        assert!(!autoplay.due(start + Duration::from_secs(10)));
+
+        autoplay.playing = true;
+        assert!(!autoplay.due(start + Duration::from_millis(900)));
+        assert!(autoplay.due(start + Duration::from_secs(1)));
+
+        // A manual step restarts the wait
+        autoplay.stepped(start + Duration::from_millis(800));
+        assert!(!autoplay.due(start + Duration::from_millis(1500)));
+
+        for _ in 0..10 {
+            autoplay.faster();
+        }
+        assert_eq!(autoplay.delay(), Duration::from_millis(500));
+        for _ in 0..10 {
+            autoplay.slower();
+        }
+        assert_eq!(autoplay.delay(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_replay_resyncs_from_snapshot() {
+        let initial = ReplayViewer::board_from_setup("ShogiOnly", None, None);
+        let mut moves = Vec::new();
+        let mut board = initial.clone();
+        let mut player = PlayerId::Player1;
+        for _ in 0..6 {
+            let mv = legal_moves(&board, player)[0];
+            board = apply_move(&board, &mv, player);
+            player = player.opponent();
+            moves.push(mv);
+        }
+        let snapshots = snapshots_every(&initial, &moves, 4);
+        assert_eq!(
+            snapshots.iter().map(|s| s.ply).collect::<Vec<_>>(),
+            vec![0, 4]
+        );
+
+        // Corrupt ply 2: plies 2-3 cannot be replayed, ply 4 onwards resyncs
+        moves[1] = moves[0];
+        let kifu = KifuData {
+            board_setup: "ShogiOnly".to_string(),
+            chess960_seed: None,
+            handicap: None,
+            player1_name: String::new(),
+            player2_name: String::new(),
+            moves: moves.clone(),
+            thinking_data: None,
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots,
+            result: None,
+            variations: Vec::new(),
+        };
+        let (boards, unavailable) = ReplayViewer::replay_boards(&kifu);
+        assert_eq!(
+            unavailable,
+            vec![false, false, true, true, false, false, false]
+        );
+        let hash = |b: &Board, p| crate::logic::ZobristHasher::compute_hash(b, p);
+        assert_eq!(
+            hash(&boards[6], PlayerId::Player1),
+            hash(&board, PlayerId::Player1)
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Kifu verification
+//!
+//! Replays stored games with the current rules and reports the ones whose
+//! moves are no longer legal (or whose positions no longer match their
+//! snapshots), so a rules change cannot leave silently inconsistent games in
+//! the training data. Games start from the selfplay setup of their
+//! `board_setup`, or from the ply 0 snapshot when the kifu has one.
+
+use super::KifuData;
+use crate::core::setup::BoardSetupType;
+use crate::core::PlayerId;
+use crate::logic::{apply_move, legal_moves, ZobristHasher};
+use std::path::{Path, PathBuf};
+
+pub struct KifuProblem {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Default)]
+pub struct VerifyReport {
+    /// Kifu files replayed (other JSON files are skipped)
+    pub checked: usize,
+    pub problems: Vec<KifuProblem>,
+}
+
+/// First inconsistency of a game under the current rules, if any
+pub fn verify_kifu(kifu: &KifuData) -> Option<String> {
+    let (mut board, mut player) = match kifu.snapshots.iter().find(|s| s.ply == 0) {
+        Some(snapshot) => (snapshot.restore(), snapshot.side_to_move),
+        None => match BoardSetupType::from_name(&kifu.board_setup) {
+            Some(setup) => (setup.create_board(), PlayerId::Player1),
+            None => return Some(format!("unknown board setup '{}'", kifu.board_setup)),
+        },
+    };
+
+    for (i, mv) in kifu.moves.iter().enumerate() {
+        if !legal_moves(&board, player).contains(mv) {
+            return Some(format!("move {} ({:?} {}) is not legal", i + 1, player, mv));
+        }
+        board = apply_move(&board, mv, player);
+        player = player.opponent();
+
+        if let Some(snapshot) = kifu.snapshots.iter().find(|s| s.ply == i + 1) {
+            if snapshot.side_to_move != player
+                || ZobristHasher::compute_hash(&snapshot.board, player)
+                    != ZobristHasher::compute_hash(&board, player)
+            {
+                return Some(format!(
+                    "position after move {} does not match its snapshot",
+                    i + 1
+                ));
+            }
+        }
+    }
+    None
+}
+
+/// Verify every kifu under `dir` (recursively)
+pub fn verify_dir(dir: &Path) -> anyhow::Result<VerifyReport> {
+    let mut files = Vec::new();
+    collect_json_files(dir, &mut files)?;
+    files.sort();
+
+    let mut report = VerifyReport::default();
+    for path in files {
+        let value: serde_json::Value = match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+        {
+            Ok(value) => value,
+            Err(e) => {
+                report.problems.push(KifuProblem {
+                    path,
+                    reason: format!("unreadable: {}", e),
+                });
+                continue;
+            }
+        };
+        // Run manifests, results and analysis reports live next to the kifu
+        if value.get("moves").is_none() || value.get("board_setup").is_none() {
+            continue;
+        }
+
+        report.checked += 1;
+        let reason = match serde_json::from_value::<KifuData>(value) {
+            Ok(kifu) => verify_kifu(&kifu),
+            Err(e) => Some(format!("not a valid kifu: {}", e)),
+        };
+        if let Some(reason) = reason {
+            report.problems.push(KifuProblem { path, reason });
+        }
+    }
+    Ok(report)
+}
+
+fn collect_json_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_json_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Move the problem files from `dir` into `quarantine`, keeping their
+/// relative paths. Returns the number of files moved.
+pub fn quarantine(
+    dir: &Path,
+    problems: &[KifuProblem],
+    quarantine: &Path,
+) -> anyhow::Result<usize> {
+    for problem in problems {
+        let relative = problem.path.strip_prefix(dir).unwrap_or(&problem.path);
+        let dest = quarantine.join(relative);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // rename fails across file systems
+        if std::fs::rename(&problem.path, &dest).is_err() {
+            std::fs::copy(&problem.path, &dest)?;
+            std::fs::remove_file(&problem.path)?;
+        }
+    }
+    Ok(problems.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_illegal_move() {
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        let mut player = PlayerId::Player1;
+        let mut moves = Vec::new();
+        for _ in 0..4 {
+            let mv = legal_moves(&board, player)[0];
+            board = apply_move(&board, &mv, player);
+            player = player.opponent();
+            moves.push(mv);
+        }
+        let mut kifu = KifuData {
+            board_setup: "ShogiOnly".to_string(),
+            chess960_seed: None,
+            handicap: None,
+            player1_name: String::new(),
+            player2_name: String::new(),
+            moves,
+            thinking_data: None,
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots: Vec::new(),
+            result: None,
+            variations: Vec::new(),
+        };
+        assert_eq!(verify_kifu(&kifu), None);
+
+        kifu.moves[3] = kifu.moves[2];
+        assert!(verify_kifu(&kifu).unwrap().starts_with("move 4 "));
+    }
+}
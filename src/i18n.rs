@@ -0,0 +1,112 @@
+//! 表示言語 (日本語 / 英語) の切り替え
+//!
+//! メニュー・入力の案内・対局中の状態表示は、英語と日本語の文を並べて書き
+//! ([`tr`] / [`trf!`](crate::trf))、表示のときに言語を選ぶ。言語は起動時の
+//! `--lang`、環境変数 `AHO_LANG`、ユーザー設定の `lang` の順に決まり
+//! (`--lang` 以外は端末 UI が [`set_lang`] する)、どれもなければロケール
+//! (`LC_ALL` / `LC_MESSAGES` / `LANG` が `ja` で始まれば日本語) に従う。
+//! 棋譜・ログ・ネットワークのメッセージは言語によらず英語のまま。
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    En,
+    Ja,
+}
+
+impl Lang {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "en" | "english" => Some(Lang::En),
+            "ja" | "jp" | "japanese" => Some(Lang::Ja),
+            _ => None,
+        }
+    }
+
+    /// `en` or `ja`, whichever is this language
+    pub fn pick<T>(self, en: T, ja: T) -> T {
+        match self {
+            Lang::En => en,
+            Lang::Ja => ja,
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Lang::En => Lang::Ja,
+            Lang::Ja => Lang::En,
+        }
+    }
+}
+
+/// 0: not chosen yet, else 1 + the language
+static LANG: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(1 + lang as u8, Ordering::Relaxed);
+}
+
+/// The display language, from the locale unless chosen with [`set_lang`]
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        1 => Lang::En,
+        2 => Lang::Ja,
+        _ => {
+            let lang = locale_lang();
+            set_lang(lang);
+            lang
+        }
+    }
+}
+
+fn locale_lang() -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|v| !v.is_empty());
+    match locale {
+        Some(v) if v.to_ascii_lowercase().starts_with("ja") => Lang::Ja,
+        _ => Lang::En,
+    }
+}
+
+/// `en` or `ja`, whichever is the display language
+pub fn tr(en: &'static str, ja: &'static str) -> &'static str {
+    lang().pick(en, ja)
+}
+
+/// `format!` in the display language: `trf!("{} wins!", "{}の勝ち", name)`
+#[macro_export]
+macro_rules! trf {
+    ($en:literal, $ja:literal, $($arg:tt)*) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::En => format!($en, $($arg)*),
+            $crate::i18n::Lang::Ja => format!($ja, $($arg)*),
+        }
+    };
+}
+
+/// The player as shown to the user (`Player1` / `先手`)
+pub fn player_name(player: crate::core::PlayerId) -> &'static str {
+    match player {
+        crate::core::PlayerId::Player1 => tr("Player1", "先手"),
+        crate::core::PlayerId::Player2 => tr("Player2", "後手"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lang_names() {
+        assert_eq!(Lang::from_name("JA"), Some(Lang::Ja));
+        assert_eq!(Lang::from_name("english"), Some(Lang::En));
+        assert_eq!(Lang::from_name("fr"), None);
+        assert_eq!(Lang::Ja.pick("Draw!", "引き分け"), "引き分け");
+        assert_eq!(Lang::En.other(), Lang::Ja);
+    }
+}
@@ -1,9 +1,71 @@
-// Library root
+//! Shogi-Aho-AI engine
+//!
+//! Shogi and chess pieces on one board: move generation, rules, the
+//! alpha-beta search with handcrafted, NN and NNUE evaluators, self-play and
+//! kifu tools. The `shogi-aho-ai` binary is the command line and terminal UI
+//! on top of this crate; GUIs, bots and services can use the same API.
+//!
+//! ```
+//! use shogi_aho_ai::{apply_move, legal_moves, search, BoardSetupType, PlayerId};
+//!
+//! let board = BoardSetupType::ShogiOnly.create_board();
+//! let moves = legal_moves(&board, PlayerId::Player1);
+//! let board = apply_move(&board, &moves[0], PlayerId::Player1);
+//!
+//! let result = search(&board, PlayerId::Player2, 2).unwrap();
+//! println!("{} ({:+})", result.best_move.unwrap().notation(&board, PlayerId::Player2), result.score);
+//! ```
+//!
+//! Engine settings (evaluator, search depth, rules) come from `ai_config.json`,
+//! `ruleset.toml` and `pieces.toml` in the working directory, or the defaults.
+
+#[cfg(feature = "cli")]
+pub mod analysis;
+#[cfg(feature = "cli")]
+pub mod bench;
 pub mod core;
+#[cfg(feature = "cli")]
+pub mod crash;
+#[cfg(feature = "cli")]
+pub mod demo;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod game;
+pub mod i18n;
 pub mod logic;
+#[cfg(test)]
+mod logic_tests;
 pub mod ml;
+#[cfg(feature = "cli")]
 pub mod network;
 pub mod player;
+pub mod profile;
+#[cfg(feature = "cli")]
 pub mod selfplay;
+#[cfg(feature = "cli")]
+pub mod selftest;
+#[cfg(feature = "cli")]
 pub mod ui;
+pub mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use crate::core::setup::BoardSetupType;
+pub use crate::core::{Board, Move, Piece, PieceKind, PlayerId, Position};
+pub use logic::{apply_move, is_checkmate, is_in_check, legal_moves};
+pub use player::ai::alpha_beta::AnalysisResult;
+pub use player::ai::{AIStrength, AlphaBetaAI};
+
+/// Searches `board` for `player` to `depth` plies with the evaluator
+/// configured in `ai_config.json`. The score is for `player`, in centipawns.
+/// `None` when `player` has no legal move.
+pub fn search(board: &Board, player: PlayerId, depth: usize) -> Option<AnalysisResult> {
+    let engine = AlphaBetaAI::new(player, "Engine", AIStrength::Strong, None, true);
+    let mut cache = player::ai::analysis_cache::AnalysisCache::new();
+    engine.analyze(board, depth, &mut cache)
+}
+
+/// Handcrafted static evaluation of `board` from Player1's view, in centipawns
+pub fn evaluate(board: &Board) -> i32 {
+    player::ai::eval::evaluate(board)
+}
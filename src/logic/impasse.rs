@@ -0,0 +1,179 @@
+//! Entering-king impasse (jishogi) and the declaration rules
+//!
+//! When both kings have entered the enemy camp neither side can usually be
+//! mated, and without a rule such games run into the move cap. A player whose
+//! king has entered may declare on their turn if
+//! - their king is in the enemy camp (promotion zone) and not in check,
+//! - at least [`MIN_PIECES_IN_CAMP`] other pieces of theirs are in the camp,
+//!
+//! and then scores 5 points for each rook or bishop (promoted or not) and 1 for
+//! every other piece, counting the pieces in the camp and in hand.
+//! - 27-point rule: Player1 wins with 28 points, Player2 with 27.
+//! - 24-point rule: 31 points win, 24 to 30 draw, fewer lose.
+//!
+//! Only shogi kings can declare: chess-style sides have no hand to count.
+
+use crate::core::ruleset::ImpasseConfig;
+use crate::core::{Board, PieceKind, PlayerId};
+use crate::logic::{is_in_check, rules};
+use serde::{Deserialize, Serialize};
+
+/// Pieces besides the king a declaring player needs in the enemy camp
+pub const MIN_PIECES_IN_CAMP: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImpasseRule {
+    /// Tournament rule: 28 (Player1) / 27 (Player2) points win
+    TwentySeven,
+    /// Amateur rule: 31 points win, 24 or more draw
+    TwentyFour,
+}
+
+/// Result of a declaration for the declaring player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Declaration {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// 5 for rooks and bishops (promoted or not), 1 for other pieces, 0 for kings
+pub fn piece_points(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::S_Rook | PieceKind::S_Bishop | PieceKind::S_ProRook | PieceKind::S_ProBishop => {
+            5
+        }
+        PieceKind::S_King | PieceKind::C_King => 0,
+        _ => 1,
+    }
+}
+
+/// Whether `player` has a shogi king in the enemy camp
+pub fn king_entered(board: &Board, player: PlayerId) -> bool {
+    board.pieces.iter().any(|(pos, piece)| {
+        piece.owner == player
+            && piece.kind == PieceKind::S_King
+            && rules::in_promotion_zone(&board.rules, player, pos.y, board.height)
+    })
+}
+
+/// Both kings have entered the enemy camp
+pub fn is_impasse(board: &Board) -> bool {
+    king_entered(board, PlayerId::Player1) && king_entered(board, PlayerId::Player2)
+}
+
+/// Points `player` would declare with, or `None` if they may not declare
+pub fn declaration_points(board: &Board, player: PlayerId) -> Option<usize> {
+    if !king_entered(board, player) || is_in_check(board, player) {
+        return None;
+    }
+    let in_camp: Vec<PieceKind> = board
+        .pieces
+        .iter()
+        .filter(|(pos, piece)| {
+            piece.owner == player
+                && !matches!(piece.kind, PieceKind::S_King | PieceKind::C_King)
+                && rules::in_promotion_zone(&board.rules, player, pos.y, board.height)
+        })
+        .map(|(_, piece)| piece.kind)
+        .collect();
+    if in_camp.len() < MIN_PIECES_IN_CAMP {
+        return None;
+    }
+    let in_hand: usize = board.hand.get(&player).map_or(0, |hand| {
+        hand.iter()
+            .map(|(&kind, &count)| piece_points(kind) * count)
+            .sum()
+    });
+    Some(in_camp.into_iter().map(piece_points).sum::<usize>() + in_hand)
+}
+
+impl ImpasseRule {
+    pub fn judge(self, player: PlayerId, points: usize) -> Declaration {
+        match self {
+            ImpasseRule::TwentySeven => {
+                let needed = if player == PlayerId::Player1 { 28 } else { 27 };
+                if points >= needed {
+                    Declaration::Win
+                } else {
+                    Declaration::Loss
+                }
+            }
+            ImpasseRule::TwentyFour if points >= 31 => Declaration::Win,
+            ImpasseRule::TwentyFour if points >= 24 => Declaration::Draw,
+            ImpasseRule::TwentyFour => Declaration::Loss,
+        }
+    }
+
+    /// What `player` gets by declaring now, if they may declare
+    pub fn declare(self, board: &Board, player: PlayerId) -> Option<(Declaration, usize)> {
+        let points = declaration_points(board, player)?;
+        Some((self.judge(player, points), points))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImpasseRule::TwentySeven => "27-point rule",
+            ImpasseRule::TwentyFour => "24-point rule",
+        }
+    }
+}
+
+impl ImpasseConfig {
+    /// The declaration the side to move makes, if any: one that wins, or
+    /// one that draws once both kings have entered
+    pub fn auto_declaration(
+        &self,
+        board: &Board,
+        player: PlayerId,
+    ) -> Option<(Declaration, usize)> {
+        if !self.enabled {
+            return None;
+        }
+        match self.rule.declare(board, player)? {
+            (Declaration::Win, points) => Some((Declaration::Win, points)),
+            (Declaration::Draw, points) if is_impasse(board) => Some((Declaration::Draw, points)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Piece, Position};
+
+    #[test]
+    fn test_declaration() {
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let mut board = Board::new(9, 9);
+        board.place_piece(Position::new(4, 1), Piece::new(PieceKind::S_King, p1));
+        board.place_piece(Position::new(4, 7), Piece::new(PieceKind::S_King, p2));
+        assert!(is_impasse(&board));
+        // King alone: too few pieces in the camp
+        assert_eq!(declaration_points(&board, p1), None);
+
+        // 8 golds and 2 rooks in the camp, a bishop and 7 pawns in hand
+        for x in 0..8 {
+            board.place_piece(Position::new(x, 0), Piece::new(PieceKind::S_Gold, p1));
+        }
+        board.place_piece(Position::new(0, 2), Piece::new(PieceKind::S_Rook, p1));
+        board.place_piece(Position::new(8, 2), Piece::new(PieceKind::S_ProRook, p1));
+        board.add_to_hand(p1, PieceKind::S_Bishop);
+        for _ in 0..7 {
+            board.add_to_hand(p1, PieceKind::S_Pawn);
+        }
+        assert_eq!(declaration_points(&board, p1), Some(8 + 10 + 5 + 7));
+        assert_eq!(
+            ImpasseRule::TwentySeven.declare(&board, p1),
+            Some((Declaration::Win, 30))
+        );
+        assert_eq!(ImpasseRule::TwentyFour.judge(p1, 30), Declaration::Draw);
+        assert_eq!(ImpasseRule::TwentySeven.judge(p1, 27), Declaration::Loss);
+        assert_eq!(ImpasseRule::TwentySeven.judge(p2, 27), Declaration::Win);
+
+        // No declaring out of check
+        board.place_piece(Position::new(4, 3), Piece::new(PieceKind::S_Rook, p2));
+        assert_eq!(declaration_points(&board, p1), None);
+    }
+}
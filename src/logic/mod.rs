@@ -1,61 +1,78 @@
 use crate::core::{Board, Move, MoveStep, Piece, PieceKind, PlayerId, Position};
+pub mod impasse;
+pub mod rules;
 pub mod zobrist;
 pub use zobrist::ZobristHasher;
 
 /// 合法手生成 (自殺手を排除)
 pub fn legal_moves(board: &Board, player: PlayerId) -> Vec<Move> {
+    let _timer = crate::profile::timer(crate::profile::Subsystem::MoveGen);
     let pseudo = pseudo_legal_moves(board, player);
+    let pawn_drop_mate = board.get_player_config(player).pawn_drop_mate;
+    let enemy_king = board.find_king(player.opponent());
     pseudo
         .into_iter()
-        .filter(|mv| {
-            let next_board = apply_move(board, mv, player);
-            !is_in_check(&next_board, player)
-        })
+        .filter(|mv| keeps_rules(board, mv, player, pawn_drop_mate, enemy_king))
         .collect()
 }
 
+/// 疑似合法手 `mv` が合法か判定
+pub fn is_legal(board: &Board, mv: &Move, player: PlayerId) -> bool {
+    let pawn_drop_mate = board.get_player_config(player).pawn_drop_mate;
+    keeps_rules(
+        board,
+        mv,
+        player,
+        pawn_drop_mate,
+        board.find_king(player.opponent()),
+    )
+}
+
+fn keeps_rules(
+    board: &Board,
+    mv: &Move,
+    player: PlayerId,
+    pawn_drop_mate: bool,
+    enemy_king: Option<Position>,
+) -> bool {
+    // Not `apply_move`: the legality check is part of move generation
+    let next_board = make_move(board, mv, player);
+    if is_in_check(&next_board, player) {
+        return false;
+    }
+    // 打ち歩詰め: only pawn drops that give check need the mate test
+    match (*mv, enemy_king) {
+        (
+            Move::Drop {
+                kind: PieceKind::S_Pawn,
+                to,
+            },
+            Some(king),
+        ) if !pawn_drop_mate => {
+            let pawn = Piece::new(PieceKind::S_Pawn, player);
+            !(piece_attacks(board, to, &pawn, king) && is_checkmate(&next_board, player.opponent()))
+        }
+        _ => true,
+    }
+}
+
 /// 疑似合法手生成 (王手放置などは考慮しない)
 pub fn pseudo_legal_moves(board: &Board, player: PlayerId) -> Vec<Move> {
+    let _timer = crate::profile::timer(crate::profile::Subsystem::MoveGen);
     let mut moves = Vec::new();
     let config = board.get_player_config(player);
-
-    for (&pos, piece) in board.pieces.iter() {
-        if piece.owner == player {
-            moves.extend(get_piece_moves(board, pos, piece));
-        }
-    }
+    push_piece_moves(board, player, &mut moves);
 
     if config.can_drop {
         if let Some(hand) = board.hand.get(&player) {
-            for (&kind, &count) in hand.iter() {
+            let mut kinds: Vec<(PieceKind, usize)> = hand.iter().map(|(&k, &c)| (k, c)).collect();
+            kinds.sort_unstable_by_key(|&(kind, _)| kind);
+            for (kind, count) in kinds {
                 if count > 0 {
                     for y in 0..board.height {
                         for x in 0..board.width {
                             let to = Position::new(x, y);
-                            if board.get_piece(to).is_none() {
-                                // Check for illegal drops (places where piece cannot move)
-                                match kind {
-                                    PieceKind::S_Pawn | PieceKind::S_Lance => {
-                                        if (player == PlayerId::Player1 && y == 0)
-                                            || (player == PlayerId::Player2 && y == 8)
-                                        {
-                                            continue;
-                                        }
-                                    }
-                                    PieceKind::S_Knight => {
-                                        if (player == PlayerId::Player1 && y <= 1)
-                                            || (player == PlayerId::Player2 && y >= 7)
-                                        {
-                                            continue;
-                                        }
-                                    }
-                                    _ => {}
-                                }
-
-                                if kind == PieceKind::S_Pawn && has_pawn_in_column(board, player, x)
-                                {
-                                    continue;
-                                }
+                            if can_drop_at(board, player, kind, to) {
                                 moves.push(Move::Drop { kind, to });
                             }
                         }
@@ -68,6 +85,36 @@ pub fn pseudo_legal_moves(board: &Board, player: PlayerId) -> Vec<Move> {
     moves
 }
 
+/// 盤上の駒の疑似合法手 (持ち駒を打つ手を除く)
+pub fn piece_moves(board: &Board, player: PlayerId) -> Vec<Move> {
+    let _timer = crate::profile::timer(crate::profile::Subsystem::MoveGen);
+    let mut moves = Vec::new();
+    push_piece_moves(board, player, &mut moves);
+    moves
+}
+
+fn push_piece_moves(board: &Board, player: PlayerId, moves: &mut Vec<Move>) {
+    // Square and piece order, not HashMap order, so that move order (and with
+    // it search tie-breaks) is the same in every process
+    let mut own: Vec<(Position, &Piece)> = board
+        .pieces
+        .iter()
+        .filter(|(_, piece)| piece.owner == player)
+        .map(|(&pos, piece)| (pos, piece))
+        .collect();
+    own.sort_unstable_by_key(|&(pos, _)| (pos.y, pos.x));
+    for (pos, piece) in own {
+        moves.extend(get_piece_moves(board, pos, piece));
+    }
+}
+
+/// `kind` を `to` に打てるか判定 (空きマス・行き所のない駒・二歩。持ち駒の有無は見ない)
+pub fn can_drop_at(board: &Board, player: PlayerId, kind: PieceKind, to: Position) -> bool {
+    board.get_piece(to).is_none()
+        && !rules::is_dead_end(kind, player, to.y, board.height)
+        && !(kind == PieceKind::S_Pawn && has_pawn_in_column(board, player, to.x))
+}
+
 /// 王が取られる状態か判定
 pub fn is_in_check(board: &Board, player: PlayerId) -> bool {
     // 自玉の位置を探す
@@ -106,6 +153,69 @@ pub fn is_in_check(board: &Board, player: PlayerId) -> bool {
     false
 }
 
+/// `from` にある駒が `target` に利いているか (盤上の遮りを考慮)
+///
+/// 合法手生成や盤面コピーを伴わない軽量な利き判定。
+pub fn piece_attacks(board: &Board, from: Position, piece: &Piece, target: Position) -> bool {
+    if from == target {
+        return false;
+    }
+
+    if piece.kind == PieceKind::C_Pawn {
+        let forward = if piece.owner == PlayerId::Player1 {
+            -1
+        } else {
+            1
+        };
+        let dx = target.x as i32 - from.x as i32;
+        let dy = target.y as i32 - from.y as i32;
+        return dx.abs() == 1 && dy == forward;
+    }
+
+    piece.movement_rules().iter().any(|step| match *step {
+        MoveStep::Step(dx, dy) => offset_pos(from, dx, dy, board) == Some(target),
+        MoveStep::Slide(dx, dy) => {
+            let mut curr = from;
+            while let Some(to) = offset_pos(curr, dx, dy, board) {
+                if to == target {
+                    return true;
+                }
+                if board.get_piece(to).is_some() {
+                    break;
+                }
+                curr = to;
+            }
+            false
+        }
+    })
+}
+
+/// `player` の駒のうち相手の駒が利いているもの (玉は王手なので除く)
+///
+/// 盤面表示の「当たり」の目印に使う。上の段から順に並べる。
+pub fn attacked_pieces(board: &Board, player: PlayerId) -> Vec<Position> {
+    let attackers: Vec<(&Position, &Piece)> = board
+        .pieces
+        .iter()
+        .filter(|(_, p)| p.owner != player)
+        .collect();
+    let mut attacked: Vec<Position> = board
+        .pieces
+        .iter()
+        .filter(|(_, p)| {
+            p.owner == player && !matches!(p.kind, PieceKind::S_King | PieceKind::C_King)
+        })
+        .map(|(&pos, _)| pos)
+        .filter(|&pos| {
+            attackers
+                .iter()
+                .any(|&(&from, piece)| piece_attacks(board, from, piece, pos))
+        })
+        .collect();
+    attacked.sort_by_key(|pos| (pos.y, pos.x));
+    attacked
+}
+
 /// 詰み（または投了状態）か判定
 pub fn is_checkmate(board: &Board, player: PlayerId) -> bool {
     is_in_check(board, player) && legal_moves(board, player).is_empty()
@@ -125,10 +235,17 @@ fn get_piece_moves(board: &Board, from: Position, piece: &Piece) -> Vec<Move> {
                 if let Some(to) = offset_pos(from, dx, dy, board) {
                     if let Some(target) = board.get_piece(to) {
                         if config.can_capture && target.owner != piece.owner {
-                            add_normal_moves(&mut moves, from, to, piece, config.can_promote);
+                            add_normal_moves(
+                                &mut moves,
+                                board,
+                                from,
+                                to,
+                                piece,
+                                config.can_promote,
+                            );
                         }
                     } else {
-                        add_normal_moves(&mut moves, from, to, piece, config.can_promote);
+                        add_normal_moves(&mut moves, board, from, to, piece, config.can_promote);
                     }
                 }
             }
@@ -137,11 +254,18 @@ fn get_piece_moves(board: &Board, from: Position, piece: &Piece) -> Vec<Move> {
                 while let Some(to) = offset_pos(curr, dx, dy, board) {
                     if let Some(target) = board.get_piece(to) {
                         if config.can_capture && target.owner != piece.owner {
-                            add_normal_moves(&mut moves, from, to, piece, config.can_promote);
+                            add_normal_moves(
+                                &mut moves,
+                                board,
+                                from,
+                                to,
+                                piece,
+                                config.can_promote,
+                            );
                         }
                         break;
                     } else {
-                        add_normal_moves(&mut moves, from, to, piece, config.can_promote);
+                        add_normal_moves(&mut moves, board, from, to, piece, config.can_promote);
                         curr = to;
                     }
                 }
@@ -153,6 +277,7 @@ fn get_piece_moves(board: &Board, from: Position, piece: &Piece) -> Vec<Move> {
 
 fn add_normal_moves(
     moves: &mut Vec<Move>,
+    board: &Board,
     from: Position,
     to: Position,
     piece: &Piece,
@@ -161,16 +286,10 @@ fn add_normal_moves(
     if can_promote {
         let promoted_kind = piece.promotable_kind();
         if let Some(p_kind) = promoted_kind {
-            let is_promotion_zone = if piece.owner == PlayerId::Player1 {
-                to.y <= 2
-            } else {
-                to.y >= 6
-            };
-            let from_promotion_zone = if piece.owner == PlayerId::Player1 {
-                from.y <= 2
-            } else {
-                from.y >= 6
-            };
+            let is_promotion_zone =
+                rules::in_promotion_zone(&board.rules, piece.owner, to.y, board.height);
+            let from_promotion_zone =
+                rules::in_promotion_zone(&board.rules, piece.owner, from.y, board.height);
 
             if is_promotion_zone || from_promotion_zone {
                 moves.push(Move::Normal {
@@ -180,18 +299,7 @@ fn add_normal_moves(
                 });
 
                 // 強制成りの判定 (Pawn, Lance, Knight)
-                // Knight (Keima) cannot move if at row 0 or 1 (for P1)
-                let must_promote = match piece.kind {
-                    PieceKind::S_Pawn | PieceKind::S_Lance => {
-                        (piece.owner == PlayerId::Player1 && to.y == 0)
-                            || (piece.owner == PlayerId::Player2 && to.y == 8)
-                    }
-                    PieceKind::S_Knight => {
-                        (piece.owner == PlayerId::Player1 && to.y <= 1)
-                            || (piece.owner == PlayerId::Player2 && to.y >= 7)
-                    }
-                    _ => false,
-                };
+                let must_promote = rules::is_dead_end(piece.kind, piece.owner, to.y, board.height);
 
                 if !must_promote {
                     moves.push(Move::Normal {
@@ -218,26 +326,13 @@ fn get_chess_pawn_moves(board: &Board, from: Position, piece: &Piece) -> Vec<Mov
     } else {
         1
     };
-    let start_y = if piece.owner == PlayerId::Player1 {
-        6
-    } else {
-        1
-    };
-    let promo_y = if piece.owner == PlayerId::Player1 {
-        0
-    } else {
-        7
-    };
+    let rows = rules::chess_pawn_rows(piece.owner);
+    let (start_y, promo_y) = (rows.start, rows.promotion);
 
     let push_move = |moves: &mut Vec<Move>, to: Position, is_promo: bool| {
         if is_promo {
             // Chess promotion: Queen, Rook, Bishop, Knight
-            for k in [
-                PieceKind::C_Queen,
-                PieceKind::C_Rook,
-                PieceKind::C_Bishop,
-                PieceKind::C_Knight,
-            ] {
+            for &k in piece.promotion_kinds() {
                 moves.push(Move::Normal {
                     from,
                     to,
@@ -282,9 +377,38 @@ fn get_chess_pawn_moves(board: &Board, from: Position, piece: &Piece) -> Vec<Mov
             }
         }
     }
+
+    // En passant: the enemy pawn that just advanced two squares stands beside
+    // this one, on the row its double step ends on
+    if let Some(x) = board.en_passant {
+        let beside = Position::new(x, from.y);
+        let is_enemy_pawn = board
+            .get_piece(beside)
+            .is_some_and(|p| p.kind == PieceKind::C_Pawn && p.owner != piece.owner);
+        if from.y == rows.en_passant && x.abs_diff(from.x) == 1 && is_enemy_pawn {
+            if let Some(to) = offset_pos(beside, 0, forward, board) {
+                if board.get_piece(to).is_none() {
+                    push_move(&mut moves, to, false);
+                }
+            }
+        }
+    }
     moves
 }
 
+/// Square of the pawn captured by `mv` if it is an en passant capture: a
+/// chess pawn moving diagonally onto an empty square
+pub fn en_passant_capture(board: &Board, mv: &Move) -> Option<Position> {
+    let Move::Normal { from, to, .. } = *mv else {
+        return None;
+    };
+    let is_pawn = board
+        .get_piece(from)
+        .is_some_and(|p| p.kind == PieceKind::C_Pawn);
+    (is_pawn && from.x != to.x && board.get_piece(to).is_none())
+        .then(|| Position::new(to.x, from.y))
+}
+
 fn offset_pos(pos: Position, dx: i32, dy: i32, board: &Board) -> Option<Position> {
     let x = pos.x as i32 + dx;
     let y = pos.y as i32 + dy;
@@ -308,15 +432,26 @@ fn has_pawn_in_column(board: &Board, player: PlayerId, x: usize) -> bool {
 
 /// 移動適用
 pub fn apply_move(board: &Board, mv: &Move, player: PlayerId) -> Board {
+    let _timer = crate::profile::timer(crate::profile::Subsystem::ApplyMove);
+    make_move(board, mv, player)
+}
+
+fn make_move(board: &Board, mv: &Move, player: PlayerId) -> Board {
     let mut next = board.clone();
-    next.last_move = Some(mv.clone());
+    next.last_move = Some(*mv);
+    next.en_passant = None;
 
     match mv {
         Move::Normal { from, to, promote } => {
+            let captured_at = en_passant_capture(board, mv).unwrap_or(*to);
             if let Some(mut piece) = next.remove_piece(*from) {
                 let config = next.get_player_config(piece.owner);
 
-                if let Some(captured) = next.remove_piece(*to) {
+                if piece.kind == PieceKind::C_Pawn && from.y.abs_diff(to.y) == 2 {
+                    next.en_passant = Some(to.x);
+                }
+
+                if let Some(captured) = next.remove_piece(captured_at) {
                     if config.keep_captured {
                         next.add_to_hand(piece.owner, captured.unpromoted_kind());
                     }
@@ -395,5 +530,6 @@ fn get_piece_value(kind: PieceKind) -> i32 {
         PieceKind::S_Lance => 300,
         PieceKind::S_Pawn | PieceKind::C_Pawn => 100,
         PieceKind::S_ProRook | PieceKind::S_ProBishop => 1100,
+        PieceKind::Custom(i) => crate::core::fairy::def(i).map_or(0, |def| def.value),
     }
 }
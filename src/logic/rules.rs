@@ -0,0 +1,510 @@
+//! Rule definitions, and the rules in force for a game
+//!
+//! Move generation uses the promotion zone, dead-end rows and chess pawn rows
+//! defined here and the board's [`Ruleset`], so [`RulesInForce::describe`]
+//! shows the rules actually applied.
+
+use crate::core::{Board, PieceKind, PlayerConfig, PlayerId, Position, Ruleset};
+
+/// Rows from `y` to the far (opponent's) end of a board `height` rows tall;
+/// 0 on the last row
+pub fn rows_to_go(player: PlayerId, y: usize, height: usize) -> usize {
+    match player {
+        PlayerId::Player1 => y,
+        PlayerId::Player2 => height.saturating_sub(1).saturating_sub(y),
+    }
+}
+
+/// Whether row `y` is in `player`'s promotion zone
+pub fn in_promotion_zone(rules: &Ruleset, player: PlayerId, y: usize, height: usize) -> bool {
+    rows_to_go(player, y, height) < rules.promotion_zone_rows
+}
+
+/// Winner when `player` has no legal move: checkmate, and stalemate unless
+/// `player`'s rules make it a draw (`None`)
+pub fn no_move_winner(board: &Board, player: PlayerId) -> Option<PlayerId> {
+    if board.get_player_config(player).stalemate_draws && !crate::logic::is_in_check(board, player)
+    {
+        None
+    } else {
+        Some(player.opponent())
+    }
+}
+
+/// Rows at the far end where `kind` could never move again: it may not be
+/// dropped there and must promote on reaching them
+pub fn dead_end_rows(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::S_Pawn | PieceKind::S_Lance => 1,
+        PieceKind::S_Knight => 2,
+        _ => 0,
+    }
+}
+
+/// Whether `kind` would be stuck on row `y`
+pub fn is_dead_end(kind: PieceKind, player: PlayerId, y: usize, height: usize) -> bool {
+    rows_to_go(player, y, height) < dead_end_rows(kind)
+}
+
+/// Chess pawn rows (on an 8x8 board)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChessPawnRows {
+    /// Starting row, from which a pawn may advance two squares
+    pub start: usize,
+    /// Row from which an enemy pawn can be taken en passant
+    pub en_passant: usize,
+    /// Row where the pawn promotes
+    pub promotion: usize,
+}
+
+pub fn chess_pawn_rows(player: PlayerId) -> ChessPawnRows {
+    match player {
+        PlayerId::Player1 => ChessPawnRows {
+            start: 6,
+            en_passant: 3,
+            promotion: 0,
+        },
+        PlayerId::Player2 => ChessPawnRows {
+            start: 1,
+            en_passant: 4,
+            promotion: 7,
+        },
+    }
+}
+
+/// Neither side can ever checkmate: nothing in hand and, besides chess kings,
+/// at most one bishop or knight, or only bishops all on one square color
+pub fn is_insufficient_material(board: &Board) -> bool {
+    if board.hand.values().flat_map(|h| h.values()).any(|&c| c > 0) {
+        return false;
+    }
+    let mut minors = Vec::new();
+    for (pos, piece) in &board.pieces {
+        match piece.kind {
+            PieceKind::C_King => {}
+            PieceKind::C_Bishop | PieceKind::C_Knight => minors.push((*pos, piece.kind)),
+            _ => return false,
+        }
+    }
+    let color = |pos: &Position| (pos.x + pos.y) % 2;
+    match minors.as_slice() {
+        [] | [_] => true,
+        [(first, _), ..] => minors
+            .iter()
+            .all(|(pos, kind)| *kind == PieceKind::C_Bishop && color(pos) == color(first)),
+    }
+}
+
+/// A game end decided by a rule before the side to move plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleEnding {
+    /// Sennichite (see [`crate::core::Ruleset::repetition_limit`])
+    Repetition,
+    /// The side to move declared with this many points
+    Impasse(
+        crate::logic::impasse::ImpasseRule,
+        crate::logic::impasse::Declaration,
+        usize,
+    ),
+    /// Neither side has the material to checkmate
+    InsufficientMaterial,
+    /// Too many plies without a capture or pawn move (the 50-move rule)
+    QuietPlies(usize),
+    /// The move cap was reached
+    MoveLimit(usize),
+}
+
+impl RuleEnding {
+    /// The winner, with `to_move` the side to move when the game ended
+    pub fn winner(self, to_move: PlayerId) -> Option<PlayerId> {
+        match self {
+            RuleEnding::Impasse(_, crate::logic::impasse::Declaration::Win, _) => Some(to_move),
+            _ => None,
+        }
+    }
+
+    /// For the kifu's [`KifuResult`]
+    pub fn reason(self) -> &'static str {
+        match self {
+            RuleEnding::Repetition => "Repetition",
+            RuleEnding::Impasse(..) => "Impasse",
+            RuleEnding::InsufficientMaterial => "Insufficient material",
+            RuleEnding::QuietPlies(_) => "Quiet plies",
+            RuleEnding::MoveLimit(_) => "Move limit",
+        }
+    }
+
+    /// In the display language (see [`crate::i18n`])
+    pub fn message(self, to_move: PlayerId) -> String {
+        use crate::i18n::{player_name, tr};
+
+        let outcome = match self.winner(to_move) {
+            Some(winner) => crate::trf!("{} wins!", "{}の勝ち", player_name(winner)),
+            None => tr("Draw!", "引き分け").to_string(),
+        };
+        match self {
+            RuleEnding::Repetition => {
+                crate::trf!("Sennichite (Repetition) - {}", "千日手 - {}", outcome)
+            }
+            RuleEnding::Impasse(rule, _, points) => crate::trf!(
+                "Impasse declaration ({}, {} points) - {}",
+                "入玉宣言 ({}、{} 点) - {}",
+                rule.name(),
+                points,
+                outcome
+            ),
+            RuleEnding::InsufficientMaterial => {
+                crate::trf!("Insufficient material - {}", "駒不足 - {}", outcome)
+            }
+            RuleEnding::QuietPlies(plies) => crate::trf!(
+                "{} plies without capture or pawn move - {}",
+                "{} 手のあいだ駒取りもポーンの移動もなし - {}",
+                plies,
+                outcome
+            ),
+            RuleEnding::MoveLimit(plies) => {
+                crate::trf!(
+                    "Move limit ({} plies) - {}",
+                    "手数制限 ({} 手) - {}",
+                    plies,
+                    outcome
+                )
+            }
+        }
+    }
+}
+
+/// Whether a rule ends the game before `to_move` plays, after `plies` moves
+/// of which the last `quiet_plies` had no capture, promotion or pawn move:
+/// repetition, insufficient material, an entering-king declaration or a move
+/// limit. A side with no legal move loses even when a move limit is reached.
+pub fn rule_ending(
+    board: &Board,
+    to_move: PlayerId,
+    plies: usize,
+    quiet_plies: usize,
+) -> Option<RuleEnding> {
+    let hash_count = board
+        .history
+        .iter()
+        .filter(|&&h| h == board.zobrist_hash)
+        .count();
+    if hash_count >= board.rules.repetition_limit {
+        return Some(RuleEnding::Repetition);
+    }
+    if is_insufficient_material(board) {
+        return Some(RuleEnding::InsufficientMaterial);
+    }
+
+    let impasse = &board.rules.impasse;
+    if let Some((declaration, points)) = impasse.auto_declaration(board, to_move) {
+        return Some(RuleEnding::Impasse(impasse.rule, declaration, points));
+    }
+
+    let rules = RulesInForce::for_board(board);
+    let ending = match rules.quiet_plies {
+        Some(limit) if quiet_plies >= limit => RuleEnding::QuietPlies(limit),
+        _ if plies >= rules.rules.max_plies => RuleEnding::MoveLimit(rules.rules.max_plies),
+        _ => return None,
+    };
+    (!crate::logic::legal_moves(board, to_move).is_empty()).then_some(ending)
+}
+
+/// One side's rules
+#[derive(Debug, Clone)]
+pub struct SideRules {
+    pub player: PlayerId,
+    pub config: PlayerConfig,
+    /// The side has shogi pieces (on the board or in hand)
+    pub shogi_pieces: bool,
+    /// The side has chess pawns on the board
+    pub chess_pawns: bool,
+}
+
+/// The rules in force for a game, gathered from the board's ruleset and
+/// player configs
+#[derive(Debug, Clone)]
+pub struct RulesInForce {
+    pub width: usize,
+    pub height: usize,
+    pub sides: [SideRules; 2],
+    pub rules: Ruleset,
+    /// Self-play draw adjudication (`None` when disabled)
+    pub adjudication: Option<crate::core::ruleset::AdjudicationConfig>,
+    /// Entering-king declarations (`None` when disabled)
+    pub impasse: Option<crate::logic::impasse::ImpasseRule>,
+    /// Plies without a capture or pawn move that draw the game (`None` unless
+    /// both sides play chess only)
+    pub quiet_plies: Option<usize>,
+}
+
+impl RulesInForce {
+    pub fn for_board(board: &Board) -> Self {
+        let side = |player: PlayerId| {
+            let on_board = board.pieces.values().filter(|p| p.owner == player);
+            let in_hand = board
+                .hand
+                .get(&player)
+                .into_iter()
+                .flat_map(|hand| hand.iter().filter(|(_, &count)| count > 0));
+            SideRules {
+                player,
+                config: board.get_player_config(player),
+                shogi_pieces: on_board.clone().any(|p| p.kind.is_shogi())
+                    || in_hand.clone().any(|(kind, _)| kind.is_shogi()),
+                chess_pawns: on_board.clone().any(|p| p.kind == PieceKind::C_Pawn),
+            }
+        };
+        let adjudication = &board.rules.adjudication;
+        let impasse = &board.rules.impasse;
+        let sides = [side(PlayerId::Player1), side(PlayerId::Player2)];
+        let chess_only = sides.iter().all(|side| !side.shogi_pieces);
+        let quiet_plies = board.rules.chess_quiet_plies;
+        RulesInForce {
+            width: board.width,
+            height: board.height,
+            sides,
+            rules: board.rules.clone(),
+            adjudication: adjudication.enabled.then(|| adjudication.clone()),
+            impasse: impasse.enabled.then_some(impasse.rule),
+            quiet_plies: (chess_only && quiet_plies > 0).then_some(quiet_plies),
+        }
+    }
+
+    /// The rules as text lines for the rules screen. Rows are numbered as on
+    /// the board display (1 at the top).
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![format!("Board: {}x{}", self.width, self.height)];
+        let fairy = crate::core::fairy::pieces();
+        if !fairy.is_empty() {
+            lines.push(String::new());
+            lines.push("Custom pieces (pieces.toml):".to_string());
+            for def in fairy {
+                let promotion = def
+                    .promotes_to
+                    .as_ref()
+                    .map_or(String::new(), |to| format!(", promotes to {}", to));
+                lines.push(format!(
+                    "  - {} ({}, @{}): value {}{}",
+                    def.name, def.display_char, def.symbol, def.value, promotion
+                ));
+            }
+        }
+        for side in &self.sides {
+            lines.push(String::new());
+            lines.push(format!("{:?}:", side.player));
+            lines.extend(
+                side.describe(&self.rules, self.height)
+                    .into_iter()
+                    .map(|line| format!("  {}", line)),
+            );
+        }
+
+        lines.push(String::new());
+        lines.push("Game end:".to_string());
+        lines.push("  - A move may not leave your own king in check".to_string());
+        lines.push(
+            "  - Checkmate wins; a side with no legal move otherwise (stalemate) \
+             loses unless its rules say draw"
+                .to_string(),
+        );
+        lines.push(format!(
+            "  - Repetition: the same position {} times is a draw (sennichite)",
+            self.rules.repetition_limit
+        ));
+        if let Some(rule) = self.impasse {
+            if self.sides.iter().any(|side| side.shogi_pieces) {
+                lines.push(format!(
+                    "  - Impasse: a shogi king in the enemy camp, not in check, with {}+ pieces \
+                     there declares ({}; rooks and bishops 5 points, others 1, hand included)",
+                    crate::logic::impasse::MIN_PIECES_IN_CAMP,
+                    rule.name()
+                ));
+            }
+        }
+        if self.sides.iter().all(|side| !side.shogi_pieces) {
+            lines.push(
+                "  - Insufficient material (kings and one bishop or knight, or bishops \
+                 on one color) is a draw"
+                    .to_string(),
+            );
+        }
+        if let Some(plies) = self.quiet_plies {
+            lines.push(format!(
+                "  - {} plies without a capture or pawn move is a draw",
+                plies
+            ));
+        }
+        lines.push(format!(
+            "  - The game is a draw after {} plies",
+            self.rules.max_plies
+        ));
+        lines.push("  - Resigning ([q]) loses".to_string());
+        match &self.adjudication {
+            Some(adjudication) => lines.push(format!(
+                "  - Self-play only: drawn after {} plies without capture, promotion or \
+                 pawn move if the score is within {}cp (window widens by {}cp every {} plies), \
+                 and always after {}",
+                adjudication.stagnation_plies,
+                adjudication.draw_score_centipawns,
+                adjudication.widen_step_centipawns,
+                adjudication.widen_every_plies,
+                adjudication.max_quiet_plies
+            )),
+            None => lines.push("  - No draw adjudication".to_string()),
+        }
+        lines
+    }
+}
+
+/// Board rows (numbered from 1) matching `pred`, e.g. "rows 7-9"
+fn rows_where(height: usize, pred: impl Fn(usize) -> bool) -> String {
+    let rows: Vec<usize> = (0..height).filter(|&y| pred(y)).map(|y| y + 1).collect();
+    match rows.as_slice() {
+        [row] => format!("row {}", row),
+        [first, .., last] => format!("rows {}-{}", first, last),
+        [] => "no rows".to_string(),
+    }
+}
+
+impl SideRules {
+    fn describe(&self, rules: &Ruleset, height: usize) -> Vec<String> {
+        let config = &self.config;
+        let mut lines = Vec::new();
+        lines.push(if !config.can_capture {
+            "- Captures: not allowed".to_string()
+        } else if config.keep_captured {
+            "- Captures: captured pieces go to the hand (unpromoted)".to_string()
+        } else {
+            "- Captures: captured pieces leave the game".to_string()
+        });
+
+        if config.can_drop {
+            lines.push(format!(
+                "- Drops: allowed on empty squares; not pawns or lances on {}, knights on {}, \
+                 or a pawn on a file with your unpromoted pawn",
+                rows_where(height, |y| is_dead_end(
+                    PieceKind::S_Pawn,
+                    self.player,
+                    y,
+                    height
+                )),
+                rows_where(height, |y| is_dead_end(
+                    PieceKind::S_Knight,
+                    self.player,
+                    y,
+                    height
+                ))
+            ));
+            if !config.pawn_drop_mate {
+                lines.push("- A pawn drop may not give checkmate (uchifuzume)".to_string());
+            }
+        } else {
+            lines.push("- Drops: not allowed".to_string());
+        }
+
+        lines.push(if config.stalemate_draws {
+            "- Stalemate (no legal move, not in check): draw".to_string()
+        } else {
+            "- Stalemate (no legal move, not in check): loss".to_string()
+        });
+
+        if self.shogi_pieces {
+            if config.can_promote {
+                lines.push(format!(
+                    "- Shogi promotion: optional for moves into, out of or within {}; \
+                     forced where the piece could not move again",
+                    rows_where(height, |y| in_promotion_zone(rules, self.player, y, height))
+                ));
+            } else {
+                lines.push("- Shogi promotion: not allowed".to_string());
+            }
+        }
+
+        if self.chess_pawns {
+            let rows = chess_pawn_rows(self.player);
+            lines.push(format!(
+                "- Chess pawns: two squares from row {}, en passant, promote on row {} \
+                 to Queen, Rook, Bishop or Knight",
+                rows.start + 1,
+                rows.promotion + 1
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::setup::BoardSetupType;
+
+    #[test]
+    fn test_rules_follow_the_setup() {
+        let shogi = RulesInForce::for_board(&BoardSetupType::ShogiOnly.create_board()).describe();
+        let text = shogi.join("\n");
+        assert!(text.contains("rows 1-3") && text.contains("rows 7-9"));
+        assert!(text.contains("pawns or lances on row 9, knights on rows 8-9"));
+        assert!(text.contains("uchifuzume") && text.contains("27-point rule"));
+        assert!(!text.contains("Chess pawns") && !text.contains("without a capture"));
+
+        let chess = RulesInForce::for_board(&BoardSetupType::ChessOnly.create_board()).describe();
+        let text = chess.join("\n");
+        assert!(text.contains("Drops: not allowed") && text.contains("leave the game"));
+        assert!(text.contains("two squares from row 7, en passant, promote on row 1"));
+        assert!(text.contains("100 plies without a capture or pawn move"));
+        assert!(text.contains("Insufficient material"));
+        assert!(text.contains("not in check): draw") && !text.contains("not in check): loss"));
+        assert!(!text.contains("Shogi promotion") && !text.contains("Impasse"));
+
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        board.set_ruleset(Ruleset {
+            promotion_zone_rows: 2,
+            ..Ruleset::default()
+        });
+        let text = RulesInForce::for_board(&board).describe().join("\n");
+        assert!(text.contains("rows 1-2") && text.contains("rows 8-9"));
+
+        assert!(is_dead_end(PieceKind::S_Knight, PlayerId::Player2, 7, 9));
+        assert!(!is_dead_end(PieceKind::S_Knight, PlayerId::Player2, 6, 9));
+        assert!(is_dead_end(PieceKind::S_Pawn, PlayerId::Player2, 4, 5));
+        assert!(!is_dead_end(PieceKind::S_Pawn, PlayerId::Player2, 3, 5));
+        assert!(in_promotion_zone(
+            &Ruleset::default(),
+            PlayerId::Player2,
+            2,
+            5
+        ));
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        use crate::core::Piece;
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let mut board = Board::new(8, 8);
+        board.place_piece(Position::new(4, 7), Piece::new(PieceKind::C_King, p1));
+        board.place_piece(Position::new(4, 0), Piece::new(PieceKind::C_King, p2));
+        assert!(is_insufficient_material(&board));
+        board.place_piece(Position::new(2, 7), Piece::new(PieceKind::C_Bishop, p1));
+        assert!(is_insufficient_material(&board));
+        // Bishops on the same color
+        board.place_piece(Position::new(3, 0), Piece::new(PieceKind::C_Bishop, p2));
+        assert!(is_insufficient_material(&board));
+        board.place_piece(Position::new(5, 0), Piece::new(PieceKind::C_Bishop, p2));
+        assert!(is_insufficient_material(&board));
+        // Opposite colors can mate
+        board.place_piece(Position::new(2, 0), Piece::new(PieceKind::C_Bishop, p2));
+        assert!(!is_insufficient_material(&board));
+
+        let mut board = Board::new(8, 8);
+        board.place_piece(Position::new(4, 7), Piece::new(PieceKind::C_King, p1));
+        board.place_piece(Position::new(4, 0), Piece::new(PieceKind::C_King, p2));
+        board.place_piece(Position::new(1, 7), Piece::new(PieceKind::C_Knight, p1));
+        assert!(is_insufficient_material(&board));
+        board.place_piece(Position::new(6, 0), Piece::new(PieceKind::C_Knight, p2));
+        assert!(!is_insufficient_material(&board));
+        board.remove_piece(Position::new(6, 0));
+        board.place_piece(Position::new(0, 6), Piece::new(PieceKind::C_Pawn, p1));
+        assert!(!is_insufficient_material(&board));
+    }
+}
@@ -1,11 +1,12 @@
+use crate::core::fairy::MAX_CUSTOM_PIECES;
 use crate::core::{Board, PieceKind, PlayerId};
-use rand::Rng;
 use std::sync::OnceLock;
 
-// 定数
+// 定数: 乱数表は 9x9 まで。それより大きい盤の外側のマスは
+// [`derived_key`] で計算する
 const WIDTH: usize = 9;
 const HEIGHT: usize = 9;
-const PIECE_TYPES: usize = 20; // PieceKindの数
+const PIECE_TYPES: usize = 20; // 組み込みの PieceKind の数
 const PLAYERS: usize = 2;
 
 // Zobrist Hash用の乱数テーブル
@@ -13,33 +14,95 @@ struct ZobristTable {
     pieces: [[[u64; PIECE_TYPES]; HEIGHT]; WIDTH],
     hand: [[u64; PIECE_TYPES]; PLAYERS],
     side_to_move: u64,
+    en_passant: [u64; WIDTH],
+    // pieces.toml の駒 (PieceKind::Custom)
+    custom_pieces: [[[u64; MAX_CUSTOM_PIECES]; HEIGHT]; WIDTH],
+    custom_hand: [[u64; MAX_CUSTOM_PIECES]; PLAYERS],
+}
+
+/// 乱数表にないマス・列の乱数 (位置と種類から決まる)
+fn derived_key(kind: u64, x: usize, y: usize, k_idx: usize) -> u64 {
+    let mut state = ZOBRIST_SEED
+        ^ kind.rotate_left(56)
+        ^ ((x as u64) << 40)
+        ^ ((y as u64) << 20)
+        ^ k_idx as u64;
+    splitmix64(&mut state);
+    splitmix64(&mut state)
+}
+
+impl ZobristTable {
+    fn piece_key(&self, x: usize, y: usize, k_idx: usize) -> u64 {
+        if x >= WIDTH || y >= HEIGHT {
+            return derived_key(1, x, y, k_idx);
+        }
+        match k_idx.checked_sub(PIECE_TYPES) {
+            Some(c) => self.custom_pieces[x][y][c],
+            None => self.pieces[x][y][k_idx],
+        }
+    }
+
+    fn hand_key(&self, p_idx: usize, k_idx: usize) -> u64 {
+        match k_idx.checked_sub(PIECE_TYPES) {
+            Some(c) => self.custom_hand[p_idx][c],
+            None => self.hand[p_idx][k_idx],
+        }
+    }
 }
 
 static ZOBRIST_TABLE: OnceLock<ZobristTable> = OnceLock::new();
 
+// 固定シード: ハッシュ値をセッション間で共通にする (解析キャッシュの保存用)
+const ZOBRIST_SEED: u64 = 0x5A0B_2157_A40A_1000;
+
+/// SplitMix64 (実装が固定されているので、乱数クレートの版に依存しない)
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 fn get_zobrist_table() -> &'static ZobristTable {
     ZOBRIST_TABLE.get_or_init(|| {
-        let mut rng = rand::thread_rng();
+        let mut state = ZOBRIST_SEED;
         let mut table = ZobristTable {
             pieces: [[[0; PIECE_TYPES]; HEIGHT]; WIDTH],
             hand: [[0; PIECE_TYPES]; PLAYERS],
-            side_to_move: rng.gen(),
+            side_to_move: splitmix64(&mut state),
+            en_passant: [0; WIDTH],
+            custom_pieces: [[[0; MAX_CUSTOM_PIECES]; HEIGHT]; WIDTH],
+            custom_hand: [[0; MAX_CUSTOM_PIECES]; PLAYERS],
         };
 
         for x in 0..WIDTH {
             for y in 0..HEIGHT {
                 for k in 0..PIECE_TYPES {
-                    table.pieces[x][y][k] = rng.gen();
+                    table.pieces[x][y][k] = splitmix64(&mut state);
                 }
             }
         }
 
         for p in 0..PLAYERS {
             for k in 0..PIECE_TYPES {
-                table.hand[p][k] = rng.gen();
+                table.hand[p][k] = splitmix64(&mut state);
             }
         }
 
+        // 最後に生成する: 既存の乱数 (保存済みのハッシュ) を変えないため
+        for key in table.en_passant.iter_mut() {
+            *key = splitmix64(&mut state);
+        }
+
+        // 追加駒の乱数も既存の乱数の後に生成する
+        for key in table.custom_pieces.iter_mut().flatten().flatten() {
+            *key = splitmix64(&mut state);
+        }
+        for key in table.custom_hand.iter_mut().flatten() {
+            *key = splitmix64(&mut state);
+        }
+
         table
     })
 }
@@ -66,6 +129,7 @@ fn piece_kind_to_index(kind: PieceKind) -> usize {
         PieceKind::C_Bishop => 17,
         PieceKind::C_Knight => 18,
         PieceKind::C_Pawn => 19,
+        PieceKind::Custom(i) => PIECE_TYPES + i as usize,
     }
 }
 
@@ -95,7 +159,7 @@ impl ZobristHasher {
             // 専用の乱数を用意するのが定石。
             // ここでは table.pieces を使うが、Player2の場合はハッシュ値を bit rotate させて使う簡易実装にする。
 
-            let mut val = table.pieces[pos.x][pos.y][k_idx];
+            let mut val = table.piece_key(pos.x, pos.y, k_idx);
             if piece.owner == PlayerId::Player2 {
                 val = val.rotate_left(32); // 簡易的な区別
             }
@@ -114,7 +178,7 @@ impl ZobristHasher {
                     // あるいは Zobrist Hash としては「各個数」に対応する乱数を持つのが正しい。
                     // 今回は簡易版として、count回XORするのではなく、(count as u64).rotate_left(...) をXORする
 
-                    let mut val = table.hand[p_idx][k_idx];
+                    let mut val = table.hand_key(p_idx, k_idx);
                     // 個数を区別するためにカウント分回す
                     val = val.rotate_left(count as u32);
                     hash ^= val;
@@ -122,6 +186,14 @@ impl ZobristHasher {
             }
         }
 
+        // アンパッサン可能な列
+        if let Some(x) = board.en_passant {
+            hash ^= match table.en_passant.get(x) {
+                Some(&key) => key,
+                None => derived_key(2, x, 0, 0),
+            };
+        }
+
         // 手番
         if current_player == PlayerId::Player2 {
             hash ^= table.side_to_move;
@@ -81,7 +81,7 @@ mod tests {
         // Chess pawn moves forward direction. P1 forward is -1?
         // P1 Chess Pawn starts at y=? In mixed game?
         // Logic says: P1 Promo Y is 0.
-        board.place_piece(Position::new(5, 1), c_pawn);
+        board.place_piece(Position::new(5, 1), c_pawn.clone());
 
         let moves = legal_moves(&board, PlayerId::Player1);
         let pawn_moves: Vec<&Move> = moves
@@ -98,6 +98,8 @@ mod tests {
         // Should have 4 moves (Queen, Rook, Bishop, Knight)
         // And NO non-promote move
         assert_eq!(pawn_moves.len(), 4, "Should have 4 promotion options");
+        assert_eq!(c_pawn.promotion_kinds().len(), 4);
+        assert_eq!(c_pawn.promotable_kind(), None);
 
         for m in pawn_moves {
             if let Move::Normal { promote, .. } = m {
@@ -113,4 +115,289 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_chess_en_passant() {
+        use crate::logic::apply_move;
+
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let mut board = Board::new(9, 9);
+        board.place_piece(Position::new(4, 3), Piece::new(PieceKind::C_Pawn, p1));
+        board.place_piece(Position::new(5, 1), Piece::new(PieceKind::C_Pawn, p2));
+        board.place_piece(Position::new(0, 1), Piece::new(PieceKind::C_Pawn, p2));
+        board.place_piece(Position::new(8, 8), Piece::new(PieceKind::C_Rook, p1));
+
+        let double_step = Move::Normal {
+            from: Position::new(5, 1),
+            to: Position::new(5, 3),
+            promote: None,
+        };
+        let board = apply_move(&board, &double_step, p2);
+        assert_eq!(board.en_passant, Some(5));
+
+        let en_passant = Move::Normal {
+            from: Position::new(4, 3),
+            to: Position::new(5, 2),
+            promote: None,
+        };
+        assert!(legal_moves(&board, p1).contains(&en_passant));
+        let captured = apply_move(&board, &en_passant, p1);
+        assert!(captured.get_piece(Position::new(5, 3)).is_none());
+        assert_eq!(
+            captured.get_piece(Position::new(5, 2)).map(|p| p.kind),
+            Some(PieceKind::C_Pawn)
+        );
+        assert_eq!(captured.en_passant, None);
+
+        // Only on the very next move: after a waiting move pair it is gone
+        let wait = Move::Normal {
+            from: Position::new(8, 8),
+            to: Position::new(8, 7),
+            promote: None,
+        };
+        let board = apply_move(&board, &wait, p1);
+        let reply = Move::Normal {
+            from: Position::new(0, 1),
+            to: Position::new(0, 2),
+            promote: None,
+        };
+        let board = apply_move(&board, &reply, p2);
+        assert!(!legal_moves(&board, p1).contains(&en_passant));
+    }
+
+    #[test]
+    fn test_dead_piece_drops() {
+        let mut board = Board::new(9, 9);
+        for player in [PlayerId::Player1, PlayerId::Player2] {
+            for kind in [PieceKind::S_Pawn, PieceKind::S_Lance, PieceKind::S_Knight] {
+                board.add_to_hand(player, kind);
+            }
+        }
+
+        // The two rows at the far end of the board for each player
+        for (player, last_row, second_row) in [(PlayerId::Player1, 0, 1), (PlayerId::Player2, 8, 7)]
+        {
+            let drop_rows = |kind| {
+                let mut rows: Vec<usize> = legal_moves(&board, player)
+                    .into_iter()
+                    .filter_map(|mv| match mv {
+                        Move::Drop { kind: k, to } if k == kind => Some(to.y),
+                        _ => None,
+                    })
+                    .collect();
+                rows.sort_unstable();
+                rows.dedup();
+                rows
+            };
+            for kind in [PieceKind::S_Pawn, PieceKind::S_Lance] {
+                let rows = drop_rows(kind);
+                assert!(!rows.contains(&last_row), "{:?} {:?}", player, kind);
+                assert!(rows.contains(&second_row));
+            }
+            let rows = drop_rows(PieceKind::S_Knight);
+            assert!(!rows.contains(&last_row) && !rows.contains(&second_row));
+            assert_eq!(rows.len(), 7);
+        }
+    }
+
+    #[test]
+    fn test_stalemate_outcome() {
+        use crate::core::PlayerConfig;
+        use crate::logic::rules::no_move_winner;
+
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let mut board = Board::new(8, 8);
+        board.place_piece(Position::new(0, 0), Piece::new(PieceKind::C_King, p2));
+        board.place_piece(Position::new(1, 2), Piece::new(PieceKind::C_Queen, p1));
+        board.place_piece(Position::new(7, 7), Piece::new(PieceKind::C_King, p1));
+        board.set_player_config(p1, PlayerConfig::chess());
+        board.set_player_config(p2, PlayerConfig::chess());
+        assert!(legal_moves(&board, p2).is_empty());
+        assert_eq!(no_move_winner(&board, p2), None);
+
+        // Shogi rules: no legal move loses
+        board.set_player_config(p2, PlayerConfig::shogi());
+        assert_eq!(no_move_winner(&board, p2), Some(p1));
+    }
+
+    #[test]
+    fn test_custom_pieces() {
+        use crate::core::setup::{setup_from_strings, setup_to_strings};
+        use crate::logic::zobrist::ZobristHasher;
+
+        // Wazir (@W, promotes) and Chancellor
+        crate::core::fairy::install_test_pieces();
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let (wazir, chancellor) = (PieceKind::Custom(0), PieceKind::Custom(1));
+        let setup = [
+            ". . k . +@w",
+            ". . . . .",
+            ". . . . .",
+            ". . @W . .",
+            ". . K . .",
+        ];
+        let board = setup_from_strings(&setup, true, true, None, None);
+        assert_eq!(board.get_piece(Position::new(2, 3)).unwrap().kind, wazir);
+        assert_eq!(
+            board.get_piece(Position::new(4, 0)).unwrap().kind,
+            chancellor
+        );
+        assert_eq!(setup_to_strings(&board)[..5], setup);
+
+        // Wazir steps orthogonally and may promote entering the zone
+        let from = Position::new(2, 3);
+        let wazir_moves: Vec<(Position, Option<PieceKind>)> = legal_moves(&board, p1)
+            .into_iter()
+            .filter_map(|mv| match mv {
+                Move::Normal {
+                    from: f,
+                    to,
+                    promote,
+                } if f == from => Some((to, promote)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(wazir_moves.len(), 4);
+        assert!(wazir_moves.contains(&(Position::new(2, 2), Some(chancellor))));
+
+        // The Chancellor's forward jumps point down the board for Player2
+        assert!(legal_moves(&board, p2).contains(&Move::Normal {
+            from: Position::new(4, 0),
+            to: Position::new(3, 2),
+            promote: None,
+        }));
+
+        // Captured, it goes to the hand unpromoted
+        let mut captured = board.clone();
+        captured.place_piece(from, Piece::new(chancellor, p2));
+        let after = crate::logic::apply_move(
+            &captured,
+            &Move::Normal {
+                from: Position::new(2, 4),
+                to: from,
+                promote: None,
+            },
+            p1,
+        );
+        assert_eq!(after.hand[&p1].get(&wazir), Some(&1));
+        assert!(legal_moves(&after, p1).contains(&Move::Drop {
+            kind: wazir,
+            to: Position::new(0, 2),
+        }));
+        assert_ne!(
+            ZobristHasher::compute_hash(&after, p1),
+            ZobristHasher::compute_hash(&captured, p1)
+        );
+    }
+
+    #[test]
+    fn test_uchifuzume() {
+        use crate::core::PlayerConfig;
+
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        let drop_at = |x, y| Move::Drop {
+            kind: PieceKind::S_Pawn,
+            to: Position::new(x, y),
+        };
+
+        // Edge king hemmed in by its own lances; a gold guards the drop square
+        // and the squares beside it
+        let mut board = Board::new(9, 9);
+        board.place_piece(Position::new(8, 8), Piece::new(PieceKind::S_King, p1));
+        board.place_piece(Position::new(4, 0), Piece::new(PieceKind::S_King, p2));
+        board.place_piece(Position::new(3, 0), Piece::new(PieceKind::S_Lance, p2));
+        board.place_piece(Position::new(5, 0), Piece::new(PieceKind::S_Lance, p2));
+        board.place_piece(Position::new(4, 2), Piece::new(PieceKind::S_Gold, p1));
+        board.add_to_hand(p1, PieceKind::S_Pawn);
+        board.add_to_hand(p1, PieceKind::S_Gold);
+
+        let moves = legal_moves(&board, p1);
+        assert!(!moves.contains(&drop_at(4, 1)), "pawn drop mate");
+        // Other drops and pawn drops elsewhere are fine
+        assert!(moves.contains(&Move::Drop {
+            kind: PieceKind::S_Gold,
+            to: Position::new(4, 1),
+        }));
+        assert!(moves.contains(&drop_at(0, 4)));
+
+        // Unguarded, the king takes the pawn: a plain check
+        let mut unguarded = board.clone();
+        unguarded.remove_piece(Position::new(4, 2));
+        assert!(legal_moves(&unguarded, p1).contains(&drop_at(4, 1)));
+
+        // Escape square left open: check, not mate
+        let mut open = board.clone();
+        open.remove_piece(Position::new(5, 0));
+        assert!(legal_moves(&open, p1).contains(&drop_at(4, 1)));
+
+        // Mating with a pawn move (not a drop) is legal
+        let mut pushed = board.clone();
+        pushed.remove_piece(Position::new(4, 2));
+        pushed.place_piece(Position::new(4, 2), Piece::new(PieceKind::S_Pawn, p1));
+        pushed.place_piece(Position::new(3, 2), Piece::new(PieceKind::S_Gold, p1));
+        pushed.place_piece(Position::new(5, 2), Piece::new(PieceKind::S_Gold, p1));
+        let push = Move::Normal {
+            from: Position::new(4, 2),
+            to: Position::new(4, 1),
+            promote: None,
+        };
+        assert!(legal_moves(&pushed, p1).contains(&push));
+        assert!(crate::logic::is_checkmate(
+            &crate::logic::apply_move(&pushed, &push, p1),
+            p2
+        ));
+
+        // Variants may allow it
+        board.set_player_config(
+            p1,
+            PlayerConfig {
+                pawn_drop_mate: true,
+                ..PlayerConfig::shogi()
+            },
+        );
+        assert!(legal_moves(&board, p1).contains(&drop_at(4, 1)));
+    }
+
+    #[test]
+    fn test_piece_attacks_matches_drop_checks() {
+        use crate::logic::{apply_move, is_in_check, piece_attacks};
+
+        // Kings with open lines and a full hand for Player1
+        let mut board = Board::new(9, 9);
+        board.place_piece(
+            Position::new(4, 0),
+            Piece::new(PieceKind::S_King, PlayerId::Player2),
+        );
+        board.place_piece(
+            Position::new(4, 8),
+            Piece::new(PieceKind::S_King, PlayerId::Player1),
+        );
+        board.place_piece(
+            Position::new(4, 3),
+            Piece::new(PieceKind::S_Pawn, PlayerId::Player2),
+        );
+        for kind in [
+            PieceKind::S_Gold,
+            PieceKind::S_Silver,
+            PieceKind::S_Knight,
+            PieceKind::S_Lance,
+            PieceKind::S_Rook,
+            PieceKind::S_Bishop,
+        ] {
+            board.add_to_hand(PlayerId::Player1, kind);
+        }
+
+        let enemy_king = board.find_king(PlayerId::Player2).unwrap();
+        for mv in legal_moves(&board, PlayerId::Player1) {
+            if let Move::Drop { kind, to } = mv {
+                let gives_check = is_in_check(
+                    &apply_move(&board, &mv, PlayerId::Player1),
+                    PlayerId::Player2,
+                );
+                let attacks =
+                    piece_attacks(&board, to, &Piece::new(kind, PlayerId::Player1), enemy_king);
+                assert_eq!(gives_check, attacks, "{:?} at {}", kind, to);
+            }
+        }
+    }
 }
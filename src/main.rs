@@ -1,14 +1,9 @@
-mod core;
-
-mod game;
-mod logic;
-#[cfg(test)]
-mod logic_tests;
-mod ml;
-mod network;
-mod player;
-mod selfplay;
-mod ui;
+// The engine is the `shogi_aho_ai` library; this binary is the command line
+// and the terminal UI on top of it.
+use shogi_aho_ai::{
+    analysis, bench, core, crash, demo, game, logic, ml, network, player, profile, selfplay,
+    selftest, ui, util,
+};
 
 use crate::core::PlayerId;
 use crate::player::{PlayerController, TuiController};
@@ -17,7 +12,16 @@ use std::io::{self};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let args: Vec<String> = std::env::args().collect();
+    crate::crash::install_panic_hook();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // `--lang ja|en` works with every mode
+    let lang = match args.iter().position(|arg| arg == "--lang") {
+        Some(i) if i + 1 < args.len() => Some(args.drain(i..i + 2).nth(1).unwrap_or_default()),
+        Some(_) => anyhow::bail!("--lang needs ja or en"),
+        None => None,
+    };
+    crate::ui::init_lang(lang.as_deref())?;
 
     // Check for CLI arguments first
     if args.len() >= 2 {
@@ -25,27 +29,32 @@ async fn main() -> anyhow::Result<()> {
 
         match mode {
             "server" => {
-                let addr = if args.len() >= 3 {
-                    &args[2]
-                } else {
-                    "127.0.0.1:8080"
-                };
-                crate::network::server::start_server(addr).await?;
-                return Ok(());
+                return run_server_cli(&args[2..]).await;
             }
             "client" => {
-                let addr = if args.len() >= 3 {
-                    &args[2]
-                } else {
-                    "127.0.0.1:8080"
-                };
+                let settings = crate::ui::settings::Settings::user();
+                let mut addr = settings.connect_address;
+                let mut name = settings.player_name;
+                let mut rest = args[2..].iter();
+                while let Some(arg) = rest.next() {
+                    match arg.as_str() {
+                        "--name" => match rest.next() {
+                            Some(value) => name = value.clone(),
+                            None => anyhow::bail!("--name needs a name"),
+                        },
+                        _ => addr = arg.clone(),
+                    }
+                }
                 terminal::enable_raw_mode()?;
                 execute!(io::stdout(), terminal::EnterAlternateScreen)?;
-                let res = run_client(addr).await;
+                let res = run_client(&addr, &name, Default::default(), None).await;
                 execute!(io::stdout(), terminal::LeaveAlternateScreen)?;
                 terminal::disable_raw_mode()?;
                 return res;
             }
+            "p2p" => {
+                return run_p2p(&args[2..]).await;
+            }
             "local" => {
                 terminal::enable_raw_mode()?;
                 execute!(io::stdout(), terminal::EnterAlternateScreen)?;
@@ -54,6 +63,37 @@ async fn main() -> anyhow::Result<()> {
                 terminal::disable_raw_mode()?;
                 return res;
             }
+            "netdebug" => {
+                let addr = if args.len() >= 3 {
+                    &args[2]
+                } else {
+                    "127.0.0.1:8080"
+                };
+                return crate::network::debug::run_netdebug(addr).await;
+            }
+            "watch" => {
+                let addr = args.get(2).map_or("127.0.0.1:8080", String::as_str);
+                let game_id = match args.get(3) {
+                    Some(id) => Some(
+                        id.parse()
+                            .map_err(|_| anyhow::anyhow!("not a game ID: '{}'", id))?,
+                    ),
+                    None => None,
+                };
+                return crate::network::observer::run_watch(addr, game_id).await;
+            }
+            "serve-engine" => {
+                return match args.get(2).map(String::as_str) {
+                    Some("--tcp") => crate::network::engine_server::serve_tcp(
+                        args.get(3).map_or("127.0.0.1:8081", String::as_str),
+                    ),
+                    Some(arg) => {
+                        eprintln!("Usage: {} serve-engine [--tcp <ADDR>]", args[0]);
+                        anyhow::bail!("unknown argument: {}", arg)
+                    }
+                    None => crate::network::engine_server::serve_stdio(),
+                };
+            }
             "replay" => {
                 if args.len() < 3 {
                     eprintln!("Usage: {} replay <kifu_file_path>", args[0]);
@@ -79,6 +119,36 @@ async fn main() -> anyhow::Result<()> {
             "selfplay" => {
                 return run_selfplay_cli(&args[2..]).await;
             }
+            "bench" => {
+                return run_bench_cli(&args[2..]);
+            }
+            "profile" => {
+                return run_profile_cli(&args[2..]);
+            }
+            "selftest" => {
+                return run_selftest_cli();
+            }
+            "tournament" => {
+                return run_tournament_cli(&args[2..]);
+            }
+            "analyze" => {
+                return run_analyze_cli(&args[2..]);
+            }
+            "kifu" => {
+                return run_kifu_cli(&args[2..]);
+            }
+            "games" => {
+                return run_games_cli(&args[2..]);
+            }
+            "train" => {
+                return run_train_cli(&args[2..]);
+            }
+            "arena" => {
+                return run_arena_cli(&args[2..]);
+            }
+            "demo" => {
+                return run_demo_cli(&args[2..]);
+            }
             _ => {} // Fall back to menu if mode is invalid
         }
     }
@@ -101,34 +171,71 @@ async fn run_menu() -> anyhow::Result<()> {
     use crossterm::event::{self, Event, KeyCode};
     use std::time::Duration;
 
-    print!("=== Unified Board Game Engine (Shogi x Chess) ===\r\n");
-
-    print!("\r\nSelect mode:\r\n");
-    print!("1. Local Play\r\n");
-    print!("2. Start Server\r\n");
-    print!("3. Connect to Server\r\n");
-    print!("4. Self-Play (Batch AI vs AI)\r\n");
+    use shogi_aho_ai::i18n::{lang, set_lang, tr};
+
+    // `l` switches the language and draws the menu again
+    let mode = 'menu: loop {
+        execute!(
+            io::stdout(),
+            terminal::Clear(terminal::ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        )?;
+        print!("=== Unified Board Game Engine (Shogi x Chess) ===\r\n");
+
+        print!("\r\n{}\r\n", tr("Select mode:", "モードを選んでください:"));
+        print!("1. {}\r\n", tr("Local Play", "ローカル対局"));
+        print!("2. {}\r\n", tr("Start Server", "サーバーを起動"));
+        print!("3. {}\r\n", tr("Connect to Server", "サーバーに接続"));
+        print!(
+            "4. {}\r\n",
+            tr("Self-Play (Batch AI vs AI)", "自己対局 (AI 同士の連続対局)")
+        );
+        print!(
+            "5. {}\r\n",
+            tr(
+                "Demo (Unattended Replays and AI Games)",
+                "デモ (棋譜の再生と AI 対局を自動で流す)"
+            )
+        );
+        print!("6. {}\r\n", tr("Settings", "設定"));
+        print!("\r\nl. Language: English / 言語: 日本語\r\n");
 
-    let mode = loop {
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('1') => break "local",
-                    KeyCode::Char('2') => break "server",
-                    KeyCode::Char('3') => break "client",
-                    KeyCode::Char('4') => break "selfplay",
-                    KeyCode::Char('q') => return Ok(()),
-                    _ => {}
+        loop {
+            if event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    match key.code {
+                        KeyCode::Char('1') => break 'menu "local",
+                        KeyCode::Char('2') => break 'menu "server",
+                        KeyCode::Char('3') => break 'menu "client",
+                        KeyCode::Char('4') => break 'menu "selfplay",
+                        KeyCode::Char('5') => break 'menu "demo",
+                        KeyCode::Char('6') => {
+                            crate::ui::settings::run_settings().await?;
+                            continue 'menu;
+                        }
+                        KeyCode::Char('l') => {
+                            set_lang(lang().other());
+                            // Kept for the next start; without a home it lasts this run only
+                            crate::ui::settings::Settings::save_lang(lang()).ok();
+                            continue 'menu;
+                        }
+                        KeyCode::Char('q') => return Ok(()),
+                        _ => {}
+                    }
                 }
             }
         }
     };
 
+    let settings = crate::ui::settings::Settings::user();
     match mode {
         "server" => {
             let addr = crate::ui::read_input_raw(
-                "127.0.0.1:8080",
-                "Enter server BIND address (e.g. 0.0.0.0:8080)",
+                &settings.bind_address,
+                tr(
+                    "Enter server BIND address (e.g. 0.0.0.0:8080)",
+                    "サーバーが待ち受けるアドレス (例: 0.0.0.0:8080)",
+                ),
             )
             .await?;
 
@@ -146,7 +253,31 @@ async fn run_menu() -> anyhow::Result<()> {
             }
 
             print!("Starting server on {}...\r\n", addr);
-            if let Err(e) = crate::network::server::start_server(&addr).await {
+            let store = crate::network::store::GameStore::open(std::path::Path::new(
+                crate::network::store::DEFAULT_DB_PATH,
+            ))?;
+            let board = crate::ui::read_input_raw(
+                "StandardMixed",
+                tr(
+                    "Board of quick-match games (ShogiOnly, ChessOnly, Fair, ... or a setup file)",
+                    "クイックマッチの盤面 (ShogiOnly・ChessOnly・Fair など、または盤面ファイル)",
+                ),
+            )
+            .await?;
+            let board = crate::network::server::GameSetup::parse(board.trim())?;
+            let ruleset =
+                crate::ui::read_input_raw("none", tr("Ruleset file", "ルールファイル")).await?;
+            let ruleset = match ruleset.trim() {
+                "none" | "" => None,
+                path => Some(crate::core::Ruleset::load(std::path::Path::new(path))?),
+            };
+            let options = crate::network::server::ServerOptions {
+                store: Some(std::sync::Arc::new(store)),
+                board,
+                ruleset,
+                ..Default::default()
+            };
+            if let Err(e) = crate::network::server::start_server(&addr, options).await {
                 eprintln!("Failed to start server: {}\r\n", e);
                 eprintln!("Try binding to '0.0.0.0:8080' instead.\r\n");
                 std::thread::sleep(std::time::Duration::from_secs(5));
@@ -154,17 +285,412 @@ async fn run_menu() -> anyhow::Result<()> {
             Ok(())
         }
         "client" => {
-            let addr =
-                crate::ui::read_input_raw("127.0.0.1:8080", "Enter server address to connect")
-                    .await?;
-            run_client(&addr).await
+            let addr = crate::ui::read_input_raw(
+                &settings.connect_address,
+                tr(
+                    "Enter server address to connect",
+                    "接続するサーバーのアドレス",
+                ),
+            )
+            .await?;
+            run_client(&addr, &settings.player_name, Default::default(), None).await
         }
         "selfplay" => run_selfplay().await,
+        "demo" => crate::demo::run(&crate::demo::DemoConfig::default()),
         _ => run_local().await,
     }
 }
 
-async fn run_client(addr: &str) -> anyhow::Result<()> {
+async fn run_server_cli(args: &[String]) -> anyhow::Result<()> {
+    let mut addr = "127.0.0.1:8080".to_string();
+    let mut cert: Option<String> = None;
+    let mut key: Option<String> = None;
+    let mut reconnect_grace = crate::network::server::DEFAULT_RECONNECT_GRACE;
+    let mut db = Some(crate::network::store::DEFAULT_DB_PATH.to_string());
+    let mut time_control = None;
+    let mut board = None;
+    let mut ruleset = None;
+    let mut status_interval = Some(crate::network::server::DEFAULT_STATUS_INTERVAL);
+    let mut ai = Some(crate::network::server::AiLimits::default());
+    let mut broadcast_addr = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--cert" => {
+                if i + 1 < args.len() {
+                    cert = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--key" => {
+                if i + 1 < args.len() {
+                    key = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--reconnect-grace" => {
+                if i + 1 < args.len() {
+                    if let Ok(secs) = args[i + 1].parse() {
+                        reconnect_grace = std::time::Duration::from_secs(secs);
+                    }
+                    i += 1;
+                }
+            }
+            "--db" => {
+                if i + 1 < args.len() {
+                    db = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--no-db" => {
+                db = None;
+            }
+            "--board" => {
+                if i + 1 < args.len() {
+                    board = Some(crate::network::server::GameSetup::parse(&args[i + 1])?);
+                    i += 1;
+                }
+            }
+            "--ruleset" => {
+                if i + 1 < args.len() {
+                    let path = std::path::Path::new(&args[i + 1]);
+                    ruleset = Some(crate::core::Ruleset::load(path)?);
+                    i += 1;
+                }
+            }
+            "--status-interval" => {
+                if i + 1 < args.len() {
+                    if let Ok(secs) = args[i + 1].parse::<u64>() {
+                        status_interval = (secs > 0).then(|| std::time::Duration::from_secs(secs));
+                    }
+                    i += 1;
+                }
+            }
+            "--time-control" => {
+                if i + 1 < args.len() {
+                    time_control = Some(args[i + 1].parse()?);
+                    i += 1;
+                }
+            }
+            "--broadcast" => {
+                if i + 1 < args.len() {
+                    broadcast_addr = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--ai-depth" => {
+                if i + 1 < args.len() {
+                    if let Ok(depth) = args[i + 1].parse::<usize>() {
+                        ai = (depth > 0).then(|| crate::network::server::AiLimits {
+                            depth,
+                            ..ai.unwrap_or_default()
+                        });
+                    }
+                    i += 1;
+                }
+            }
+            "--ai-time" => {
+                if i + 1 < args.len() {
+                    if let Ok(secs) = args[i + 1].parse::<f64>() {
+                        if let Some(limits) = ai.as_mut() {
+                            limits.move_time = std::time::Duration::from_secs_f64(secs.max(0.1));
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            "--ai-games" => {
+                if i + 1 < args.len() {
+                    if let Ok(games) = args[i + 1].parse::<usize>() {
+                        if let Some(limits) = ai.as_mut() {
+                            limits.max_games = games;
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Server Mode Usage:");
+                println!("  cargo run --release -- server [ADDR] [OPTIONS]");
+                println!();
+                println!("Options:");
+                println!(
+                    "  --cert <FILE>            PEM certificate chain; serve TLS (tls://, wss://)"
+                );
+                println!("  --key <FILE>             PEM private key of the certificate");
+                println!("  --reconnect-grace <SECS> How long a disconnected player may take to");
+                println!("                           rejoin before forfeiting (default: 60)");
+                println!(
+                    "  --db <FILE>              SQLite file keeping finished games and player"
+                );
+                println!("                           records (default: server_games.sqlite3)");
+                println!("  --no-db                  Do not keep finished games");
+                println!("  --board <TYPE|FILE>      Setup of quick-match games: StandardMixed,");
+                println!("                           ReversedMixed, ShogiOnly, ChessOnly, Fair,");
+                println!("                           ReversedFair, or a file of setup rows");
+                println!("                           (default: StandardMixed)");
+                println!("  --ruleset <FILE>         Rules of every game (ruleset.toml format)");
+                println!(
+                    "  --status-interval <SECS> Log a status line (games, rooms, uptime) every"
+                );
+                println!("                           SECS seconds; 0 disables (default: 60)");
+                println!(
+                    "  --time-control <S+I/B>   Clocks for every game: S seconds per side plus"
+                );
+                println!(
+                    "                           I seconds per move, then B seconds of byoyomi"
+                );
+                println!(
+                    "                           per move, e.g. 300+5 or 600/30 (default: none)"
+                );
+                println!("  --ai-depth <N>           Search depth of the engine clients can play");
+                println!("                           (PlayAi); 0 refuses AI games (default: 4)");
+                println!(
+                    "  --ai-time <SECS>         The engine's time limit per move (default: 5)"
+                );
+                println!(
+                    "  --ai-games <N>           AI games played at once; more are refused (default: 4)"
+                );
+                println!(
+                    "  --broadcast <ADDR>       Also serve HTTP on ADDR for observers: GET /games"
+                );
+                println!(
+                    "                           lists the games being played, GET /games/<ID>"
+                );
+                println!("                           streams one as server-sent events");
+                println!("  --help, -h               Show this help message");
+                return Ok(());
+            }
+            arg if !arg.starts_with("--") => {
+                addr = arg.to_string();
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    let tls = match (cert, key) {
+        (Some(cert), Some(key)) => Some(crate::network::transport::load_tls_acceptor(
+            std::path::Path::new(&cert),
+            std::path::Path::new(&key),
+        )?),
+        (None, None) => None,
+        _ => anyhow::bail!("--cert and --key must be given together"),
+    };
+    let store = match db {
+        Some(db) => Some(std::sync::Arc::new(crate::network::store::GameStore::open(
+            std::path::Path::new(&db),
+        )?)),
+        None => None,
+    };
+    let options = crate::network::server::ServerOptions {
+        tls,
+        reconnect_grace,
+        store,
+        time_control,
+        board: board.unwrap_or(crate::network::server::GameSetup::Named(
+            crate::core::setup::BoardSetupType::StandardMixed,
+        )),
+        ruleset,
+        status_interval,
+        ai,
+        broadcast_addr,
+    };
+    crate::network::server::start_server(&addr, options).await
+}
+
+/// Direct play between two players: the host runs the relay in-process and
+/// plays from it, the other player connects straight to the host
+async fn run_p2p(args: &[String]) -> anyhow::Result<()> {
+    use crate::network::client::LobbyAction;
+    use crate::network::transport::LocalBinding;
+
+    let role = args.first().map(String::as_str).unwrap_or("--help");
+    let mut addr: Option<String> = None;
+    let mut binding = LocalBinding::default();
+    let mut board = None;
+    let mut time_control = None;
+    let mut notice = None;
+    let mut name = crate::ui::settings::Settings::user().player_name;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--bind" => {
+                if i + 1 < args.len() {
+                    binding.addr =
+                        Some(args[i + 1].parse().map_err(|_| {
+                            anyhow::anyhow!("bad --bind address '{}'", args[i + 1])
+                        })?);
+                    i += 1;
+                }
+            }
+            "--reuse-port" => {
+                binding.reuse_port = true;
+            }
+            "--name" => {
+                if i + 1 < args.len() {
+                    name = args[i + 1].clone();
+                    i += 1;
+                }
+            }
+            "--board" => {
+                if i + 1 < args.len() {
+                    board = Some(crate::network::server::GameSetup::parse(&args[i + 1])?);
+                    i += 1;
+                }
+            }
+            "--time-control" => {
+                if i + 1 < args.len() {
+                    time_control = Some(args[i + 1].parse()?);
+                    i += 1;
+                }
+            }
+            arg if !arg.starts_with("--") => {
+                addr = Some(arg.to_string());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    let (connect_to, binding) = match role {
+        "host" => {
+            let addr = addr.unwrap_or_else(|| "0.0.0.0:8080".to_string());
+            if binding.addr.is_some() {
+                anyhow::bail!("p2p host listens on ADDR; --bind is for p2p join");
+            }
+            let listener =
+                crate::network::transport::bind_listener(&addr, binding.reuse_port).await?;
+            let local = listener.local_addr()?;
+            // The relay runs under the terminal UI, so its log stays quiet
+            crate::network::log::set_quiet(true);
+            let options = crate::network::server::ServerOptions {
+                board: board.unwrap_or(crate::network::server::GameSetup::Named(
+                    crate::core::setup::BoardSetupType::StandardMixed,
+                )),
+                time_control,
+                status_interval: None,
+                ai: None,
+                ..Default::default()
+            };
+            tokio::spawn(crate::network::server::serve_on(listener, options));
+            notice = Some(format!(
+                "Hosting on {}; the other player runs: p2p join <this machine's address>:{}",
+                local,
+                local.port()
+            ));
+            // The host plays through its own relay
+            let ip = if local.ip().is_unspecified() {
+                std::net::Ipv4Addr::LOCALHOST.into()
+            } else {
+                local.ip()
+            };
+            let own = std::net::SocketAddr::new(ip, local.port()).to_string();
+            (own, LocalBinding::default())
+        }
+        "join" => {
+            let Some(addr) = addr else {
+                anyhow::bail!("Usage: p2p join <HOST:PORT> [--bind <ADDR>] [--reuse-port]");
+            };
+            if board.is_some() || time_control.is_some() {
+                anyhow::bail!("--board and --time-control are chosen by the host");
+            }
+            (addr, binding)
+        }
+        _ => {
+            println!("P2P Mode Usage:");
+            println!("  cargo run --release -- p2p host [ADDR] [OPTIONS]");
+            println!("  cargo run --release -- p2p join <HOST:PORT> [OPTIONS]");
+            println!();
+            println!("The host plays first and needs no separate server; the other player");
+            println!("connects to it directly (the host's port must be reachable).");
+            println!();
+            println!("Host options:");
+            println!("  ADDR                     Address to listen on (default: 0.0.0.0:8080)");
+            println!("  --board <TYPE|FILE>      Setup of the game (default: StandardMixed)");
+            println!("  --time-control <S+I/B>   Clocks, e.g. 300+5 or 600/30 (default: none)");
+            println!("Join options:");
+            println!("  --bind <ADDR>            Connect from this local address and port, e.g.");
+            println!("                           the one a NAT mapping or port forward expects");
+            println!("Both:");
+            println!("  --name <NAME>            Name shown to the opponent (default: settings)");
+            println!("  --reuse-port             Set SO_REUSEADDR/SO_REUSEPORT so the port can be");
+            println!(
+                "                           shared, e.g. listened on and connected from at once"
+            );
+            return Ok(());
+        }
+    };
+
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+    if let Some(notice) = notice {
+        print!("{}\r\n", notice);
+    }
+    let res = run_client(&connect_to, &name, binding, Some(LobbyAction::QuickMatch)).await;
+    execute!(io::stdout(), terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    res
+}
+
+/// Lists the server's open rooms and asks which to join or what to open
+async fn choose_lobby_action(
+    client: &mut crate::network::client::NetworkClient,
+) -> anyhow::Result<crate::network::client::LobbyAction> {
+    use crate::network::client::LobbyAction;
+    use shogi_aho_ai::i18n::tr;
+
+    let rooms = client.list_rooms().await?;
+    if rooms.is_empty() {
+        print!(
+            "{}\r\n",
+            tr("No open rooms.", "空いている部屋はありません。")
+        );
+    } else {
+        print!("{}\r\n", tr("Open rooms:", "空いている部屋:"));
+        for room in &rooms {
+            print!("  {:>3}  {} ({})\r\n", room.id, room.host, room.board_setup);
+        }
+    }
+    let choice = crate::ui::read_input_raw(
+        "quick",
+        tr(
+            "Room ID to join, a setup name to open a room (e.g. ShogiOnly), 'quick', or 'ai [setup]'",
+            "入る部屋の ID、部屋を開く盤面名 (例: ShogiOnly)、'quick'、または 'ai [盤面名]'",
+        ),
+    )
+    .await?;
+    let choice = choice.trim();
+    let action = if let Ok(id) = choice.parse::<u64>() {
+        LobbyAction::JoinRoom(id)
+    } else if choice == "quick" {
+        LobbyAction::QuickMatch
+    } else if let Some(setup) = choice
+        .strip_prefix("ai")
+        .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+    {
+        let setup = setup.trim();
+        LobbyAction::PlayAi((!setup.is_empty()).then(|| setup.to_string()))
+    } else {
+        LobbyAction::CreateRoom(choice.to_string())
+    };
+    Ok(action)
+}
+
+/// Plays a network game in the terminal UI as `name`. Without `action` the
+/// player picks a room from the server's lobby.
+async fn run_client(
+    addr: &str,
+    name: &str,
+    binding: crate::network::transport::LocalBinding,
+    action: Option<crate::network::client::LobbyAction>,
+) -> anyhow::Result<()> {
     use crate::core::{Board, Move};
     use crate::game::Game;
     use crate::network::client::NetworkClient;
@@ -173,20 +699,50 @@ async fn run_client(addr: &str) -> anyhow::Result<()> {
     use tokio::sync::mpsc as tokio_mpsc;
 
     let sanitized = NetworkClient::sanitize_addr(addr);
-    print!("Connecting to {}... (Original: {})\r\n", sanitized, addr);
+    print!(
+        "{}\r\n",
+        shogi_aho_ai::trf!(
+            "Connecting to {}... (Original: {})",
+            "{} に接続しています... (入力: {})",
+            sanitized,
+            addr
+        )
+    );
 
-    let client_res = NetworkClient::connect(&sanitized).await;
+    let client_res = NetworkClient::connect_from(&sanitized, binding).await;
     let client = match client_res {
         Ok(c) => c,
         Err(e) => {
-            print!("\r\n[!] Connection Failed: {}\r\n", e);
-            print!("    Hint: If using ngrok, try 'ngrok tcp 8080' instead of 'ngrok http'.\r\n");
-            print!("    Wait 5s to return to menu...\r\n");
+            print!(
+                "\r\n[!] {}: {}\r\n",
+                shogi_aho_ai::i18n::tr("Connection Failed", "接続できませんでした"),
+                e
+            );
+            print!(
+                "    Hint: With 'ngrok http 8080', connect to its https:// URL (WebSocket);\r\n"
+            );
+            print!("          with 'ngrok tcp 8080', to the host:port it prints.\r\n");
+            print!(
+                "    {}\r\n",
+                shogi_aho_ai::i18n::tr(
+                    "Wait 5s to return to menu...",
+                    "5 秒後にメニューに戻ります..."
+                )
+            );
             std::thread::sleep(std::time::Duration::from_secs(5));
             return Err(e);
         }
     };
-    print!("Connected!\r\n");
+    print!(
+        "{}\r\n",
+        shogi_aho_ai::i18n::tr("Connected!", "接続しました")
+    );
+
+    let mut client_handle = client.with_name(name);
+    let action = match action {
+        Some(action) => action,
+        None => choose_lobby_action(&mut client_handle).await?,
+    };
 
     let (player_id_tx, player_id_rx) = mpsc::channel::<PlayerId>();
     let (remote_move_tx, remote_move_rx) = mpsc::channel::<Move>();
@@ -194,12 +750,23 @@ async fn run_client(addr: &str) -> anyhow::Result<()> {
 
     // 盤面更新同期用
     let (board_sync_tx, board_sync_rx) = mpsc::channel::<(Board, PlayerId)>();
+    // サーバーの持ち時間（ネットワーク側が更新し、TUI が表示する）
+    let clock = std::sync::Arc::new(std::sync::Mutex::new(
+        crate::player::clock::RemoteClock::default(),
+    ));
+    let net_clock = std::sync::Arc::clone(&clock);
 
-    let mut client_handle = client;
     tokio::spawn(async move {
         // board_tx の代わりに board_sync_tx を渡す
         if let Err(e) = client_handle
-            .run(player_id_tx, board_sync_tx, remote_move_tx, local_move_rx)
+            .run(
+                action,
+                player_id_tx,
+                board_sync_tx,
+                remote_move_tx,
+                local_move_rx,
+                net_clock,
+            )
             .await
         {
             eprintln!("Client networking error: {}", e);
@@ -207,7 +774,10 @@ async fn run_client(addr: &str) -> anyhow::Result<()> {
     });
 
     // Wait for initial data
-    print!("Waiting for opponent...\r\n");
+    print!(
+        "{}\r\n",
+        shogi_aho_ai::i18n::tr("Waiting for opponent...", "対戦相手を待っています...")
+    );
     let my_id = player_id_rx.recv()?;
     // 初期盤面を同期チャネルから受け取る
     let (board, _next_player) = board_sync_rx.recv()?;
@@ -219,55 +789,115 @@ async fn run_client(addr: &str) -> anyhow::Result<()> {
     let p1: Box<dyn PlayerController>;
     let p2: Box<dyn PlayerController>;
 
+    let you = TuiController::new(my_id, "You")
+        .with_remote_clock(std::sync::Arc::clone(&clock))
+        .without_hints();
+    let remote =
+        NetworkController::new(my_id.opponent(), "Remote", remote_move_rx).with_remote_clock(clock);
     if my_id == PlayerId::Player1 {
-        p1 = Box::new(TuiController::new(PlayerId::Player1, "You"));
-        p2 = Box::new(NetworkController::new(
-            PlayerId::Player2,
-            "Remote",
-            remote_move_rx,
-        ));
+        p1 = Box::new(you);
+        p2 = Box::new(remote);
     } else {
-        p1 = Box::new(NetworkController::new(
-            PlayerId::Player1,
-            "Remote",
-            remote_move_rx,
-        ));
-        p2 = Box::new(TuiController::new(PlayerId::Player2, "You"));
+        p1 = Box::new(remote);
+        p2 = Box::new(you);
     }
 
     game.play(p1.as_ref(), p2.as_ref(), |mv| {
-        let _ = local_move_tx.send(mv.clone());
+        let _ = local_move_tx.send(*mv);
     });
 
     Ok(())
 }
 
+/// Menu entries 2, 3 and 6 (a human against the engine)
+fn print_ai_modes() {
+    use shogi_aho_ai::i18n::tr;
+
+    print!(
+        "2. {}\r\n",
+        tr(
+            "Player vs Alpha-Beta AI (Light)",
+            "人 vs Alpha-Beta AI (弱)"
+        )
+    );
+    print!(
+        "3. {}\r\n",
+        tr(
+            "Player vs Alpha-Beta AI (Strong)",
+            "人 vs Alpha-Beta AI (強)"
+        )
+    );
+    print!(
+        "6. {}\r\n",
+        tr(
+            "Player vs Alpha-Beta AI (Adaptive)",
+            "人 vs Alpha-Beta AI (相手に合わせて強さを調整)"
+        )
+    );
+}
+
 async fn run_local() -> anyhow::Result<()> {
     use crate::game::Game;
     use crossterm::event::{self, Event, KeyCode};
     use std::time::Duration;
 
-    print!("\r\nSelect game mode:\r\n");
-    print!("1. Human vs Human (TUI)\r\n");
+    use shogi_aho_ai::i18n::tr;
+
+    let settings = crate::ui::settings::Settings::user();
+    print!(
+        "\r\n{}\r\n",
+        tr("Select game mode:", "対局の種類を選んでください:")
+    );
+    print!("1. {}\r\n", tr("Human vs Human (TUI)", "人 vs 人 (TUI)"));
     print!("\r\n");
-    print!("--- Player vs AI ---\r\n");
-    print!("2. Player vs Alpha-Beta AI (Light)\r\n");
-    print!("3. Player vs Alpha-Beta AI (Strong)\r\n");
+    print!("--- {} ---\r\n", tr("Player vs AI", "人 vs AI"));
+    print_ai_modes();
     print!("\r\n");
     print!("--- AI vs AI ---\r\n");
-    print!("4. Alpha-Beta AI (Strong) vs Alpha-Beta AI (Strong)\r\n");
+    print!(
+        "4. {}\r\n",
+        tr(
+            "Alpha-Beta AI (Strong) vs Alpha-Beta AI (Strong)",
+            "Alpha-Beta AI (強) vs Alpha-Beta AI (強)"
+        )
+    );
     print!("\r\n");
-    print!("5. Replay Game Record (Kifu)\r\n");
+    print!("5. {}\r\n", tr("Replay Game Record (Kifu)", "棋譜の再生"));
+    print!(
+        "7. {}\r\n",
+        tr(
+            "Hot-seat Blitz (you play both sides against the clock)",
+            "早指し (持ち時間つきで両方の手を自分で指す)"
+        )
+    );
+    print!(
+        "8. {}\r\n",
+        tr(
+            "Board Editor (set up a position to play, analyze or export)",
+            "盤面エディタ (局面を作って対局・検討・書き出し)"
+        )
+    );
+    print!(
+        "\r\n{}\r\n",
+        tr(
+            "[Enter]: Player vs the AI strength in the settings",
+            "[Enter]: 設定の強さの AI と対局"
+        )
+    );
 
     let p_choice = loop {
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
                 match key.code {
+                    KeyCode::Enter => break settings.ai_strength.menu_choice(),
                     KeyCode::Char('1') => break "1",
                     KeyCode::Char('2') => break "2",
                     KeyCode::Char('3') => break "3",
                     KeyCode::Char('4') => break "4",
                     KeyCode::Char('5') => break "5",
+                    KeyCode::Char('6') => break "6",
+                    KeyCode::Char('7') => break "7",
+                    KeyCode::Char('8') => break "8",
                     KeyCode::Char('q') => return Ok(()),
                     _ => {}
                 }
@@ -275,6 +905,53 @@ async fn run_local() -> anyhow::Result<()> {
         }
     };
 
+    // The edited position replaces the setup chosen below
+    let mut edited = None;
+    let p_choice = if p_choice == "8" {
+        let (board, setup_name, _, _) = crate::ui::selection::select_board_setup()?;
+        let editor = crate::ui::editor::BoardEditor::new(board, setup_name);
+        let Some(position) = editor.run()? else {
+            return Ok(());
+        };
+        edited = Some(position);
+
+        execute!(
+            std::io::stdout(),
+            terminal::Clear(terminal::ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        )?;
+        print!(
+            "{}\r\n",
+            tr("Play the position as:", "この局面から指す対局の種類:")
+        );
+        print!("1. {}\r\n", tr("Human vs Human (TUI)", "人 vs 人 (TUI)"));
+        print_ai_modes();
+        print!(
+            "4. {}\r\n",
+            tr(
+                "Alpha-Beta AI (Strong) vs Alpha-Beta AI (Strong)",
+                "Alpha-Beta AI (強) vs Alpha-Beta AI (強)"
+            )
+        );
+        loop {
+            if event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    match key.code {
+                        KeyCode::Char('1') => break "1",
+                        KeyCode::Char('2') => break "2",
+                        KeyCode::Char('3') => break "3",
+                        KeyCode::Char('4') => break "4",
+                        KeyCode::Char('6') => break "6",
+                        KeyCode::Char('q') => return Ok(()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    } else {
+        p_choice
+    };
+
     if p_choice == "5" {
         // Replay - Use new scrollable selector
         use crate::ui::kifu_selector::KifuSelector;
@@ -289,57 +966,95 @@ async fn run_local() -> anyhow::Result<()> {
 
         let mut selector = KifuSelector::scan_directories(&dirs)?;
 
-        loop {
-            match selector.run()? {
-                Some(selected_path) => {
-                    // User selected a file
-                    let mut viewer =
-                        crate::game::replay::ReplayViewer::from_kifu_path(&selected_path)?;
-                    viewer.run()?;
-                    // After replay, return to file selection
-                }
-                None => {
-                    // User quit
-                    break;
-                }
-            }
+        // Loop until the user quits the selector
+        while let Some(selected_path) = selector.run()? {
+            let mut viewer = crate::game::replay::ReplayViewer::from_kifu_path(&selected_path)?;
+            viewer.run()?;
+            // After replay, return to file selection
         }
 
         return Ok(());
     }
 
+    let evaluator_type = settings.evaluator_type();
     let mut model_path = None;
-    if p_choice == "2" || p_choice == "3" || p_choice == "4" {
-        use crate::player::ai::config::AIConfig;
-        let config = AIConfig::get();
-        if config.evaluation.evaluator_type == "NeuralNetwork" {
-            model_path = crate::ui::selection::select_model()?;
-        }
+    if matches!(p_choice, "2" | "3" | "4" | "6")
+        && matches!(evaluator_type.as_str(), "NeuralNetwork" | "NNUE")
+    {
+        model_path = crate::ui::selection::select_model(&evaluator_type)?;
     }
 
-    let (p1, p2, perspective) =
-        crate::ui::selection::create_player_controllers(p_choice, model_path.clone())?;
+    let (p1, p2, perspective) = if p_choice == "7" {
+        use crate::player::clock::BlitzConfig;
+        let defaults = BlitzConfig::default();
+        let per_move = crate::ui::read_input_raw(
+            &defaults.per_move.as_secs().to_string(),
+            tr("\r\nSeconds per move", "\r\n1 手の秒数"),
+        )
+        .await?;
+        let bank = crate::ui::read_input_raw(
+            &defaults.bank.as_secs().to_string(),
+            tr(
+                "Seconds per side for the whole game",
+                "1 局を通した各側の持ち時間 (秒)",
+            ),
+        )
+        .await?;
+        let secs = |input: String, default: Duration| {
+            input
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&s| s > 0)
+                .map_or(default, Duration::from_secs)
+        };
+        crate::ui::selection::create_blitz_controllers(BlitzConfig {
+            per_move: secs(per_move, defaults.per_move),
+            bank: secs(bank, defaults.bank),
+        })
+    } else {
+        crate::ui::selection::create_player_controllers(
+            p_choice,
+            &evaluator_type,
+            model_path.clone(),
+        )?
+    };
 
     // Display evaluator if AI is involved
-    if p_choice == "2" || p_choice == "3" || p_choice == "4" {
+    if matches!(p_choice, "2" | "3" | "4" | "6") {
         use crate::core::PlayerId;
         use crate::player::ai::alpha_beta::AlphaBetaAI;
-        let temp_ai = AlphaBetaAI::new(
+        let temp_ai = AlphaBetaAI::with_evaluator_type(
             PlayerId::Player1,
             "Display",
             crate::player::ai::alpha_beta::AIStrength::Strong,
+            &evaluator_type,
             model_path,
             true,
         );
-        print!("\r\n[AI Configuration]\r\n");
-        print!("Evaluator: {}\r\n\r\n", temp_ai.evaluator_name());
+        print!("\r\n{}\r\n", tr("[AI Configuration]", "[AI の設定]"));
+        print!(
+            "{}: {}\r\n\r\n",
+            tr("Evaluator", "評価関数"),
+            temp_ai.evaluator_name()
+        );
         std::thread::sleep(std::time::Duration::from_millis(500)); // Brief pause to show message
     }
 
-    let (board, setup_name) = crate::ui::selection::select_board_setup()?;
-
-    let mut game = Game::with_setup(board, setup_name);
-    game.perspective_mode = perspective;
+    let mut game = match edited {
+        Some(position) => {
+            Game::from_position(position.board, position.setup_name, position.side_to_move)
+        }
+        None => {
+            let (board, setup_name, chess960_seed, handicap) =
+                crate::ui::selection::select_board_setup()?;
+            let mut game = Game::with_setup(board, setup_name);
+            game.chess960_seed = chess960_seed;
+            game.handicap = handicap;
+            game
+        }
+    };
+    game.perspective_mode = settings.perspective.map_or(perspective, |p| p.mode());
     game.play(p1.as_ref(), p2.as_ref(), |_| {});
 
     Ok(())
@@ -356,6 +1071,13 @@ async fn run_selfplay() -> anyhow::Result<()> {
         crossterm::cursor::MoveTo(0, 0)
     )?;
 
+    let settings = crate::ui::settings::Settings::user();
+    // 設定の AI の強さを既定の選択にする
+    let default_strength = match settings.ai_strength.strength() {
+        crate::player::ai::AIStrength::Light => '1',
+        crate::player::ai::AIStrength::Strong => '2',
+    };
+
     print!("=== Self-Play Configuration ===\r\n\r\n");
 
     // Number of games
@@ -373,12 +1095,10 @@ async fn run_selfplay() -> anyhow::Result<()> {
                         print!("{}", c);
                         std::io::Write::flush(&mut std::io::stdout())?;
                     }
-                    KeyCode::Backspace => {
-                        if !num_input.is_empty() {
-                            num_input.pop();
-                            print!("\u{0008} \u{0008}");
-                            std::io::Write::flush(&mut std::io::stdout())?;
-                        }
+                    KeyCode::Backspace if !num_input.is_empty() => {
+                        num_input.pop();
+                        print!("\u{0008} \u{0008}");
+                        std::io::Write::flush(&mut std::io::stdout())?;
                     }
                     _ => {}
                 }
@@ -395,17 +1115,22 @@ async fn run_selfplay() -> anyhow::Result<()> {
     print!("\r\nPlayer 1 AI Strength:\r\n");
     print!("1. Light (Depth 4, 1s)\r\n");
     print!("2. Strong (Depth 6, 3s)\r\n");
-    print!("Select (default: 2): ");
+    print!("Select (default: {}): ", default_strength);
 
     let ai1_strength = loop {
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('1') => {
+                let choice = match key.code {
+                    KeyCode::Enter => default_strength,
+                    KeyCode::Char(c) => c,
+                    _ => continue,
+                };
+                match choice {
+                    '1' => {
                         print!("1\r\n");
                         break crate::player::ai::AIStrength::Light;
                     }
-                    KeyCode::Char('2') | KeyCode::Enter => {
+                    '2' => {
                         print!("2\r\n");
                         break crate::player::ai::AIStrength::Strong;
                     }
@@ -419,17 +1144,22 @@ async fn run_selfplay() -> anyhow::Result<()> {
     print!("\r\nPlayer 2 AI Strength:\r\n");
     print!("1. Light (Depth 4, 1s)\r\n");
     print!("2. Strong (Depth 6, 3s)\r\n");
-    print!("Select (default: 2): ");
+    print!("Select (default: {}): ", default_strength);
 
     let ai2_strength = loop {
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('1') => {
+                let choice = match key.code {
+                    KeyCode::Enter => default_strength,
+                    KeyCode::Char(c) => c,
+                    _ => continue,
+                };
+                match choice {
+                    '1' => {
                         print!("1\r\n");
                         break crate::player::ai::AIStrength::Light;
                     }
-                    KeyCode::Char('2') | KeyCode::Enter => {
+                    '2' => {
                         print!("2\r\n");
                         break crate::player::ai::AIStrength::Strong;
                     }
@@ -508,13 +1238,10 @@ async fn run_selfplay() -> anyhow::Result<()> {
             }
         }
     };
+    let evaluator_type = settings.evaluator_type();
     let mut model_path = None;
-    {
-        use crate::player::ai::config::AIConfig;
-        let config = AIConfig::get();
-        if config.evaluation.evaluator_type == "NeuralNetwork" {
-            model_path = crate::ui::selection::select_model()?;
-        }
+    if matches!(evaluator_type.as_str(), "NeuralNetwork" | "NNUE") {
+        model_path = crate::ui::selection::select_model(&evaluator_type)?;
     }
 
     print!("\r\n\r\nRunning {} games...\r\n\r\n", num_games);
@@ -528,7 +1255,22 @@ async fn run_selfplay() -> anyhow::Result<()> {
         use_parallel, // Use the selected mode
         save_kifus: true,
         update_interval_moves: 1,
-        model_path: model_path.clone(),
+        ai1_evaluator: settings.evaluator.clone(),
+        ai2_evaluator: settings.evaluator.clone(),
+        ai1_model_path: model_path.clone(),
+        ai2_model_path: model_path.clone(),
+        random_opening_plies: 0,
+        chess960: false,
+        handicap: None,
+        move_sampling: None,
+        sprt: None,
+        eval_sample_interval: 1,
+        export_dataset: false,
+        mirror_dataset: false,
+        curriculum: None,
+        snapshot_interval: 0,
+        seed: None,
+        resume_run_id: None,
     };
 
     // Display configuration before starting
@@ -587,8 +1329,7 @@ async fn run_selfplay() -> anyhow::Result<()> {
         results_dir,
         chrono::Local::now().format("%Y%m%d_%H%M%S")
     );
-    let file = std::fs::File::create(&results_file)?;
-    serde_json::to_writer_pretty(file, &stats)?;
+    util::atomic_write_json(std::path::Path::new(&results_file), &stats, true)?;
 
     if let Ok(abs_path) = std::fs::canonicalize(&results_file) {
         print!("\r\nResults saved to {}\r\n", abs_path.display());
@@ -616,6 +1357,25 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
     let mut ai2_strength = crate::player::ai::AIStrength::Strong;
     let mut use_parallel = true;
     let mut num_threads: Option<usize> = None;
+    let mut random_opening_plies = 0;
+    let mut chess960 = false;
+    let mut handicap: Option<crate::core::handicap::Handicap> = None;
+    let mut sprt_bounds: Option<(f64, f64)> = None;
+    let mut sprt_alpha = 0.05;
+    let mut sprt_beta = 0.05;
+    let mut eval_sample_interval = 1;
+    let mut resume_run_id: Option<String> = None;
+    let mut export_dataset = false;
+    let mut mirror_dataset = false;
+    let mut curriculum: Option<crate::selfplay::curriculum::CurriculumConfig> = None;
+    let mut snapshot_interval = 0;
+    let mut seed: Option<u64> = None;
+    let mut temperature_plies = 0;
+    let mut temperature = 100.0;
+    let mut ai1_evaluator: Option<String> = None;
+    let mut ai2_evaluator: Option<String> = None;
+    let mut ai1_model_path: Option<String> = None;
+    let mut ai2_model_path: Option<String> = None;
 
     let mut i = 0;
     while i < args.len() {
@@ -628,18 +1388,11 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
             }
             "--board" => {
                 if i + 1 < args.len() {
-                    board_setup = match args[i + 1].as_str() {
-                        "StandardMixed" => crate::selfplay::BoardSetupType::StandardMixed,
-                        "ReversedMixed" => crate::selfplay::BoardSetupType::ReversedMixed,
-                        "ShogiOnly" => crate::selfplay::BoardSetupType::ShogiOnly,
-                        "ChessOnly" => crate::selfplay::BoardSetupType::ChessOnly,
-                        "Fair" => crate::selfplay::BoardSetupType::Fair,
-                        "ReversedFair" => crate::selfplay::BoardSetupType::ReversedFair,
-                        _ => {
+                    board_setup = crate::selfplay::BoardSetupType::from_name(&args[i + 1])
+                        .unwrap_or_else(|| {
                             eprintln!("Unknown board type: {}, using ShogiOnly", args[i + 1]);
                             crate::selfplay::BoardSetupType::ShogiOnly
-                        }
-                    };
+                        });
                     i += 1;
                 }
             }
@@ -669,6 +1422,38 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
                     i += 1;
                 }
             }
+            "--ai1-evaluator" | "--ai2-evaluator" => {
+                if i + 1 < args.len() {
+                    let evaluator = match args[i + 1].as_str() {
+                        "Handcrafted" | "NeuralNetwork" | "NNUE" => Some(args[i + 1].clone()),
+                        _ => {
+                            eprintln!(
+                                "Unknown evaluator: {}, using the one in ai_config.json",
+                                args[i + 1]
+                            );
+                            None
+                        }
+                    };
+                    if args[i] == "--ai1-evaluator" {
+                        ai1_evaluator = evaluator;
+                    } else {
+                        ai2_evaluator = evaluator;
+                    }
+                    i += 1;
+                }
+            }
+            "--ai1-model" => {
+                if i + 1 < args.len() {
+                    ai1_model_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--ai2-model" => {
+                if i + 1 < args.len() {
+                    ai2_model_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
             "--parallel" => {
                 use_parallel = true;
                 if i + 1 < args.len() {
@@ -681,6 +1466,114 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
             "--sequential" => {
                 use_parallel = false;
             }
+            "--random-opening-plies" => {
+                if i + 1 < args.len() {
+                    random_opening_plies = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--chess960" => {
+                chess960 = true;
+            }
+            "--handicap" => {
+                if i + 1 < args.len() {
+                    use crate::core::handicap::Handicap;
+                    let name = &args[i + 1];
+                    handicap = Some(Handicap::from_name(name).ok_or_else(|| {
+                        let valid: Vec<&str> = Handicap::ALL.iter().map(|h| h.name()).collect();
+                        anyhow::anyhow!("unknown handicap '{}' (valid: {})", name, valid.join(", "))
+                    })?);
+                    i += 1;
+                }
+            }
+            "--temperature-plies" => {
+                if i + 1 < args.len() {
+                    temperature_plies = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--temperature" => {
+                if i + 1 < args.len() {
+                    temperature = args[i + 1].parse().unwrap_or(100.0);
+                    i += 1;
+                }
+            }
+            "--sprt" => {
+                if i + 2 < args.len() {
+                    match (args[i + 1].parse::<f64>(), args[i + 2].parse::<f64>()) {
+                        (Ok(elo0), Ok(elo1)) if elo0 < elo1 => sprt_bounds = Some((elo0, elo1)),
+                        _ => eprintln!(
+                            "Invalid SPRT bounds: {} {} (expected <elo0> <elo1> with elo0 < elo1)",
+                            args[i + 1],
+                            args[i + 2]
+                        ),
+                    }
+                    i += 2;
+                }
+            }
+            "--sprt-alpha" => {
+                if i + 1 < args.len() {
+                    sprt_alpha = args[i + 1].parse().unwrap_or(0.05);
+                    i += 1;
+                }
+            }
+            "--sprt-beta" => {
+                if i + 1 < args.len() {
+                    sprt_beta = args[i + 1].parse().unwrap_or(0.05);
+                    i += 1;
+                }
+            }
+            "--resume" => {
+                if i + 1 < args.len() {
+                    resume_run_id = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--export-dataset" => {
+                export_dataset = true;
+            }
+            "--mirror" => {
+                export_dataset = true;
+                mirror_dataset = true;
+            }
+            "--curriculum" => {
+                export_dataset = true;
+                curriculum.get_or_insert_with(Default::default);
+            }
+            "--imbalance-threshold" => {
+                if i + 1 < args.len() {
+                    let config = curriculum.get_or_insert_with(Default::default);
+                    config.imbalance_threshold = args[i + 1]
+                        .parse()
+                        .unwrap_or(crate::selfplay::curriculum::DEFAULT_IMBALANCE_THRESHOLD);
+                    export_dataset = true;
+                    i += 1;
+                }
+            }
+            "--seed" => {
+                if i + 1 < args.len() {
+                    seed = args[i + 1].parse().ok();
+                    if seed.is_none() {
+                        eprintln!(
+                            "Invalid seed: {} (expected an unsigned integer)",
+                            args[i + 1]
+                        );
+                    }
+                    i += 1;
+                }
+            }
+            "--snapshot-interval" => {
+                if i + 1 < args.len() {
+                    snapshot_interval = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--eval-sample-interval" => {
+                if i + 1 < args.len() {
+                    eval_sample_interval = args[i + 1].parse().unwrap_or(1);
+                    i += 1;
+                }
+            }
             "--help" | "-h" => {
                 println!("Self-Play Mode Usage:");
                 println!("  cargo run --release -- selfplay [OPTIONS]");
@@ -690,8 +1583,37 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
                 println!("  --board <TYPE>           Board type: StandardMixed, ReversedMixed, ShogiOnly, ChessOnly, Fair, ReversedFair (default: ShogiOnly)");
                 println!("  --ai1-strength <STR>     Player 1 AI strength: Light, Strong (default: Strong)");
                 println!("  --ai2-strength <STR>     Player 2 AI strength: Light, Strong (default: Strong)");
+                println!("  --ai1-evaluator <EVAL>   Player 1 evaluator: Handcrafted, NeuralNetwork, NNUE (default: ai_config.json)");
+                println!("  --ai2-evaluator <EVAL>   Player 2 evaluator: Handcrafted, NeuralNetwork, NNUE (default: ai_config.json)");
+                println!(
+                    "  --ai1-model <PATH>       Player 1 ONNX model (default: ai_config.json)"
+                );
+                println!(
+                    "  --ai2-model <PATH>       Player 2 ONNX model (default: ai_config.json)"
+                );
                 println!("  --parallel [N]           Enable parallel execution with optional thread count");
                 println!("  --sequential             Enable sequential execution");
+                println!("  --random-opening-plies <N>  Play N random plies before the AIs take over (default: 0)");
+                println!("  --chess960               Shuffle the chess back ranks of each game (seed stored in the kifu)");
+                println!("  --handicap <NAME>        Player1 gives a handicap: lance, bishop, rook, 2-piece, 4-piece,");
+                println!(
+                    "                           pawn-odds, knight-odds, rook-odds, queen-odds"
+                );
+                println!("  --temperature-plies <N>  Sample among the top moves for the first N plies (default: 0)");
+                println!(
+                    "  --temperature <T>        Sampling temperature in centipawns (default: 100)"
+                );
+                println!("  --sprt <ELO0> <ELO1>     Stop early once an SPRT on AI1 - AI2 decides (--num-games is the maximum)");
+                println!("  --sprt-alpha <P>         SPRT false positive rate (default: 0.05)");
+                println!("  --sprt-beta <P>          SPRT false negative rate (default: 0.05)");
+                println!("  --eval-sample-interval <N>  Keep every N-th eval in saved trajectories, 0 disables (default: 1)");
+                println!("  --export-dataset         Write features, search scores, moves and outcomes to dataset.bin in the run directory");
+                println!("  --mirror                 Also export each position reflected left-right, doubling the dataset (implies --export-dataset)");
+                println!("  --curriculum             Also split the dataset into opening/midgame/endgame x balanced/imbalanced shards (implies --export-dataset)");
+                println!("  --imbalance-threshold <CP>  Material difference for an imbalanced position (default: 300, implies --curriculum)");
+                println!("  --seed <N>               Seed the random opening and move sampling to reproduce a run");
+                println!("  --snapshot-interval <N>  Store the full board in each kifu every N plies (default: 0, disabled)");
+                println!("  --resume <RUN_ID>        Continue an interrupted run (its settings are reused)");
                 println!("  --help, -h               Show this help message");
                 println!();
                 println!("Examples:");
@@ -699,6 +1621,9 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
                     "  cargo run --release -- selfplay --num-games 100 --board Fair --parallel 6"
                 );
                 println!("  cargo run --release -- selfplay --num-games 10 --board ShogiOnly --sequential");
+                println!("  cargo run --release -- selfplay --num-games 2000 --ai2-strength Light --sprt 0 10 --random-opening-plies 4");
+                println!("  cargo run --release -- selfplay --resume 20260108_124038 --parallel 6");
+                println!("  cargo run --release --features ml -- selfplay --ai1-evaluator NeuralNetwork --ai1-model models/Fair/v0.3.0/model.onnx --ai2-evaluator Handcrafted --sprt 0 10");
                 return Ok(());
             }
             _ => {
@@ -716,27 +1641,85 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
             .ok();
     }
 
-    let mut model_path = None;
     {
         use crate::player::ai::config::AIConfig;
         let config = AIConfig::get();
-        if config.evaluation.evaluator_type == "NeuralNetwork" {
-            // For CLI mode, try to auto-detect model or skip
-            // Since we can't use interactive UI here
-            println!("Note: NeuralNetwork evaluator detected in config.");
-            println!("To use a specific model, please set it in ai_config.json");
+        let uses_nn = [&ai1_evaluator, &ai2_evaluator].iter().any(|e| {
+            e.as_deref()
+                .unwrap_or(config.evaluation.evaluator_type.as_str())
+                == "NeuralNetwork"
+        });
+        if uses_nn {
+            if crate::ml::ML_ENABLED {
+                // For CLI mode, try to auto-detect model or skip
+                // Since we can't use interactive UI here
+                println!("Note: NeuralNetwork evaluator in use.");
+                println!("Models come from --ai1-model/--ai2-model or ai_config.json");
+            } else {
+                println!("Note: {}", crate::ml::ML_UNAVAILABLE_MESSAGE);
+            }
         }
     }
 
+    // A resumed run keeps its original settings; only the execution mode comes from the flags
+    let config = if let Some(ref run_id) = resume_run_id {
+        let dir = crate::selfplay::run_log::find_run_dir(run_id)?;
+        crate::selfplay::run_log::read_manifest(&dir)?.to_config(use_parallel)?
+    } else {
+        crate::selfplay::SelfPlayConfig {
+            num_games,
+            board_setup,
+            ai1_strength,
+            ai2_strength,
+            use_parallel,
+            save_kifus: true,
+            update_interval_moves: 1,
+            ai1_evaluator,
+            ai2_evaluator,
+            ai1_model_path,
+            ai2_model_path,
+            random_opening_plies,
+            chess960,
+            handicap,
+            move_sampling: (temperature_plies > 0).then_some(crate::selfplay::MoveSampling {
+                plies: temperature_plies,
+                temperature,
+            }),
+            sprt: sprt_bounds.map(|(elo0, elo1)| crate::selfplay::elo::SprtConfig {
+                elo0,
+                elo1,
+                alpha: sprt_alpha,
+                beta: sprt_beta,
+            }),
+            eval_sample_interval,
+            export_dataset,
+            mirror_dataset,
+            curriculum,
+            snapshot_interval,
+            seed,
+            resume_run_id: None,
+        }
+    };
+    // Fail before the run starts if Player1 lacks the handicap pieces
+    config
+        .board_setup
+        .create_board_with(None, config.handicap)?;
+
     println!();
     println!("=== Self-Play Configuration ===");
-    println!("Games: {}", num_games);
-    println!("Board: {:?}", board_setup);
-    println!("AI1 Strength: {:?}", ai1_strength);
-    println!("AI2 Strength: {:?}", ai2_strength);
+    if let Some(ref run_id) = config.resume_run_id {
+        println!("Resuming Run: {}", run_id);
+    }
+    println!("Games: {}", config.num_games);
+    println!("Board: {:?}", config.board_setup);
+    if let Some(handicap) = config.handicap {
+        println!("Handicap: {}", handicap.label());
+    }
+    println!("AI1 Strength: {:?}", config.ai1_strength);
+    println!("AI2 Strength: {:?}", config.ai2_strength);
     println!(
         "Mode: {}",
-        if use_parallel {
+        if config.use_parallel {
             "Parallel"
         } else {
             "Sequential"
@@ -745,32 +1728,30 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
     if let Some(n) = num_threads {
         println!("Threads: {}", n);
     }
+    if config.random_opening_plies > 0 {
+        println!("Random Opening: {} plies", config.random_opening_plies);
+    }
+    if let Some(sampling) = config.move_sampling {
+        println!(
+            "Move Sampling: first {} plies, temperature {}",
+            sampling.plies, sampling.temperature
+        );
+    }
+    if let Some(seed) = config.seed {
+        println!("Seed: {}", seed);
+    }
+    if let Some(sprt) = config.sprt {
+        println!(
+            "SPRT: elo0={} elo1={} alpha={} beta={}",
+            sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta
+        );
+    }
     println!("==============================");
     println!();
 
-    // Run self-play
-    let config = crate::selfplay::SelfPlayConfig {
-        num_games,
-        board_setup,
-        ai1_strength,
-        ai2_strength,
-        use_parallel,
-        save_kifus: true,
-        update_interval_moves: 1,
-        model_path: model_path.clone(),
-    };
-
     // Display evaluator
-    use crate::core::PlayerId;
-    use crate::player::ai::alpha_beta::AlphaBetaAI;
-    let temp_ai = AlphaBetaAI::new(
-        PlayerId::Player1,
-        "Display",
-        config.ai1_strength,
-        model_path,
-        true,
-    );
-    println!("Evaluator: {}", temp_ai.evaluator_name());
+    let evaluator_label = config.evaluator_label();
+    println!("Evaluator: {}", evaluator_label);
     println!();
 
     let stats = crate::selfplay::run_selfplay(config)?;
@@ -778,7 +1759,7 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
     // Display results
     println!();
     println!("=== Self-Play Results ===");
-    println!("Evaluator: {}", temp_ai.evaluator_name());
+    println!("Evaluator: {}", evaluator_label);
     println!("Total Games: {}", stats.total_games);
     println!(
         "Player 1 Wins: {} ({:.1}%)",
@@ -807,8 +1788,7 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
         results_dir,
         chrono::Local::now().format("%Y%m%d_%H%M%S")
     );
-    let file = std::fs::File::create(&results_file)?;
-    serde_json::to_writer_pretty(file, &stats)?;
+    util::atomic_write_json(std::path::Path::new(&results_file), &stats, true)?;
 
     if let Ok(abs_path) = std::fs::canonicalize(&results_file) {
         println!();
@@ -821,8 +1801,1381 @@ async fn run_selfplay_cli(args: &[String]) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn run_replay_file(kifu_path: &std::path::Path) -> anyhow::Result<()> {
-    let mut viewer = crate::game::replay::ReplayViewer::from_kifu_path(kifu_path)?;
-    viewer.run()?;
+fn run_tournament_cli(args: &[String]) -> anyhow::Result<()> {
+    use crate::selfplay::tournament::{EngineSpec, TournamentConfig, TournamentFormat};
+    use crate::selfplay::BoardSetupType;
+
+    let mut engines: Vec<EngineSpec> = Vec::new();
+    let mut format = TournamentFormat::RoundRobin;
+    let mut board_setups = vec![BoardSetupType::ShogiOnly];
+    let mut games_per_pairing = 10;
+    let mut use_parallel = false;
+    let mut num_threads: Option<usize> = None;
+    let mut random_opening_plies = 0;
+    let mut swiss_rounds: Option<usize> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--engines" => {
+                if i + 1 < args.len() {
+                    let content = std::fs::read_to_string(&args[i + 1])?;
+                    let specs: Vec<EngineSpec> = serde_json::from_str(&content)?;
+                    engines.extend(specs);
+                    i += 1;
+                }
+            }
+            "--engine" => {
+                if i + 1 < args.len() {
+                    engines.push(EngineSpec::parse(&args[i + 1])?);
+                    i += 1;
+                }
+            }
+            "--format" => {
+                if i + 1 < args.len() {
+                    format = match args[i + 1].as_str() {
+                        "round-robin" => TournamentFormat::RoundRobin,
+                        "gauntlet" => TournamentFormat::Gauntlet,
+                        "swiss" => TournamentFormat::Swiss { rounds: 0 },
+                        "knockout" | "single-elimination" => TournamentFormat::SingleElimination,
+                        "double-elimination" => TournamentFormat::DoubleElimination,
+                        _ => {
+                            eprintln!("Unknown format: {}, using round-robin", args[i + 1]);
+                            TournamentFormat::RoundRobin
+                        }
+                    };
+                    i += 1;
+                }
+            }
+            "--boards" => {
+                if i + 1 < args.len() {
+                    board_setups = args[i + 1]
+                        .split(',')
+                        .filter_map(|name| {
+                            let setup = BoardSetupType::from_name(name);
+                            if setup.is_none() {
+                                eprintln!("Unknown board type: {}, skipping", name);
+                            }
+                            setup
+                        })
+                        .collect();
+                    i += 1;
+                }
+            }
+            "--rounds" => {
+                if i + 1 < args.len() {
+                    swiss_rounds = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--games" => {
+                if i + 1 < args.len() {
+                    games_per_pairing = args[i + 1].parse().unwrap_or(10);
+                    i += 1;
+                }
+            }
+            "--parallel" => {
+                use_parallel = true;
+                if i + 1 < args.len() {
+                    if let Ok(n) = args[i + 1].parse::<usize>() {
+                        num_threads = Some(n);
+                        i += 1;
+                    }
+                }
+            }
+            "--random-opening-plies" => {
+                if i + 1 < args.len() {
+                    random_opening_plies = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Tournament Mode Usage:");
+                println!("  cargo run --release -- tournament [OPTIONS]");
+                println!();
+                println!("Options:");
+                println!("  --engines <FILE>         JSON list of engines: [{{\"name\", \"strength\", \"evaluator\", \"model_path\"}}]");
+                println!("  --engine <SPEC>          Add an engine as name:strength[:evaluator[:model_path]] (repeatable)");
+                println!("  --format <FMT>           round-robin, gauntlet (first engine vs the rest), swiss,");
+                println!("                           knockout or double-elimination (default: round-robin)");
+                println!("  --rounds <N>             Swiss rounds (default: ceil(log2(engines)))");
+                println!(
+                    "  --boards <LIST>          Comma-separated board types (default: ShogiOnly)"
+                );
+                println!("  --games <N>              Games per pairing and board, colors alternate (default: 10)");
+                println!(
+                    "  --parallel [N]           Play games in parallel with optional thread count"
+                );
+                println!("  --random-opening-plies <N>  Play N random plies before the AIs take over (default: 0)");
+                println!("  --help, -h               Show this help message");
+                println!();
+                println!("Examples:");
+                println!("  cargo run --release -- tournament --engine strong:Strong --engine light:Light --boards ShogiOnly,Fair --games 20 --parallel 6");
+                println!(
+                    "  cargo run --release -- tournament --engines engines.json --format gauntlet"
+                );
+                println!("  cargo run --release -- tournament --engines ladder.json --format swiss --rounds 6 --games 4");
+                println!();
+                println!(
+                    "Engines are seeded in the order given, best first. Swiss and elimination"
+                );
+                println!(
+                    "matches are decided by game points over --games games per board; a drawn"
+                );
+                println!(
+                    "elimination match goes to single playoff games, then to the better seed."
+                );
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    if engines.len() < 2 {
+        anyhow::bail!("At least two engines are required (use --engines or --engine, see --help)");
+    }
+    if board_setups.is_empty() {
+        anyhow::bail!("No valid board setups given");
+    }
+    if let TournamentFormat::Swiss { rounds } = &mut format {
+        *rounds = swiss_rounds
+            .unwrap_or_else(|| crate::selfplay::tournament::default_swiss_rounds(engines.len()));
+    }
+
+    if let Some(n) = num_threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build_global()
+            .ok();
+    }
+
+    println!("=== Tournament Configuration ===");
+    println!("Format: {:?}", format);
+    for engine in &engines {
+        println!(
+            "Engine: {} ({:?}, {}{})",
+            engine.name,
+            engine.strength,
+            engine.evaluator,
+            engine
+                .model_path
+                .as_ref()
+                .map(|p| format!(", {}", p))
+                .unwrap_or_default()
+        );
+    }
+    println!(
+        "Boards: {}",
+        board_setups
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("Games per pairing: {}", games_per_pairing);
+    println!("================================");
+    println!();
+
+    let config = TournamentConfig {
+        engines,
+        format,
+        board_setups,
+        games_per_pairing,
+        use_parallel,
+        random_opening_plies,
+    };
+    let report = crate::selfplay::tournament::run_tournament(&config)?;
+    crate::selfplay::tournament::print_report(&report);
+
+    let results_dir = "selfplay_results";
+    std::fs::create_dir_all(results_dir)?;
+    let results_file = format!(
+        "{}/tournament_{}.json",
+        results_dir,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    );
+    util::atomic_write_json(std::path::Path::new(&results_file), &report, true)?;
+    println!();
+    println!("Results saved to {}", results_file);
+
     Ok(())
 }
+
+fn run_games_cli(args: &[String]) -> anyhow::Result<()> {
+    use crate::network::store::{GameStore, DEFAULT_DB_PATH};
+
+    let mut db = DEFAULT_DB_PATH.to_string();
+    let mut limit = 20;
+    let mut positional: Vec<String> = Vec::new();
+
+    let usage = || {
+        println!("Games Mode Usage:");
+        println!("  cargo run --release -- games list [OPTIONS]");
+        println!("  cargo run --release -- games players [OPTIONS]");
+        println!("  cargo run --release -- games export <ID> [FILE] [OPTIONS]");
+        println!();
+        println!("Shows the network games kept by the server. 'export' writes a game as a");
+        println!("kifu JSON (default: kifu/server_game_<ID>.json) that 'replay' can open.");
+        println!();
+        println!("Options:");
+        println!("  --db <FILE>              Server database (default: server_games.sqlite3)");
+        println!("  --limit <N>              Games shown by 'list' (default: 20)");
+        println!("  --help, -h               Show this help message");
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--db" => {
+                if i + 1 < args.len() {
+                    db = args[i + 1].clone();
+                    i += 1;
+                }
+            }
+            "--limit" => {
+                if i + 1 < args.len() {
+                    if let Ok(n) = args[i + 1].parse() {
+                        limit = n;
+                    }
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                usage();
+                return Ok(());
+            }
+            arg if !arg.starts_with("--") => {
+                positional.push(arg.to_string());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(command) = positional.first() else {
+        usage();
+        return Ok(());
+    };
+    if !std::path::Path::new(&db).exists() {
+        anyhow::bail!("No server database at {}", db);
+    }
+    let store = GameStore::open(std::path::Path::new(&db))?;
+
+    match command.as_str() {
+        "list" => {
+            let games = store.list_games(limit)?;
+            if games.is_empty() {
+                println!("No games recorded");
+            }
+            for game in games {
+                let result = match game.winner {
+                    Some(PlayerId::Player1) => format!("{} won", game.player1),
+                    Some(PlayerId::Player2) => format!("{} won", game.player2),
+                    None => "Draw".to_string(),
+                };
+                println!(
+                    "{:>5}  {}  {} vs {}  {}  {} moves  {} ({})",
+                    game.id,
+                    game.finished_at,
+                    game.player1,
+                    game.player2,
+                    game.board_setup,
+                    game.moves,
+                    result,
+                    game.reason
+                );
+            }
+        }
+        "players" => {
+            println!(
+                "{:<20} {:>5} {:>6} {:>5}",
+                "Player", "Wins", "Losses", "Draws"
+            );
+            for player in store.players()? {
+                println!(
+                    "{:<20} {:>5} {:>6} {:>5}",
+                    player.name, player.wins, player.losses, player.draws
+                );
+            }
+        }
+        "export" => {
+            let Some(id) = positional.get(1).and_then(|id| id.parse::<i64>().ok()) else {
+                anyhow::bail!("Usage: games export <id> [file] (see --help)");
+            };
+            let Some(json) = store.kifu_json(id)? else {
+                anyhow::bail!("No game {} in {}", id, db);
+            };
+            let path = match positional.get(2) {
+                Some(file) => std::path::PathBuf::from(file),
+                None => {
+                    std::fs::create_dir_all("kifu")?;
+                    std::path::Path::new("kifu").join(format!("server_game_{}.json", id))
+                }
+            };
+            std::fs::write(&path, json)?;
+            println!("Game {} exported to {}", id, path.display());
+        }
+        other => {
+            usage();
+            anyhow::bail!("unknown games command: {}", other);
+        }
+    }
+    Ok(())
+}
+
+fn run_kifu_cli(args: &[String]) -> anyhow::Result<()> {
+    let mut dir: Option<String> = None;
+    let mut quarantine: Option<String> = None;
+
+    let usage = || {
+        println!("Kifu Mode Usage:");
+        println!("  cargo run --release -- kifu verify <DIR> [OPTIONS]");
+        println!();
+        println!("Replays every kifu under DIR with the current rules and lists the games");
+        println!("whose moves are no longer legal.");
+        println!();
+        println!("Options:");
+        println!(
+            "  --quarantine <DIR>       Move the failing kifu there (relative paths are kept)"
+        );
+        println!("  --help, -h               Show this help message");
+    };
+
+    if args.first().map(String::as_str) != Some("verify") {
+        usage();
+        return Ok(());
+    }
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--quarantine" => {
+                if i + 1 < args.len() {
+                    quarantine = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                usage();
+                return Ok(());
+            }
+            arg if !arg.starts_with("--") && dir.is_none() => {
+                dir = Some(arg.to_string());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(dir) = dir else {
+        anyhow::bail!("Usage: kifu verify <dir> [--quarantine <dir>] (see --help)");
+    };
+    let dir = std::path::Path::new(&dir);
+    let report = crate::game::verify::verify_dir(dir)?;
+
+    for problem in &report.problems {
+        println!("{}: {}", problem.path.display(), problem.reason);
+    }
+    println!(
+        "Checked {} kifu, {} failed",
+        report.checked,
+        report.problems.len()
+    );
+
+    if let Some(quarantine) = quarantine {
+        if !report.problems.is_empty() {
+            let moved = crate::game::verify::quarantine(
+                dir,
+                &report.problems,
+                std::path::Path::new(&quarantine),
+            )?;
+            println!("Moved {} files to {}", moved, quarantine);
+        }
+    }
+
+    if !report.problems.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_analyze_cli(args: &[String]) -> anyhow::Result<()> {
+    let mut kifu_path: Option<String> = None;
+    let mut depth = 4;
+    let mut cache: Option<String> = None;
+    let mut use_cache = true;
+    let mut output: Option<String> = None;
+    let mut heatmap = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--depth" => {
+                if i + 1 < args.len() {
+                    depth = args[i + 1].parse().unwrap_or(4);
+                    i += 1;
+                }
+            }
+            "--cache" => {
+                if i + 1 < args.len() {
+                    cache = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--no-cache" => {
+                use_cache = false;
+            }
+            "--heatmap" => {
+                heatmap = true;
+            }
+            "--output" => {
+                if i + 1 < args.len() {
+                    output = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Analyze Mode Usage:");
+                println!("  cargo run --release -- analyze <KIFU_FILE> [OPTIONS]");
+                println!();
+                println!("Options:");
+                println!("  --depth <N>              Search depth per position (default: 4)");
+                println!("  --cache <FILE>           Analysis cache file");
+                println!("                           (default: analysis_cache/<run>_<game>.json)");
+                println!("  --no-cache               Do not read or write the analysis cache");
+                println!("  --output <FILE>          Also write the annotations as JSON");
+                println!("  --heatmap                Show each piece's evaluation contribution");
+                println!("                           per position (also added to --output)");
+                println!("  --help, -h               Show this help message");
+                return Ok(());
+            }
+            arg if !arg.starts_with("--") && kifu_path.is_none() => {
+                kifu_path = Some(arg.to_string());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(kifu_path) = kifu_path else {
+        anyhow::bail!("Usage: analyze <kifu_file_path> [--depth N] (see --help)");
+    };
+    let kifu_path = std::path::Path::new(&kifu_path);
+    let kifu: crate::game::KifuData =
+        serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(kifu_path)?))?;
+
+    let cache_path = if use_cache {
+        Some(
+            cache
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(|| crate::analysis::default_cache_path(kifu_path)),
+        )
+    } else {
+        None
+    };
+
+    println!(
+        "=== Analysis: {} ({}, depth {}) ===",
+        kifu_path.display(),
+        kifu.board_setup,
+        depth
+    );
+    let config = crate::analysis::AnalysisConfig {
+        depth,
+        cache_path: cache_path.clone(),
+        heatmap,
+    };
+    let report = crate::analysis::analyze_kifu(&kifu, &config)?;
+
+    println!("Evaluator: {}", report.evaluator);
+    println!();
+    println!(
+        "{:>4} {:<8} {:<12} {:<12} {:>7} {:>5}  {:>6} {:>4} {:>4} {:>5}  PV",
+        "#", "Player", "Played", "Best", "Score", "Depth", "Hash%", "QS%", "Cut%", "BF"
+    );
+    let (boards, _) = crate::game::replay::ReplayViewer::replay_boards(&kifu);
+    for p in &report.positions {
+        let board = &boards[p.move_number - 1];
+        let best = p
+            .best_move
+            .map(|m| m.notation(board, p.player).to_string())
+            .unwrap_or("-".to_string());
+        let marker = if p.best_move == Some(p.played) {
+            ""
+        } else {
+            "?"
+        };
+        let mut pv = Vec::new();
+        let (mut pv_board, mut pv_player) = (board.clone(), p.player);
+        for mv in &p.pv {
+            pv.push(mv.notation(&pv_board, pv_player).to_string());
+            pv_board = crate::logic::apply_move(&pv_board, mv, pv_player);
+            pv_player = pv_player.opponent();
+        }
+        // Cached positions were not searched
+        let health = match p.health {
+            Some(h) => format!(
+                "{:>6.1} {:>4.0} {:>4.0} {:>5.2}",
+                h.hashfull as f32 / 10.0,
+                h.qsearch_share * 100.0,
+                h.cut_rate * 100.0,
+                h.branching_factor
+            ),
+            None => format!("{:>6} {:>4} {:>4} {:>5}", "-", "-", "-", "-"),
+        };
+        println!(
+            "{:>4} {:<8} {:<12} {:<12} {:>7} {:>5}{} {} {}",
+            p.move_number,
+            format!("{:?}", p.player),
+            format!("{}{}", p.played.notation(board, p.player), marker),
+            best,
+            p.score,
+            p.depth,
+            if p.cached { "*" } else { " " },
+            health,
+            pv.join(" ")
+        );
+    }
+    if heatmap {
+        let (mut board, mut player) = crate::game::replay::ReplayViewer::initial_position(&kifu);
+        for (i, played) in kifu.moves.iter().enumerate() {
+            if let Some(breakdown) = report
+                .positions
+                .iter()
+                .find(|p| p.move_number == i + 1)
+                .and_then(|p| p.heatmap.as_ref())
+            {
+                println!();
+                println!(
+                    "Move {} ({:?} to play) eval {:+}: hand {:+}, drop pressure {:+}, mobility {:+}",
+                    i + 1,
+                    player,
+                    breakdown.total,
+                    breakdown.hand,
+                    breakdown.drop_pressure,
+                    breakdown.mobility
+                );
+                for line in crate::ui::display::heatmap_lines(&board, breakdown) {
+                    println!("{}", line);
+                }
+            }
+            board = crate::logic::apply_move(&board, played, player);
+            player = player.opponent();
+        }
+    }
+    println!();
+    println!(
+        "{} positions, {} from cache (*), {} nodes searched",
+        report.positions.len(),
+        report.cache_hits,
+        report.nodes
+    );
+    if let Some(path) = cache_path {
+        println!("Cache: {}", path.display());
+    }
+
+    if let Some(path) = output {
+        util::atomic_write_json(std::path::Path::new(&path), &report, true)?;
+        println!("Annotations saved to {}", path);
+    }
+
+    Ok(())
+}
+
+fn run_selftest_cli() -> anyhow::Result<()> {
+    use crate::selftest::CheckStatus;
+
+    println!("=== Self-Test ===");
+    println!(
+        "Build: {} (ml feature {})",
+        env!("CARGO_PKG_VERSION"),
+        if crate::ml::ML_ENABLED {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    println!();
+
+    let results = crate::selftest::run_checks();
+    for result in &results {
+        let label = match result.status {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Skip => "SKIP",
+            CheckStatus::Fail => "FAIL",
+        };
+        println!("[{}] {:<8} {}", label, result.name, result.detail);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|r| r.status == CheckStatus::Fail)
+        .count();
+    println!();
+    if failed > 0 {
+        anyhow::bail!("{} self-test check(s) failed", failed);
+    }
+    println!("All checks passed");
+    Ok(())
+}
+
+fn run_train_cli(args: &[String]) -> anyhow::Result<()> {
+    use crate::ml::train::{find_datasets, run_training, TrainConfig};
+
+    let mut config = TrainConfig::default();
+    let mut board = "Fair".to_string();
+    let mut data_paths: Vec<std::path::PathBuf> = Vec::new();
+    let mut output_dir: Option<std::path::PathBuf> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--board" => {
+                if i + 1 < args.len() {
+                    board = args[i + 1].clone();
+                    i += 1;
+                }
+            }
+            "--version" => {
+                if i + 1 < args.len() {
+                    config.version = args[i + 1].clone();
+                    i += 1;
+                }
+            }
+            "--data" => {
+                if i + 1 < args.len() {
+                    data_paths.push(args[i + 1].clone().into());
+                    i += 1;
+                }
+            }
+            "--output" => {
+                if i + 1 < args.len() {
+                    output_dir = Some(args[i + 1].clone().into());
+                    i += 1;
+                }
+            }
+            "--epochs" => {
+                if i + 1 < args.len() {
+                    config.epochs = args[i + 1].parse().unwrap_or(config.epochs);
+                    i += 1;
+                }
+            }
+            "--batch-size" => {
+                if i + 1 < args.len() {
+                    config.batch_size = args[i + 1].parse().unwrap_or(config.batch_size);
+                    i += 1;
+                }
+            }
+            "--learning-rate" | "--lr" => {
+                if i + 1 < args.len() {
+                    config.learning_rate = args[i + 1].parse().unwrap_or(config.learning_rate);
+                    i += 1;
+                }
+            }
+            "--hidden" => {
+                if i + 1 < args.len() {
+                    config.hidden = args[i + 1]
+                        .split(',')
+                        .map(|w| w.trim().parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| anyhow::anyhow!("Invalid --hidden: {}", args[i + 1]))?;
+                    i += 1;
+                }
+            }
+            "--validation" => {
+                if i + 1 < args.len() {
+                    config.validation_split =
+                        args[i + 1].parse().unwrap_or(config.validation_split);
+                    i += 1;
+                }
+            }
+            "--seed" => {
+                if i + 1 < args.len() {
+                    config.seed = args[i + 1].parse().unwrap_or(config.seed);
+                    i += 1;
+                }
+            }
+            "--resume" => {
+                config.resume = true;
+            }
+            "--parent-model" => {
+                if i + 1 < args.len() {
+                    config.parent_model = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--generation" => {
+                if i + 1 < args.len() {
+                    config.generation = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Train Mode Usage:");
+                println!("  cargo run --release --features train -- train [OPTIONS]");
+                println!();
+                println!(
+                    "Trains an MLP value network on selfplay datasets (selfplay --export-dataset)"
+                );
+                println!("and exports it as ONNX for the NeuralNetwork evaluator.");
+                println!();
+                println!("Options:");
+                println!("  --board <NAME>           Board type (default: Fair)");
+                println!("  --version <V>            Model version (default: 0.1.0)");
+                println!("  --data <PATH>            dataset.bin file, run directory or directory of runs (repeatable)");
+                println!("                           (default: selfplay_kifu/<board>, dataset_dedup.bin preferred)");
+                println!("  --output <DIR>           Output directory (default: models/<board>/v<version>)");
+                println!("  --epochs <N>             Number of epochs (default: 10)");
+                println!("  --batch-size <N>         Mini-batch size (default: 64)");
+                println!("  --learning-rate <LR>     SGD learning rate (default: 0.01)");
+                println!("  --hidden <LIST>          Comma-separated hidden layer widths (default: 256,64)");
+                println!("  --validation <FRAC>      Share of positions held out for validation (default: 0.1)");
+                println!("  --seed <N>               Shuffle seed (default: 42)");
+                println!("  --resume                 Continue from the last checkpoint in the output directory");
+                println!("  --parent-model <FILE>    ONNX model this one derives from (recorded in the lineage)");
+                println!("  --generation <N>         Model generation (default: parent generation + 1, or 0)");
+                println!("  --help, -h               Show this help message");
+                println!();
+                println!("Examples:");
+                println!("  cargo run --release --features train -- train --board Fair --version 0.3.0 --epochs 20");
+                println!("  cargo run --release --features train -- train --board Fair --version 0.3.0 --epochs 40 --resume");
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    if !crate::ml::train::TRAIN_ENABLED {
+        anyhow::bail!(crate::ml::train::TRAIN_UNAVAILABLE_MESSAGE);
+    }
+
+    if data_paths.is_empty() {
+        data_paths.push(std::path::Path::new("selfplay_kifu").join(&board));
+    }
+    config.data = data_paths.iter().flat_map(|p| find_datasets(p)).collect();
+    if config.data.is_empty() {
+        anyhow::bail!(
+            "No dataset.bin found in {}. Run selfplay with --export-dataset first.",
+            data_paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    config.output_dir = output_dir.unwrap_or_else(|| {
+        std::path::Path::new("models")
+            .join(&board)
+            .join(format!("v{}", config.version))
+    });
+
+    println!("Training version {} ({})", config.version, board);
+    println!("Output: {}", config.output_dir.display());
+    let report = run_training(&config)?;
+
+    println!();
+    println!(
+        "Model saved to {} ({} positions, {:.1}s)",
+        report.model_path.display(),
+        report.train_samples + report.validation_samples,
+        report.elapsed_secs
+    );
+    if let Some(last) = report.history.last() {
+        print!("Final train loss {:.4}", last.train_loss);
+        if let Some(loss) = last.validation_loss {
+            print!(", validation loss {:.4}", loss);
+        }
+        println!();
+    }
+    println!(
+        "Lineage: generation {}, feature version {}",
+        report.lineage.generation.unwrap_or(0),
+        report.lineage.feature_version.unwrap_or(0)
+    );
+    Ok(())
+}
+
+fn run_arena_cli(args: &[String]) -> anyhow::Result<()> {
+    use crate::selfplay::arena::{run_arena, ArenaConfig, BEST_DIR, BEST_MODEL_FILE};
+    use crate::selfplay::BoardSetupType;
+
+    let mut config = ArenaConfig {
+        candidate: std::path::PathBuf::new(),
+        best_dir: std::path::PathBuf::from(BEST_DIR),
+        board_setups: vec![BoardSetupType::Fair],
+        games: 20,
+        threshold: 0.55,
+        strength: crate::player::ai::AIStrength::Strong,
+        use_parallel: false,
+        random_opening_plies: 4,
+        dry_run: false,
+    };
+    let mut num_threads: Option<usize> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--candidate" => {
+                if i + 1 < args.len() {
+                    config.candidate = args[i + 1].clone().into();
+                    i += 1;
+                }
+            }
+            "--best-dir" => {
+                if i + 1 < args.len() {
+                    config.best_dir = args[i + 1].clone().into();
+                    i += 1;
+                }
+            }
+            "--boards" | "--board" => {
+                if i + 1 < args.len() {
+                    config.board_setups = args[i + 1]
+                        .split(',')
+                        .filter_map(|name| {
+                            let setup = BoardSetupType::from_name(name);
+                            if setup.is_none() {
+                                eprintln!("Unknown board type: {}, skipping", name);
+                            }
+                            setup
+                        })
+                        .collect();
+                    i += 1;
+                }
+            }
+            "--games" => {
+                if i + 1 < args.len() {
+                    config.games = args[i + 1].parse().unwrap_or(config.games);
+                    i += 1;
+                }
+            }
+            "--threshold" => {
+                if i + 1 < args.len() {
+                    config.threshold = args[i + 1].parse().unwrap_or(config.threshold);
+                    i += 1;
+                }
+            }
+            "--strength" => {
+                if i + 1 < args.len() {
+                    config.strength = match args[i + 1].as_str() {
+                        "Light" => crate::player::ai::AIStrength::Light,
+                        "Strong" => crate::player::ai::AIStrength::Strong,
+                        _ => {
+                            eprintln!("Unknown AI strength: {}, using Strong", args[i + 1]);
+                            crate::player::ai::AIStrength::Strong
+                        }
+                    };
+                    i += 1;
+                }
+            }
+            "--parallel" => {
+                config.use_parallel = true;
+                if i + 1 < args.len() {
+                    if let Ok(n) = args[i + 1].parse::<usize>() {
+                        num_threads = Some(n);
+                        i += 1;
+                    }
+                }
+            }
+            "--random-opening-plies" => {
+                if i + 1 < args.len() {
+                    config.random_opening_plies =
+                        args[i + 1].parse().unwrap_or(config.random_opening_plies);
+                    i += 1;
+                }
+            }
+            "--dry-run" => {
+                config.dry_run = true;
+            }
+            "--help" | "-h" => {
+                println!("Arena Mode Usage:");
+                println!(
+                    "  cargo run --release --features ml -- arena --candidate <MODEL> [OPTIONS]"
+                );
+                println!();
+                println!(
+                    "Plays a candidate ONNX model against the current best model and promotes it"
+                );
+                println!("(copies it to <best-dir>/model.onnx and records best.json) if its score passes");
+                println!(
+                    "the threshold. Without a best model, the candidate is promoted directly."
+                );
+                println!();
+                println!("Options:");
+                println!("  --candidate <FILE>       Candidate ONNX model (required)");
+                println!(
+                    "  --best-dir <DIR>         Directory of the best model (default: models/best)"
+                );
+                println!("  --boards <LIST>          Comma-separated board types (default: Fair)");
+                println!(
+                    "  --games <N>              Games per board, colors alternate (default: 20)"
+                );
+                println!("  --threshold <SCORE>      Score needed for promotion, draw = 0.5 (default: 0.55)");
+                println!("  --strength <STR>         AI strength for both models: Light, Strong (default: Strong)");
+                println!(
+                    "  --parallel [N]           Play games in parallel with optional thread count"
+                );
+                println!("  --random-opening-plies <N>  Random plies before the models take over (default: 4)");
+                println!("  --dry-run                Play the match without promoting");
+                println!("  --help, -h               Show this help message");
+                println!();
+                println!("Point ai_config.json at the best model with:");
+                println!("  \"nn_model_path\": \"models/best/model.onnx\"");
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    if config.candidate.as_os_str().is_empty() {
+        anyhow::bail!("--candidate <MODEL> is required (see --help)");
+    }
+    if config.board_setups.is_empty() {
+        anyhow::bail!("No valid board setups given");
+    }
+    if !crate::ml::ML_ENABLED {
+        anyhow::bail!(
+            "compiled without ml — an arena needs the NeuralNetwork evaluator, rebuild with `--features ml`"
+        );
+    }
+    if let Some(n) = num_threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build_global()
+            .ok();
+    }
+
+    println!("Candidate: {}", config.candidate.display());
+    println!("Best: {}", config.best_dir.join(BEST_MODEL_FILE).display());
+    let Some(result) = run_arena(&config)? else {
+        if config.dry_run {
+            println!("No best model yet; the candidate would be promoted");
+        } else {
+            println!(
+                "No best model yet; promoted the candidate to {}",
+                config.best_dir.join(BEST_MODEL_FILE).display()
+            );
+        }
+        return Ok(());
+    };
+
+    println!();
+    println!(
+        "Candidate vs best: +{} ={} -{} (score {:.1}%, threshold {:.1}%)",
+        result.wins,
+        result.draws,
+        result.losses,
+        result.score * 100.0,
+        result.threshold * 100.0
+    );
+    if let (Some(elo), Some(error)) = (result.elo, result.elo_error_95) {
+        println!("Elo: {:+.1} ± {:.1}", elo, error);
+    }
+    if result.promoted {
+        println!("Promoted: {} is the new best model", result.candidate);
+    } else if result.score >= result.threshold {
+        println!("Passed the threshold (dry run, not promoted)");
+    } else {
+        println!("Not promoted: {} stays the best model", result.best);
+    }
+
+    let results_dir = "selfplay_results";
+    std::fs::create_dir_all(results_dir)?;
+    let results_file = format!(
+        "{}/arena_{}.json",
+        results_dir,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    );
+    util::atomic_write_json(std::path::Path::new(&results_file), &result, true)?;
+    println!("Results saved to {}", results_file);
+
+    Ok(())
+}
+
+fn run_bench_cli(args: &[String]) -> anyhow::Result<()> {
+    let mut depth = 4;
+    let mut strength = crate::player::ai::AIStrength::Strong;
+    let mut output: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--depth" => {
+                if i + 1 < args.len() {
+                    depth = args[i + 1].parse().unwrap_or(4);
+                    i += 1;
+                }
+            }
+            "--strength" => {
+                if i + 1 < args.len() {
+                    strength = match args[i + 1].as_str() {
+                        "Light" => crate::player::ai::AIStrength::Light,
+                        "Strong" => crate::player::ai::AIStrength::Strong,
+                        _ => {
+                            eprintln!("Unknown AI strength: {}, using Strong", args[i + 1]);
+                            crate::player::ai::AIStrength::Strong
+                        }
+                    };
+                    i += 1;
+                }
+            }
+            "--output" => {
+                if i + 1 < args.len() {
+                    output = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Bench Mode Usage:");
+                println!("  cargo run --release -- bench [OPTIONS]");
+                println!();
+                println!("Options:");
+                println!("  --depth <N>              Fixed search depth (default: 4)");
+                println!(
+                    "  --strength <STR>         Search preset: Light, Strong (default: Strong)"
+                );
+                println!("  --output <FILE>          Also write the report as JSON");
+                println!("  --help, -h               Show this help message");
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    let config = crate::bench::BenchConfig { depth, strength };
+    println!("=== Bench (depth {}, {:?}) ===", depth, strength);
+
+    let report = crate::bench::run_bench(&config);
+
+    println!("Evaluator: {}", report.evaluator);
+    println!();
+    println!(
+        "{:<16} {:>12} {:>10} {:>10} {:>8}  Best Move",
+        "Position", "Nodes", "Time(ms)", "NPS", "TT Hit%"
+    );
+    for r in &report.positions {
+        println!(
+            "{:<16} {:>12} {:>10} {:>10} {:>7.1}%  {}",
+            r.name,
+            r.nodes,
+            r.time_ms,
+            r.nps,
+            r.tt_hits as f64 / r.tt_probes.max(1) as f64 * 100.0,
+            r.best_move.as_deref().unwrap_or("-")
+        );
+    }
+    println!();
+    println!(
+        "Total: {} nodes in {} ms ({} NPS), TT hit rate {:.1}%",
+        report.total_nodes,
+        report.total_time_ms,
+        report.nps,
+        report.tt_hit_rate * 100.0
+    );
+
+    if let Some(path) = output {
+        util::atomic_write_json(std::path::Path::new(&path), &report, true)?;
+        println!("Report saved to {}", path);
+    }
+
+    Ok(())
+}
+
+fn run_profile_cli(args: &[String]) -> anyhow::Result<()> {
+    let mut config = crate::profile::ProfileConfig {
+        games: 3,
+        board_setup: crate::selfplay::BoardSetupType::Fair,
+        strength: crate::player::ai::AIStrength::Light,
+        max_moves: 60,
+        render: true,
+    };
+    let mut output: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--games" => {
+                if i + 1 < args.len() {
+                    config.games = args[i + 1].parse().unwrap_or(3);
+                    i += 1;
+                }
+            }
+            "--board" => {
+                if i + 1 < args.len() {
+                    config.board_setup = crate::selfplay::BoardSetupType::from_name(&args[i + 1])
+                        .unwrap_or_else(|| {
+                            eprintln!("Unknown board type: {}, using Fair", args[i + 1]);
+                            crate::selfplay::BoardSetupType::Fair
+                        });
+                    i += 1;
+                }
+            }
+            "--strength" => {
+                if i + 1 < args.len() {
+                    config.strength = match args[i + 1].as_str() {
+                        "Light" => crate::player::ai::AIStrength::Light,
+                        "Strong" => crate::player::ai::AIStrength::Strong,
+                        _ => {
+                            eprintln!("Unknown AI strength: {}, using Light", args[i + 1]);
+                            crate::player::ai::AIStrength::Light
+                        }
+                    };
+                    i += 1;
+                }
+            }
+            "--max-moves" => {
+                if i + 1 < args.len() {
+                    config.max_moves = args[i + 1].parse().unwrap_or(60);
+                    i += 1;
+                }
+            }
+            "--no-render" => {
+                config.render = false;
+            }
+            "--output" => {
+                if i + 1 < args.len() {
+                    output = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Profile Mode Usage:");
+                println!("  cargo run --release -- profile [OPTIONS]");
+                println!();
+                println!("Plays instrumented AI vs AI games and shows where the time goes");
+                println!("(move generation, evaluation, TT, apply_move, NN inference, rendering).");
+                println!();
+                println!("Options:");
+                println!("  --games <N>              Number of games (default: 3)");
+                println!("  --board <TYPE>           Board setup (default: Fair)");
+                println!(
+                    "  --strength <STR>         Search preset: Light, Strong (default: Light)"
+                );
+                println!(
+                    "  --max-moves <N>          Plies per game before it is cut off (default: 60)"
+                );
+                println!("  --no-render              Do not draw the board before each move");
+                println!("  --output <FILE>          Also write the report as JSON");
+                println!("  --help, -h               Show this help message");
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    if config.render {
+        execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+    }
+    let report = crate::profile::run_profile(&config);
+    if config.render {
+        execute!(io::stdout(), terminal::LeaveAlternateScreen)?;
+    }
+
+    println!(
+        "=== Profile ({} games, {} plies, {}, {}) ===",
+        report.games, report.plies, report.board_setup, report.strength
+    );
+    println!("Evaluator: {}", report.evaluator);
+    println!();
+    println!(
+        "{:<16} {:>12} {:>12} {:>7} {:>10}",
+        "Subsystem", "Calls", "Time(ms)", "Share", "ns/call"
+    );
+    for s in &report.subsystems {
+        let per_call = if s.calls > 0 {
+            format!("{:.0}", s.time_ms * 1e6 / s.calls as f64)
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{:<16} {:>12} {:>12.1} {:>6.1}% {:>10}",
+            s.name,
+            s.calls,
+            s.time_ms,
+            s.share * 100.0,
+            per_call
+        );
+    }
+    println!();
+    println!(
+        "Total: {:.1} ms ({:.1} ms per ply)",
+        report.total_ms,
+        report.total_ms / report.plies.max(1) as f64
+    );
+    println!();
+    for hint in &report.hints {
+        println!("- {}", hint);
+    }
+
+    if let Some(path) = output {
+        util::atomic_write_json(std::path::Path::new(&path), &report, true)?;
+        println!("Report saved to {}", path);
+    }
+
+    Ok(())
+}
+
+fn run_demo_cli(args: &[String]) -> anyhow::Result<()> {
+    use crate::demo::{DemoConfig, DemoSource};
+
+    let mut config = DemoConfig::default();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--delay" => {
+                if i + 1 < args.len() {
+                    if let Ok(ms) = args[i + 1].parse() {
+                        config.move_delay = std::time::Duration::from_millis(ms);
+                    }
+                    i += 1;
+                }
+            }
+            "--source" => {
+                if i + 1 < args.len() {
+                    config.source = match args[i + 1].as_str() {
+                        "replay" => DemoSource::Replay,
+                        "live" => DemoSource::Live,
+                        "mixed" => DemoSource::Mixed,
+                        other => {
+                            eprintln!("Unknown demo source: {}, using mixed", other);
+                            DemoSource::Mixed
+                        }
+                    };
+                    i += 1;
+                }
+            }
+            "--kifu-dir" => {
+                if i + 1 < args.len() {
+                    config.kifu_dirs = vec![std::path::PathBuf::from(&args[i + 1])];
+                    i += 1;
+                }
+            }
+            "--max-plies" => {
+                if i + 1 < args.len() {
+                    config.max_plies = args[i + 1].parse().unwrap_or(config.max_plies);
+                    i += 1;
+                }
+            }
+            "--rounds" => {
+                if i + 1 < args.len() {
+                    config.rounds = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--seed" => {
+                if i + 1 < args.len() {
+                    config.seed = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                println!("Demo Mode Usage:");
+                println!("  cargo run --release -- demo [OPTIONS]");
+                println!();
+                println!("Loops over archived kifu replays and live Light vs Light games");
+                println!("until a key is pressed.");
+                println!();
+                println!("Options:");
+                println!("  --delay <MS>             Pause after each move (default: 800)");
+                println!("  --source <SRC>           mixed, replay or live (default: mixed)");
+                println!(
+                    "  --kifu-dir <DIR>         Archive to replay from (default: kifu, selfplay_results, selfplay_kifu)"
+                );
+                println!(
+                    "  --max-plies <N>          Adjourn live games after N plies (default: 200)"
+                );
+                println!("  --rounds <N>             Stop after N rounds (default: run until a key is pressed)");
+                println!("  --seed <N>               Random seed for choosing rounds and setups");
+                println!("  --help, -h               Show this help message");
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", args[i]);
+            }
+        }
+        i += 1;
+    }
+
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+    let res = crate::demo::run(&config);
+    execute!(io::stdout(), terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    res
+}
+
+fn run_replay_file(kifu_path: &std::path::Path) -> anyhow::Result<()> {
+    let mut viewer = crate::game::replay::ReplayViewer::from_kifu_path(kifu_path)?;
+    viewer.run()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::store::GameStore;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_games_cli() {
+        let dir = std::env::temp_dir().join(format!("aho_games_cli_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("games.sqlite3");
+        let db_arg = db.to_str().unwrap();
+        assert!(run_games_cli(&args(&["list", "--db", db_arg])).is_err());
+
+        let kifu: game::KifuData = serde_json::from_str(
+            r#"{"board_setup":"ShogiOnly","player1_name":"alice","player2_name":"bob","moves":[]}"#,
+        )
+        .unwrap();
+        let id = GameStore::open(&db)
+            .unwrap()
+            .record_game(&kifu, Some(PlayerId::Player1), "Resignation")
+            .unwrap();
+
+        run_games_cli(&args(&["list", "--db", db_arg])).unwrap();
+        run_games_cli(&args(&["players", "--db", db_arg])).unwrap();
+        let file = dir.join("exported.json");
+        let id_arg = id.to_string();
+        run_games_cli(&args(&[
+            "export",
+            &id_arg,
+            file.to_str().unwrap(),
+            "--db",
+            db_arg,
+        ]))
+        .unwrap();
+        let exported: game::KifuData =
+            serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(exported.player1_name, "alice");
+        assert_eq!(exported.player2_name, "bob");
+        assert!(run_games_cli(&args(&["export", "999", "--db", db_arg])).is_err());
+        assert!(run_games_cli(&args(&["rename", "--db", db_arg])).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,237 @@
+//! Batched model inference
+//!
+//! Evaluations from concurrent searches sharing a model (parallel selfplay or
+//! tournament games, and later parallel search or MCTS leaves) are queued to
+//! one worker thread per model, which runs everything queued so far through
+//! the model in a single call. A lone caller is served at once, so batching
+//! costs nothing without concurrency; `max_wait_micros` trades latency for
+//! fuller batches.
+
+use crate::player::ai::config::NNBatchConfig;
+use crate::util::Instant;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs `count` positions (`count * feature_size` features, row-major) through
+/// the model, returning one value per position
+pub type BatchFn = Box<dyn FnMut(&[f32], usize) -> Result<Vec<f32>, String> + Send>;
+
+type Reply = Result<Vec<f32>, String>;
+
+struct Request {
+    features: Vec<f32>,
+    count: usize,
+    reply: Sender<Reply>,
+}
+
+#[derive(Default)]
+struct Counters {
+    batches: AtomicUsize,
+    positions: AtomicUsize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchStats {
+    pub batches: usize,
+    pub positions: usize,
+}
+
+impl BatchStats {
+    pub fn average_batch_size(&self) -> f64 {
+        if self.batches == 0 {
+            0.0
+        } else {
+            self.positions as f64 / self.batches as f64
+        }
+    }
+}
+
+/// Queue in front of a model; the worker thread stops once it is dropped
+pub struct InferenceBatcher {
+    sender: Sender<Request>,
+    feature_size: usize,
+    counters: Arc<Counters>,
+}
+
+impl InferenceBatcher {
+    pub fn new(feature_size: usize, config: &NNBatchConfig, model: BatchFn) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let counters = Arc::new(Counters::default());
+        let worker = Worker {
+            receiver,
+            model,
+            feature_size,
+            max_batch_size: config.max_batch_size.max(1),
+            max_wait: Duration::from_micros(config.max_wait_micros),
+            counters: Arc::clone(&counters),
+        };
+        std::thread::Builder::new()
+            .name("nn-batcher".to_string())
+            .spawn(move || worker.run())
+            .expect("failed to spawn the inference batcher thread");
+        Self {
+            sender,
+            feature_size,
+            counters,
+        }
+    }
+
+    /// Value of one position
+    pub fn evaluate(&self, features: Vec<f32>) -> Result<f32, String> {
+        Ok(self.evaluate_many(features, 1)?[0])
+    }
+
+    /// Values of `count` positions given back to back, kept in one batch
+    pub fn evaluate_many(&self, features: Vec<f32>, count: usize) -> Result<Vec<f32>, String> {
+        if features.len() != count * self.feature_size {
+            return Err(format!(
+                "expected {} features for {} positions, got {}",
+                count * self.feature_size,
+                count,
+                features.len()
+            ));
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let (reply, response) = mpsc::channel();
+        self.sender
+            .send(Request {
+                features,
+                count,
+                reply,
+            })
+            .map_err(|_| "inference batcher stopped".to_string())?;
+        response
+            .recv()
+            .map_err(|_| "inference batcher stopped".to_string())?
+    }
+
+    pub fn stats(&self) -> BatchStats {
+        BatchStats {
+            batches: self.counters.batches.load(Ordering::Relaxed),
+            positions: self.counters.positions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Worker {
+    receiver: Receiver<Request>,
+    model: BatchFn,
+    feature_size: usize,
+    max_batch_size: usize,
+    max_wait: Duration,
+    counters: Arc<Counters>,
+}
+
+impl Worker {
+    fn run(mut self) {
+        while let Ok(first) = self.receiver.recv() {
+            let requests = self.collect(first);
+            self.run_batch(requests);
+        }
+    }
+
+    /// `first` plus whatever else is queued (or arrives within `max_wait`),
+    /// up to `max_batch_size` positions
+    fn collect(&self, first: Request) -> Vec<Request> {
+        let deadline = Instant::now() + self.max_wait;
+        let mut positions = first.count;
+        let mut requests = vec![first];
+        while positions < self.max_batch_size {
+            let next = match self.receiver.try_recv() {
+                Ok(request) => request,
+                Err(TryRecvError::Disconnected) => break,
+                Err(TryRecvError::Empty) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    match self.receiver.recv_timeout(deadline - now) {
+                        Ok(request) => request,
+                        Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            };
+            positions += next.count;
+            requests.push(next);
+        }
+        requests
+    }
+
+    fn run_batch(&mut self, requests: Vec<Request>) {
+        let positions: usize = requests.iter().map(|r| r.count).sum();
+        let mut input = Vec::with_capacity(positions * self.feature_size);
+        for request in &requests {
+            input.extend_from_slice(&request.features);
+        }
+
+        let result = (self.model)(&input, positions).and_then(|values| {
+            if values.len() == positions {
+                Ok(values)
+            } else {
+                Err(format!(
+                    "model returned {} values for {} positions",
+                    values.len(),
+                    positions
+                ))
+            }
+        });
+        self.counters.batches.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .positions
+            .fetch_add(positions, Ordering::Relaxed);
+
+        let mut offset = 0;
+        for request in requests {
+            let reply = match &result {
+                Ok(values) => Ok(values[offset..offset + request.count].to_vec()),
+                Err(e) => Err(e.clone()),
+            };
+            offset += request.count;
+            // The caller may have given up; nothing to do then
+            request.reply.send(reply).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_requests_are_batched() {
+        // "Model": sum of the features, slow enough for requests to queue up
+        let model: BatchFn = Box::new(|features, count| {
+            std::thread::sleep(Duration::from_millis(2));
+            Ok(features
+                .chunks(features.len() / count)
+                .map(|f| f.iter().sum())
+                .collect())
+        });
+        let batcher = InferenceBatcher::new(2, &NNBatchConfig::default(), model);
+
+        std::thread::scope(|scope| {
+            for t in 0..8 {
+                let batcher = &batcher;
+                scope.spawn(move || {
+                    for i in 0..20 {
+                        let x = (t * 100 + i) as f32;
+                        assert_eq!(batcher.evaluate(vec![x, 1.0]).unwrap(), x + 1.0);
+                    }
+                });
+            }
+        });
+        assert_eq!(
+            batcher.evaluate_many(vec![1.0, 2.0, 3.0, 4.0], 2).unwrap(),
+            vec![3.0, 7.0]
+        );
+        assert!(batcher.evaluate_many(vec![1.0], 1).is_err());
+
+        let stats = batcher.stats();
+        assert_eq!(stats.positions, 162);
+        assert!(stats.batches < 161, "{} batches", stats.batches);
+    }
+}
@@ -4,14 +4,111 @@
 
 use crate::core::{Board, PieceKind, PlayerId, Position};
 
+/// Version of the feature layout below. Bump on any change so that models
+/// trained on an older layout can be detected (see `ModelLineage`).
+pub const FEATURE_VERSION: u32 = 1;
+
+/// ONNX metadata key under which models declare the feature version they
+/// were trained on
+pub const FEATURE_VERSION_KEY: &str = "feature_version";
+
+/// Side of the square grid the board is encoded on. Smaller boards sit in
+/// its top-left corner and leave the other squares all zero (not even the
+/// empty-square feature); larger boards do not fit (see [`BoardFeatureExtractor::fits`]).
+pub const FEATURE_BOARD_SIZE: usize = 9;
+
 /// Number of piece types (including empty squares)
 /// 1 (empty) + 20 (own pieces) + 20 (opponent pieces) = 41
 const NUM_PIECE_TYPES: usize = 41;
 
+/// Hand counts are divided by this (and capped at 1.0)
+const MAX_HAND_COUNT: f32 = 18.0;
+
+/// Piece kinds of the one-hot square encoding; index `i` is feature `i + 1`
+/// for the perspective player's pieces and `i + 21` for the opponent's
+const SQUARE_KINDS: [PieceKind; 20] = [
+    PieceKind::S_Pawn,
+    PieceKind::S_Lance,
+    PieceKind::S_Knight,
+    PieceKind::S_Silver,
+    PieceKind::S_Gold,
+    PieceKind::S_Bishop,
+    PieceKind::S_Rook,
+    PieceKind::S_King,
+    PieceKind::S_ProPawn,
+    PieceKind::S_ProLance,
+    PieceKind::S_ProKnight,
+    PieceKind::S_ProSilver,
+    PieceKind::S_ProBishop,
+    PieceKind::S_ProRook,
+    PieceKind::C_Pawn,
+    PieceKind::C_Knight,
+    PieceKind::C_Bishop,
+    PieceKind::C_Rook,
+    PieceKind::C_Queen,
+    PieceKind::C_King,
+];
+
+/// Hand piece kinds, in feature order (per player)
+const HAND_KINDS: [PieceKind; 11] = [
+    PieceKind::S_Pawn,
+    PieceKind::S_Lance,
+    PieceKind::S_Knight,
+    PieceKind::S_Silver,
+    PieceKind::S_Gold,
+    PieceKind::S_Bishop,
+    PieceKind::S_Rook,
+    PieceKind::C_Pawn,
+    PieceKind::C_Knight,
+    PieceKind::C_Bishop,
+    PieceKind::C_Rook,
+];
+
+/// Pieces recovered from a feature vector; `true` marks pieces of the
+/// player the features were extracted for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodedPieces {
+    pub on_board: Vec<(PieceKind, bool)>,
+    /// One entry per piece in hand (counts above the cap are lost)
+    pub in_hand: Vec<(PieceKind, bool)>,
+}
+
 /// Feature extractor for board states
 pub struct BoardFeatureExtractor;
 
 impl BoardFeatureExtractor {
+    /// Feature layout version of [`Self::extract`]
+    pub const VERSION: u32 = FEATURE_VERSION;
+
+    /// Check the feature version a model declares ([`FEATURE_VERSION_KEY`]
+    /// metadata). A model without one, or trained on another layout, would
+    /// evaluate garbage, so it is refused.
+    pub fn check_model_version(declared: Option<&str>) -> anyhow::Result<()> {
+        let Some(declared) = declared else {
+            anyhow::bail!(
+                "model declares no {} in its metadata (this build extracts feature version {}); \
+                 retrain it, or stamp it with scripts/ml/train.py --stamp-feature-version if it \
+                 is known to use the current layout",
+                FEATURE_VERSION_KEY,
+                Self::VERSION
+            );
+        };
+        match declared.trim().parse::<u32>() {
+            Ok(version) if version == Self::VERSION => Ok(()),
+            Ok(version) => anyhow::bail!(
+                "model was trained on feature version {}, this build extracts version {}; \
+                 retrain it on data from this build",
+                version,
+                Self::VERSION
+            ),
+            Err(_) => anyhow::bail!(
+                "model declares an invalid {} '{}'",
+                FEATURE_VERSION_KEY,
+                declared
+            ),
+        }
+    }
+
     /// Extract features from a board state
     ///
     /// Returns a flattened feature vector representing:
@@ -23,11 +120,14 @@ impl BoardFeatureExtractor {
 
         // 1. Board state (9x9x41 = 3321 features)
         // Each square has one-hot encoding for piece type
-        for y in 0..board.height {
-            for x in 0..board.width {
-                let pos = Position::new(x, y);
-                let piece_features = Self::encode_square(board, pos, current_player);
-                features.extend(piece_features);
+        for y in 0..FEATURE_BOARD_SIZE {
+            for x in 0..FEATURE_BOARD_SIZE {
+                if x < board.width && y < board.height {
+                    let pos = Position::new(x, y);
+                    features.extend(Self::encode_square(board, pos, current_player));
+                } else {
+                    features.extend([0.0; NUM_PIECE_TYPES]);
+                }
             }
         }
 
@@ -42,6 +142,27 @@ impl BoardFeatureExtractor {
         features
     }
 
+    /// Index in [`Self::extract`]'s output of the one-hot feature that is set
+    /// for `pos` (the empty-square feature when nothing is there)
+    pub(crate) fn square_feature(board: &Board, pos: Position, perspective: PlayerId) -> usize {
+        let idx = board.get_piece(pos).map_or(0, |piece| {
+            Self::piece_to_index(piece.kind, piece.owner, perspective)
+        });
+        (pos.y * FEATURE_BOARD_SIZE + pos.x) * NUM_PIECE_TYPES + idx
+    }
+
+    /// Index in [`Self::extract`]'s output of the first hand feature; the
+    /// [`Self::encode_hand`] values follow, then the side-to-move feature
+    pub(crate) fn hand_offset() -> usize {
+        FEATURE_BOARD_SIZE * FEATURE_BOARD_SIZE * NUM_PIECE_TYPES
+    }
+
+    /// Whether `board` fits the feature grid; the NN evaluators fall back to
+    /// the handcrafted evaluation on boards that do not
+    pub fn fits(board: &Board) -> bool {
+        board.width <= FEATURE_BOARD_SIZE && board.height <= FEATURE_BOARD_SIZE
+    }
+
     /// Encode a single square as one-hot vector
     fn encode_square(board: &Board, pos: Position, perspective: PlayerId) -> Vec<f32> {
         let mut encoding = vec![0.0; NUM_PIECE_TYPES];
@@ -57,26 +178,18 @@ impl BoardFeatureExtractor {
     }
 
     /// Encode hand pieces as normalized counts
-    fn encode_hand(board: &Board, perspective: PlayerId) -> Vec<f32> {
+    pub(crate) fn encode_hand(board: &Board, perspective: PlayerId) -> Vec<f32> {
         let mut hand_features = Vec::new();
-        const MAX_HAND_COUNT: f32 = 18.0;
 
         for &player in &[PlayerId::Player1, PlayerId::Player2] {
             if let Some(hand) = board.hand.get(&player) {
-                for &kind in &[
-                    PieceKind::S_Pawn,
-                    PieceKind::S_Lance,
-                    PieceKind::S_Knight,
-                    PieceKind::S_Silver,
-                    PieceKind::S_Gold,
-                    PieceKind::S_Bishop,
-                    PieceKind::S_Rook,
-                    PieceKind::C_Pawn,
-                    PieceKind::C_Knight,
-                    PieceKind::C_Bishop,
-                    PieceKind::C_Rook,
-                ] {
-                    let count = hand.get(&kind).copied().unwrap_or(0) as f32;
+                for &kind in &HAND_KINDS {
+                    // Custom pieces in hand count as their built-in kind
+                    let count = hand
+                        .iter()
+                        .filter(|(k, _)| k.builtin() == kind)
+                        .map(|(_, &c)| c)
+                        .sum::<usize>() as f32;
                     let normalized = (count / MAX_HAND_COUNT).min(1.0);
 
                     // Flip perspective if needed
@@ -98,28 +211,11 @@ impl BoardFeatureExtractor {
 
     /// Map piece kind and owner to feature index
     fn piece_to_index(kind: PieceKind, owner: PlayerId, perspective: PlayerId) -> usize {
-        let base_idx = match kind {
-            PieceKind::S_Pawn => 1,
-            PieceKind::S_Lance => 2,
-            PieceKind::S_Knight => 3,
-            PieceKind::S_Silver => 4,
-            PieceKind::S_Gold => 5,
-            PieceKind::S_Bishop => 6,
-            PieceKind::S_Rook => 7,
-            PieceKind::S_King => 8,
-            PieceKind::S_ProPawn => 9,
-            PieceKind::S_ProLance => 10,
-            PieceKind::S_ProKnight => 11,
-            PieceKind::S_ProSilver => 12,
-            PieceKind::S_ProBishop => 13,
-            PieceKind::S_ProRook => 14,
-            PieceKind::C_Pawn => 15,
-            PieceKind::C_Knight => 16,
-            PieceKind::C_Bishop => 17,
-            PieceKind::C_Rook => 18,
-            PieceKind::C_Queen => 19,
-            PieceKind::C_King => 20,
-        };
+        let kind = kind.builtin();
+        let base_idx = 1 + SQUARE_KINDS
+            .iter()
+            .position(|&k| k == kind)
+            .expect("every piece kind has a square feature");
 
         // Offset by 20 if piece belongs to opponent (from perspective)
         if owner == perspective {
@@ -129,10 +225,34 @@ impl BoardFeatureExtractor {
         }
     }
 
+    /// Recover the pieces on the board and in hand from extracted features
+    pub fn decode_pieces(features: &[f32]) -> DecodedPieces {
+        let mut decoded = DecodedPieces::default();
+        let squares = FEATURE_BOARD_SIZE * FEATURE_BOARD_SIZE;
+        for square in features.chunks(NUM_PIECE_TYPES).take(squares) {
+            if let Some(idx) = square.iter().position(|&v| v > 0.5).filter(|&i| i > 0) {
+                let own = idx <= SQUARE_KINDS.len();
+                decoded
+                    .on_board
+                    .push((SQUARE_KINDS[(idx - 1) % SQUARE_KINDS.len()], own));
+            }
+        }
+
+        let hand = features.iter().skip(squares * NUM_PIECE_TYPES);
+        for (i, &value) in hand.take(2 * HAND_KINDS.len()).enumerate() {
+            let count = (value.abs() * MAX_HAND_COUNT).round() as usize;
+            let kind = HAND_KINDS[i % HAND_KINDS.len()];
+            decoded
+                .in_hand
+                .extend(std::iter::repeat_n((kind, value > 0.0), count));
+        }
+        decoded
+    }
+
     /// Get the expected feature vector size
     pub fn feature_size() -> usize {
         // Board: 9*9*41 + Hand: 2*11 + Turn: 1
-        9 * 9 * NUM_PIECE_TYPES + 2 * 11 + 1
+        FEATURE_BOARD_SIZE * FEATURE_BOARD_SIZE * NUM_PIECE_TYPES + 2 * 11 + 1
     }
 }
 
@@ -146,10 +266,72 @@ mod tests {
         assert_eq!(BoardFeatureExtractor::feature_size(), expected);
     }
 
+    #[test]
+    fn test_model_feature_version_check() {
+        let current = FEATURE_VERSION.to_string();
+        assert!(BoardFeatureExtractor::check_model_version(Some(&current)).is_ok());
+        let older = BoardFeatureExtractor::check_model_version(Some("0")).unwrap_err();
+        assert!(older.to_string().contains("feature version 0"));
+        assert!(BoardFeatureExtractor::check_model_version(Some("v1")).is_err());
+        assert!(BoardFeatureExtractor::check_model_version(None).is_err());
+    }
+
+    #[test]
+    fn test_decode_pieces_roundtrip() {
+        let mut board = crate::core::setup::setup_from_strings(
+            &crate::core::setup::get_shogi_setup(),
+            true,
+            true,
+            Some(true),
+            Some(true),
+        );
+        board
+            .hand
+            .entry(PlayerId::Player2)
+            .or_default()
+            .insert(PieceKind::S_Gold, 2);
+        let features = BoardFeatureExtractor::extract(&board, PlayerId::Player2);
+        let decoded = BoardFeatureExtractor::decode_pieces(&features);
+
+        assert_eq!(decoded.on_board.len(), board.pieces.len());
+        let own = decoded.on_board.iter().filter(|&&(_, own)| own).count();
+        let p2 = board
+            .pieces
+            .values()
+            .filter(|p| p.owner == PlayerId::Player2)
+            .count();
+        assert_eq!(own, p2);
+        assert_eq!(decoded.in_hand, [(PieceKind::S_Gold, true); 2]);
+    }
+
     #[test]
     fn test_extract_empty_board() {
         let board = Board::new(9, 9);
         let features = BoardFeatureExtractor::extract(&board, PlayerId::Player1);
         assert_eq!(features.len(), BoardFeatureExtractor::feature_size());
     }
+
+    #[test]
+    fn test_smaller_board_sits_in_the_grid() {
+        let board = crate::core::setup::BoardSetupType::ChessOnly.create_board();
+        assert!(BoardFeatureExtractor::fits(&board));
+        let features = BoardFeatureExtractor::extract(&board, PlayerId::Player1);
+        assert_eq!(features.len(), BoardFeatureExtractor::feature_size());
+        assert_eq!(
+            BoardFeatureExtractor::decode_pieces(&features)
+                .on_board
+                .len(),
+            32
+        );
+        // The king on (4, 7) is at its 9x9 grid index
+        let king =
+            BoardFeatureExtractor::square_feature(&board, Position::new(4, 7), PlayerId::Player1);
+        assert_eq!(features[king], 1.0);
+        // Squares off the 8x8 board set no feature, not even "empty"
+        let off_board = (8 * FEATURE_BOARD_SIZE + 8) * NUM_PIECE_TYPES;
+        assert!(features[off_board..off_board + NUM_PIECE_TYPES]
+            .iter()
+            .all(|&v| v == 0.0));
+        assert!(!BoardFeatureExtractor::fits(&Board::new(10, 10)));
+    }
 }
@@ -1,5 +1,21 @@
+pub mod batch;
 pub mod features;
 pub mod model_registry;
-
-#[cfg(feature = "ml")]
 pub mod nn_evaluator;
+pub mod nnue;
+pub mod onnx;
+#[cfg(feature = "cli")]
+pub mod train;
+
+/// Whether this build includes ONNX Runtime (the `ml` feature)
+pub const ML_ENABLED: bool = cfg!(feature = "ml");
+
+/// Shown when the NeuralNetwork evaluator is requested in a build without `ml`
+pub const ML_UNAVAILABLE_MESSAGE: &str =
+    "compiled without ml — NeuralNetwork evaluator unavailable, using Handcrafted";
+
+/// Print [`ML_UNAVAILABLE_MESSAGE`] once per process
+pub fn warn_ml_unavailable() {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| eprintln!("[ML] {}\r", ML_UNAVAILABLE_MESSAGE));
+}
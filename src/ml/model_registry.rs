@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 #[cfg(feature = "ml")]
 use ort::session::Session;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ModelType {
     ONNX,
@@ -17,6 +19,157 @@ pub struct ModelMetadata {
     pub path: PathBuf,
     pub version: Option<String>,
     pub created_at: Option<String>,
+    /// Entry of the models directory's manifest, if it lists the model
+    pub manifest: Option<ManifestEntry>,
+}
+
+/// Provenance stamped into the ONNX custom metadata by `scripts/ml/train.py`
+/// or the `train` subcommand
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelLineage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// 0 for a model trained from scratch, parent's generation + 1 otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<u32>,
+    /// SHA-256 of the training data file (files concatenated in order when
+    /// trained on several)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_data_hash: Option<String>,
+    /// [`crate::ml::features::FEATURE_VERSION`] the model was trained with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_model: Option<String>,
+}
+
+impl ModelLineage {
+    /// Build from custom metadata lookups; unparsable numbers are treated as missing
+    pub fn from_custom(get: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            version: get("version"),
+            generation: get("generation").and_then(|v| v.parse().ok()),
+            training_data_hash: get("training_data_hash"),
+            feature_version: get(crate::ml::features::FEATURE_VERSION_KEY)
+                .and_then(|v| v.parse().ok()),
+            parent_model: get("parent_model"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Manifest in the root of a models directory (`models/manifest.json`)
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Training provenance of one model. Unlike [`ModelLineage`] it is kept
+/// outside the ONNX file, so facts learned after export (arena results) can
+/// be added later.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Positions the model was trained on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_positions: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_model: Option<String>,
+    /// Elo difference against the best model in the model's last arena match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arena_elo: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+impl ManifestEntry {
+    /// Take every field that `update` sets
+    fn merge(&mut self, update: ManifestEntry) {
+        self.training_positions = update.training_positions.or(self.training_positions);
+        self.parent_model = update.parent_model.or(self.parent_model.take());
+        self.arena_elo = update.arena_elo.or(self.arena_elo);
+        self.created_at = update.created_at.or(self.created_at.take());
+    }
+
+    /// One-line summary for model lists, e.g.
+    /// "parent Fair/v0.2.0, 120000 positions, Elo +35, 2026-10-01"
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(parent) = &self.parent_model {
+            parts.push(format!("parent {}", parent));
+        }
+        if let Some(positions) = self.training_positions {
+            parts.push(format!("{} positions", positions));
+        }
+        if let Some(elo) = self.arena_elo {
+            parts.push(format!("Elo {:+.0}", elo));
+        }
+        if let Some(created_at) = &self.created_at {
+            // Date part of an RFC 3339 timestamp
+            parts.push(created_at.chars().take(10).collect());
+        }
+        parts.join(", ")
+    }
+}
+
+/// `manifest.json`: provenance of the models under one models directory,
+/// keyed by path relative to it (e.g. `Fair/v0.3.0/model.onnx`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelManifest {
+    #[serde(default)]
+    pub models: BTreeMap<String, ManifestEntry>,
+}
+
+impl ModelManifest {
+    /// Read the manifest of `models_dir`; empty if there is none
+    pub fn load(models_dir: &Path) -> anyhow::Result<Self> {
+        let path = models_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(crate::util::read_json_or_skip(&path)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, models_dir: &Path) -> anyhow::Result<()> {
+        crate::util::atomic_write_json(&models_dir.join(MANIFEST_FILE), self, true)
+    }
+
+    /// Key of `model` in the manifest of `models_dir`
+    fn key(models_dir: &Path, model: &Path) -> String {
+        let relative = model.strip_prefix(models_dir).unwrap_or(model);
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn get(&self, models_dir: &Path, model: &Path) -> Option<&ManifestEntry> {
+        self.models.get(&Self::key(models_dir, model))
+    }
+
+    /// The directory whose manifest covers `model`: its nearest ancestor
+    /// named `models`
+    pub fn models_dir_of(model: &Path) -> Option<&Path> {
+        model
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.file_name().is_some_and(|name| name == "models"))
+    }
+
+    /// Merge `update` into the entry of `model` in the manifest of its models
+    /// directory. Models outside a `models` directory are not recorded.
+    pub fn record(model: &Path, update: ManifestEntry) -> anyhow::Result<()> {
+        let Some(models_dir) = Self::models_dir_of(model) else {
+            return Ok(());
+        };
+        let mut manifest = Self::load(models_dir)?;
+        manifest
+            .models
+            .entry(Self::key(models_dir, model))
+            .or_default()
+            .merge(update);
+        manifest.save(models_dir)
+    }
 }
 
 /// Registry for managing available ML models
@@ -55,77 +208,55 @@ impl ModelRegistry {
             return Ok(());
         }
 
+        let manifest = ModelManifest::load(models_dir).unwrap_or_default();
+
         // Use a stack for recursive traversal
         let mut dirs_to_visit = vec![models_dir.to_path_buf()];
 
         while let Some(current_dir) = dirs_to_visit.pop() {
             if let Ok(entries) = std::fs::read_dir(&current_dir) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
+                for entry in entries.flatten() {
+                    let path = entry.path();
+
+                    if path.is_dir() {
+                        dirs_to_visit.push(path);
+                        continue;
+                    }
 
-                        if path.is_dir() {
-                            dirs_to_visit.push(path);
-                            continue;
+                    let extension = path.extension().and_then(|s| s.to_str());
+                    let model_type = match extension {
+                        Some("onnx") => Some(ModelType::ONNX),
+                        _ => None,
+                    };
+
+                    if let Some(model_type) = model_type {
+                        // Calculate relative name from base directory
+                        let relative_path = path.strip_prefix(models_dir).unwrap_or(&path);
+                        let mut name = relative_path
+                            .with_extension("") // Remove .onnx
+                            .to_string_lossy()
+                            .into_owned();
+
+                        // Strip "model" from end if it exists (e.g. Fair/v1.0/model -> Fair/v1.0)
+                        if name.ends_with("/model") {
+                            name = name.strip_suffix("/model").unwrap().to_string();
+                        } else if name == "model" {
+                            // Keep it if it's just "model" at the root, or handle as needed
                         }
 
-                        let extension = path.extension().and_then(|s| s.to_str());
-                        let model_type = match extension {
-                            Some("onnx") => Some(ModelType::ONNX),
-                            _ => None,
+                        let version = Self::get_model_version(&path);
+                        let entry = manifest.get(models_dir, &path).cloned();
+
+                        let metadata = ModelMetadata {
+                            name: name.clone(),
+                            model_type,
+                            path: path.clone(),
+                            version,
+                            created_at: entry.as_ref().and_then(|e| e.created_at.clone()),
+                            manifest: entry,
                         };
 
-                        if let Some(model_type) = model_type {
-                            // Calculate relative name from base directory
-                            let relative_path = path.strip_prefix(models_dir).unwrap_or(&path);
-                            let mut name = relative_path
-                                .with_extension("") // Remove .onnx
-                                .to_string_lossy()
-                                .into_owned();
-
-                            // Strip "model" from end if it exists (e.g. Fair/v1.0/model -> Fair/v1.0)
-                            if name.ends_with("/model") {
-                                name = name.strip_suffix("/model").unwrap().to_string();
-                            } else if name == "model" {
-                                // Keep it if it's just "model" at the root, or handle as needed
-                            }
-
-                            let version = {
-                                #[cfg(feature = "ml")]
-                                {
-                                    // Try to load session to read metadata
-                                    if let Ok(session) =
-                                        Session::builder().and_then(|b| b.commit_from_file(&path))
-                                    {
-                                        if let Ok(metadata) = session.metadata() {
-                                            if let Ok(Some(version)) = metadata.custom("version") {
-                                                Some(version)
-                                            } else {
-                                                None
-                                            }
-                                        } else {
-                                            None
-                                        }
-                                    } else {
-                                        None
-                                    }
-                                }
-                                #[cfg(not(feature = "ml"))]
-                                {
-                                    None
-                                }
-                            };
-
-                            let metadata = ModelMetadata {
-                                name: name.clone(),
-                                model_type,
-                                path: path.clone(),
-                                version,
-                                created_at: None,
-                            };
-
-                            self.register(metadata);
-                        }
+                        self.register(metadata);
                     }
                 }
             }
@@ -136,12 +267,21 @@ impl ModelRegistry {
 
     /// Helper to read version from an ONNX file
     pub fn get_model_version(path: &Path) -> Option<String> {
+        Self::get_model_lineage(path).and_then(|lineage| lineage.version)
+    }
+
+    /// Read the lineage stamped into an ONNX file.
+    /// `None` if the model cannot be loaded or carries no lineage at all.
+    #[cfg_attr(not(feature = "ml"), allow(unused_variables))]
+    pub fn get_model_lineage(path: &Path) -> Option<ModelLineage> {
         #[cfg(feature = "ml")]
         {
             if let Ok(session) = Session::builder().and_then(|b| b.commit_from_file(path)) {
                 if let Ok(metadata) = session.metadata() {
-                    if let Ok(Some(version)) = metadata.custom("version") {
-                        return Some(version);
+                    let lineage =
+                        ModelLineage::from_custom(|key| metadata.custom(key).ok().flatten());
+                    if !lineage.is_empty() {
+                        return Some(lineage);
                     }
                 }
             }
@@ -170,6 +310,7 @@ mod tests {
             path: PathBuf::from("models/test.onnx"),
             version: Some("1.0".to_string()),
             created_at: None,
+            manifest: None,
         };
 
         registry.register(metadata.clone());
@@ -180,4 +321,71 @@ mod tests {
         );
         assert_eq!(registry.list().len(), 1);
     }
+
+    #[test]
+    fn test_lineage_from_custom_metadata() {
+        let lineage = ModelLineage::from_custom(|key| match key {
+            "version" => Some("0.4.0".to_string()),
+            "generation" => Some("3".to_string()),
+            "feature_version" => Some("x".to_string()),
+            _ => None,
+        });
+        assert_eq!(lineage.version.as_deref(), Some("0.4.0"));
+        assert_eq!(lineage.generation, Some(3));
+        assert_eq!(lineage.feature_version, None);
+        assert!(!lineage.is_empty());
+        assert!(ModelLineage::from_custom(|_| None).is_empty());
+    }
+
+    #[test]
+    fn test_manifest_record_merges_and_discovery_reads_it() {
+        let root = std::env::temp_dir().join(format!("aho_manifest_{}", std::process::id()));
+        let models_dir = root.join("models");
+        let model = models_dir.join("Fair").join("v0.2.0").join("model.onnx");
+        std::fs::create_dir_all(model.parent().unwrap()).unwrap();
+        std::fs::write(&model, b"").unwrap();
+
+        ModelManifest::record(
+            &model,
+            ManifestEntry {
+                training_positions: Some(1200),
+                parent_model: Some("models/Fair/v0.1.0/model.onnx".to_string()),
+                created_at: Some("2026-10-01T12:00:00+09:00".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        ModelManifest::record(
+            &model,
+            ManifestEntry {
+                arena_elo: Some(35.2),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // Outside a models directory: nothing recorded
+        ModelManifest::record(&root.join("loose.onnx"), ManifestEntry::default()).unwrap();
+
+        let manifest = ModelManifest::load(&models_dir).unwrap();
+        let mut registry = ModelRegistry::new();
+        registry.discover_models(&models_dir).unwrap();
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!(
+            manifest.models.keys().collect::<Vec<_>>(),
+            ["Fair/v0.2.0/model.onnx"]
+        );
+        let entry = registry
+            .get("Fair/v0.2.0")
+            .unwrap()
+            .manifest
+            .clone()
+            .unwrap();
+        assert_eq!(entry.training_positions, Some(1200));
+        assert_eq!(entry.arena_elo, Some(35.2));
+        assert_eq!(
+            entry.summary(),
+            "parent models/Fair/v0.1.0/model.onnx, 1200 positions, Elo +35, 2026-10-01"
+        );
+    }
 }
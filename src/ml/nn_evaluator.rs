@@ -1,19 +1,71 @@
 //! Neural Network Evaluator using ONNX Runtime
+//!
+//! Evaluators loading the same model share one session behind an
+//! [`InferenceBatcher`], so positions from concurrent games are run through
+//! the model together.
+//!
+//! Int8-quantized models are detected when loading. Dynamically quantized
+//! models keep a float interface and need nothing else; models with integer
+//! inputs or outputs get their features quantized and their value
+//! dequantized with the scale and zero point stored in the model metadata.
 
 #[cfg(feature = "ml")]
-use ndarray::Array1;
+use ndarray::Array2;
 #[cfg(feature = "ml")]
-use ort::{inputs, session::Session, value::Value};
+use once_cell::sync::Lazy;
 #[cfg(feature = "ml")]
-use std::sync::Mutex;
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, DirectMLExecutionProvider, ExecutionProvider,
+};
+#[cfg(feature = "ml")]
+use ort::{
+    inputs,
+    session::{builder::SessionBuilder, Session},
+    value::Value,
+};
+#[cfg(feature = "ml")]
+use std::collections::HashMap;
+#[cfg(feature = "ml")]
+use std::sync::{Arc, Mutex, Weak};
 
-use crate::core::{Board, PlayerId};
-use crate::ml::features::BoardFeatureExtractor;
+use crate::core::Board;
+#[cfg(feature = "ml")]
+use crate::core::PlayerId;
+#[cfg(feature = "ml")]
+use crate::ml::batch::{BatchFn, InferenceBatcher};
+#[cfg(feature = "ml")]
+use crate::ml::features::{BoardFeatureExtractor, FEATURE_VERSION_KEY};
+#[cfg(feature = "ml")]
+use crate::ml::onnx::{ElementType, QuantParams};
+#[cfg(feature = "ml")]
+use crate::player::ai::config::{ExecutionProviderKind, NNRuntimeConfig};
 use crate::player::ai::evaluator::Evaluator;
 
+/// A loaded model and the batcher in front of its session
+#[cfg(feature = "ml")]
+struct SharedModel {
+    batcher: InferenceBatcher,
+    version: Option<String>,
+    provider: ExecutionProviderKind,
+    quantized: bool,
+}
+
+/// Conversion of the model's integer input and output, if any
+#[cfg(feature = "ml")]
+#[derive(Clone, Copy)]
+struct IoQuantization {
+    input: Option<QuantParams>,
+    output: Option<QuantParams>,
+}
+
+/// Models currently loaded, by path; dropped with their last evaluator
+#[cfg(feature = "ml")]
+static SHARED_MODELS: Lazy<Mutex<HashMap<String, Weak<SharedModel>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 #[cfg(feature = "ml")]
 pub struct NNEvaluator {
-    session: Mutex<Session>,
+    model: Arc<SharedModel>,
     model_name: String,
 }
 
@@ -30,25 +82,18 @@ impl NNEvaluator {
     }
 
     fn load_internal(model_path: &str, silent: bool) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut builder = Session::builder()?;
-
-        // Enable DirectML for GPU acceleration (AMD/NVIDIA/Intel on Windows)
-        #[cfg(target_os = "windows")]
-        {
-            if let Err(e) =
-                builder.with_execution_providers([ort::ExecutionProviderDispatch::DirectML(
-                    Default::default(),
-                )])
-            {
-                if !silent {
-                    eprintln!("[ML] Warning: DirectML not available, using CPU: {}\r", e);
-                }
-            } else if !silent {
-                eprintln!("[ML] GPU acceleration enabled (DirectML)\r");
+        // Hold the lock while loading so parallel games don't load the same model twice
+        let mut shared = SHARED_MODELS.lock().unwrap();
+        let model = match shared.get(model_path).and_then(Weak::upgrade) {
+            Some(model) => model,
+            None => {
+                let model = Arc::new(Self::load_model(model_path, silent)?);
+                shared.retain(|_, m| m.strong_count() > 0);
+                shared.insert(model_path.to_string(), Arc::downgrade(&model));
+                model
             }
-        }
-
-        let session = builder.commit_from_file(model_path)?;
+        };
+        drop(shared);
 
         // Clean up name for display (remove models/ prefix and .onnx / /model suffix)
         let name_trimmed = model_path
@@ -63,69 +108,197 @@ impl NNEvaluator {
             .trim_end_matches('/')
             .to_string();
 
-        let mut version_str = None;
-        if let Ok(metadata) = session.metadata() {
-            if let Ok(Some(version)) = metadata.custom("version") {
-                version_str = Some(version);
-            }
+        if !silent {
+            let version = match model.version {
+                Some(ref v) => format!("v{}", v),
+                None => "no version".to_string(),
+            };
+            let quantized = if model.quantized { ", int8" } else { "" };
+            eprintln!(
+                "[ML] Loaded model: {} ({}{})\r",
+                name_trimmed, version, quantized
+            );
         }
 
+        Ok(Self {
+            model,
+            model_name: name_trimmed,
+        })
+    }
+
+    fn load_model(
+        model_path: &str,
+        silent: bool,
+    ) -> Result<SharedModel, Box<dyn std::error::Error>> {
+        // Before creating a session: a model for another feature layout loads fine
+        // but evaluates garbage
+        let metadata = crate::ml::onnx::read_metadata(std::path::Path::new(model_path))?;
+        BoardFeatureExtractor::check_model_version(
+            metadata.get(FEATURE_VERSION_KEY).map(String::as_str),
+        )?;
+        let quantization = crate::ml::onnx::read_quantization(std::path::Path::new(model_path))?;
+        let params = |prefix, element| match element {
+            ElementType::Float => Ok(None),
+            element => QuantParams::from_metadata(&metadata, prefix, element).map(Some),
+        };
+        let io = IoQuantization {
+            input: params("input", quantization.input)?,
+            output: params("output", quantization.output)?,
+        };
+
+        let runtime = &crate::player::ai::config::AIConfig::get()
+            .evaluation
+            .nn_runtime;
+        let mut builder = Session::builder()?;
+        if runtime.intra_threads > 0 {
+            builder = builder.with_intra_threads(runtime.intra_threads)?;
+        }
+        if runtime.inter_threads > 0 {
+            builder = builder.with_inter_threads(runtime.inter_threads)?;
+        }
+        let provider = Self::register_execution_provider(&mut builder, runtime, silent);
         if !silent {
-            if let Some(ref v) = version_str {
-                eprintln!("[ML] Loaded model: {} (v{})\r", name_trimmed, v);
-            } else {
-                eprintln!("[ML] Loaded model: {} (no version)\r", name_trimmed);
+            eprintln!("[ML] Execution provider: {}\r", provider.label());
+        }
+
+        let mut session = builder.commit_from_file(model_path)?;
+
+        let mut version = None;
+        if let Ok(metadata) = session.metadata() {
+            if let Ok(Some(v)) = metadata.custom("version") {
+                version = Some(v);
             }
         }
 
-        Ok(Self {
-            session: Mutex::new(session),
-            model_name: name_trimmed,
+        let feature_size = BoardFeatureExtractor::feature_size();
+        let run: BatchFn = Box::new(move |features, count| {
+            Self::run_inference(&mut session, io, features, count, feature_size)
+                .map_err(|e| e.to_string())
+        });
+        let config = &crate::player::ai::config::AIConfig::get()
+            .evaluation
+            .nn_batch;
+        Ok(SharedModel {
+            batcher: InferenceBatcher::new(feature_size, config, run),
+            version,
+            provider,
+            quantized: quantization.is_quantized(),
         })
     }
 
-    /// Run inference on board state
-    fn run_inference(&self, features: &[f32]) -> Result<f32, Box<dyn std::error::Error>> {
-        // Create input array with batch dimension [1, features_size]
-        let input_array: Array1<f32> = Array1::from_vec(features.to_vec());
-        let input_array = input_array.insert_axis(ndarray::Axis(0));
+    /// Register the first usable provider among those the config asks for and
+    /// return it, or `Cpu` when none could be registered. Failures of an
+    /// explicitly requested provider are reported even when `silent`.
+    fn register_execution_provider(
+        builder: &mut SessionBuilder,
+        runtime: &NNRuntimeConfig,
+        silent: bool,
+    ) -> ExecutionProviderKind {
+        let explicit = runtime.execution_provider != ExecutionProviderKind::Auto;
+        for &kind in runtime.execution_provider.candidates() {
+            let provider: Box<dyn ExecutionProvider> = match kind {
+                ExecutionProviderKind::Cuda => Box::new(CUDAExecutionProvider::default()),
+                ExecutionProviderKind::DirectML => Box::new(DirectMLExecutionProvider::default()),
+                ExecutionProviderKind::CoreML => Box::new(CoreMLExecutionProvider::default()),
+                ExecutionProviderKind::Auto | ExecutionProviderKind::Cpu => continue,
+            };
+            let result = if !provider.supported_by_platform() {
+                Err("not supported on this platform".to_string())
+            } else {
+                match provider.is_available() {
+                    Ok(true) => provider
+                        .register(builder)
+                        .map_err(|e| ort::Error::from(e).to_string()),
+                    Ok(false) => Err("not included in this ONNX Runtime build".to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            };
+            match result {
+                Ok(()) => return kind,
+                Err(e) if explicit || !silent => {
+                    eprintln!("[ML] {} unavailable ({}), trying next\r", kind.label(), e)
+                }
+                Err(_) => {}
+            }
+        }
+        if explicit {
+            eprintln!("[ML] Falling back to CPU execution\r");
+        }
+        ExecutionProviderKind::Cpu
+    }
 
-        // Create Value from array
-        let input_value = Value::from_array(input_array)?;
+    /// Run inference on `count` positions at once
+    fn run_inference(
+        session: &mut Session,
+        io: IoQuantization,
+        features: &[f32],
+        count: usize,
+        feature_size: usize,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        // Input array [count, features_size]
+        let shape = (count, feature_size);
+        let quantized = |q: QuantParams| features.iter().map(move |&x| q.quantize(x));
+        let input_value = match io.input {
+            None => {
+                Value::from_array(Array2::from_shape_vec(shape, features.to_vec())?)?.into_dyn()
+            }
+            Some(q) if q.element == ElementType::Uint8 => Value::from_array(
+                Array2::from_shape_vec(shape, quantized(q).map(|v| v as u8).collect())?,
+            )?
+            .into_dyn(),
+            Some(q) => Value::from_array(Array2::from_shape_vec(
+                shape,
+                quantized(q).map(|v| v as i8).collect(),
+            )?)?
+            .into_dyn(),
+        };
 
-        // Run inference (lock mutex to get mutable access)
-        let mut session = self.session.lock().unwrap();
         let outputs = session.run(inputs!["board_features" => input_value])?;
 
         // Extract value from output (second output is value head)
         let value_tensor = outputs.get("value").ok_or("Could not find value output")?;
 
-        // Extract tensor data
-        let (_shape, data) = value_tensor.try_extract_tensor::<f32>()?;
-        let value = data.first().copied().unwrap_or(0.0);
+        // Extract tensor data, [count, 1]
+        let values = match io.output {
+            None => value_tensor.try_extract_tensor::<f32>()?.1.to_vec(),
+            Some(q) if q.element == ElementType::Uint8 => {
+                let (_shape, data) = value_tensor.try_extract_tensor::<u8>()?;
+                data.iter().map(|&v| q.dequantize(v as i32)).collect()
+            }
+            Some(q) => {
+                let (_shape, data) = value_tensor.try_extract_tensor::<i8>()?;
+                data.iter().map(|&v| q.dequantize(v as i32)).collect()
+            }
+        };
+        Ok(values)
+    }
+
+    /// Execution provider the model's session actually runs on
+    pub fn execution_provider(&self) -> ExecutionProviderKind {
+        self.model.provider
+    }
 
-        Ok(value)
+    /// Whether the model is int8-quantized
+    pub fn is_quantized(&self) -> bool {
+        self.model.quantized
+    }
+
+    fn to_score(value: f32) -> i32 {
+        (value * 10000.0) as i32
     }
 }
 
 #[cfg(feature = "ml")]
 impl Evaluator for NNEvaluator {
     fn evaluate(&mut self, board: &Board) -> i32 {
+        if !BoardFeatureExtractor::fits(board) {
+            return crate::player::ai::eval::evaluate(board);
+        }
         let features = BoardFeatureExtractor::extract(board, PlayerId::Player1);
 
-        // Use static counter to show occasional confirmation
-        static EVAL_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
-        let count = EVAL_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-        match self.run_inference(&features) {
-            Ok(value) => {
-                let score = (value * 10000.0) as i32;
-                // Show confirmation every 1000 evaluations to indicate activity
-                // if count > 0 && count % 10000 == 0 {
-                //     eprintln!("[ML] Running: {} evals, last_val={:.4}\r", count, value);
-                // }
-                score
-            }
+        let _timer = crate::profile::timer(crate::profile::Subsystem::NNInference);
+        match self.model.batcher.evaluate(features) {
+            Ok(value) => Self::to_score(value),
             Err(e) => {
                 eprintln!("[ERROR] NN inference failed: {}\r", e);
                 0
@@ -133,12 +306,32 @@ impl Evaluator for NNEvaluator {
         }
     }
 
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<i32> {
+        if !boards.iter().all(BoardFeatureExtractor::fits) {
+            return boards.iter().map(|board| self.evaluate(board)).collect();
+        }
+        let features: Vec<f32> = boards
+            .iter()
+            .flat_map(|board| BoardFeatureExtractor::extract(board, PlayerId::Player1))
+            .collect();
+
+        let _timer = crate::profile::timer(crate::profile::Subsystem::NNInference);
+        match self.model.batcher.evaluate_many(features, boards.len()) {
+            Ok(values) => values.into_iter().map(Self::to_score).collect(),
+            Err(e) => {
+                eprintln!("[ERROR] NN inference failed: {}\r", e);
+                vec![0; boards.len()]
+            }
+        }
+    }
+
     fn name(&self) -> String {
-        format!("NeuralNetwork ({})", self.model_name)
+        let quantized = if self.model.quantized { ", int8" } else { "" };
+        format!("NeuralNetwork ({}{})", self.model_name, quantized)
     }
 }
 
-// Stub when ml feature disabled
+// Stub when ml feature disabled: loading always fails so callers fall back to Handcrafted
 #[cfg(not(feature = "ml"))]
 pub struct NNEvaluator {
     pub model_name: String,
@@ -149,6 +342,10 @@ impl NNEvaluator {
     pub fn load(_model_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         Err("ML feature not enabled. Rebuild with --features ml".into())
     }
+
+    pub fn load_silent(model_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load(model_path)
+    }
 }
 
 #[cfg(not(feature = "ml"))]
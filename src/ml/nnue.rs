@@ -0,0 +1,380 @@
+//! NNUE-style CPU evaluator
+//!
+//! Runs the value MLPs the in-crate trainer exports (see [`crate::ml::onnx`])
+//! without ONNX Runtime. The first layer sees the sparse one-hot board
+//! features of [`BoardFeatureExtractor`], so its output (the accumulator) is
+//! kept up to date as the search makes and unmakes moves: a move changes at
+//! most two squares and one hand count, i.e. a handful of weight columns,
+//! instead of a full 3344-wide matrix product. Only the small layers behind
+//! the accumulator are evaluated per position, which makes the network cheap
+//! enough to call at every leaf of the alpha-beta search.
+//!
+//! Scores match [`crate::ml::nn_evaluator::NNEvaluator`] on the same model
+//! (up to float rounding): features from Player1's perspective, value scaled
+//! by 10000.
+
+use crate::core::{Board, Move, PlayerId};
+use crate::ml::features::{BoardFeatureExtractor, FEATURE_VERSION_KEY};
+use crate::ml::onnx::{self, DenseLayer};
+use crate::player::ai::evaluator::Evaluator;
+use std::path::Path;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Features are extracted from this side's point of view, as the ONNX
+/// evaluator does
+const PERSPECTIVE: PlayerId = PlayerId::Player1;
+
+/// A value MLP with its first layer stored by input column
+pub struct NnueNetwork {
+    /// First-layer weights, `[features, hidden]`: the column of feature `f`
+    /// is `columns[f * hidden..(f + 1) * hidden]`
+    columns: Vec<f32>,
+    bias: Vec<f32>,
+    /// Layers after the first one
+    layers: Vec<DenseLayer>,
+}
+
+/// Networks currently loaded, by path; dropped with their last evaluator
+static NETWORKS: Mutex<Vec<(String, Weak<NnueNetwork>)>> = Mutex::new(Vec::new());
+
+impl NnueNetwork {
+    pub fn from_layers(mut layers: Vec<DenseLayer>) -> anyhow::Result<Self> {
+        if layers.is_empty() {
+            anyhow::bail!("A model needs at least one layer");
+        }
+        let first = layers.remove(0);
+        if first.inputs != BoardFeatureExtractor::feature_size() {
+            anyhow::bail!(
+                "model takes {} features, the extractor produces {}",
+                first.inputs,
+                BoardFeatureExtractor::feature_size()
+            );
+        }
+        let mut columns = vec![0.0; first.inputs * first.outputs];
+        for (o, row) in first.weight.chunks_exact(first.inputs).enumerate() {
+            for (f, &w) in row.iter().enumerate() {
+                columns[f * first.outputs + o] = w;
+            }
+        }
+        Ok(Self {
+            columns,
+            bias: first.bias,
+            layers,
+        })
+    }
+
+    /// Load a model written by `train` (or any graph [`onnx::read_value_mlp`]
+    /// accepts), refusing models trained on another feature layout
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let metadata = onnx::read_metadata(path)?;
+        BoardFeatureExtractor::check_model_version(
+            metadata.get(FEATURE_VERSION_KEY).map(String::as_str),
+        )?;
+        Self::from_layers(onnx::read_value_mlp(path)?)
+    }
+
+    /// Load `path`, sharing the weights with evaluators that already did
+    pub fn load_shared(path: &str) -> anyhow::Result<Arc<Self>> {
+        let mut networks = NETWORKS.lock().unwrap();
+        networks.retain(|(_, network)| network.strong_count() > 0);
+        if let Some(network) = networks
+            .iter()
+            .find(|(loaded, _)| loaded == path)
+            .and_then(|(_, network)| network.upgrade())
+        {
+            return Ok(network);
+        }
+        let network = Arc::new(Self::load(Path::new(path))?);
+        networks.push((path.to_string(), Arc::downgrade(&network)));
+        Ok(network)
+    }
+
+    fn hidden(&self) -> usize {
+        self.bias.len()
+    }
+
+    fn add_column(&self, accumulator: &mut [f32], feature: usize, scale: f32) {
+        let hidden = self.hidden();
+        let column = &self.columns[feature * hidden..(feature + 1) * hidden];
+        for (a, &w) in accumulator.iter_mut().zip(column) {
+            *a += scale * w;
+        }
+    }
+
+    /// First-layer output for `board`, from scratch
+    pub fn refresh(&self, board: &Board, accumulator: &mut Vec<f32>) {
+        accumulator.clear();
+        accumulator.extend_from_slice(&self.bias);
+        for y in 0..board.height {
+            for x in 0..board.width {
+                let pos = crate::core::Position::new(x, y);
+                let feature = BoardFeatureExtractor::square_feature(board, pos, PERSPECTIVE);
+                self.add_column(accumulator, feature, 1.0);
+            }
+        }
+        let offset = BoardFeatureExtractor::hand_offset();
+        let hand = BoardFeatureExtractor::encode_hand(board, PERSPECTIVE);
+        for (i, &value) in hand.iter().enumerate() {
+            if value != 0.0 {
+                self.add_column(accumulator, offset + i, value);
+            }
+        }
+        // Side to move, always set
+        self.add_column(accumulator, offset + hand.len(), 1.0);
+    }
+
+    /// Turn `parent`'s accumulator into `child`'s, where `child` is `parent`
+    /// after `mv`
+    pub fn update(&self, accumulator: &mut [f32], parent: &Board, mv: &Move, child: &Board) {
+        let en_passant = crate::logic::en_passant_capture(parent, mv);
+        let (squares, hand_changed) = match *mv {
+            Move::Normal { from, to, .. } => (
+                [Some(from), Some(to), en_passant],
+                parent.get_piece(to).is_some() || en_passant.is_some(),
+            ),
+            Move::Drop { to, .. } => ([Some(to), None, None], true),
+        };
+        for pos in squares.into_iter().flatten() {
+            let before = BoardFeatureExtractor::square_feature(parent, pos, PERSPECTIVE);
+            let after = BoardFeatureExtractor::square_feature(child, pos, PERSPECTIVE);
+            if before != after {
+                self.add_column(accumulator, before, -1.0);
+                self.add_column(accumulator, after, 1.0);
+            }
+        }
+        if hand_changed {
+            let offset = BoardFeatureExtractor::hand_offset();
+            let before = BoardFeatureExtractor::encode_hand(parent, PERSPECTIVE);
+            let after = BoardFeatureExtractor::encode_hand(child, PERSPECTIVE);
+            for (i, (b, a)) in before.iter().zip(&after).enumerate() {
+                if a != b {
+                    self.add_column(accumulator, offset + i, a - b);
+                }
+            }
+        }
+    }
+
+    /// Network output in [-1, 1] for a position with this accumulator
+    pub fn output(&self, accumulator: &[f32]) -> f32 {
+        if self.layers.is_empty() {
+            return accumulator[0].tanh();
+        }
+        let mut input: Vec<f32> = accumulator.iter().map(|&a| a.max(0.0)).collect();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let last = i + 1 == self.layers.len();
+            input = layer
+                .weight
+                .chunks_exact(layer.inputs)
+                .zip(&layer.bias)
+                .map(|(row, &bias)| {
+                    let sum = bias + row.iter().zip(&input).map(|(w, x)| w * x).sum::<f32>();
+                    if last {
+                        sum.tanh()
+                    } else {
+                        sum.max(0.0)
+                    }
+                })
+                .collect();
+        }
+        input[0]
+    }
+}
+
+/// Accumulator of one position, identified by its Zobrist hash
+#[derive(Default)]
+struct Accumulator {
+    hash: u64,
+    values: Vec<f32>,
+}
+
+/// [`Evaluator`] on an [`NnueNetwork`] with one accumulator per search ply
+pub struct NnueEvaluator {
+    network: Arc<NnueNetwork>,
+    model_name: String,
+    /// Accumulators of the positions on the current search path; `len` of
+    /// them are in use, the rest are kept to reuse their buffers
+    stack: Vec<Accumulator>,
+    len: usize,
+    /// Last position evaluated from scratch (normally the search root)
+    root: Accumulator,
+}
+
+impl NnueEvaluator {
+    pub fn load(model_path: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            NnueNetwork::load_shared(model_path)?,
+            Path::new(model_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| model_path.to_string()),
+        ))
+    }
+
+    pub fn new(network: Arc<NnueNetwork>, model_name: String) -> Self {
+        Self {
+            network,
+            model_name,
+            stack: Vec::new(),
+            len: 0,
+            root: Accumulator::default(),
+        }
+    }
+
+    /// Accumulator of `board` if it is the top of the stack or the root
+    fn cached(&self, board: &Board) -> Option<&[f32]> {
+        let top = self.len.checked_sub(1).map(|i| &self.stack[i]);
+        [top, Some(&self.root)]
+            .into_iter()
+            .flatten()
+            .find(|a| a.hash == board.zobrist_hash && !a.values.is_empty())
+            .map(|a| a.values.as_slice())
+    }
+
+    fn refresh_root(&mut self, board: &Board) {
+        self.network.refresh(board, &mut self.root.values);
+        self.root.hash = board.zobrist_hash;
+    }
+}
+
+impl Evaluator for NnueEvaluator {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        if !BoardFeatureExtractor::fits(board) {
+            return crate::player::ai::eval::evaluate(board);
+        }
+        let _timer = crate::profile::timer(crate::profile::Subsystem::NNInference);
+        if self.cached(board).is_none() {
+            self.refresh_root(board);
+        }
+        let accumulator = self.cached(board).expect("accumulator was just refreshed");
+        (self.network.output(accumulator) * 10000.0) as i32
+    }
+
+    fn make_move(&mut self, parent: &Board, mv: &Move, child: &Board) {
+        if self.len == self.stack.len() {
+            self.stack.push(Accumulator::default());
+        }
+        if !BoardFeatureExtractor::fits(child) {
+            // Evaluated by the fallback: only keep the stack depth
+            self.stack[self.len].values.clear();
+            self.len += 1;
+            return;
+        }
+        if self.cached(parent).is_none() {
+            self.refresh_root(parent);
+        }
+        let mut next = std::mem::take(&mut self.stack[self.len]);
+        next.values.clear();
+        next.values.extend_from_slice(
+            self.cached(parent)
+                .expect("parent accumulator was refreshed"),
+        );
+        self.network.update(&mut next.values, parent, mv, child);
+        next.hash = child.zobrist_hash;
+        self.stack[self.len] = next;
+        self.len += 1;
+    }
+
+    fn unmake_move(&mut self) {
+        self.len = self.len.saturating_sub(1);
+    }
+
+    fn name(&self) -> String {
+        format!("NNUE ({})", self.model_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{apply_move, legal_moves};
+
+    /// Small random network over the real feature size
+    fn network() -> NnueNetwork {
+        let mut seed = 12345u64;
+        let mut next = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) as f32 / (1u64 << 31) as f32 - 0.5) * 0.2
+        };
+        let dense = |inputs: usize, outputs: usize, next: &mut dyn FnMut() -> f32| DenseLayer {
+            inputs,
+            outputs,
+            weight: (0..inputs * outputs).map(|_| next()).collect(),
+            bias: (0..outputs).map(|_| next()).collect(),
+        };
+        let features = BoardFeatureExtractor::feature_size();
+        NnueNetwork::from_layers(vec![
+            dense(features, 16, &mut next),
+            dense(16, 8, &mut next),
+            dense(8, 1, &mut next),
+        ])
+        .unwrap()
+    }
+
+    /// Plain dense forward pass over the extracted features
+    fn reference(network: &NnueNetwork, board: &Board) -> f32 {
+        let features = BoardFeatureExtractor::extract(board, PERSPECTIVE);
+        let hidden = network.hidden();
+        let accumulator: Vec<f32> = (0..hidden)
+            .map(|o| {
+                network.bias[o]
+                    + features
+                        .iter()
+                        .enumerate()
+                        .map(|(f, &x)| x * network.columns[f * hidden + o])
+                        .sum::<f32>()
+            })
+            .collect();
+        network.output(&accumulator)
+    }
+
+    #[test]
+    fn test_incremental_matches_full_evaluation() {
+        let network = Arc::new(network());
+        let mut evaluator = NnueEvaluator::new(network.clone(), "test".to_string());
+        let mut board = crate::core::setup::BoardSetupType::Fair.create_board();
+        let mut player = PlayerId::Player1;
+        let mut made = 0;
+
+        // Captures and drops along a deterministic game
+        for ply in 0..40 {
+            let moves = legal_moves(&board, player);
+            let Some(&mv) = moves
+                .iter()
+                .find(|mv| matches!(mv, Move::Drop { .. }))
+                .or_else(|| {
+                    moves.iter().find(|mv| match mv {
+                        Move::Normal { to, .. } => board.get_piece(*to).is_some(),
+                        _ => false,
+                    })
+                })
+                .or(moves.get(ply * 7 % moves.len().max(1)))
+            else {
+                break;
+            };
+            let child = apply_move(&board, &mv, player);
+            evaluator.make_move(&board, &mv, &child);
+            made += 1;
+
+            let incremental = evaluator.cached(&child).unwrap().to_vec();
+            let mut fresh = Vec::new();
+            network.refresh(&child, &mut fresh);
+            for (a, b) in incremental.iter().zip(&fresh) {
+                assert!((a - b).abs() < 1e-3, "ply {}: {} vs {}", ply, a, b);
+            }
+            let expected = (reference(&network, &child) * 10000.0) as i32;
+            assert!((evaluator.evaluate(&child) - expected).abs() <= 2);
+
+            board = child;
+            player = player.opponent();
+        }
+        assert!(made > 10);
+
+        // Unmaking returns to the earlier positions' accumulators
+        for _ in 0..made {
+            evaluator.unmake_move();
+        }
+        assert_eq!(evaluator.len, 0);
+    }
+}
@@ -0,0 +1,751 @@
+//! Minimal ONNX writer and reader
+//!
+//! Encodes just what the in-crate trainer needs (dense layers as `Gemm`
+//! followed by `Relu`, or `Tanh` on the last layer, float initializers and
+//! custom metadata) directly in protobuf wire format, so exporting a model
+//! needs neither protobuf nor ONNX tooling. The graph has the same interface
+//! as the models exported by `scripts/ml/train.py`: input `board_features`
+//! `[batch_size, features]` and output `value` `[batch_size, 1]`. The same
+//! kind of graph can be read back as dense layers for the CPU evaluator in
+//! [`crate::ml::nnue`].
+//!
+//! [`read_quantization`] inspects any model, including int8-quantized ones
+//! made by `scripts/ml/quantize.py`, so the evaluator can adapt its input and
+//! output conversion.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+const IR_VERSION: u64 = 8;
+const OPSET_VERSION: u64 = 13;
+const INPUT_NAME: &str = "board_features";
+const OUTPUT_NAME: &str = "value";
+
+// TensorProto.DataType.{FLOAT, UINT8, INT8} and AttributeProto.AttributeType.INT
+const FLOAT: u64 = 1;
+const UINT8: u64 = 2;
+const INT8: u64 = 3;
+const ATTRIBUTE_INT: u64 = 2;
+
+/// A fully connected layer, `y = x * weight^T + bias`
+pub struct DenseLayer {
+    pub inputs: usize,
+    pub outputs: usize,
+    /// Row-major `[outputs, inputs]`
+    pub weight: Vec<f32>,
+    pub bias: Vec<f32>,
+}
+
+/// Protobuf message being encoded
+#[derive(Default)]
+struct Proto(Vec<u8>);
+
+impl Proto {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn uint(&mut self, field: u32, value: u64) {
+        self.varint((field as u64) << 3);
+        self.varint(value);
+    }
+
+    fn bytes(&mut self, field: u32, bytes: &[u8]) {
+        self.varint(((field as u64) << 3) | 2);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, message: Proto) {
+        self.bytes(field, &message.0);
+    }
+}
+
+/// Float tensor `[batch_size, width]`
+fn value_info(name: &str, width: usize) -> Proto {
+    typed_value_info(name, width, FLOAT)
+}
+
+fn typed_value_info(name: &str, width: usize, elem_type: u64) -> Proto {
+    let mut shape = Proto::default();
+    let mut batch = Proto::default();
+    batch.string(2, "batch_size");
+    shape.message(1, batch);
+    let mut features = Proto::default();
+    features.uint(1, width as u64);
+    shape.message(1, features);
+
+    let mut tensor_type = Proto::default();
+    tensor_type.uint(1, elem_type);
+    tensor_type.message(2, shape);
+    let mut type_proto = Proto::default();
+    type_proto.message(1, tensor_type);
+
+    let mut info = Proto::default();
+    info.string(1, name);
+    info.message(2, type_proto);
+    info
+}
+
+fn initializer(name: &str, dims: &[usize], values: &[f32]) -> Proto {
+    let mut tensor = Proto::default();
+    for &dim in dims {
+        tensor.uint(1, dim as u64);
+    }
+    tensor.uint(2, FLOAT);
+    tensor.string(8, name);
+    let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    tensor.bytes(9, &raw);
+    tensor
+}
+
+fn node(op_type: &str, name: &str, inputs: &[&str], output: &str) -> Proto {
+    let mut node = Proto::default();
+    for input in inputs {
+        node.string(1, input);
+    }
+    node.string(2, output);
+    node.string(3, name);
+    node.string(4, op_type);
+    node
+}
+
+/// Write an MLP value network: ReLU between layers, tanh on the output
+pub fn write_value_mlp(
+    path: &Path,
+    layers: &[DenseLayer],
+    metadata: &[(&str, String)],
+) -> anyhow::Result<()> {
+    let (Some(first), Some(last)) = (layers.first(), layers.last()) else {
+        anyhow::bail!("A model needs at least one layer");
+    };
+    if last.outputs != 1 {
+        anyhow::bail!("The last layer must have a single output");
+    }
+
+    let mut graph = Proto::default();
+    let mut input = INPUT_NAME.to_string();
+    for (i, layer) in layers.iter().enumerate() {
+        if layer.weight.len() != layer.inputs * layer.outputs || layer.bias.len() != layer.outputs {
+            anyhow::bail!("Layer {} has inconsistent weight shapes", i);
+        }
+        let weight = format!("layer{}.weight", i);
+        let bias = format!("layer{}.bias", i);
+        let linear = format!("layer{}.linear", i);
+        let (activation, output) = if i + 1 == layers.len() {
+            ("Tanh", OUTPUT_NAME.to_string())
+        } else {
+            ("Relu", format!("layer{}.out", i))
+        };
+
+        let mut gemm = node("Gemm", &linear, &[&input, &weight, &bias], &linear);
+        let mut trans_b = Proto::default();
+        trans_b.string(1, "transB");
+        trans_b.uint(3, 1);
+        trans_b.uint(20, ATTRIBUTE_INT);
+        gemm.message(5, trans_b);
+        graph.message(1, gemm);
+        graph.message(
+            1,
+            node(
+                activation,
+                &format!("layer{}.{}", i, activation.to_lowercase()),
+                &[&linear],
+                &output,
+            ),
+        );
+        graph.message(
+            5,
+            initializer(&weight, &[layer.outputs, layer.inputs], &layer.weight),
+        );
+        graph.message(5, initializer(&bias, &[layer.outputs], &layer.bias));
+        input = output;
+    }
+    graph.string(2, "value_mlp");
+    graph.message(11, value_info(INPUT_NAME, first.inputs));
+    graph.message(12, value_info(OUTPUT_NAME, 1));
+
+    let mut model = Proto::default();
+    model.uint(1, IR_VERSION);
+    model.string(2, env!("CARGO_PKG_NAME"));
+    model.string(3, env!("CARGO_PKG_VERSION"));
+    model.message(7, graph);
+    let mut opset = Proto::default();
+    opset.string(1, "");
+    opset.uint(2, OPSET_VERSION);
+    model.message(8, opset);
+    for (key, value) in metadata {
+        let mut entry = Proto::default();
+        entry.string(1, key);
+        entry.string(2, value);
+        model.message(14, entry);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    crate::util::atomic_write(path, model.0)?;
+    Ok(())
+}
+
+/// A decoded protobuf field value
+#[derive(Debug, Clone, Copy)]
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+fn read_varint(buf: &mut &[u8]) -> anyhow::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("Truncated varint"))?;
+        *buf = rest;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("Varint too long")
+}
+
+/// Top-level fields of a protobuf message, in order
+fn fields(mut buf: &[u8]) -> anyhow::Result<Vec<(u32, Field<'_>)>> {
+    let mut fields = Vec::new();
+    while !buf.is_empty() {
+        let key = read_varint(&mut buf)?;
+        let field = match key & 7 {
+            0 => Field::Varint(read_varint(&mut buf)?),
+            wire @ (1 | 5) => {
+                let len = if wire == 1 { 8 } else { 4 };
+                if buf.len() < len {
+                    anyhow::bail!("Truncated field");
+                }
+                buf = &buf[len..];
+                Field::Fixed
+            }
+            2 => {
+                let len = read_varint(&mut buf)? as usize;
+                if buf.len() < len {
+                    anyhow::bail!("Truncated field");
+                }
+                let (bytes, rest) = buf.split_at(len);
+                buf = rest;
+                Field::Bytes(bytes)
+            }
+            wire => anyhow::bail!("Unsupported wire type {}", wire),
+        };
+        fields.push(((key >> 3) as u32, field));
+    }
+    Ok(fields)
+}
+
+/// Custom metadata (`metadata_props`) of an ONNX model
+pub fn read_metadata(path: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let data = std::fs::read(path)?;
+    let mut metadata = HashMap::new();
+    for (number, field) in fields(&data)? {
+        let (14, Field::Bytes(entry)) = (number, field) else {
+            continue;
+        };
+        let (mut key, mut value) = (String::new(), String::new());
+        for (number, field) in fields(entry)? {
+            match (number, field) {
+                (1, Field::Bytes(b)) => key = String::from_utf8_lossy(b).to_string(),
+                (2, Field::Bytes(b)) => value = String::from_utf8_lossy(b).to_string(),
+                _ => {}
+            }
+        }
+        metadata.insert(key, value);
+    }
+    Ok(metadata)
+}
+
+/// Operators that only appear in quantized graphs
+const QUANTIZED_OPS: [&str; 8] = [
+    "QuantizeLinear",
+    "DequantizeLinear",
+    "DynamicQuantizeLinear",
+    "QLinearMatMul",
+    "QLinearGemm",
+    "QLinearConv",
+    "MatMulInteger",
+    "ConvInteger",
+];
+
+/// Element type of a graph input or output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    Float,
+    Uint8,
+    Int8,
+}
+
+impl ElementType {
+    fn from_onnx(data_type: u64) -> anyhow::Result<Self> {
+        match data_type {
+            FLOAT => Ok(Self::Float),
+            UINT8 => Ok(Self::Uint8),
+            INT8 => Ok(Self::Int8),
+            other => anyhow::bail!("Unsupported tensor element type {}", other),
+        }
+    }
+
+    /// Representable integer range; unbounded for floats
+    fn range(self) -> (i32, i32) {
+        match self {
+            Self::Float => (i32::MIN, i32::MAX),
+            Self::Uint8 => (u8::MIN as i32, u8::MAX as i32),
+            Self::Int8 => (i8::MIN as i32, i8::MAX as i32),
+        }
+    }
+}
+
+/// How a model is quantized, as far as the evaluator is concerned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quantization {
+    /// Quantized operators (`MatMulInteger`, `QLinearMatMul`, ...) in the graph
+    pub quantized_ops: usize,
+    /// Element type of `board_features`
+    pub input: ElementType,
+    /// Element type of `value`
+    pub output: ElementType,
+}
+
+impl Quantization {
+    pub fn is_quantized(&self) -> bool {
+        self.quantized_ops > 0
+            || self.input != ElementType::Float
+            || self.output != ElementType::Float
+    }
+}
+
+/// Affine quantization of a graph input or output, `real = (q - zero_point) * scale`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantParams {
+    pub scale: f32,
+    pub zero_point: i32,
+    pub element: ElementType,
+}
+
+impl QuantParams {
+    /// Read `<prefix>_scale` and `<prefix>_zero_point` from the model metadata.
+    /// Models with integer inputs or outputs must carry them, since the graph
+    /// itself does not say how its integers map to features or values.
+    pub fn from_metadata(
+        metadata: &HashMap<String, String>,
+        prefix: &str,
+        element: ElementType,
+    ) -> anyhow::Result<Self> {
+        let get = |key: String| {
+            metadata
+                .get(&key)
+                .ok_or_else(|| anyhow::anyhow!("Quantized model has no '{}' metadata", key))
+        };
+        let scale: f32 = get(format!("{}_scale", prefix))?.parse()?;
+        let zero_point: i32 = get(format!("{}_zero_point", prefix))?.parse()?;
+        if !scale.is_finite() || scale <= 0.0 {
+            anyhow::bail!("Invalid {}_scale {}", prefix, scale);
+        }
+        let (min, max) = element.range();
+        if !(min..=max).contains(&zero_point) {
+            anyhow::bail!("{}_zero_point {} is out of range", prefix, zero_point);
+        }
+        Ok(Self {
+            scale,
+            zero_point,
+            element,
+        })
+    }
+
+    pub fn quantize(&self, value: f32) -> i32 {
+        let (min, max) = self.element.range();
+        ((value / self.scale).round() as i32)
+            .saturating_add(self.zero_point)
+            .clamp(min, max)
+    }
+
+    pub fn dequantize(&self, value: i32) -> f32 {
+        (value - self.zero_point) as f32 * self.scale
+    }
+}
+
+/// The graph message of a model
+fn read_graph(data: &[u8]) -> anyhow::Result<&[u8]> {
+    fields(data)?
+        .into_iter()
+        .find_map(|(number, field)| match (number, field) {
+            (7, Field::Bytes(graph)) => Some(graph),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("Model has no graph"))
+}
+
+/// Name and element type of a graph input or output (`ValueInfoProto`)
+fn read_value_info(info: &[u8]) -> anyhow::Result<(String, Option<u64>)> {
+    let (mut name, mut elem_type) = (String::new(), None);
+    for (number, field) in fields(info)? {
+        match (number, field) {
+            (1, Field::Bytes(b)) => name = String::from_utf8_lossy(b).to_string(),
+            // TypeProto.tensor_type.elem_type
+            (2, Field::Bytes(type_proto)) => {
+                for (number, field) in fields(type_proto)? {
+                    let (1, Field::Bytes(tensor_type)) = (number, field) else {
+                        continue;
+                    };
+                    for (number, field) in fields(tensor_type)? {
+                        if let (1, Field::Varint(t)) = (number, field) {
+                            elem_type = Some(t);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((name, elem_type))
+}
+
+/// Detect whether a model is quantized and how its interface is typed
+pub fn read_quantization(path: &Path) -> anyhow::Result<Quantization> {
+    let data = std::fs::read(path)?;
+    let graph = read_graph(&data)?;
+
+    let (mut quantized_ops, mut input, mut output) = (0, None, None);
+    for (number, field) in fields(graph)? {
+        match (number, field) {
+            (1, Field::Bytes(node)) => {
+                let op_type = read_node(node)?.op_type;
+                quantized_ops += QUANTIZED_OPS.contains(&op_type.as_str()) as usize;
+            }
+            (11 | 12, Field::Bytes(info)) => {
+                let (name, elem_type) = read_value_info(info)?;
+                let slot = match (number, name.as_str()) {
+                    (11, INPUT_NAME) => &mut input,
+                    (12, OUTPUT_NAME) => &mut output,
+                    _ => continue,
+                };
+                *slot = Some(ElementType::from_onnx(elem_type.unwrap_or(FLOAT))?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Quantization {
+        quantized_ops,
+        input: input.ok_or_else(|| anyhow::anyhow!("Model has no '{}' input", INPUT_NAME))?,
+        output: output.ok_or_else(|| anyhow::anyhow!("Model has no '{}' output", OUTPUT_NAME))?,
+    })
+}
+
+/// Varints of a packed repeated field
+fn packed_varints(mut buf: &[u8]) -> anyhow::Result<Vec<u64>> {
+    let mut values = Vec::new();
+    while !buf.is_empty() {
+        values.push(read_varint(&mut buf)?);
+    }
+    Ok(values)
+}
+
+/// A float initializer: name, dims and values
+fn read_initializer(tensor: &[u8]) -> anyhow::Result<(String, Vec<usize>, Vec<f32>)> {
+    let (mut name, mut dims, mut data_type) = (String::new(), Vec::new(), FLOAT);
+    let mut floats = Vec::new();
+    for (number, field) in fields(tensor)? {
+        match (number, field) {
+            (1, Field::Varint(dim)) => dims.push(dim as usize),
+            (1, Field::Bytes(packed)) => {
+                dims.extend(packed_varints(packed)?.into_iter().map(|d| d as usize))
+            }
+            (2, Field::Varint(t)) => data_type = t,
+            (8, Field::Bytes(b)) => name = String::from_utf8_lossy(b).to_string(),
+            // raw_data, or packed float_data
+            (9 | 4, Field::Bytes(raw)) => floats.extend(
+                raw.chunks_exact(4)
+                    .map(|c| f32::from_le_bytes(c.try_into().unwrap())),
+            ),
+            _ => {}
+        }
+    }
+    if data_type != FLOAT {
+        anyhow::bail!("Initializer '{}' is not a float tensor", name);
+    }
+    if floats.len() != dims.iter().product::<usize>() {
+        anyhow::bail!("Initializer '{}' has no or truncated data", name);
+    }
+    Ok((name, dims, floats))
+}
+
+/// A graph node: op type, inputs, outputs and integer attributes
+struct Node {
+    op_type: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    ints: HashMap<String, i64>,
+}
+
+fn read_node(node: &[u8]) -> anyhow::Result<Node> {
+    let text = |b: &[u8]| String::from_utf8_lossy(b).to_string();
+    let mut parsed = Node {
+        op_type: String::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        ints: HashMap::new(),
+    };
+    for (number, field) in fields(node)? {
+        match (number, field) {
+            (1, Field::Bytes(b)) => parsed.inputs.push(text(b)),
+            (2, Field::Bytes(b)) => parsed.outputs.push(text(b)),
+            (4, Field::Bytes(b)) => parsed.op_type = text(b),
+            (5, Field::Bytes(attribute)) => {
+                let (mut name, mut value) = (String::new(), None);
+                for (number, field) in fields(attribute)? {
+                    match (number, field) {
+                        (1, Field::Bytes(b)) => name = text(b),
+                        (3, Field::Varint(v)) => value = Some(v as i64),
+                        _ => {}
+                    }
+                }
+                if let Some(value) = value {
+                    parsed.ints.insert(name, value);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// Read a value MLP back as dense layers. Accepts the graphs
+/// [`write_value_mlp`] produces: a chain of `Gemm` nodes, each followed by
+/// `Relu`, the last one by `Tanh`. Anything else (convolutions, residual
+/// connections, ...) is refused.
+pub fn read_value_mlp(path: &Path) -> anyhow::Result<Vec<DenseLayer>> {
+    let data = std::fs::read(path)?;
+    let graph = read_graph(&data)?;
+
+    let mut nodes = Vec::new();
+    let mut initializers = HashMap::new();
+    for (number, field) in fields(graph)? {
+        match (number, field) {
+            (1, Field::Bytes(node)) => nodes.push(read_node(node)?),
+            (5, Field::Bytes(tensor)) => {
+                let (name, dims, values) = read_initializer(tensor)?;
+                initializers.insert(name, (dims, values));
+            }
+            _ => {}
+        }
+    }
+
+    let mut layers: Vec<DenseLayer> = Vec::new();
+    let mut input = INPUT_NAME.to_string();
+    let mut nodes = nodes.iter();
+    while let Some(gemm) = nodes.next() {
+        let (Some(activation), [x, w, b]) = (nodes.next(), gemm.inputs.as_slice()) else {
+            anyhow::bail!("Unsupported graph: expected Gemm followed by an activation");
+        };
+        if gemm.op_type != "Gemm" || *x != input {
+            anyhow::bail!(
+                "Unsupported graph: '{}' node where a Gemm on '{}' was expected",
+                gemm.op_type,
+                input
+            );
+        }
+        if activation.inputs != gemm.outputs {
+            anyhow::bail!("Unsupported graph: activation does not follow its Gemm");
+        }
+        let attr = |name: &str, default: i64| gemm.ints.get(name).copied().unwrap_or(default);
+        if attr("transA", 0) != 0 {
+            anyhow::bail!("Unsupported graph: Gemm with transA");
+        }
+        let (Some((w_dims, weight)), Some((_, bias))) = (initializers.get(w), initializers.get(b))
+        else {
+            anyhow::bail!("Gemm weights '{}' / '{}' are not initializers", w, b);
+        };
+        let &[rows, cols] = w_dims.as_slice() else {
+            anyhow::bail!("Weight '{}' is not a matrix", w);
+        };
+        // Stored as [outputs, inputs] with transB, else [inputs, outputs]
+        let (inputs, outputs, weight) = if attr("transB", 0) != 0 {
+            (cols, rows, weight.clone())
+        } else {
+            let transposed = (0..cols)
+                .flat_map(|o| (0..rows).map(move |i| (i, o)))
+                .map(|(i, o)| weight[i * cols + o])
+                .collect();
+            (rows, cols, transposed)
+        };
+        if bias.len() != outputs || layers.last().is_some_and(|l| l.outputs != inputs) {
+            anyhow::bail!("Layer {} has inconsistent shapes", layers.len());
+        }
+        let last = activation.outputs.first().map(String::as_str) == Some(OUTPUT_NAME);
+        match (activation.op_type.as_str(), last) {
+            ("Relu", false) | ("Tanh", true) => {}
+            (op, _) => anyhow::bail!(
+                "Unsupported graph: '{}' activation in layer {}",
+                op,
+                layers.len()
+            ),
+        }
+        layers.push(DenseLayer {
+            inputs,
+            outputs,
+            weight,
+            bias: bias.clone(),
+        });
+        input = activation.outputs.first().cloned().unwrap_or_default();
+        if last {
+            if nodes.next().is_some() {
+                anyhow::bail!("Unsupported graph: nodes after the value output");
+            }
+            if outputs != 1 {
+                anyhow::bail!("The value output must have a single unit");
+            }
+            return Ok(layers);
+        }
+    }
+    anyhow::bail!("Unsupported graph: no '{}' output", OUTPUT_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// String fields with the given number in a message
+    fn strings(message: &[u8], number: u32) -> Vec<&str> {
+        fields(message)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(n, f)| match f {
+                Field::Bytes(b) if n == number => std::str::from_utf8(b).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn submessages(message: &[u8], number: u32) -> Vec<&[u8]> {
+        fields(message)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(n, f)| match f {
+                Field::Bytes(b) if n == number => Some(b),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_value_mlp_graph_and_metadata() {
+        let layers = [
+            DenseLayer {
+                inputs: 3,
+                outputs: 2,
+                weight: vec![0.5, -1.0, 2.0, 0.0, 1.5, -0.25],
+                bias: vec![0.1, -0.1],
+            },
+            DenseLayer {
+                inputs: 2,
+                outputs: 1,
+                weight: vec![1.0, -1.0],
+                bias: vec![0.0],
+            },
+        ];
+        let path = std::env::temp_dir().join(format!("aho_onnx_{}.onnx", std::process::id()));
+        write_value_mlp(&path, &layers, &[("version", "0.1.0".to_string())]).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let metadata = read_metadata(&path).unwrap();
+        let read_back = read_value_mlp(&path).unwrap();
+        let quantization = read_quantization(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(read_back.len(), layers.len());
+        for (read, written) in read_back.iter().zip(&layers) {
+            assert_eq!(
+                (read.inputs, read.outputs),
+                (written.inputs, written.outputs)
+            );
+            assert_eq!((&read.weight, &read.bias), (&written.weight, &written.bias));
+        }
+
+        assert_eq!(metadata.get("version").map(String::as_str), Some("0.1.0"));
+        assert!(!quantization.is_quantized());
+
+        let graph = submessages(&data, 7)[0];
+        let ops: Vec<&str> = submessages(graph, 1)
+            .iter()
+            .map(|node| strings(node, 4)[0])
+            .collect();
+        assert_eq!(ops, ["Gemm", "Relu", "Gemm", "Tanh"]);
+        let last = submessages(graph, 1)[3];
+        assert_eq!(strings(last, 2), ["value"]);
+        let input = submessages(graph, 11)[0];
+        assert_eq!(strings(input, 1), ["board_features"]);
+
+        // First initializer: layer0.weight [2, 3] with the raw floats
+        let weight = submessages(graph, 5)[0];
+        let dims: Vec<u64> = fields(weight)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(n, f)| match f {
+                Field::Varint(v) if n == 1 => Some(v),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dims, [2, 3]);
+        let raw = submessages(weight, 9)[0];
+        let values: Vec<f32> = raw
+            .chunks(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, layers[0].weight);
+    }
+
+    #[test]
+    fn test_quantized_model_detection() {
+        let mut graph = Proto::default();
+        graph.message(
+            1,
+            node("MatMulInteger", "matmul", &["board_features", "w"], "acc"),
+        );
+        graph.message(1, node("Cast", "cast", &["acc"], "value"));
+        graph.message(11, typed_value_info(INPUT_NAME, 4, UINT8));
+        graph.message(12, typed_value_info(OUTPUT_NAME, 1, INT8));
+        let mut model = Proto::default();
+        model.message(7, graph);
+        let path = std::env::temp_dir().join(format!("aho_onnx_q_{}.onnx", std::process::id()));
+        std::fs::write(&path, &model.0).unwrap();
+        let quantization = read_quantization(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            quantization,
+            Quantization {
+                quantized_ops: 1,
+                input: ElementType::Uint8,
+                output: ElementType::Int8,
+            }
+        );
+        assert!(quantization.is_quantized());
+
+        let metadata: HashMap<String, String> =
+            [("output_scale", "0.0078125"), ("output_zero_point", "0")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        let output = QuantParams::from_metadata(&metadata, "output", ElementType::Int8).unwrap();
+        assert_eq!(output.quantize(0.5), 64);
+        assert_eq!(output.quantize(3.0), 127);
+        assert_eq!(output.dequantize(-128), -1.0);
+        assert!(QuantParams::from_metadata(&metadata, "input", ElementType::Uint8).is_err());
+    }
+}
@@ -0,0 +1,587 @@
+//! In-crate value network training (`train` feature)
+//!
+//! Trains an MLP value network straight from the `dataset.bin` files written
+//! by `selfplay --export-dataset`, so the selfplay → train → play loop needs
+//! no Python. The network regresses the game outcome for the side to move
+//! (weighted by how many positions a deduplicated record merges) with
+//! mini-batch SGD on the CPU, and is exported as `model.onnx` with the same
+//! input/output names and lineage metadata as `scripts/ml/train.py`. Models
+//! written under a `models` directory are also recorded in its
+//! `manifest.json` ([`crate::ml::model_registry::ModelManifest`]).
+//!
+//! Output directory layout:
+//! - `model.onnx`: the exported model
+//! - `training.json`: [`TrainReport`] of the run
+//! - `checkpoints/epoch_NNN.safetensors` and `checkpoints/latest.json`
+//!   ([`TrainCheckpoint`]), used by `--resume` to continue an interrupted run
+
+use crate::ml::model_registry::ModelLineage;
+use crate::selfplay::dataset::{DATASET_FILE, DEDUP_FILE};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Whether this build includes the trainer (the `train` feature)
+pub const TRAIN_ENABLED: bool = cfg!(feature = "train");
+
+/// Shown when `train` is run in a build without the `train` feature
+pub const TRAIN_UNAVAILABLE_MESSAGE: &str =
+    "compiled without train — rebuild with `--features train` to train models";
+
+pub const MODEL_FILE: &str = "model.onnx";
+pub const REPORT_FILE: &str = "training.json";
+
+#[derive(Debug, Clone)]
+pub struct TrainConfig {
+    /// `dataset.bin` / `dataset_dedup.bin` files to train on
+    pub data: Vec<PathBuf>,
+    pub output_dir: PathBuf,
+    pub version: String,
+    /// Hidden layer widths, input to output
+    pub hidden: Vec<usize>,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    /// Share of positions held out for validation, split by Zobrist hash
+    pub validation_split: f32,
+    pub seed: u64,
+    /// Continue from `checkpoints/latest.json` instead of starting over
+    pub resume: bool,
+    pub parent_model: Option<String>,
+    /// Default: parent's generation + 1, or 0 without a parent
+    pub generation: Option<u32>,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            output_dir: PathBuf::from("models/Fair/v0.1.0"),
+            version: "0.1.0".to_string(),
+            hidden: vec![256, 64],
+            epochs: 10,
+            batch_size: 64,
+            learning_rate: 0.01,
+            validation_split: 0.1,
+            seed: 42,
+            resume: false,
+            parent_model: None,
+            generation: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochLoss {
+    pub epoch: usize,
+    pub train_loss: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation_loss: Option<f32>,
+}
+
+/// Training state after a finished epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainCheckpoint {
+    /// Number of finished epochs
+    pub epoch: usize,
+    pub input_size: usize,
+    pub hidden: Vec<usize>,
+    /// Weights file, relative to the checkpoint directory
+    pub weights: String,
+    pub history: Vec<EpochLoss>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainReport {
+    pub model_path: PathBuf,
+    pub data: Vec<PathBuf>,
+    pub train_samples: usize,
+    pub validation_samples: usize,
+    pub hidden: Vec<usize>,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    pub history: Vec<EpochLoss>,
+    pub lineage: ModelLineage,
+    pub elapsed_secs: f64,
+}
+
+/// Dataset files under `path`: the file itself, the dataset of a selfplay run
+/// directory (`dataset_dedup.bin` when present), or those of every run
+/// directory below it, e.g. `selfplay_kifu/Fair`
+pub fn find_datasets(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    for name in [DEDUP_FILE, DATASET_FILE] {
+        let file = path.join(name);
+        if file.is_file() {
+            return vec![file];
+        }
+    }
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs.iter().flat_map(|dir| find_datasets(dir)).collect()
+}
+
+#[cfg(not(feature = "train"))]
+pub fn run_training(_config: &TrainConfig) -> anyhow::Result<TrainReport> {
+    anyhow::bail!(TRAIN_UNAVAILABLE_MESSAGE)
+}
+
+#[cfg(feature = "train")]
+pub use trainer::run_training;
+
+#[cfg(feature = "train")]
+mod trainer {
+    use super::*;
+    use crate::ml::features::{BoardFeatureExtractor, FEATURE_VERSION, FEATURE_VERSION_KEY};
+    use crate::ml::model_registry::{ManifestEntry, ModelManifest};
+    use crate::ml::onnx::{self, DenseLayer};
+    use crate::selfplay::dataset::read_dataset;
+    use candle_core::{DType, Device, Tensor};
+    use candle_nn::{Linear, Module, Optimizer, VarBuilder, VarMap, SGD};
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    const CHECKPOINT_DIR: &str = "checkpoints";
+    const LATEST_CHECKPOINT: &str = "latest.json";
+
+    fn read_checkpoint(output_dir: &Path) -> Option<TrainCheckpoint> {
+        let path = output_dir.join(CHECKPOINT_DIR).join(LATEST_CHECKPOINT);
+        if !path.exists() {
+            return None;
+        }
+        crate::util::read_json_or_skip(&path).ok().flatten()
+    }
+
+    /// A position kept sparse; batches are densified on the fly
+    struct Sample {
+        features: Vec<(u16, f32)>,
+        outcome: f32,
+        weight: f32,
+    }
+
+    struct ValueNet {
+        layers: Vec<Linear>,
+    }
+
+    impl ValueNet {
+        fn new(vb: VarBuilder, input_size: usize, hidden: &[usize]) -> anyhow::Result<Self> {
+            let mut widths = vec![input_size];
+            widths.extend_from_slice(hidden);
+            widths.push(1);
+            let layers = widths
+                .windows(2)
+                .enumerate()
+                .map(|(i, w)| candle_nn::linear(w[0], w[1], vb.pp(format!("layer{}", i))))
+                .collect::<Result<_, _>>()?;
+            Ok(Self { layers })
+        }
+
+        fn forward(&self, x: &Tensor) -> anyhow::Result<Tensor> {
+            let mut x = x.clone();
+            for (i, layer) in self.layers.iter().enumerate() {
+                x = layer.forward(&x)?;
+                x = if i + 1 == self.layers.len() {
+                    x.tanh()?
+                } else {
+                    x.relu()?
+                };
+            }
+            Ok(x)
+        }
+
+        fn dense_layers(&self) -> anyhow::Result<Vec<DenseLayer>> {
+            self.layers
+                .iter()
+                .map(|layer| {
+                    let (outputs, inputs) = layer.weight().dims2()?;
+                    let bias = match layer.bias() {
+                        Some(bias) => bias.to_vec1()?,
+                        None => vec![0.0; outputs],
+                    };
+                    Ok(DenseLayer {
+                        inputs,
+                        outputs,
+                        weight: layer.weight().flatten_all()?.to_vec1()?,
+                        bias,
+                    })
+                })
+                .collect()
+        }
+    }
+
+    /// Inputs, outcomes and weights of a mini-batch
+    fn batch(
+        samples: &[Sample],
+        indices: &[usize],
+        input_size: usize,
+        device: &Device,
+    ) -> anyhow::Result<(Tensor, Tensor, Tensor)> {
+        let mut x = vec![0f32; indices.len() * input_size];
+        let mut y = Vec::with_capacity(indices.len());
+        let mut w = Vec::with_capacity(indices.len());
+        for (row, &i) in indices.iter().enumerate() {
+            let sample = &samples[i];
+            for &(index, value) in &sample.features {
+                x[row * input_size + index as usize] = value;
+            }
+            y.push(sample.outcome);
+            w.push(sample.weight);
+        }
+        let n = indices.len();
+        Ok((
+            Tensor::from_vec(x, (n, input_size), device)?,
+            Tensor::from_vec(y, (n, 1), device)?,
+            Tensor::from_vec(w, (n, 1), device)?,
+        ))
+    }
+
+    fn weighted_mse(pred: &Tensor, target: &Tensor, weight: &Tensor) -> anyhow::Result<Tensor> {
+        let loss = pred.sub(target)?.sqr()?.mul(weight)?.sum_all()?;
+        Ok(loss.div(&weight.sum_all()?)?)
+    }
+
+    fn evaluate(
+        net: &ValueNet,
+        samples: &[Sample],
+        batch_size: usize,
+        input_size: usize,
+        device: &Device,
+    ) -> anyhow::Result<f32> {
+        let indices: Vec<usize> = (0..samples.len()).collect();
+        let (mut loss_sum, mut weight_sum) = (0.0, 0.0);
+        for chunk in indices.chunks(batch_size) {
+            let (x, y, w) = batch(samples, chunk, input_size, device)?;
+            let chunk_weight: f32 = chunk.iter().map(|&i| samples[i].weight).sum();
+            let loss = weighted_mse(&net.forward(&x)?, &y, &w)?.to_scalar::<f32>()?;
+            loss_sum += loss * chunk_weight;
+            weight_sum += chunk_weight;
+        }
+        Ok(loss_sum / weight_sum.max(f32::EPSILON))
+    }
+
+    /// Training and validation samples, split by Zobrist hash so a position
+    /// never lands on both sides
+    fn load_samples(config: &TrainConfig) -> anyhow::Result<(Vec<Sample>, Vec<Sample>)> {
+        let feature_size = BoardFeatureExtractor::feature_size();
+        let holdout = (config.validation_split.clamp(0.0, 1.0) * 1000.0) as u64;
+        let (mut train, mut validation) = (Vec::new(), Vec::new());
+        for path in &config.data {
+            let records = read_dataset(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+            println!("  {}: {} positions", path.display(), records.len());
+            for record in records {
+                if record.features.len() != feature_size {
+                    anyhow::bail!(
+                        "{} has {} features per position, expected {} (exported by another feature version?)",
+                        path.display(),
+                        record.features.len(),
+                        feature_size
+                    );
+                }
+                let sample = Sample {
+                    features: record
+                        .features
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, v)| **v != 0.0)
+                        .map(|(i, v)| (i as u16, *v))
+                        .collect(),
+                    outcome: record.outcome,
+                    weight: record.weight as f32,
+                };
+                if record.hash % 1000 < holdout {
+                    validation.push(sample);
+                } else {
+                    train.push(sample);
+                }
+            }
+        }
+        Ok((train, validation))
+    }
+
+    /// SHA-256 of the training data (the files concatenated in order)
+    fn data_hash(paths: &[PathBuf]) -> anyhow::Result<String> {
+        let mut digest = Sha256::new();
+        let mut buf = vec![0u8; 1 << 20];
+        for path in paths {
+            let mut file = std::fs::File::open(path)?;
+            loop {
+                let n = file.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                digest.update(&buf[..n]);
+            }
+        }
+        Ok(digest
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    fn lineage(config: &TrainConfig) -> anyhow::Result<ModelLineage> {
+        let generation = match (config.generation, &config.parent_model) {
+            (Some(generation), _) => generation,
+            (None, Some(parent)) => {
+                let metadata = onnx::read_metadata(Path::new(parent)).map_err(|e| {
+                    anyhow::anyhow!("Failed to read parent model {}: {}", parent, e)
+                })?;
+                ModelLineage::from_custom(|key| metadata.get(key).cloned())
+                    .generation
+                    .map_or(1, |g| g + 1)
+            }
+            (None, None) => 0,
+        };
+        Ok(ModelLineage {
+            version: Some(config.version.clone()),
+            generation: Some(generation),
+            training_data_hash: Some(data_hash(&config.data)?),
+            feature_version: Some(FEATURE_VERSION),
+            parent_model: config.parent_model.clone(),
+        })
+    }
+
+    fn save_checkpoint(
+        varmap: &VarMap,
+        checkpoint_dir: &Path,
+        checkpoint: &TrainCheckpoint,
+    ) -> anyhow::Result<()> {
+        std::fs::create_dir_all(checkpoint_dir)?;
+        varmap.save(checkpoint_dir.join(&checkpoint.weights))?;
+        // Written last, so it never points at missing weights
+        crate::util::atomic_write_json(&checkpoint_dir.join(LATEST_CHECKPOINT), checkpoint, true)
+    }
+
+    pub fn run_training(config: &TrainConfig) -> anyhow::Result<TrainReport> {
+        let start = std::time::Instant::now();
+        if config.data.is_empty() {
+            anyhow::bail!("No training data given");
+        }
+        if config.batch_size == 0 {
+            anyhow::bail!("Batch size must be at least 1");
+        }
+
+        println!("Loading training data...");
+        let (train, validation) = load_samples(config)?;
+        if train.is_empty() {
+            anyhow::bail!("No training positions in the given data");
+        }
+        println!(
+            "Training on {} positions, validating on {}",
+            train.len(),
+            validation.len()
+        );
+
+        let input_size = BoardFeatureExtractor::feature_size();
+        let device = Device::Cpu;
+        let mut varmap = VarMap::new();
+        let net = ValueNet::new(
+            VarBuilder::from_varmap(&varmap, DType::F32, &device),
+            input_size,
+            &config.hidden,
+        )?;
+
+        let checkpoint_dir = config.output_dir.join(CHECKPOINT_DIR);
+        let mut first_epoch = 0;
+        let mut history = Vec::new();
+        if config.resume {
+            match read_checkpoint(&config.output_dir) {
+                Some(checkpoint) => {
+                    if checkpoint.hidden != config.hidden || checkpoint.input_size != input_size {
+                        anyhow::bail!(
+                            "Checkpoint has layers {:?} for {} inputs, but this run uses {:?} for {}",
+                            checkpoint.hidden,
+                            checkpoint.input_size,
+                            config.hidden,
+                            input_size
+                        );
+                    }
+                    varmap.load(checkpoint_dir.join(&checkpoint.weights))?;
+                    println!("Resuming after epoch {}", checkpoint.epoch);
+                    first_epoch = checkpoint.epoch;
+                    history = checkpoint.history;
+                }
+                None => println!(
+                    "No checkpoint in {}, starting over",
+                    checkpoint_dir.display()
+                ),
+            }
+        }
+
+        let mut optimizer = SGD::new(varmap.all_vars(), config.learning_rate)?;
+        let mut order: Vec<usize> = (0..train.len()).collect();
+        for epoch in first_epoch..config.epochs {
+            let epoch_start = std::time::Instant::now();
+            // Seeded per epoch, so a resumed run sees the same order
+            order.sort_unstable();
+            order.shuffle(&mut StdRng::seed_from_u64(
+                config.seed.wrapping_add(epoch as u64),
+            ));
+
+            let (mut loss_sum, mut weight_sum) = (0.0, 0.0);
+            for chunk in order.chunks(config.batch_size) {
+                let (x, y, w) = batch(&train, chunk, input_size, &device)?;
+                let loss = weighted_mse(&net.forward(&x)?, &y, &w)?;
+                optimizer.backward_step(&loss)?;
+                let chunk_weight: f32 = chunk.iter().map(|&i| train[i].weight).sum();
+                loss_sum += loss.to_scalar::<f32>()? * chunk_weight;
+                weight_sum += chunk_weight;
+            }
+            let validation_loss = if validation.is_empty() {
+                None
+            } else {
+                Some(evaluate(
+                    &net,
+                    &validation,
+                    config.batch_size,
+                    input_size,
+                    &device,
+                )?)
+            };
+            let entry = EpochLoss {
+                epoch: epoch + 1,
+                train_loss: loss_sum / weight_sum,
+                validation_loss,
+            };
+            println!(
+                "Epoch {}/{}: train loss {:.4}{} ({:.1}s)",
+                entry.epoch,
+                config.epochs,
+                entry.train_loss,
+                entry
+                    .validation_loss
+                    .map(|l| format!(", validation loss {:.4}", l))
+                    .unwrap_or_default(),
+                epoch_start.elapsed().as_secs_f64()
+            );
+            history.push(entry);
+
+            save_checkpoint(
+                &varmap,
+                &checkpoint_dir,
+                &TrainCheckpoint {
+                    epoch: epoch + 1,
+                    input_size,
+                    hidden: config.hidden.clone(),
+                    weights: format!("epoch_{:03}.safetensors", epoch + 1),
+                    history: history.clone(),
+                },
+            )?;
+        }
+
+        let lineage = lineage(config)?;
+        let mut metadata = vec![
+            ("version", config.version.clone()),
+            ("generation", lineage.generation.unwrap_or(0).to_string()),
+            (
+                "training_data_hash",
+                lineage.training_data_hash.clone().unwrap_or_default(),
+            ),
+            (FEATURE_VERSION_KEY, FEATURE_VERSION.to_string()),
+        ];
+        if let Some(parent) = &config.parent_model {
+            metadata.push(("parent_model", parent.clone()));
+        }
+        let model_path = config.output_dir.join(MODEL_FILE);
+        onnx::write_value_mlp(&model_path, &net.dense_layers()?, &metadata)?;
+        ModelManifest::record(
+            &model_path,
+            ManifestEntry {
+                training_positions: Some(train.len()),
+                parent_model: config.parent_model.clone(),
+                created_at: Some(chrono::Local::now().to_rfc3339()),
+                ..Default::default()
+            },
+        )?;
+
+        let report = TrainReport {
+            model_path,
+            data: config.data.clone(),
+            train_samples: train.len(),
+            validation_samples: validation.len(),
+            hidden: config.hidden.clone(),
+            batch_size: config.batch_size,
+            learning_rate: config.learning_rate,
+            history,
+            lineage,
+            elapsed_secs: start.elapsed().as_secs_f64(),
+        };
+        crate::util::atomic_write_json(&config.output_dir.join(REPORT_FILE), &report, true)?;
+        Ok(report)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::core::{Move, PieceKind, PlayerId, Position};
+        use crate::selfplay::dataset::{append_records, DatasetRecord};
+
+        #[test]
+        fn test_training_fits_and_resumes() {
+            // Outcome follows the sign of feature 0
+            let feature_size = BoardFeatureExtractor::feature_size();
+            let records: Vec<DatasetRecord> = (0..64)
+                .map(|i| {
+                    let mut features = vec![0.0; feature_size];
+                    features[0] = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    features[1 + i % 7] = 1.0;
+                    DatasetRecord {
+                        game_num: i,
+                        move_number: 1,
+                        player: PlayerId::Player1,
+                        hash: i as u64 * 7919,
+                        outcome: features[0] * 0.9,
+                        weight: 1,
+                        score: 0,
+                        depth: 1,
+                        mirrored: false,
+                        chosen: Move::Drop {
+                            kind: PieceKind::S_Pawn,
+                            to: Position::new(0, 0),
+                        },
+                        features,
+                    }
+                })
+                .collect();
+            let dir = std::env::temp_dir().join(format!("aho_train_{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            append_records(&dir, &records).unwrap();
+
+            let mut config = TrainConfig {
+                data: vec![dir.join(DATASET_FILE)],
+                output_dir: dir.join("model"),
+                hidden: vec![8],
+                epochs: 2,
+                batch_size: 8,
+                learning_rate: 0.05,
+                validation_split: 0.0,
+                ..TrainConfig::default()
+            };
+            run_training(&config).unwrap();
+            config.epochs = 30;
+            config.resume = true;
+            let report = run_training(&config).unwrap();
+            let metadata = onnx::read_metadata(&report.model_path).unwrap();
+            std::fs::remove_dir_all(&dir).ok();
+
+            assert_eq!(report.history.len(), 30);
+            let first = report.history[0].train_loss;
+            let last = report.history[29].train_loss;
+            assert!(last < first / 2.0, "loss {} -> {}", first, last);
+            assert_eq!(metadata.get("generation").map(String::as_str), Some("0"));
+        }
+    }
+}
@@ -1,29 +1,91 @@
 use crate::core::{Board, Move, PlayerId};
-use crate::network::protocol::NetMessage;
-use std::sync::mpsc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
+use crate::network::protocol::{NetMessage, RoomInfo, WireFormat, TIME_FORFEIT};
+use crate::network::transport::{self, LocalBinding, MessageReader, MessageWriter};
+use crate::player::clock::RemoteClock;
+use std::sync::{mpsc, Arc, Mutex};
 use tokio::sync::mpsc as tokio_mpsc;
 
+/// 再接続を試みる回数と間隔（サーバーの既定の猶予 60 秒に合わせる）
+const REJOIN_ATTEMPTS: usize = 30;
+const REJOIN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// 接続時にサーバーへ提示するワイヤーフォーマット（優先順）。盤面全体を送る Update が小さくなる MessagePack を優先する
+pub const WIRE_FORMATS: [WireFormat; 2] = [WireFormat::MessagePack, WireFormat::Json];
+
+/// ロビーでの選択
+#[derive(Debug, Clone)]
+pub enum LobbyAction {
+    /// サーバーのクイックマッチ用の盤面（既定は StandardMixed）の空き部屋に入る（無ければ作って待つ）
+    QuickMatch,
+    /// 指定した盤面設定（"ShogiOnly" など）で部屋を作って待つ
+    CreateRoom(String),
+    /// 部屋 ID を指定して入る
+    JoinRoom(u64),
+    /// サーバーの AI と対局する（先手は自分）。盤面設定を省くとクイックマッチ用の盤面
+    PlayAi(Option<String>),
+}
+
 pub struct NetworkClient {
-    stream: TcpStream,
+    /// 正規化した接続先（再接続に使う）
+    addr: String,
+    /// 自分側のアドレスとポート再利用の指定（再接続でも同じものを使う）
+    binding: LocalBinding,
+    reader: MessageReader,
+    writer: MessageWriter,
+    /// ロビーで名乗る名前（対戦相手・観戦者・サーバーの記録に出る）
+    name: String,
 }
 
+/// 名前を指定しないときにロビーで名乗る名前
+pub const DEFAULT_PLAYER_NAME: &str = "Player";
+
 impl NetworkClient {
     pub async fn connect(addr: &str) -> anyhow::Result<Self> {
-        let sanitized = Self::sanitize_addr(addr);
-        // 10秒のタイムアウトを設定
-        let connect_fut = TcpStream::connect(&sanitized);
-        let stream = tokio::time::timeout(std::time::Duration::from_secs(10), connect_fut)
-            .await
-            .map_err(|_| anyhow::anyhow!("Connection timed out: {}", sanitized))??;
-
-        Ok(Self { stream })
+        Self::connect_from(addr, LocalBinding::default()).await
+    }
+
+    /// 自分側のアドレス・ポートを指定して接続する（NAT 越えの P2P 対戦用、生 TCP のみ）
+    pub async fn connect_from(addr: &str, binding: LocalBinding) -> anyhow::Result<Self> {
+        // 10秒でタイムアウト
+        let addr = Self::sanitize_addr(addr);
+        let (mut reader, mut writer) = transport::connect_from(&addr, &binding).await?;
+        transport::negotiate(&mut reader, &mut writer, &WIRE_FORMATS).await?;
+        Ok(Self {
+            addr,
+            binding,
+            reader,
+            writer,
+            name: DEFAULT_PLAYER_NAME.to_string(),
+        })
     }
 
+    /// ロビーで `name` を名乗る
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// 入力されたアドレスを接続先に正規化する
+    ///
+    /// `ws://` / `wss://` はそのまま WebSocket、`http://` / `https://` は
+    /// 対応する WebSocket の URL（`ngrok http` の URL をそのまま使える）、
+    /// `tls://` は TLS の `tls://host:port`、それ以外は生 TCP の `host:port`。
     pub fn sanitize_addr(addr: &str) -> String {
         let mut s = addr.trim().to_string();
 
+        if transport::is_websocket_url(&s) {
+            return s;
+        }
+        if let Some(rest) = s.strip_prefix("tls://") {
+            return format!("tls://{}", Self::sanitize_addr(rest));
+        }
+        if let Some(rest) = s.strip_prefix("https://") {
+            return format!("wss://{}", rest);
+        }
+        if let Some(rest) = s.strip_prefix("http://") {
+            return format!("ws://{}", rest);
+        }
+
         // プロトコルスキームの除去 (xxx://)
         if let Some(pos) = s.find("://") {
             s = s[(pos + 3)..].to_string();
@@ -41,72 +103,199 @@ impl NetworkClient {
         s
     }
 
+    /// 対戦相手を待っている部屋の一覧を取得する
+    pub async fn list_rooms(&mut self) -> anyhow::Result<Vec<RoomInfo>> {
+        self.writer.send(&NetMessage::ListRooms).await?;
+        loop {
+            match self.reader.recv().await? {
+                Some(NetMessage::RoomList { rooms }) => return Ok(rooms),
+                Some(NetMessage::Error { message }) => anyhow::bail!("Server Error: {}", message),
+                Some(_) => {}
+                None => anyhow::bail!("Connection closed by server"),
+            }
+        }
+    }
+
     pub async fn run(
         &mut self,
+        action: LobbyAction,
         player_id_tx: mpsc::Sender<PlayerId>,
         board_tx: mpsc::Sender<(Board, PlayerId)>,
         remote_move_tx: mpsc::Sender<Move>,
         mut local_move_rx: tokio_mpsc::UnboundedReceiver<Move>,
+        clock: Arc<Mutex<RemoteClock>>,
     ) -> anyhow::Result<()> {
-        let (reader, mut writer) = self.stream.split();
-        let mut lines = BufReader::new(reader).lines();
         let mut my_id: Option<PlayerId> = None;
+        // 再接続用のトークン（Welcome で受け取る）
+        let mut session_token: Option<String> = None;
+        // 直前の Update の手番。再同期の Update で同じ指し手を二重に流さないために使う
+        let mut last_next: Option<PlayerId> = None;
+        // 送ったがまだ Update で確認されていない自分の指し手
+        let mut pending: Option<Move> = None;
+        let mut rejoined = false;
 
-        // 1. Join
-        let join = NetMessage::Join {
-            name: "Player".to_string(),
+        // 1. ロビーで部屋を選ぶ
+        let name = self.name.clone();
+        let join = match action {
+            LobbyAction::QuickMatch => NetMessage::Join { name },
+            LobbyAction::CreateRoom(board_setup) => NetMessage::CreateRoom { name, board_setup },
+            LobbyAction::JoinRoom(room_id) => NetMessage::JoinRoom { name, room_id },
+            LobbyAction::PlayAi(board_setup) => NetMessage::PlayAi { name, board_setup },
         };
-        let join_json = serde_json::to_string(&join)? + "\n";
-        writer.write_all(join_json.as_bytes()).await?;
+        self.writer.send(&join).await?;
 
         // 2. Relay loop
         loop {
-            tokio::select! {
+            let lost = tokio::select! {
                 // Incoming from network
-                line_res = lines.next_line() => {
-                    let line = line_res?.ok_or_else(|| anyhow::anyhow!("Connection closed by server"))?;
-                    let msg: NetMessage = serde_json::from_str(&line)?;
-                    match msg {
-                        NetMessage::Welcome { player_id, board } => {
-                            my_id = Some(player_id);
-                            let _ = player_id_tx.send(player_id);
-                            let _ = board_tx.send((board, PlayerId::Player1));
-                        }
-                        NetMessage::MatchFound { opponent_name: _ } => {
-                        }
-                        NetMessage::Update { board, last_move, next_player } => {
-                            // 相手の指し手のみをUI表示用に流す
-                            // (自分が指した直後のUpdateでは next_player != me なのでスルーされる)
-                            if let Some(mv) = last_move {
-                                if let Some(me) = my_id {
-                                    if next_player == me {
+                msg_res = self.reader.recv() => match msg_res {
+                    Ok(Some(msg)) => {
+                        match msg {
+                            NetMessage::Welcome { player_id, board, session_token: token, time_control } => {
+                                my_id = Some(player_id);
+                                // 持ち時間制なら両者の初期時間から先手の時計を動かす（再接続時は直後の Update で上書き）
+                                if let Some(tc) = time_control {
+                                    let initial = tc.initial().as_millis() as u64;
+                                    let mut clock = clock.lock().unwrap();
+                                    clock.set_byoyomi(tc.byoyomi());
+                                    clock.sync([initial; 2], PlayerId::Player1);
+                                }
+                                session_token = Some(token).filter(|t| !t.is_empty());
+                                let _ = player_id_tx.send(player_id);
+                                let _ = board_tx.send((board, PlayerId::Player1));
+                            }
+                            NetMessage::MatchFound { opponent_name: _ } => {
+                            }
+                            NetMessage::Update { board, last_move, next_player, clock_ms } => {
+                                if let Some(ms) = clock_ms {
+                                    clock.lock().unwrap().sync(ms, next_player);
+                                }
+                                // 相手の指し手のみをUI表示用に流す
+                                // (自分が指した直後のUpdateでは next_player != me なのでスルーされる。
+                                //  再接続後の Update は、切断中に相手が指した場合だけ流す)
+                                if let (Some(mv), Some(me)) = (last_move, my_id) {
+                                    if next_player == me && last_next != Some(me) {
                                         let _ = remote_move_tx.send(mv);
                                     }
                                 }
+                                // 切断で届かなかった自分の指し手を送り直す
+                                if std::mem::take(&mut rejoined) && Some(next_player) == my_id {
+                                    if let Some(mv) = pending {
+                                        self.writer.send(&NetMessage::MakeMove { mv }).await?;
+                                    }
+                                }
+                                if Some(next_player) != my_id {
+                                    pending = None;
+                                }
+                                last_next = Some(next_player);
+                                let _ = board_tx.send((board, next_player));
                             }
-                            let _ = board_tx.send((board, next_player));
-                        }
-                        NetMessage::GameOver { winner: _, reason: _ } => {
-                            break;
-                        }
-                        NetMessage::Error { message } => {
-                            eprintln!("Server Error: {}", message);
+                            NetMessage::OpponentDisconnected { grace_secs } => {
+                                eprintln!("Opponent disconnected; waiting up to {}s\r", grace_secs);
+                            }
+                            NetMessage::OpponentReconnected => {
+                                eprintln!("Opponent reconnected\r");
+                            }
+                            NetMessage::GameOver { winner, reason } => {
+                                // 時間切れは TUI 側の手番待ちを終わらせるために時計に記録する
+                                if reason == TIME_FORFEIT {
+                                    clock.lock().unwrap().set_flagged(winner.opponent());
+                                }
+                                break;
+                            }
+                            NetMessage::RoomCreated { room_id } => {
+                                eprintln!("Room {} created; waiting for an opponent\r", room_id);
+                            }
+                            // 対局前のエラー（部屋が無いなど）はロビーに戻る
+                            NetMessage::Error { message } if my_id.is_none() => {
+                                anyhow::bail!("Server Error: {}", message);
+                            }
+                            NetMessage::Error { message } => {
+                                eprintln!("Server Error: {}", message);
+                            }
+                            _ => {}
                         }
-                        _ => {}
+                        false
                     }
-                }
+                    Ok(None) | Err(_) => true,
+                },
                 // Outgoing to network
                 local_mv_opt = local_move_rx.recv() => {
                     if let Some(mv) = local_mv_opt {
+                        pending = Some(mv);
                         let msg = NetMessage::MakeMove { mv };
-                        let json = serde_json::to_string(&msg)? + "\n";
-                        writer.write_all(json.as_bytes()).await?;
+                        self.writer.send(&msg).await.is_err()
                     } else {
                         break;
                     }
                 }
+            };
+
+            if lost {
+                // 対局中なら同じ席に戻る。再接続直後にまた切れた（拒否された）場合は諦める
+                match session_token.as_deref() {
+                    Some(token) if !rejoined => {
+                        self.rejoin(token).await?;
+                        rejoined = true;
+                    }
+                    _ => anyhow::bail!("Connection closed by server"),
+                }
             }
         }
         Ok(())
     }
+
+    /// 切断後、サーバーの猶予時間内に同じ対局へ再接続する
+    async fn rejoin(&mut self, token: &str) -> anyhow::Result<()> {
+        eprintln!("Connection lost; rejoining the game...\r");
+        for _ in 0..REJOIN_ATTEMPTS {
+            tokio::time::sleep(REJOIN_INTERVAL).await;
+            let Ok((mut reader, mut writer)) =
+                transport::connect_from(&self.addr, &self.binding).await
+            else {
+                continue;
+            };
+            if transport::negotiate(&mut reader, &mut writer, &WIRE_FORMATS)
+                .await
+                .is_err()
+            {
+                continue;
+            }
+            let rejoin = NetMessage::Rejoin {
+                token: token.to_string(),
+            };
+            if writer.send(&rejoin).await.is_ok() {
+                self.reader = reader;
+                self.writer = writer;
+                return Ok(());
+            }
+        }
+        anyhow::bail!("Could not rejoin the game")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_addr() {
+        assert_eq!(
+            NetworkClient::sanitize_addr(" 127.0.0.1 "),
+            "127.0.0.1:8080"
+        );
+        assert_eq!(
+            NetworkClient::sanitize_addr("tcp://host:9000/"),
+            "host:9000"
+        );
+        assert_eq!(
+            NetworkClient::sanitize_addr("ws://host:8080/"),
+            "ws://host:8080/"
+        );
+        // An `ngrok http` URL becomes a WebSocket over TLS
+        assert_eq!(
+            NetworkClient::sanitize_addr("https://abc.ngrok-free.app"),
+            "wss://abc.ngrok-free.app"
+        );
+    }
 }
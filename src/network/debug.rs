@@ -0,0 +1,168 @@
+//! Protocol console for debugging the network game
+//!
+//! Connects like a normal client (raw TCP or `ws://` / `wss://`), but instead of playing it prints every line
+//! exchanged with the server (with a timestamp) and sends whatever is typed at
+//! the prompt: `NetMessage` JSON, shortcuts for the common client messages, or
+//! arbitrary raw text to see how the server copes with malformed input.
+
+use crate::network::client::NetworkClient;
+use crate::network::protocol::NetMessage;
+use crate::network::transport;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const HELP: &str = "\
+Commands:
+  join [name]       Send Join, i.e. quick match (default name: netdebug)
+  rooms             Send ListRooms
+  status            Send Status (active games, open rooms, uptime)
+  create <setup>    Send CreateRoom with a setup name, e.g. ShogiOnly
+  room <id>         Send JoinRoom for the room with that ID
+  ai [setup]        Send PlayAi, i.e. play the server's engine
+  games             Send ListGames (games being played)
+  watch <id>        Send Watch to observe the game with that ID
+  resign            Send Resign
+  rejoin <token>    Send Rejoin with the session token from Welcome
+  {...} / \"...\"     Send a NetMessage written as JSON, e.g. {\"Join\":{\"name\":\"a\"}}
+  raw <text>        Send <text> verbatim, without validation
+  help              Show this help
+  quit              Disconnect";
+
+/// What the developer asked for at the prompt
+#[derive(Debug, PartialEq)]
+enum Command {
+    Send(String),
+    Help,
+    Quit,
+}
+
+fn parse_command(input: &str) -> anyhow::Result<Command> {
+    let input = input.trim();
+    let (word, rest) = input.split_once(' ').unwrap_or((input, ""));
+    let rest = rest.trim();
+
+    let msg = match word {
+        "" | "help" => return Ok(Command::Help),
+        "quit" | "exit" => return Ok(Command::Quit),
+        "raw" => return Ok(Command::Send(rest.to_string())),
+        "join" => NetMessage::Join {
+            name: if rest.is_empty() { "netdebug" } else { rest }.to_string(),
+        },
+        "rooms" => NetMessage::ListRooms,
+        "status" => NetMessage::Status,
+        "create" if !rest.is_empty() => NetMessage::CreateRoom {
+            name: "netdebug".to_string(),
+            board_setup: rest.to_string(),
+        },
+        "room" => NetMessage::JoinRoom {
+            name: "netdebug".to_string(),
+            room_id: rest
+                .parse()
+                .map_err(|_| anyhow::anyhow!("usage: room <id>"))?,
+        },
+        "ai" => NetMessage::PlayAi {
+            name: "netdebug".to_string(),
+            board_setup: (!rest.is_empty()).then(|| rest.to_string()),
+        },
+        "games" => NetMessage::ListGames,
+        "watch" => NetMessage::Watch {
+            game_id: rest
+                .parse()
+                .map_err(|_| anyhow::anyhow!("usage: watch <id>"))?,
+        },
+        "resign" => NetMessage::Resign,
+        "rejoin" if !rest.is_empty() => NetMessage::Rejoin {
+            token: rest.to_string(),
+        },
+        _ if input.starts_with('{') || input.starts_with('"') => serde_json::from_str(input)
+            .map_err(|e| {
+                anyhow::anyhow!("not a NetMessage ({}); use 'raw' to send it anyway", e)
+            })?,
+        _ => anyhow::bail!("unknown command '{}' (type 'help')", word),
+    };
+    Ok(Command::Send(serde_json::to_string(&msg)?))
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%H:%M:%S%.3f").to_string()
+}
+
+pub async fn run_netdebug(addr: &str) -> anyhow::Result<()> {
+    let addr = NetworkClient::sanitize_addr(addr);
+    println!("Connecting to {}...", addr);
+    let (mut reader, mut writer) = transport::connect(&addr).await?;
+    println!("[{}] connected to {}", timestamp(), addr);
+    println!("{}", HELP);
+
+    let mut input_lines = BufReader::new(tokio::io::stdin()).lines();
+
+    loop {
+        tokio::select! {
+            line = reader.recv_text() => {
+                let Some(line) = line? else {
+                    println!("[{}] connection closed by server", timestamp());
+                    break;
+                };
+                match serde_json::from_str::<NetMessage>(&line) {
+                    Ok(_) => println!("[{}] <- {}", timestamp(), line),
+                    Err(e) => println!("[{}] <- {}  (not a NetMessage: {})", timestamp(), line, e),
+                }
+            }
+            input = input_lines.next_line() => {
+                // EOF on stdin (e.g. piped input) ends the session
+                let Some(input) = input? else { break };
+                match parse_command(&input) {
+                    Ok(Command::Send(text)) => {
+                        writer.send_text(&text).await?;
+                        println!("[{}] -> {}", timestamp(), text);
+                    }
+                    Ok(Command::Help) => println!("{}", HELP),
+                    Ok(Command::Quit) => break,
+                    Err(e) => println!("[!] {}", e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("join alice").unwrap(),
+            Command::Send(r#"{"Join":{"name":"alice"}}"#.to_string())
+        );
+        assert_eq!(
+            parse_command(r#""Resign""#).unwrap(),
+            Command::Send(r#""Resign""#.to_string())
+        );
+        assert_eq!(
+            parse_command("raw {oops").unwrap(),
+            Command::Send("{oops".to_string())
+        );
+        assert!(parse_command("{oops").is_err());
+        assert_eq!(
+            parse_command("rejoin 0123").unwrap(),
+            Command::Send(r#"{"Rejoin":{"token":"0123"}}"#.to_string())
+        );
+        assert!(parse_command("rejoin").is_err());
+        assert_eq!(
+            parse_command("room 3").unwrap(),
+            Command::Send(r#"{"JoinRoom":{"name":"netdebug","room_id":3}}"#.to_string())
+        );
+        assert!(parse_command("room x").is_err());
+        assert_eq!(
+            parse_command("watch 2").unwrap(),
+            Command::Send(r#"{"Watch":{"game_id":2}}"#.to_string())
+        );
+        assert_eq!(
+            parse_command("ai").unwrap(),
+            Command::Send(r#"{"PlayAi":{"name":"netdebug","board_setup":null}}"#.to_string())
+        );
+        assert!(parse_command("dance").is_err());
+        assert_eq!(parse_command("quit").unwrap(), Command::Quit);
+    }
+}
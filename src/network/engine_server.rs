@@ -0,0 +1,373 @@
+//! Headless engine server (`serve-engine`)
+//!
+//! Line-delimited JSON-RPC 2.0 over stdio or TCP, one request per line:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"newposition","params":{"setup":"ShogiOnly","moves":["7g7f"]}}
+//! {"jsonrpc":"2.0","id":2,"method":"bestmove","params":{"depth":4}}
+//! ```
+//!
+//! Each connection keeps its own position and side to move. Moves in the
+//! responses are `{"move": <Move JSON>, "notation": "☗7六歩"}`; requests accept
+//! the Move JSON or typed USI / algebraic text (`7g7f`, `e2e4`, `P*5e`).
+//! Unlike the relay server, a malformed line gets an error response and the
+//! connection stays open.
+
+use crate::core::{Board, Move, PlayerId};
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Deepest search `bestmove` accepts
+const MAX_DEPTH: usize = 32;
+/// Depth of `bestmove` without `depth` or `movetime_ms`
+const DEFAULT_DEPTH: usize = 4;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The request was understood but the engine refused it (e.g. an illegal move)
+const ENGINE_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// A move in a request: Move JSON or typed text
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoveSpec {
+    Text(String),
+    Move(Move),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct NewPositionParams {
+    /// Named setup ("ShogiOnly", "Fair", ...); StandardMixed by default
+    setup: Option<String>,
+    /// A full board instead of a named setup
+    board: Option<Board>,
+    /// Side to move before `moves`; Player1 by default
+    player: Option<PlayerId>,
+    moves: Vec<MoveSpec>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PlayerParams {
+    player: Option<PlayerId>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct BestMoveParams {
+    depth: Option<usize>,
+    movetime_ms: Option<u64>,
+}
+
+/// One client's position and engines
+pub struct EngineSession {
+    board: Board,
+    player: PlayerId,
+    /// Engines are bound to a side, so one per player, created on first use
+    engines: HashMap<PlayerId, AlphaBetaAI>,
+}
+
+impl Default for EngineSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngineSession {
+    /// A session at the StandardMixed start position, Player1 to move
+    pub fn new() -> Self {
+        EngineSession {
+            board: crate::core::setup::BoardSetupType::StandardMixed.create_board(),
+            player: PlayerId::Player1,
+            engines: HashMap::new(),
+        }
+    }
+
+    /// Answers one request line; `None` for a notification (no `id`)
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e))),
+        };
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(Value::as_str) {
+            Some(method) => {
+                let params = request.get("params").cloned().unwrap_or(Value::Null);
+                self.call(method, params)
+            }
+            None => Err(RpcError::new(INVALID_REQUEST, "missing method")),
+        };
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string(),
+            Err(e) => error_response(id, e),
+        })
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "newposition" => self.new_position(parse_params(params)?),
+            "legalmoves" => {
+                let params: PlayerParams = parse_params(params)?;
+                let player = params.player.unwrap_or(self.player);
+                let moves = crate::logic::legal_moves(&self.board, player);
+                Ok(Value::Array(
+                    moves
+                        .into_iter()
+                        .map(|mv| move_json(mv, &self.board, player))
+                        .collect(),
+                ))
+            }
+            "bestmove" => self.best_move(parse_params(params)?),
+            "evaluate" => {
+                let engine = engine(&mut self.engines, self.player);
+                Ok(json!({
+                    "score": engine.static_eval(&self.board),
+                    "evaluator": engine.evaluator_name(),
+                }))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{}'", method),
+            )),
+        }
+    }
+
+    fn new_position(&mut self, params: NewPositionParams) -> Result<Value, RpcError> {
+        let mut board = match (params.board, params.setup) {
+            (Some(_), Some(_)) => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "give either board or setup, not both",
+                ))
+            }
+            (Some(board), None) => board,
+            (None, setup) => {
+                let name = setup.as_deref().unwrap_or("StandardMixed");
+                crate::core::setup::BoardSetupType::from_name(name)
+                    .ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, format!("unknown board setup '{}'", name))
+                    })?
+                    .create_board()
+            }
+        };
+        let mut player = params.player.unwrap_or(PlayerId::Player1);
+        for (i, spec) in params.moves.into_iter().enumerate() {
+            let mv = match spec {
+                MoveSpec::Text(text) => crate::core::r#move::parse_move(&text, &board, player)
+                    .map_err(|e| RpcError::new(ENGINE_ERROR, format!("move {}: {}", i + 1, e)))?,
+                MoveSpec::Move(mv) if crate::logic::legal_moves(&board, player).contains(&mv) => mv,
+                MoveSpec::Move(mv) => {
+                    return Err(RpcError::new(
+                        ENGINE_ERROR,
+                        format!("move {}: {} is not legal for {:?}", i + 1, mv, player),
+                    ))
+                }
+            };
+            board = crate::logic::apply_move(&board, &mv, player);
+            player = player.opponent();
+        }
+
+        self.board = board;
+        self.player = player;
+        Ok(json!({"player": player, "board": self.board}))
+    }
+
+    fn best_move(&mut self, params: BestMoveParams) -> Result<Value, RpcError> {
+        let depth = match (params.depth, params.movetime_ms) {
+            (Some(depth), _) if depth == 0 || depth > MAX_DEPTH => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("depth must be 1 to {}", MAX_DEPTH),
+                ))
+            }
+            (Some(depth), _) => depth,
+            (None, Some(_)) => MAX_DEPTH,
+            (None, None) => DEFAULT_DEPTH,
+        };
+        let time_limit = params
+            .movetime_ms
+            .map_or(Duration::MAX, Duration::from_millis);
+
+        let (board, player) = (&self.board, self.player);
+        let engine = engine(&mut self.engines, player);
+        let Some(best) = engine.search_with_limits(board, depth, time_limit) else {
+            return Ok(
+                json!({"best_move": null, "in_check": crate::logic::is_in_check(board, player)}),
+            );
+        };
+        let info = (*engine.last_thinking.borrow()).expect("a search that found a move");
+        let mut pv = engine.extract_pv(board, info.depth);
+        if pv.first() != Some(&best) {
+            pv = vec![best];
+        }
+
+        let mut pv_json = Vec::new();
+        let (mut position, mut mover) = (board.clone(), player);
+        for mv in pv {
+            pv_json.push(move_json(mv, &position, mover));
+            position = crate::logic::apply_move(&position, &mv, mover);
+            mover = mover.opponent();
+        }
+        Ok(json!({
+            "best_move": move_json(best, board, player),
+            "score": info.score,
+            "depth": info.depth,
+            "nodes": info.nodes,
+            "time_ms": info.time_ms as u64,
+            "pv": pv_json,
+        }))
+    }
+}
+
+/// The session's engine for `player`, created on first use
+fn engine(engines: &mut HashMap<PlayerId, AlphaBetaAI>, player: PlayerId) -> &AlphaBetaAI {
+    engines
+        .entry(player)
+        .or_insert_with(|| AlphaBetaAI::new(player, "Engine", AIStrength::Strong, None, true))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de> + Default>(params: Value) -> Result<T, RpcError> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+fn move_json(mv: Move, board: &Board, player: PlayerId) -> Value {
+    json!({"move": mv, "notation": mv.notation(board, player).to_string()})
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+    .to_string()
+}
+
+/// Serves one session until `reader` reaches end of input
+pub fn serve<R: BufRead, W: Write>(reader: R, mut writer: W) -> anyhow::Result<()> {
+    let mut session = EngineSession::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = session.handle_line(&line) {
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Serves requests on stdin, answering on stdout
+pub fn serve_stdio() -> anyhow::Result<()> {
+    serve(std::io::stdin().lock(), std::io::stdout().lock())
+}
+
+/// Listens on `addr`, one session (and thread) per connection
+pub fn serve_tcp(addr: &str) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("Engine server listening on {}", addr);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        std::thread::spawn(move || {
+            let peer = stream.peer_addr().ok();
+            if let Err(e) = serve_connection(stream) {
+                eprintln!("Engine connection {:?} failed: {}", peer, e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn serve_connection(stream: TcpStream) -> anyhow::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    serve(reader, stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(session: &mut EngineSession, method: &str, params: Value) -> Value {
+        let line = json!({"jsonrpc": "2.0", "id": 7, "method": method, "params": params});
+        let response = session.handle_line(&line.to_string()).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_engine_session() {
+        let mut session = EngineSession::new();
+        let response = request(
+            &mut session,
+            "newposition",
+            json!({"setup": "ShogiOnly", "moves": ["7g7f"]}),
+        );
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["player"], "Player2");
+
+        let moves = request(&mut session, "legalmoves", Value::Null);
+        assert_eq!(moves["result"].as_array().unwrap().len(), 30);
+
+        let best = request(&mut session, "bestmove", json!({"depth": 1}));
+        let best_move = best["result"]["best_move"]["move"].clone();
+        assert!(best_move.is_object());
+        assert_eq!(best["result"]["pv"][0]["move"], best_move);
+
+        // The returned Move JSON can be played back
+        let replay = request(
+            &mut session,
+            "newposition",
+            json!({"setup": "ShogiOnly", "moves": ["7g7f", best_move]}),
+        );
+        assert_eq!(replay["result"]["player"], "Player1");
+        assert!(request(&mut session, "evaluate", Value::Null)["result"]["score"].is_i64());
+
+        let illegal = request(&mut session, "newposition", json!({"moves": ["5e5d"]}));
+        assert_eq!(illegal["error"]["code"], ENGINE_ERROR);
+        let unknown = request(&mut session, "go", Value::Null);
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        let parse = session.handle_line("{not json").unwrap();
+        assert!(parse.contains(&PARSE_ERROR.to_string()));
+        // Notifications get no response
+        assert!(session
+            .handle_line(r#"{"jsonrpc":"2.0","method":"evaluate"}"#)
+            .is_none());
+    }
+
+    #[test]
+    fn test_movetime_is_kept() {
+        let mut session = EngineSession::new();
+        request(&mut session, "newposition", json!({"setup": "ShogiOnly"}));
+        let best = request(&mut session, "bestmove", json!({"movetime_ms": 300}));
+        assert!(best["result"]["best_move"].is_object());
+        let time_ms = best["result"]["time_ms"].as_u64().unwrap();
+        assert!(time_ms < 300 + 200, "searched {} ms for 300", time_ms);
+    }
+}
@@ -0,0 +1,99 @@
+//! Structured log of the network server
+//!
+//! One line per event in logfmt (`key=value` pairs), info on stdout and
+//! warnings and errors on stderr, so the output can be grepped or fed to a
+//! log collector as it is:
+//!
+//! ```text
+//! ts=2026-10-16T12:00:00.123+09:00 level=info event=match game=3 p1=alice p2=bob setup=ShogiOnly
+//! ```
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Silences every event, e.g. for a server embedded under the terminal UI
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// A field value, quoted when it would not read back as one token
+fn value(text: &str) -> String {
+    if text.is_empty() || text.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+        format!("{:?}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// The line of an event without its timestamp
+pub fn format_event(level: Level, event: &str, fields: &[(&str, &dyn Display)]) -> String {
+    let mut line = format!("level={} event={}", level.name(), event);
+    for (key, field) in fields {
+        line.push_str(&format!(" {}={}", key, value(&field.to_string())));
+    }
+    line
+}
+
+pub fn log(level: Level, event: &str, fields: &[(&str, &dyn Display)]) {
+    if QUIET.load(Ordering::Relaxed) {
+        return;
+    }
+    let ts = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z");
+    let line = format!("ts={} {}", ts, format_event(level, event, fields));
+    match level {
+        Level::Info => println!("{}", line),
+        Level::Warn | Level::Error => eprintln!("{}", line),
+    }
+}
+
+pub fn info(event: &str, fields: &[(&str, &dyn Display)]) {
+    log(Level::Info, event, fields);
+}
+
+pub fn warn(event: &str, fields: &[(&str, &dyn Display)]) {
+    log(Level::Warn, event, fields);
+}
+
+pub fn error(event: &str, fields: &[(&str, &dyn Display)]) {
+    log(Level::Error, event, fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_event() {
+        assert_eq!(
+            format_event(Level::Info, "match", &[("game", &3), ("p1", &"alice")]),
+            "level=info event=match game=3 p1=alice"
+        );
+        assert_eq!(
+            format_event(
+                Level::Warn,
+                "bad",
+                &[("error", &"expected `,` at x=1"), ("name", &"")]
+            ),
+            r#"level=warn event=bad error="expected `,` at x=1" name="""#
+        );
+    }
+}
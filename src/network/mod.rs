@@ -1,3 +1,9 @@
 pub mod client;
+pub mod debug;
+pub mod engine_server;
+pub mod log;
+pub mod observer;
 pub mod protocol;
 pub mod server;
+pub mod store;
+pub mod transport;
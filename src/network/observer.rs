@@ -0,0 +1,98 @@
+//! 対局の観戦（`watch` サブコマンド）
+//!
+//! サーバーで進行中の対局を `Watch` で購読し、指し手が届くたびに盤面を表示する。
+
+use crate::core::Board;
+use crate::network::client::{NetworkClient, WIRE_FORMATS};
+use crate::network::protocol::NetMessage;
+use crate::network::transport;
+use crate::ui::display::{render_board, DisplayState};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// `game_id` の対局を終局まで観戦する。省略すると進行中の対局の一覧から選ぶ
+pub async fn run_watch(addr: &str, game_id: Option<u64>) -> anyhow::Result<()> {
+    let addr = NetworkClient::sanitize_addr(addr);
+    let (mut reader, mut writer) = transport::connect(&addr).await?;
+    transport::negotiate(&mut reader, &mut writer, &WIRE_FORMATS).await?;
+
+    let game_id = match game_id {
+        Some(id) => id,
+        None => {
+            writer.send(&NetMessage::ListGames).await?;
+            let games = match reader.recv().await? {
+                Some(NetMessage::GameList { games }) => games,
+                Some(NetMessage::Error { message }) => anyhow::bail!("Server Error: {}", message),
+                other => anyhow::bail!("Unexpected reply to ListGames: {:?}", other),
+            };
+            if games.is_empty() {
+                anyhow::bail!("No games are being played on {}", addr);
+            }
+            println!("Games being played:");
+            for game in &games {
+                println!(
+                    "  {:>3}  {} vs {} ({}, {} moves)",
+                    game.id, game.player1, game.player2, game.board_setup, game.plies
+                );
+            }
+            println!("Game ID to watch:");
+            let mut input = String::new();
+            BufReader::new(tokio::io::stdin())
+                .read_line(&mut input)
+                .await?;
+            input
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("not a game ID: '{}'", input.trim()))?
+        }
+    };
+    writer.send(&NetMessage::Watch { game_id }).await?;
+
+    let mut title = String::new();
+    let mut plies = 0;
+    // 指し手の表記には指す前の盤面が要る
+    let mut previous: Option<Board> = None;
+    while let Some(msg) = reader.recv().await? {
+        match msg {
+            NetMessage::Watching { game } => {
+                title = format!(
+                    "Game {}: ☗{} vs ☖{} ({})",
+                    game.id, game.player1, game.player2, game.board_setup
+                );
+                plies = game.plies;
+            }
+            NetMessage::Update {
+                board,
+                last_move,
+                next_player,
+                ..
+            } => {
+                // 最初の Update は観戦を始めた時点の局面
+                if previous.is_some() {
+                    plies += 1;
+                }
+                let mut status = format!("{}  move {}", title, plies);
+                if let (Some(prev), Some(mv)) = (&previous, last_move) {
+                    let notation = mv.notation(prev, next_player.opponent());
+                    status.push_str(&format!("  {}", notation));
+                }
+                let state = DisplayState {
+                    status_msg: Some(status),
+                    last_move,
+                    show_cursor: false,
+                    ..Default::default()
+                };
+                render_board(&board, &state);
+                previous = Some(board);
+            }
+            NetMessage::GameOver { winner, reason } => {
+                println!("{:?} wins ({})", winner, reason);
+                return Ok(());
+            }
+            NetMessage::Error { message } => anyhow::bail!("Server Error: {}", message),
+            _ => {}
+        }
+    }
+    // 引き分けの終局は GameOver なしで配信が閉じられる
+    println!("Stream ended: the game was drawn or the server closed the connection");
+    Ok(())
+}
@@ -1,21 +1,89 @@
 use crate::core::{Board, Move, PlayerId};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// `GameOver` reason when the side to move ran out of time
+pub const TIME_FORFEIT: &str = "Time forfeit";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NetMessage {
     // Client -> Server
+    /// Offer wire formats, preferred first; the server answers `Encoding`.
+    /// Connections that never send it speak JSON throughout.
+    Hello {
+        formats: Vec<WireFormat>,
+    },
+    /// Quick match: take the oldest open room with the server's quick-match
+    /// setup (StandardMixed unless `server --board`), or open one
     Join {
         name: String,
     },
+    ListRooms,
+    /// Open a room with a named setup ("ShogiOnly", "Fair", ..., or the
+    /// server's custom setup by name) and wait in it
+    CreateRoom {
+        name: String,
+        board_setup: String,
+    },
+    JoinRoom {
+        name: String,
+        room_id: u64,
+    },
+    /// Play the server's engine instead of waiting for a person; the client
+    /// moves first. `board_setup` as in `CreateRoom`, `None` for the
+    /// quick-match setup.
+    PlayAi {
+        name: String,
+        #[serde(default)]
+        board_setup: Option<String>,
+    },
+    /// Games being played, to pick one to `Watch`
+    ListGames,
+    /// Follow a game as an observer: `Watching` and the position, then every
+    /// `Update` until the `GameOver`
+    Watch {
+        game_id: u64,
+    },
+    /// Ask for the server's `StatusReport` (from the lobby)
+    Status,
     MakeMove {
         mv: Move,
     },
     Resign,
+    /// Take back a seat after losing the connection, with the token from `Welcome`
+    Rejoin {
+        token: String,
+    },
 
     // Server -> Client
+    /// The wire format both sides switch to after this message
+    Encoding {
+        format: WireFormat,
+    },
+    RoomList {
+        rooms: Vec<RoomInfo>,
+    },
+    RoomCreated {
+        room_id: u64,
+    },
+    StatusReport {
+        status: ServerStatus,
+    },
+    GameList {
+        games: Vec<GameInfo>,
+    },
+    Watching {
+        game: GameInfo,
+    },
     Welcome {
         player_id: PlayerId,
         board: Board,
+        /// Sent back in `Rejoin` to resume this game after a disconnection
+        #[serde(default)]
+        session_token: String,
+        /// Clocks of the game; `None` for an untimed game
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time_control: Option<TimeControl>,
     },
     MatchFound {
         opponent_name: String,
@@ -24,7 +92,16 @@ pub enum NetMessage {
         board: Board,
         last_move: Option<Move>,
         next_player: PlayerId,
+        /// Main time (byoyomi not included) left for Player1 and Player2 in
+        /// milliseconds when this was sent; `next_player`'s clock is running
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        clock_ms: Option<[u64; 2]>,
+    },
+    /// The opponent lost the connection; they forfeit unless they rejoin in time
+    OpponentDisconnected {
+        grace_secs: u64,
     },
+    OpponentReconnected,
     GameOver {
         winner: PlayerId,
         reason: String,
@@ -33,3 +110,121 @@ pub enum NetMessage {
         message: String,
     },
 }
+
+/// Encoding of `NetMessage`s on a connection
+///
+/// JSON is one line (or WebSocket text frame) per message. MessagePack is a
+/// 4-byte big-endian length and the message on raw TCP, one binary frame over
+/// WebSocket; it cuts the full-board `Update`s by about a third.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl WireFormat {
+    pub fn encode(self, msg: &NetMessage) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            WireFormat::Json => serde_json::to_vec(msg)?,
+            // Named fields, so `#[serde(default)]` fields still read back
+            WireFormat::MessagePack => rmp_serde::to_vec_named(msg)?,
+        })
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> anyhow::Result<NetMessage> {
+        Ok(match self {
+            WireFormat::Json => serde_json::from_slice(bytes)?,
+            WireFormat::MessagePack => rmp_serde::from_slice(bytes)?,
+        })
+    }
+}
+
+impl std::fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WireFormat::Json => "json",
+            WireFormat::MessagePack => "msgpack",
+        })
+    }
+}
+
+/// An open room in the lobby, waiting for a second player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: u64,
+    pub host: String,
+    pub board_setup: String,
+}
+
+/// A game being played on the server, for observers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameInfo {
+    pub id: u64,
+    pub player1: String,
+    pub player2: String,
+    pub board_setup: String,
+    /// Moves played so far
+    pub plies: usize,
+}
+
+/// Fischer clock: `initial` per side, plus `increment` after every move.
+/// With `byoyomi`, a side whose time is used up still has that long for
+/// each move, as in shogi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub initial_secs: u64,
+    pub increment_secs: u64,
+    #[serde(default)]
+    pub byoyomi_secs: u64,
+}
+
+impl TimeControl {
+    pub fn initial(&self) -> Duration {
+        Duration::from_secs(self.initial_secs)
+    }
+
+    pub fn increment(&self) -> Duration {
+        Duration::from_secs(self.increment_secs)
+    }
+
+    pub fn byoyomi(&self) -> Duration {
+        Duration::from_secs(self.byoyomi_secs)
+    }
+}
+
+impl std::str::FromStr for TimeControl {
+    type Err = anyhow::Error;
+
+    /// `<initial secs>[+<increment secs>][/<byoyomi secs>]`, e.g. "300+5"
+    /// or "600/30"
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (fischer, byoyomi) = s.split_once('/').unwrap_or((s, "0"));
+        let (initial, increment) = fischer.split_once('+').unwrap_or((fischer, "0"));
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("bad time control '{}' (e.g. 300+5, 600/30)", s))
+        };
+        let time_control = TimeControl {
+            initial_secs: parse(initial)?,
+            increment_secs: parse(increment)?,
+            byoyomi_secs: parse(byoyomi)?,
+        };
+        if time_control.initial_secs == 0 && time_control.byoyomi_secs == 0 {
+            anyhow::bail!("time control '{}' has no time", s);
+        }
+        Ok(time_control)
+    }
+}
+
+/// Load of the server, as answered to `Status` and logged periodically
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub uptime_secs: u64,
+    pub active_games: usize,
+    /// Rooms whose host is waiting for an opponent
+    pub open_rooms: usize,
+    /// Games decided since the server started
+    pub finished_games: usize,
+}
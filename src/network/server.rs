@@ -1,196 +1,2078 @@
-use crate::core::PlayerId;
-use crate::network::protocol::NetMessage;
-use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use crate::core::setup::BoardSetupType;
+use crate::core::{Board, Move, PlayerId, Ruleset};
+use crate::network::log;
+use crate::network::protocol::{
+    GameInfo, NetMessage, RoomInfo, ServerStatus, TimeControl, TIME_FORFEIT,
+};
+use crate::network::store::GameStore;
+use crate::network::transport::{self, MessageReader, MessageWriter};
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+use tokio_rustls::TlsAcceptor;
 
-pub async fn start_server(addr: &str) -> anyhow::Result<()> {
+/// How long a disconnected player may take to rejoin before forfeiting
+pub const DEFAULT_RECONNECT_GRACE: Duration = Duration::from_secs(60);
+
+/// How often the server logs a `status` line
+pub const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct ServerOptions {
+    /// Serve every connection over TLS (see [`transport::load_tls_acceptor`])
+    pub tls: Option<TlsAcceptor>,
+    pub reconnect_grace: Duration,
+    /// Where finished games and player records are kept; `None` discards them
+    pub store: Option<Arc<GameStore>>,
+    /// Clocks of every game; `None` plays untimed
+    pub time_control: Option<TimeControl>,
+    /// Setup of quick-match games (`Join`)
+    pub board: GameSetup,
+    /// Rules of every game instead of the working directory's ruleset file
+    pub ruleset: Option<Ruleset>,
+    /// Period of the `status` log line; `None` logs none
+    pub status_interval: Option<Duration>,
+    /// How the engine plays `PlayAi` games; `None` refuses them
+    pub ai: Option<AiLimits>,
+    /// Address of the HTTP endpoint streaming games to observers as
+    /// server-sent events; `None` serves none
+    pub broadcast_addr: Option<String>,
+}
+
+/// Search limits of the server's engine per move
+#[derive(Debug, Clone, Copy)]
+pub struct AiLimits {
+    pub depth: usize,
+    /// Also capped at a tenth of the engine's clock in timed games
+    pub move_time: Duration,
+    /// Games against the engine at once, each searching on a thread of its
+    /// own; further `PlayAi` requests are refused
+    pub max_games: usize,
+}
+
+impl Default for AiLimits {
+    fn default() -> Self {
+        AiLimits {
+            depth: 4,
+            move_time: Duration::from_secs(5),
+            max_games: 4,
+        }
+    }
+}
+
+/// Starting position of a hosted game
+#[derive(Debug, Clone)]
+pub enum GameSetup {
+    Named(BoardSetupType),
+    /// Rows of a setup file (see [`crate::core::setup::setup_from_strings`]),
+    /// with shogi rules for both sides unless the ruleset overrides them
+    Custom {
+        name: String,
+        rows: Vec<String>,
+    },
+}
+
+impl GameSetup {
+    /// A setup name ("ShogiOnly", "Fair", ...) or else a setup file, named
+    /// after its file stem
+    pub fn parse(arg: &str) -> anyhow::Result<Self> {
+        if let Some(setup) = BoardSetupType::from_name(arg) {
+            return Ok(GameSetup::Named(setup));
+        }
+        let path = std::path::Path::new(arg);
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("'{}' is neither a setup name nor a file: {}", arg, e))?;
+        let rows: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty() && !row.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+        if rows.is_empty() {
+            anyhow::bail!("{} has no board rows", arg);
+        }
+        let name = path.file_stem().map_or_else(
+            || arg.to_string(),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+        Ok(GameSetup::Custom { name, rows })
+    }
+
+    fn create_board(&self, ruleset: Option<&Ruleset>) -> Board {
+        let mut board = match self {
+            GameSetup::Named(setup) => setup.create_board(),
+            GameSetup::Custom { rows, .. } => {
+                let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+                crate::core::setup::setup_from_strings(&rows, true, true, None, None)
+            }
+        };
+        if let Some(rules) = ruleset {
+            board.set_ruleset(rules.clone());
+        }
+        board
+    }
+}
+
+impl std::fmt::Display for GameSetup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameSetup::Named(setup) => write!(f, "{}", setup),
+            GameSetup::Custom { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            tls: None,
+            reconnect_grace: DEFAULT_RECONNECT_GRACE,
+            store: None,
+            time_control: None,
+            board: GameSetup::Named(BoardSetupType::StandardMixed),
+            ruleset: None,
+            status_interval: Some(DEFAULT_STATUS_INTERVAL),
+            ai: Some(AiLimits::default()),
+            broadcast_addr: None,
+        }
+    }
+}
+
+/// A room waiting for a second player. The host's connection stays with
+/// [`host_room`], which notices if the host leaves and takes the game.
+struct OpenRoom {
+    host: String,
+    setup: GameSetup,
+    seated: oneshot::Sender<SharedGame>,
+}
+
+/// Rooms waiting for an opponent, by ID (oldest first)
+#[derive(Default)]
+struct Lobby {
+    next_id: u64,
+    rooms: BTreeMap<u64, OpenRoom>,
+}
+
+impl Lobby {
+    /// A new room or game ID
+    fn allocate_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn open(&mut self, room: OpenRoom) -> u64 {
+        let id = self.allocate_id();
+        log::info(
+            "room_open",
+            &[("room", &id), ("host", &room.host), ("setup", &room.setup)],
+        );
+        self.rooms.insert(id, room);
+        id
+    }
+
+    /// Removes room `id` of a host who left; `false` if a guest has taken it
+    fn close(&mut self, id: u64) -> bool {
+        let closed = self.rooms.remove(&id).is_some();
+        if closed {
+            log::info("room_close", &[("room", &id)]);
+        }
+        closed
+    }
+
+    fn list(&self) -> Vec<RoomInfo> {
+        self.rooms
+            .iter()
+            .map(|(&id, room)| RoomInfo {
+                id,
+                host: room.host.clone(),
+                board_setup: room.setup.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// One player's side of a game
+struct Seat {
+    name: String,
+    /// Sent in `Welcome`; `Rejoin` with it takes the seat back
+    token: String,
+    /// Messages for the player's connection; `None` while the player is
+    /// disconnected
+    writer: Option<SeatQueue>,
+    /// Counts (re)connections, so a superseded reader or an expired grace
+    /// timer can tell that the player has come back since
+    connection: u64,
+}
+
+/// Sends queued messages to a player's connection from a task of its own,
+/// so the game is never locked while a slow peer's socket is full
+type SeatQueue = mpsc::UnboundedSender<NetMessage>;
+
+/// Starts the task that drains a seat's queue into `writer`; it ends when the
+/// queue is dropped or the connection fails (which the reader notices)
+fn seat_queue(mut writer: MessageWriter) -> SeatQueue {
+    let (queue, mut messages) = mpsc::unbounded_channel::<NetMessage>();
+    tokio::spawn(async move {
+        while let Some(msg) = messages.recv().await {
+            if writer.send(&msg).await.is_err() {
+                break;
+            }
+        }
+    });
+    queue
+}
+
+/// Messages an observer misses before it only gets the latest `Update`
+const OBSERVER_BACKLOG: usize = 64;
+
+/// Name of the engine's seat in `MatchFound`, the log and the records
+const AI_NAME: &str = "AI";
+
+type AiRequest = (Board, Duration, oneshot::Sender<Option<Move>>);
+
+/// The server's engine in one seat of a game. It searches on a thread of its
+/// own, which ends with the game.
+struct AiSeat {
+    player: PlayerId,
+    move_time: Duration,
+    requests: std::sync::mpsc::Sender<AiRequest>,
+    _slot: AiSlot,
+}
+
+/// One of the `AiLimits::max_games` engine seats, given back when dropped
+struct AiSlot(Arc<AtomicUsize>);
+
+impl AiSlot {
+    /// `None` if `max` engine seats are taken
+    fn take(in_use: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        in_use
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()?;
+        Some(AiSlot(Arc::clone(in_use)))
+    }
+}
+
+impl Drop for AiSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl AiSeat {
+    fn spawn(player: PlayerId, limits: AiLimits, slot: AiSlot) -> Self {
+        let (requests, queue) = std::sync::mpsc::channel::<AiRequest>();
+        std::thread::spawn(move || {
+            let ai = AlphaBetaAI::new(player, AI_NAME, AIStrength::Strong, None, true);
+            for (board, time_limit, reply) in queue {
+                let _ = reply.send(ai.search_with_limits(&board, limits.depth, time_limit));
+            }
+        });
+        AiSeat {
+            player,
+            move_time: limits.move_time,
+            requests,
+            _slot: slot,
+        }
+    }
+}
+
+/// Both players' clocks; the side to move's has been running since `turn_started`
+struct GameClock {
+    /// Main time left, byoyomi not included
+    remaining: [Duration; 2],
+    increment: Duration,
+    byoyomi: Duration,
+    turn_started: Instant,
+}
+
+impl GameClock {
+    fn new(time_control: TimeControl) -> Self {
+        GameClock {
+            remaining: [time_control.initial(); 2],
+            increment: time_control.increment(),
+            byoyomi: time_control.byoyomi(),
+            turn_started: Instant::now(),
+        }
+    }
+
+    /// Time `player` has spent on the current move, with `to_move` on the move
+    fn spent(&self, player: PlayerId, to_move: PlayerId) -> Duration {
+        if player == to_move {
+            self.turn_started.elapsed()
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Time before `player`'s flag falls right now, with `to_move` on the move
+    fn left(&self, player: PlayerId, to_move: PlayerId) -> Duration {
+        (self.remaining[seat_index(player)] + self.byoyomi)
+            .saturating_sub(self.spent(player, to_move))
+    }
+
+    /// Stops `player`'s clock after their move and starts the opponent's.
+    /// `false` if the move came too late.
+    fn punch(&mut self, player: PlayerId) -> bool {
+        if self.left(player, player).is_zero() {
+            return false;
+        }
+        // A move within the byoyomi leaves the main time at zero
+        let main = &mut self.remaining[seat_index(player)];
+        *main = main.saturating_sub(self.turn_started.elapsed()) + self.increment;
+        self.turn_started = Instant::now();
+        true
+    }
+
+    /// Main time left for each side, as sent in `Update`
+    fn millis(&self, to_move: PlayerId) -> [u64; 2] {
+        [PlayerId::Player1, PlayerId::Player2].map(|player| {
+            self.remaining[seat_index(player)]
+                .saturating_sub(self.spent(player, to_move))
+                .as_millis() as u64
+        })
+    }
+}
+
+struct Game {
+    /// ID of the room the game was set up in, for the log
+    id: u64,
+    setup: GameSetup,
+    /// Starting position, kept in the kifu so a replay needs no setup name
+    initial: Board,
+    board: Board,
+    /// Every move so far, for the kifu
+    moves: Vec<Move>,
+    /// Plies without progress, for the quiet-ply limit
+    stagnation: crate::selfplay::adjudication::StagnationTracker,
+    next_player: PlayerId,
+    last_move: Option<Move>,
+    seats: [Seat; 2],
+    clock: Option<GameClock>,
+    /// The engine's seat in a `PlayAi` game
+    ai: Option<AiSeat>,
+    /// Every `Update` and the `GameOver`, for whoever watches; dropped when
+    /// the game ends, which ends every observer's stream
+    observers: Option<broadcast::Sender<NetMessage>>,
+    finished: bool,
+}
+
+type SharedGame = Arc<Mutex<Game>>;
+
+fn seat_index(player: PlayerId) -> usize {
+    match player {
+        PlayerId::Player1 => 0,
+        PlayerId::Player2 => 1,
+    }
+}
+
+/// Player in log lines
+fn side(player: PlayerId) -> &'static str {
+    match player {
+        PlayerId::Player1 => "Player1",
+        PlayerId::Player2 => "Player2",
+    }
+}
+
+impl Game {
+    /// A game about to start from `board`, Player1 to move
+    fn new(
+        id: u64,
+        setup: GameSetup,
+        board: Board,
+        seats: [Seat; 2],
+        time_control: Option<TimeControl>,
+        ai: Option<AiSeat>,
+    ) -> Self {
+        Game {
+            id,
+            setup,
+            initial: board.clone(),
+            board,
+            moves: Vec::new(),
+            stagnation: Default::default(),
+            next_player: PlayerId::Player1,
+            last_move: None,
+            seats,
+            clock: time_control.map(GameClock::new),
+            ai,
+            observers: Some(broadcast::channel(OBSERVER_BACKLOG).0),
+            finished: false,
+        }
+    }
+
+    fn seat(&mut self, player: PlayerId) -> &mut Seat {
+        &mut self.seats[seat_index(player)]
+    }
+
+    /// Queues a message for `player` if connected
+    fn send_to(&self, player: PlayerId, msg: &NetMessage) {
+        if let Some(queue) = &self.seats[seat_index(player)].writer {
+            let _ = queue.send(msg.clone());
+        }
+    }
+
+    /// Sends to the observers, if any
+    fn broadcast(&self, msg: &NetMessage) {
+        if let Some(observers) = &self.observers {
+            let _ = observers.send(msg.clone());
+        }
+    }
+
+    fn info(&self) -> GameInfo {
+        GameInfo {
+            id: self.id,
+            player1: self.seats[0].name.clone(),
+            player2: self.seats[1].name.clone(),
+            board_setup: self.setup.to_string(),
+            plies: self.moves.len(),
+        }
+    }
+
+    /// Collected before awaiting: `Game` holds connections that are not `Sync`
+    fn tokens(&self) -> Vec<String> {
+        self.seats.iter().map(|seat| seat.token.clone()).collect()
+    }
+
+    fn kifu(&self) -> crate::game::KifuData {
+        crate::game::KifuData {
+            board_setup: self.setup.to_string(),
+            chess960_seed: None,
+            handicap: None,
+            player1_name: self.seats[0].name.clone(),
+            player2_name: self.seats[1].name.clone(),
+            moves: self.moves.clone(),
+            thinking_data: None,
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots: vec![crate::game::KifuSnapshot {
+                ply: 0,
+                side_to_move: PlayerId::Player1,
+                board: self.initial.clone(),
+            }],
+            result: None,
+            variations: Vec::new(),
+        }
+    }
+
+    /// Marks the game decided, logs and saves it
+    fn finish(&mut self, state: &ServerState, winner: Option<PlayerId>, reason: &str) {
+        self.finished = true;
+        // Ends the engine's thread and frees its seat for another game
+        self.ai = None;
+        state.active_games.fetch_sub(1, Ordering::Relaxed);
+        state.finished_games.fetch_add(1, Ordering::Relaxed);
+        let winner_name = winner.map_or("draw", side);
+        log::info(
+            "game_over",
+            &[
+                ("game", &self.id),
+                ("winner", &winner_name),
+                ("reason", &reason),
+                ("plies", &self.moves.len()),
+            ],
+        );
+        state.record(self, winner, reason);
+        // Observers are told even when the players see it on the board.
+        // `GameOver` has no draw, so the feed is closed as well: its end is
+        // the end of the game.
+        if let Some(winner) = winner {
+            self.broadcast(&NetMessage::GameOver {
+                winner,
+                reason: reason.to_string(),
+            });
+        }
+        self.observers = None;
+    }
+
+    /// Ends the game with a `GameOver` to whoever is connected, and saves it
+    fn game_over(&mut self, state: &ServerState, winner: PlayerId, reason: &str) {
+        self.finish(state, Some(winner), reason);
+        let game_over = NetMessage::GameOver {
+            winner,
+            reason: reason.to_string(),
+        };
+        self.send_to(PlayerId::Player1, &game_over);
+        self.send_to(PlayerId::Player2, &game_over);
+    }
+
+    /// Plays `player`'s legal move `mv`: runs the clocks, sends the new
+    /// position to both sides and ends the game if the reply has no move
+    fn play(&mut self, state: &ServerState, player: PlayerId, mv: Move) {
+        if !self.clock.as_mut().is_none_or(|clock| clock.punch(player)) {
+            // Flag fell before the clock watcher noticed
+            self.game_over(state, player.opponent(), TIME_FORFEIT);
+            return;
+        }
+        self.stagnation.record(&self.board, &mv);
+        self.board = crate::logic::apply_move(&self.board, &mv, player);
+        self.next_player = player.opponent();
+        self.last_move = Some(mv);
+        self.moves.push(mv);
+        log::info(
+            "move",
+            &[
+                ("game", &self.id),
+                ("player", &side(player)),
+                ("ply", &self.moves.len()),
+                ("move", &mv),
+            ],
+        );
+        let update = self.update();
+        self.send_to(PlayerId::Player1, &update);
+        self.send_to(PlayerId::Player2, &update);
+        self.broadcast(&update);
+
+        // A rule or the lack of a reply decides the game
+        // (the clients see it on the board themselves)
+        let to_move = self.next_player;
+        if let Some(ending) = crate::logic::rules::rule_ending(
+            &self.board,
+            to_move,
+            self.moves.len(),
+            self.stagnation.quiet_plies(),
+        ) {
+            self.finish(state, ending.winner(to_move), ending.reason());
+        } else if crate::logic::legal_moves(&self.board, to_move).is_empty() {
+            let winner = crate::logic::rules::no_move_winner(&self.board, to_move);
+            let reason = if crate::logic::is_in_check(&self.board, to_move) {
+                "Checkmate"
+            } else {
+                "Stalemate"
+            };
+            self.finish(state, winner, reason);
+        }
+    }
+
+    fn update(&self) -> NetMessage {
+        NetMessage::Update {
+            board: self.board.clone(),
+            last_move: self.last_move,
+            next_player: self.next_player,
+            clock_ms: self.clock.as_ref().map(|c| c.millis(self.next_player)),
+        }
+    }
+}
+
+struct ServerState {
+    options: ServerOptions,
+    lobby: Mutex<Lobby>,
+    /// Session token -> the game and the player it seats
+    sessions: Mutex<HashMap<String, (SharedGame, PlayerId)>>,
+    /// Games by ID, for observers; an entry outlives its game only until
+    /// the next one starts
+    games: Mutex<BTreeMap<u64, Weak<Mutex<Game>>>>,
+    started: Instant,
+    /// Games being played, and games decided since the start
+    active_games: AtomicUsize,
+    finished_games: AtomicUsize,
+    /// Engine seats taken, up to `AiLimits::max_games`
+    ai_games: Arc<AtomicUsize>,
+}
+
+/// Hosts a lobby of rooms on `addr` and relays each game between the two
+/// players of a room, any number of games at once. A player who drops out
+/// mid-game keeps their seat for `options.reconnect_grace` and can take it
+/// back with `Rejoin`; after that the opponent wins.
+pub async fn start_server(addr: &str, options: ServerOptions) -> anyhow::Result<()> {
     let listener = TcpListener::bind(addr).await?;
-    println!("Server started on {}", addr);
+    let http = match &options.broadcast_addr {
+        Some(http_addr) => {
+            let http = TcpListener::bind(http_addr).await?;
+            log::info("broadcast_start", &[("addr", http_addr)]);
+            Some(http)
+        }
+        None => None,
+    };
+    let security = if options.tls.is_some() {
+        "TLS"
+    } else {
+        "plaintext"
+    };
+    log::info(
+        "server_start",
+        &[
+            ("addr", &addr),
+            ("transports", &"tcp,websocket"),
+            ("security", &security),
+        ],
+    );
+
+    serve(listener, http, options).await
+}
 
-    let waiting_room: Arc<Mutex<Option<(TcpStream, String)>>> = Arc::new(Mutex::new(None));
+/// Like [`start_server`], on a listener the caller has bound (e.g. with
+/// [`transport::bind_listener`]), without an HTTP endpoint
+pub async fn serve_on(listener: TcpListener, options: ServerOptions) -> anyhow::Result<()> {
+    serve(listener, None, options).await
+}
+
+async fn serve(
+    listener: TcpListener,
+    http: Option<TcpListener>,
+    options: ServerOptions,
+) -> anyhow::Result<()> {
+    let state = Arc::new(ServerState {
+        options,
+        lobby: Mutex::new(Lobby::default()),
+        sessions: Mutex::new(HashMap::new()),
+        games: Mutex::new(BTreeMap::new()),
+        started: Instant::now(),
+        active_games: AtomicUsize::new(0),
+        finished_games: AtomicUsize::new(0),
+        ai_games: Arc::new(AtomicUsize::new(0)),
+    });
+    if let Some(interval) = state.options.status_interval {
+        tokio::spawn(log_status(Arc::clone(&state), interval));
+    }
+    if let Some(http) = http {
+        tokio::spawn(serve_broadcast(http, Arc::clone(&state)));
+    }
 
     loop {
-        let (socket, _) = listener.accept().await?;
-        let waiting_room = Arc::clone(&waiting_room);
+        let (socket, peer) = listener.accept().await?;
+        let state = Arc::clone(&state);
+        log::info("connect", &[("peer", &peer)]);
 
         tokio::spawn(async move {
-            if let Err(e) = handle_new_connection(socket, waiting_room).await {
-                eprintln!("Error handling connection: {}", e);
+            if let Err(e) = handle_new_connection(socket, peer, state).await {
+                log::warn("connection_error", &[("peer", &peer), ("error", &e)]);
             }
         });
     }
 }
 
+/// Logs a `status` line every `interval`
+async fn log_status(state: Arc<ServerState>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let status = state.status().await;
+        log::info(
+            "status",
+            &[
+                ("uptime_secs", &status.uptime_secs),
+                ("active_games", &status.active_games),
+                ("open_rooms", &status.open_rooms),
+                ("finished_games", &status.finished_games),
+            ],
+        );
+    }
+}
+
+fn new_token() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Lobby loop of a new connection: answers `ListRooms` until the client
+/// opens a room, takes a seat in one, or rejoins a game
 async fn handle_new_connection(
-    mut socket: TcpStream,
-    waiting_room: Arc<Mutex<Option<(TcpStream, String)>>>,
+    socket: TcpStream,
+    peer: SocketAddr,
+    state: Arc<ServerState>,
 ) -> anyhow::Result<()> {
-    let mut reader = BufReader::new(&mut socket);
-    let mut line = String::new();
-    reader.read_line(&mut line).await?;
+    let (mut reader, mut writer) = transport::accept(socket, state.options.tls.as_ref()).await?;
 
-    let msg: NetMessage = serde_json::from_str(&line)?;
-    let player_name = if let NetMessage::Join { name } = msg {
-        name
-    } else {
-        return Err(anyhow::anyhow!("Expected Join message"));
-    };
+    loop {
+        let frame = reader
+            .recv_frame()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Connection closed in the lobby"))?;
+        let msg = match reader.format().decode(&frame) {
+            Ok(msg) => msg,
+            Err(e) => {
+                log::warn("malformed_message", &[("peer", &peer), ("error", &e)]);
+                let message = format!("Malformed message: {}", e);
+                writer.send(&NetMessage::Error { message }).await?;
+                continue;
+            }
+        };
 
-    let mut lock = waiting_room.lock().await;
-    if let Some((mut socket1, name1)) = lock.take() {
-        drop(lock); // Release lock as soon as possible
-        println!("Match found: {} vs {}", name1, player_name);
-
-        // Setup game
-        let board = crate::core::setup::setup_from_strings(
-            &crate::core::setup::get_standard_mixed_setup(),
-            true,
-            true,
-            None,
-            None,
-        );
+        let (name, room) = match msg {
+            NetMessage::ListRooms => {
+                let rooms = state.lobby.lock().await.list();
+                writer.send(&NetMessage::RoomList { rooms }).await?;
+                continue;
+            }
+            NetMessage::Hello { formats } => {
+                // Every format is supported, so the client's first choice wins
+                let format = formats.first().copied().unwrap_or_default();
+                writer.send(&NetMessage::Encoding { format }).await?;
+                reader.set_format(format);
+                writer.set_format(format);
+                log::info("encoding", &[("peer", &peer), ("format", &format)]);
+                continue;
+            }
+            NetMessage::ListGames => {
+                let games = state.live_games().await;
+                writer.send(&NetMessage::GameList { games }).await?;
+                continue;
+            }
+            NetMessage::Watch { game_id } => {
+                let Some((intro, feed)) = state.watch(game_id).await else {
+                    let message = format!("No game {} being played", game_id);
+                    writer.send(&NetMessage::Error { message }).await?;
+                    continue;
+                };
+                log::info("watch", &[("peer", &peer), ("game", &game_id)]);
+                return observe(reader, writer, intro, feed).await;
+            }
+            NetMessage::Status => {
+                let status = state.status().await;
+                writer.send(&NetMessage::StatusReport { status }).await?;
+                continue;
+            }
+            NetMessage::Rejoin { token } => return rejoin(state, token, reader, writer).await,
+            NetMessage::PlayAi { name, board_setup } => {
+                let Some(limits) = state.options.ai else {
+                    let message = "This server does not host games against the AI".to_string();
+                    writer.send(&NetMessage::Error { message }).await?;
+                    continue;
+                };
+                let setup = match board_setup {
+                    Some(board_setup) => state.setup_named(&board_setup).ok_or(board_setup),
+                    None => Ok(state.options.board.clone()),
+                };
+                let setup = match setup {
+                    Ok(setup) => setup,
+                    Err(board_setup) => {
+                        let message = format!("Unknown board setup '{}'", board_setup);
+                        writer.send(&NetMessage::Error { message }).await?;
+                        continue;
+                    }
+                };
+                let Some(slot) = AiSlot::take(&state.ai_games, limits.max_games) else {
+                    log::warn(
+                        "ai_busy",
+                        &[("peer", &peer), ("max_games", &limits.max_games)],
+                    );
+                    let message =
+                        "All of the server's AI seats are taken; try again later".to_string();
+                    writer.send(&NetMessage::Error { message }).await?;
+                    continue;
+                };
+                let game = start_ai_game(&state, name, setup, limits, slot, writer).await?;
+                relay_player(state, game, PlayerId::Player1, reader, 0).await;
+                return Ok(());
+            }
+            NetMessage::CreateRoom { name, board_setup } => {
+                let Some(setup) = state.setup_named(&board_setup) else {
+                    let message = format!("Unknown board setup '{}'", board_setup);
+                    writer.send(&NetMessage::Error { message }).await?;
+                    continue;
+                };
+                let (seated, seat) = oneshot::channel();
+                let room = OpenRoom {
+                    host: name,
+                    setup,
+                    seated,
+                };
+                let room_id = state.lobby.lock().await.open(room);
+                if let Err(e) = writer.send(&NetMessage::RoomCreated { room_id }).await {
+                    state.lobby.lock().await.close(room_id);
+                    return Err(e);
+                }
+                return host_room(state, room_id, reader, writer, seat).await;
+            }
+            NetMessage::JoinRoom { name, room_id } => {
+                let room = state.lobby.lock().await.rooms.remove(&room_id);
+                if room.is_none() {
+                    let message = format!("No open room {}", room_id);
+                    writer.send(&NetMessage::Error { message }).await?;
+                    continue;
+                }
+                (name, room.map(|room| (room_id, room)))
+            }
+            NetMessage::Join { name } => {
+                let mut lobby = state.lobby.lock().await;
+                let quick = state.options.board.to_string();
+                let id = lobby
+                    .rooms
+                    .iter()
+                    .find(|(_, room)| room.setup.to_string() == quick)
+                    .map(|(&id, _)| id);
+                match id {
+                    Some(id) => (name, lobby.rooms.remove(&id).map(|room| (id, room))),
+                    None => {
+                        let (seated, seat) = oneshot::channel();
+                        let room_id = lobby.open(OpenRoom {
+                            host: name,
+                            setup: state.options.board.clone(),
+                            seated,
+                        });
+                        drop(lobby);
+                        return host_room(state, room_id, reader, writer, seat).await;
+                    }
+                }
+            }
+            _ => {
+                let message = "Expected a lobby message".to_string();
+                writer.send(&NetMessage::Error { message }).await?;
+                continue;
+            }
+        };
 
-        // Notify players
-        send_msg(
-            &mut socket1,
-            &NetMessage::Welcome {
-                player_id: PlayerId::Player1,
-                board: board.clone(),
-            },
-        )
-        .await?;
-        send_msg(
-            &mut socket1,
-            &NetMessage::MatchFound {
-                opponent_name: player_name.clone(),
-            },
-        )
-        .await?;
+        let Some((id, room)) = room else { continue };
+        match start_game(&state, id, room, &name, writer).await {
+            Ok(game) => {
+                relay_player(state, game, PlayerId::Player2, reader, 0).await;
+                return Ok(());
+            }
+            Err(returned) => {
+                log::warn("host_gone", &[("room", &id), ("guest", &name)]);
+                writer = returned;
+                let message = "The room's host has left".to_string();
+                writer.send(&NetMessage::Error { message }).await?;
+            }
+        }
+    }
+}
+
+/// Seats `guest` opposite the host of `room` and hands the game to the
+/// host's [`host_room`]. If the host has gone, the guest's writer comes back
+/// so they can stay in the lobby.
+async fn start_game(
+    state: &Arc<ServerState>,
+    id: u64,
+    room: OpenRoom,
+    guest: &str,
+    guest_writer: MessageWriter,
+) -> Result<SharedGame, MessageWriter> {
+    let OpenRoom {
+        host,
+        setup,
+        seated,
+    } = room;
+    let board = setup.create_board(state.options.ruleset.as_ref());
+    let (token1, token2) = (new_token(), new_token());
+    log::info(
+        "match",
+        &[
+            ("game", &id),
+            ("p1", &host),
+            ("p2", &guest),
+            ("setup", &setup),
+        ],
+    );
+
+    // Each side is welcomed by its own connection before it gets a writer
+    let seats = [
+        Seat {
+            name: host.clone(),
+            token: token1.clone(),
+            writer: None,
+            connection: 0,
+        },
+        Seat {
+            name: guest.to_string(),
+            token: token2.clone(),
+            writer: None,
+            connection: 0,
+        },
+    ];
+    let game = Game::new(
+        id,
+        setup,
+        board.clone(),
+        seats,
+        state.options.time_control,
+        None,
+    );
+    let game = Arc::new(Mutex::new(game));
+    if seated.send(Arc::clone(&game)).is_err() {
+        return Err(guest_writer);
+    }
+
+    let queue = seat_queue(guest_writer);
+    let _ = queue.send(NetMessage::Welcome {
+        player_id: PlayerId::Player2,
+        board,
+        session_token: token2.clone(),
+        time_control: state.options.time_control,
+    });
+    let _ = queue.send(NetMessage::MatchFound {
+        opponent_name: host,
+    });
+    game.lock().await.seat(PlayerId::Player2).writer = Some(queue);
 
-        send_msg(
-            &mut socket,
-            &NetMessage::Welcome {
-                player_id: PlayerId::Player2,
-                board: board.clone(),
+    state.active_games.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut sessions = state.sessions.lock().await;
+        sessions.insert(token1, (Arc::clone(&game), PlayerId::Player1));
+        sessions.insert(token2, (Arc::clone(&game), PlayerId::Player2));
+    }
+    state.register(id, &game).await;
+    if state.options.time_control.is_some() {
+        tokio::spawn(watch_clock(Arc::clone(state), Arc::clone(&game)));
+    }
+    Ok(game)
+}
+
+/// The host's connection while room `id` is open: closes the room if the
+/// host leaves, else plays the host's side once a guest takes the room.
+/// Anything the host sends meanwhile is ignored.
+async fn host_room(
+    state: Arc<ServerState>,
+    id: u64,
+    mut reader: MessageReader,
+    writer: MessageWriter,
+    mut seat: oneshot::Receiver<SharedGame>,
+) -> anyhow::Result<()> {
+    let game = loop {
+        tokio::select! {
+            game = &mut seat => match game {
+                Ok(game) => break game,
+                Err(_) => return Ok(()),
             },
-        )
+            frame = reader.recv_frame() => {
+                if matches!(frame, Ok(Some(_))) {
+                    continue;
+                }
+                if state.lobby.lock().await.close(id) {
+                    return Ok(());
+                }
+                // A guest took the room just now; the game sees the host gone
+                match seat.await {
+                    Ok(game) => break game,
+                    Err(_) => return Ok(()),
+                }
+            }
+        }
+    };
+
+    {
+        let mut g = game.lock().await;
+        let queue = seat_queue(writer);
+        let _ = queue.send(NetMessage::Welcome {
+            player_id: PlayerId::Player1,
+            board: g.board.clone(),
+            session_token: g.seats[0].token.clone(),
+            time_control: state.options.time_control,
+        });
+        let _ = queue.send(NetMessage::MatchFound {
+            opponent_name: g.seats[1].name.clone(),
+        });
+        g.seat(PlayerId::Player1).writer = Some(queue);
+    }
+    relay_player(state, game, PlayerId::Player1, reader, 0).await;
+    Ok(())
+}
+
+/// Seats `name` as Player1 opposite the server's engine
+async fn start_ai_game(
+    state: &Arc<ServerState>,
+    name: String,
+    setup: GameSetup,
+    limits: AiLimits,
+    slot: AiSlot,
+    mut writer: MessageWriter,
+) -> anyhow::Result<SharedGame> {
+    let id = state.lobby.lock().await.allocate_id();
+    let board = setup.create_board(state.options.ruleset.as_ref());
+    let token = new_token();
+
+    writer
+        .send(&NetMessage::Welcome {
+            player_id: PlayerId::Player1,
+            board: board.clone(),
+            session_token: token.clone(),
+            time_control: state.options.time_control,
+        })
         .await?;
-        send_msg(
-            &mut socket,
-            &NetMessage::MatchFound {
-                opponent_name: name1.clone(),
-            },
-        )
+    writer
+        .send(&NetMessage::MatchFound {
+            opponent_name: AI_NAME.to_string(),
+        })
         .await?;
+    log::info(
+        "match",
+        &[
+            ("game", &id),
+            ("p1", &name),
+            ("p2", &AI_NAME),
+            ("setup", &setup),
+            ("ai_depth", &limits.depth),
+        ],
+    );
 
-        // Relay loop
-        relay_game(socket1, socket, board).await?;
-    } else {
-        *lock = Some((socket, player_name));
+    state.active_games.fetch_add(1, Ordering::Relaxed);
+    let seats = [
+        Seat {
+            name,
+            token: token.clone(),
+            writer: Some(seat_queue(writer)),
+            connection: 0,
+        },
+        // The engine has no connection and cannot rejoin
+        Seat {
+            name: AI_NAME.to_string(),
+            token: String::new(),
+            writer: None,
+            connection: 0,
+        },
+    ];
+    let ai = AiSeat::spawn(PlayerId::Player2, limits, slot);
+    let game = Game::new(
+        id,
+        setup,
+        board,
+        seats,
+        state.options.time_control,
+        Some(ai),
+    );
+    let game = Arc::new(Mutex::new(game));
+    state
+        .sessions
+        .lock()
+        .await
+        .insert(token, (Arc::clone(&game), PlayerId::Player1));
+    state.register(id, &game).await;
+    if state.options.time_control.is_some() {
+        tokio::spawn(watch_clock(Arc::clone(state), Arc::clone(&game)));
+    }
+    Ok(game)
+}
+
+/// Has the engine answer if it is on the move; its search runs without
+/// holding the game. An engine without a move resigns.
+async fn ai_turn(state: Arc<ServerState>, game: SharedGame) {
+    let (reply, ply) = {
+        let g = game.lock().await;
+        let Some(ai) = &g.ai else { return };
+        if g.finished || g.next_player != ai.player {
+            return;
+        }
+        let time_limit = match &g.clock {
+            Some(clock) => ai.move_time.min(clock.left(ai.player, ai.player) / 10),
+            None => ai.move_time,
+        };
+        let (tx, rx) = oneshot::channel();
+        if ai.requests.send((g.board.clone(), time_limit, tx)).is_err() {
+            return;
+        }
+        (rx, g.moves.len())
+    };
+    // `Err`: the search thread is gone
+    let reply = reply.await.ok().flatten();
+
+    let mut g = game.lock().await;
+    // Decided meanwhile, e.g. by the opponent's resignation or a time forfeit
+    if g.finished || g.moves.len() != ply {
+        return;
+    }
+    let player = g.next_player;
+    match reply {
+        Some(mv) => g.play(&state, player, mv),
+        None => g.game_over(&state, player.opponent(), "Resignation"),
     }
-    Ok(())
 }
 
-async fn relay_game(
-    mut s1: TcpStream,
-    mut s2: TcpStream,
-    board: crate::core::Board,
+/// Puts a returning player back in their seat and resyncs the full position
+async fn rejoin(
+    state: Arc<ServerState>,
+    token: String,
+    reader: MessageReader,
+    mut writer: MessageWriter,
 ) -> anyhow::Result<()> {
-    let (r1, w1) = s1.split();
-    let (r2, w2) = s2.split();
-
-    let board_state = Arc::new(Mutex::new(board));
-    let next_player = Arc::new(Mutex::new(crate::core::PlayerId::Player1));
-
-    let w1 = Arc::new(Mutex::new(w1));
-    let w2 = Arc::new(Mutex::new(w2));
-
-    let b1 = Arc::clone(&board_state);
-    let p1 = Arc::clone(&next_player);
-    let out1_1 = Arc::clone(&w1);
-    let out2_1 = Arc::clone(&w2);
-
-    let f1 = async move {
-        let mut lines = BufReader::new(r1).lines();
-        while let Some(line) = lines.next_line().await? {
-            let msg: NetMessage = serde_json::from_str(&line)?;
-            if let NetMessage::MakeMove { mv } = msg {
-                let mut b = b1.lock().await;
-                let mut p = p1.lock().await;
-                if *p == crate::core::PlayerId::Player1 {
-                    let moves = crate::logic::legal_moves(&b, *p);
-                    if moves.contains(&mv) {
-                        *b = crate::logic::apply_move(&b, &mv, *p);
-                        *p = p.opponent();
-                        let update = NetMessage::Update {
-                            board: b.clone(),
-                            last_move: Some(mv),
-                            next_player: *p,
-                        };
-                        let json = serde_json::to_string(&update)? + "\n";
-                        out1_1.lock().await.write_all(json.as_bytes()).await?;
-                        out2_1.lock().await.write_all(json.as_bytes()).await?;
-                    } else {
-                        let err = NetMessage::Error {
-                            message: "Illegal move".to_string(),
-                        };
-                        let json = serde_json::to_string(&err)? + "\n";
-                        out1_1.lock().await.write_all(json.as_bytes()).await?;
+    let session = state.sessions.lock().await.get(&token).cloned();
+    let Some((game, player)) = session else {
+        log::warn("rejoin_refused", &[("reason", &"unknown session")]);
+        writer
+            .send(&NetMessage::Error {
+                message: "Unknown or expired session".to_string(),
+            })
+            .await?;
+        return Ok(());
+    };
+
+    let connection = {
+        let mut g = game.lock().await;
+        if g.finished {
+            drop(g);
+            writer
+                .send(&NetMessage::Error {
+                    message: "The game is over".to_string(),
+                })
+                .await?;
+            return Ok(());
+        }
+        let queue = seat_queue(writer);
+        let _ = queue.send(NetMessage::Welcome {
+            player_id: player,
+            board: g.board.clone(),
+            session_token: token,
+            time_control: state.options.time_control,
+        });
+        let _ = queue.send(g.update());
+
+        let seat = g.seat(player);
+        // A still-open old connection (e.g. half-closed TCP) is replaced
+        seat.writer = Some(queue);
+        seat.connection += 1;
+        let connection = seat.connection;
+        g.send_to(player.opponent(), &NetMessage::OpponentReconnected);
+        connection
+    };
+    log::info(
+        "rejoin",
+        &[("game", &game.lock().await.id), ("player", &side(player))],
+    );
+
+    relay_player(state, game, player, reader, connection).await;
+    Ok(())
+}
+
+/// Relays `player`'s moves until their connection drops, then holds the
+/// seat for the grace period
+async fn relay_player(
+    state: Arc<ServerState>,
+    game: SharedGame,
+    player: PlayerId,
+    mut reader: MessageReader,
+    connection: u64,
+) {
+    // Any read error counts as a disconnection
+    while let Ok(Some(frame)) = reader.recv_frame().await {
+        let mut g = game.lock().await;
+        let msg = match reader.format().decode(&frame) {
+            Ok(msg) => msg,
+            Err(e) => {
+                log::warn(
+                    "malformed_message",
+                    &[("game", &g.id), ("player", &side(player)), ("error", &e)],
+                );
+                let error = NetMessage::Error {
+                    message: format!("Malformed message: {}", e),
+                };
+                g.send_to(player, &error);
+                continue;
+            }
+        };
+        match msg {
+            NetMessage::MakeMove { mv } => {
+                if g.finished || g.next_player != player {
+                    continue;
+                }
+                let moves = crate::logic::legal_moves(&g.board, player);
+                if moves.contains(&mv) {
+                    g.play(&state, player, mv);
+                    if g.ai.is_some() {
+                        tokio::spawn(ai_turn(Arc::clone(&state), Arc::clone(&game)));
                     }
+                } else {
+                    log::warn(
+                        "illegal_move",
+                        &[("game", &g.id), ("player", &side(player)), ("move", &mv)],
+                    );
+                    let err = NetMessage::Error {
+                        message: "Illegal move".to_string(),
+                    };
+                    g.send_to(player, &err);
                 }
             }
+            NetMessage::Resign if !g.finished => {
+                g.game_over(&state, player.opponent(), "Resignation");
+            }
+            _ => {}
         }
-        Ok::<(), anyhow::Error>(())
+    }
+
+    let mut g = game.lock().await;
+    if g.seat(player).connection != connection {
+        return; // Superseded by a rejoin
+    }
+    g.seat(player).writer = None;
+    if g.finished {
+        if g.seats.iter().all(|seat| seat.writer.is_none()) {
+            state.forget(g.tokens()).await;
+        }
+        return;
+    }
+
+    let grace = state.options.reconnect_grace;
+    log::warn(
+        "disconnect",
+        &[
+            ("game", &g.id),
+            ("player", &side(player)),
+            ("grace_secs", &grace.as_secs()),
+        ],
+    );
+    let notice = NetMessage::OpponentDisconnected {
+        grace_secs: grace.as_secs(),
     };
+    g.send_to(player.opponent(), &notice);
+    drop(g);
 
-    let b2 = Arc::clone(&board_state);
-    let p2 = Arc::clone(&next_player);
-    let out1_2 = Arc::clone(&w1);
-    let out2_2 = Arc::clone(&w2);
-
-    let f2 = async move {
-        let mut lines = BufReader::new(r2).lines();
-        while let Some(line) = lines.next_line().await? {
-            let msg: NetMessage = serde_json::from_str(&line)?;
-            if let NetMessage::MakeMove { mv } = msg {
-                let mut b = b2.lock().await;
-                let mut p = p2.lock().await;
-                if *p == crate::core::PlayerId::Player2 {
-                    let moves = crate::logic::legal_moves(&b, *p);
-                    if moves.contains(&mv) {
-                        *b = crate::logic::apply_move(&b, &mv, *p);
-                        *p = p.opponent();
-                        let update = NetMessage::Update {
-                            board: b.clone(),
-                            last_move: Some(mv),
-                            next_player: *p,
-                        };
-                        let json = serde_json::to_string(&update)? + "\n";
-                        out1_2.lock().await.write_all(json.as_bytes()).await?;
-                        out2_2.lock().await.write_all(json.as_bytes()).await?;
-                    } else {
-                        let err = NetMessage::Error {
-                            message: "Illegal move".to_string(),
-                        };
-                        let json = serde_json::to_string(&err)? + "\n";
-                        out2_2.lock().await.write_all(json.as_bytes()).await?;
-                    }
+    tokio::spawn(async move {
+        tokio::time::sleep(grace).await;
+        let mut g = game.lock().await;
+        if g.finished || g.seat(player).connection != connection {
+            return; // Over already, or the player came back
+        }
+        g.game_over(&state, player.opponent(), "Opponent disconnected");
+        state.forget(g.tokens()).await;
+    });
+}
+
+/// The next message for an observer, `None` once the game is over
+async fn next_broadcast(feed: &mut broadcast::Receiver<NetMessage>) -> Option<NetMessage> {
+    loop {
+        match feed.recv().await {
+            Ok(msg) => return Some(msg),
+            // Every `Update` carries the whole board, so skipping some is harmless
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Streams a game to an observer in the lobby until the game ends or the
+/// observer disconnects
+async fn observe(
+    mut reader: MessageReader,
+    mut writer: MessageWriter,
+    intro: Vec<NetMessage>,
+    mut feed: broadcast::Receiver<NetMessage>,
+) -> anyhow::Result<()> {
+    for msg in &intro {
+        writer.send(msg).await?;
+    }
+    loop {
+        tokio::select! {
+            msg = next_broadcast(&mut feed) => {
+                let Some(msg) = msg else { return Ok(()) };
+                writer.send(&msg).await?;
+                if matches!(msg, NetMessage::GameOver { .. }) {
+                    return Ok(());
+                }
+            }
+            // Anything the observer sends is ignored
+            frame = reader.recv_frame() => {
+                if !matches!(frame, Ok(Some(_))) {
+                    return Ok(());
                 }
             }
         }
-        Ok::<(), anyhow::Error>(())
+    }
+}
+
+/// Plain HTTP for viewers that cannot speak the game protocol, e.g. a
+/// browser's `EventSource`: `GET /games` lists the games being played as JSON
+/// and `GET /games/<id>` streams one as server-sent events, one `NetMessage`
+/// JSON per `data:` line, as an observer in the lobby would receive them
+async fn serve_broadcast(listener: TcpListener, state: Arc<ServerState>) {
+    loop {
+        let Ok((socket, peer)) = listener.accept().await else {
+            continue;
+        };
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            if let Err(e) = handle_broadcast_request(socket, peer, state).await {
+                log::warn("broadcast_error", &[("peer", &peer), ("error", &e)]);
+            }
+        });
+    }
+}
+
+/// Longest request line and headers a viewer may send
+const MAX_REQUEST_HEAD: u64 = 8 * 1024;
+
+/// How long a viewer may take to send them
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The request line of an HTTP request; the headers do not matter
+async fn read_request_line(read: impl AsyncRead + Unpin) -> anyhow::Result<String> {
+    let mut lines = BufReader::new(read.take(MAX_REQUEST_HEAD)).lines();
+    let request = lines.next_line().await?.unwrap_or_default();
+    while let Some(line) = lines.next_line().await? {
+        if line.is_empty() {
+            return Ok(request);
+        }
+    }
+    anyhow::bail!("Request head cut short or over {} bytes", MAX_REQUEST_HEAD)
+}
+
+async fn handle_broadcast_request(
+    socket: TcpStream,
+    peer: SocketAddr,
+    state: Arc<ServerState>,
+) -> anyhow::Result<()> {
+    let (read, mut write) = socket.into_split();
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request_line(read))
+        .await
+        .map_err(|_| anyhow::anyhow!("No request within {:?}", REQUEST_TIMEOUT))??;
+
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next(), parts.next().unwrap_or("/"));
+    let path = target
+        .split('?')
+        .next()
+        .unwrap_or("/")
+        .trim_end_matches('/');
+    let game_id = path
+        .strip_prefix("/games/")
+        .and_then(|id| id.parse::<u64>().ok());
+    let respond = |status: &str, content_type: &str, body: &str| {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )
+    };
+
+    if method != Some("GET") {
+        let response = respond("405 Method Not Allowed", "text/plain", "GET only\n");
+        return Ok(write.write_all(response.as_bytes()).await?);
+    }
+    if path == "/games" {
+        let games = serde_json::to_string(&state.live_games().await)?;
+        let response = respond("200 OK", "application/json", &games);
+        return Ok(write.write_all(response.as_bytes()).await?);
+    }
+    let watched = match game_id {
+        Some(id) => state.watch(id).await.map(|watched| (id, watched)),
+        None => None,
     };
+    let Some((id, (intro, mut feed))) = watched else {
+        let response = respond("404 Not Found", "text/plain", "No such game\n");
+        return Ok(write.write_all(response.as_bytes()).await?);
+    };
+
+    log::info("watch", &[("peer", &peer), ("game", &id), ("via", &"sse")]);
+    write
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+              Cache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+        )
+        .await?;
+    let mut pending = intro.into_iter();
+    loop {
+        let msg = match pending.next() {
+            Some(msg) => msg,
+            None => match next_broadcast(&mut feed).await {
+                Some(msg) => msg,
+                None => return Ok(()),
+            },
+        };
+        let event = format!("data: {}\n\n", serde_json::to_string(&msg)?);
+        write.write_all(event.as_bytes()).await?;
+        if matches!(msg, NetMessage::GameOver { .. }) {
+            return Ok(());
+        }
+    }
+}
 
-    tokio::select! {
-        res = f1 => res,
-        res = f2 => res,
+/// Declares a time forfeit when the side to move's clock runs out, also
+/// while they are disconnected
+async fn watch_clock(state: Arc<ServerState>, game: SharedGame) {
+    loop {
+        let left = {
+            let g = game.lock().await;
+            match &g.clock {
+                Some(clock) if !g.finished => clock.left(g.next_player, g.next_player),
+                _ => return,
+            }
+        };
+        // Wakes up at the current flag time; a move meanwhile pushes it back
+        tokio::time::sleep(left).await;
+
+        let mut g = game.lock().await;
+        let to_move = g.next_player;
+        let flagged = match &g.clock {
+            Some(clock) if !g.finished => clock.left(to_move, to_move).is_zero(),
+            _ => return,
+        };
+        if flagged {
+            g.game_over(&state, to_move.opponent(), TIME_FORFEIT);
+            return;
+        }
     }
 }
 
-async fn send_msg(socket: &mut TcpStream, msg: &NetMessage) -> anyhow::Result<()> {
-    let json = serde_json::to_string(msg)? + "\n";
-    socket.write_all(json.as_bytes()).await?;
-    Ok(())
+impl ServerState {
+    /// Named setups, and the server's own setup by its name
+    fn setup_named(&self, name: &str) -> Option<GameSetup> {
+        match BoardSetupType::from_name(name) {
+            Some(setup) => Some(GameSetup::Named(setup)),
+            None if name == self.options.board.to_string() => Some(self.options.board.clone()),
+            None => None,
+        }
+    }
+
+    /// Lists a new game for observers, dropping the entries of games gone since
+    async fn register(&self, id: u64, game: &SharedGame) {
+        let mut games = self.games.lock().await;
+        games.retain(|_, game| game.strong_count() > 0);
+        games.insert(id, Arc::downgrade(game));
+    }
+
+    async fn live_games(&self) -> Vec<GameInfo> {
+        let games: Vec<SharedGame> = self
+            .games
+            .lock()
+            .await
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let mut infos = Vec::new();
+        for game in games {
+            let g = game.lock().await;
+            if !g.finished {
+                infos.push(g.info());
+            }
+        }
+        infos
+    }
+
+    /// What a new observer of game `id` is sent first (`Watching` and the
+    /// position), and the feed of what follows
+    async fn watch(&self, id: u64) -> Option<(Vec<NetMessage>, broadcast::Receiver<NetMessage>)> {
+        let game = self.games.lock().await.get(&id)?.upgrade()?;
+        let g = game.lock().await;
+        if g.finished {
+            return None;
+        }
+        let feed = g.observers.as_ref()?.subscribe();
+        let intro = vec![NetMessage::Watching { game: g.info() }, g.update()];
+        Some((intro, feed))
+    }
+
+    async fn status(&self) -> ServerStatus {
+        ServerStatus {
+            uptime_secs: self.started.elapsed().as_secs(),
+            active_games: self.active_games.load(Ordering::Relaxed),
+            open_rooms: self.lobby.lock().await.rooms.len(),
+            finished_games: self.finished_games.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Saves a game that has just ended, if the server keeps a database.
+    /// SQLite blocks, so the write runs off the runtime and without the game.
+    fn record(&self, game: &Game, winner: Option<PlayerId>, reason: &str) {
+        let Some(store) = self.options.store.clone() else {
+            return;
+        };
+        let mut kifu = game.kifu();
+        kifu.result = Some(crate::game::KifuResult {
+            winner,
+            reason: reason.to_string(),
+        });
+        let (game_id, reason) = (game.id, reason.to_string());
+        tokio::task::spawn_blocking(move || match store.record_game(&kifu, winner, &reason) {
+            Ok(id) => log::info("saved", &[("game", &game_id), ("db_id", &id)]),
+            Err(e) => log::error("save_failed", &[("game", &game_id), ("error", &e)]),
+        });
+    }
+
+    /// Drops the session tokens of a game nobody can rejoin any more
+    async fn forget(&self, tokens: Vec<String>) {
+        let mut sessions = self.sessions.lock().await;
+        for token in tokens {
+            sessions.remove(&token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::protocol::WireFormat;
+    use crate::network::transport::{connect, negotiate};
+
+    async fn join(addr: &str, name: &str) -> (MessageReader, MessageWriter) {
+        let (reader, mut writer) = connect(addr).await.unwrap();
+        let name = name.to_string();
+        writer.send(&NetMessage::Join { name }).await.unwrap();
+        (reader, writer)
+    }
+
+    /// The games in `store`, once the background write of one has landed
+    async fn recorded(store: &GameStore) -> Vec<crate::network::store::GameSummary> {
+        for _ in 0..100 {
+            let games = store.list_games(10).unwrap();
+            if !games.is_empty() {
+                return games;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("no game was recorded");
+    }
+
+    async fn welcome(reader: &mut MessageReader) -> String {
+        match reader.recv().await.unwrap() {
+            Some(NetMessage::Welcome { session_token, .. }) => session_token,
+            other => panic!("expected Welcome, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejoin_after_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let store = Arc::new(GameStore::open_in_memory().unwrap());
+        let options = ServerOptions {
+            reconnect_grace: Duration::from_millis(300),
+            store: Some(Arc::clone(&store)),
+            ..Default::default()
+        };
+        tokio::spawn(serve(listener, None, options));
+
+        let (mut r1, mut w1) = join(&addr, "alice").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (mut r2, w2) = join(&addr, "bob").await;
+        welcome(&mut r1).await;
+        r1.recv().await.unwrap(); // MatchFound
+        let token2 = welcome(&mut r2).await;
+        assert_eq!(token2.len(), 32);
+
+        // Player2 drops; Player1 plays on while the seat is held
+        drop((r2, w2));
+        assert!(matches!(
+            r1.recv().await.unwrap(),
+            Some(NetMessage::OpponentDisconnected { .. })
+        ));
+        let board = crate::core::setup::BoardSetupType::StandardMixed.create_board();
+        let mv = crate::logic::legal_moves(&board, PlayerId::Player1)[0];
+        w1.send(&NetMessage::MakeMove { mv }).await.unwrap();
+        assert!(matches!(
+            r1.recv().await.unwrap(),
+            Some(NetMessage::Update { .. })
+        ));
+
+        // An unknown token is refused
+        let (mut bad, mut bad_w) = connect(&addr).await.unwrap();
+        let token = "nope".to_string();
+        bad_w.send(&NetMessage::Rejoin { token }).await.unwrap();
+        assert!(matches!(
+            bad.recv().await.unwrap(),
+            Some(NetMessage::Error { .. })
+        ));
+
+        // Rejoining resyncs the position, including the move made meanwhile,
+        // this time over MessagePack while Player1 stays on JSON
+        let (mut r2, mut w2) = connect(&addr).await.unwrap();
+        let format = negotiate(&mut r2, &mut w2, &[WireFormat::MessagePack])
+            .await
+            .unwrap();
+        assert_eq!(format, WireFormat::MessagePack);
+        let token = token2.clone();
+        w2.send(&NetMessage::Rejoin { token }).await.unwrap();
+        assert_eq!(welcome(&mut r2).await, token2);
+        match r2.recv().await.unwrap() {
+            Some(NetMessage::Update {
+                last_move,
+                next_player,
+                ..
+            }) => {
+                assert_eq!(last_move, Some(mv));
+                assert_eq!(next_player, PlayerId::Player2);
+            }
+            other => panic!("expected Update, got {:?}", other),
+        }
+        assert!(matches!(
+            r1.recv().await.unwrap(),
+            Some(NetMessage::OpponentReconnected)
+        ));
+
+        // Gone for longer than the grace period: the opponent wins
+        drop((r2, w2));
+        r1.recv().await.unwrap(); // OpponentDisconnected
+        match r1.recv().await.unwrap() {
+            Some(NetMessage::GameOver { winner, .. }) => assert_eq!(winner, PlayerId::Player1),
+            other => panic!("expected GameOver, got {:?}", other),
+        }
+
+        // The forfeited game is kept with its move and counted in the records
+        let games = recorded(&store).await;
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].winner, Some(PlayerId::Player1));
+        assert_eq!(games[0].moves, 1);
+        let kifu = store.kifu_json(games[0].id).unwrap().unwrap();
+        let kifu: crate::game::KifuData = serde_json::from_str(&kifu).unwrap();
+        assert_eq!(kifu.moves, vec![mv]);
+        assert_eq!(kifu.player2_name, "bob");
+        let players = store.players().unwrap();
+        assert_eq!((players[0].name.as_str(), players[0].wins), ("alice", 1));
+        assert_eq!((players[1].name.as_str(), players[1].losses), ("bob", 1));
+    }
+
+    #[tokio::test]
+    async fn test_lobby_rooms() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(serve(listener, None, ServerOptions::default()));
+
+        let (mut host, mut host_w) = connect(&addr).await.unwrap();
+        let create = NetMessage::CreateRoom {
+            name: "alice".to_string(),
+            board_setup: "NoSuchSetup".to_string(),
+        };
+        host_w.send(&create).await.unwrap();
+        assert!(matches!(
+            host.recv().await.unwrap(),
+            Some(NetMessage::Error { .. })
+        ));
+        let create = NetMessage::CreateRoom {
+            name: "alice".to_string(),
+            board_setup: "ShogiOnly".to_string(),
+        };
+        host_w.send(&create).await.unwrap();
+        let room_id = match host.recv().await.unwrap() {
+            Some(NetMessage::RoomCreated { room_id }) => room_id,
+            other => panic!("expected RoomCreated, got {:?}", other),
+        };
+
+        // A quick match does not take a room with another setup
+        let (quick, quick_w) = join(&addr, "carol").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let (mut guest, mut guest_w) = connect(&addr).await.unwrap();
+        guest_w.send(&NetMessage::ListRooms).await.unwrap();
+        let rooms = match guest.recv().await.unwrap() {
+            Some(NetMessage::RoomList { rooms }) => rooms,
+            other => panic!("expected RoomList, got {:?}", other),
+        };
+        assert_eq!(rooms.len(), 2);
+        assert_eq!(
+            rooms[0],
+            RoomInfo {
+                id: room_id,
+                host: "alice".to_string(),
+                board_setup: "ShogiOnly".to_string(),
+            }
+        );
+
+        // A missing room leaves the guest in the lobby
+        let name = "bob".to_string();
+        let missing = NetMessage::JoinRoom { name, room_id: 99 };
+        guest_w.send(&missing).await.unwrap();
+        assert!(matches!(
+            guest.recv().await.unwrap(),
+            Some(NetMessage::Error { .. })
+        ));
+        let name = "bob".to_string();
+        guest_w
+            .send(&NetMessage::JoinRoom { name, room_id })
+            .await
+            .unwrap();
+        let expected = BoardSetupType::ShogiOnly.create_board();
+        for (reader, player) in [
+            (&mut host, PlayerId::Player1),
+            (&mut guest, PlayerId::Player2),
+        ] {
+            match reader.recv().await.unwrap() {
+                Some(NetMessage::Welcome {
+                    player_id, board, ..
+                }) => {
+                    assert_eq!(player_id, player);
+                    let hash = |board: &Board| {
+                        crate::logic::zobrist::ZobristHasher::compute_hash(board, PlayerId::Player1)
+                    };
+                    assert_eq!(hash(&board), hash(&expected));
+                }
+                other => panic!("expected Welcome, got {:?}", other),
+            }
+        }
+
+        // Only carol's quick-match room is left open
+        let (mut lister, mut lister_w) = connect(&addr).await.unwrap();
+        lister_w.send(&NetMessage::ListRooms).await.unwrap();
+        match lister.recv().await.unwrap() {
+            Some(NetMessage::RoomList { rooms }) => assert_eq!(rooms.len(), 1),
+            other => panic!("expected RoomList, got {:?}", other),
+        }
+        lister_w.send(&NetMessage::Status).await.unwrap();
+        match lister.recv().await.unwrap() {
+            Some(NetMessage::StatusReport { status }) => {
+                assert_eq!((status.active_games, status.open_rooms), (1, 1));
+                assert_eq!(status.finished_games, 0);
+            }
+            other => panic!("expected StatusReport, got {:?}", other),
+        }
+
+        // ... until carol gives up waiting
+        drop((quick, quick_w));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        lister_w.send(&NetMessage::ListRooms).await.unwrap();
+        match lister.recv().await.unwrap() {
+            Some(NetMessage::RoomList { rooms }) => assert!(rooms.is_empty()),
+            other => panic!("expected RoomList, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http_addr = http.local_addr().unwrap();
+        tokio::spawn(serve(listener, Some(http), ServerOptions::default()));
+
+        let (mut r1, mut w1) = join(&addr, "alice").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (mut r2, _w2) = join(&addr, "bob").await;
+        welcome(&mut r1).await;
+        welcome(&mut r2).await;
+
+        // An observer over the game protocol
+        let (mut watcher, mut watcher_w) = connect(&addr).await.unwrap();
+        watcher_w.send(&NetMessage::ListGames).await.unwrap();
+        let game = match watcher.recv().await.unwrap() {
+            Some(NetMessage::GameList { mut games }) => {
+                assert_eq!(games.len(), 1);
+                games.remove(0)
+            }
+            other => panic!("expected GameList, got {:?}", other),
+        };
+        assert_eq!(
+            (game.player1.as_str(), game.player2.as_str()),
+            ("alice", "bob")
+        );
+        watcher_w
+            .send(&NetMessage::Watch { game_id: 99 })
+            .await
+            .unwrap();
+        assert!(matches!(
+            watcher.recv().await.unwrap(),
+            Some(NetMessage::Error { .. })
+        ));
+        let game_id = game.id;
+        watcher_w
+            .send(&NetMessage::Watch { game_id })
+            .await
+            .unwrap();
+        assert!(matches!(
+            watcher.recv().await.unwrap(),
+            Some(NetMessage::Watching { .. })
+        ));
+        assert!(matches!(
+            watcher.recv().await.unwrap(),
+            Some(NetMessage::Update {
+                last_move: None,
+                ..
+            })
+        ));
+
+        // And one over HTTP
+        let mut http = TcpStream::connect(http_addr).await.unwrap();
+        http.write_all(format!("GET /games/{} HTTP/1.1\r\n\r\n", game_id).as_bytes())
+            .await
+            .unwrap();
+        let mut sse = BufReader::new(http).lines();
+        assert_eq!(sse.next_line().await.unwrap().unwrap(), "HTTP/1.1 200 OK");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let board = BoardSetupType::StandardMixed.create_board();
+        let mv = crate::logic::legal_moves(&board, PlayerId::Player1)[0];
+        w1.send(&NetMessage::MakeMove { mv }).await.unwrap();
+        match watcher.recv().await.unwrap() {
+            Some(NetMessage::Update { last_move, .. }) => assert_eq!(last_move, Some(mv)),
+            other => panic!("expected Update, got {:?}", other),
+        }
+        w1.send(&NetMessage::Resign).await.unwrap();
+        assert!(matches!(
+            watcher.recv().await.unwrap(),
+            Some(NetMessage::GameOver { .. })
+        ));
+        // The observer is let go after the result
+        assert!(watcher.recv().await.unwrap().is_none());
+
+        let mut events = Vec::new();
+        while let Some(line) = sse.next_line().await.unwrap() {
+            if let Some(data) = line.strip_prefix("data: ") {
+                events.push(serde_json::from_str::<NetMessage>(data).unwrap());
+            }
+        }
+        assert!(matches!(events[0], NetMessage::Watching { .. }));
+        assert!(matches!(
+            events[2],
+            NetMessage::Update {
+                last_move: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(events[3], NetMessage::GameOver { .. }));
+        assert_eq!(events.len(), 4);
+
+        // An endless request line is cut off without an answer
+        let mut http = TcpStream::connect(http_addr).await.unwrap();
+        let junk = vec![b'a'; MAX_REQUEST_HEAD as usize];
+        http.write_all(&junk).await.unwrap();
+        let mut answer = Vec::new();
+        http.read_to_end(&mut answer).await.unwrap();
+        assert!(answer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_play_ai() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let store = Arc::new(GameStore::open_in_memory().unwrap());
+        let options = ServerOptions {
+            store: Some(Arc::clone(&store)),
+            ai: Some(AiLimits {
+                depth: 1,
+                move_time: Duration::from_secs(1),
+                max_games: 1,
+            }),
+            ..Default::default()
+        };
+        tokio::spawn(serve(listener, None, options));
+
+        let (mut reader, mut writer) = connect(&addr).await.unwrap();
+        let play = NetMessage::PlayAi {
+            name: "alice".to_string(),
+            board_setup: Some("ShogiOnly".to_string()),
+        };
+        writer.send(&play).await.unwrap();
+        welcome(&mut reader).await;
+        match reader.recv().await.unwrap() {
+            Some(NetMessage::MatchFound { opponent_name }) => assert_eq!(opponent_name, AI_NAME),
+            other => panic!("expected MatchFound, got {:?}", other),
+        }
+
+        // Our move comes back, then the engine's reply
+        let board = BoardSetupType::ShogiOnly.create_board();
+        let mv = crate::logic::legal_moves(&board, PlayerId::Player1)[0];
+        writer.send(&NetMessage::MakeMove { mv }).await.unwrap();
+        let mut replies = Vec::new();
+        for _ in 0..2 {
+            match reader.recv().await.unwrap() {
+                Some(NetMessage::Update {
+                    last_move,
+                    next_player,
+                    ..
+                }) => replies.push((last_move, next_player)),
+                other => panic!("expected Update, got {:?}", other),
+            }
+        }
+        assert_eq!(replies[0], (Some(mv), PlayerId::Player2));
+        assert!(replies[1].0.is_some_and(|reply| reply != mv));
+        assert_eq!(replies[1].1, PlayerId::Player1);
+
+        // The only engine seat is taken
+        let (mut other_reader, mut other_writer) = connect(&addr).await.unwrap();
+        other_writer.send(&play).await.unwrap();
+        assert!(matches!(
+            other_reader.recv().await.unwrap(),
+            Some(NetMessage::Error { .. })
+        ));
+
+        writer.send(&NetMessage::Resign).await.unwrap();
+        match reader.recv().await.unwrap() {
+            Some(NetMessage::GameOver { winner, .. }) => assert_eq!(winner, PlayerId::Player2),
+            other => panic!("expected GameOver, got {:?}", other),
+        }
+        let games = recorded(&store).await;
+        assert_eq!(games[0].player2, AI_NAME);
+        assert_eq!(games[0].moves, 2);
+
+        // ... and free again once the game is over
+        other_writer.send(&play).await.unwrap();
+        welcome(&mut other_reader).await;
+
+        // A server without the engine refuses
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let options = ServerOptions {
+            ai: None,
+            ..Default::default()
+        };
+        tokio::spawn(serve(listener, None, options));
+        let (mut reader, mut writer) = connect(&addr).await.unwrap();
+        writer.send(&play).await.unwrap();
+        assert!(matches!(
+            reader.recv().await.unwrap(),
+            Some(NetMessage::Error { .. })
+        ));
+    }
+
+    #[test]
+    fn test_byoyomi_clock() {
+        let mut clock = GameClock::new("10/2".parse().unwrap());
+        let p1 = PlayerId::Player1;
+
+        // 11 s: the main time is gone but the byoyomi saves the move
+        clock.turn_started = Instant::now() - Duration::from_secs(11);
+        assert!(clock.punch(p1));
+        assert_eq!(clock.millis(PlayerId::Player2)[0], 0);
+        assert_eq!(clock.left(p1, PlayerId::Player2), Duration::from_secs(2));
+
+        // From then on every move has the byoyomi only
+        clock.turn_started = Instant::now() - Duration::from_secs(3);
+        assert!(!clock.punch(p1));
+    }
+
+    #[tokio::test]
+    async fn test_time_forfeit() {
+        let time_control: TimeControl = "1+0".parse().unwrap();
+        assert!("0+5".parse::<TimeControl>().is_err());
+        assert_eq!("300+5".parse::<TimeControl>().unwrap().increment_secs, 5);
+        assert_eq!("0/10".parse::<TimeControl>().unwrap().byoyomi_secs, 10);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let options = ServerOptions {
+            time_control: Some(time_control),
+            ..Default::default()
+        };
+        tokio::spawn(serve(listener, None, options));
+
+        let (mut r1, mut w1) = join(&addr, "alice").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (mut r2, _w2) = join(&addr, "bob").await;
+        match r1.recv().await.unwrap() {
+            Some(NetMessage::Welcome {
+                time_control: sent, ..
+            }) => {
+                assert_eq!(sent, Some(time_control));
+            }
+            other => panic!("expected Welcome, got {:?}", other),
+        }
+        r1.recv().await.unwrap(); // MatchFound
+
+        // Player1 moves at once; Player2's clock then runs out
+        let board = BoardSetupType::StandardMixed.create_board();
+        let mv = crate::logic::legal_moves(&board, PlayerId::Player1)[0];
+        w1.send(&NetMessage::MakeMove { mv }).await.unwrap();
+        match r1.recv().await.unwrap() {
+            Some(NetMessage::Update {
+                clock_ms: Some([p1, p2]),
+                ..
+            }) => {
+                assert!(p1 > 500 && p1 <= 1000, "{}", p1);
+                assert!(p2 > 900 && p2 <= 1000, "{}", p2);
+            }
+            other => panic!("expected Update with clocks, got {:?}", other),
+        }
+        for reader in [&mut r1, &mut r2] {
+            loop {
+                match reader.recv().await.unwrap() {
+                    Some(NetMessage::GameOver { winner, reason }) => {
+                        assert_eq!(winner, PlayerId::Player1);
+                        assert_eq!(reason, TIME_FORFEIT);
+                        break;
+                    }
+                    Some(_) => {}
+                    None => panic!("closed before GameOver"),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_setup_and_ruleset() {
+        let rows = [
+            "k . . . .",
+            ". . . . .",
+            ". . . . .",
+            ". . . . .",
+            ". . . . K",
+        ];
+        let options = ServerOptions {
+            board: GameSetup::Custom {
+                name: "mini".to_string(),
+                rows: rows.iter().map(|row| row.to_string()).collect(),
+            },
+            ruleset: Some(Ruleset {
+                max_plies: 40,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(serve(listener, None, options));
+
+        // The custom setup is open by name, and quick matches join it
+        let (mut host, mut host_w) = connect(&addr).await.unwrap();
+        let create = NetMessage::CreateRoom {
+            name: "alice".to_string(),
+            board_setup: "mini".to_string(),
+        };
+        host_w.send(&create).await.unwrap();
+        assert!(matches!(
+            host.recv().await.unwrap(),
+            Some(NetMessage::RoomCreated { .. })
+        ));
+        let (mut guest, _guest_w) = join(&addr, "bob").await;
+        match guest.recv().await.unwrap() {
+            Some(NetMessage::Welcome { board, .. }) => {
+                assert_eq!((board.width, board.height), (5, 5));
+                assert_eq!(board.rules.max_plies, 40);
+            }
+            other => panic!("expected Welcome, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rule_ending_decides_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let store = Arc::new(GameStore::open_in_memory().unwrap());
+        let options = ServerOptions {
+            store: Some(Arc::clone(&store)),
+            board: GameSetup::Named(BoardSetupType::ShogiOnly),
+            ruleset: Some(Ruleset {
+                max_plies: 2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        tokio::spawn(serve(listener, None, options));
+
+        let (mut r1, mut w1) = join(&addr, "alice").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (mut r2, mut w2) = join(&addr, "bob").await;
+        welcome(&mut r1).await;
+        r1.recv().await.unwrap(); // MatchFound
+        welcome(&mut r2).await;
+        r2.recv().await.unwrap(); // MatchFound
+        let (mut observer, mut observer_w) = connect(&addr).await.unwrap();
+        observer_w
+            .send(&NetMessage::Watch { game_id: 1 })
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            observer.recv().await.unwrap(); // Watching, Update
+        }
+
+        // The move limit ends the game as a draw after the second move
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        for (player, writer) in [(PlayerId::Player1, &mut w1), (PlayerId::Player2, &mut w2)] {
+            let mv = crate::logic::legal_moves(&board, player)[0];
+            board = crate::logic::apply_move(&board, &mv, player);
+            writer.send(&NetMessage::MakeMove { mv }).await.unwrap();
+            r1.recv().await.unwrap(); // Update
+        }
+
+        let games = recorded(&store).await;
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].winner, None);
+        assert_eq!(games[0].reason, "Move limit");
+        assert_eq!(games[0].moves, 2);
+
+        // The observer gets both moves, then the stream ends
+        for _ in 0..2 {
+            assert!(matches!(
+                observer.recv().await.unwrap(),
+                Some(NetMessage::Update { .. })
+            ));
+        }
+        let end = tokio::time::timeout(Duration::from_secs(5), observer.recv()).await;
+        assert!(matches!(end, Ok(Ok(None))));
+    }
 }
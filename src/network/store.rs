@@ -0,0 +1,303 @@
+//! SQLite storage of the server's finished games and player records
+//!
+//! Every game that ends on the server is kept as a kifu (the same JSON as the
+//! files under `kifu/`, so an exported game opens with `replay`) together with
+//! its result, and every player name keeps a win/loss/draw record.
+
+use crate::core::PlayerId;
+use crate::game::KifuData;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Database file of `server` and `games` unless `--db` says otherwise
+pub const DEFAULT_DB_PATH: &str = "server_games.sqlite3";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS games (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    finished_at TEXT NOT NULL,
+    player1     TEXT NOT NULL,
+    player2     TEXT NOT NULL,
+    board_setup TEXT NOT NULL,
+    winner      INTEGER,
+    reason      TEXT NOT NULL,
+    moves       INTEGER NOT NULL,
+    kifu        TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS players (
+    name   TEXT PRIMARY KEY,
+    wins   INTEGER NOT NULL DEFAULT 0,
+    losses INTEGER NOT NULL DEFAULT 0,
+    draws  INTEGER NOT NULL DEFAULT 0
+);
+";
+
+/// A stored game without its moves, for listings
+#[derive(Debug, Clone)]
+pub struct GameSummary {
+    pub id: i64,
+    /// Local time, `%Y-%m-%d %H:%M:%S`
+    pub finished_at: String,
+    pub player1: String,
+    pub player2: String,
+    pub board_setup: String,
+    /// `None` for a draw
+    pub winner: Option<PlayerId>,
+    pub reason: String,
+    pub moves: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerRecord {
+    pub name: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+pub struct GameStore {
+    conn: Mutex<Connection>,
+}
+
+fn winner_code(winner: Option<PlayerId>) -> Option<u8> {
+    winner.map(|player| match player {
+        PlayerId::Player1 => 1,
+        PlayerId::Player2 => 2,
+    })
+}
+
+fn winner_from_code(code: Option<u8>) -> Option<PlayerId> {
+    match code {
+        Some(1) => Some(PlayerId::Player1),
+        Some(2) => Some(PlayerId::Player2),
+        _ => None,
+    }
+}
+
+impl GameStore {
+    /// Opens (creating if needed) the database at `path`
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> anyhow::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(GameStore {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Saves a finished game and counts it in both players' records.
+    /// Names are chosen by the clients, so a game between two players of
+    /// the same name (e.g. both left at the default) is kept but not counted.
+    /// Returns the ID of the game.
+    pub fn record_game(
+        &self,
+        kifu: &KifuData,
+        winner: Option<PlayerId>,
+        reason: &str,
+    ) -> anyhow::Result<i64> {
+        let json = serde_json::to_string(kifu)?;
+        let finished_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO games (finished_at, player1, player2, board_setup, winner, reason, moves, kifu)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                finished_at,
+                kifu.player1_name,
+                kifu.player2_name,
+                kifu.board_setup,
+                winner_code(winner),
+                reason,
+                kifu.moves.len() as i64,
+                json,
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+
+        let seats = [
+            (&kifu.player1_name, PlayerId::Player1),
+            (&kifu.player2_name, PlayerId::Player2),
+        ];
+        let counted = if kifu.player1_name == kifu.player2_name {
+            0
+        } else {
+            seats.len()
+        };
+        for (name, player) in seats.into_iter().take(counted) {
+            let column = match winner {
+                None => "draws",
+                Some(w) if w == player => "wins",
+                Some(_) => "losses",
+            };
+            tx.execute(
+                "INSERT INTO players (name) VALUES (?1) ON CONFLICT(name) DO NOTHING",
+                [name],
+            )?;
+            tx.execute(
+                &format!("UPDATE players SET {0} = {0} + 1 WHERE name = ?1", column),
+                [name],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// The `limit` most recent games, newest first
+    pub fn list_games(&self, limit: usize) -> anyhow::Result<Vec<GameSummary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, finished_at, player1, player2, board_setup, winner, reason, moves
+             FROM games ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok(GameSummary {
+                id: row.get(0)?,
+                finished_at: row.get(1)?,
+                player1: row.get(2)?,
+                player2: row.get(3)?,
+                board_setup: row.get(4)?,
+                winner: winner_from_code(row.get(5)?),
+                reason: row.get(6)?,
+                moves: row.get::<_, i64>(7)? as usize,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// The kifu JSON of game `id`, if there is one
+    pub fn kifu_json(&self, id: i64) -> anyhow::Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let json = conn
+            .query_row("SELECT kifu FROM games WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(json)
+    }
+
+    /// Every player's record, most wins first
+    pub fn players(&self) -> anyhow::Result<Vec<PlayerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, wins, losses, draws FROM players ORDER BY wins DESC, losses, name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(PlayerRecord {
+                name: row.get(0)?,
+                wins: row.get(1)?,
+                losses: row.get(2)?,
+                draws: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{apply_move, legal_moves};
+    use crate::selfplay::BoardSetupType;
+
+    fn sample_kifu(player1: &str, player2: &str) -> KifuData {
+        let mut board = BoardSetupType::ShogiOnly.create_board();
+        let mut player = PlayerId::Player1;
+        let mut moves = Vec::new();
+        for _ in 0..4 {
+            let mv = legal_moves(&board, player)[0];
+            board = apply_move(&board, &mv, player);
+            player = player.opponent();
+            moves.push(mv);
+        }
+        KifuData {
+            board_setup: "ShogiOnly".to_string(),
+            chess960_seed: None,
+            handicap: None,
+            player1_name: player1.to_string(),
+            player2_name: player2.to_string(),
+            moves,
+            thinking_data: None,
+            evaluator: String::new(),
+            model_path: None,
+            model_version: None,
+            model_lineage: None,
+            eval_trajectory: None,
+            snapshots: Vec::new(),
+            result: None,
+            variations: Vec::new(),
+        }
+    }
+
+    fn record(name: &str, wins: u32, losses: u32, draws: u32) -> PlayerRecord {
+        PlayerRecord {
+            name: name.to_string(),
+            wins,
+            losses,
+            draws,
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let store = GameStore::open_in_memory().unwrap();
+        let kifu = sample_kifu("alice", "bob");
+        store
+            .record_game(&kifu, Some(PlayerId::Player1), "Checkmate")
+            .unwrap();
+        store
+            .record_game(&kifu, Some(PlayerId::Player2), "Resignation")
+            .unwrap();
+        store
+            .record_game(&kifu, Some(PlayerId::Player1), "Checkmate")
+            .unwrap();
+        store.record_game(&kifu, None, "Repetition").unwrap();
+        assert_eq!(
+            store.players().unwrap(),
+            vec![record("alice", 2, 1, 1), record("bob", 1, 2, 1)]
+        );
+
+        let games = store.list_games(3).unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].reason, "Repetition");
+        assert_eq!(games[0].winner, None);
+        assert_eq!(games[2].winner, Some(PlayerId::Player2));
+        assert_eq!(games[0].moves, 4);
+    }
+
+    #[test]
+    fn test_same_names_are_not_counted() {
+        let store = GameStore::open_in_memory().unwrap();
+        let kifu = sample_kifu("Player", "Player");
+        store
+            .record_game(&kifu, Some(PlayerId::Player1), "Checkmate")
+            .unwrap();
+        assert!(store.players().unwrap().is_empty());
+        assert_eq!(store.list_games(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_kifu_round_trip() {
+        let store = GameStore::open_in_memory().unwrap();
+        let kifu = sample_kifu("alice", "bob");
+        let id = store
+            .record_game(&kifu, Some(PlayerId::Player2), "Checkmate")
+            .unwrap();
+
+        let json = store.kifu_json(id).unwrap().unwrap();
+        let exported: KifuData = serde_json::from_str(&json).unwrap();
+        assert_eq!(exported.board_setup, kifu.board_setup);
+        assert_eq!(exported.player1_name, kifu.player1_name);
+        assert_eq!(exported.player2_name, kifu.player2_name);
+        assert_eq!(exported.moves, kifu.moves);
+        assert!(store.kifu_json(id + 1).unwrap().is_none());
+    }
+}
@@ -0,0 +1,718 @@
+//! Connections carrying `NetMessage`s over raw TCP or WebSocket, optionally TLS
+//!
+//! On raw TCP every message is one JSON line; over WebSocket it is one text
+//! frame with the same JSON, so browser clients and `ngrok http` tunnels
+//! (which only forward HTTP) speak the same protocol. A client may switch its
+//! connection to MessagePack with `Hello` (see [`negotiate`]). The server tells the two
+//! apart by the first bytes a client sends, so one port serves both.
+//!
+//! A server started with a certificate speaks TLS on that port; clients then
+//! connect with `tls://host:port` (raw) or `wss://` (WebSocket). Besides the
+//! usual web roots, clients trust the certificates in the PEM file named by
+//! `AHO_TLS_CA`, e.g. a self-signed server certificate.
+
+use crate::network::protocol::{NetMessage, WireFormat};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use tokio_tungstenite::tungstenite::error::ProtocolError;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::Connector;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the server waits for a client's TLS handshake and then for its
+/// first bytes (see [`accept`])
+const SNIFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Environment variable naming a PEM file of extra certificates clients trust
+pub const TLS_CA_ENV: &str = "AHO_TLS_CA";
+
+type ByteReader = Box<dyn AsyncRead + Send + Unpin>;
+type ByteWriter = Box<dyn AsyncWrite + Send + Unpin>;
+type WsSink = Pin<Box<dyn Sink<Message, Error = WsError> + Send>>;
+type WsStream = Pin<Box<dyn Stream<Item = Result<Message, WsError>> + Send>>;
+
+/// Largest JSON line or length-prefixed MessagePack frame accepted on raw TCP
+const MAX_FRAME_LEN: usize = 1 << 20;
+
+enum Incoming {
+    /// Raw TCP, plain or TLS
+    Tcp(BufReader<ByteReader>),
+    WebSocket(WsStream),
+}
+
+enum Outgoing {
+    Tcp(ByteWriter),
+    WebSocket(WsSink),
+}
+
+/// Receiving half of a connection
+pub struct MessageReader {
+    incoming: Incoming,
+    format: WireFormat,
+}
+
+/// Sending half of a connection
+pub struct MessageWriter {
+    outgoing: Outgoing,
+    format: WireFormat,
+}
+
+impl MessageReader {
+    pub fn format(&self) -> WireFormat {
+        self.format
+    }
+
+    /// Reads every following message in `format`
+    pub fn set_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    /// The next message undecoded, or `None` once the peer has closed the connection
+    pub async fn recv_frame(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        match &mut self.incoming {
+            Incoming::Tcp(reader) => match self.format {
+                WireFormat::Json => {
+                    // One byte past the limit tells an overlong line from one that fits
+                    let mut line = Vec::new();
+                    let mut limited = (&mut *reader).take(MAX_FRAME_LEN as u64 + 1);
+                    if limited.read_until(b'\n', &mut line).await? == 0 {
+                        return Ok(None);
+                    }
+                    anyhow::ensure!(
+                        line.ends_with(b"\n") || line.len() <= MAX_FRAME_LEN,
+                        "line longer than {} bytes",
+                        MAX_FRAME_LEN
+                    );
+                    while line.last().is_some_and(|&b| b == b'\n' || b == b'\r') {
+                        line.pop();
+                    }
+                    Ok(Some(line))
+                }
+                WireFormat::MessagePack => {
+                    let len = match reader.read_u32().await {
+                        Ok(len) => len as usize,
+                        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                        Err(e) => return Err(e.into()),
+                    };
+                    anyhow::ensure!(len <= MAX_FRAME_LEN, "frame of {} bytes is too large", len);
+                    let mut frame = vec![0; len];
+                    reader.read_exact(&mut frame).await?;
+                    Ok(Some(frame))
+                }
+            },
+            Incoming::WebSocket(stream) => loop {
+                match stream.next().await.transpose() {
+                    // A peer that just drops the TCP connection counts as closed, as on raw TCP
+                    Err(WsError::ConnectionClosed)
+                    | Err(WsError::Protocol(ProtocolError::ResetWithoutClosingHandshake)) => {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e.into()),
+                    Ok(None | Some(Message::Close(_))) => return Ok(None),
+                    Ok(Some(Message::Text(text))) => return Ok(Some(text.into_bytes())),
+                    Ok(Some(Message::Binary(bytes))) => return Ok(Some(bytes)),
+                    // Pings are answered by tungstenite
+                    Ok(Some(_)) => {}
+                }
+            },
+        }
+    }
+
+    /// The next message as text, or `None` once the peer has closed the connection
+    pub async fn recv_text(&mut self) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .recv_frame()
+            .await?
+            .map(|frame| String::from_utf8_lossy(&frame).into_owned()))
+    }
+
+    /// The next message, or `None` once the peer has closed the connection
+    pub async fn recv(&mut self) -> anyhow::Result<Option<NetMessage>> {
+        match self.recv_frame().await? {
+            Some(frame) => Ok(Some(self.format.decode(&frame)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl MessageWriter {
+    pub fn format(&self) -> WireFormat {
+        self.format
+    }
+
+    /// Writes every following message in `format`
+    pub fn set_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    /// Sends `frame` verbatim as one message in the current format's framing
+    async fn send_frame(&mut self, mut frame: Vec<u8>) -> anyhow::Result<()> {
+        match (&mut self.outgoing, self.format) {
+            (Outgoing::Tcp(writer), WireFormat::Json) => {
+                frame.push(b'\n');
+                writer.write_all(&frame).await?
+            }
+            (Outgoing::Tcp(writer), WireFormat::MessagePack) => {
+                let mut prefixed = (frame.len() as u32).to_be_bytes().to_vec();
+                prefixed.append(&mut frame);
+                writer.write_all(&prefixed).await?
+            }
+            (Outgoing::WebSocket(sink), WireFormat::Json) => {
+                sink.send(Message::Text(String::from_utf8(frame)?)).await?
+            }
+            (Outgoing::WebSocket(sink), WireFormat::MessagePack) => {
+                sink.send(Message::Binary(frame)).await?
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends `text` verbatim as one message
+    pub async fn send_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.send_frame(text.as_bytes().to_vec()).await
+    }
+
+    pub async fn send(&mut self, msg: &NetMessage) -> anyhow::Result<()> {
+        let frame = self.format.encode(msg)?;
+        self.send_frame(frame).await
+    }
+}
+
+/// Client side of the handshake: offers `formats` (preferred first) and
+/// switches both halves to the one the server picks. Servers that predate
+/// `Hello` refuse it and close the connection, so this fails against them.
+pub async fn negotiate(
+    reader: &mut MessageReader,
+    writer: &mut MessageWriter,
+    formats: &[WireFormat],
+) -> anyhow::Result<WireFormat> {
+    writer
+        .send(&NetMessage::Hello {
+            formats: formats.to_vec(),
+        })
+        .await?;
+    let format = match reader.recv().await? {
+        Some(NetMessage::Encoding { format }) => format,
+        Some(NetMessage::Error { message }) => {
+            anyhow::bail!("The server refused Hello (too old?): {}", message)
+        }
+        Some(other) => anyhow::bail!("Unexpected reply to Hello: {:?}", other),
+        None => anyhow::bail!("Connection closed during the handshake"),
+    };
+    reader.set_format(format);
+    writer.set_format(format);
+    Ok(format)
+}
+
+/// Whether `addr` names a WebSocket endpoint (`ws://` or `wss://`)
+pub fn is_websocket_url(addr: &str) -> bool {
+    addr.starts_with("ws://") || addr.starts_with("wss://")
+}
+
+/// Server side TLS from a PEM certificate chain and its PEM private key
+pub fn load_tls_acceptor(cert: &Path, key: &Path) -> anyhow::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("cannot read certificate {}: {}", cert.display(), e))?;
+    anyhow::ensure!(!certs.is_empty(), "no certificate in {}", cert.display());
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| anyhow::anyhow!("cannot read private key {}: {}", key.display(), e))?;
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Client side TLS: the web roots plus the certificates in the PEM file `ca`
+fn client_tls_config(ca: Option<&Path>) -> anyhow::Result<Arc<rustls::ClientConfig>> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = ca {
+        for cert in CertificateDer::pem_file_iter(path)
+            .map_err(|e| anyhow::anyhow!("cannot read CA certificates {}: {}", path.display(), e))?
+        {
+            roots.add(
+                cert.map_err(|e| anyhow::anyhow!("bad certificate in {}: {}", path.display(), e))?,
+            )?;
+        }
+    }
+    Ok(Arc::new(
+        rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    ))
+}
+
+fn split_lines<S>(stream: S) -> (MessageReader, MessageWriter)
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (reader, writer) = tokio::io::split(stream);
+    (
+        MessageReader {
+            incoming: Incoming::Tcp(BufReader::new(Box::new(reader) as ByteReader)),
+            format: WireFormat::Json,
+        },
+        MessageWriter {
+            outgoing: Outgoing::Tcp(Box::new(writer)),
+            format: WireFormat::Json,
+        },
+    )
+}
+
+fn split_websocket<S>(ws: S) -> (MessageReader, MessageWriter)
+where
+    S: Sink<Message, Error = WsError> + Stream<Item = Result<Message, WsError>> + Send + 'static,
+{
+    let (sink, stream) = ws.split();
+    (
+        MessageReader {
+            incoming: Incoming::WebSocket(Box::pin(stream)),
+            format: WireFormat::Json,
+        },
+        MessageWriter {
+            outgoing: Outgoing::WebSocket(Box::pin(sink)),
+            format: WireFormat::Json,
+        },
+    )
+}
+
+/// Local end of raw TCP sockets, for playing across NATs: a fixed local port
+/// that a port forward or a hole punch expects, and `SO_REUSEADDR` /
+/// `SO_REUSEPORT` so that port can be shared, e.g. by a listener and an
+/// outgoing connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalBinding {
+    pub addr: Option<SocketAddr>,
+    pub reuse_port: bool,
+}
+
+fn tcp_socket(family: SocketAddr, binding: &LocalBinding) -> std::io::Result<TcpSocket> {
+    let socket = if family.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    if binding.reuse_port {
+        socket.set_reuseaddr(true)?;
+        #[cfg(unix)]
+        socket.set_reuseport(true)?;
+    }
+    if let Some(addr) = binding.addr {
+        socket.bind(addr)?;
+    }
+    Ok(socket)
+}
+
+/// Listens on `addr` (`host:port`), with port reuse if asked
+pub async fn bind_listener(addr: &str, reuse_port: bool) -> anyhow::Result<TcpListener> {
+    let local = tokio::net::lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("cannot resolve {}", addr))?;
+    let binding = LocalBinding {
+        addr: Some(local),
+        reuse_port,
+    };
+    Ok(tcp_socket(local, &binding)?.listen(1024)?)
+}
+
+/// Connects to a server: a `ws://` / `wss://` URL over WebSocket,
+/// `tls://host:port` over TLS, anything else (`host:port`, see
+/// [`crate::network::client::NetworkClient::sanitize_addr`]) over raw TCP
+pub async fn connect(addr: &str) -> anyhow::Result<(MessageReader, MessageWriter)> {
+    connect_from(addr, &LocalBinding::default()).await
+}
+
+/// Like [`connect`], from the local end `binding` (raw TCP and TLS only).
+/// TLS clients also trust the certificates in the file named by `AHO_TLS_CA`.
+pub async fn connect_from(
+    addr: &str,
+    binding: &LocalBinding,
+) -> anyhow::Result<(MessageReader, MessageWriter)> {
+    let ca = std::env::var_os(TLS_CA_ENV).map(std::path::PathBuf::from);
+    connect_trusting(addr, binding, ca.as_deref()).await
+}
+
+/// Like [`connect_from`], trusting the certificates in the PEM file `ca`
+async fn connect_trusting(
+    addr: &str,
+    binding: &LocalBinding,
+    ca: Option<&Path>,
+) -> anyhow::Result<(MessageReader, MessageWriter)> {
+    let timed_out = || anyhow::anyhow!("Connection timed out: {}", addr);
+    if is_websocket_url(addr) {
+        anyhow::ensure!(
+            *binding == LocalBinding::default(),
+            "a local address or port reuse needs a raw TCP address, not {}",
+            addr
+        );
+        let connector = Connector::Rustls(client_tls_config(ca)?);
+        let connect =
+            tokio_tungstenite::connect_async_tls_with_config(addr, None, false, Some(connector));
+        let (ws, _) = tokio::time::timeout(CONNECT_TIMEOUT, connect)
+            .await
+            .map_err(|_| timed_out())??;
+        return Ok(split_websocket(ws));
+    }
+
+    let host_port = addr.strip_prefix("tls://").unwrap_or(addr);
+    let open = async {
+        let mut targets = tokio::net::lookup_host(host_port).await?;
+        // The bound address decides the family
+        let target = match binding.addr {
+            Some(local) => targets.find(|t| t.is_ipv4() == local.is_ipv4()),
+            None => targets.next(),
+        }
+        .ok_or_else(|| anyhow::anyhow!("cannot resolve {}", host_port))?;
+        anyhow::Ok(tcp_socket(target, binding)?.connect(target).await?)
+    };
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, open)
+        .await
+        .map_err(|_| timed_out())??;
+    if host_port.len() == addr.len() {
+        return Ok(split_lines(stream));
+    }
+    let host = host_port
+        .rsplit_once(':')
+        .map_or(host_port, |(host, _)| host)
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let server_name = ServerName::try_from(host.to_string())?;
+    let connector = TlsConnector::from(client_tls_config(ca)?);
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, connector.connect(server_name, stream))
+        .await
+        .map_err(|_| timed_out())??;
+    Ok(split_lines(stream))
+}
+
+/// Takes a client that connected to the server, after the TLS handshake when
+/// `tls` is given: WebSocket clients open with an HTTP upgrade request
+/// (`GET ...`), raw TCP clients with a JSON line
+pub async fn accept(
+    stream: TcpStream,
+    tls: Option<&TlsAcceptor>,
+) -> anyhow::Result<(MessageReader, MessageWriter)> {
+    match tls {
+        Some(acceptor) => {
+            let stream = tokio::time::timeout(SNIFF_TIMEOUT, acceptor.accept(stream))
+                .await
+                .map_err(|_| anyhow::anyhow!("TLS handshake timed out"))??;
+            accept_stream(stream).await
+        }
+        None => accept_stream(stream).await,
+    }
+}
+
+async fn accept_stream<S>(stream: S) -> anyhow::Result<(MessageReader, MessageWriter)>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (is_http, stream) = sniff_http(stream).await?;
+    if is_http {
+        Ok(split_websocket(
+            tokio_tungstenite::accept_async(stream).await?,
+        ))
+    } else {
+        Ok(split_lines(stream))
+    }
+}
+
+/// Reads the first bytes a client sends, which may arrive in several packets,
+/// until they show whether it opened with `GET ` (an HTTP upgrade request),
+/// the client closes or [`SNIFF_TIMEOUT`] passes. The bytes are put back in
+/// front of the returned stream.
+async fn sniff_http<S>(mut stream: S) -> anyhow::Result<(bool, Rewind<S>)>
+where
+    S: AsyncRead + Unpin,
+{
+    const GET: &[u8] = b"GET ";
+    let mut prefix = Vec::with_capacity(GET.len());
+    let read = async {
+        while prefix.len() < GET.len() && GET.starts_with(&prefix) {
+            let mut buf = [0u8; 4];
+            let n = stream.read(&mut buf[..GET.len() - prefix.len()]).await?;
+            if n == 0 {
+                break;
+            }
+            prefix.extend_from_slice(&buf[..n]);
+        }
+        anyhow::Ok(())
+    };
+    // A client that stops sending is sorted out by what it sent so far
+    if let Ok(result) = tokio::time::timeout(SNIFF_TIMEOUT, read).await {
+        result?;
+    }
+    Ok((
+        prefix == GET,
+        Rewind {
+            prefix,
+            pos: 0,
+            inner: stream,
+        },
+    ))
+}
+
+/// A stream whose first bytes were already read: reads return `prefix`
+/// before the rest of `inner`
+struct Rewind<S> {
+    prefix: Vec<u8>,
+    pos: usize,
+    inner: S,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Rewind<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        if this.pos < this.prefix.len() {
+            let rest = &this.prefix[this.pos..];
+            let n = rest.len().min(buf.remaining());
+            buf.put_slice(&rest[..n]);
+            this.pos += n;
+            return std::task::Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Rewind<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_and_websocket_on_one_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // Echo every message back, whatever the transport
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = accept(stream, None).await.unwrap();
+                    while let Some(msg) = reader.recv().await.unwrap() {
+                        writer.send(&msg).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        for url in [addr.to_string(), format!("ws://{}/", addr)] {
+            let (mut reader, mut writer) = connect(&url).await.unwrap();
+            writer.send(&NetMessage::Resign).await.unwrap();
+            let echo = reader.recv().await.unwrap();
+            assert!(matches!(echo, Some(NetMessage::Resign)), "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_pack() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // Answer `Hello` like the server, then echo
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = accept(stream, None).await.unwrap();
+                    while let Some(msg) = reader.recv().await.unwrap() {
+                        if let NetMessage::Hello { formats } = msg {
+                            let format = formats[0];
+                            writer.send(&NetMessage::Encoding { format }).await.unwrap();
+                            reader.set_format(format);
+                            writer.set_format(format);
+                        } else {
+                            writer.send(&msg).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+
+        let board = crate::core::setup::BoardSetupType::StandardMixed.create_board();
+        let update = NetMessage::Update {
+            board,
+            last_move: None,
+            next_player: crate::core::PlayerId::Player1,
+            clock_ms: None,
+        };
+        let json = WireFormat::Json.encode(&update).unwrap().len();
+        let packed = WireFormat::MessagePack.encode(&update).unwrap().len();
+        assert!(packed < json, "{} >= {}", packed, json);
+
+        for url in [addr.to_string(), format!("ws://{}/", addr)] {
+            let (mut reader, mut writer) = connect(&url).await.unwrap();
+            let format = negotiate(&mut reader, &mut writer, &[WireFormat::MessagePack])
+                .await
+                .unwrap();
+            assert_eq!(format, WireFormat::MessagePack);
+            writer.send(&update).await.unwrap();
+            let echo = reader.recv().await.unwrap();
+            assert!(
+                matches!(echo, Some(NetMessage::Update { clock_ms: None, .. })),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_binding() {
+        let listener = bind_listener("127.0.0.1:0", true).await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (peers_tx, mut peers) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = listener.accept().await.unwrap();
+                peers_tx.send(peer).unwrap();
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = accept(stream, None).await.unwrap();
+                    while let Some(msg) = reader.recv().await.unwrap() {
+                        writer.send(&msg).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        // Listening on a port and connecting out from it at once, as for a hole punch
+        let own = bind_listener("127.0.0.1:0", true).await.unwrap();
+        let port = own.local_addr().unwrap();
+        let binding = LocalBinding {
+            addr: Some(port),
+            reuse_port: true,
+        };
+        let (mut reader, mut writer) = connect_from(&addr, &binding).await.unwrap();
+        assert_eq!(peers.recv().await.unwrap(), port);
+        writer.send(&NetMessage::Resign).await.unwrap();
+        assert!(matches!(
+            reader.recv().await.unwrap(),
+            Some(NetMessage::Resign)
+        ));
+        drop(own);
+        assert!(connect_from(&format!("ws://{}/", addr), &binding)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_overlong_line() {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let (mut reader, _writer) = split_lines(server);
+        tokio::spawn(async move {
+            let chunk = vec![b'x'; 64 * 1024];
+            while client.write_all(&chunk).await.is_ok() {}
+        });
+        let err = reader.recv_frame().await.unwrap_err();
+        assert!(err.to_string().contains("longer than"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_sniff_split_request() {
+        let (mut client, server) = tokio::io::duplex(64);
+        client.write_all(b"GE").await.unwrap();
+        let sniff = tokio::spawn(sniff_http(server));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        client.write_all(b"T / HTTP/1.1\r\n").await.unwrap();
+        let (is_http, mut stream) = sniff.await.unwrap().unwrap();
+        assert!(is_http);
+        let mut line = String::new();
+        BufReader::new(&mut stream)
+            .read_line(&mut line)
+            .await
+            .unwrap();
+        assert_eq!(line, "GET / HTTP/1.1\r\n");
+
+        // A game client is told apart by its first byte
+        let (mut client, server) = tokio::io::duplex(64);
+        client.write_all(b"{").await.unwrap();
+        let (is_http, _) = sniff_http(server).await.unwrap();
+        assert!(!is_http);
+    }
+
+    #[tokio::test]
+    async fn test_tls() {
+        let dir = std::env::temp_dir().join(format!("aho_tls_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+        let acceptor = load_tls_acceptor(&cert_path, &key_path).unwrap();
+        assert!(load_tls_acceptor(&key_path, &cert_path).is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // Untrusted clients fail the handshake
+                    let handshake = accept(stream, Some(&acceptor));
+                    let Ok(Ok((mut reader, mut writer))) =
+                        tokio::time::timeout(Duration::from_secs(10), handshake).await
+                    else {
+                        return;
+                    };
+                    while let Some(msg) = reader.recv().await.unwrap() {
+                        writer.send(&msg).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        let url = format!("tls://localhost:{}", port);
+        let binding = LocalBinding::default();
+        assert!(
+            connect_trusting(&url, &binding, None).await.is_err(),
+            "self-signed certificate trusted"
+        );
+        for url in [url, format!("wss://localhost:{}/", port)] {
+            let (mut reader, mut writer) = connect_trusting(&url, &binding, Some(&cert_path))
+                .await
+                .unwrap();
+            writer.send(&NetMessage::Resign).await.unwrap();
+            let echo = reader.recv().await.unwrap();
+            assert!(matches!(echo, Some(NetMessage::Resign)), "{}", url);
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,235 @@
+//! Adaptive difficulty: an AI that adjusts its search depth to the human
+//!
+//! [`OpponentModel`] keeps the human's recent moves: whether each one was a
+//! blunder (the AI's evaluation jumped by more than the configured threshold
+//! across it) and how long the human thought. Once a window of moves is full
+//! the depth moves one step within the configured band (see
+//! [`AdaptiveDifficultyConfig`]) and the window starts over.
+
+use super::alpha_beta::{AIStrength, AlphaBetaAI};
+use super::config::AdaptiveDifficultyConfig;
+use crate::core::{Board, Move, PlayerId};
+use crate::player::PlayerController;
+use crate::util::Instant;
+use std::cell::RefCell;
+use std::time::Duration;
+
+/// The human's recent play and the search depth it calls for
+#[derive(Debug, Clone)]
+pub struct OpponentModel {
+    config: AdaptiveDifficultyConfig,
+    depth: u8,
+    blunders: Vec<bool>,
+    think_times: Vec<Duration>,
+}
+
+impl OpponentModel {
+    pub fn new(config: &AdaptiveDifficultyConfig) -> Self {
+        let min_depth = config.min_depth.max(1);
+        Self {
+            depth: config
+                .initial_depth
+                .clamp(min_depth, config.max_depth.max(min_depth)),
+            config: config.clone(),
+            blunders: Vec::new(),
+            think_times: Vec::new(),
+        }
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Record one human move: `gain` is how much the AI's evaluation rose
+    /// across it, `think_time` how long the human took. Returns the depth for
+    /// the following searches.
+    pub fn record_move(&mut self, gain: i32, think_time: Duration) -> u8 {
+        self.blunders
+            .push(gain >= self.config.blunder_threshold_centipawns);
+        self.think_times.push(think_time);
+        if self.blunders.len() < self.config.window.max(1) {
+            return self.depth;
+        }
+
+        let min_depth = self.config.min_depth.max(1);
+        let max_depth = self.config.max_depth.max(min_depth);
+        let blunder_rate = self.blunder_rate();
+        let think_secs = self.average_think_time().as_secs_f64();
+        if blunder_rate > self.config.max_blunder_rate || think_secs > self.config.slow_move_secs {
+            self.depth = self.depth.saturating_sub(1).max(min_depth);
+        } else if blunder_rate <= self.config.min_blunder_rate
+            && think_secs < self.config.slow_move_secs / 2.0
+        {
+            self.depth = (self.depth + 1).min(max_depth);
+        }
+        self.blunders.clear();
+        self.think_times.clear();
+        self.depth
+    }
+
+    /// Share of blunders among the moves in the current window
+    pub fn blunder_rate(&self) -> f64 {
+        if self.blunders.is_empty() {
+            return 0.0;
+        }
+        self.blunders.iter().filter(|&&b| b).count() as f64 / self.blunders.len() as f64
+    }
+
+    pub fn average_think_time(&self) -> Duration {
+        if self.think_times.is_empty() {
+            return Duration::ZERO;
+        }
+        self.think_times.iter().sum::<Duration>() / self.think_times.len() as u32
+    }
+}
+
+/// The AI's last move: its evaluation, when it was played, and the ply count after it
+#[derive(Clone, Copy)]
+struct LastTurn {
+    score: i32,
+    played_at: Instant,
+    plies: usize,
+}
+
+/// Alpha-beta AI whose depth follows an [`OpponentModel`] of the human
+pub struct AdaptiveAI {
+    ai: AlphaBetaAI,
+    model: RefCell<OpponentModel>,
+    last_turn: RefCell<Option<LastTurn>>,
+    time_limit: Duration,
+}
+
+impl AdaptiveAI {
+    pub fn new(
+        player_id: PlayerId,
+        name: &str,
+        config: &AdaptiveDifficultyConfig,
+        custom_model_path: Option<String>,
+        silent: bool,
+    ) -> Self {
+        Self::with_evaluator_type(
+            player_id,
+            name,
+            config,
+            &crate::player::ai::config::AIConfig::get()
+                .evaluation
+                .evaluator_type,
+            custom_model_path,
+            silent,
+        )
+    }
+
+    /// Like [`AdaptiveAI::new`], but with an explicit evaluator type (see
+    /// [`AlphaBetaAI::with_evaluator_type`])
+    pub fn with_evaluator_type(
+        player_id: PlayerId,
+        name: &str,
+        config: &AdaptiveDifficultyConfig,
+        evaluator_type: &str,
+        custom_model_path: Option<String>,
+        silent: bool,
+    ) -> Self {
+        Self {
+            ai: AlphaBetaAI::with_evaluator_type(
+                player_id,
+                name,
+                AIStrength::Strong,
+                evaluator_type,
+                custom_model_path,
+                silent,
+            ),
+            model: RefCell::new(OpponentModel::new(config)),
+            last_turn: RefCell::new(None),
+            time_limit: Duration::from_secs_f64(config.time_limit_secs.max(0.1)),
+        }
+    }
+
+    /// Search depth currently in use
+    pub fn depth(&self) -> u8 {
+        self.model.borrow().depth()
+    }
+
+    pub fn opponent_model(&self) -> OpponentModel {
+        self.model.borrow().clone()
+    }
+}
+
+impl PlayerController for AdaptiveAI {
+    fn choose_move(&self, board: &Board, _moves: &[Move]) -> Option<Move> {
+        let started = Instant::now();
+        let depth = self.model.borrow().depth();
+        let best_move = self
+            .ai
+            .search_with_limits(board, depth as usize, self.time_limit)?;
+        let score = (*self.ai.last_thinking.borrow()).map_or(0, |info| info.score);
+
+        // Exactly one human move since our last one: judge it by how much our
+        // evaluation moved. The new depth applies from the next move.
+        if let Some(last) = *self.last_turn.borrow() {
+            if board.history.len() == last.plies + 1 {
+                self.model.borrow_mut().record_move(
+                    score.saturating_sub(last.score),
+                    started.duration_since(last.played_at),
+                );
+            }
+        }
+        *self.last_turn.borrow_mut() = Some(LastTurn {
+            score,
+            played_at: Instant::now(),
+            plies: board.history.len() + 1,
+        });
+        Some(best_move)
+    }
+
+    fn name(&self) -> &str {
+        self.ai.name()
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn last_search(&self, board: &Board) -> Option<super::alpha_beta::AnalysisResult> {
+        self.ai.last_search(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_follows_blunders_and_think_time() {
+        let config = AdaptiveDifficultyConfig {
+            window: 3,
+            ..AdaptiveDifficultyConfig::default()
+        };
+        let quick = Duration::from_secs(5);
+        let mut model = OpponentModel::new(&config);
+        assert_eq!(model.depth(), 3);
+
+        // Two blunders out of three: weaker, and the window starts over
+        model.record_move(500, quick);
+        model.record_move(0, quick);
+        assert_eq!(model.record_move(400, quick), 2);
+        assert_eq!(model.blunder_rate(), 0.0);
+
+        // Clean, quick play: stronger
+        for _ in 0..3 {
+            model.record_move(50, quick);
+        }
+        assert_eq!(model.depth(), 3);
+
+        // Clean but slow: the human is struggling
+        for _ in 0..3 {
+            model.record_move(0, Duration::from_secs(90));
+        }
+        assert_eq!(model.depth(), 2);
+
+        // Never below the band
+        for _ in 0..12 {
+            model.record_move(1000, quick);
+        }
+        assert_eq!(model.depth(), config.min_depth);
+    }
+}
@@ -1,34 +1,156 @@
+use super::analysis_cache::{AnalysisCache, CachedEntry, DISTILL_MIN_DEPTH};
+use super::config::{AdaptiveDepthConfig, OpeningRandomnessConfig, SwindleConfig};
 use super::eval::HandcraftedEvaluator;
 use super::evaluator::Evaluator;
 use super::tt::{Bound, TranspositionTable};
-use crate::core::{Board, Move, PlayerId};
+use crate::core::{Board, Move, PieceKind, PlayerId, Position};
+use crate::game::SearchHealth;
 use crate::logic::ZobristHasher;
-use crate::logic::{apply_move, is_in_check, legal_moves};
+use crate::logic::{apply_move, can_drop_at, is_in_check, is_legal, legal_moves, piece_moves};
 use crate::player::PlayerController;
 
-use std::cell::RefCell;
-use std::time::{Duration, Instant};
+use crate::util::Instant;
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
 
 pub struct AlphaBetaAI {
     player_id: PlayerId,
     name: String,
     tt: RefCell<TranspositionTable>,
     nodes_evaluated: RefCell<usize>,
+    counters: RefCell<SearchCounters>,
     time_limit: Duration,
     strength: AIStrength,
-    pub last_thinking: RefCell<Option<(usize, i32, usize, u128)>>, // (depth, score, nodes, time_ms)
-    killer_moves: RefCell<[[Option<Move>; 2]; 64]>,                // Ply indexed
+    pub last_thinking: RefCell<Option<SearchInfo>>,
+    killer_moves: RefCell<[[Option<Move>; 2]; 64]>, // Ply indexed
     evaluator: RefCell<Box<dyn Evaluator>>,
+    /// Decaying root-move randomness for sparring games; `None` always plays the best move
+    opening_randomness: Option<OpeningRandomnessConfig>,
+    /// Play for complications once clearly lost; `None` always plays the best move
+    swindle: Option<SwindleConfig>,
+    /// Start and time limit of the running search; `negamax` gives up once
+    /// the limit has passed
+    budget: Cell<Option<(Instant, Duration)>>,
+    /// Set when the running iteration ran out of time; its results are discarded
+    aborted: Cell<bool>,
 }
 
 const MAX_PLY: usize = 64;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+/// Per-search counters behind [`SearchHealth`]
+#[derive(Clone, Copy, Default)]
+struct SearchCounters {
+    qsearch_nodes: usize,
+    /// Main-search nodes whose moves were searched
+    expanded_nodes: usize,
+    cutoffs: usize,
+    moves_searched: usize,
+}
+
+/// Number of root moves considered by [`AlphaBetaAI::choose_move_with_temperature`]
+const SAMPLING_TOP_MOVES: usize = 5;
+/// Cap on the depth used to score the sampling candidates
+const SAMPLING_MAX_DEPTH: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub enum AIStrength {
     Strong,
     Light,
 }
 
+/// How the nominal search budget was adapted to the position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DepthProfile {
+    /// Nominal depth and time
+    Normal,
+    /// Simplified endgame: few pieces, cheap nodes, search deeper
+    Endgame,
+    /// Explosive drop-heavy middlegame: search shallower to hold latency
+    DropHeavy,
+}
+
+impl DepthProfile {
+    /// Classify a position according to a preset's adaptive depth rules
+    pub fn classify(board: &Board, config: &AdaptiveDepthConfig) -> Self {
+        if !config.enabled {
+            return DepthProfile::Normal;
+        }
+
+        // Only hand pieces that can actually be dropped widen the tree
+        let hand_pieces: usize = board
+            .hand
+            .iter()
+            .filter(|(player, _)| board.get_player_config(**player).can_drop)
+            .map(|(_, hand)| hand.values().sum::<usize>())
+            .sum();
+        let board_pieces = board
+            .pieces
+            .values()
+            .filter(|p| !matches!(p.kind, PieceKind::S_King | PieceKind::C_King))
+            .count();
+
+        if board_pieces + hand_pieces <= config.endgame_piece_threshold {
+            DepthProfile::Endgame
+        } else if hand_pieces >= config.drop_heavy_hand_threshold {
+            DepthProfile::DropHeavy
+        } else {
+            DepthProfile::Normal
+        }
+    }
+
+    /// Apply this profile to a nominal depth and time budget
+    pub fn adjust(
+        self,
+        depth: usize,
+        time_limit: Duration,
+        config: &AdaptiveDepthConfig,
+    ) -> (usize, Duration) {
+        match self {
+            DepthProfile::Normal => (depth, time_limit),
+            DepthProfile::Endgame => (
+                depth + config.endgame_depth_bonus as usize,
+                time_limit.mul_f64(config.endgame_time_multiplier),
+            ),
+            DepthProfile::DropHeavy => (
+                depth
+                    .saturating_sub(config.drop_heavy_depth_penalty as usize)
+                    .max(1),
+                time_limit.mul_f64(config.drop_heavy_time_multiplier),
+            ),
+        }
+    }
+}
+
+/// Summary of the last completed search
+#[derive(Clone, Copy, Debug)]
+pub struct SearchInfo {
+    pub depth: usize,
+    pub score: i32,
+    pub nodes: usize,
+    pub time_ms: u128,
+    /// Depth limit after adaptive adjustment
+    pub max_depth: usize,
+    pub profile: DepthProfile,
+    pub tt_probes: usize,
+    pub tt_hits: usize,
+    pub health: SearchHealth,
+}
+
+/// Result of [`AlphaBetaAI::analyze`]
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AnalysisResult {
+    pub depth: usize,
+    /// Score for the side to move
+    pub score: i32,
+    pub best_move: Option<Move>,
+    pub pv: Vec<Move>,
+    /// Answered from the cache without searching
+    pub cached: bool,
+    pub nodes: usize,
+    /// `None` when answered from the cache
+    pub health: Option<SearchHealth>,
+}
+
 impl AlphaBetaAI {
     #[allow(unused)]
     pub fn new(
@@ -42,43 +164,86 @@ impl AlphaBetaAI {
 
         // Create evaluator based on config
         let config = AIConfig::get();
-        let evaluator: Box<dyn Evaluator> = match config.evaluation.evaluator_type.as_str() {
+        Self::with_evaluator_type(
+            player_id,
+            name,
+            strength,
+            &config.evaluation.evaluator_type,
+            custom_model_path,
+            silent,
+        )
+    }
+
+    /// Like [`AlphaBetaAI::new`], but with an explicit evaluator type
+    /// ("Handcrafted", "NeuralNetwork" or "NNUE") instead of the one in ai_config.json
+    pub fn with_evaluator_type(
+        player_id: PlayerId,
+        name: &str,
+        strength: AIStrength,
+        evaluator_type: &str,
+        custom_model_path: Option<String>,
+        silent: bool,
+    ) -> Self {
+        use crate::player::ai::config::AIConfig;
+
+        let config = AIConfig::get();
+        let evaluator: Box<dyn Evaluator> = match evaluator_type {
+            "NeuralNetwork" if !crate::ml::ML_ENABLED => {
+                // Built without ONNX Runtime: say so once instead of silently switching
+                crate::ml::warn_ml_unavailable();
+                Box::new(HandcraftedEvaluator::new())
+            }
             "NeuralNetwork" => {
-                #[cfg(feature = "ml")]
-                {
-                    use crate::ml::nn_evaluator::NNEvaluator;
-
-                    let model_path =
-                        custom_model_path.or_else(|| config.evaluation.nn_model_path.clone());
-
-                    if let Some(ref path) = model_path {
-                        let result = if silent {
-                            NNEvaluator::load_silent(path)
-                        } else {
-                            NNEvaluator::load(path)
-                        };
-
-                        match result {
-                            Ok(nn_eval) => {
-                                // Successfully loaded
-                                Box::new(nn_eval)
-                            }
-                            Err(_e) => {
-                                // Failed to load, fallback
-                                Box::new(HandcraftedEvaluator::new())
+                use crate::ml::nn_evaluator::NNEvaluator;
+
+                let model_path =
+                    custom_model_path.or_else(|| config.evaluation.nn_model_path.clone());
+
+                if let Some(ref path) = model_path {
+                    let result = if silent {
+                        NNEvaluator::load_silent(path)
+                    } else {
+                        NNEvaluator::load(path)
+                    };
+
+                    match result {
+                        Ok(nn_eval) => {
+                            // Successfully loaded
+                            Box::new(nn_eval)
+                        }
+                        Err(e) => {
+                            // Failed to load, fallback
+                            if !silent {
+                                eprintln!(
+                                    "[ML] Failed to load {}: {}, using Handcrafted\r",
+                                    path, e
+                                );
                             }
+                            Box::new(HandcraftedEvaluator::new())
                         }
-                    } else {
-                        // No model path
-                        Box::new(HandcraftedEvaluator::new())
                     }
-                }
-                #[cfg(not(feature = "ml"))]
-                {
-                    // ML feature not enabled
+                } else {
+                    // No model path
                     Box::new(HandcraftedEvaluator::new())
                 }
             }
+            "NNUE" => {
+                use crate::ml::nnue::NnueEvaluator;
+
+                // Pure Rust, so available without the ml feature
+                let model_path =
+                    custom_model_path.or_else(|| config.evaluation.nn_model_path.clone());
+                match model_path.map(|path| (NnueEvaluator::load(&path), path)) {
+                    Some((Ok(nnue), _)) => Box::new(nnue),
+                    Some((Err(e), path)) => {
+                        if !silent {
+                            eprintln!("[ML] Failed to load {}: {}, using Handcrafted\r", path, e);
+                        }
+                        Box::new(HandcraftedEvaluator::new())
+                    }
+                    None => Box::new(HandcraftedEvaluator::new()),
+                }
+            }
             _ => {
                 // Default: Handcrafted evaluator
                 Box::new(HandcraftedEvaluator::new())
@@ -90,37 +255,272 @@ impl AlphaBetaAI {
             name: name.to_string(),
             tt: RefCell::new(TranspositionTable::new(64)), // 64MB
             nodes_evaluated: RefCell::new(0),
+            counters: RefCell::new(SearchCounters::default()),
             time_limit: Duration::from_secs(if strength == AIStrength::Strong { 3 } else { 1 }),
             strength,
             last_thinking: RefCell::new(None),
             killer_moves: RefCell::new([[None; 2]; MAX_PLY]),
             evaluator: RefCell::new(evaluator),
+            opening_randomness: None,
+            swindle: None,
+            budget: Cell::new(None),
+            aborted: Cell::new(false),
         }
     }
 
+    /// Vary the opening: sample root moves with a temperature that decays by
+    /// move number (see [`OpeningRandomnessConfig`]). Disabled configs are ignored.
+    pub fn with_opening_randomness(mut self, config: &OpeningRandomnessConfig) -> Self {
+        self.opening_randomness = config.enabled.then(|| config.clone());
+        self
+    }
+
+    /// Swindle in clearly lost positions (see [`SwindleConfig`]). Disabled
+    /// configs are ignored.
+    pub fn with_swindle(mut self, config: &SwindleConfig) -> Self {
+        self.swindle = config.enabled.then(|| config.clone());
+        self
+    }
+
     /// Get the name of the evaluator being used
     pub fn evaluator_name(&self) -> String {
         self.evaluator.borrow().name().to_string()
     }
 
+    /// Static evaluation of `board` by this engine's evaluator, from Player1's view
+    pub fn static_eval(&self, board: &Board) -> i32 {
+        self.evaluator.borrow_mut().evaluate(board)
+    }
+
     // --- Search Root (Iterative Deepening) ---
     fn search_root(&self, board: &Board) -> Option<Move> {
+        // Nominal depth comes from the strength preset, then adapts to the position
+        let search_config = &crate::player::ai::config::AIConfig::get().search;
+        let adaptive = search_config.adaptive(self.strength);
+        let profile = DepthProfile::classify(board, adaptive);
+        let (max_depth, time_limit) = profile.adjust(
+            search_config.max_depth(self.strength) as usize,
+            self.time_limit,
+            adaptive,
+        );
+
         self.tt.borrow_mut().clear(); // Clear TT for new search
+        self.iterative_deepening(board, max_depth, time_limit, profile)
+    }
+
+    /// Search up to `max_depth` plies within `time_limit`, ignoring the
+    /// strength preset (used by the adaptive difficulty)
+    pub fn search_with_limits(
+        &self,
+        board: &Board,
+        max_depth: usize,
+        time_limit: Duration,
+    ) -> Option<Move> {
+        self.tt.borrow_mut().clear();
+        self.iterative_deepening(board, max_depth, time_limit, DepthProfile::Normal)
+    }
+
+    /// Search to exactly `depth` plies with no time limit (used by `bench`)
+    pub fn search_fixed_depth(&self, board: &Board, depth: usize) -> Option<Move> {
+        self.tt.borrow_mut().clear();
+        self.iterative_deepening(board, depth, Duration::MAX, DepthProfile::Normal)
+    }
+
+    /// Search to `depth` plies for analysis, seeding the transposition table
+    /// from `cache` and distilling the results back into it afterwards.
+    /// A position already analysed at least this deep is answered from the cache.
+    pub fn analyze(
+        &self,
+        board: &Board,
+        depth: usize,
+        cache: &mut AnalysisCache,
+    ) -> Option<AnalysisResult> {
+        let root_hash = ZobristHasher::compute_hash(board, self.player_id);
+        if let Some(entry) = cache.get(root_hash) {
+            if entry.bound == Bound::Exact && entry.depth >= depth && entry.best_move.is_some() {
+                return Some(AnalysisResult {
+                    depth: entry.depth,
+                    score: entry.score,
+                    best_move: entry.best_move,
+                    pv: entry.pv.clone(),
+                    cached: true,
+                    nodes: 0,
+                    health: None,
+                });
+            }
+        }
+
+        {
+            let mut tt = self.tt.borrow_mut();
+            tt.clear();
+            for (hash, entry) in cache.iter() {
+                tt.store(hash, entry.depth, entry.score, entry.bound, entry.best_move);
+            }
+        }
+
+        let best_move =
+            self.iterative_deepening(board, depth, Duration::MAX, DepthProfile::Normal)?;
+        let info = (*self.last_thinking.borrow())?;
+        let pv = self.extract_pv(board, depth);
+
+        for (entry, mv) in self.tt.borrow().iter() {
+            if entry.depth >= DISTILL_MIN_DEPTH {
+                cache.insert(
+                    entry.hash,
+                    CachedEntry {
+                        depth: entry.depth,
+                        score: entry.score,
+                        bound: entry.bound,
+                        best_move: *mv,
+                        pv: Vec::new(),
+                    },
+                );
+            }
+        }
+        cache.insert(
+            root_hash,
+            CachedEntry {
+                depth: info.depth,
+                score: info.score,
+                bound: Bound::Exact,
+                best_move: Some(best_move),
+                pv: pv.clone(),
+            },
+        );
+
+        Some(AnalysisResult {
+            depth: info.depth,
+            score: info.score,
+            best_move: Some(best_move),
+            pv,
+            cached: false,
+            nodes: info.nodes,
+            health: Some(info.health),
+        })
+    }
+
+    /// Search as usual, then sample among the best root moves with probability
+    /// proportional to `exp(score / temperature)` (temperature in centipawns).
+    /// The candidates are rescored by a shallow search, and the searched best
+    /// move is always among them. `last_thinking` still describes the full search.
+    pub fn choose_move_with_temperature(
+        &self,
+        board: &Board,
+        temperature: f64,
+        rng: &mut impl rand::Rng,
+    ) -> Option<Move> {
+        let best_move = self.search_root(board)?;
+        if temperature <= 0.0 {
+            return Some(best_move);
+        }
+        let mut scored = self.score_root_moves(board);
+        if let Some(pos) = scored.iter().position(|&(mv, _)| mv == best_move) {
+            if pos >= SAMPLING_TOP_MOVES {
+                scored.swap(pos, SAMPLING_TOP_MOVES - 1);
+            }
+        }
+        scored.truncate(SAMPLING_TOP_MOVES);
+
+        let top = scored.first().map_or(0, |&(_, score)| score);
+        let weights: Vec<f64> = scored
+            .iter()
+            .map(|&(_, score)| ((score - top) as f64 / temperature).exp())
+            .collect();
+        let dist = rand::distributions::WeightedIndex::new(&weights).ok()?;
+        Some(scored[rand::distributions::Distribution::sample(&dist, rng)].0)
+    }
+
+    /// Every root move with the score of a shallow search after it (capped at
+    /// [`SAMPLING_MAX_DEPTH`] and below the last search's depth), best first
+    fn score_root_moves(&self, board: &Board) -> Vec<(Move, i32)> {
+        let searched_depth = (*self.last_thinking.borrow()).map_or(0, |info| info.depth);
+        let depth = searched_depth.saturating_sub(1).min(SAMPLING_MAX_DEPTH);
+
+        let opponent = self.player_id.opponent();
+        let mut scored: Vec<(Move, i32)> = legal_moves(board, self.player_id)
+            .into_iter()
+            .map(|mv| {
+                let child = apply_move(board, &mv, self.player_id);
+                (
+                    mv,
+                    -self.negamax(&child, depth, -200000, 200000, opponent, 1),
+                )
+            })
+            .collect();
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        scored
+    }
+
+    /// After a search that scored the position at or below the swindle
+    /// threshold, trade up to the margin of evaluation for complications
+    /// (see [`crate::player::ai::swindle`]). `last_thinking` still describes
+    /// the full search.
+    fn swindle_move(&self, board: &Board, best_move: Move, config: &SwindleConfig) -> Move {
+        let mut scored = self.score_root_moves(board);
+        // The searched best move leads with the top score, whatever the
+        // shallow search made of it
+        let top = scored.first().map_or(0, |&(_, score)| score);
+        scored.retain(|&(mv, _)| mv != best_move);
+        scored.insert(0, (best_move, top));
+        let scored: Vec<_> = scored
+            .into_iter()
+            .map(|(mv, score)| {
+                let child = apply_move(board, &mv, self.player_id);
+                (
+                    mv,
+                    score,
+                    super::swindle::complexity(&child, self.player_id),
+                )
+            })
+            .collect();
+        config.pick(&scored).unwrap_or(best_move)
+    }
+
+    /// Follow best moves through the transposition table, stopping at an
+    /// illegal (hash collision) move or a repeated position
+    pub fn extract_pv(&self, board: &Board, max_len: usize) -> Vec<Move> {
+        let tt = self.tt.borrow();
+        let mut pv = Vec::new();
+        let mut seen = Vec::new();
+        let mut board = board.clone();
+        let mut player = self.player_id;
+        while pv.len() < max_len {
+            let hash = ZobristHasher::compute_hash(&board, player);
+            if seen.contains(&hash) {
+                break;
+            }
+            seen.push(hash);
+            let Some((_, Some(mv))) = tt.get(hash) else {
+                break;
+            };
+            if !legal_moves(&board, player).contains(&mv) {
+                break;
+            }
+            board = apply_move(&board, &mv, player);
+            player = player.opponent();
+            pv.push(mv);
+        }
+        pv
+    }
+
+    fn iterative_deepening(
+        &self,
+        board: &Board,
+        max_depth: usize,
+        time_limit: Duration,
+        profile: DepthProfile,
+    ) -> Option<Move> {
         *self.nodes_evaluated.borrow_mut() = 0;
+        *self.counters.borrow_mut() = SearchCounters::default();
         *self.killer_moves.borrow_mut() = [[None; 2]; MAX_PLY];
         let start_time = Instant::now();
+        self.budget.set(Some((start_time, time_limit)));
+        self.aborted.set(false);
 
         let mut best_move = None;
         let alpha = -200000;
         let beta = 200000;
 
-        // Reduced max depth to prevent stack overflow
-        let max_depth = if self.strength == AIStrength::Strong {
-            6
-        } else {
-            4
-        };
-
         let mut final_depth = 0;
         let mut final_score = 0;
         for depth in 1..=max_depth {
@@ -128,8 +528,9 @@ impl AlphaBetaAI {
             // But we should probably clear them between searches (done in search_root start)
             let score = self.negamax(board, depth, alpha, beta, self.player_id, 0);
 
-            // Check time
-            if start_time.elapsed() > self.time_limit {
+            // An iteration cut short by the clock has no result; one that
+            // finished just in time still counts
+            if self.aborted.get() {
                 break;
             }
 
@@ -143,10 +544,13 @@ impl AlphaBetaAI {
                 // Display thinking info
                 if std::env::var("VERBOSE_AI").is_ok() {
                     print!(
-                        " [d={} s={} n={}]",
+                        " [d={}/{} s={} n={} {:?} {}]",
                         depth,
+                        max_depth,
                         score,
-                        self.nodes_evaluated.borrow()
+                        self.nodes_evaluated.borrow(),
+                        profile,
+                        self.search_health()
                     );
                     std::io::Write::flush(&mut std::io::stdout()).ok();
                 }
@@ -158,25 +562,47 @@ impl AlphaBetaAI {
         if best_move.is_none() {
             let legal_moves = legal_moves(board, self.player_id);
             if !legal_moves.is_empty() {
-                best_move = Some(legal_moves[0].clone());
+                best_move = Some(legal_moves[0]);
                 final_depth = 0;
                 final_score = 0;
                 eprintln!("⚠️ AI time limit exceeded, using fallback move");
             }
         }
 
+        self.budget.set(None);
+        self.aborted.set(false);
+
         // Save thinking data
         let elapsed = start_time.elapsed();
-        *self.last_thinking.borrow_mut() = Some((
-            final_depth,
-            final_score,
-            *self.nodes_evaluated.borrow(),
-            elapsed.as_millis(),
-        ));
+        let (tt_probes, tt_hits) = self.tt.borrow().stats();
+        *self.last_thinking.borrow_mut() = Some(SearchInfo {
+            depth: final_depth,
+            score: final_score,
+            nodes: *self.nodes_evaluated.borrow(),
+            time_ms: elapsed.as_millis(),
+            tt_probes,
+            tt_hits,
+            max_depth,
+            profile,
+            health: self.search_health(),
+        });
 
         best_move
     }
 
+    /// Health of the search so far (since the last `iterative_deepening` start)
+    fn search_health(&self) -> SearchHealth {
+        let counters = *self.counters.borrow();
+        let nodes = *self.nodes_evaluated.borrow();
+        let expanded = counters.expanded_nodes.max(1) as f32;
+        SearchHealth {
+            hashfull: self.tt.borrow().hashfull(),
+            qsearch_share: counters.qsearch_nodes as f32 / nodes.max(1) as f32,
+            cut_rate: counters.cutoffs as f32 / expanded,
+            branching_factor: counters.moves_searched as f32 / expanded,
+        }
+    }
+
     // --- Negamax with Alpha-Beta Pruning ---
     fn negamax(
         &self,
@@ -187,6 +613,9 @@ impl AlphaBetaAI {
         current_player: PlayerId,
         ply: usize,
     ) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
         *self.nodes_evaluated.borrow_mut() += 1;
 
         let alpha_orig = alpha;
@@ -196,7 +625,7 @@ impl AlphaBetaAI {
         // Count how many times this position appeared in history
         let rep_count = board.history.iter().filter(|&&h| h == hash).count();
 
-        if rep_count >= 4 {
+        if rep_count >= board.rules.repetition_limit {
             // 4-fold repetition: Return draw score (0)
             // The game loop will handle this as a loss for the player who caused it
             return 0;
@@ -235,8 +664,8 @@ impl AlphaBetaAI {
         let mut moves = legal_moves(board, current_player);
 
         if moves.is_empty() {
-            if in_check {
-                return -200000 + (100 - depth) as i32; // Checkmate
+            if in_check || !board.get_player_config(current_player).stalemate_draws {
+                return -200000 + (100 - depth) as i32; // Checkmate (or a losing stalemate)
             } else {
                 return 0; // Stalemate
             }
@@ -263,6 +692,7 @@ impl AlphaBetaAI {
 
         // Move Ordering
         self.order_moves(board, &mut moves, current_player, ply);
+        self.counters.borrow_mut().expanded_nodes += 1;
 
         let mut best_score = -200000;
         let mut best_move = None;
@@ -305,7 +735,9 @@ impl AlphaBetaAI {
                 // If it's a drop, no SEE needed
             }
 
+            self.counters.borrow_mut().moves_searched += 1;
             let next_board = apply_move(board, mv, current_player);
+            let _made = self.make_move(board, mv, &next_board);
             let mut score;
 
             // PVS & LMR (Only for Strong AI and non-first moves)
@@ -324,7 +756,7 @@ impl AlphaBetaAI {
                 if depth >= 3
                     && moves_searched >= 4
                     && !is_capture
-                    && !is_promote_move.is_some()
+                    && is_promote_move.is_none()
                     && !gives_check
                 {
                     reduction = 1;
@@ -372,13 +804,14 @@ impl AlphaBetaAI {
 
             if score > best_score {
                 best_score = score;
-                best_move = Some(mv.clone());
+                best_move = Some(*mv);
             }
 
             alpha = alpha.max(score);
 
             if alpha >= beta {
                 // Beta Cutoff
+                self.counters.borrow_mut().cutoffs += 1;
                 let is_capture = match mv {
                     Move::Normal { to, .. } => board.get_piece(*to).is_some(),
                     _ => false,
@@ -388,8 +821,8 @@ impl AlphaBetaAI {
                 if !is_capture && ply < MAX_PLY {
                     let mut killers = self.killer_moves.borrow_mut();
                     if killers[ply][0].as_ref() != Some(mv) {
-                        killers[ply][1] = killers[ply][0].clone();
-                        killers[ply][0] = Some(mv.clone());
+                        killers[ply][1] = killers[ply][0];
+                        killers[ply][0] = Some(*mv);
                     }
                 }
 
@@ -397,6 +830,11 @@ impl AlphaBetaAI {
             }
         }
 
+        // The scores of an aborted search are not real
+        if self.aborted.get() {
+            return 0;
+        }
+
         // TT Store
         let bound = if best_score <= alpha_orig {
             Bound::Upper
@@ -413,10 +851,22 @@ impl AlphaBetaAI {
         best_score
     }
 
+    /// Whether the running search has used up its time limit
+    fn out_of_time(&self) -> bool {
+        if !self.aborted.get() {
+            let expired = self
+                .budget
+                .get()
+                .is_some_and(|(start, limit)| start.elapsed() > limit);
+            self.aborted.set(expired);
+        }
+        self.aborted.get()
+    }
+
     fn order_moves(&self, board: &Board, moves: &mut [Move], _player: PlayerId, ply: usize) {
         // Collect killer moves for this ply
         let killers = if ply < MAX_PLY {
-            self.killer_moves.borrow()[ply].clone()
+            self.killer_moves.borrow()[ply]
         } else {
             [None, None]
         };
@@ -434,14 +884,24 @@ impl AlphaBetaAI {
                 if board.get_piece(*to).is_some() {
                     score -= 10000; // Capture bonus
                 }
-                if promote.is_some() {
-                    score -= 5000; // Promote bonus
+                if let Some(kind) = promote {
+                    // Promote bonus; queen before the underpromotions
+                    score -= 5000 + super::eval::piece_val(*kind) / 10;
                 }
             }
             score
         });
     }
 
+    /// Tell the evaluator about a move the search makes; it is unmade when
+    /// the returned guard is dropped
+    fn make_move(&self, parent: &Board, mv: &Move, child: &Board) -> MadeMove<'_> {
+        self.evaluator.borrow_mut().make_move(parent, mv, child);
+        MadeMove {
+            evaluator: &self.evaluator,
+        }
+    }
+
     // --- Quiescence Search ---
     // Searches only captures, promotions, and checks to reach a stable state.
     fn qsearch(&self, board: &Board, alpha: i32, beta: i32, current_player: PlayerId) -> i32 {
@@ -459,9 +919,13 @@ impl AlphaBetaAI {
         const MAX_QSEARCH_DEPTH: usize = 8;
 
         *self.nodes_evaluated.borrow_mut() += 1;
+        self.counters.borrow_mut().qsearch_nodes += 1;
 
         // 1. Stand-pat (Static Evaluation)
-        let eval_score = self.evaluator.borrow_mut().evaluate(board);
+        let eval_score = {
+            let _timer = crate::profile::timer(crate::profile::Subsystem::Eval);
+            self.evaluator.borrow_mut().evaluate(board)
+        };
         let stand_pat = if current_player == PlayerId::Player1 {
             eval_score
         } else {
@@ -481,30 +945,26 @@ impl AlphaBetaAI {
             return alpha;
         }
 
-        // 2. Generate and filter tactical moves
-        let moves = legal_moves(board, current_player);
-        let mut tactical_moves: Vec<_> = moves
+        // 2. Generate and filter tactical moves: captures and promotions,
+        // without building the full legal move list
+        let mut tactical_moves: Vec<Move> = piece_moves(board, current_player)
             .into_iter()
-            .filter(|mv| {
-                match mv {
-                    Move::Normal { to, promote, .. } => {
-                        // Include captures and promotions
-                        board.get_piece(*to).is_some() || promote.is_some()
-                    }
-                    Move::Drop { .. } => {
-                        // Include drops that give check
-                        // This is important for Shogi tactics
-                        if depth < 4 {
-                            // Only check for drops in early QS levels
-                            let next_board = apply_move(board, mv, current_player);
-                            is_in_check(&next_board, current_player.opponent())
-                        } else {
-                            false
-                        }
-                    }
+            .filter(|mv| match mv {
+                // Rook and bishop underpromotions only do what a queen does, minus some
+                Move::Normal { to, promote, .. } => {
+                    !matches!(promote, Some(PieceKind::C_Rook | PieceKind::C_Bishop))
+                        && (board.get_piece(*to).is_some() || promote.is_some())
                 }
+                Move::Drop { .. } => false,
             })
+            .filter(|mv| is_legal(board, mv, current_player))
             .collect();
+        // Plus drops next to the enemy king, and next to our own king when in
+        // check (interpositions). Only in early QS levels to bound the drop
+        // explosion.
+        if depth < 4 && board.get_player_config(current_player).can_drop {
+            tactical_moves.extend(king_drops(board, current_player));
+        }
 
         if tactical_moves.is_empty() {
             return alpha;
@@ -514,6 +974,7 @@ impl AlphaBetaAI {
 
         for mv in tactical_moves {
             let next_board = apply_move(board, &mv, current_player);
+            let _made = self.make_move(board, &mv, &next_board);
             let score = -self.qsearch_depth(
                 &next_board,
                 -beta,
@@ -534,9 +995,84 @@ impl AlphaBetaAI {
     }
 }
 
+/// Legal drops on the squares next to the enemy king, and next to our own
+/// king when in check
+fn king_drops(board: &Board, player: PlayerId) -> Vec<Move> {
+    let Some(hand) = board.hand.get(&player) else {
+        return Vec::new();
+    };
+    let mut kinds: Vec<PieceKind> = hand
+        .iter()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(&kind, _)| kind)
+        .collect();
+    if kinds.is_empty() {
+        return Vec::new();
+    }
+    kinds.sort_unstable();
+
+    let mut kings = vec![board.find_king(player.opponent())];
+    if is_in_check(board, player) {
+        kings.push(board.find_king(player));
+    }
+    let mut squares: Vec<Position> = Vec::new();
+    for king in kings.into_iter().flatten() {
+        for y in king.y.saturating_sub(1)..=(king.y + 1).min(board.height - 1) {
+            for x in king.x.saturating_sub(1)..=(king.x + 1).min(board.width - 1) {
+                let to = Position::new(x, y);
+                if is_adjacent(king, to) && !squares.contains(&to) {
+                    squares.push(to);
+                }
+            }
+        }
+    }
+
+    let mut drops = Vec::new();
+    for to in squares {
+        for &kind in &kinds {
+            let mv = Move::Drop { kind, to };
+            if can_drop_at(board, player, kind, to) && is_legal(board, &mv, player) {
+                drops.push(mv);
+            }
+        }
+    }
+    drops
+}
+
+/// A move the evaluator was told about (see [`Evaluator::make_move`])
+struct MadeMove<'a> {
+    evaluator: &'a RefCell<Box<dyn Evaluator>>,
+}
+
+impl Drop for MadeMove<'_> {
+    fn drop(&mut self) {
+        self.evaluator.borrow_mut().unmake_move();
+    }
+}
+
+/// Whether two squares touch (including diagonally)
+fn is_adjacent(a: Position, b: Position) -> bool {
+    a != b && a.x.abs_diff(b.x) <= 1 && a.y.abs_diff(b.y) <= 1
+}
+
 impl PlayerController for AlphaBetaAI {
     fn choose_move(&self, board: &Board, _moves: &[Move]) -> Option<Move> {
-        self.search_root(board)
+        let temperature = self
+            .opening_randomness
+            .as_ref()
+            .map_or(0.0, |config| config.temperature_at(board.history.len()));
+        let best_move = if temperature > 0.0 {
+            self.choose_move_with_temperature(board, temperature, &mut rand::thread_rng())
+        } else {
+            self.search_root(board)
+        }?;
+        let score = (*self.last_thinking.borrow()).map(|info| info.score);
+        match (&self.swindle, score) {
+            (Some(config), Some(score)) if config.applies(score) => {
+                Some(self.swindle_move(board, best_move, config))
+            }
+            _ => Some(best_move),
+        }
     }
 
     fn name(&self) -> &str {
@@ -546,4 +1082,99 @@ impl PlayerController for AlphaBetaAI {
     fn is_local(&self) -> bool {
         true
     }
+
+    fn last_search(&self, board: &Board) -> Option<AnalysisResult> {
+        let info = (*self.last_thinking.borrow())?;
+        let pv = self.extract_pv(board, info.depth.max(1));
+        Some(AnalysisResult {
+            depth: info.depth,
+            score: info.score,
+            best_move: pv.first().copied(),
+            pv,
+            cached: false,
+            nodes: info.nodes,
+            health: Some(info.health),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::setup::{get_chess_setup, get_shogi_setup, setup_from_strings};
+
+    #[test]
+    fn test_king_drops() {
+        let mut board = setup_from_strings(&get_shogi_setup(), true, true, None, None);
+        board.pieces.retain(|_, p| p.kind == PieceKind::S_King);
+        board.add_to_hand(PlayerId::Player1, PieceKind::S_Gold);
+        let enemy_king = board.find_king(PlayerId::Player2).unwrap();
+
+        let drops = king_drops(&board, PlayerId::Player1);
+        let legal = legal_moves(&board, PlayerId::Player1);
+        assert_eq!(drops.len(), 5);
+        for mv in &drops {
+            let Move::Drop { to, .. } = *mv else {
+                panic!("not a drop: {:?}", mv);
+            };
+            assert!(is_adjacent(enemy_king, to));
+            assert!(legal.contains(mv));
+        }
+    }
+
+    #[test]
+    fn test_depth_profile_classification() {
+        let config = AdaptiveDepthConfig::default();
+
+        // Opening position: neither simplified nor drop-heavy
+        let board = setup_from_strings(&get_shogi_setup(), true, true, None, None);
+        assert_eq!(
+            DepthProfile::classify(&board, &config),
+            DepthProfile::Normal
+        );
+
+        // Many droppable pieces in hand
+        let mut drop_heavy = board.clone();
+        for _ in 0..config.drop_heavy_hand_threshold {
+            drop_heavy.add_to_hand(PlayerId::Player1, PieceKind::S_Pawn);
+        }
+        assert_eq!(
+            DepthProfile::classify(&drop_heavy, &config),
+            DepthProfile::DropHeavy
+        );
+
+        // Bare kings plus a rook: simplified endgame
+        let mut endgame = setup_from_strings(&get_chess_setup(), false, false, None, None);
+        endgame
+            .pieces
+            .retain(|_, p| matches!(p.kind, PieceKind::C_King));
+        endgame.place_piece(
+            Position::new(0, 0),
+            crate::core::Piece::new(PieceKind::C_Rook, PlayerId::Player1),
+        );
+        assert_eq!(
+            DepthProfile::classify(&endgame, &config),
+            DepthProfile::Endgame
+        );
+
+        let (depth, _) = DepthProfile::Endgame.adjust(4, Duration::from_secs(1), &config);
+        assert_eq!(depth, 4 + config.endgame_depth_bonus as usize);
+        let (depth, _) = DepthProfile::DropHeavy.adjust(1, Duration::from_secs(1), &config);
+        assert_eq!(depth, 1);
+    }
+
+    #[test]
+    fn test_search_health_is_recorded() {
+        let mut board = setup_from_strings(&get_shogi_setup(), true, true, None, None);
+        board.zobrist_hash = ZobristHasher::compute_hash(&board, PlayerId::Player1);
+        board.history = vec![board.zobrist_hash];
+        let ai = AlphaBetaAI::new(PlayerId::Player1, "Test", AIStrength::Light, None, true);
+        ai.search_fixed_depth(&board, 2);
+
+        let health = ai.last_thinking.borrow().unwrap().health;
+        assert!(health.qsearch_share > 0.0 && health.qsearch_share < 1.0);
+        assert!(health.cut_rate > 0.0 && health.cut_rate <= 1.0);
+        assert!(health.branching_factor >= 1.0);
+        assert!(health.hashfull <= 1000);
+    }
 }
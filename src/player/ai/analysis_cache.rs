@@ -0,0 +1,142 @@
+//! Persistent analysis cache
+//!
+//! A distilled copy of the transposition table (Zobrist hash -> depth, score,
+//! bound, best move and, for analysed roots, the PV) that is saved to disk per
+//! position set. Loading it before a search lets a deeper re-analysis reuse
+//! the earlier results instead of starting from an empty table.
+
+use super::tt::Bound;
+use crate::core::Move;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Bumped whenever hashing or the search score scale changes incompatibly
+pub const CACHE_VERSION: u32 = 1;
+
+/// Only transposition table entries at least this deep are kept
+pub const DISTILL_MIN_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedEntry {
+    pub depth: usize,
+    /// Score for the side to move
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+    /// Principal variation, stored for analysed root positions only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pv: Vec<Move>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AnalysisCache {
+    version: u32,
+    entries: HashMap<u64, CachedEntry>,
+}
+
+impl Default for AnalysisCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalysisCache {
+    pub fn new() -> Self {
+        Self {
+            version: CACHE_VERSION,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Load a cache file. A missing file gives an empty cache; a file from an
+    /// incompatible version is ignored with a warning.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        // A cache cut off by a crash is only lost work: start over
+        let Some(cache) = crate::util::read_json_or_skip::<Self>(path)? else {
+            return Ok(Self::new());
+        };
+        if cache.version != CACHE_VERSION {
+            eprintln!(
+                "Ignoring analysis cache {} (version {}, expected {})",
+                path.display(),
+                cache.version,
+                CACHE_VERSION
+            );
+            return Ok(Self::new());
+        }
+        Ok(cache)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        crate::util::atomic_write_json(path, self, false)
+    }
+
+    pub fn get(&self, hash: u64) -> Option<&CachedEntry> {
+        self.entries.get(&hash)
+    }
+
+    /// Keep the deeper of the existing and new entry. At equal depth the new
+    /// one wins, unless it would drop a PV.
+    pub fn insert(&mut self, hash: u64, entry: CachedEntry) {
+        match self.entries.get(&hash) {
+            Some(old)
+                if old.depth > entry.depth
+                    || (old.depth == entry.depth && entry.pv.is_empty() && !old.pv.is_empty()) => {}
+            _ => {
+                self.entries.insert(hash, entry);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &CachedEntry)> + '_ {
+        self.entries.iter().map(|(&hash, entry)| (hash, entry))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(depth: usize, score: i32) -> CachedEntry {
+        CachedEntry {
+            depth,
+            score,
+            bound: Bound::Exact,
+            best_move: None,
+            pv: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_keeps_deeper_entry_and_roundtrips() {
+        let mut cache = AnalysisCache::new();
+        cache.insert(1, entry(4, 10));
+        cache.insert(1, entry(3, 20));
+        assert_eq!(cache.get(1).unwrap().score, 10);
+        cache.insert(1, entry(5, 30));
+        assert_eq!(cache.get(1).unwrap().score, 30);
+
+        let path = std::env::temp_dir().join(format!("aho_cache_{}.json", std::process::id()));
+        cache.save(&path).unwrap();
+        let loaded = AnalysisCache::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get(1).unwrap().depth, 5);
+        assert!(AnalysisCache::load(&path).unwrap().is_empty());
+    }
+}
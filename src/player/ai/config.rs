@@ -1,3 +1,4 @@
+use super::AIStrength;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -9,6 +10,14 @@ pub struct AIConfig {
     pub search: SearchConfig,
     #[serde(default)]
     pub resignation: ResignationConfig,
+    #[serde(default)]
+    pub opening_randomness: OpeningRandomnessConfig,
+    #[serde(default)]
+    pub adaptive_difficulty: AdaptiveDifficultyConfig,
+    #[serde(default)]
+    pub swindle: SwindleConfig,
+    #[serde(default)]
+    pub hints: HintConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +29,87 @@ pub struct EvaluationConfig {
     pub evaluator_type: String,
     #[serde(default)]
     pub nn_model_path: Option<String>,
+    #[serde(default)]
+    pub nn_batch: NNBatchConfig,
+    #[serde(default)]
+    pub nn_runtime: NNRuntimeConfig,
+}
+
+/// ONNX Runtime session settings for the NeuralNetwork evaluator
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NNRuntimeConfig {
+    #[serde(default)]
+    pub execution_provider: ExecutionProviderKind,
+    /// Threads used inside an operator; 0 lets ONNX Runtime decide
+    #[serde(default)]
+    pub intra_threads: usize,
+    /// Threads running independent operators; 0 lets ONNX Runtime decide
+    #[serde(default)]
+    pub inter_threads: usize,
+}
+
+/// Hardware the ONNX session runs on. Accelerators that are unavailable
+/// (not compiled in, no driver, unsupported platform) fall back to the CPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ExecutionProviderKind {
+    /// The first available of CUDA, DirectML and CoreML
+    #[default]
+    Auto,
+    #[serde(rename = "CPU", alias = "cpu")]
+    Cpu,
+    #[serde(rename = "CUDA", alias = "cuda")]
+    Cuda,
+    #[serde(alias = "directml")]
+    DirectML,
+    #[serde(alias = "coreml")]
+    CoreML,
+}
+
+impl ExecutionProviderKind {
+    /// Accelerators to try, in order, before falling back to the CPU
+    pub fn candidates(self) -> &'static [ExecutionProviderKind] {
+        match self {
+            ExecutionProviderKind::Auto => &[
+                ExecutionProviderKind::Cuda,
+                ExecutionProviderKind::DirectML,
+                ExecutionProviderKind::CoreML,
+            ],
+            ExecutionProviderKind::Cpu => &[],
+            ExecutionProviderKind::Cuda => &[ExecutionProviderKind::Cuda],
+            ExecutionProviderKind::DirectML => &[ExecutionProviderKind::DirectML],
+            ExecutionProviderKind::CoreML => &[ExecutionProviderKind::CoreML],
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExecutionProviderKind::Auto => "Auto",
+            ExecutionProviderKind::Cpu => "CPU",
+            ExecutionProviderKind::Cuda => "CUDA",
+            ExecutionProviderKind::DirectML => "DirectML",
+            ExecutionProviderKind::CoreML => "CoreML",
+        }
+    }
+}
+
+/// Batching of NeuralNetwork evaluations from concurrent searches sharing a
+/// model (see `ml::batch`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NNBatchConfig {
+    /// Most positions run through the model in one call
+    pub max_batch_size: usize,
+    /// How long a batch waits for more positions before running; 0 runs
+    /// whatever is queued at once, so a lone search never waits
+    pub max_wait_micros: u64,
+}
+
+impl Default for NNBatchConfig {
+    fn default() -> Self {
+        NNBatchConfig {
+            max_batch_size: 256,
+            max_wait_micros: 0,
+        }
+    }
 }
 
 fn default_evaluator_type() -> String {
@@ -30,6 +120,25 @@ fn default_evaluator_type() -> String {
 pub struct SearchConfig {
     pub max_depth_light: u8,
     pub max_depth_strong: u8,
+    #[serde(default)]
+    pub adaptive_light: AdaptiveDepthConfig,
+    #[serde(default)]
+    pub adaptive_strong: AdaptiveDepthConfig,
+}
+
+/// Per-preset rules for stretching or shrinking the nominal search budget
+/// depending on how simplified or drop-heavy the position is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveDepthConfig {
+    pub enabled: bool,
+    /// Non-king pieces (board + droppable hand) at or below which the position is a simplified endgame
+    pub endgame_piece_threshold: usize,
+    pub endgame_depth_bonus: u8,
+    pub endgame_time_multiplier: f64,
+    /// Droppable hand pieces (both players) at or above which the position is drop-heavy
+    pub drop_heavy_hand_threshold: usize,
+    pub drop_heavy_depth_penalty: u8,
+    pub drop_heavy_time_multiplier: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +148,75 @@ pub struct ResignationConfig {
     pub min_depth: u8,
 }
 
+/// Root-move randomness for AIs playing against a human. The AI samples
+/// among its best root moves with a softmax temperature that starts at
+/// `initial_temperature` and decays with the move number, so practice games
+/// vary early while the engine plays precisely later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpeningRandomnessConfig {
+    pub enabled: bool,
+    /// Softmax temperature in centipawns at the first ply
+    pub initial_temperature: f64,
+    pub decay: DecayCurve,
+    /// Linear/Step: ply at which randomness ends. Exponential: half-life in plies
+    pub decay_plies: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DecayCurve {
+    Linear,
+    Exponential,
+    Step,
+}
+
+/// "Adaptive" difficulty against a human. The AI's search depth moves within
+/// `min_depth..=max_depth`: down when the human blundered often or took long
+/// over their recent moves, up when they made no blunders and played quickly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveDifficultyConfig {
+    pub min_depth: u8,
+    pub max_depth: u8,
+    pub initial_depth: u8,
+    /// Time limit per AI move
+    pub time_limit_secs: f64,
+    /// Human moves observed before each adjustment
+    pub window: usize,
+    /// Gain for the AI over one human move that counts as a blunder
+    pub blunder_threshold_centipawns: i32,
+    /// Blunder rate above which the AI gets weaker
+    pub max_blunder_rate: f64,
+    /// Blunder rate at or below which the AI may get stronger
+    pub min_blunder_rate: f64,
+    /// Average thinking time (seconds) above which the human is struggling;
+    /// the AI only gets stronger below half of it
+    pub slow_move_secs: f64,
+}
+
+/// "Swindle mode" for AIs in lost positions (see [`crate::player::ai::swindle`]).
+/// Below `threshold_centipawns` the AI picks, among root moves within
+/// `margin_centipawns` of the best, the one with the highest score plus
+/// complexity bonus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwindleConfig {
+    pub enabled: bool,
+    /// Search score (for the AI) at or below which the AI swindles
+    pub threshold_centipawns: i32,
+    /// Evaluation the AI may give up for a more complicated position
+    pub margin_centipawns: i32,
+    /// Bonus per move of mobility the AI has over the opponent
+    pub mobility_weight: i32,
+    /// Bonus per piece of either side under attack
+    pub tension_weight: i32,
+}
+
+/// Suggested moves a human can ask for with `h` during play, from a Light
+/// search for their side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HintConfig {
+    /// Hints per game and player; 0 turns hints off
+    pub max_per_game: usize,
+}
+
 // Global config instance - loaded once at startup
 pub static AI_CONFIG: Lazy<AIConfig> = Lazy::new(|| {
     AIConfig::load().unwrap_or_else(|e| {
@@ -62,6 +240,48 @@ impl AIConfig {
     }
 }
 
+impl SearchConfig {
+    /// Nominal iterative-deepening depth for a strength preset
+    pub fn max_depth(&self, strength: AIStrength) -> u8 {
+        match strength {
+            AIStrength::Strong => self.max_depth_strong,
+            AIStrength::Light => self.max_depth_light,
+        }
+    }
+
+    /// Adaptive depth rules for a strength preset
+    pub fn adaptive(&self, strength: AIStrength) -> &AdaptiveDepthConfig {
+        match strength {
+            AIStrength::Strong => &self.adaptive_strong,
+            AIStrength::Light => &self.adaptive_light,
+        }
+    }
+}
+
+impl OpeningRandomnessConfig {
+    /// Below this the temperature is treated as zero (play the best move)
+    const MIN_TEMPERATURE: f64 = 1.0;
+
+    /// Temperature for the move played after `ply` plies, 0 once randomness has ended
+    pub fn temperature_at(&self, ply: usize) -> f64 {
+        if !self.enabled || self.decay_plies == 0 {
+            return 0.0;
+        }
+        let progress = ply as f64 / self.decay_plies as f64;
+        let temperature = match self.decay {
+            DecayCurve::Linear => self.initial_temperature * (1.0 - progress).max(0.0),
+            DecayCurve::Exponential => self.initial_temperature * 0.5f64.powf(progress),
+            DecayCurve::Step if ply < self.decay_plies => self.initial_temperature,
+            DecayCurve::Step => 0.0,
+        };
+        if temperature < Self::MIN_TEMPERATURE {
+            0.0
+        } else {
+            temperature
+        }
+    }
+}
+
 impl Default for AIConfig {
     fn default() -> Self {
         let mut material_values = HashMap::new();
@@ -82,12 +302,50 @@ impl Default for AIConfig {
                 pst_enabled: true,
                 evaluator_type: "Handcrafted".to_string(),
                 nn_model_path: None,
+                nn_batch: NNBatchConfig::default(),
+                nn_runtime: NNRuntimeConfig::default(),
             },
             search: SearchConfig {
                 max_depth_light: 4,
                 max_depth_strong: 6,
+                adaptive_light: AdaptiveDepthConfig::default(),
+                adaptive_strong: AdaptiveDepthConfig::default(),
             },
             resignation: ResignationConfig::default(),
+            opening_randomness: OpeningRandomnessConfig::default(),
+            adaptive_difficulty: AdaptiveDifficultyConfig::default(),
+            swindle: SwindleConfig::default(),
+            hints: HintConfig::default(),
+        }
+    }
+}
+
+impl Default for AdaptiveDepthConfig {
+    fn default() -> Self {
+        AdaptiveDepthConfig {
+            enabled: true,
+            endgame_piece_threshold: 8,
+            endgame_depth_bonus: 2,
+            endgame_time_multiplier: 1.5,
+            drop_heavy_hand_threshold: 8,
+            drop_heavy_depth_penalty: 1,
+            drop_heavy_time_multiplier: 0.75,
+        }
+    }
+}
+
+impl Default for AdaptiveDifficultyConfig {
+    fn default() -> Self {
+        AdaptiveDifficultyConfig {
+            min_depth: 1,
+            max_depth: 6,
+            initial_depth: 3,
+            time_limit_secs: 3.0,
+            window: 6,
+            blunder_threshold_centipawns: 300,
+            max_blunder_rate: 0.34,
+            min_blunder_rate: 0.0,
+            slow_move_secs: 60.0,
         }
     }
 }
@@ -101,3 +359,61 @@ impl Default for ResignationConfig {
         }
     }
 }
+
+impl Default for OpeningRandomnessConfig {
+    fn default() -> Self {
+        OpeningRandomnessConfig {
+            enabled: false,
+            initial_temperature: 200.0,
+            decay: DecayCurve::Linear,
+            decay_plies: 20,
+        }
+    }
+}
+
+impl Default for SwindleConfig {
+    fn default() -> Self {
+        SwindleConfig {
+            enabled: false,
+            threshold_centipawns: -1500,
+            margin_centipawns: 300,
+            mobility_weight: 3,
+            tension_weight: 25,
+        }
+    }
+}
+
+impl Default for HintConfig {
+    fn default() -> Self {
+        HintConfig { max_per_game: 3 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opening_randomness_decay() {
+        let mut config = OpeningRandomnessConfig {
+            enabled: true,
+            initial_temperature: 200.0,
+            decay: DecayCurve::Linear,
+            decay_plies: 20,
+        };
+        assert_eq!(config.temperature_at(0), 200.0);
+        assert_eq!(config.temperature_at(10), 100.0);
+        assert_eq!(config.temperature_at(20), 0.0);
+
+        config.decay = DecayCurve::Exponential;
+        assert_eq!(config.temperature_at(20), 100.0);
+        assert_eq!(config.temperature_at(400), 0.0);
+
+        config.decay = DecayCurve::Step;
+        assert_eq!(config.temperature_at(19), 200.0);
+        assert_eq!(config.temperature_at(20), 0.0);
+
+        config.enabled = false;
+        assert_eq!(config.temperature_at(0), 0.0);
+    }
+}
@@ -9,6 +9,7 @@
 //! 1. **Material Balance**: Sum of values of all pieces on the board.
 //! 2. **Piece-Square Tables (PST)**: Positional bonuses for pieces (e.g., King safety, advancing pawns).
 //! 3. **Hand Material**: Value of captured pieces (drops) with a multiplier bonus.
+//! 4. **Drop Pressure**: Checking drops available next to the enemy king.
 //!
 //! ## Values
 //! - Material values are tuned for a mixed Shogi/Chess environment.
@@ -16,8 +17,10 @@
 
 use super::config::AIConfig;
 use super::evaluator::Evaluator;
-use crate::core::{Board, PieceKind, PlayerId};
+use crate::core::{Board, PieceKind, PlayerId, Position};
 use crate::player::ai::pst::get_pst_value;
+use serde::Serialize;
+use std::collections::HashMap;
 
 /// Handcrafted evaluation function (rule-based)
 pub struct HandcraftedEvaluator;
@@ -32,6 +35,12 @@ impl Evaluator for HandcraftedEvaluator {
     }
 }
 
+impl Default for HandcraftedEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HandcraftedEvaluator {
     pub fn new() -> Self {
         Self
@@ -85,12 +94,14 @@ pub fn piece_val(k: PieceKind) -> i32 {
         PieceKind::S_ProSilver => VAL_PRO_SILVER,
         PieceKind::S_ProBishop => VAL_PRO_BISHOP,
         PieceKind::S_ProRook => VAL_PRO_ROOK,
+
+        PieceKind::Custom(i) => crate::core::fairy::def(i).map_or(0, |def| def.value),
     }
 }
 
 /// Game phase for phase-dependent evaluation
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum GamePhase {
+pub enum GamePhase {
     Opening, // Most pieces alive
     Midgame, // Normal play
     Endgame, // Few pieces left
@@ -106,9 +117,12 @@ fn count_total_material(board: &Board) -> i32 {
 }
 
 /// Detect current game phase based on material
-fn detect_game_phase(board: &Board) -> GamePhase {
-    let total_material = count_total_material(board);
+pub fn detect_game_phase(board: &Board) -> GamePhase {
+    phase_for_material(count_total_material(board))
+}
 
+/// Game phase for the total material on the board (both players)
+pub fn phase_for_material(total_material: i32) -> GamePhase {
     // Thresholds tuned for 9x9 board with mixed pieces
     if total_material > 8000 {
         GamePhase::Opening
@@ -150,6 +164,12 @@ fn calculate_mobility(board: &Board, player: PlayerId) -> i32 {
     (weighted_mobility * 2).min(200)
 }
 
+const PENALTY_DOUBLED: i32 = 20;
+const PENALTY_ISOLATED: i32 = 20;
+const BONUS_PASSED_PAWN: i32 = 50;
+const BONUS_BISHOP_PAIR: i32 = 30;
+const BONUS_ROOK_OPEN_FILE: i32 = 40;
+
 /// Evaluates the current board state and returns a score from Player1's perspective.
 ///
 /// Positive score indicates Player1 advantage.
@@ -177,9 +197,7 @@ pub fn evaluate(board: &Board) -> i32 {
     for (&pos, piece) in &board.pieces {
         let mat = piece_val(piece.kind);
 
-        // Calculate PST index (y * 9 + x) assuming 9x9 board and 0-indexed Position
-        let idx = (pos.y * 9) + pos.x;
-        let pst = get_pst_value(piece.kind, idx, piece.owner);
+        let pst = get_pst_value(piece.kind, pos, board.width, board.height, piece.owner);
 
         if piece.owner == PlayerId::Player1 {
             score += mat + pst;
@@ -202,9 +220,6 @@ pub fn evaluate(board: &Board) -> i32 {
     }
 
     // Pawn Structure (Doubled & Isolated)
-    const PENALTY_DOUBLED: i32 = 20;
-    const PENALTY_ISOLATED: i32 = 20;
-
     for x in 0..board.width {
         // Player 1
         if p1_pawn_cols[x] > 1 {
@@ -247,7 +262,15 @@ pub fn evaluate(board: &Board) -> i32 {
         score -= enhanced_king_safety(board, kpos, PlayerId::Player2, phase);
     }
 
-    // 2. Hand Material
+    // 3. Drop Pressure (hand pieces that could be dropped with check next to the enemy king)
+    if let Some(kpos) = p2_king {
+        score += drop_pressure(board, kpos, PlayerId::Player1);
+    }
+    if let Some(kpos) = p1_king {
+        score -= drop_pressure(board, kpos, PlayerId::Player2);
+    }
+
+    // 4. Hand Material
     // Evaluation favors having pieces in hand slightly more than raw material
     // to account for drop flexibility.
 
@@ -323,7 +346,7 @@ fn calc_king_safety(
             if let Some(piece) = board.get_piece(npos) {
                 if piece.owner == owner {
                     // Bonus for defenders
-                    safety += match piece.kind {
+                    safety += match piece.kind.builtin() {
                         PieceKind::S_Gold
                         | PieceKind::S_Silver
                         | PieceKind::S_ProPawn
@@ -409,7 +432,7 @@ fn count_enemy_attackers(board: &Board, king_pos: crate::core::Position, owner:
 
                 if let Some(piece) = board.get_piece(npos) {
                     if piece.owner != owner {
-                        attackers += match piece.kind {
+                        attackers += match piece.kind.builtin() {
                             PieceKind::S_Rook
                             | PieceKind::S_ProRook
                             | PieceKind::C_Rook
@@ -449,20 +472,92 @@ fn enhanced_king_safety(
     safety
 }
 
+/// Bonus for a hand piece that can be dropped next to the enemy king with check.
+/// Golds and silvers are the classic mating drops.
+fn drop_threat_weight(kind: PieceKind) -> i32 {
+    match kind.builtin() {
+        PieceKind::S_Gold => 40,
+        PieceKind::S_Silver => 30,
+        PieceKind::S_Rook => 30,
+        PieceKind::S_Bishop => 20,
+        PieceKind::S_Lance => 15,
+        PieceKind::S_Pawn => 10,
+        _ => 0,
+    }
+}
+
+/// Estimate drop pressure against the enemy king.
+///
+/// For each empty square adjacent to the king, takes the strongest piece in
+/// `attacker`'s hand that would give check when dropped there.
+fn drop_pressure(board: &Board, king_pos: crate::core::Position, attacker: PlayerId) -> i32 {
+    if !board.get_player_config(attacker).can_drop {
+        return 0;
+    }
+    let Some(hand) = board.hand.get(&attacker) else {
+        return 0;
+    };
+
+    // Strongest checking drop per direction from the drop square back to the
+    // king, indexed by (dy + 1) * 3 + (dx + 1); each hand kind is looked at once
+    let mut best = [0i32; 9];
+    for (&kind, _) in hand.iter().filter(|(_, &count)| count > 0) {
+        let weight = drop_threat_weight(kind);
+        for step in crate::core::Piece::new(kind, attacker).movement_rules() {
+            let (sx, sy) = match step {
+                crate::core::MoveStep::Step(sx, sy) | crate::core::MoveStep::Slide(sx, sy) => {
+                    (sx, sy)
+                }
+            };
+            if sx.abs() <= 1 && sy.abs() <= 1 {
+                let dir = ((sy + 1) * 3 + (sx + 1)) as usize;
+                best[dir] = best[dir].max(weight);
+            }
+        }
+    }
+
+    let mut pressure = 0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+
+            let nx = king_pos.x as i32 + dx;
+            let ny = king_pos.y as i32 + dy;
+            if nx < 0 || nx >= board.width as i32 || ny < 0 || ny >= board.height as i32 {
+                continue;
+            }
+            let npos = crate::core::Position {
+                x: nx as usize,
+                y: ny as usize,
+            };
+            if board.get_piece(npos).is_some() {
+                continue;
+            }
+
+            // Direction from the drop square back to the king
+            pressure += best[((1 - dy) * 3 + (1 - dx)) as usize];
+        }
+    }
+
+    pressure
+}
+
 /// Detect tactical patterns and return bonus score
 fn detect_tactical_patterns(board: &Board, player: PlayerId) -> i32 {
     let mut bonus = 0;
 
     // 1. Passed pawns
-    bonus += count_passed_pawns(board, player) * 50;
+    bonus += count_passed_pawns(board, player) * BONUS_PASSED_PAWN;
 
     // 2. Bishop pair
     if has_bishop_pair(board, player) {
-        bonus += 30;
+        bonus += BONUS_BISHOP_PAIR;
     }
 
     // 3. Rooks on open files
-    bonus += count_rooks_on_open_files(board, player) * 40;
+    bonus += count_rooks_on_open_files(board, player) * BONUS_ROOK_OPEN_FILE;
 
     bonus
 }
@@ -470,38 +565,40 @@ fn detect_tactical_patterns(board: &Board, player: PlayerId) -> i32 {
 /// Count passed pawns (simplified: no enemy pawns ahead in column)
 fn count_passed_pawns(board: &Board, player: PlayerId) -> i32 {
     let mut passed = 0;
-    let forward_dir = if player == PlayerId::Player1 { -1 } else { 1 };
 
     for (&pos, piece) in &board.pieces {
-        if piece.owner == player && matches!(piece.kind, PieceKind::S_Pawn | PieceKind::C_Pawn) {
-            let mut is_passed = true;
-
-            // Check ahead in this column for enemy pawns
-            let mut check_y = pos.y as i32 + forward_dir;
-            while check_y >= 0 && check_y < 9 {
-                let check_pos = crate::core::Position {
-                    x: pos.x,
-                    y: check_y as usize,
-                };
+        if piece.owner == player
+            && matches!(piece.kind, PieceKind::S_Pawn | PieceKind::C_Pawn)
+            && is_passed_pawn(board, pos, player)
+        {
+            passed += 1;
+        }
+    }
 
-                if let Some(p) = board.get_piece(check_pos) {
-                    if p.owner != player && matches!(p.kind, PieceKind::S_Pawn | PieceKind::C_Pawn)
-                    {
-                        is_passed = false;
-                        break;
-                    }
-                }
+    passed
+}
 
-                check_y += forward_dir;
-            }
+/// No enemy pawn ahead of `player`'s pawn at `pos` in its column
+fn is_passed_pawn(board: &Board, pos: Position, player: PlayerId) -> bool {
+    let forward_dir = if player == PlayerId::Player1 { -1 } else { 1 };
 
-            if is_passed {
-                passed += 1;
+    // Check ahead in this column for enemy pawns
+    let mut check_y = pos.y as i32 + forward_dir;
+    while (0..board.height as i32).contains(&check_y) {
+        let check_pos = crate::core::Position {
+            x: pos.x,
+            y: check_y as usize,
+        };
+
+        if let Some(p) = board.get_piece(check_pos) {
+            if p.owner != player && matches!(p.kind, PieceKind::S_Pawn | PieceKind::C_Pawn) {
+                return false;
             }
         }
-    }
 
-    passed
+        check_y += forward_dir;
+    }
+    true
 }
 
 /// Check if player has bishop pair
@@ -526,59 +623,256 @@ fn count_rooks_on_open_files(board: &Board, player: PlayerId) -> i32 {
     let mut rooks_on_open = 0;
 
     for (&pos, piece) in &board.pieces {
-        if piece.owner == player
-            && matches!(
-                piece.kind,
-                PieceKind::S_Rook | PieceKind::C_Rook | PieceKind::S_ProRook
-            )
-        {
-            let mut has_pawn = false;
-            for y in 0..9 {
-                let check_pos = crate::core::Position { x: pos.x, y };
-                if let Some(p) = board.get_piece(check_pos) {
-                    if matches!(p.kind, PieceKind::S_Pawn | PieceKind::C_Pawn) {
-                        has_pawn = true;
-                        break;
-                    }
-                }
-            }
-
-            if !has_pawn {
-                rooks_on_open += 1;
-            }
+        if piece.owner == player && is_rook_on_open_file(board, pos, piece.kind) {
+            rooks_on_open += 1;
         }
     }
 
     rooks_on_open
 }
 
+/// A rook at `pos` with no pawn of either side in its column
+fn is_rook_on_open_file(board: &Board, pos: Position, kind: PieceKind) -> bool {
+    if !matches!(
+        kind,
+        PieceKind::S_Rook | PieceKind::C_Rook | PieceKind::S_ProRook
+    ) {
+        return false;
+    }
+    !(0..board.height).any(|y| {
+        board
+            .get_piece(crate::core::Position { x: pos.x, y })
+            .is_some_and(|p| matches!(p.kind, PieceKind::S_Pawn | PieceKind::C_Pawn))
+    })
+}
+
 /// Penalize undeveloped pieces in opening
 fn development_score(board: &Board, player: PlayerId, phase: GamePhase) -> i32 {
     if !matches!(phase, GamePhase::Opening) {
         return 0; // Only active in opening
     }
 
-    let mut undeveloped = 0;
-    let start_rank = if player == PlayerId::Player1 { 8 } else { 0 };
+    let undeveloped = board
+        .pieces
+        .iter()
+        .filter(|(&pos, piece)| piece.owner == player && is_undeveloped(board, pos, piece))
+        .count() as i32;
+
+    -undeveloped * DEVELOPMENT_PENALTY
+}
+
+/// Small penalty per undeveloped piece
+const DEVELOPMENT_PENALTY: i32 = 10;
+
+/// A major piece still on its owner's back rank
+fn is_undeveloped(board: &Board, pos: Position, piece: &crate::core::Piece) -> bool {
+    let start_rank = if piece.owner == PlayerId::Player1 {
+        board.height - 1
+    } else {
+        0
+    };
+    // Major pieces should be developed
+    pos.y == start_rank
+        && matches!(
+            piece.kind,
+            PieceKind::S_Bishop
+                | PieceKind::C_Bishop
+                | PieceKind::S_Rook
+                | PieceKind::C_Rook
+                | PieceKind::C_Knight
+                | PieceKind::S_Knight
+        )
+}
+
+/// What the evaluator credits to one piece on the board, from its owner's
+/// point of view (positive = good for the owner)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PieceContribution {
+    pub owner: PlayerId,
+    pub material: i32,
+    pub pst: i32,
+    /// Doubled / isolated pawn penalties
+    pub pawn_structure: i32,
+    /// Passed pawn, bishop pair, rook on an open file
+    pub tactical: i32,
+    /// Penalty for a major piece left on the back rank in the opening
+    pub development: i32,
+    /// The king's whole safety term
+    pub king_safety: i32,
+}
+
+impl PieceContribution {
+    pub fn total(&self) -> i32 {
+        self.material + self.positional()
+    }
+
+    /// Everything but the material value: the evaluator's opinion of where
+    /// the piece stands
+    pub fn positional(&self) -> i32 {
+        self.pst + self.pawn_structure + self.tactical + self.development + self.king_safety
+    }
+}
+
+/// `evaluate` split into per-square contributions plus the terms that belong
+/// to no single piece. Those are from Player1's perspective, like `total`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvalBreakdown {
+    #[serde(serialize_with = "serialize_squares")]
+    pub squares: HashMap<Position, PieceContribution>,
+    pub hand: i32,
+    pub drop_pressure: i32,
+    pub mobility: i32,
+    /// Equal to `evaluate(board)`
+    pub total: i32,
+}
+
+fn serialize_squares<S: serde::Serializer>(
+    squares: &HashMap<Position, PieceContribution>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeMap;
+    let mut sorted: Vec<_> = squares.iter().collect();
+    sorted.sort_by_key(|(pos, _)| (pos.y, pos.x));
+    let mut map = serializer.serialize_map(Some(sorted.len()))?;
+    for (pos, contribution) in sorted {
+        map.serialize_entry(&format!("{},{}", pos.x, pos.y), contribution)?;
+    }
+    map.end()
+}
+
+impl EvalBreakdown {
+    /// Positional value of every piece from its owner's point of view, the
+    /// number a heatmap colors its square by
+    pub fn positional_map(&self) -> HashMap<Position, i32> {
+        self.squares
+            .iter()
+            .map(|(&pos, c)| (pos, c.positional()))
+            .collect()
+    }
+}
+
+/// Break the handcrafted evaluation of `board` down by piece. Slower than
+/// `evaluate`; meant for analysis and teaching views, not the search.
+pub fn eval_breakdown(board: &Board) -> EvalBreakdown {
+    let hand_multiplier = AIConfig::get().evaluation.hand_piece_bonus_multiplier as f32;
+    let phase = detect_game_phase(board);
+    let is_pawn = |kind: PieceKind| matches!(kind, PieceKind::S_Pawn | PieceKind::C_Pawn);
+    let mut breakdown = EvalBreakdown::default();
+    let mut kings: HashMap<PlayerId, Position> = HashMap::new();
+    let mut bishops: HashMap<PlayerId, Vec<Position>> = HashMap::new();
+    let mut pawn_files: HashMap<(PlayerId, usize), Vec<Position>> = HashMap::new();
 
+    // Same iteration as `evaluate`, so the same king is picked if a side has several
     for (&pos, piece) in &board.pieces {
-        if piece.owner == player && pos.y == start_rank {
-            // Major pieces should be developed
-            if matches!(
-                piece.kind,
-                PieceKind::S_Bishop
-                    | PieceKind::C_Bishop
-                    | PieceKind::S_Rook
-                    | PieceKind::C_Rook
-                    | PieceKind::C_Knight
-                    | PieceKind::S_Knight
-            ) {
-                undeveloped += 1;
+        let mut contribution = PieceContribution {
+            owner: piece.owner,
+            material: piece_val(piece.kind),
+            pst: get_pst_value(piece.kind, pos, board.width, board.height, piece.owner),
+            pawn_structure: 0,
+            tactical: 0,
+            development: 0,
+            king_safety: 0,
+        };
+        match piece.kind {
+            PieceKind::S_King | PieceKind::C_King => {
+                kings.insert(piece.owner, pos);
+            }
+            PieceKind::S_Bishop | PieceKind::C_Bishop => {
+                bishops.entry(piece.owner).or_default().push(pos);
+            }
+            kind if is_pawn(kind) => {
+                pawn_files
+                    .entry((piece.owner, pos.x))
+                    .or_default()
+                    .push(pos);
+                if is_passed_pawn(board, pos, piece.owner) {
+                    contribution.tactical += BONUS_PASSED_PAWN;
+                }
+            }
+            _ => {}
+        }
+        if is_rook_on_open_file(board, pos, piece.kind) {
+            contribution.tactical += BONUS_ROOK_OPEN_FILE;
+        }
+        if phase == GamePhase::Opening && is_undeveloped(board, pos, piece) {
+            contribution.development -= DEVELOPMENT_PENALTY;
+        }
+        breakdown.squares.insert(pos, contribution);
+    }
+
+    // Pawn structure: the topmost pawn of a file carries the isolated
+    // penalty, each extra pawn on it the doubled one
+    for (&(owner, x), pawns) in &mut pawn_files {
+        pawns.sort_by_key(|pos| pos.y);
+        let has_neighbour = |nx: Option<usize>| {
+            nx.is_some_and(|nx| nx < board.width && has_pawn_in_file(board, owner, nx))
+        };
+        if !has_neighbour(x.checked_sub(1)) && !has_neighbour(Some(x + 1)) {
+            if let Some(c) = breakdown.squares.get_mut(&pawns[0]) {
+                c.pawn_structure -= PENALTY_ISOLATED;
+            }
+        }
+        for pos in &pawns[1..] {
+            if let Some(c) = breakdown.squares.get_mut(pos) {
+                c.pawn_structure -= PENALTY_DOUBLED;
             }
         }
     }
 
-    -undeveloped * 10 // Small penalty per undeveloped piece
+    // The bishop pair bonus is shared by the first two bishops
+    for positions in bishops.values().filter(|b| b.len() >= 2) {
+        let half = BONUS_BISHOP_PAIR / 2;
+        for (i, pos) in positions.iter().take(2).enumerate() {
+            if let Some(c) = breakdown.squares.get_mut(pos) {
+                c.tactical += if i == 0 {
+                    BONUS_BISHOP_PAIR - half
+                } else {
+                    half
+                };
+            }
+        }
+    }
+
+    for (&owner, &kpos) in &kings {
+        if let Some(c) = breakdown.squares.get_mut(&kpos) {
+            c.king_safety = enhanced_king_safety(board, kpos, owner, phase);
+        }
+    }
+
+    let sign = |owner: PlayerId| if owner == PlayerId::Player1 { 1 } else { -1 };
+    for owner in [PlayerId::Player1, PlayerId::Player2] {
+        if let Some(&enemy_king) = kings.get(&owner.opponent()) {
+            breakdown.drop_pressure += sign(owner) * drop_pressure(board, enemy_king, owner);
+        }
+        if let Some(hand) = board.hand.get(&owner) {
+            for (kind, &count) in hand {
+                if count > 0 {
+                    let val = (piece_val(*kind) as f32 * hand_multiplier) as i32;
+                    breakdown.hand += sign(owner) * val * count as i32;
+                }
+            }
+        }
+        breakdown.mobility += sign(owner) * calculate_mobility(board, owner);
+    }
+
+    breakdown.total = breakdown
+        .squares
+        .values()
+        .map(|c| sign(c.owner) * c.total())
+        .sum::<i32>()
+        + breakdown.hand
+        + breakdown.drop_pressure
+        + breakdown.mobility;
+    breakdown
+}
+
+/// Whether `owner` has a pawn in column `x`
+fn has_pawn_in_file(board: &Board, owner: PlayerId, x: usize) -> bool {
+    board.pieces.iter().any(|(pos, piece)| {
+        pos.x == x
+            && piece.owner == owner
+            && matches!(piece.kind, PieceKind::S_Pawn | PieceKind::C_Pawn)
+    })
 }
 
 #[cfg(test)]
@@ -593,6 +887,40 @@ mod tests {
         assert!(piece_val(PieceKind::C_Queen) > piece_val(PieceKind::S_Rook));
     }
 
+    #[test]
+    fn test_other_board_sizes() {
+        use crate::core::{Piece, Position};
+        use crate::player::ai::pst::get_pst_value;
+
+        // Home and last ranks of an 8x8 board use the tables' home and last ranks
+        let pawn = PieceKind::C_Pawn;
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        for (x8, x9) in [(0, 0), (7, 8)] {
+            assert_eq!(
+                get_pst_value(pawn, Position::new(x8, 6), 8, 8, p1),
+                get_pst_value(pawn, Position::new(x9, 7), 9, 9, p1)
+            );
+            assert_eq!(
+                get_pst_value(pawn, Position::new(x8, 1), 8, 8, p2),
+                get_pst_value(pawn, Position::new(x9, 1), 9, 9, p2)
+            );
+        }
+
+        // A 10x10 board evaluates and hashes without indexing out of bounds
+        let mut board = Board::new(10, 10);
+        board.place_piece(Position::new(9, 9), Piece::new(PieceKind::C_King, p1));
+        board.place_piece(Position::new(0, 0), Piece::new(PieceKind::C_King, p2));
+        board.place_piece(Position::new(9, 1), Piece::new(PieceKind::C_Rook, p1));
+        assert!(evaluate(&board) > 0);
+        let before = crate::logic::ZobristHasher::compute_hash(&board, p1);
+        let rook = board.remove_piece(Position::new(9, 1)).unwrap();
+        board.place_piece(Position::new(9, 2), rook);
+        assert_ne!(
+            crate::logic::ZobristHasher::compute_hash(&board, p1),
+            before
+        );
+    }
+
     #[test]
     fn test_eval_material_balance() {
         let mut board = Board::new(9, 9);
@@ -627,4 +955,66 @@ mod tests {
         let score_balanced = evaluate(&board);
         assert!(score_balanced.abs() < 200); // PST diff is small
     }
+
+    #[test]
+    fn test_drop_pressure() {
+        use crate::core::{Piece, PlayerConfig, Position};
+
+        let mut board = Board::new(9, 9);
+        board.set_player_config(PlayerId::Player1, PlayerConfig::shogi());
+        let king_pos = Position::new(4, 0);
+        board.place_piece(king_pos, Piece::new(PieceKind::S_King, PlayerId::Player2));
+
+        // Nothing in hand: no pressure
+        assert_eq!(drop_pressure(&board, king_pos, PlayerId::Player1), 0);
+
+        // A gold in hand checks from the three squares below the king and both sides
+        board.add_to_hand(PlayerId::Player1, PieceKind::S_Gold);
+        let gold_pressure = drop_pressure(&board, king_pos, PlayerId::Player1);
+        assert_eq!(gold_pressure, 5 * drop_threat_weight(PieceKind::S_Gold));
+
+        // Chess players cannot drop
+        board.set_player_config(PlayerId::Player1, PlayerConfig::chess());
+        assert_eq!(drop_pressure(&board, king_pos, PlayerId::Player1), 0);
+    }
+
+    #[test]
+    fn test_breakdown_sums_to_evaluate() {
+        use crate::core::setup::BoardSetupType;
+
+        let mut boards = vec![Board::new(9, 9)];
+        for setup in [
+            BoardSetupType::ShogiOnly,
+            BoardSetupType::ChessOnly,
+            BoardSetupType::StandardMixed,
+            BoardSetupType::Fair,
+        ] {
+            boards.push(setup.create_board());
+        }
+        // A middlegame-like position with doubled pawns, a passed pawn and a hand
+        let mut board = boards[1].clone();
+        let pawn = board.pieces[&Position::new(2, 6)].clone();
+        board.pieces.remove(&Position::new(3, 6));
+        board.place_piece(Position::new(2, 4), pawn);
+        board
+            .pieces
+            .retain(|pos, p| !(p.owner == PlayerId::Player2 && pos.x == 7 && pos.y == 2));
+        board.add_to_hand(PlayerId::Player1, PieceKind::S_Gold);
+        boards.push(board);
+
+        let doubled = eval_breakdown(&boards[5]);
+        assert_eq!(
+            doubled.squares[&Position::new(2, 6)].pawn_structure,
+            -PENALTY_DOUBLED
+        );
+        assert_eq!(
+            doubled.squares[&Position::new(7, 6)].tactical,
+            BONUS_PASSED_PAWN
+        );
+
+        for board in &boards {
+            let breakdown = eval_breakdown(board);
+            assert_eq!(breakdown.total, evaluate(board));
+        }
+    }
 }
@@ -2,7 +2,7 @@
 //!
 //! Defines a common interface for different evaluation strategies.
 
-use crate::core::Board;
+use crate::core::{Board, Move};
 
 /// Trait for evaluating board positions
 pub trait Evaluator: Send + Sync {
@@ -14,6 +14,23 @@ pub trait Evaluator: Send + Sync {
     ///   - Zero: Equal position
     fn evaluate(&mut self, board: &Board) -> i32;
 
+    /// Evaluate several boards at once, as [`Evaluator::evaluate`] would
+    ///
+    /// Evaluators with batched inference (NeuralNetwork) run them in one call.
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<i32> {
+        boards.iter().map(|board| self.evaluate(board)).collect()
+    }
+
+    /// The search made `mv` in `parent`, reaching `child`, and will evaluate
+    /// positions below `child` until the matching [`Evaluator::unmake_move`].
+    /// Incrementally updated evaluators (NNUE) follow the search path here;
+    /// the others ignore it.
+    fn make_move(&mut self, _parent: &Board, _mv: &Move, _child: &Board) {}
+
+    /// The search went back from the last position passed to
+    /// [`Evaluator::make_move`] to its parent
+    fn unmake_move(&mut self) {}
+
     /// Get evaluator name for debugging
     fn name(&self) -> String;
 }
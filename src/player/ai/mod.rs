@@ -1,9 +1,12 @@
+pub mod adaptive;
 pub mod alpha_beta;
+pub mod analysis_cache;
 pub mod config;
 pub mod eval;
 pub mod evaluator;
 pub mod pst;
 pub mod see;
+pub mod swindle;
 pub mod tt;
 
 pub use alpha_beta::{AIStrength, AlphaBetaAI};
@@ -1,15 +1,13 @@
-use crate::core::{PieceKind, PlayerId};
+use crate::core::{PieceKind, PlayerId, Position};
 
 // Scores are in centipawns (roughly).
 // Perspective: Player 1 (Bottom moving Up).
-// Index 0 is (0,0) -> top-left from white's perspective?
-// Wait, our board is 9x9. (0,0) is usually top-left.
-// Player 1 is at the bottom (rank 8, 9). Moving to rank 0.
-// So:
+// The tables are for a 9x9 board; other sizes are scaled onto them
+// (see `get_pst_value`). (0,0) is the top-left.
 // Rank 0 (Top) is promotion zone for P1.
 // Rank 8 (Bottom) is home base for P1.
 //
-// Board index = y * 9 + x.
+// Table index = y * 9 + x.
 // y=0 is top. y=8 is bottom.
 //
 // So the table should be defined:
@@ -137,19 +135,30 @@ const MG_GENERIC: [i32; 81] = [
     -10, -10, -10, -10, -10, -10, -10, -10, -10, // Rank 8 (Back rank, bad for active pieces)
 ];
 
-pub fn get_pst_value(kind: PieceKind, idx: usize, player: PlayerId) -> i32 {
-    // If Player 2, we need to mirror the index
-    // x = idx % 9
-    // y = idx / 9
-    // P2's y = 8 - y. x is typically also mirrored?
-    // Wait, "Reversed" Mixed map has pieces at top.
-    // If P2 is at top (Camp 0), attacking down (Camp 8).
-    // Our tables are defined for P1 (Camp 8 -> Camp 0).
-    // So for P2:
-    // y_p2 = 8 - y_p1.
-    // x_p2 = 8 - x_p1. (Mirror board completely)
+/// Size of the tables: they are defined for a 9x9 board
+const PST_SIZE: usize = 9;
 
-    let table = match kind {
+/// Row or column `v` of a board `n` squares wide, scaled onto the tables
+/// (home rank to home rank, last rank to last rank)
+fn scale_to_table(v: usize, n: usize) -> usize {
+    if n <= 1 {
+        return 0;
+    }
+    ((v * (PST_SIZE - 1) + (n - 1) / 2) / (n - 1)).min(PST_SIZE - 1)
+}
+
+/// Piece-square bonus of a `kind` at `pos` on a `width` x `height` board.
+///
+/// Boards of other sizes are scaled onto the 9x9 tables, so the home and
+/// last ranks always use the tables' home and last ranks.
+pub fn get_pst_value(
+    kind: PieceKind,
+    pos: Position,
+    width: usize,
+    height: usize,
+    player: PlayerId,
+) -> i32 {
+    let table = match kind.builtin() {
         PieceKind::S_Pawn => &MG_S_PAWN,
         PieceKind::C_Pawn => &MG_C_PAWN,
         PieceKind::S_Lance => &MG_LANCE,
@@ -171,29 +180,19 @@ pub fn get_pst_value(kind: PieceKind, idx: usize, player: PlayerId) -> i32 {
         | PieceKind::S_ProLance
         | PieceKind::S_ProKnight
         | PieceKind::S_ProSilver => &MG_GOLD,
+        PieceKind::Custom(_) => unreachable!("custom kinds use their `like` table"),
     };
 
-    let lookup_idx = if player == PlayerId::Player1 {
-        idx
+    // The tables are for Player1; Player2 sees the board rotated
+    let (x, y) = if player == PlayerId::Player1 {
+        (pos.x, pos.y)
     } else {
-        // Mirror Logic:
-        // P1(0) is Top-Left. P1(80) is Bottom-Right.
-        // P2(0) should be ... ?
-        // If P2 moves DOWN, then Rank 0 is "Home" and Rank 8 is "Enemy Base".
-        // Our table: Rank 0 = Enemy Base, Rank 8 = Home.
-        // So for P2: Rank 0 (Top) is Home.
-        // We need to reverse the rows.
-        // Index i -> (i / 9), (i % 9).
-        // Row r -> 8 - r.
-        // Col c -> 8 - c? (Left/Right symmetry).
-        // Yes, 80 - idx does exactly this (mirrors both axes).
-        80 - idx
+        (
+            width.saturating_sub(1 + pos.x),
+            height.saturating_sub(1 + pos.y),
+        )
     };
-
-    // Boundary check
-    if lookup_idx >= table.len() {
-        return 0;
-    }
+    let lookup_idx = scale_to_table(y, height) * PST_SIZE + scale_to_table(x, width);
 
     table[lookup_idx]
 }
@@ -55,10 +55,8 @@ fn count_attackers(board: &Board, target: Position, attacker: PlayerId) -> usize
     let mut count = 0;
 
     for (&pos, piece) in &board.pieces {
-        if piece.owner == attacker {
-            if can_piece_attack(board, pos, target, piece.kind, attacker) {
-                count += 1;
-            }
+        if piece.owner == attacker && can_piece_attack(board, pos, target, piece.kind, attacker) {
+            count += 1;
         }
     }
 
@@ -97,8 +95,7 @@ fn can_piece_attack(
 
         // Silver: 5 directions (forward, forward-diagonals, back-diagonals)
         PieceKind::S_Silver => {
-            (dx == 0 && dy == forward) || // Forward
-            (dx.abs() == 1 && dy == forward) || // Forward diagonals
+            (dx.abs() <= 1 && dy == forward) || // Forward and forward diagonals
             (dx.abs() == 1 && dy == -forward) // Back diagonals
         }
 
@@ -159,6 +156,18 @@ fn can_piece_attack(
 
         // Chess Knight: L-shape in any direction
         PieceKind::C_Knight => (dx.abs() == 2 && dy.abs() == 1) || (dx.abs() == 1 && dy.abs() == 2),
+
+        // Fairy pieces: their registered steps and slides
+        PieceKind::Custom(_) => crate::core::Piece::new(piece_kind, owner)
+            .movement_rules()
+            .into_iter()
+            .any(|step| match step {
+                crate::core::MoveStep::Step(sx, sy) => (sx, sy) == (dx, dy),
+                crate::core::MoveStep::Slide(sx, sy) => {
+                    let n = dx.abs().max(dy.abs());
+                    (sx * n, sy * n) == (dx, dy) && is_path_clear(board, from, to)
+                }
+            }),
     }
 }
 
@@ -172,7 +181,7 @@ fn is_path_clear(board: &Board, from: Position, to: Position) -> bool {
 
     while x != to.x as i32 || y != to.y as i32 {
         // Check bounds
-        if x < 0 || x >= 9 || y < 0 || y >= 9 {
+        if !(0..9).contains(&x) || !(0..9).contains(&y) {
             return false;
         }
 
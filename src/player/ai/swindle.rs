@@ -0,0 +1,116 @@
+//! Swindle mode: practical chances in lost positions
+//!
+//! Once the search says the AI is clearly losing (see [`SwindleConfig`]), the
+//! objectively best move usually just loses slowly against any competent
+//! opponent. Among the root moves that give up at most a margin of
+//! evaluation, the AI then prefers the one that leaves the opponent the most
+//! complicated position: more mobility for the AI than for the opponent, and
+//! more pieces under attack on both sides.
+
+use super::config::SwindleConfig;
+use crate::core::{Board, Move, PlayerId};
+use crate::logic::{piece_attacks, pseudo_legal_moves};
+
+/// How hard a position is to play for the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Complexity {
+    /// Pseudo-legal moves of the swindler minus those of the opponent
+    pub mobility: i32,
+    /// Pieces of either side attacked by an enemy piece
+    pub tension: i32,
+}
+
+/// Complexity of `board` for the opponent of `swindler`, who just moved
+pub fn complexity(board: &Board, swindler: PlayerId) -> Complexity {
+    let mobility = pseudo_legal_moves(board, swindler).len() as i32
+        - pseudo_legal_moves(board, swindler.opponent()).len() as i32;
+    let tension = board
+        .pieces
+        .iter()
+        .filter(|(&target, piece)| {
+            board.pieces.iter().any(|(&from, attacker)| {
+                attacker.owner != piece.owner && piece_attacks(board, from, attacker, target)
+            })
+        })
+        .count() as i32;
+    Complexity { mobility, tension }
+}
+
+impl SwindleConfig {
+    /// Whether a search score (for the side to move) calls for swindling
+    pub fn applies(&self, score: i32) -> bool {
+        self.enabled && score <= self.threshold_centipawns
+    }
+
+    /// Centipawns a position's complexity is worth to the swindler
+    pub fn bonus(&self, complexity: Complexity) -> i32 {
+        self.mobility_weight * complexity.mobility + self.tension_weight * complexity.tension
+    }
+
+    /// The swindle among `scored` root moves (score for the swindler, best
+    /// first): the highest score plus complexity bonus among the moves within
+    /// the margin of the best. Ties go to the better-scored move.
+    pub fn pick(&self, scored: &[(Move, i32, Complexity)]) -> Option<Move> {
+        let best = scored.first()?.1;
+        scored
+            .iter()
+            .filter(|&&(_, score, _)| score >= best.saturating_sub(self.margin_centipawns))
+            .enumerate()
+            .max_by_key(|&(i, &(_, score, complexity))| {
+                (score + self.bonus(complexity), std::cmp::Reverse(i))
+            })
+            .map(|(_, &(mv, _, _))| mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Piece, PieceKind, Position};
+
+    #[test]
+    fn test_swindle_prefers_complex_moves_within_margin() {
+        let mut board = Board::new(9, 9);
+        let (p1, p2) = (PlayerId::Player1, PlayerId::Player2);
+        board.place_piece(Position::new(4, 4), Piece::new(PieceKind::S_Rook, p1));
+        board.place_piece(Position::new(4, 0), Piece::new(PieceKind::S_Gold, p2));
+        board.place_piece(Position::new(0, 0), Piece::new(PieceKind::S_Pawn, p2));
+        let c = complexity(&board, p1);
+        // The rook attacks the gold; it has 19 moves (promotions included)
+        // to the gold's 5 and the pawn's 1
+        assert_eq!(
+            c,
+            Complexity {
+                mobility: 13,
+                tension: 1
+            }
+        );
+
+        let config = SwindleConfig {
+            enabled: true,
+            ..SwindleConfig::default()
+        };
+        assert!(config.applies(config.threshold_centipawns));
+        assert!(!config.applies(0));
+
+        let mv = |x| Move::Normal {
+            from: Position::new(x, 8),
+            to: Position::new(x, 7),
+            promote: None,
+        };
+        let calm = Complexity::default();
+        let sharp = Complexity {
+            mobility: 20,
+            tension: 12,
+        };
+        assert!(config.bonus(sharp) > config.margin_centipawns);
+        let scored = [
+            (mv(0), -2000, calm),
+            (mv(1), -2000 - config.margin_centipawns, sharp),
+            (mv(2), -2001 - config.margin_centipawns, sharp),
+        ];
+        assert_eq!(config.pick(&scored), Some(mv(1)));
+        // With nothing to gain it plays the best move
+        assert_eq!(config.pick(&scored[..1]), Some(mv(0)));
+    }
+}
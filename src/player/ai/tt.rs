@@ -1,6 +1,8 @@
 use crate::core::Move;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Bound {
     Exact,
     Lower, // Beta cut (これ以上の値があるかもしれない)
@@ -31,6 +33,9 @@ pub struct TTEntry {
 pub struct TranspositionTable {
     entries: Vec<Option<(TTEntry, Option<Move>)>>,
     size: usize,
+    // 統計 (clear でリセット)
+    probes: Cell<usize>,
+    hits: Cell<usize>,
 }
 
 impl TranspositionTable {
@@ -43,6 +48,8 @@ impl TranspositionTable {
         Self {
             entries: std::iter::repeat_n(None, num_entries).collect(),
             size: num_entries,
+            probes: Cell::new(0),
+            hits: Cell::new(0),
         }
     }
 
@@ -50,13 +57,39 @@ impl TranspositionTable {
         for entry in &mut self.entries {
             *entry = None;
         }
+        self.probes.set(0);
+        self.hits.set(0);
+    }
+
+    /// (probes, hits) since the last clear
+    pub fn stats(&self) -> (usize, usize) {
+        (self.probes.get(), self.hits.get())
+    }
+
+    /// Occupied entries per mille, estimated from the first entries like the
+    /// UCI `hashfull` (the index is a hash, so they are a fair sample)
+    pub fn hashfull(&self) -> u16 {
+        let sample = self.size.min(1000);
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|e| e.is_some())
+            .count();
+        (used * 1000 / sample.max(1)) as u16
+    }
+
+    /// All occupied entries (does not count as probes)
+    pub fn iter(&self) -> impl Iterator<Item = &(TTEntry, Option<Move>)> + '_ {
+        self.entries.iter().flatten()
     }
 
     pub fn get(&self, hash: u64) -> Option<(TTEntry, Option<Move>)> {
+        let _timer = crate::profile::timer(crate::profile::Subsystem::TT);
         let idx = (hash as usize) % self.size;
+        self.probes.set(self.probes.get() + 1);
         if let Some((entry, mv)) = &self.entries[idx] {
             if entry.hash == hash {
-                return Some((*entry, mv.clone()));
+                self.hits.set(self.hits.get() + 1);
+                return Some((*entry, *mv));
             }
         }
         None
@@ -70,6 +103,7 @@ impl TranspositionTable {
         bound: Bound,
         best_move: Option<Move>,
     ) {
+        let _timer = crate::profile::timer(crate::profile::Subsystem::TT);
         let idx = (hash as usize) % self.size;
 
         // 既存のエントリと衝突した場合の置換戦略（Deepest優先）
@@ -0,0 +1,226 @@
+//! Clocks for hot-seat blitz, where one human plays both sides
+//!
+//! Each side has a bank of time for the whole game, and every move must also
+//! be made within a per-move countdown. Running out of either forfeits the
+//! game. Both [`crate::player::TuiController`]s share one clock so each can
+//! show both sides' time.
+//!
+//! Network games are timed by the server instead; [`RemoteClock`] is the
+//! client's copy of its clocks, for display. Either clock is shown above the
+//! board as a [`ClockFace`].
+
+use crate::core::PlayerId;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlitzConfig {
+    /// Time per side for the whole game
+    pub bank: Duration,
+    /// Limit for a single move
+    pub per_move: Duration,
+}
+
+impl Default for BlitzConfig {
+    fn default() -> Self {
+        Self {
+            bank: Duration::from_secs(180),
+            per_move: Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlitzClock {
+    config: BlitzConfig,
+    /// Bank left for Player1 and Player2
+    remaining: [Duration; 2],
+}
+
+fn side(player: PlayerId) -> usize {
+    match player {
+        PlayerId::Player1 => 0,
+        PlayerId::Player2 => 1,
+    }
+}
+
+/// `m:ss`, rounded up so a clock never shows 0:00 while time is left
+fn format_clock(time: Duration) -> String {
+    let secs = time.as_millis().div_ceil(1000);
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Both sides' clocks as shown above the board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockFace {
+    /// Player1's and Player2's time, e.g. "2:41" or "0:00 byoyomi 0:23"
+    pub times: [String; 2],
+    /// The side whose clock is running
+    pub running: Option<PlayerId>,
+}
+
+impl ClockFace {
+    pub fn time(&self, player: PlayerId) -> &str {
+        &self.times[side(player)]
+    }
+}
+
+impl BlitzClock {
+    pub fn new(config: BlitzConfig) -> Self {
+        Self {
+            config,
+            remaining: [config.bank; 2],
+        }
+    }
+
+    /// Time `player` has left for the current move after thinking `elapsed`
+    pub fn time_left(&self, player: PlayerId, elapsed: Duration) -> Duration {
+        self.config
+            .per_move
+            .min(self.remaining[side(player)])
+            .saturating_sub(elapsed)
+    }
+
+    /// Deduct a finished move from `player`'s bank. Returns `true` if the
+    /// move took longer than was allowed, i.e. the player lost on time.
+    pub fn charge(&mut self, player: PlayerId, elapsed: Duration) -> bool {
+        let flagged = self.time_left(player, elapsed).is_zero();
+        let bank = &mut self.remaining[side(player)];
+        *bank = bank.saturating_sub(elapsed);
+        flagged
+    }
+
+    /// Both banks while `player` is thinking, theirs with the move's
+    /// countdown, e.g. "2:41 move 0:12"
+    pub fn face(&self, player: PlayerId, elapsed: Duration) -> ClockFace {
+        let time = |p: PlayerId| {
+            if p == player {
+                format!(
+                    "{} move {}",
+                    format_clock(self.remaining[side(p)].saturating_sub(elapsed)),
+                    format_clock(self.time_left(player, elapsed))
+                )
+            } else {
+                format_clock(self.remaining[side(p)])
+            }
+        };
+        ClockFace {
+            times: [time(PlayerId::Player1), time(PlayerId::Player2)],
+            running: Some(player),
+        }
+    }
+}
+
+/// The server's clocks of a network game as last reported, ticking down
+/// locally for the side to move until the next report
+#[derive(Debug, Clone)]
+pub struct RemoteClock {
+    /// Main time left for Player1 and Player2 at `since`; `None` for an
+    /// untimed game
+    remaining: Option<[Duration; 2]>,
+    /// Time for every move once the main time is used up
+    byoyomi: Duration,
+    to_move: PlayerId,
+    since: Instant,
+    /// Side that lost on time, once the server says so
+    flagged: Option<PlayerId>,
+}
+
+impl Default for RemoteClock {
+    fn default() -> Self {
+        Self {
+            remaining: None,
+            byoyomi: Duration::ZERO,
+            to_move: PlayerId::Player1,
+            since: Instant::now(),
+            flagged: None,
+        }
+    }
+}
+
+impl RemoteClock {
+    pub fn set_byoyomi(&mut self, byoyomi: Duration) {
+        self.byoyomi = byoyomi;
+    }
+
+    /// Main time left for each side in milliseconds, with `to_move`'s clock running
+    pub fn sync(&mut self, millis: [u64; 2], to_move: PlayerId) {
+        self.remaining = Some(millis.map(Duration::from_millis));
+        self.to_move = to_move;
+        self.since = Instant::now();
+    }
+
+    pub fn set_flagged(&mut self, player: PlayerId) {
+        self.flagged = Some(player);
+    }
+
+    pub fn flagged(&self) -> Option<PlayerId> {
+        self.flagged
+    }
+
+    /// Both clocks, the byoyomi counting down once the main time is used
+    /// up, or `None` for an untimed game
+    pub fn face(&self) -> Option<ClockFace> {
+        let remaining = self.remaining?;
+        let running = Some(self.to_move).filter(|_| self.flagged.is_none());
+        let time = |p: PlayerId| {
+            let spent = if running == Some(p) {
+                self.since.elapsed()
+            } else {
+                Duration::ZERO
+            };
+            let main = remaining[side(p)];
+            if main > spent || self.byoyomi.is_zero() {
+                format_clock(main.saturating_sub(spent))
+            } else {
+                format!(
+                    "0:00 byoyomi {}",
+                    format_clock((main + self.byoyomi).saturating_sub(spent))
+                )
+            }
+        };
+        Some(ClockFace {
+            times: [time(PlayerId::Player1), time(PlayerId::Player2)],
+            running,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_move_limit_and_bank() {
+        let mut clock = BlitzClock::new(BlitzConfig {
+            bank: Duration::from_secs(20),
+            per_move: Duration::from_secs(15),
+        });
+        let p1 = PlayerId::Player1;
+
+        assert_eq!(clock.time_left(p1, Duration::ZERO), Duration::from_secs(15));
+        assert!(!clock.charge(p1, Duration::from_secs(10)));
+        // Only 10s of bank left: that is now the limit for the move
+        assert_eq!(clock.time_left(p1, Duration::ZERO), Duration::from_secs(10));
+        let face = clock.face(p1, Duration::from_millis(2500));
+        assert_eq!(face.time(p1), "0:08 move 0:08");
+        assert_eq!(face.time(PlayerId::Player2), "0:20");
+        assert!(clock.charge(p1, Duration::from_secs(10)));
+
+        // The other side's clock is untouched, and over-long moves flag
+        let p2 = PlayerId::Player2;
+        assert!(clock.charge(p2, Duration::from_secs(16)));
+    }
+
+    #[test]
+    fn test_remote_byoyomi() {
+        let mut clock = RemoteClock::default();
+        assert_eq!(clock.face(), None);
+
+        clock.set_byoyomi(Duration::from_secs(30));
+        clock.sync([0, 90_000], PlayerId::Player1);
+        let face = clock.face().unwrap();
+        assert_eq!(face.running, Some(PlayerId::Player1));
+        assert_eq!(face.time(PlayerId::Player1), "0:00 byoyomi 0:30");
+        assert_eq!(face.time(PlayerId::Player2), "1:30");
+    }
+}
@@ -1,8 +1,79 @@
-use crate::core::{Board, Move};
+use crate::core::{Board, Move, PlayerId};
+use crate::logic::apply_move;
+use crate::player::ai::alpha_beta::AnalysisResult;
+
+/// Why a local player's `choose_move` returned no move, besides resigning
+/// or running out of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TakeBack {
+    /// Take back the player's last move and the reply to it
+    Undo,
+    /// Play them again
+    Redo,
+}
+
+/// An engine's last search as shown during play
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineView {
+    pub name: String,
+    pub depth: usize,
+    /// Player1's point of view, in centipawns
+    pub score: i32,
+    pub nodes: usize,
+    /// Principal variation in notation
+    pub pv: Vec<String>,
+}
+
+impl EngineView {
+    /// `search` of `board` by `player`'s engine `name`
+    pub fn new(name: &str, search: &AnalysisResult, board: &Board, player: PlayerId) -> Self {
+        let mut board = board.clone();
+        let mut mover = player;
+        let mut pv = Vec::new();
+        for &mv in &search.pv {
+            pv.push(mv.notation(&board, mover).to_string());
+            board = apply_move(&board, &mv, mover);
+            mover = mover.opponent();
+        }
+        EngineView {
+            name: name.to_string(),
+            depth: search.depth,
+            score: if player == PlayerId::Player1 {
+                search.score
+            } else {
+                -search.score
+            },
+            nodes: search.nodes,
+            pv,
+        }
+    }
+}
 
 /// プレイヤー操作のtrait
 pub trait PlayerController {
     fn choose_move(&self, board: &Board, legal_moves: &[Move]) -> Option<Move>;
     fn name(&self) -> &str;
     fn is_local(&self) -> bool;
+    /// Whether the last `choose_move` returned `None` because the player ran
+    /// out of time rather than resigning
+    fn timed_out(&self) -> bool {
+        false
+    }
+    /// Whether the last `choose_move` returned `None` to take moves back
+    fn take_back(&self) -> Option<TakeBack> {
+        None
+    }
+    /// The moves played so far, in notation, before each `choose_move`
+    fn show_history(&self, _moves: &[String]) {}
+    /// The last search of an engine, by the other side, before each `choose_move`
+    fn show_engine(&self, _engine: Option<&EngineView>) {}
+    /// An engine's last search, from `board` (the position it last moved from)
+    fn last_search(&self, _board: &Board) -> Option<AnalysisResult> {
+        None
+    }
+    /// Whether the player has hidden the pieces to play blindfold, so the
+    /// board drawn on the other side's turn hides them too
+    fn blindfold(&self) -> bool {
+        false
+    }
 }
@@ -1,9 +1,14 @@
 pub mod ai;
+pub mod clock;
 pub mod controller;
+#[cfg(feature = "cli")]
 pub mod network;
+#[cfg(feature = "cli")]
 pub mod tui;
 
-pub use controller::PlayerController;
+pub use controller::{EngineView, PlayerController, TakeBack};
+#[cfg(feature = "cli")]
 #[allow(unused_imports)]
 pub use network::NetworkController;
+#[cfg(feature = "cli")]
 pub use tui::TuiController;
@@ -1,20 +1,35 @@
 use crate::core::{Board, Move, PlayerId};
+use crate::player::clock::RemoteClock;
 use crate::player::PlayerController;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// How often the clocks are redrawn while waiting for the opponent
+const CLOCK_REFRESH: Duration = Duration::from_millis(200);
 
 pub struct NetworkController {
+    player_id: PlayerId,
     name: String,
     // Channel to receive moves from the network thread
     rx: mpsc::Receiver<Move>,
+    /// Server clocks, to tell a time forfeit from a closed connection
+    clock: Option<Arc<Mutex<RemoteClock>>>,
 }
 
 impl NetworkController {
-    pub fn new(_player_id: PlayerId, name: &str, rx: mpsc::Receiver<Move>) -> Self {
+    pub fn new(player_id: PlayerId, name: &str, rx: mpsc::Receiver<Move>) -> Self {
         Self {
+            player_id,
             name: name.to_string(),
             rx,
+            clock: None,
         }
     }
+
+    pub fn with_remote_clock(mut self, clock: Arc<Mutex<RemoteClock>>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
 }
 
 impl PlayerController for NetworkController {
@@ -23,11 +38,30 @@ impl PlayerController for NetworkController {
     }
 
     fn choose_move(&self, _board: &Board, _moves: &[Move]) -> Option<Move> {
-        // Block until a move is received from the server
-        self.rx.recv().ok()
+        // Block until a move is received from the server, keeping the
+        // opponent's clock ticking on screen meanwhile
+        let Some(clock) = &self.clock else {
+            return self.rx.recv().ok();
+        };
+        loop {
+            if let Some(face) = clock.lock().unwrap().face() {
+                crate::ui::display::render_clocks(&face);
+            }
+            match self.rx.recv_timeout(CLOCK_REFRESH) {
+                Ok(mv) => return Some(mv),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            }
+        }
     }
 
     fn is_local(&self) -> bool {
         false
     }
+
+    fn timed_out(&self) -> bool {
+        self.clock
+            .as_ref()
+            .is_some_and(|clock| clock.lock().unwrap().flagged() == Some(self.player_id))
+    }
 }
@@ -1,14 +1,44 @@
 use crate::core::{Board, Move, PlayerId, Position};
-use crate::player::PlayerController;
+use crate::i18n::{player_name, tr};
+use crate::player::clock::{BlitzClock, RemoteClock};
+use crate::player::{EngineView, PlayerController, TakeBack};
+use crate::ui::display::{click_target, hand_items, scroll_move_list, ClickTarget};
 use crate::ui::display::{render_board, DisplayState};
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
-use std::cell::RefCell;
-use std::time::Duration;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Moves the list beside the board scrolls by per [PgUp]/[PgDn]
+const MOVE_LIST_PAGE: isize = 8;
+/// How long a hint stays marked on the board
+const HINT_SHOWN: Duration = Duration::from_secs(3);
 
 pub struct TuiController {
     player_id: PlayerId,
     name: String,
     last_cursor: RefCell<Option<Position>>,
+    /// Blitz clock, shared with the controller of the other side
+    clock: Option<Rc<RefCell<BlitzClock>>>,
+    /// Server clocks of a network game, updated by the network task
+    remote_clock: Option<Arc<Mutex<RemoteClock>>>,
+    timed_out: Cell<bool>,
+    take_back: Cell<Option<TakeBack>>,
+    /// Moves of the game so far, shown beside the board
+    move_list: RefCell<Vec<String>>,
+    /// The opposing engine's last search, and whether it is shown
+    /// (hidden with `e` for fair play)
+    engine: RefCell<Option<EngineView>>,
+    show_engine: Cell<bool>,
+    /// Mark own pieces the opponent attacks (toggled with `t`)
+    show_threats: Cell<bool>,
+    /// Hints asked for with `h` this game
+    hints_used: Cell<usize>,
+    /// `false` against a remote opponent, where an engine's help is unfair
+    hints_allowed: bool,
+    /// Pieces hidden for blindfold play (toggled with `b`)
+    blindfold: Cell<bool>,
 }
 
 impl TuiController {
@@ -17,8 +47,38 @@ impl TuiController {
             player_id,
             name: name.to_string(),
             last_cursor: RefCell::new(None),
+            clock: None,
+            remote_clock: None,
+            timed_out: Cell::new(false),
+            take_back: Cell::new(None),
+            move_list: RefCell::new(Vec::new()),
+            engine: RefCell::new(None),
+            show_engine: Cell::new(true),
+            show_threats: Cell::new(false),
+            hints_used: Cell::new(0),
+            hints_allowed: true,
+            blindfold: Cell::new(false),
         }
     }
+
+    /// Play against `clock`: a move not made in time loses the game
+    pub fn with_clock(mut self, clock: Rc<RefCell<BlitzClock>>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Show the server's clocks; the move is abandoned when the server
+    /// declares this side out of time
+    pub fn with_remote_clock(mut self, clock: Arc<Mutex<RemoteClock>>) -> Self {
+        self.remote_clock = Some(clock);
+        self
+    }
+
+    /// Play a remote opponent: `h` gives no hints
+    pub fn without_hints(mut self) -> Self {
+        self.hints_allowed = false;
+        self
+    }
 }
 
 impl PlayerController for TuiController {
@@ -31,10 +91,156 @@ impl PlayerController for TuiController {
     }
 
     fn choose_move(&self, board: &Board, legal_moves_list: &[Move]) -> Option<Move> {
+        let started = Instant::now();
+        self.timed_out.set(false);
+        self.take_back.set(None);
+        let mv = self.select_move(board, legal_moves_list, started);
+        if let Some(clock) = &self.clock {
+            // Also catches time spent in the (blocking) promotion prompt
+            if clock.borrow_mut().charge(self.player_id, started.elapsed()) {
+                self.timed_out.set(true);
+                return None;
+            }
+        }
+        mv
+    }
+
+    fn timed_out(&self) -> bool {
+        self.timed_out.get()
+    }
+
+    fn take_back(&self) -> Option<TakeBack> {
+        self.take_back.get()
+    }
+
+    fn show_history(&self, moves: &[String]) {
+        *self.move_list.borrow_mut() = moves.to_vec();
+    }
+
+    fn show_engine(&self, engine: Option<&EngineView>) {
+        *self.engine.borrow_mut() = engine.cloned();
+    }
+
+    fn blindfold(&self) -> bool {
+        self.blindfold.get()
+    }
+}
+
+impl TuiController {
+    /// Turns a click into the key that does the same at the clicked square:
+    /// a piece in hand is picked up (showing where it can be dropped) and a
+    /// square gets the cursor and [`KeyCode::Enter`]
+    fn click(
+        &self,
+        board: &Board,
+        legal_moves_list: &[Move],
+        state: &mut DisplayState,
+        column: u16,
+        row: u16,
+    ) -> Option<KeyCode> {
+        match click_target(board, state, column, row)? {
+            ClickTarget::Hand(player, index) if player == self.player_id => {
+                state.selected = None;
+                state.hand_mode = true;
+                state.hand_index = index;
+                self.highlight_drops(board, legal_moves_list, state);
+                None
+            }
+            ClickTarget::Hand(..) => None,
+            ClickTarget::Square(pos) => {
+                state.cursor = pos;
+                *self.last_cursor.borrow_mut() = Some(pos);
+                if !state.hand_mode
+                    && state.selected.is_some_and(|from| from != pos)
+                    && !state.highlights.contains(&pos)
+                {
+                    // Another square than a destination: start over from it
+                    state.selected = None;
+                    state.highlights.clear();
+                }
+                Some(KeyCode::Enter)
+            }
+        }
+    }
+
+    /// Moves can be taken back in untimed local games only: a clock would
+    /// keep the time spent on them
+    fn can_take_back(&self) -> bool {
+        self.clock.is_none() && self.remote_clock.is_none()
+    }
+
+    /// A Light search's move for this side, or why there is none, within the
+    /// per-game limit of `ai_config.json`
+    fn hint(&self, board: &Board, legal_moves_list: &[Move]) -> Result<Move, String> {
+        use crate::player::ai::alpha_beta::{AIStrength, AlphaBetaAI};
+
+        let limit = crate::player::ai::config::AIConfig::get()
+            .hints
+            .max_per_game;
+        if !self.hints_allowed {
+            return Err(tr(
+                "No hints against a remote opponent",
+                "ネットワーク対戦ではヒントは使えません",
+            )
+            .to_string());
+        }
+        if limit == 0 {
+            return Err(tr("Hints are turned off", "ヒントは使えない設定です").to_string());
+        }
+        if self.hints_used.get() >= limit {
+            return Err(crate::trf!(
+                "No hints left ({} per game)",
+                "ヒントは使い切りました (1 局 {} 回まで)",
+                limit
+            ));
+        }
+        let engine = AlphaBetaAI::new(self.player_id, "Hint", AIStrength::Light, None, true);
+        let mv = engine
+            .choose_move(board, legal_moves_list)
+            .ok_or_else(|| tr("No move to suggest", "勧める手がありません").to_string())?;
+        self.hints_used.set(self.hints_used.get() + 1);
+        Ok(mv)
+    }
+
+    /// Highlights every square the piece in hand under the hand cursor can be
+    /// dropped on, or nothing outside hand mode
+    fn highlight_drops(&self, board: &Board, legal_moves_list: &[Move], state: &mut DisplayState) {
+        let kind = hand_items(board, self.player_id)
+            .and_then(|items| items.get(state.hand_index).map(|&(kind, _)| kind))
+            .filter(|_| state.hand_mode);
+        state.highlights = legal_moves_list
+            .iter()
+            .filter_map(|m| match m {
+                Move::Drop { kind: k, to } if Some(*k) == kind => Some(*to),
+                _ => None,
+            })
+            .collect();
+    }
+
+    fn select_move(
+        &self,
+        board: &Board,
+        legal_moves_list: &[Move],
+        started: Instant,
+    ) -> Option<Move> {
+        let _mouse = crate::ui::MouseCapture::enable();
         let mut state = DisplayState {
             perspective: self.player_id,
-            last_move: board.last_move.clone(),
-            status_msg: Some(format!("{}'s turn ({:?})", self.name, self.player_id)),
+            last_move: board.last_move,
+            status_msg: Some(crate::trf!(
+                "{}'s turn ({})",
+                "{}の手番 ({})",
+                self.name,
+                player_name(self.player_id)
+            )),
+            move_list: self.move_list.borrow().clone(),
+            engine: self
+                .engine
+                .borrow()
+                .clone()
+                .filter(|_| self.show_engine.get()),
+            show_threats: self.show_threats.get(),
+            blindfold: self.blindfold.get(),
             ..Default::default()
         };
 
@@ -47,172 +253,408 @@ impl PlayerController for TuiController {
             *self.last_cursor.borrow_mut() = Some(king_pos);
         }
 
+        let turn = crate::trf!(
+            "{}'s turn ({})",
+            "{}の手番 ({})",
+            self.name,
+            player_name(self.player_id)
+        );
+        // Move typed after [:] (USI or algebraic), and why the last one was refused
+        let mut typed: Option<String> = None;
+        let mut input_error: Option<String> = None;
+        // The hint on the board and since when
+        let mut hint: Option<(Move, Instant)> = None;
         loop {
+            let mut status = turn.clone();
+            if hint.is_some_and(|(_, shown)| shown.elapsed() > HINT_SHOWN) {
+                hint = None;
+            }
+            state.hint = hint.map(|(mv, _)| mv);
+            if let Some(clock) = &self.clock {
+                let clock = clock.borrow();
+                let elapsed = started.elapsed();
+                if clock.time_left(self.player_id, elapsed).is_zero() {
+                    return None;
+                }
+                state.clocks = Some(clock.face(self.player_id, elapsed));
+            }
+            if let Some(clock) = &self.remote_clock {
+                let clock = clock.lock().unwrap();
+                if clock.flagged() == Some(self.player_id) {
+                    self.timed_out.set(true);
+                    return None;
+                }
+                state.clocks = clock.face();
+            }
+            if let Some(text) = &typed {
+                status = format!("{} | {}: {}_", status, tr("Move", "指し手"), text);
+            } else if let Some(err) = &input_error {
+                status = format!("{} | {}", status, err);
+            } else if let Some((mv, _)) = hint {
+                status = crate::trf!(
+                    "{} | Hint: {}",
+                    "{} | ヒント: {}",
+                    status,
+                    mv.notation(board, self.player_id)
+                );
+            }
+            state.status_msg = Some(status);
+
             // 描画
             render_board(board, &state);
-            print!(
-                "[Arrows]: Move | [Enter]: Select | [Esc]: Cancel | [p]: Hand | [q]: Resign\r\n"
-            );
+            if typed.is_some() {
+                print!(
+                    "{}\r\n",
+                    tr(
+                        "[Enter]: Play | [Esc]: Cancel | e.g. 7g7f, 8h2b+, P*5e, e2e4, e7e8q",
+                        "[Enter]: 指す | [Esc]: やめる | 例: 7g7f, 8h2b+, P*5e, e2e4, e7e8q"
+                    )
+                );
+            } else {
+                print!(
+                    "{}\r\n",
+                    tr(
+                        "[Arrows]: Move | [Enter]: Select | [Esc]: Cancel | [p]: Hand | [:]: Type move | [PgUp/PgDn]: Moves | [e]: Engine | [t]: Threats | [h]: Hint | [b]: Blindfold | [r]: Rules | [q]: Resign",
+                        "[矢印]: 移動 | [Enter]: 選択 | [Esc]: 取り消し | [p]: 持ち駒 | [:]: 指し手を入力 | [PgUp/PgDn]: 棋譜 | [e]: AI の読み | [t]: 当たり | [h]: ヒント | [b]: 目隠し | [r]: ルール | [q]: 投了"
+                    )
+                );
+                if self.can_take_back() {
+                    print!(
+                        "{}\r\n",
+                        tr(
+                            "[u]: Undo your last move and the reply | [U]: Redo",
+                            "[u]: 自分の直前の手と相手の応手を戻す | [U]: やり直す"
+                        )
+                    );
+                }
+            }
 
             if event::poll(Duration::from_millis(100)).unwrap() {
-                if let Event::Key(KeyEvent { code, .. }) = event::read().unwrap() {
+                let code = match event::read().unwrap() {
+                    Event::Key(KeyEvent { code, .. }) => code,
+                    Event::Mouse(MouseEvent {
+                        kind: MouseEventKind::Down(MouseButton::Left),
+                        column,
+                        row,
+                        ..
+                    }) if typed.is_none() => {
+                        match self.click(board, legal_moves_list, &mut state, column, row) {
+                            Some(code) => code,
+                            None => continue,
+                        }
+                    }
+                    Event::Mouse(MouseEvent {
+                        kind: MouseEventKind::ScrollUp,
+                        ..
+                    }) => {
+                        scroll_move_list(board, &mut state, 1);
+                        continue;
+                    }
+                    Event::Mouse(MouseEvent {
+                        kind: MouseEventKind::ScrollDown,
+                        ..
+                    }) => {
+                        scroll_move_list(board, &mut state, -1);
+                        continue;
+                    }
+                    // 次の描画で端末の大きさに合う配置を選び直す
+                    Event::Resize(..) => continue,
+                    _ => continue,
+                };
+                if let Some(text) = typed.as_mut() {
+                    // 指し手の文字入力
                     match code {
-                        KeyCode::Char('q') => return None,
-                        KeyCode::Esc => {
-                            state.selected = None;
-                            state.highlights.clear();
-                            state.hand_mode = false;
+                        KeyCode::Char(c) => text.push(c),
+                        KeyCode::Backspace => {
+                            text.pop();
                         }
-                        KeyCode::Up => {
-                            if state.perspective == PlayerId::Player1 {
-                                if state.cursor.y > 0 {
-                                    state.cursor.y -= 1;
+                        KeyCode::Esc => typed = None,
+                        KeyCode::Enter => {
+                            match crate::core::r#move::parse_move(text, board, self.player_id) {
+                                Ok(mv) if legal_moves_list.contains(&mv) => return Some(mv),
+                                Ok(mv) => {
+                                    input_error = Some(crate::trf!(
+                                        "{} is not allowed",
+                                        "{} は指せません",
+                                        mv
+                                    ))
                                 }
-                            } else if state.cursor.y < board.height - 1 {
+                                Err(e) => input_error = Some(format!("{}: {}", text, e)),
+                            }
+                            typed = None;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                input_error = None;
+                match code {
+                    KeyCode::Char(':') | KeyCode::Char('/') => {
+                        typed = Some(String::new());
+                    }
+                    KeyCode::Char('q') => return None,
+                    KeyCode::Char('u') | KeyCode::Char('U') if self.can_take_back() => {
+                        self.take_back.set(Some(if code == KeyCode::Char('u') {
+                            TakeBack::Undo
+                        } else {
+                            TakeBack::Redo
+                        }));
+                        return None;
+                    }
+                    KeyCode::Char('r') => {
+                        let _ = crate::ui::show_rules(board);
+                    }
+                    KeyCode::Char('h') => {
+                        state.status_msg = Some(format!(
+                            "{} | {}",
+                            turn,
+                            tr("Looking for a hint...", "ヒントを考えています...")
+                        ));
+                        render_board(board, &state);
+                        match self.hint(board, legal_moves_list) {
+                            Ok(mv) => hint = Some((mv, Instant::now())),
+                            Err(e) => input_error = Some(e),
+                        }
+                    }
+                    KeyCode::Char('b') => {
+                        self.blindfold.set(!self.blindfold.get());
+                        state.blindfold = self.blindfold.get();
+                    }
+                    KeyCode::Char('t') => {
+                        self.show_threats.set(!self.show_threats.get());
+                        state.show_threats = self.show_threats.get();
+                    }
+                    KeyCode::Char('e') => {
+                        self.show_engine.set(!self.show_engine.get());
+                        state.engine = self
+                            .engine
+                            .borrow()
+                            .clone()
+                            .filter(|_| self.show_engine.get());
+                    }
+                    KeyCode::PageUp => scroll_move_list(board, &mut state, MOVE_LIST_PAGE),
+                    KeyCode::PageDown => scroll_move_list(board, &mut state, -MOVE_LIST_PAGE),
+                    KeyCode::Esc => {
+                        state.selected = None;
+                        state.highlights.clear();
+                        state.hand_mode = false;
+                    }
+                    KeyCode::Up => {
+                        if state.perspective == PlayerId::Player1 {
+                            if state.cursor.y > 0 {
+                                state.cursor.y -= 1;
+                            }
+                        } else if state.cursor.y < board.height - 1 {
+                            state.cursor.y += 1;
+                        }
+                        *self.last_cursor.borrow_mut() = Some(state.cursor);
+                    }
+                    KeyCode::Down => {
+                        if state.perspective == PlayerId::Player1 {
+                            if state.cursor.y < board.height - 1 {
                                 state.cursor.y += 1;
                             }
-                            *self.last_cursor.borrow_mut() = Some(state.cursor);
+                        } else if state.cursor.y > 0 {
+                            state.cursor.y -= 1;
                         }
-                        KeyCode::Down => {
+                        *self.last_cursor.borrow_mut() = Some(state.cursor);
+                    }
+                    KeyCode::Left => {
+                        if state.hand_mode {
+                            if let Some(hand) = board.hand.get(&self.player_id) {
+                                let items_count = hand.values().filter(|&&c| c > 0).count();
+                                if items_count > 0 {
+                                    state.hand_index =
+                                        (state.hand_index + items_count - 1) % items_count;
+                                }
+                            }
+                            self.highlight_drops(board, legal_moves_list, &mut state);
+                        } else {
                             if state.perspective == PlayerId::Player1 {
-                                if state.cursor.y < board.height - 1 {
-                                    state.cursor.y += 1;
+                                if state.cursor.x > 0 {
+                                    state.cursor.x -= 1;
                                 }
-                            } else if state.cursor.y > 0 {
-                                state.cursor.y -= 1;
+                            } else if state.cursor.x < board.width - 1 {
+                                state.cursor.x += 1;
                             }
                             *self.last_cursor.borrow_mut() = Some(state.cursor);
                         }
-                        KeyCode::Left => {
-                            if state.hand_mode {
-                                if let Some(hand) = board.hand.get(&self.player_id) {
-                                    let items_count = hand.values().filter(|&&c| c > 0).count();
-                                    if items_count > 0 {
-                                        state.hand_index =
-                                            (state.hand_index + items_count - 1) % items_count;
-                                    }
+                    }
+                    KeyCode::Right => {
+                        if state.hand_mode {
+                            if let Some(hand) = board.hand.get(&self.player_id) {
+                                let items_count = hand.values().filter(|&&c| c > 0).count();
+                                if items_count > 0 {
+                                    state.hand_index = (state.hand_index + 1) % items_count;
                                 }
-                            } else {
-                                if state.perspective == PlayerId::Player1 {
-                                    if state.cursor.x > 0 {
-                                        state.cursor.x -= 1;
-                                    }
-                                } else if state.cursor.x < board.width - 1 {
+                            }
+                            self.highlight_drops(board, legal_moves_list, &mut state);
+                        } else {
+                            if state.perspective == PlayerId::Player1 {
+                                if state.cursor.x < board.width - 1 {
                                     state.cursor.x += 1;
                                 }
-                                *self.last_cursor.borrow_mut() = Some(state.cursor);
+                            } else if state.cursor.x > 0 {
+                                state.cursor.x -= 1;
                             }
+                            *self.last_cursor.borrow_mut() = Some(state.cursor);
                         }
-                        KeyCode::Right => {
-                            if state.hand_mode {
-                                if let Some(hand) = board.hand.get(&self.player_id) {
-                                    let items_count = hand.values().filter(|&&c| c > 0).count();
-                                    if items_count > 0 {
-                                        state.hand_index = (state.hand_index + 1) % items_count;
-                                    }
-                                }
-                            } else {
-                                if state.perspective == PlayerId::Player1 {
-                                    if state.cursor.x < board.width - 1 {
-                                        state.cursor.x += 1;
-                                    }
-                                } else if state.cursor.x > 0 {
-                                    state.cursor.x -= 1;
-                                }
-                                *self.last_cursor.borrow_mut() = Some(state.cursor);
+                    }
+                    KeyCode::Char('p') => {
+                        if state.hand_mode {
+                            state.hand_mode = false;
+                            state.highlights.clear();
+                        } else if let Some(hand) = board.hand.get(&self.player_id) {
+                            if !hand.values().all(|&v| v == 0) {
+                                state.hand_mode = true;
+                                state.hand_index = 0;
+                                state.selected = None;
+                                self.highlight_drops(board, legal_moves_list, &mut state);
                             }
                         }
-                        KeyCode::Char('p') => {
-                            if state.hand_mode {
-                                state.hand_mode = false;
-                            } else if let Some(hand) = board.hand.get(&self.player_id) {
-                                if !hand.values().all(|&v| v == 0) {
-                                    state.hand_mode = true;
-                                    state.hand_index = 0;
+                    }
+                    KeyCode::Enter | KeyCode::Char(' ') => {
+                        if state.hand_mode {
+                            // 持ち駒の確定
+                            if let Some(items) = hand_items(board, self.player_id) {
+                                if let Some(&(h_kind, _)) = items.get(state.hand_index) {
+                                    state.hand_mode = false;
+                                    state.highlights.clear();
+                                    // 全位置からドロップ可能個所を選択
+                                    // 簡易的に現在のカーソル位置に落とす
+                                    let mv = Move::Drop {
+                                        kind: h_kind,
+                                        to: state.cursor,
+                                    };
+                                    if legal_moves_list.contains(&mv) {
+                                        return Some(mv);
+                                    }
                                 }
                             }
-                        }
-                        KeyCode::Enter | KeyCode::Char(' ') => {
-                            if state.hand_mode {
-                                // 持ち駒の確定
-                                if let Some(hand) = board.hand.get(&self.player_id) {
-                                    let mut items: Vec<_> =
-                                        hand.iter().filter(|(_, &c)| c > 0).collect();
-                                    items.sort_by_key(|(k, _)| format!("{:?}", k));
-                                    if let Some(&(kind, _)) = items.get(state.hand_index) {
-                                        let h_kind = *kind;
-                                        state.hand_mode = false;
-                                        // 全位置からドロップ可能個所を選択
-                                        // 簡易的に現在のカーソル位置に落とす
-                                        let mv = Move::Drop {
-                                            kind: h_kind,
-                                            to: state.cursor,
-                                        };
-                                        if legal_moves_list.contains(&mv) {
-                                            return Some(mv);
+                        } else if let Some(from) = state.selected {
+                            // 移動先確定
+                            let to = state.cursor;
+                            if from == to {
+                                state.selected = None;
+                                state.highlights.clear();
+                            } else {
+                                let found_moves: Vec<Move> = legal_moves_list
+                                    .iter()
+                                    .filter(|m| match m {
+                                        Move::Normal { from: f, to: t, .. } => {
+                                            *f == from && *t == to
                                         }
-                                    }
-                                }
-                            } else if let Some(from) = state.selected {
-                                // 移動先確定
-                                let to = state.cursor;
-                                if from == to {
-                                    state.selected = None;
-                                    state.highlights.clear();
-                                } else {
-                                    let found_moves: Vec<Move> = legal_moves_list
-                                        .iter()
-                                        .filter(|m| match m {
-                                            Move::Normal { from: f, to: t, .. } => {
-                                                *f == from && *t == to
-                                            }
-                                            _ => false,
-                                        })
-                                        .cloned()
-                                        .collect();
+                                        _ => false,
+                                    })
+                                    .cloned()
+                                    .collect();
 
-                                    if !found_moves.is_empty() {
-                                        if found_moves.len() > 1 {
-                                            // 成り選択
-                                            render_board(board, &state);
+                                if !found_moves.is_empty() {
+                                    if found_moves.len() > 1 {
+                                        // 成り選択
+                                        render_board(board, &state);
 
-                                            // Check if we have both promote=Some and promote=None
-                                            let has_non_promote = found_moves.iter().any(|m| {
-                                                matches!(m, Move::Normal { promote: None, .. })
-                                            });
+                                        // Check if we have both promote=Some and promote=None
+                                        let has_non_promote = found_moves.iter().any(|m| {
+                                            matches!(m, Move::Normal { promote: None, .. })
+                                        });
 
-                                            // Create a list of (PromotedKind, Move)
-                                            let promote_options: Vec<_> = found_moves
-                                                .iter()
-                                                .filter_map(|m| {
-                                                    if let Move::Normal {
-                                                        promote: Some(k), ..
-                                                    } = m
-                                                    {
-                                                        Some((k, m.clone()))
-                                                    } else {
-                                                        None
+                                        // Create a list of (PromotedKind, Move)
+                                        let promote_options: Vec<_> = found_moves
+                                            .iter()
+                                            .filter_map(|m| {
+                                                if let Move::Normal {
+                                                    promote: Some(k), ..
+                                                } = m
+                                                {
+                                                    Some((k, *m))
+                                                } else {
+                                                    None
+                                                }
+                                            })
+                                            .collect();
+
+                                        if !promote_options.is_empty()
+                                            && has_non_promote
+                                            && promote_options.len() == 1
+                                        {
+                                            // Shogi-style simple question
+                                            println!(
+                                                "\n{}",
+                                                tr(
+                                                    "Promote? [y] Yes / [n] No",
+                                                    "成りますか? [y] はい / [n] いいえ"
+                                                )
+                                            );
+                                            loop {
+                                                if let Event::Key(KeyEvent {
+                                                    code: KeyCode::Char(c),
+                                                    ..
+                                                }) = event::read().unwrap()
+                                                {
+                                                    if c == 'y' {
+                                                        return Some(promote_options[0].1);
+                                                    } else if c == 'n' {
+                                                        return found_moves.into_iter().find(|m| {
+                                                            matches!(
+                                                                m,
+                                                                Move::Normal { promote: None, .. }
+                                                            )
+                                                        });
                                                     }
-                                                })
-                                                .collect();
+                                                }
+                                            }
+                                        } else {
+                                            // Chess-style: Multiple promotion options OR mandatory choice
+                                            println!(
+                                                "\n{}\r",
+                                                tr("Select promotion:", "成る駒を選んでください:")
+                                            );
+                                            for (i, (k, _)) in promote_options.iter().enumerate() {
+                                                let key = if k.is_shogi() {
+                                                    (i + 1).to_string()
+                                                } else {
+                                                    format!(
+                                                        "{}/{}",
+                                                        i + 1,
+                                                        k.display_char().to_ascii_lowercase()
+                                                    )
+                                                };
+                                                println!(
+                                                    "[{}] {}\r",
+                                                    key,
+                                                    crate::trf!(
+                                                        "Promote to {:?}",
+                                                        "{:?} に成る",
+                                                        k
+                                                    )
+                                                );
+                                            }
+                                            if has_non_promote {
+                                                println!(
+                                                    "[0] {}\r",
+                                                    tr("Don't promote", "成らない")
+                                                );
+                                            }
 
-                                            if !promote_options.is_empty()
-                                                && has_non_promote
-                                                && promote_options.len() == 1
-                                            {
-                                                // Shogi-style simple question
-                                                println!("\nPromote? [y] Yes / [n] No");
-                                                loop {
-                                                    if let Event::Key(KeyEvent {
-                                                        code: KeyCode::Char(c),
-                                                        ..
-                                                    }) = event::read().unwrap()
+                                            loop {
+                                                if let Event::Key(KeyEvent {
+                                                    code: KeyCode::Char(c),
+                                                    ..
+                                                }) = event::read().unwrap()
+                                                {
+                                                    // Chess pieces also by letter (q, r, b, n)
+                                                    if let Some((_, mv)) =
+                                                        promote_options.iter().find(|(k, _)| {
+                                                            !k.is_shogi()
+                                                                && k.display_char()
+                                                                    .eq_ignore_ascii_case(&c)
+                                                        })
                                                     {
-                                                        if c == 'y' {
-                                                            return Some(
-                                                                promote_options[0].1.clone(),
-                                                            );
-                                                        } else if c == 'n' {
+                                                        return Some(*mv);
+                                                    }
+                                                    if let Some(digit) = c.to_digit(10) {
+                                                        if digit == 0 && has_non_promote {
                                                             return found_moves.into_iter().find(
                                                                 |m| {
                                                                     matches!(
@@ -225,81 +667,44 @@ impl PlayerController for TuiController {
                                                                 },
                                                             );
                                                         }
-                                                    }
-                                                }
-                                            } else {
-                                                // Chess-style: Multiple promotion options OR mandatory choice
-                                                println!("\nSelect promotion:\r");
-                                                for (i, (k, _)) in
-                                                    promote_options.iter().enumerate()
-                                                {
-                                                    println!("[{}] Promote to {:?}\r", i + 1, k);
-                                                }
-                                                if has_non_promote {
-                                                    println!("[0] Don't promote\r");
-                                                }
-
-                                                loop {
-                                                    if let Event::Key(KeyEvent {
-                                                        code: KeyCode::Char(c),
-                                                        ..
-                                                    }) = event::read().unwrap()
-                                                    {
-                                                        if let Some(digit) = c.to_digit(10) {
-                                                            if digit == 0 && has_non_promote {
-                                                                return found_moves
-                                                                    .into_iter()
-                                                                    .find(|m| {
-                                                                        matches!(
-                                                                            m,
-                                                                            Move::Normal {
-                                                                                promote: None,
-                                                                                ..
-                                                                            }
-                                                                        )
-                                                                    });
-                                                            }
-                                                            if digit > 0
-                                                                && (digit as usize)
-                                                                    <= promote_options.len()
-                                                            {
-                                                                return Some(
-                                                                    promote_options
-                                                                        [digit as usize - 1]
-                                                                        .1
-                                                                        .clone(),
-                                                                );
-                                                            }
+                                                        if digit > 0
+                                                            && (digit as usize)
+                                                                <= promote_options.len()
+                                                        {
+                                                            return Some(
+                                                                promote_options[digit as usize - 1]
+                                                                    .1,
+                                                            );
                                                         }
                                                     }
                                                 }
                                             }
                                         }
-                                        return Some(found_moves[0].clone());
                                     }
+                                    return Some(found_moves[0]);
                                 }
-                            } else {
-                                // 移動元選択
-                                if let Some(piece) = board.get_piece(state.cursor) {
-                                    if piece.owner == self.player_id {
-                                        state.selected = Some(state.cursor);
-                                        state.highlights = legal_moves_list
-                                            .iter()
-                                            .filter_map(|m| match m {
-                                                Move::Normal { from, to, .. }
-                                                    if *from == state.cursor =>
-                                                {
-                                                    Some(*to)
-                                                }
-                                                _ => None,
-                                            })
-                                            .collect();
-                                    }
+                            }
+                        } else {
+                            // 移動元選択
+                            if let Some(piece) = board.get_piece(state.cursor) {
+                                if piece.owner == self.player_id {
+                                    state.selected = Some(state.cursor);
+                                    state.highlights = legal_moves_list
+                                        .iter()
+                                        .filter_map(|m| match m {
+                                            Move::Normal { from, to, .. }
+                                                if *from == state.cursor =>
+                                            {
+                                                Some(*to)
+                                            }
+                                            _ => None,
+                                        })
+                                        .collect();
                                 }
                             }
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
         }
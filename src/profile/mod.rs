@@ -0,0 +1,354 @@
+//! Game-speed profiling
+//!
+//! Coarse timers around the engine's subsystems (move generation, evaluation,
+//! transposition table, make-move, NN inference, board rendering). They cost a
+//! single relaxed atomic load while disabled; `profile` enables them for a few
+//! AI vs AI games and reports where the time went, so users can tell whether
+//! batching, a GPU, or a lower search depth would help on their hardware.
+//!
+//! Times are exclusive: a subsystem entered from another one (move generation
+//! inside the evaluator's mobility term) is counted under its own row and not
+//! under its caller's. Whatever the search does outside the timed subsystems
+//! (move ordering, pruning, bookkeeping) is reported as "search (other)".
+
+use crate::core::setup::BoardSetupType;
+use crate::core::{Board, PlayerId};
+use crate::logic::{apply_move, legal_moves, ZobristHasher};
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use crate::player::PlayerController;
+use crate::util::Instant;
+use serde::Serialize;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    MoveGen,
+    ApplyMove,
+    Eval,
+    NNInference,
+    TT,
+    Render,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 6] = [
+        Subsystem::MoveGen,
+        Subsystem::ApplyMove,
+        Subsystem::Eval,
+        Subsystem::NNInference,
+        Subsystem::TT,
+        Subsystem::Render,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::MoveGen => "movegen",
+            Subsystem::ApplyMove => "apply_move",
+            Subsystem::Eval => "eval",
+            Subsystem::NNInference => "nn_inference",
+            Subsystem::TT => "tt",
+            Subsystem::Render => "render",
+        }
+    }
+}
+
+struct Counter {
+    nanos: AtomicU64,
+    calls: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: Counter = Counter {
+    nanos: AtomicU64::new(0),
+    calls: AtomicU64::new(0),
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static COUNTERS: [Counter; Subsystem::ALL.len()] = [ZERO; Subsystem::ALL.len()];
+
+thread_local! {
+    /// Innermost running timer on this thread and when it last resumed
+    static ACTIVE: Cell<Option<(Subsystem, Instant)>> = const { Cell::new(None) };
+}
+
+fn credit(subsystem: Subsystem, elapsed: Duration, calls: u64) {
+    let counter = &COUNTERS[subsystem as usize];
+    counter
+        .nanos
+        .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    counter.calls.fetch_add(calls, Ordering::Relaxed);
+}
+
+/// Running timer; the time is credited when it is dropped
+pub struct Timer {
+    parent: Option<Subsystem>,
+}
+
+/// Start timing `subsystem` until the returned guard is dropped. `None` (and
+/// no clock read) unless profiling is enabled. Pauses the enclosing timer.
+#[inline]
+pub fn timer(subsystem: Subsystem) -> Option<Timer> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let now = Instant::now();
+    let parent = ACTIVE.with(|active| active.replace(Some((subsystem, now))));
+    if let Some((parent, since)) = parent {
+        credit(parent, now - since, 0);
+    }
+    Some(Timer {
+        parent: parent.map(|(parent, _)| parent),
+    })
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let now = Instant::now();
+        let resumed = self.parent.map(|parent| (parent, now));
+        if let Some((subsystem, since)) = ACTIVE.with(|active| active.replace(resumed)) {
+            credit(subsystem, now - since, 1);
+        }
+    }
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn reset() {
+    for counter in &COUNTERS {
+        counter.nanos.store(0, Ordering::Relaxed);
+        counter.calls.store(0, Ordering::Relaxed);
+    }
+}
+
+pub struct ProfileConfig {
+    pub games: usize,
+    pub board_setup: BoardSetupType,
+    pub strength: AIStrength,
+    /// Plies per game before it is cut off
+    pub max_moves: usize,
+    /// Draw the board before every move, as a local AI vs AI game does
+    /// (needs the `cli` feature)
+    pub render: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemTime {
+    pub name: String,
+    pub calls: u64,
+    pub time_ms: f64,
+    /// Fraction of the total game time
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileReport {
+    pub games: usize,
+    pub plies: usize,
+    pub board_setup: String,
+    pub strength: String,
+    pub evaluator: String,
+    pub total_ms: f64,
+    /// Timed subsystems, then "search (other)" for the untimed rest
+    pub subsystems: Vec<SubsystemTime>,
+    pub hints: Vec<String>,
+}
+
+impl ProfileReport {
+    fn share(&self, names: &[&str]) -> f64 {
+        self.subsystems
+            .iter()
+            .filter(|s| names.contains(&s.name.as_str()))
+            .map(|s| s.share)
+            .sum()
+    }
+}
+
+/// Play `config.games` instrumented AI vs AI games and break their time down
+/// by subsystem
+pub fn run_profile(config: &ProfileConfig) -> ProfileReport {
+    reset();
+    set_enabled(true);
+    let start = Instant::now();
+    let mut plies = 0;
+    let mut evaluator = String::new();
+
+    for game_num in 1..=config.games {
+        let p1 = AlphaBetaAI::new(PlayerId::Player1, "AI 1", config.strength, None, true);
+        let p2 = AlphaBetaAI::new(PlayerId::Player2, "AI 2", config.strength, None, true);
+        evaluator = p1.evaluator_name();
+        plies += play_game(config, game_num, &p1, &p2);
+    }
+
+    set_enabled(false);
+    let total = start.elapsed();
+    let total_ms = total.as_secs_f64() * 1000.0;
+    let share = |ms: f64| if total_ms > 0.0 { ms / total_ms } else { 0.0 };
+
+    let mut subsystems: Vec<SubsystemTime> = Subsystem::ALL
+        .iter()
+        .map(|&subsystem| {
+            let counter = &COUNTERS[subsystem as usize];
+            let time_ms = counter.nanos.load(Ordering::Relaxed) as f64 / 1e6;
+            SubsystemTime {
+                name: subsystem.name().to_string(),
+                calls: counter.calls.load(Ordering::Relaxed),
+                time_ms,
+                share: share(time_ms),
+            }
+        })
+        .collect();
+    let timed: f64 = subsystems.iter().map(|s| s.time_ms).sum();
+    let other_ms = (total_ms - timed).max(0.0);
+    subsystems.push(SubsystemTime {
+        name: "search (other)".to_string(),
+        calls: 0,
+        time_ms: other_ms,
+        share: share(other_ms),
+    });
+
+    let mut report = ProfileReport {
+        games: config.games,
+        plies,
+        board_setup: config.board_setup.to_string(),
+        strength: format!("{:?}", config.strength),
+        evaluator,
+        total_ms,
+        subsystems,
+        hints: Vec::new(),
+    };
+    report.hints = hints(&report);
+    report
+}
+
+/// Play one game; returns the number of plies
+#[cfg_attr(not(feature = "cli"), allow(unused_variables))]
+fn play_game(config: &ProfileConfig, game_num: usize, p1: &AlphaBetaAI, p2: &AlphaBetaAI) -> usize {
+    let mut board: Board = config.board_setup.create_board();
+    board.zobrist_hash = ZobristHasher::compute_hash(&board, PlayerId::Player1);
+    board.history = vec![board.zobrist_hash];
+    let mut player = PlayerId::Player1;
+
+    for ply in 0..config.max_moves {
+        #[cfg(feature = "cli")]
+        if config.render {
+            let state = crate::ui::display::DisplayState {
+                status_msg: Some(format!(
+                    "Profiling game {}/{}, move {}",
+                    game_num,
+                    config.games,
+                    ply + 1
+                )),
+                last_move: board.last_move,
+                perspective: PlayerId::Player1,
+                show_cursor: false,
+                ..Default::default()
+            };
+            crate::ui::display::render_board(&board, &state);
+        }
+
+        let repetitions = board
+            .history
+            .iter()
+            .filter(|&&h| h == board.zobrist_hash)
+            .count();
+        let moves = legal_moves(&board, player);
+        if repetitions >= board.rules.repetition_limit || moves.is_empty() {
+            return ply;
+        }
+        let controller = match player {
+            PlayerId::Player1 => p1,
+            PlayerId::Player2 => p2,
+        };
+        let Some(mv) = controller.choose_move(&board, &moves) else {
+            return ply;
+        };
+        board = apply_move(&board, &mv, player);
+        player = player.opponent();
+    }
+    config.max_moves
+}
+
+/// What the breakdown suggests doing
+fn hints(report: &ProfileReport) -> Vec<String> {
+    let mut hints = Vec::new();
+    let board_ops = report.share(&["movegen", "apply_move"]);
+    if board_ops >= 0.4 {
+        hints.push(format!(
+            "Move generation and make-move take {:.0}% of the time: the board representation \
+             is the bottleneck (a bitboard backend would pay off most). Until then, lower \
+             search.max_depth_{} in ai_config.json to trade strength for speed.",
+            board_ops * 100.0,
+            report.strength.to_lowercase()
+        ));
+    }
+    let nn = report.share(&["nn_inference"]);
+    if nn >= 0.3 {
+        hints.push(format!(
+            "NN inference takes {:.0}% of the time: raise evaluation.nn_batch.max_batch_size / \
+             max_wait_micros to batch more positions per call, or select a GPU with \
+             evaluation.nn_runtime.execution_provider.",
+            nn * 100.0
+        ));
+    }
+    let eval = report.share(&["eval"]);
+    if eval >= 0.4 {
+        hints.push(format!(
+            "Static evaluation takes {:.0}% of the time: a lower search depth (or the Light \
+             preset) reduces the number of evaluated positions.",
+            eval * 100.0
+        ));
+    }
+    let render = report.share(&["render"]);
+    if render >= 0.1 {
+        hints.push(format!(
+            "Drawing the board takes {:.0}% of the time: the terminal is slow to redraw; \
+             selfplay (which does not draw every move) is faster for bulk games.",
+            render * 100.0
+        ));
+    }
+    if hints.is_empty() {
+        hints.push(
+            "No single subsystem dominates; the search itself (ordering, pruning) is the main cost."
+                .to_string(),
+        );
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_timers_are_exclusive() {
+        set_enabled(true);
+        let start = Instant::now();
+        {
+            let _render = timer(Subsystem::Render);
+            std::thread::sleep(Duration::from_millis(5));
+            {
+                let _nn = timer(Subsystem::NNInference);
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let outer_ms = start.elapsed().as_secs_f64() * 1000.0;
+        set_enabled(false);
+        assert!(timer(Subsystem::Render).is_none());
+
+        let ms = |s: Subsystem| COUNTERS[s as usize].nanos.load(Ordering::Relaxed) as f64 / 1e6;
+        let calls = |s: Subsystem| COUNTERS[s as usize].calls.load(Ordering::Relaxed);
+        assert_eq!(
+            (calls(Subsystem::Render), calls(Subsystem::NNInference)),
+            (1, 1)
+        );
+        assert!(ms(Subsystem::NNInference) >= 20.0);
+        assert!(ms(Subsystem::Render) >= 10.0);
+        // The nested time is not counted twice
+        assert!((outer_ms - ms(Subsystem::Render) - ms(Subsystem::NNInference)).abs() < 1.0);
+    }
+}
@@ -0,0 +1,135 @@
+//! Stagnation-based draw adjudication
+//!
+//! Engines that cannot make progress tend to shuffle pieces until the move
+//! limit. A ply is progress when it captures, promotes or moves a pawn; once
+//! the quiet stretch reaches `stagnation_plies`, the game is adjudicated a
+//! draw if both sides' latest searches stay within a score window. The window
+//! widens every `widen_every_plies` quiet plies, and at `max_quiet_plies` the
+//! game is drawn regardless of the score.
+
+use crate::core::ruleset::AdjudicationConfig;
+use crate::core::{Board, Move, PieceKind};
+
+/// Whether `mv` (not yet applied to `board`) resets the stagnation counter
+pub fn is_progress(board: &Board, mv: &Move) -> bool {
+    match *mv {
+        Move::Normal { from, to, promote } => {
+            promote.is_some()
+                || board.get_piece(to).is_some()
+                || board
+                    .get_piece(from)
+                    .is_some_and(|p| matches!(p.kind, PieceKind::S_Pawn | PieceKind::C_Pawn))
+        }
+        Move::Drop { .. } => false,
+    }
+}
+
+/// Counts consecutive plies without progress
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StagnationTracker {
+    quiet_plies: usize,
+}
+
+impl StagnationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call with the board before `mv` is applied
+    pub fn record(&mut self, board: &Board, mv: &Move) {
+        if is_progress(board, mv) {
+            self.quiet_plies = 0;
+        } else {
+            self.quiet_plies += 1;
+        }
+    }
+
+    pub fn quiet_plies(&self) -> usize {
+        self.quiet_plies
+    }
+
+    /// Largest absolute score (centipawns) still adjudicated as a draw, or
+    /// `None` while the game has not stagnated long enough
+    pub fn draw_window(&self, config: &AdjudicationConfig) -> Option<i32> {
+        if !config.enabled || self.quiet_plies < config.stagnation_plies {
+            return None;
+        }
+        if self.quiet_plies >= config.max_quiet_plies {
+            return Some(i32::MAX);
+        }
+        let steps = (self.quiet_plies - config.stagnation_plies) / config.widen_every_plies.max(1);
+        let window = config
+            .draw_score_centipawns
+            .saturating_add(config.widen_step_centipawns.saturating_mul(steps as i32));
+        Some(window.min(config.max_draw_score_centipawns))
+    }
+
+    /// Whether the game should end as a draw given the latest search scores
+    /// of both players (any perspective, only magnitudes are compared)
+    pub fn should_adjudicate(&self, config: &AdjudicationConfig, recent_scores: &[i32]) -> bool {
+        match self.draw_window(config) {
+            Some(i32::MAX) => true,
+            Some(window) => {
+                recent_scores.len() >= 2 && recent_scores.iter().all(|s| s.abs() <= window)
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AdjudicationConfig {
+        AdjudicationConfig {
+            enabled: true,
+            stagnation_plies: 10,
+            draw_score_centipawns: 100,
+            widen_every_plies: 5,
+            widen_step_centipawns: 50,
+            max_draw_score_centipawns: 200,
+            max_quiet_plies: 40,
+        }
+    }
+
+    #[test]
+    fn test_window_widens_with_stagnation() {
+        let config = config();
+        let mut tracker = StagnationTracker::new();
+        tracker.quiet_plies = 9;
+        assert_eq!(tracker.draw_window(&config), None);
+        tracker.quiet_plies = 10;
+        assert_eq!(tracker.draw_window(&config), Some(100));
+        assert!(!tracker.should_adjudicate(&config, &[150, -20]));
+        tracker.quiet_plies = 15;
+        assert!(tracker.should_adjudicate(&config, &[150, -20]));
+        tracker.quiet_plies = 35;
+        assert_eq!(tracker.draw_window(&config), Some(200));
+        assert!(!tracker.should_adjudicate(&config, &[900, -900]));
+        tracker.quiet_plies = 40;
+        assert!(tracker.should_adjudicate(&config, &[900, -900]));
+    }
+
+    #[test]
+    fn test_pawn_moves_reset_counter() {
+        let board = crate::selfplay::BoardSetupType::ShogiOnly.create_board();
+        let player = crate::core::PlayerId::Player1;
+        let moves = crate::logic::legal_moves(&board, player);
+        let is_pawn = |mv: &Move| match *mv {
+            Move::Normal { from, .. } => board
+                .get_piece(from)
+                .is_some_and(|p| p.kind == PieceKind::S_Pawn),
+            Move::Drop { .. } => false,
+        };
+        let pawn_move = moves.iter().find(|mv| is_pawn(mv)).unwrap();
+        let quiet_move = moves.iter().find(|mv| !is_pawn(mv)).unwrap();
+
+        let mut tracker = StagnationTracker::new();
+        tracker.record(&board, quiet_move);
+        tracker.record(&board, quiet_move);
+        assert_eq!(tracker.quiet_plies(), 2);
+        tracker.record(&board, pawn_move);
+        assert_eq!(tracker.quiet_plies(), 0);
+    }
+}
@@ -0,0 +1,220 @@
+//! Model arena: a candidate ONNX model against the current best one
+//!
+//! The candidate plays the best model as a two-engine gauntlet (both on the
+//! NeuralNetwork evaluator, colors alternating). If its score reaches the
+//! threshold it is promoted: copied to `models/best/model.onnx`, which
+//! `ai_config.json` can point to, and recorded in `models/best/best.json`.
+//! A copy rather than a symlink keeps this working on Windows. Without a best
+//! model yet, the candidate is promoted without playing. The candidate's
+//! measured Elo is recorded in the models manifest either way.
+
+use super::elo::EloEstimate;
+use super::tournament::{run_tournament, EngineSpec, TournamentConfig, TournamentFormat};
+use super::BoardSetupType;
+use crate::ml::model_registry::{ManifestEntry, ModelLineage, ModelManifest};
+use crate::ml::nn_evaluator::NNEvaluator;
+use crate::player::ai::AIStrength;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const BEST_DIR: &str = "models/best";
+pub const BEST_MODEL_FILE: &str = "model.onnx";
+pub const MANIFEST_FILE: &str = "best.json";
+
+pub struct ArenaConfig {
+    pub candidate: PathBuf,
+    /// Directory holding the best model and its manifest
+    pub best_dir: PathBuf,
+    pub board_setups: Vec<BoardSetupType>,
+    /// Games per board setup
+    pub games: usize,
+    /// Score (win = 1, draw = 0.5) the candidate needs to be promoted
+    pub threshold: f64,
+    pub strength: AIStrength,
+    pub use_parallel: bool,
+    pub random_opening_plies: usize,
+    /// Play the match but never promote
+    pub dry_run: bool,
+}
+
+/// Outcome of an arena match, from the candidate's point of view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArenaResult {
+    pub candidate: String,
+    pub best: String,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// Win = 1, draw = 0.5, per game
+    pub score: f64,
+    pub threshold: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elo: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elo_error_95: Option<f64>,
+    pub promoted: bool,
+}
+
+/// `best.json`: which model `model.onnx` is and why it was promoted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BestModelManifest {
+    /// Path the promoted model was copied from
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<ModelLineage>,
+    pub promoted_at: String,
+    /// Match that promoted it; `None` for the first best model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arena: Option<ArenaResult>,
+    /// Sources of the previous best models, most recent first
+    #[serde(default)]
+    pub previous: Vec<String>,
+}
+
+pub fn read_manifest(best_dir: &Path) -> Option<BestModelManifest> {
+    let path = best_dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        return None;
+    }
+    crate::util::read_json_or_skip(&path).ok().flatten()
+}
+
+fn read_lineage(model: &Path) -> Option<ModelLineage> {
+    let metadata = crate::ml::onnx::read_metadata(model).ok()?;
+    let lineage = ModelLineage::from_custom(|key| metadata.get(key).cloned());
+    (!lineage.is_empty()).then_some(lineage)
+}
+
+/// Make `candidate` the best model
+pub fn promote(
+    candidate: &Path,
+    best_dir: &Path,
+    arena: Option<ArenaResult>,
+) -> anyhow::Result<BestModelManifest> {
+    std::fs::create_dir_all(best_dir)?;
+    let mut previous = Vec::new();
+    if let Some(old) = read_manifest(best_dir) {
+        previous.push(old.source);
+        previous.extend(old.previous);
+    }
+
+    // Atomic, so a player loading the best model never sees half a file
+    crate::util::atomic_write(&best_dir.join(BEST_MODEL_FILE), std::fs::read(candidate)?)?;
+
+    let manifest = BestModelManifest {
+        source: candidate.display().to_string(),
+        lineage: read_lineage(candidate),
+        promoted_at: chrono::Local::now().to_rfc3339(),
+        arena,
+        previous,
+    };
+    crate::util::atomic_write_json(&best_dir.join(MANIFEST_FILE), &manifest, true)?;
+    Ok(manifest)
+}
+
+/// Play the candidate against the best model and promote it if it passes the
+/// threshold. `None` when there was no best model and the candidate was
+/// promoted without a match.
+pub fn run_arena(config: &ArenaConfig) -> anyhow::Result<Option<ArenaResult>> {
+    if !config.candidate.is_file() {
+        anyhow::bail!("Candidate model not found: {}", config.candidate.display());
+    }
+    let best_model = config.best_dir.join(BEST_MODEL_FILE);
+    if !best_model.is_file() {
+        if !config.dry_run {
+            promote(&config.candidate, &config.best_dir, None)?;
+        }
+        return Ok(None);
+    }
+
+    // Players fall back to Handcrafted when a model fails to load, which
+    // would make the match meaningless
+    for model in [&config.candidate, &best_model] {
+        NNEvaluator::load_silent(&model.display().to_string())
+            .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", model.display(), e))?;
+    }
+
+    let best_name = read_manifest(&config.best_dir)
+        .map(|m| m.source)
+        .unwrap_or_else(|| best_model.display().to_string());
+    let engine = |name: &str, path: &Path| EngineSpec {
+        name: name.to_string(),
+        strength: config.strength,
+        evaluator: "NeuralNetwork".to_string(),
+        model_path: Some(path.display().to_string()),
+    };
+    let tournament = TournamentConfig {
+        engines: vec![
+            engine("candidate", &config.candidate),
+            engine("best", &best_model),
+        ],
+        format: TournamentFormat::Gauntlet,
+        board_setups: config.board_setups.clone(),
+        games_per_pairing: config.games,
+        use_parallel: config.use_parallel,
+        random_opening_plies: config.random_opening_plies,
+    };
+    let report = run_tournament(&tournament)?;
+
+    let (wins, draws, losses) = report.pairings.iter().fold((0, 0, 0), |(w, d, l), p| {
+        (w + p.a_wins, d + p.draws, l + p.b_wins)
+    });
+    let games = wins + draws + losses;
+    let score = if games > 0 {
+        (wins as f64 + draws as f64 / 2.0) / games as f64
+    } else {
+        0.0
+    };
+    let elo = EloEstimate::from_counts(wins, draws, losses);
+    let mut result = ArenaResult {
+        candidate: config.candidate.display().to_string(),
+        best: best_name,
+        wins,
+        draws,
+        losses,
+        score,
+        threshold: config.threshold,
+        elo: elo.as_ref().map(|e| e.elo),
+        elo_error_95: elo.as_ref().map(|e| e.error_95),
+        promoted: false,
+    };
+    if result.elo.is_some() {
+        ModelManifest::record(
+            &config.candidate,
+            ManifestEntry {
+                arena_elo: result.elo,
+                ..Default::default()
+            },
+        )?;
+    }
+    if games > 0 && score >= config.threshold && !config.dry_run {
+        result.promoted = true;
+        promote(&config.candidate, &config.best_dir, Some(result.clone()))?;
+    }
+    Ok(Some(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promotion_keeps_history() {
+        let dir = std::env::temp_dir().join(format!("aho_arena_{}", std::process::id()));
+        let best_dir = dir.join("best");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (first, second) = (dir.join("a.onnx"), dir.join("b.onnx"));
+        std::fs::write(&first, b"first").unwrap();
+        std::fs::write(&second, b"second").unwrap();
+
+        promote(&first, &best_dir, None).unwrap();
+        let manifest = promote(&second, &best_dir, None).unwrap();
+        let model = std::fs::read(best_dir.join(BEST_MODEL_FILE)).unwrap();
+        let stored = read_manifest(&best_dir).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(model, b"second");
+        assert_eq!(manifest.previous, vec![first.display().to_string()]);
+        assert_eq!(stored.source, second.display().to_string());
+    }
+}
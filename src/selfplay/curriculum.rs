@@ -0,0 +1,254 @@
+//! Curriculum shards of the exported dataset
+//!
+//! Splits a run's dataset by game phase (opening / midgame / endgame, from the
+//! non-king material on the board, with the handcrafted evaluator's
+//! thresholds) and by material balance (board and hand, for the side to move),
+//! so training can weight or stage positions by kind. Both are read back from
+//! the stored features. Each non-empty bucket is written to
+//! `curriculum/{phase}_{balance}.bin` in the dataset layout, described by
+//! `curriculum/manifest.json`.
+
+use super::dataset::{self, DatasetRecord};
+use crate::core::PieceKind;
+use crate::ml::features::BoardFeatureExtractor;
+use crate::player::ai::eval::{phase_for_material, piece_val, GamePhase};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const CURRICULUM_DIR: &str = "curriculum";
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// Material difference (centipawns) from which a position counts as imbalanced
+pub const DEFAULT_IMBALANCE_THRESHOLD: i32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurriculumConfig {
+    pub imbalance_threshold: i32,
+}
+
+impl Default for CurriculumConfig {
+    fn default() -> Self {
+        Self {
+            imbalance_threshold: DEFAULT_IMBALANCE_THRESHOLD,
+        }
+    }
+}
+
+/// Phase and material balance of a position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub phase: GamePhase,
+    pub imbalanced: bool,
+}
+
+impl Bucket {
+    pub fn phase_name(&self) -> &'static str {
+        match self.phase {
+            GamePhase::Opening => "opening",
+            GamePhase::Midgame => "midgame",
+            GamePhase::Endgame => "endgame",
+        }
+    }
+
+    pub fn balance_name(&self) -> &'static str {
+        if self.imbalanced {
+            "imbalanced"
+        } else {
+            "balanced"
+        }
+    }
+
+    /// Shard file name, e.g. `endgame_imbalanced.bin`
+    pub fn file_name(&self) -> String {
+        format!("{}_{}.bin", self.phase_name(), self.balance_name())
+    }
+
+    fn order(&self) -> usize {
+        let phase = match self.phase {
+            GamePhase::Opening => 0,
+            GamePhase::Midgame => 1,
+            GamePhase::Endgame => 2,
+        };
+        phase * 2 + self.imbalanced as usize
+    }
+}
+
+/// One shard in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurriculumShard {
+    pub file: String,
+    pub phase: String,
+    pub balance: String,
+    pub positions: usize,
+    /// Sum of record weights (positions before dedup)
+    pub weight: u64,
+    /// Weighted mean outcome for the side to move
+    pub avg_outcome: f64,
+}
+
+/// `curriculum/manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurriculumManifest {
+    /// Dataset file the shards were made from
+    pub source: String,
+    pub imbalance_threshold: i32,
+    pub positions: usize,
+    pub shards: Vec<CurriculumShard>,
+}
+
+fn is_king(kind: PieceKind) -> bool {
+    matches!(kind, PieceKind::S_King | PieceKind::C_King)
+}
+
+/// Bucket of a record, from its features
+pub fn classify(features: &[f32], config: &CurriculumConfig) -> Bucket {
+    let pieces = BoardFeatureExtractor::decode_pieces(features);
+    // Kings are left out: with them every position would count as the opening
+    let board_material: i32 = pieces
+        .on_board
+        .iter()
+        .filter(|(kind, _)| !is_king(*kind))
+        .map(|&(kind, _)| piece_val(kind))
+        .sum();
+    let balance: i32 = pieces
+        .on_board
+        .iter()
+        .chain(&pieces.in_hand)
+        .filter(|(kind, _)| !is_king(*kind))
+        .map(|&(kind, own)| {
+            if own {
+                piece_val(kind)
+            } else {
+                -piece_val(kind)
+            }
+        })
+        .sum();
+    Bucket {
+        phase: phase_for_material(board_material),
+        imbalanced: balance.abs() >= config.imbalance_threshold,
+    }
+}
+
+/// Shard the run's dataset (the deduplicated one if present) into
+/// [`CURRICULUM_DIR`]. `None` when the run exported nothing.
+pub fn write_curriculum(
+    dir: &Path,
+    config: &CurriculumConfig,
+) -> anyhow::Result<Option<CurriculumManifest>> {
+    let source = [dataset::DEDUP_FILE, dataset::DATASET_FILE]
+        .into_iter()
+        .find(|file| dir.join(file).exists());
+    let Some(source) = source else {
+        return Ok(None);
+    };
+    let records = dataset::read_dataset(&dir.join(source))?;
+    let positions = records.len();
+
+    let mut buckets: Vec<(Bucket, Vec<DatasetRecord>)> = Vec::new();
+    for record in records {
+        let bucket = classify(&record.features, config);
+        match buckets.iter_mut().find(|(b, _)| *b == bucket) {
+            Some((_, shard)) => shard.push(record),
+            None => buckets.push((bucket, vec![record])),
+        }
+    }
+    buckets.sort_by_key(|(bucket, _)| bucket.order());
+
+    let out = dir.join(CURRICULUM_DIR);
+    if out.exists() {
+        // Stale shards of an earlier pass must not linger next to the new manifest
+        std::fs::remove_dir_all(&out)?;
+    }
+    std::fs::create_dir_all(&out)?;
+
+    let mut shards = Vec::new();
+    for (bucket, records) in &buckets {
+        let file = bucket.file_name();
+        dataset::write_records(&out.join(&file), records)?;
+        let weight: u64 = records.iter().map(|r| r.weight as u64).sum();
+        let outcome: f64 = records
+            .iter()
+            .map(|r| r.outcome as f64 * r.weight as f64)
+            .sum();
+        shards.push(CurriculumShard {
+            file,
+            phase: bucket.phase_name().to_string(),
+            balance: bucket.balance_name().to_string(),
+            positions: records.len(),
+            weight,
+            avg_outcome: if weight > 0 {
+                outcome / weight as f64
+            } else {
+                0.0
+            },
+        });
+    }
+
+    let manifest = CurriculumManifest {
+        source: source.to_string(),
+        imbalance_threshold: config.imbalance_threshold,
+        positions,
+        shards,
+    };
+    crate::util::atomic_write_json(&out.join(MANIFEST_FILE), &manifest, true)?;
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Move, PlayerId, Position};
+    use crate::logic::ZobristHasher;
+    use crate::selfplay::BoardSetupType;
+
+    #[test]
+    fn test_shards_by_phase_and_balance() {
+        let config = CurriculumConfig::default();
+        let start = BoardSetupType::ShogiOnly.create_board();
+        let mut endgame = start.clone();
+        endgame.pieces.retain(|_, p| is_king(p.kind));
+        let rook = start
+            .pieces
+            .iter()
+            .find(|(_, p)| p.kind == PieceKind::S_Rook && p.owner == PlayerId::Player1)
+            .map(|(pos, p)| (*pos, p.clone()))
+            .unwrap();
+        endgame.pieces.insert(rook.0, rook.1);
+
+        let record = |game_num, board: &crate::core::Board| DatasetRecord {
+            game_num,
+            move_number: 1,
+            player: PlayerId::Player1,
+            hash: ZobristHasher::compute_hash(board, PlayerId::Player1),
+            outcome: 1.0,
+            weight: 2,
+            score: 0,
+            depth: 1,
+            mirrored: false,
+            chosen: Move::Drop {
+                kind: PieceKind::S_Pawn,
+                to: Position::new(0, 0),
+            },
+            features: BoardFeatureExtractor::extract(board, PlayerId::Player1),
+        };
+        let opening = classify(&record(1, &start).features, &config);
+        assert_eq!(opening.file_name(), "opening_balanced.bin");
+        let lone_rook = classify(&record(2, &endgame).features, &config);
+        assert_eq!(lone_rook.file_name(), "endgame_imbalanced.bin");
+
+        let dir = std::env::temp_dir().join(format!("aho_curriculum_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dataset::append_records(&dir, &[record(1, &start), record(2, &endgame)]).unwrap();
+        let manifest = write_curriculum(&dir, &config).unwrap().unwrap();
+        let shard = dataset::read_dataset(&dir.join(CURRICULUM_DIR).join("endgame_imbalanced.bin"));
+        let stored = std::fs::read_to_string(dir.join(CURRICULUM_DIR).join(MANIFEST_FILE));
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(manifest.source, dataset::DATASET_FILE);
+        assert_eq!(manifest.positions, 2);
+        let files: Vec<&str> = manifest.shards.iter().map(|s| s.file.as_str()).collect();
+        assert_eq!(files, ["opening_balanced.bin", "endgame_imbalanced.bin"]);
+        assert_eq!(manifest.shards[1].weight, 2);
+        assert_eq!(shard.unwrap()[0].game_num, 2);
+        assert!(stored.unwrap().contains("\"balance\": \"imbalanced\""));
+    }
+}
@@ -0,0 +1,481 @@
+//! Binary training-data export
+//!
+//! When enabled, every searched position of a selfplay game is appended to
+//! `dataset.bin` in the run directory as soon as the game ends, so training
+//! does not have to replay JSON kifu through `extract_features`.
+//!
+//! At the end of the run the positions are deduplicated by Zobrist hash into
+//! `dataset_dedup.bin` (same layout), so repeated openings do not dominate
+//! training; merged records carry the averaged outcome and score.
+//!
+//! With mirroring enabled, each position is also exported reflected left-right
+//! (features, hash and move), doubling the data: the movement rules do not
+//! depend on the file, so the mirrored position has the same outcome and score.
+//!
+//! Layout (little-endian):
+//! - Header: magic `AHOD`, format version `u16`, feature vector length `u32`
+//! - One record per position:
+//!   - `game_num: u32`, `move_number: u16` (1-based ply), `player: u8` (1 or 2)
+//!   - `hash: u64`: Zobrist hash of the position with `player` to move
+//!   - `outcome: f32`: +1 win / 0 draw / -1 loss for the side to move,
+//!     averaged over merged positions
+//!   - `weight: u32`: number of positions merged into the record
+//!   - `score: i32`: search score for the side to move, `depth: u8`
+//!   - `mirrored: u8`: 1 for the left-right reflection of a played position
+//!   - move: `tag: u8` (0 normal, 1 drop), then `from.x, from.y, to.x, to.y,
+//!     promote` for a normal move or `kind, 0, to.x, to.y, 0xFF` for a drop;
+//!     piece kinds are [`PieceKind`] declaration indices, `0xFF` means none
+//!   - features, sparse: `count: u16`, then `count` x (`index: u16`, `value: f32`)
+//!
+//! Positions from the random opening are not searched and are not exported.
+//! A merged record keeps the move, features and game of its first occurrence.
+
+use super::GameResult;
+use crate::core::{Board, Move, PieceKind, PlayerId, Position};
+use crate::game::ThinkingInfo;
+use crate::logic::{apply_move, ZobristHasher};
+use crate::ml::features::BoardFeatureExtractor;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub const DATASET_FILE: &str = "dataset.bin";
+pub const DEDUP_FILE: &str = "dataset_dedup.bin";
+const MAGIC: &[u8; 4] = b"AHOD";
+const FORMAT_VERSION: u16 = 3;
+const HEADER_LEN: usize = 10;
+const NO_PIECE: u8 = 0xFF;
+
+/// One exported position
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetRecord {
+    pub game_num: usize,
+    pub move_number: usize,
+    pub player: PlayerId,
+    pub hash: u64,
+    /// +1 win, 0 draw, -1 loss for `player` (averaged once merged)
+    pub outcome: f32,
+    /// Number of positions merged into this record
+    pub weight: u32,
+    /// Search score for `player`
+    pub score: i32,
+    pub depth: usize,
+    /// Reflected left-right from the played position (`chosen` too)
+    pub mirrored: bool,
+    pub chosen: Move,
+    pub features: Vec<f32>,
+}
+
+/// Build the records of a finished game by replaying its moves from `board`.
+/// `thinking` scores are Player1's perspective, as recorded by selfplay.
+/// With `mirror`, each position is followed by its left-right reflection
+/// (unless the position is symmetric).
+pub fn game_records(
+    board: Board,
+    moves: &[Move],
+    thinking: &[ThinkingInfo],
+    result: &GameResult,
+    mirror: bool,
+) -> Vec<DatasetRecord> {
+    let mut records = Vec::new();
+    let mut board = board;
+    let mut player = PlayerId::Player1;
+
+    for (i, mv) in moves.iter().enumerate() {
+        let move_number = i + 1;
+        if let Some(info) = thinking.iter().find(|t| t.move_number == move_number) {
+            let outcome = match result.winner {
+                Some(w) if w == player => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            };
+            let record = DatasetRecord {
+                game_num: result.game_num,
+                move_number,
+                player,
+                hash: ZobristHasher::compute_hash(&board, player),
+                outcome,
+                weight: 1,
+                score: if player == PlayerId::Player1 {
+                    info.score
+                } else {
+                    -info.score
+                },
+                depth: info.depth,
+                mirrored: false,
+                chosen: *mv,
+                features: BoardFeatureExtractor::extract(&board, player),
+            };
+            let reflection = mirror
+                .then(|| mirror_record(&record, &board))
+                // A symmetric position would only be counted twice
+                .filter(|r| r.hash != record.hash);
+            records.push(record);
+            records.extend(reflection);
+        }
+        board = apply_move(&board, mv, player);
+        player = player.opponent();
+    }
+
+    records
+}
+
+/// The left-right reflection of `record`, played from `board`
+fn mirror_record(record: &DatasetRecord, board: &Board) -> DatasetRecord {
+    let mirrored = board.mirrored();
+    DatasetRecord {
+        hash: ZobristHasher::compute_hash(&mirrored, record.player),
+        mirrored: true,
+        chosen: record.chosen.mirrored(board.width),
+        features: BoardFeatureExtractor::extract(&mirrored, record.player),
+        ..record.clone()
+    }
+}
+
+/// Merge records of the same position (Zobrist hash), averaging outcome and
+/// score by weight. A position recorded twice for the same game and ply (a
+/// game replayed after an interrupted run) counts once, keeping the last copy.
+pub fn dedup_records(records: Vec<DatasetRecord>) -> Vec<DatasetRecord> {
+    let mut latest: HashMap<(usize, usize, bool), DatasetRecord> = HashMap::new();
+    let mut order = Vec::new();
+    for record in records {
+        let key = (record.game_num, record.move_number, record.mirrored);
+        if latest.insert(key, record).is_none() {
+            order.push(key);
+        }
+    }
+
+    let mut merged: Vec<DatasetRecord> = Vec::new();
+    let mut index_of: HashMap<u64, usize> = HashMap::new();
+    for record in order.iter().filter_map(|key| latest.remove(key)) {
+        match index_of.get(&record.hash) {
+            Some(&i) => {
+                let into = &mut merged[i];
+                let (w0, w1) = (into.weight as f64, record.weight as f64);
+                let total = w0 + w1;
+                into.outcome =
+                    ((into.outcome as f64 * w0 + record.outcome as f64 * w1) / total) as f32;
+                into.score =
+                    ((into.score as f64 * w0 + record.score as f64 * w1) / total).round() as i32;
+                into.depth = into.depth.max(record.depth);
+                into.weight += record.weight;
+            }
+            None => {
+                index_of.insert(record.hash, merged.len());
+                merged.push(record);
+            }
+        }
+    }
+    merged
+}
+
+/// Deduplicate the run's dataset into [`DEDUP_FILE`]. Returns the number of
+/// positions before and after, or `None` when the run exported nothing.
+pub fn write_dedup(dir: &Path) -> anyhow::Result<Option<(usize, usize)>> {
+    let path = dir.join(DATASET_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let records = read_dataset(&path)?;
+    let total = records.len();
+    let merged = dedup_records(records);
+    write_records(&dir.join(DEDUP_FILE), &merged)?;
+    Ok(Some((total, merged.len())))
+}
+
+/// Write `records` as a complete dataset file, replacing any existing one
+pub fn write_records(path: &Path, records: &[DatasetRecord]) -> anyhow::Result<()> {
+    let mut buf = header();
+    for record in records {
+        encode_record(record, &mut buf);
+    }
+    crate::util::atomic_write(path, buf)?;
+    Ok(())
+}
+
+fn header() -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN);
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    buf.extend_from_slice(&(BoardFeatureExtractor::feature_size() as u32).to_le_bytes());
+    buf
+}
+
+/// Append records to the run's dataset, writing the header first if the file
+/// is new. Each game is a single write so concurrent workers do not interleave.
+pub fn append_records(dir: &Path, records: &[DatasetRecord]) -> anyhow::Result<()> {
+    let path = dir.join(DATASET_FILE);
+    let mut buf = Vec::new();
+    if path.exists() {
+        // Never mix record layouts in one file (a run resumed across versions)
+        let mut existing = [0u8; HEADER_LEN];
+        std::fs::File::open(&path)?.read_exact(&mut existing)?;
+        if existing != header()[..] {
+            anyhow::bail!(
+                "{} was written with a different dataset format; move it away to continue",
+                path.display()
+            );
+        }
+    } else {
+        buf = header();
+    }
+    for record in records {
+        encode_record(record, &mut buf);
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(&buf)?;
+    Ok(())
+}
+
+fn encode_record(record: &DatasetRecord, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&(record.game_num as u32).to_le_bytes());
+    buf.extend_from_slice(&(record.move_number as u16).to_le_bytes());
+    buf.push(match record.player {
+        PlayerId::Player1 => 1,
+        PlayerId::Player2 => 2,
+    });
+    buf.extend_from_slice(&record.hash.to_le_bytes());
+    buf.extend_from_slice(&record.outcome.to_le_bytes());
+    buf.extend_from_slice(&record.weight.to_le_bytes());
+    buf.extend_from_slice(&record.score.to_le_bytes());
+    buf.push(record.depth.min(u8::MAX as usize) as u8);
+    buf.push(record.mirrored as u8);
+    buf.extend_from_slice(&encode_move(record.chosen));
+
+    let nonzero: Vec<(usize, f32)> = record
+        .features
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, v)| v != 0.0)
+        .collect();
+    buf.extend_from_slice(&(nonzero.len() as u16).to_le_bytes());
+    for (index, value) in nonzero {
+        buf.extend_from_slice(&(index as u16).to_le_bytes());
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn encode_move(mv: Move) -> [u8; 6] {
+    match mv {
+        Move::Normal { from, to, promote } => [
+            0,
+            from.x as u8,
+            from.y as u8,
+            to.x as u8,
+            to.y as u8,
+            promote.map(PieceKind::code).unwrap_or(NO_PIECE),
+        ],
+        Move::Drop { kind, to } => [1, kind.code(), 0, to.x as u8, to.y as u8, NO_PIECE],
+    }
+}
+
+fn decode_move(bytes: [u8; 6]) -> anyhow::Result<Move> {
+    let kind = |code: u8| {
+        PieceKind::from_code(code).ok_or_else(|| anyhow::anyhow!("Invalid piece code {}", code))
+    };
+    let to = Position::new(bytes[3] as usize, bytes[4] as usize);
+    match bytes[0] {
+        0 => Ok(Move::Normal {
+            from: Position::new(bytes[1] as usize, bytes[2] as usize),
+            to,
+            promote: if bytes[5] == NO_PIECE {
+                None
+            } else {
+                Some(kind(bytes[5])?)
+            },
+        }),
+        1 => Ok(Move::Drop {
+            kind: kind(bytes[1])?,
+            to,
+        }),
+        tag => anyhow::bail!("Invalid move tag {}", tag),
+    }
+}
+
+/// Read a dataset file back. A record cut off by an interruption ends the file.
+pub fn read_dataset(path: &Path) -> anyhow::Result<Vec<DatasetRecord>> {
+    let mut data = Vec::new();
+    std::fs::File::open(path)?.read_to_end(&mut data)?;
+    let mut r = &data[..];
+
+    let mut header = [0u8; HEADER_LEN];
+    r.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        anyhow::bail!("{} is not a selfplay dataset", path.display());
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != FORMAT_VERSION {
+        anyhow::bail!("Unsupported dataset version {}", version);
+    }
+    let feature_size = u32::from_le_bytes(header[6..10].try_into()?) as usize;
+
+    let mut records = Vec::new();
+    while !r.is_empty() {
+        match decode_record(&mut r, feature_size) {
+            Ok(record) => records.push(record),
+            Err(_) => break,
+        }
+    }
+    Ok(records)
+}
+
+fn decode_record(r: &mut &[u8], feature_size: usize) -> anyhow::Result<DatasetRecord> {
+    let mut fixed = [0u8; 35];
+    r.read_exact(&mut fixed)?;
+    let game_num = u32::from_le_bytes(fixed[0..4].try_into()?) as usize;
+    let move_number = u16::from_le_bytes([fixed[4], fixed[5]]) as usize;
+    let player = match fixed[6] {
+        1 => PlayerId::Player1,
+        2 => PlayerId::Player2,
+        p => anyhow::bail!("Invalid player {}", p),
+    };
+    let hash = u64::from_le_bytes(fixed[7..15].try_into()?);
+    let outcome = f32::from_le_bytes(fixed[15..19].try_into()?);
+    let weight = u32::from_le_bytes(fixed[19..23].try_into()?);
+    let score = i32::from_le_bytes(fixed[23..27].try_into()?);
+    let depth = fixed[27] as usize;
+    let mirrored = fixed[28] != 0;
+    let chosen = decode_move(fixed[29..35].try_into()?)?;
+
+    let mut count = [0u8; 2];
+    r.read_exact(&mut count)?;
+    let mut features = vec![0.0; feature_size];
+    for _ in 0..u16::from_le_bytes(count) {
+        let mut entry = [0u8; 6];
+        r.read_exact(&mut entry)?;
+        let index = u16::from_le_bytes([entry[0], entry[1]]) as usize;
+        let value = f32::from_le_bytes(entry[2..6].try_into()?);
+        *features
+            .get_mut(index)
+            .ok_or_else(|| anyhow::anyhow!("Feature index {} out of range", index))? = value;
+    }
+
+    Ok(DatasetRecord {
+        game_num,
+        move_number,
+        player,
+        hash,
+        outcome,
+        weight,
+        score,
+        depth,
+        mirrored,
+        chosen,
+        features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selfplay::BoardSetupType;
+
+    #[test]
+    fn test_dataset_roundtrip() {
+        let board = BoardSetupType::ShogiOnly.create_board();
+        let moves: Vec<Move> = {
+            let mut b = board.clone();
+            let mut player = PlayerId::Player1;
+            (0..3)
+                .map(|_| {
+                    let mv = crate::logic::legal_moves(&b, player)[0];
+                    b = apply_move(&b, &mv, player);
+                    player = player.opponent();
+                    mv
+                })
+                .collect()
+        };
+        // Ply 1 came from the random opening and has no search info
+        let thinking: Vec<ThinkingInfo> = [(2, 30), (3, -12)]
+            .iter()
+            .map(|&(move_number, score)| ThinkingInfo {
+                move_number,
+                player: String::new(),
+                depth: 4,
+                score,
+                nodes: 0,
+                time_ms: 0,
+                max_depth: 4,
+                depth_profile: None,
+                health: None,
+            })
+            .collect();
+        let result = GameResult {
+            game_num: 7,
+            winner: Some(PlayerId::Player2),
+            moves: 3,
+            time_ms: 0,
+            material_diff: 0,
+            avg_move_time_ms: 0.0,
+            resigned: false,
+            adjudicated: false,
+            eval_trajectory: None,
+            phases: None,
+        };
+
+        let plain = game_records(board.clone(), &moves, &thinking, &result, false);
+        assert_eq!(plain.len(), 2);
+        assert_eq!(plain[0].player, PlayerId::Player2);
+        assert_eq!((plain[0].score, plain[0].outcome), (-30, 1.0));
+        assert_eq!((plain[1].score, plain[1].outcome), (-12, -1.0));
+
+        let records = game_records(board, &moves, &thinking, &result, true);
+        assert_eq!(records.len(), 4);
+        let (played, reflected) = (&records[0], &records[1]);
+        assert_eq!(played, &plain[0]);
+        assert!(reflected.mirrored);
+        assert_ne!(reflected.hash, played.hash);
+        assert_ne!(reflected.features, played.features);
+        assert_eq!(reflected.chosen, played.chosen.mirrored(9));
+        assert_eq!(reflected.chosen.mirrored(9), played.chosen);
+        assert_eq!((reflected.score, reflected.outcome), (-30, 1.0));
+        // Reflections are separate positions, not replays of the same ply
+        assert_eq!(dedup_records(records.clone()).len(), 4);
+
+        let dir = std::env::temp_dir().join(format!("aho_dataset_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        append_records(&dir, &records[..1]).unwrap();
+        append_records(&dir, &records[1..]).unwrap();
+        let loaded = read_dataset(&dir.join(DATASET_FILE)).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(loaded, records);
+    }
+
+    #[test]
+    fn test_dedup_averages_repeated_positions() {
+        let record = |game_num: usize, hash: u64, outcome: f32, score: i32| DatasetRecord {
+            game_num,
+            move_number: 1,
+            player: PlayerId::Player1,
+            hash,
+            outcome,
+            weight: 1,
+            score,
+            depth: 4,
+            mirrored: false,
+            chosen: Move::Drop {
+                kind: PieceKind::S_Pawn,
+                to: Position::new(0, 0),
+            },
+            features: Vec::new(),
+        };
+        let merged = dedup_records(vec![
+            record(1, 7, 1.0, 100),
+            record(2, 7, -1.0, 0),
+            record(3, 9, 0.0, 5),
+            // Game 2 replayed after a resume: counts once
+            record(2, 7, 0.0, 50),
+            record(4, 7, 1.0, 100),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].game_num, merged[0].weight), (1, 3));
+        assert!((merged[0].outcome - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(merged[0].score, 83);
+        assert_eq!((merged[1].hash, merged[1].weight), (9, 1));
+    }
+}
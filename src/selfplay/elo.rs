@@ -0,0 +1,211 @@
+//! Elo estimation and Sequential Probability Ratio Test (SPRT)
+//!
+//! Turns raw win/draw/loss counts from self-play into an Elo difference with
+//! error bars, and decides when a match has gathered enough evidence to stop.
+//! Results are from Player1's (AI1's) point of view.
+
+use serde::{Deserialize, Serialize};
+
+/// Elo difference corresponding to an expected score in (0, 1)
+pub fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Expected score for an Elo difference
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Mean score and per-game variance of the trinomial (win/draw/loss) distribution
+fn score_stats(wins: usize, draws: usize, losses: usize) -> Option<(f64, f64)> {
+    let n = (wins + draws + losses) as f64;
+    if n == 0.0 {
+        return None;
+    }
+    let (w, d, l) = (wins as f64 / n, draws as f64 / n, losses as f64 / n);
+    let score = w + d / 2.0;
+    let variance = w * (1.0 - score).powi(2) + d * (0.5 - score).powi(2) + l * score.powi(2);
+    Some((score, variance))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EloEstimate {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// Estimated Elo difference (AI1 - AI2)
+    pub elo: f64,
+    /// Half-width of the 95% confidence interval
+    pub error_95: f64,
+    /// Likelihood of superiority (probability that AI1 is stronger)
+    pub los: f64,
+}
+
+impl EloEstimate {
+    /// Returns `None` until at least one game has been played
+    pub fn from_counts(wins: usize, draws: usize, losses: usize) -> Option<Self> {
+        let (score, variance) = score_stats(wins, draws, losses)?;
+        let n = (wins + draws + losses) as f64;
+
+        // Clamp so a perfect score still yields a finite (if huge) estimate
+        let clamp = |s: f64| s.clamp(1e-6, 1.0 - 1e-6);
+        let stderr = (variance / n).sqrt();
+        let elo = elo_from_score(clamp(score));
+        let upper = elo_from_score(clamp(score + 1.96 * stderr));
+        let lower = elo_from_score(clamp(score - 1.96 * stderr));
+
+        let los = if wins + losses == 0 {
+            0.5
+        } else {
+            0.5 * (1.0 + erf((wins as f64 - losses as f64) / (2.0 * (wins + losses) as f64).sqrt()))
+        };
+
+        Some(Self {
+            wins,
+            draws,
+            losses,
+            elo,
+            error_95: (upper - lower) / 2.0,
+            los,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SprtConfig {
+    /// Elo difference under the null hypothesis
+    pub elo0: f64,
+    /// Elo difference under the alternative hypothesis
+    pub elo1: f64,
+    /// False positive rate
+    pub alpha: f64,
+    /// False negative rate
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SprtDecision {
+    /// Not enough evidence yet
+    Continue,
+    /// H0 accepted: the Elo difference is `elo0` (or worse)
+    AcceptH0,
+    /// H1 accepted: the Elo difference is `elo1` (or better)
+    AcceptH1,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SprtResult {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub llr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub decision: SprtDecision,
+}
+
+impl SprtConfig {
+    /// Log-likelihood ratio of H1 vs H0 (normal approximation of the trinomial model)
+    pub fn llr(&self, wins: usize, draws: usize, losses: usize) -> f64 {
+        let Some((score, variance)) = score_stats(wins, draws, losses) else {
+            return 0.0;
+        };
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let n = (wins + draws + losses) as f64;
+        let s0 = score_from_elo(self.elo0);
+        let s1 = score_from_elo(self.elo1);
+        (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance / n)
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn evaluate(&self, wins: usize, draws: usize, losses: usize) -> SprtResult {
+        let llr = self.llr(wins, draws, losses);
+        let (lower_bound, upper_bound) = self.bounds();
+        let decision = if llr >= upper_bound {
+            SprtDecision::AcceptH1
+        } else if llr <= lower_bound {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        };
+        SprtResult {
+            elo0: self.elo0,
+            elo1: self.elo1,
+            alpha: self.alpha,
+            beta: self.beta,
+            llr,
+            lower_bound,
+            upper_bound,
+            decision,
+        }
+    }
+}
+
+/// Error function (Abramowitz & Stegun 7.1.26, |error| < 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x >= 0.0 {
+        y
+    } else {
+        -y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elo_score_roundtrip() {
+        assert!(elo_from_score(0.5).abs() < 1e-9);
+        for elo in [-200.0, -50.0, 0.0, 35.0, 400.0] {
+            assert!((elo_from_score(score_from_elo(elo)) - elo).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_elo_estimate() {
+        let even = EloEstimate::from_counts(40, 20, 40).unwrap();
+        assert!(even.elo.abs() < 1e-9);
+        assert!((even.los - 0.5).abs() < 1e-9);
+
+        let ahead = EloEstimate::from_counts(60, 20, 20).unwrap();
+        assert!(ahead.elo > 100.0);
+        assert!(ahead.error_95 > 0.0);
+        assert!(ahead.los > 0.99);
+
+        assert!(EloEstimate::from_counts(0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_sprt_decisions() {
+        let sprt = SprtConfig {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        assert_eq!(sprt.evaluate(5, 5, 5).decision, SprtDecision::Continue);
+        assert_eq!(
+            sprt.evaluate(700, 200, 100).decision,
+            SprtDecision::AcceptH1
+        );
+        assert_eq!(
+            sprt.evaluate(100, 200, 700).decision,
+            SprtDecision::AcceptH0
+        );
+    }
+}
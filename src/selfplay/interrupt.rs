@@ -0,0 +1,72 @@
+//! Ctrl+C handling for selfplay runs
+//!
+//! The first Ctrl+C asks the workers to stop after their current game, so the
+//! finished games, kifu and stats are still written and the run can be
+//! resumed. A second one restores the terminal and exits at once. In raw mode
+//! (the interactive menu) Ctrl+C arrives as a key event instead of SIGINT, so
+//! the progress UI forwards it through [`request_stop`].
+
+use super::SharedProgress;
+use crossterm::{cursor, execute, terminal};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, Once};
+
+/// Run that receives Ctrl+C, if one is in progress
+static ACTIVE_RUN: Mutex<Option<Arc<SharedProgress>>> = Mutex::new(None);
+static INSTALL_HANDLER: Once = Once::new();
+
+/// Exit status of a process killed by SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Route Ctrl+C to `shared` until the returned guard is dropped
+pub(super) fn watch(shared: &Arc<SharedProgress>) -> InterruptGuard {
+    INSTALL_HANDLER.call_once(install_handler);
+    *ACTIVE_RUN.lock().unwrap() = Some(Arc::clone(shared));
+    InterruptGuard
+}
+
+pub(super) struct InterruptGuard;
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        *ACTIVE_RUN.lock().unwrap() = None;
+    }
+}
+
+fn install_handler() {
+    // The handler stays installed for the rest of the process, so Ctrl+C
+    // outside a run falls through to request_stop and exits as usual
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    handle.spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            request_stop();
+        }
+    });
+}
+
+/// Stop the active run after its current games, or exit when it was already
+/// asked to stop (or no run is active)
+pub(super) fn request_stop() {
+    let active = ACTIVE_RUN.lock().unwrap().clone();
+    match active {
+        Some(shared) if !shared.interrupted.swap(true, Ordering::Relaxed) => {
+            shared.stop_requested.store(true, Ordering::Relaxed);
+        }
+        _ => {
+            restore_terminal();
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    }
+}
+
+fn restore_terminal() {
+    // Only the interactive menu runs in raw mode on the alternate screen
+    if terminal::is_raw_mode_enabled().unwrap_or(false) {
+        terminal::disable_raw_mode().ok();
+        execute!(std::io::stdout(), terminal::LeaveAlternateScreen).ok();
+    }
+    execute!(std::io::stdout(), cursor::Show).ok();
+    println!();
+}
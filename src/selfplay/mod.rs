@@ -1,70 +1,43 @@
 use crate::core::PlayerId;
-use crate::game::{Game, KifuData, PerspectiveMode, ThinkingInfo};
+use crate::game::{
+    CriticalMoment, EvalSample, EvalTrajectory, Game, KifuData, PerspectiveMode, RuleEnding,
+    ThinkingInfo,
+};
 use crate::player::ai::{AIStrength, AlphaBetaAI};
 use crate::player::PlayerController;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use crossterm::{execute, terminal};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     Arc, Mutex,
 };
-use std::thread;
 use std::time::{Duration, Instant};
 
-#[derive(Clone, Copy, Debug)]
-pub enum BoardSetupType {
-    StandardMixed,
-    ReversedMixed,
-    ShogiOnly,
-    ChessOnly,
-    Fair,
-    ReversedFair,
-}
-
-impl std::fmt::Display for BoardSetupType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            BoardSetupType::StandardMixed => "StandardMixed",
-            BoardSetupType::ReversedMixed => "ReversedMixed",
-            BoardSetupType::ShogiOnly => "ShogiOnly",
-            BoardSetupType::ChessOnly => "ChessOnly",
-            BoardSetupType::Fair => "Fair",
-            BoardSetupType::ReversedFair => "ReversedFair",
-        };
-        write!(f, "{}", s)
-    }
-}
-
-impl BoardSetupType {
-    fn create_board(&self) -> crate::core::Board {
-        match self {
-            BoardSetupType::StandardMixed => {
-                let map = crate::core::setup::get_standard_mixed_setup();
-                crate::core::setup::setup_from_strings(&map, true, true, None, None)
-            }
-            BoardSetupType::ReversedMixed => {
-                let map = crate::core::setup::get_reversed_mixed_setup();
-                crate::core::setup::setup_from_strings(&map, true, true, None, None)
-            }
-            BoardSetupType::ShogiOnly => {
-                let map = crate::core::setup::get_shogi_setup();
-                crate::core::setup::setup_from_strings(&map, true, true, None, None)
-            }
-            BoardSetupType::ChessOnly => {
-                let map = crate::core::setup::get_chess_setup();
-                crate::core::setup::setup_from_strings(&map, false, false, None, None)
-            }
-            BoardSetupType::Fair => {
-                let map = crate::core::setup::get_fair_setup();
-                crate::core::setup::setup_from_strings(&map, true, true, None, None)
-            }
-            BoardSetupType::ReversedFair => {
-                let map = crate::core::setup::get_reversed_fair_setup();
-                crate::core::setup::setup_from_strings(&map, false, false, None, None)
-            }
-        }
-    }
+pub mod adjudication;
+pub mod arena;
+pub mod curriculum;
+pub mod dataset;
+pub mod elo;
+mod interrupt;
+pub mod phases;
+pub mod run_log;
+pub mod tournament;
+use elo::{EloEstimate, SprtConfig, SprtDecision, SprtResult};
+
+pub use crate::core::setup::BoardSetupType;
+
+/// Temperature-based move selection for the first plies of a game
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MoveSampling {
+    /// Sample while fewer than this many plies (random opening included) have been played
+    pub plies: usize,
+    /// Softmax temperature in centipawns
+    pub temperature: f64,
 }
 
 #[derive(Clone)]
@@ -76,57 +49,168 @@ pub struct SelfPlayConfig {
     pub save_kifus: bool,
     pub use_parallel: bool,
     pub update_interval_moves: usize, // How often workers update shared state
-    pub model_path: Option<String>,
+    /// Evaluator type per player ("Handcrafted", "NeuralNetwork" or "NNUE");
+    /// `None` uses the one in ai_config.json
+    pub ai1_evaluator: Option<String>,
+    pub ai2_evaluator: Option<String>,
+    /// NN model per player; `None` uses the one in ai_config.json
+    pub ai1_model_path: Option<String>,
+    pub ai2_model_path: Option<String>,
+    /// Number of uniformly random plies played before the engines take over
+    pub random_opening_plies: usize,
+    /// Start each game from a Chess960 shuffle of the setup's chess back ranks
+    pub chess960: bool,
+    /// Pieces Player1 gives up at the start of each game
+    pub handicap: Option<crate::core::handicap::Handicap>,
+    /// Sample among the top moves instead of always playing the best one
+    pub move_sampling: Option<MoveSampling>,
+    /// Stop early once the SPRT reaches a decision (`num_games` becomes the maximum)
+    pub sprt: Option<SprtConfig>,
+    /// Keep every N-th evaluation in the saved trajectories (0 disables them)
+    pub eval_sample_interval: usize,
+    /// Append searched positions to the run's binary training dataset (see [`dataset`])
+    pub export_dataset: bool,
+    /// Also export each position reflected left-right
+    pub mirror_dataset: bool,
+    /// Also split the dataset into phase / material-balance shards at the end
+    /// of the run (see [`curriculum`])
+    pub curriculum: Option<curriculum::CurriculumConfig>,
+    /// Store a full board snapshot in the kifu every N plies (0 disables them)
+    pub snapshot_interval: usize,
+    /// Seed for the random opening and move sampling; each game derives its
+    /// own generator from it, so results do not depend on scheduling
+    pub seed: Option<u64>,
+    /// Continue this run (see [`run_log`]) instead of starting a new one
+    pub resume_run_id: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct GameResult {
-    winner: Option<PlayerId>,
-    moves: usize,
-    time_ms: u128,
+impl SelfPlayConfig {
+    /// Engine for `player_id` with that player's strength, evaluator and model
+    pub fn create_ai(&self, player_id: PlayerId, name: &str) -> AlphaBetaAI {
+        let (strength, evaluator, model_path) = match player_id {
+            PlayerId::Player1 => (self.ai1_strength, &self.ai1_evaluator, &self.ai1_model_path),
+            PlayerId::Player2 => (self.ai2_strength, &self.ai2_evaluator, &self.ai2_model_path),
+        };
+        match evaluator {
+            Some(evaluator) => AlphaBetaAI::with_evaluator_type(
+                player_id,
+                name,
+                strength,
+                evaluator,
+                model_path.clone(),
+                true,
+            ),
+            None => AlphaBetaAI::new(player_id, name, strength, model_path.clone(), true),
+        }
+    }
+
+    /// Evaluator names of both players, for display
+    pub fn evaluator_label(&self) -> String {
+        let e1 = self
+            .create_ai(PlayerId::Player1, "Display")
+            .evaluator_name();
+        let e2 = self
+            .create_ai(PlayerId::Player2, "Display")
+            .evaluator_name();
+        if e1 == e2 {
+            e1
+        } else {
+            format!("{} (P1) vs {} (P2)", e1, e2)
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameResult {
+    pub game_num: usize,
+    pub winner: Option<PlayerId>,
+    pub moves: usize,
+    pub time_ms: u128,
     /// Final material difference (positive favors Player1)
-    material_diff: i32,
+    pub material_diff: i32,
     /// Average move time in milliseconds
-    avg_move_time_ms: f32,
+    pub avg_move_time_ms: f32,
+    pub resigned: bool,
+    /// Drawn by stagnation adjudication (see [`adjudication`])
+    #[serde(default)]
+    pub adjudicated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eval_trajectory: Option<EvalTrajectory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phases: Option<phases::GamePhaseStats>,
 }
 
 #[derive(Serialize)]
 pub struct SelfPlayStats {
+    pub run_id: String,
     pub total_games: usize,
     pub p1_wins: usize,
     pub p2_wins: usize,
     pub draws: usize,
     pub resignations: usize,
+    /// Draws adjudicated because the game stagnated
+    pub adjudicated_draws: usize,
     pub avg_moves: f64,
     pub avg_time_ms: f64,
     pub board_setup: String,
     pub ai1_strength: String,
     pub ai2_strength: String,
+    pub random_opening_plies: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Elo difference of AI1 (Player1) over AI2, counting resignations as results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elo: Option<EloEstimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sprt: Option<SprtResult>,
+    /// When captures, checks and promotions happen and how long each phase lasts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phases: Option<phases::PhaseSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throughput: Option<Throughput>,
+    /// Stopped by Ctrl+C before every game was played
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+    /// Per-game records, including resigned games
+    pub games: Vec<GameResult>,
 }
 
 impl SelfPlayStats {
     fn new(board_setup: String, ai1_strength: AIStrength, ai2_strength: AIStrength) -> Self {
         Self {
+            run_id: String::new(),
             total_games: 0,
             p1_wins: 0,
             p2_wins: 0,
             draws: 0,
             resignations: 0,
+            adjudicated_draws: 0,
             avg_moves: 0.0,
             avg_time_ms: 0.0,
             board_setup,
             ai1_strength: format!("{:?}", ai1_strength),
             ai2_strength: format!("{:?}", ai2_strength),
+            random_opening_plies: 0,
+            seed: None,
+            elo: None,
+            sprt: None,
+            phases: None,
+            throughput: None,
+            interrupted: false,
+            games: Vec::new(),
         }
     }
 
-    fn add_result(&mut self, result: GameResult) {
+    fn add_result(&mut self, result: &GameResult) {
         self.total_games += 1;
         match result.winner {
             Some(PlayerId::Player1) => self.p1_wins += 1,
             Some(PlayerId::Player2) => self.p2_wins += 1,
             None => self.draws += 1,
         }
+        if result.adjudicated {
+            self.adjudicated_draws += 1;
+        }
 
         // Update averages
         let n = self.total_games as f64;
@@ -147,8 +231,43 @@ pub struct GameExecutionResult {
     pub critical_moments: Vec<usize>,
     /// Whether the game ended via termination (stalemate/no legal moves) rather than checkmate
     pub was_terminated: bool,
+    /// Evaluator actually used by Player1 and Player2 (after any fallback)
+    pub evaluators: [String; 2],
     /// Whether the game ended by resignation
     pub resigned: bool,
+    /// Whether the game was drawn by stagnation adjudication
+    pub adjudicated: bool,
+}
+
+/// Speed of the games played in this session (games from before a resume excluded)
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Throughput {
+    pub elapsed_secs: f64,
+    pub games: usize,
+    pub games_per_hour: f64,
+    pub total_nodes: u64,
+    /// Nodes per second of search time, i.e. the average speed of one worker
+    pub nodes_per_second: f64,
+}
+
+impl Throughput {
+    /// Time left for `remaining` games at the current rate
+    pub fn eta(&self, remaining: usize) -> Option<Duration> {
+        (self.games_per_hour > 0.0)
+            .then(|| Duration::from_secs_f64(remaining as f64 / self.games_per_hour * 3600.0))
+    }
+}
+
+/// `1h02m`, `3m05s` or `42s`
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
 }
 
 // State for a single worker slot
@@ -165,9 +284,83 @@ struct SharedProgress {
     draws: AtomicUsize,
     total_games: usize,
     is_running: AtomicBool,
+    /// Where finished games are recorded (kifu and results log)
+    run_dir: PathBuf,
+    /// Set once the SPRT has decided or on Ctrl+C; games not yet started are skipped
+    stop_requested: AtomicBool,
+    /// Ctrl+C was pressed (a second press exits immediately)
+    interrupted: AtomicBool,
     // Statistics
     total_moves: AtomicUsize,
     termination_count: AtomicUsize, // Count of abnormal terminations
+    // Throughput of this session
+    started_at: Instant,
+    session_games: AtomicUsize,
+    session_nodes: AtomicU64,
+    session_search_ms: AtomicU64,
+}
+
+impl SharedProgress {
+    fn throughput(&self) -> Throughput {
+        let elapsed_secs = self.started_at.elapsed().as_secs_f64();
+        let games = self.session_games.load(Ordering::Relaxed);
+        let total_nodes = self.session_nodes.load(Ordering::Relaxed);
+        let search_ms = self.session_search_ms.load(Ordering::Relaxed);
+        Throughput {
+            elapsed_secs,
+            games,
+            games_per_hour: if elapsed_secs > 0.0 {
+                games as f64 / elapsed_secs * 3600.0
+            } else {
+                0.0
+            },
+            total_nodes,
+            nodes_per_second: if search_ms > 0 {
+                total_nodes as f64 / search_ms as f64 * 1000.0
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Build the (downsampled) evaluation trajectory of a game.
+/// Returns `None` when trajectories are disabled or nothing was searched.
+fn build_eval_trajectory(
+    thinking_data: &[ThinkingInfo],
+    position_evaluations: &[i32],
+    critical_moments: &[usize],
+    sample_interval: usize,
+) -> Option<EvalTrajectory> {
+    if sample_interval == 0 || position_evaluations.is_empty() {
+        return None;
+    }
+
+    let last = position_evaluations.len() - 1;
+    let samples = position_evaluations
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i % sample_interval == 0 || i == last)
+        .map(|(i, &score)| EvalSample {
+            move_number: thinking_data[i].move_number,
+            score,
+        })
+        .collect();
+
+    let critical_moments = critical_moments
+        .iter()
+        .map(|&i| CriticalMoment {
+            move_number: thinking_data[i].move_number,
+            score_before: position_evaluations[i - 1],
+            score_after: position_evaluations[i],
+        })
+        .collect();
+
+    Some(EvalTrajectory {
+        sample_interval,
+        samples,
+        critical_moments,
+    })
 }
 
 /// Compute enhanced game metrics from thinking data and final board state
@@ -181,7 +374,7 @@ fn compute_game_metrics(
 
     // 2. Calculate average move time
     let avg_move_time_ms = if !thinking_data.is_empty() {
-        let total_time: u128 = thinking_data.iter().map(|t| t.time_ms as u128).sum();
+        let total_time: u128 = thinking_data.iter().map(|t| t.time_ms).sum();
         (total_time as f32) / (thinking_data.len() as f32)
     } else {
         (duration.as_millis() as f32) / thinking_data.len().max(1) as f32
@@ -193,19 +386,8 @@ fn compute_game_metrics(
         .map(|t| t.score) // Already normalized to Player1's perspective in run_game_silent
         .collect();
 
-    // 4. Identify critical moments (large evaluation swings > 2000 centipawns)
-    let mut critical_moments = Vec::new();
-    const CRITICAL_THRESHOLD: i32 = 2000;
-
-    for i in 1..position_evaluations.len() {
-        let prev_eval = position_evaluations[i - 1];
-        let curr_eval = position_evaluations[i];
-        let swing = (curr_eval - prev_eval).abs();
-
-        if swing > CRITICAL_THRESHOLD {
-            critical_moments.push(i); // Store move index
-        }
-    }
+    // 4. Identify critical moments (large evaluation swings), as move indices
+    let critical_moments = crate::game::critical_swings(&position_evaluations);
 
     (
         material_diff,
@@ -232,6 +414,26 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
         config.ai1_strength,
         config.ai2_strength,
     );
+    stats.random_opening_plies = config.random_opening_plies;
+    stats.seed = config.seed;
+
+    // The run directory is created up front so an interrupted run can be resumed
+    let run_id = config
+        .resume_run_id
+        .clone()
+        .unwrap_or_else(|| chrono::Local::now().format("%Y%m%d_%H%M%S").to_string());
+    let run_dir = run_log::run_dir(&stats.board_setup, &run_id);
+    let previous = if config.resume_run_id.is_some() {
+        run_log::repair_results(&run_dir)?;
+        run_log::load_results(&run_dir)
+    } else {
+        run_log::write_manifest(&run_dir, &run_log::RunManifest::new(&run_id, &config))?;
+        Vec::new()
+    };
+    let pending: Vec<usize> = (1..=config.num_games)
+        .filter(|n| !previous.iter().any(|r| r.game_num == *n))
+        .collect();
+    stats.run_id = run_id.clone();
 
     let mode = if config.use_parallel {
         if rayon::current_num_threads() > 0 {
@@ -244,10 +446,33 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
     };
 
     println!("\n=== Self-Play Configuration Details ===\r");
+    println!(
+        "Run ID: {} (resume with: selfplay --resume {})\r",
+        run_id, run_id
+    );
     println!("Total Games: {}\r", config.num_games);
+    if config.resume_run_id.is_some() {
+        println!(
+            "Resuming: {} of {} games already completed\r",
+            previous.len(),
+            config.num_games
+        );
+    }
     println!("Execution Mode: {}\r", mode);
     println!("Board Setup: {}\r", config.board_setup);
     println!("Update Interval: {} moves\r", config.update_interval_moves);
+    if config.random_opening_plies > 0 {
+        println!("Random Opening: {} plies\r", config.random_opening_plies);
+    }
+    if let Some(sampling) = config.move_sampling {
+        println!(
+            "Move Sampling: first {} plies, temperature {}\r",
+            sampling.plies, sampling.temperature
+        );
+    }
+    if let Some(seed) = config.seed {
+        println!("Seed: {}\r", seed);
+    }
 
     // Determine promotion status based on board setup
     // For Mixed/Fair, both can promote. For others it might vary, but in this codebase promotion is generally enabled.
@@ -283,30 +508,64 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
                 })
                 .collect(),
         ),
-        completed_games: AtomicUsize::new(0),
-        p1_wins: AtomicUsize::new(0),
-        p2_wins: AtomicUsize::new(0),
-        draws: AtomicUsize::new(0),
+        // Resumed runs start from the recorded games
+        completed_games: AtomicUsize::new(previous.len()),
+        p1_wins: AtomicUsize::new(count_winner(&previous, Some(PlayerId::Player1))),
+        p2_wins: AtomicUsize::new(count_winner(&previous, Some(PlayerId::Player2))),
+        draws: AtomicUsize::new(count_winner(&previous, None)),
         total_games: config.num_games,
         is_running: AtomicBool::new(true),
+        run_dir: run_dir.clone(),
+        stop_requested: AtomicBool::new(false),
+        interrupted: AtomicBool::new(false),
         // Statistics
-        total_moves: AtomicUsize::new(0),
+        total_moves: AtomicUsize::new(previous.iter().map(|r| r.moves).sum()),
         termination_count: AtomicUsize::new(0),
+        started_at: Instant::now(),
+        session_games: AtomicUsize::new(0),
+        session_nodes: AtomicU64::new(0),
+        session_search_ms: AtomicU64::new(0),
     });
+    if let Some(sprt) = config.sprt {
+        let result = sprt.evaluate(
+            shared_state.p1_wins.load(Ordering::Relaxed),
+            shared_state.draws.load(Ordering::Relaxed),
+            shared_state.p2_wins.load(Ordering::Relaxed),
+        );
+        if result.decision != SprtDecision::Continue {
+            shared_state.stop_requested.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let interrupt_guard = interrupt::watch(&shared_state);
 
     // Start UI thread
     let ui_handle = {
         let shared = Arc::clone(&shared_state);
+        let sprt = config.sprt;
         std::thread::spawn(move || {
-            let mut stdout = std::io::stdout();
+            let stdout = std::io::stdout();
             use std::io::Write; // Import Write trait for flush
 
             loop {
-                std::thread::sleep(std::time::Duration::from_millis(200));
+                // Raw mode turns Ctrl+C into a key event instead of SIGINT
+                if terminal::is_raw_mode_enabled().unwrap_or(false) {
+                    if event::poll(Duration::from_millis(200)).unwrap_or(false) {
+                        if let Ok(Event::Key(key)) = event::read() {
+                            if key.code == KeyCode::Char('c')
+                                && key.modifiers.contains(KeyModifiers::CONTROL)
+                            {
+                                interrupt::request_stop();
+                            }
+                        }
+                        continue;
+                    }
+                } else {
+                    std::thread::sleep(std::time::Duration::from_millis(200));
+                }
 
-                if !shared.is_running.load(Ordering::Relaxed)
-                    && shared.completed_games.load(Ordering::Relaxed) >= shared.total_games
-                {
+                // Cleared only after every game has finished (or been skipped)
+                if !shared.is_running.load(Ordering::Relaxed) {
                     break;
                 }
 
@@ -356,17 +615,45 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
                 )
                 .ok();
 
+                let sprt_text = match sprt {
+                    Some(sprt) => {
+                        let result = sprt.evaluate(p1_w, d, p2_w);
+                        format!(
+                            ", LLR: {:.2} [{:.2}, {:.2}]",
+                            result.llr, result.lower_bound, result.upper_bound
+                        )
+                    }
+                    None => String::new(),
+                };
+                let throughput = shared.throughput();
+                let speed_text = if throughput.games > 0 {
+                    let eta = throughput
+                        .eta(total.saturating_sub(completed))
+                        .map(format_duration)
+                        .unwrap_or_else(|| "-".to_string());
+                    format!(
+                        ", {:.1} games/h, {:.0} NPS, ETA {}",
+                        throughput.games_per_hour, throughput.nodes_per_second, eta
+                    )
+                } else {
+                    String::new()
+                };
+                let interrupt_text = if shared.interrupted.load(Ordering::Relaxed) {
+                    " - Stopping after current games (Ctrl+C again to quit now)"
+                } else {
+                    ""
+                };
                 write!(
                     stdout_locked,
-                    "\r\x1B[KStats: Avg {:.1} moves, Terminations: {}\r\n",
-                    avg_moves, termination_count
+                    "\r\x1B[KStats: Avg {:.1} moves, Terminations: {}{}{}{}\r\n",
+                    avg_moves, termination_count, speed_text, sprt_text, interrupt_text
                 )
                 .ok();
 
                 // Draw Slots
                 {
                     let workers = shared.workers.lock().unwrap();
-                    for (i, worker) in workers.iter().enumerate().take(num_display_slots) {
+                    for (_i, worker) in workers.iter().enumerate().take(num_display_slots) {
                         let status_text = if let Some(gid) = worker.game_id {
                             format!("[Game {}] {}", gid, worker.status)
                         } else {
@@ -387,12 +674,13 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
     };
 
     let results: Vec<_> = if config.use_parallel {
-        (1..=config.num_games)
+        pending
             .into_par_iter()
             .map(|game_num| execute_game_with_monitoring(game_num, &config, &shared_state))
             .collect()
     } else {
-        (1..=config.num_games)
+        pending
+            .into_iter()
             .map(|game_num| execute_game_with_monitoring(game_num, &config, &shared_state))
             .collect()
     };
@@ -400,53 +688,60 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
     // Signal UI to stop
     shared_state.is_running.store(false, Ordering::Relaxed);
     ui_handle.join().ok();
+    drop(interrupt_guard);
+    stats.throughput = Some(shared_state.throughput());
+    stats.interrupted = shared_state.interrupted.load(Ordering::Relaxed);
 
     println!("\r\n\r\nProcessing results...\r");
 
-    // Generate unique run ID (timestamp)
-    let run_id = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
-
-    // Loop for processing results
-    for (idx, result) in results.into_iter().enumerate() {
-        let game_num = idx + 1;
-        let exec_result = result?;
-
-        // Compute enhanced metrics
-        let (material_diff, avg_move_time_ms, _, _) = compute_game_metrics(
-            &exec_result.game,
-            &exec_result.thinking_data,
-            exec_result.duration,
-        );
+    // Elo / SPRT over every finished game (resignations included)
+    let (p1_wins, p2_wins, draws) = (
+        shared_state.p1_wins.load(Ordering::Relaxed),
+        shared_state.p2_wins.load(Ordering::Relaxed),
+        shared_state.draws.load(Ordering::Relaxed),
+    );
+    stats.elo = EloEstimate::from_counts(p1_wins, draws, p2_wins);
+    stats.sprt = config
+        .sprt
+        .map(|sprt| sprt.evaluate(p1_wins, draws, p2_wins));
 
-        let game_result = GameResult {
-            winner: exec_result.winner,
-            moves: exec_result.move_count,
-            time_ms: exec_result.duration.as_millis(),
-            material_diff,
-            avg_move_time_ms,
-        };
+    for result in results {
+        result?;
+    }
+    // The results log is the record of the run: games recorded before a
+    // resume plus the ones played now
+    let games = run_log::load_results(&run_dir);
 
+    for game_result in games {
         // Track resignations separately
-        if exec_result.resigned {
+        if game_result.resigned {
             stats.resignations += 1;
         } else {
             // Only count completed games in win/draw stats
-            stats.add_result(game_result);
+            stats.add_result(&game_result);
         }
+        stats.games.push(game_result);
+    }
+    stats.phases = phases::summarize(&stats.games);
 
-        // Only save kifu for completed games (not resigned)
-        if config.save_kifus && !exec_result.resigned {
-            save_kifu(
-                &exec_result.game,
-                game_num,
-                &stats.board_setup,
-                config.ai1_strength,
-                config.ai2_strength,
-                exec_result.thinking_data,
-                &run_id,
-            )?;
-        } else if exec_result.resigned {
-            eprintln!("Skipping kifu save for game {} (resigned)\r", game_num);
+    // Repeated positions (mostly openings) are merged once the run is complete
+    let dedup = if config.export_dataset {
+        dataset::write_dedup(&run_dir)?
+    } else {
+        None
+    };
+    let curriculum = match config.curriculum {
+        Some(ref curriculum) if config.export_dataset => {
+            curriculum::write_curriculum(&run_dir, curriculum)?
+        }
+        _ => None,
+    };
+
+    if config.save_kifus {
+        if let Ok(abs_path) = std::fs::canonicalize(&run_dir) {
+            println!("Saved kifu to: {}\r", abs_path.display());
+        } else {
+            println!("Saved kifu to: {}\r", run_dir.display());
         }
     }
 
@@ -474,17 +769,88 @@ pub fn run_selfplay(config: SelfPlayConfig) -> anyhow::Result<SelfPlayStats> {
         stats.draws,
         stats.draws as f64 / stats.total_games as f64 * 100.0
     );
+    if stats.adjudicated_draws > 0 {
+        println!("  Adjudicated (stagnation): {}\r", stats.adjudicated_draws);
+    }
     println!("Avg Moves: {:.1}\r", stats.avg_moves);
-    println!("Avg Time: {:.1}s\r\n", stats.avg_time_ms / 1000.0);
+    println!("Avg Time: {:.1}s\r", stats.avg_time_ms / 1000.0);
+    if let Some(throughput) = stats.throughput {
+        println!(
+            "Throughput: {:.1} games/h, {:.0} NPS per worker ({} in {})\r",
+            throughput.games_per_hour,
+            throughput.nodes_per_second,
+            throughput.games,
+            format_duration(Duration::from_secs_f64(throughput.elapsed_secs))
+        );
+    }
+    println!("\r");
+    print_elo_summary(&stats);
+    if let Some(ref summary) = stats.phases {
+        phases::print_phase_summary(summary);
+    }
+    if let Some((total, unique)) = dedup {
+        println!(
+            "Dataset: {} positions, {} unique after dedup ({})\r",
+            total,
+            unique,
+            dataset::DEDUP_FILE
+        );
+    }
+    if let Some(ref manifest) = curriculum {
+        println!(
+            "Curriculum: {} shards in {}/\r",
+            manifest.shards.len(),
+            curriculum::CURRICULUM_DIR
+        );
+        for shard in &manifest.shards {
+            println!("  {:<24} {:>8} positions\r", shard.file, shard.positions);
+        }
+    }
+    if stats.interrupted {
+        println!(
+            "\r\nInterrupted after {} of {} games (resume with: selfplay --resume {})\r",
+            stats.games.len(),
+            config.num_games,
+            run_id
+        );
+    }
 
     Ok(stats)
 }
 
+/// Print the Elo estimate and SPRT outcome, if any
+fn print_elo_summary(stats: &SelfPlayStats) {
+    if let Some(ref elo) = stats.elo {
+        println!(
+            "Elo (AI1 - AI2): {:+.1} +/- {:.1} (LOS: {:.1}%)\r",
+            elo.elo,
+            elo.error_95,
+            elo.los * 100.0
+        );
+    }
+    if let Some(ref sprt) = stats.sprt {
+        let verdict = match sprt.decision {
+            SprtDecision::Continue => "inconclusive",
+            SprtDecision::AcceptH0 => "H0 accepted",
+            SprtDecision::AcceptH1 => "H1 accepted",
+        };
+        println!(
+            "SPRT [{:.1}, {:.1}]: LLR {:.2} ({:.2}, {:.2}) - {}\r",
+            sprt.elo0, sprt.elo1, sprt.llr, sprt.lower_bound, sprt.upper_bound, verdict
+        );
+    }
+}
+
 fn execute_game_with_monitoring(
     game_num: usize,
     config: &SelfPlayConfig,
     shared: &Arc<SharedProgress>,
-) -> anyhow::Result<GameExecutionResult> {
+) -> anyhow::Result<()> {
+    // Skipped after an SPRT decision or Ctrl+C
+    if shared.stop_requested.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
     // Allocate slot
     let slot_idx = {
         let mut workers = shared.workers.lock().unwrap();
@@ -502,7 +868,7 @@ fn execute_game_with_monitoring(
     let update_interval = config.update_interval_moves;
     let shared_clone = Arc::clone(shared);
     let on_progress = move |moves: usize, player: PlayerId| {
-        if moves % update_interval == 0 {
+        if moves.is_multiple_of(update_interval) {
             let mut workers = shared_clone.workers.lock().unwrap();
             if slot_idx < workers.len() {
                 workers[slot_idx].status =
@@ -538,6 +904,15 @@ fn execute_game_with_monitoring(
             if res.was_terminated {
                 shared.termination_count.fetch_add(1, Ordering::Relaxed);
             }
+
+            shared.session_games.fetch_add(1, Ordering::Relaxed);
+            let (nodes, search_ms) = res.thinking_data.iter().fold((0u64, 0u64), |acc, t| {
+                (acc.0 + t.nodes as u64, acc.1 + t.time_ms as u64)
+            });
+            shared.session_nodes.fetch_add(nodes, Ordering::Relaxed);
+            shared
+                .session_search_ms
+                .fetch_add(search_ms, Ordering::Relaxed);
         }
 
         let mut workers = shared.workers.lock().unwrap();
@@ -546,41 +921,134 @@ fn execute_game_with_monitoring(
             workers[slot_idx].game_id = None; // Free slot
         }
         shared.completed_games.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(ref sprt) = config.sprt {
+            let result = sprt.evaluate(
+                shared.p1_wins.load(Ordering::Relaxed),
+                shared.draws.load(Ordering::Relaxed),
+                shared.p2_wins.load(Ordering::Relaxed),
+            );
+            if result.decision != SprtDecision::Continue {
+                shared.stop_requested.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    record_game(game_num, result?, config, &shared.run_dir)
+}
+
+/// Number of recorded games with the given winner (`None` counts draws)
+fn count_winner(results: &[GameResult], winner: Option<PlayerId>) -> usize {
+    results.iter().filter(|r| r.winner == winner).count()
+}
+
+/// Save a finished game (kifu plus a line in the run's results log) as soon
+/// as it ends, so an interrupted run loses at most the games in progress
+fn record_game(
+    game_num: usize,
+    exec_result: GameExecutionResult,
+    config: &SelfPlayConfig,
+    run_dir: &Path,
+) -> anyhow::Result<()> {
+    // Compute enhanced metrics
+    let (material_diff, avg_move_time_ms, _, _) = compute_game_metrics(
+        &exec_result.game,
+        &exec_result.thinking_data,
+        exec_result.duration,
+    );
+
+    let eval_trajectory = build_eval_trajectory(
+        &exec_result.thinking_data,
+        &exec_result.position_evaluations,
+        &exec_result.critical_moments,
+        config.eval_sample_interval,
+    );
+
+    let game_result = GameResult {
+        game_num,
+        winner: exec_result.winner,
+        moves: exec_result.move_count,
+        time_ms: exec_result.duration.as_millis(),
+        material_diff,
+        avg_move_time_ms,
+        resigned: exec_result.resigned,
+        adjudicated: exec_result.adjudicated,
+        eval_trajectory: eval_trajectory.clone(),
+        phases: Some(phases::game_phase_stats(
+            exec_result.game.initial_board.clone(),
+            &exec_result.game.history,
+        )),
+    };
+
+    if config.export_dataset {
+        let records = dataset::game_records(
+            exec_result.game.initial_board.clone(),
+            &exec_result.game.history,
+            &exec_result.thinking_data,
+            &game_result,
+            config.mirror_dataset,
+        );
+        dataset::append_records(run_dir, &records)?;
     }
 
-    result
+    // Only save kifu for completed games (not resigned)
+    if config.save_kifus && !exec_result.resigned {
+        save_kifu(
+            &exec_result.game,
+            game_num,
+            run_dir,
+            config,
+            &exec_result.evaluators,
+            exec_result.thinking_data,
+            eval_trajectory,
+        )?;
+    }
+    run_log::append_result(run_dir, &game_result)
+}
+
+/// Random generator of one game: derived from the run seed, or from entropy
+fn game_rng(seed: Option<u64>, game_num: usize) -> StdRng {
+    match seed {
+        Some(seed) => {
+            StdRng::seed_from_u64(seed ^ (game_num as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        }
+        None => StdRng::from_entropy(),
+    }
 }
 
 fn run_single_game(
-    _game_num: usize,
+    game_num: usize,
     config: &SelfPlayConfig,
     silent: bool,
     on_progress: Option<Box<dyn Fn(usize, PlayerId) + Send + Sync>>,
 ) -> anyhow::Result<GameExecutionResult> {
     let start_time = Instant::now();
 
-    let board = config.board_setup.create_board();
+    let mut rng = game_rng(config.seed, game_num);
+    let chess960_seed = config.chess960.then(|| rng.gen::<u64>());
+    let board = config
+        .board_setup
+        .create_board_with(chess960_seed, config.handicap)?;
 
-    let p1: Box<dyn PlayerController> = Box::new(AlphaBetaAI::new(
-        PlayerId::Player1,
-        "AI-P1",
-        config.ai1_strength,
-        config.model_path.clone(),
-        true,
-    ));
-    let p2: Box<dyn PlayerController> = Box::new(AlphaBetaAI::new(
-        PlayerId::Player2,
-        "AI-P2",
-        config.ai2_strength,
-        config.model_path.clone(),
-        true,
-    ));
+    let p1 = config.create_ai(PlayerId::Player1, "AI-P1");
+    let p2 = config.create_ai(PlayerId::Player2, "AI-P2");
+    let evaluators = [p1.evaluator_name(), p2.evaluator_name()];
 
     let mut game = Game::new(board);
+    game.chess960_seed = chess960_seed;
+    game.handicap = config.handicap;
     game.perspective_mode = PerspectiveMode::Fixed(PlayerId::Player1);
-
-    let (winner, move_count, thinking_data) =
-        run_game_silent(&mut game, p1.as_ref(), p2.as_ref(), silent, on_progress)?;
+    play_random_opening(&mut game, config.random_opening_plies, &mut rng);
+
+    let (winner, move_count, thinking_data, adjudicated) = run_game_silent(
+        &mut game,
+        &p1,
+        &p2,
+        silent,
+        config.move_sampling,
+        &mut rng,
+        on_progress,
+    )?;
 
     let elapsed = start_time.elapsed();
 
@@ -589,7 +1057,7 @@ fn run_single_game(
     // 1. Game ended with a winner (not draw/stalemate)
     // 2. NOT a checkmate scenario (which would have legal_moves == 0 and in_check)
     // 3. Score indicates hopeless position
-    let resigned = if let Some(_) = winner {
+    let resigned = if winner.is_some() {
         // Check if last thinking info indicates resignation threshold
         if let Some(last_thinking) = thinking_data.last() {
             let config = crate::player::ai::config::AIConfig::get();
@@ -616,26 +1084,42 @@ fn run_single_game(
         position_evaluations,
         critical_moments,
         was_terminated: false, // This will be set by run_game_silent if abnormal
+        evaluators,
         resigned,
+        adjudicated,
     })
 }
 
+/// Play up to `plies` uniformly random legal moves so that deterministic
+/// engines do not replay the same game over and over.
+/// Stops early if the side to move has no legal moves.
+fn play_random_opening(game: &mut Game, plies: usize, rng: &mut StdRng) {
+    use rand::seq::SliceRandom;
+
+    for _ in 0..plies {
+        let moves = crate::logic::legal_moves(&game.board, game.current_player);
+        let Some(&mv) = moves.choose(rng) else {
+            break;
+        };
+        game.make_move(mv);
+    }
+}
+
 fn run_game_silent(
     game: &mut Game,
-    p1: &dyn PlayerController,
-    p2: &dyn PlayerController,
+    p1: &AlphaBetaAI,
+    p2: &AlphaBetaAI,
     silent: bool,
+    sampling: Option<MoveSampling>,
+    rng: &mut StdRng,
     on_progress: Option<Box<dyn Fn(usize, PlayerId) + Send + Sync>>,
-) -> anyhow::Result<(Option<PlayerId>, usize, Vec<ThinkingInfo>)> {
-    let mut move_count = 0;
+) -> anyhow::Result<(Option<PlayerId>, usize, Vec<ThinkingInfo>, bool)> {
+    // Count any opening plies already on the board
+    let mut move_count = game.history.len();
     let mut thinking_data = Vec::new();
-    let max_moves = 500;
+    let adjudication = game.board.rules.adjudication.clone();
 
     loop {
-        if move_count >= max_moves {
-            return Ok((None, move_count, thinking_data.clone()));
-        }
-
         let current_player = game.current_player;
         let controller = match current_player {
             PlayerId::Player1 => p1,
@@ -646,23 +1130,31 @@ fn run_game_silent(
             cb(move_count, current_player);
         }
 
-        let hash_count = game
-            .board
-            .history
-            .iter()
-            .filter(|&&h| h == game.board.zobrist_hash)
-            .count();
-        if hash_count >= 4 {
-            // Sennichite (4-fold repetition) - draw
-            thinking_data.push(ThinkingInfo {
-                move_number: move_count + 1,
-                player: format!("{:?}", current_player),
-                depth: 0,
-                score: 0, // Draw score
-                nodes: 0,
-                time_ms: 0,
-            });
-            return Ok((None, move_count, thinking_data.clone()));
+        if let Some(ending) = game.rule_ending() {
+            if !silent {
+                eprintln!("\r{}\r", ending.message(current_player));
+            }
+            if matches!(ending, RuleEnding::Repetition | RuleEnding::QuietPlies(_)) {
+                // Drawn position: record the draw score
+                thinking_data.push(ThinkingInfo {
+                    move_number: move_count + 1,
+                    player: format!("{:?}", current_player),
+                    depth: 0,
+                    score: 0, // Draw score
+                    nodes: 0,
+                    time_ms: 0,
+                    max_depth: 0,
+                    depth_profile: None,
+                    health: None,
+                });
+            }
+            game.end(ending.winner(current_player), ending.reason());
+            return Ok((
+                ending.winner(current_player),
+                move_count,
+                thinking_data.clone(),
+                false,
+            ));
         }
 
         let legal_moves = crate::logic::legal_moves(&game.board, current_player);
@@ -676,11 +1168,13 @@ fn run_game_silent(
                 Player: {:?}\r\n\
                 In Check: {}\r\n\
                 Legal Moves: 0\r\n\
-                Pieces on board: {}\r\n",
+                Pieces on board: {}\r\n\
+                Last move: {}\r\n",
                 move_count + 1,
                 current_player,
                 in_check,
-                game.board.pieces.len()
+                game.board.pieces.len(),
+                game.last_move_notation().unwrap_or("-".to_string())
             );
 
             eprintln!("{}", termination_log);
@@ -691,7 +1185,9 @@ fn run_game_silent(
             }
             let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
             let log_file = format!("selfplay_termination_logs/termination_{}.txt", timestamp);
-            if let Err(e) = std::fs::write(&log_file, &termination_log) {
+            if let Err(e) =
+                crate::util::atomic_write(std::path::Path::new(&log_file), &termination_log)
+            {
                 eprintln!("Failed to write termination log: {}", e);
             } else {
                 eprintln!("Termination log saved to: {}\r", log_file);
@@ -716,16 +1212,19 @@ fn run_game_silent(
             // Show pseudo-legal moves count for comparison
             let pseudo_legal = crate::logic::pseudo_legal_moves(&game.board, current_player);
             eprintln!("Pseudo-legal moves: {}\r", pseudo_legal.len());
-            if pseudo_legal.len() > 0 && pseudo_legal.len() <= 5 {
+            if !pseudo_legal.is_empty() && pseudo_legal.len() <= 5 {
                 eprintln!("Pseudo-legal moves (filtered as illegal):\r");
                 for mv in &pseudo_legal {
-                    eprintln!("  {:?}\r", mv);
+                    eprintln!("  {}\r", mv.notation(&game.board, current_player));
                 }
             }
             eprintln!("==============================\r\n");
 
-            if in_check {
-                // This is checkmate! Record the mate score
+            let checkmate = crate::logic::is_checkmate(&game.board, current_player);
+            if crate::logic::rules::no_move_winner(&game.board, current_player).is_some() {
+                let reason = if checkmate { "Checkmate" } else { "No moves" };
+                game.end(Some(current_player.opponent()), reason);
+                // Checkmate, or a stalemate that loses under these rules: record the mate score
                 let mate_score = -199900 - (move_count as i32); // Mate score from loser's perspective
                 let normalized_score = if current_player == crate::core::PlayerId::Player1 {
                     mate_score // Player1 is in checkmate, negative score
@@ -741,15 +1240,19 @@ fn run_game_silent(
                     score: normalized_score,
                     nodes: 0,
                     time_ms: 0,
+                    max_depth: 0,
+                    depth_profile: None,
+                    health: None,
                 });
 
                 return Ok((
                     Some(current_player.opponent()),
                     move_count,
                     thinking_data.clone(),
+                    false,
                 ));
             } else {
-                // Stalemate (no legal moves, not in check) - this is abnormal, treat as draw
+                // Stalemate that the side's rules make a draw
                 thinking_data.push(ThinkingInfo {
                     move_number: move_count + 1,
                     player: format!("{:?}", current_player),
@@ -757,8 +1260,12 @@ fn run_game_silent(
                     score: 0, // Draw score
                     nodes: 0,
                     time_ms: 0,
+                    max_depth: 0,
+                    depth_profile: None,
+                    health: None,
                 });
-                return Ok((None, move_count, thinking_data.clone()));
+                game.end(None, "Stalemate");
+                return Ok((None, move_count, thinking_data.clone(), false));
             }
         }
 
@@ -771,12 +1278,16 @@ fn run_game_silent(
             std::io::Write::flush(&mut std::io::stdout())?;
         }
 
-        if let Some(chosen_move) = controller.choose_move(&game.board, &legal_moves) {
-            let ai_ptr =
-                controller as *const dyn crate::player::PlayerController as *const AlphaBetaAI;
-            if let Some((depth, score, nodes, time_ms)) =
-                unsafe { *(*ai_ptr).last_thinking.borrow() }
-            {
+        let chosen = match sampling {
+            Some(sampling) if move_count < sampling.plies => {
+                controller.choose_move_with_temperature(&game.board, sampling.temperature, rng)
+            }
+            _ => controller.choose_move(&game.board, &legal_moves),
+        };
+        if let Some(chosen_move) = chosen {
+            if let Some(info) = *controller.last_thinking.borrow() {
+                let depth = info.depth;
+                let score = info.score;
                 let normalized_score = if current_player == crate::core::PlayerId::Player1 {
                     score
                 } else {
@@ -788,8 +1299,11 @@ fn run_game_silent(
                     player: format!("{:?}", current_player),
                     depth,
                     score: normalized_score,
-                    nodes,
-                    time_ms,
+                    nodes: info.nodes,
+                    time_ms: info.time_ms,
+                    max_depth: info.max_depth,
+                    depth_profile: Some(format!("{:?}", info.profile)),
+                    health: Some(info.health),
                 });
 
                 // Check for resignation
@@ -808,18 +1322,33 @@ fn run_game_silent(
                         depth
                     );
 
+                    game.end(Some(current_player.opponent()), "Resignation");
                     return Ok((
                         Some(current_player.opponent()),
                         move_count,
                         thinking_data.clone(),
+                        false,
                     ));
                 }
             }
 
-            game.board = crate::logic::apply_move(&game.board, &chosen_move, current_player);
-            game.history.push(chosen_move);
-            game.current_player = current_player.opponent();
+            game.make_move(chosen_move);
             move_count += 1;
+
+            // Draw once the game stagnates and neither side's search sees an edge
+            let recent_scores: Vec<i32> = thinking_data
+                .iter()
+                .rev()
+                .take(2)
+                .map(|t| t.score)
+                .collect();
+            if game
+                .stagnation
+                .should_adjudicate(&adjudication, &recent_scores)
+            {
+                game.end(None, "Adjudication");
+                return Ok((None, move_count, thinking_data.clone(), true));
+            }
         } else {
             // AI failed to choose a move - this should not happen with legal moves available
             // Treat as a loss for the current player
@@ -837,60 +1366,148 @@ fn run_game_silent(
                 score: normalized_score,
                 nodes: 0,
                 time_ms: 0,
+                max_depth: 0,
+                depth_profile: None,
+                health: None,
             });
 
+            game.end(Some(current_player.opponent()), "Engine failure");
             return Ok((
                 Some(current_player.opponent()),
                 move_count,
                 thinking_data.clone(),
+                false,
             ));
         }
     }
 }
 
+/// Write `game_NNNN.json` into the run directory (`selfplay_kifu/{BoardSetup}/{RunID}/`)
 fn save_kifu(
     game: &Game,
     game_num: usize,
-    board_setup: &str,
-    ai1_strength: AIStrength,
-    ai2_strength: AIStrength,
+    run_dir: &Path,
+    selfplay_config: &SelfPlayConfig,
+    evaluators: &[String; 2],
     thinking_data: Vec<ThinkingInfo>,
-    run_id: &str,
+    eval_trajectory: Option<EvalTrajectory>,
 ) -> anyhow::Result<()> {
-    let base_dir = "selfplay_kifu";
-    // Directory structure: selfplay_kifu/{BoardSetup}/{RunID}/
-    let board_dir = format!("{}/{}/{}", base_dir, board_setup, run_id);
-    std::fs::create_dir_all(&board_dir)?;
-
-    let filename = format!("{}/game_{:04}.json", board_dir, game_num);
+    std::fs::create_dir_all(run_dir)?;
+    let filename = run_dir.join(format!("game_{:04}.json", game_num));
 
     let config = crate::player::ai::config::AIConfig::get();
 
-    let model_version = if let Some(ref path) = config.evaluation.nn_model_path {
-        crate::ml::model_registry::ModelRegistry::get_model_version(std::path::Path::new(path))
+    // Per-player model; the kifu-level model fields describe the first NN player
+    let models: Vec<Option<String>> = [
+        &selfplay_config.ai1_model_path,
+        &selfplay_config.ai2_model_path,
+    ]
+    .into_iter()
+    .zip(evaluators)
+    .map(|(path, evaluator)| {
+        (evaluator.starts_with("NeuralNetwork") || evaluator.starts_with("NNUE"))
+            .then(|| {
+                path.clone()
+                    .or_else(|| config.evaluation.nn_model_path.clone())
+            })
+            .flatten()
+    })
+    .collect();
+    let model_path = models.iter().flatten().next().cloned();
+    let model_lineage = model_path.as_ref().and_then(|path| {
+        crate::ml::model_registry::ModelRegistry::get_model_lineage(std::path::Path::new(path))
+    });
+
+    let player_name = |strength: AIStrength, evaluator: &str, model: &Option<String>| match model {
+        Some(model) => format!("AI ({:?}, {}: {})", strength, evaluator, model),
+        None => format!("AI ({:?}, {})", strength, evaluator),
+    };
+    let evaluator = if evaluators[0] == evaluators[1] {
+        evaluators[0].clone()
     } else {
-        None
+        format!("{} vs {}", evaluators[0], evaluators[1])
     };
 
     let kifu_data = KifuData {
-        board_setup: board_setup.to_string(),
-        player1_name: format!("AI ({:?})", ai1_strength),
-        player2_name: format!("AI ({:?})", ai2_strength),
+        board_setup: selfplay_config.board_setup.to_string(),
+        chess960_seed: game.chess960_seed,
+        handicap: game.handicap,
+        player1_name: player_name(selfplay_config.ai1_strength, &evaluators[0], &models[0]),
+        player2_name: player_name(selfplay_config.ai2_strength, &evaluators[1], &models[1]),
         moves: game.history.clone(),
         thinking_data: Some(thinking_data),
-        evaluator: config.evaluation.evaluator_type.clone(),
-        model_path: config.evaluation.nn_model_path.clone(),
-        model_version,
+        evaluator,
+        model_path,
+        model_version: model_lineage.as_ref().and_then(|l| l.version.clone()),
+        model_lineage,
+        eval_trajectory,
+        snapshots: crate::game::snapshots_every(
+            &game.initial_board,
+            &game.history,
+            selfplay_config.snapshot_interval,
+        ),
+        result: game.result.clone(),
+        variations: Vec::new(),
     };
 
-    let file = std::fs::File::create(&filename)?;
-    serde_json::to_writer_pretty(file, &kifu_data)?;
+    // Written from worker threads while the progress UI is drawn, so no output here
+    crate::util::atomic_write_json(std::path::Path::new(&filename), &kifu_data, true)?;
 
-    if let Ok(abs_path) = std::fs::canonicalize(&filename) {
-        println!("Saved kifu to: {}\r", abs_path.display());
-    } else {
-        println!("Saved kifu to: {}\r", filename);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_opening() {
+        use crate::core::setup::BoardSetupType;
+        use rand::SeedableRng;
+
+        let opening = |seed: u64| {
+            let mut game = Game::new(BoardSetupType::Fair.create_board());
+            play_random_opening(&mut game, 8, &mut StdRng::seed_from_u64(seed));
+            game.history
+        };
+        let moves = opening(7);
+        assert_eq!(moves.len(), 8);
+        assert_eq!(moves, opening(7));
+
+        let mut board = BoardSetupType::Fair.create_board();
+        let mut player = PlayerId::Player1;
+        for mv in &moves {
+            assert!(crate::logic::legal_moves(&board, player).contains(mv));
+            board = crate::logic::apply_move(&board, mv, player);
+            player = player.opponent();
+        }
     }
 
-    Ok(())
+    #[test]
+    fn test_eval_trajectory_downsampling() {
+        let thinking: Vec<ThinkingInfo> = (0..7)
+            .map(|i| ThinkingInfo {
+                move_number: i + 3,
+                player: "P1".to_string(),
+                depth: 1,
+                score: 0,
+                nodes: 0,
+                time_ms: 0,
+                max_depth: 0,
+                depth_profile: None,
+                health: None,
+            })
+            .collect();
+        let evals = vec![0, 10, 20, 3000, 3010, 3020, 3030];
+
+        let trajectory = build_eval_trajectory(&thinking, &evals, &[3], 3).unwrap();
+        let kept: Vec<usize> = trajectory.samples.iter().map(|s| s.move_number).collect();
+        // Indices 0, 3, 6 (the last one is always kept)
+        assert_eq!(kept, vec![3, 6, 9]);
+        assert_eq!(trajectory.critical_moments.len(), 1);
+        assert_eq!(trajectory.critical_moments[0].move_number, 6);
+        assert_eq!(trajectory.critical_moments[0].score_before, 20);
+
+        assert!(build_eval_trajectory(&thinking, &evals, &[3], 0).is_none());
+    }
 }
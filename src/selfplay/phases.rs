@@ -0,0 +1,198 @@
+//! Game-phase statistics
+//!
+//! Per-game records of when the first capture, check and promotion happen and
+//! how many plies are spent in each phase (as classified by the handcrafted
+//! evaluator's phase detector), aggregated over a run to show how rule or
+//! evaluation changes alter the character of games.
+
+use super::GameResult;
+use crate::core::{Board, Move, PlayerId};
+use crate::logic::{apply_move, is_in_check};
+use crate::player::ai::eval::{detect_game_phase, GamePhase};
+use serde::{Deserialize, Serialize};
+
+/// Phase statistics of one game. Ply numbers are 1-based and include any
+/// random opening plies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GamePhaseStats {
+    pub first_capture: Option<usize>,
+    pub first_check: Option<usize>,
+    pub first_promotion: Option<usize>,
+    pub promotions: usize,
+    pub opening_plies: usize,
+    pub midgame_plies: usize,
+    pub endgame_plies: usize,
+}
+
+/// Run-wide averages. Averages of "first" events are over the games in which
+/// the event happened at all; `games_with_*` gives that count.
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseSummary {
+    pub games: usize,
+    pub games_with_capture: usize,
+    pub avg_first_capture: Option<f64>,
+    pub games_with_check: usize,
+    pub avg_first_check: Option<f64>,
+    pub games_with_promotion: usize,
+    pub avg_first_promotion: Option<f64>,
+    pub avg_promotions: f64,
+    pub avg_opening_plies: f64,
+    pub avg_midgame_plies: f64,
+    pub avg_endgame_plies: f64,
+}
+
+/// Replay `moves` from `board` (Player1 first) and collect phase statistics
+pub fn game_phase_stats(board: Board, moves: &[Move]) -> GamePhaseStats {
+    let mut stats = GamePhaseStats::default();
+    let mut board = board;
+    let mut player = PlayerId::Player1;
+
+    for (i, mv) in moves.iter().enumerate() {
+        let ply = i + 1;
+        match detect_game_phase(&board) {
+            GamePhase::Opening => stats.opening_plies += 1,
+            GamePhase::Midgame => stats.midgame_plies += 1,
+            GamePhase::Endgame => stats.endgame_plies += 1,
+        }
+
+        if let Move::Normal { to, promote, .. } = mv {
+            if board.get_piece(*to).is_some_and(|p| p.owner != player) {
+                stats.first_capture.get_or_insert(ply);
+            }
+            if promote.is_some() {
+                stats.promotions += 1;
+                stats.first_promotion.get_or_insert(ply);
+            }
+        }
+
+        board = apply_move(&board, mv, player);
+        player = player.opponent();
+        if is_in_check(&board, player) {
+            stats.first_check.get_or_insert(ply);
+        }
+    }
+
+    stats
+}
+
+/// Aggregate the games that have phase statistics (results recorded before
+/// this was added have none). `None` if no game has them.
+pub fn summarize(games: &[GameResult]) -> Option<PhaseSummary> {
+    let stats: Vec<&GamePhaseStats> = games.iter().filter_map(|g| g.phases.as_ref()).collect();
+    if stats.is_empty() {
+        return None;
+    }
+    let n = stats.len() as f64;
+
+    let first = |get: fn(&GamePhaseStats) -> Option<usize>| {
+        let plies: Vec<usize> = stats.iter().filter_map(|s| get(s)).collect();
+        let avg = if plies.is_empty() {
+            None
+        } else {
+            Some(plies.iter().sum::<usize>() as f64 / plies.len() as f64)
+        };
+        (plies.len(), avg)
+    };
+    let mean =
+        |get: fn(&GamePhaseStats) -> usize| stats.iter().map(|s| get(s)).sum::<usize>() as f64 / n;
+
+    let (games_with_capture, avg_first_capture) = first(|s| s.first_capture);
+    let (games_with_check, avg_first_check) = first(|s| s.first_check);
+    let (games_with_promotion, avg_first_promotion) = first(|s| s.first_promotion);
+
+    Some(PhaseSummary {
+        games: stats.len(),
+        games_with_capture,
+        avg_first_capture,
+        games_with_check,
+        avg_first_check,
+        games_with_promotion,
+        avg_first_promotion,
+        avg_promotions: mean(|s| s.promotions),
+        avg_opening_plies: mean(|s| s.opening_plies),
+        avg_midgame_plies: mean(|s| s.midgame_plies),
+        avg_endgame_plies: mean(|s| s.endgame_plies),
+    })
+}
+
+/// Print the phase summary under the selfplay results
+pub fn print_phase_summary(summary: &PhaseSummary) {
+    let fmt = |avg: Option<f64>, games: usize| match avg {
+        Some(avg) => format!("ply {:.1} ({}/{} games)", avg, games, summary.games),
+        None => format!("never (0/{} games)", summary.games),
+    };
+    println!("Game Phases ({} games):\r", summary.games);
+    println!(
+        "  First capture:   {}\r",
+        fmt(summary.avg_first_capture, summary.games_with_capture)
+    );
+    println!(
+        "  First check:     {}\r",
+        fmt(summary.avg_first_check, summary.games_with_check)
+    );
+    println!(
+        "  First promotion: {}, {:.1} promotions per game\r",
+        fmt(summary.avg_first_promotion, summary.games_with_promotion),
+        summary.avg_promotions
+    );
+    println!(
+        "  Phase length:    opening {:.1} / midgame {:.1} / endgame {:.1} plies\r",
+        summary.avg_opening_plies, summary.avg_midgame_plies, summary.avg_endgame_plies
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selfplay::BoardSetupType;
+
+    #[test]
+    fn test_phase_stats_count_every_ply() {
+        let board = BoardSetupType::ChessOnly.create_board();
+        let mut b = board.clone();
+        let mut player = PlayerId::Player1;
+        let moves: Vec<Move> = (0..6)
+            .map(|_| {
+                let mv = crate::logic::legal_moves(&b, player)[0];
+                b = apply_move(&b, &mv, player);
+                player = player.opponent();
+                mv
+            })
+            .collect();
+
+        let stats = game_phase_stats(board, &moves);
+        assert_eq!(
+            stats.opening_plies + stats.midgame_plies + stats.endgame_plies,
+            6
+        );
+
+        let game = |phases| GameResult {
+            game_num: 1,
+            winner: None,
+            moves: 6,
+            time_ms: 0,
+            material_diff: 0,
+            avg_move_time_ms: 0.0,
+            resigned: false,
+            adjudicated: false,
+            eval_trajectory: None,
+            phases,
+        };
+        let with_capture = GamePhaseStats {
+            first_capture: Some(10),
+            opening_plies: 4,
+            ..Default::default()
+        };
+        let summary = summarize(&[
+            game(Some(with_capture)),
+            game(Some(GamePhaseStats::default())),
+            game(None),
+        ])
+        .unwrap();
+        assert_eq!(summary.games, 2);
+        assert_eq!(summary.games_with_capture, 1);
+        assert_eq!(summary.avg_first_capture, Some(10.0));
+        assert_eq!(summary.avg_first_check, None);
+        assert_eq!(summary.avg_opening_plies, 2.0);
+    }
+}
@@ -0,0 +1,246 @@
+//! On-disk record of a selfplay run, used to resume interrupted runs
+//!
+//! Each run lives in `selfplay_kifu/{BoardSetup}/{RunID}/` next to its kifu:
+//! - `run.json`: the configuration the run was started with
+//! - `results.jsonl`: one [`GameResult`] per finished game, appended as games finish
+//! - `dataset.bin`: training positions, when exported (see [`super::dataset`])
+
+use super::curriculum::CurriculumConfig;
+use super::elo::SprtConfig;
+use super::{BoardSetupType, GameResult, MoveSampling, SelfPlayConfig};
+use crate::player::ai::AIStrength;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const BASE_DIR: &str = "selfplay_kifu";
+const MANIFEST_FILE: &str = "run.json";
+const RESULTS_FILE: &str = "results.jsonl";
+
+#[derive(Serialize, Deserialize)]
+pub struct RunManifest {
+    pub run_id: String,
+    pub board_setup: String,
+    pub ai1_strength: AIStrength,
+    pub ai2_strength: AIStrength,
+    pub num_games: usize,
+    /// Model shared by both players (manifests written before per-player settings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_path: Option<String>,
+    #[serde(default)]
+    pub ai1_evaluator: Option<String>,
+    #[serde(default)]
+    pub ai2_evaluator: Option<String>,
+    #[serde(default)]
+    pub ai1_model_path: Option<String>,
+    #[serde(default)]
+    pub ai2_model_path: Option<String>,
+    #[serde(default)]
+    pub random_opening_plies: usize,
+    #[serde(default)]
+    pub chess960: bool,
+    #[serde(default)]
+    pub handicap: Option<crate::core::handicap::Handicap>,
+    #[serde(default)]
+    pub move_sampling: Option<MoveSampling>,
+    #[serde(default)]
+    pub sprt: Option<SprtConfig>,
+    #[serde(default = "default_eval_sample_interval")]
+    pub eval_sample_interval: usize,
+    #[serde(default)]
+    pub export_dataset: bool,
+    #[serde(default)]
+    pub mirror_dataset: bool,
+    #[serde(default)]
+    pub curriculum: Option<CurriculumConfig>,
+    #[serde(default)]
+    pub snapshot_interval: usize,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_eval_sample_interval() -> usize {
+    1
+}
+
+impl RunManifest {
+    pub fn new(run_id: &str, config: &SelfPlayConfig) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            board_setup: config.board_setup.to_string(),
+            ai1_strength: config.ai1_strength,
+            ai2_strength: config.ai2_strength,
+            num_games: config.num_games,
+            model_path: None,
+            ai1_evaluator: config.ai1_evaluator.clone(),
+            ai2_evaluator: config.ai2_evaluator.clone(),
+            ai1_model_path: config.ai1_model_path.clone(),
+            ai2_model_path: config.ai2_model_path.clone(),
+            random_opening_plies: config.random_opening_plies,
+            chess960: config.chess960,
+            handicap: config.handicap,
+            move_sampling: config.move_sampling,
+            sprt: config.sprt,
+            eval_sample_interval: config.eval_sample_interval,
+            export_dataset: config.export_dataset,
+            mirror_dataset: config.mirror_dataset,
+            curriculum: config.curriculum,
+            snapshot_interval: config.snapshot_interval,
+            seed: config.seed,
+        }
+    }
+
+    /// Rebuild the run's configuration. Execution settings (parallelism,
+    /// UI refresh) are not part of the run and come from the caller.
+    pub fn to_config(&self, use_parallel: bool) -> anyhow::Result<SelfPlayConfig> {
+        let board_setup = BoardSetupType::from_name(&self.board_setup)
+            .ok_or_else(|| anyhow::anyhow!("Unknown board setup '{}'", self.board_setup))?;
+        Ok(SelfPlayConfig {
+            num_games: self.num_games,
+            board_setup,
+            ai1_strength: self.ai1_strength,
+            ai2_strength: self.ai2_strength,
+            save_kifus: true,
+            use_parallel,
+            update_interval_moves: 1,
+            ai1_evaluator: self.ai1_evaluator.clone(),
+            ai2_evaluator: self.ai2_evaluator.clone(),
+            ai1_model_path: self.ai1_model_path.clone().or(self.model_path.clone()),
+            ai2_model_path: self.ai2_model_path.clone().or(self.model_path.clone()),
+            random_opening_plies: self.random_opening_plies,
+            chess960: self.chess960,
+            handicap: self.handicap,
+            move_sampling: self.move_sampling,
+            sprt: self.sprt,
+            eval_sample_interval: self.eval_sample_interval,
+            export_dataset: self.export_dataset,
+            mirror_dataset: self.mirror_dataset,
+            curriculum: self.curriculum,
+            snapshot_interval: self.snapshot_interval,
+            seed: self.seed,
+            resume_run_id: Some(self.run_id.clone()),
+        })
+    }
+}
+
+/// `selfplay_kifu/{BoardSetup}/{RunID}`
+pub fn run_dir(board_setup: &str, run_id: &str) -> PathBuf {
+    Path::new(BASE_DIR).join(board_setup).join(run_id)
+}
+
+/// Find the directory of `run_id` under any board setup
+pub fn find_run_dir(run_id: &str) -> anyhow::Result<PathBuf> {
+    if let Ok(entries) = std::fs::read_dir(BASE_DIR) {
+        for entry in entries.flatten() {
+            let dir = entry.path().join(run_id);
+            if dir.join(MANIFEST_FILE).exists() {
+                return Ok(dir);
+            }
+        }
+    }
+    anyhow::bail!(
+        "No resumable run '{}' found under {}/ (runs started before resume support have no {})",
+        run_id,
+        BASE_DIR,
+        MANIFEST_FILE
+    )
+}
+
+pub fn write_manifest(dir: &Path, manifest: &RunManifest) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    crate::util::atomic_write_json(&dir.join(MANIFEST_FILE), manifest, true)
+}
+
+pub fn read_manifest(dir: &Path) -> anyhow::Result<RunManifest> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST_FILE))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Append one finished game. Each record is a single write so concurrent
+/// workers do not interleave lines, and is flushed to disk before returning
+/// so a crash cannot lose games that were reported as finished.
+pub fn append_result(dir: &Path, result: &GameResult) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(result)?;
+    line.push('\n');
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(RESULTS_FILE))?;
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Drop a last line cut off by a crash, so results appended on resume start
+/// on a line of their own
+pub fn repair_results(dir: &Path) -> anyhow::Result<()> {
+    let path = dir.join(RESULTS_FILE);
+    let Ok(content) = std::fs::read(&path) else {
+        return Ok(());
+    };
+    if content.last().is_some_and(|&b| b != b'\n') {
+        let keep = content
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_len(keep as u64)?;
+    }
+    Ok(())
+}
+
+/// Results recorded so far, one per game number. A line cut off by an
+/// interruption is ignored (that game is simply played again).
+pub fn load_results(dir: &Path) -> Vec<GameResult> {
+    let Ok(content) = std::fs::read_to_string(dir.join(RESULTS_FILE)) else {
+        return Vec::new();
+    };
+    let mut results: Vec<GameResult> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    results.sort_by_key(|r| r.game_num);
+    results.dedup_by_key(|r| r.game_num);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_torn_line_is_dropped_before_appending() {
+        let result = |game_num: usize| GameResult {
+            game_num,
+            winner: None,
+            moves: 10,
+            time_ms: 100,
+            material_diff: 0,
+            avg_move_time_ms: 10.0,
+            resigned: false,
+            adjudicated: false,
+            eval_trajectory: None,
+            phases: None,
+        };
+        let dir = std::env::temp_dir().join(format!("aho_run_log_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        append_result(&dir, &result(1)).unwrap();
+        // A crash in the middle of writing game 2
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join(RESULTS_FILE))
+            .unwrap();
+        file.write_all(br#"{"game_num":2,"win"#).unwrap();
+
+        repair_results(&dir).unwrap();
+        append_result(&dir, &result(3)).unwrap();
+        let loaded = load_results(&dir);
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(
+            loaded.iter().map(|r| r.game_num).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+    }
+}
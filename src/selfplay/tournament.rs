@@ -0,0 +1,958 @@
+//! Tournaments between engine configurations
+//!
+//! Every pairing plays `games_per_pairing` games on each board setup with
+//! colors alternating, and the results are collected into per-pairing records
+//! from which cross-tables and standings are built.
+//!
+//! Round-robin and gauntlet fix all pairings up front. For large fields, Swiss
+//! and elimination formats play in rounds instead: each round's matches are
+//! paired from the results so far (Swiss by running score, elimination by
+//! bracket), and a match is decided by the game points of its games. Engines
+//! are seeded in the order they are given, best first.
+
+use super::elo::EloEstimate;
+use super::{play_random_opening, run_game_silent, BoardSetupType};
+use crate::core::PlayerId;
+use crate::game::{Game, PerspectiveMode};
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// One engine taking part in a tournament
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineSpec {
+    pub name: String,
+    #[serde(default = "default_strength")]
+    pub strength: AIStrength,
+    /// "Handcrafted", "NeuralNetwork" or "NNUE"
+    #[serde(default = "default_evaluator")]
+    pub evaluator: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_path: Option<String>,
+}
+
+fn default_strength() -> AIStrength {
+    AIStrength::Strong
+}
+
+fn default_evaluator() -> String {
+    "Handcrafted".to_string()
+}
+
+impl EngineSpec {
+    /// Parse the compact CLI form `name:strength[:evaluator[:model_path]]`
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let mut parts = spec.splitn(4, ':');
+        let name = parts.next().filter(|n| !n.is_empty());
+        let Some(name) = name else {
+            anyhow::bail!("Engine spec '{}' has no name", spec);
+        };
+        let strength = match parts.next() {
+            None | Some("Strong") => AIStrength::Strong,
+            Some("Light") => AIStrength::Light,
+            Some(other) => anyhow::bail!("Unknown AI strength '{}' in '{}'", other, spec),
+        };
+        let evaluator = parts
+            .next()
+            .map(str::to_string)
+            .unwrap_or_else(default_evaluator);
+        let model_path = parts.next().map(str::to_string);
+
+        Ok(Self {
+            name: name.to_string(),
+            strength,
+            evaluator,
+            model_path,
+        })
+    }
+
+    fn create_ai(&self, player_id: PlayerId) -> AlphaBetaAI {
+        AlphaBetaAI::with_evaluator_type(
+            player_id,
+            &self.name,
+            self.strength,
+            &self.evaluator,
+            self.model_path.clone(),
+            true,
+        )
+    }
+}
+
+/// Single games played to decide a drawn elimination match before the better
+/// seed advances
+const MAX_PLAYOFF_GAMES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TournamentFormat {
+    /// Every engine plays every other engine
+    RoundRobin,
+    /// The first engine plays each of the others
+    Gauntlet,
+    /// Each round pairs engines with similar running scores, without rematches
+    Swiss { rounds: usize },
+    /// Knockout bracket, out after one lost match
+    SingleElimination,
+    /// Winners and losers brackets, out after two lost matches
+    DoubleElimination,
+}
+
+impl TournamentFormat {
+    /// Lost matches that eliminate an engine, for the elimination formats
+    fn lives(self) -> Option<usize> {
+        match self {
+            TournamentFormat::SingleElimination => Some(1),
+            TournamentFormat::DoubleElimination => Some(2),
+            _ => None,
+        }
+    }
+
+    fn is_round_based(self) -> bool {
+        !matches!(
+            self,
+            TournamentFormat::RoundRobin | TournamentFormat::Gauntlet
+        )
+    }
+}
+
+/// Swiss rounds needed to separate `num_engines` engines, ceil(log2(n))
+pub fn default_swiss_rounds(num_engines: usize) -> usize {
+    num_engines.max(2).next_power_of_two().trailing_zeros() as usize
+}
+
+pub struct TournamentConfig {
+    pub engines: Vec<EngineSpec>,
+    pub format: TournamentFormat,
+    pub board_setups: Vec<BoardSetupType>,
+    /// Games per pairing and board setup; colors alternate between games
+    pub games_per_pairing: usize,
+    pub use_parallel: bool,
+    pub random_opening_plies: usize,
+}
+
+/// Results of one pairing on one board setup, from engine A's point of view
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingResult {
+    /// Round of a round-based format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub round: Option<usize>,
+    pub engine_a: String,
+    pub engine_b: String,
+    pub board_setup: String,
+    pub a_wins: usize,
+    pub draws: usize,
+    pub b_wins: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elo: Option<EloEstimate>,
+}
+
+impl PairingResult {
+    fn points(&self) -> (f64, f64) {
+        let half = self.draws as f64 / 2.0;
+        (self.a_wins as f64 + half, self.b_wins as f64 + half)
+    }
+}
+
+/// One match of a round-based format: all games of a pairing in a round
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchResult {
+    pub round: usize,
+    pub engine_a: String,
+    /// `None` for a bye
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_b: Option<String>,
+    /// Game points over all boards, win = 1, draw = 0.5
+    pub a_points: f64,
+    pub b_points: f64,
+    /// Playoff of a drawn elimination match: (A wins, draws, B wins)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playoff: Option<(usize, usize, usize)>,
+    /// `None` for a drawn Swiss match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+    /// "winners", "losers" or "final" in double elimination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bracket: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Standing {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// Win = 1, draw = 0.5
+    pub points: f64,
+    /// Sum over opponents of points scored against them times their points
+    pub sonneborn_berger: f64,
+    /// Swiss: match win = 1 (also for a bye), drawn match = 0.5
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_points: Option<f64>,
+    /// Swiss: sum of the opponents' match points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buchholz: Option<f64>,
+    /// Elimination: round of the last lost match, `None` for the winner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eliminated_in_round: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct TournamentReport {
+    pub format: TournamentFormat,
+    pub engines: Vec<EngineSpec>,
+    pub board_setups: Vec<String>,
+    pub games_per_pairing: usize,
+    pub random_opening_plies: usize,
+    pub pairings: Vec<PairingResult>,
+    /// Matches of a round-based format, in round order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<MatchResult>,
+    pub standings: Vec<Standing>,
+}
+
+/// Engine index pairs (a, b) that meet in the given format. Round-based
+/// formats pair each round from the results so far and have none up front.
+pub fn pairings(num_engines: usize, format: TournamentFormat) -> Vec<(usize, usize)> {
+    match format {
+        TournamentFormat::RoundRobin => (0..num_engines)
+            .flat_map(|a| (a + 1..num_engines).map(move |b| (a, b)))
+            .collect(),
+        TournamentFormat::Gauntlet => (1..num_engines).map(|b| (0, b)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Pairings for the next Swiss round, plus the engine with a bye if the field
+/// is odd. Engines are ranked by match points, then game points, then seed;
+/// each takes the highest-ranked opponent it has not met yet, a rematch only
+/// when nobody else is left. The bye goes to the lowest-ranked engine that has
+/// not had one.
+pub fn swiss_pairings(
+    match_points: &[f64],
+    game_points: &[f64],
+    met: &HashSet<(usize, usize)>,
+    had_bye: &[bool],
+) -> (Vec<(usize, usize)>, Option<usize>) {
+    let mut ranked: Vec<usize> = (0..match_points.len()).collect();
+    ranked.sort_by(|&a, &b| {
+        match_points[b]
+            .total_cmp(&match_points[a])
+            .then(game_points[b].total_cmp(&game_points[a]))
+            .then(a.cmp(&b))
+    });
+
+    let mut bye = None;
+    if ranked.len() % 2 == 1 {
+        let pos = ranked
+            .iter()
+            .rposition(|&e| !had_bye[e])
+            .unwrap_or(ranked.len() - 1);
+        bye = Some(ranked.remove(pos));
+    }
+
+    let mut pairs = Vec::new();
+    while !ranked.is_empty() {
+        let a = ranked.remove(0);
+        let pos = ranked
+            .iter()
+            .position(|&b| !met.contains(&(a.min(b), a.max(b))))
+            .unwrap_or(0);
+        pairs.push((a, ranked.remove(pos)));
+    }
+    (pairs, bye)
+}
+
+/// Pairings and byes for the next elimination round among the engines that
+/// are still in. Engines meet others with as many lost matches (the winners
+/// and losers brackets), best seed against worst seed. An odd bracket leaves
+/// out its best seed without a bye yet, which then gets a bye, or, once the
+/// next bracket is down to a single engine, plays it (the grand final).
+pub fn elimination_pairings(
+    alive: &[usize],
+    losses: &[usize],
+    had_bye: &[bool],
+) -> (Vec<(usize, usize)>, Vec<usize>) {
+    let mut loss_counts: Vec<usize> = alive.iter().map(|&e| losses[e]).collect();
+    loss_counts.sort_unstable();
+    loss_counts.dedup();
+
+    let mut pairs = Vec::new();
+    let mut byes = Vec::new();
+    let mut carry: Option<usize> = None;
+    for count in loss_counts {
+        // Seeds are engine indices, so `alive` order is seed order
+        let mut bracket: Vec<usize> = alive
+            .iter()
+            .copied()
+            .filter(|&e| losses[e] == count)
+            .collect();
+        if let Some(c) = carry.take() {
+            if bracket.len() == 1 {
+                pairs.push((c, bracket[0]));
+                continue;
+            }
+            byes.push(c);
+        }
+        if bracket.len() % 2 == 1 {
+            let pos = bracket.iter().position(|&e| !had_bye[e]).unwrap_or(0);
+            carry = Some(bracket.remove(pos));
+        }
+        while bracket.len() >= 2 {
+            let a = bracket.remove(0);
+            let b = bracket.pop().unwrap_or(a);
+            pairs.push((a, b));
+        }
+    }
+    byes.extend(carry);
+    (pairs, byes)
+}
+
+struct GameJob {
+    /// Index into the pairing records
+    record: usize,
+    board_setup: BoardSetupType,
+    a: usize,
+    b: usize,
+    /// Engine A plays Player1 in this game
+    a_is_p1: bool,
+}
+
+pub fn run_tournament(config: &TournamentConfig) -> anyhow::Result<TournamentReport> {
+    if config.engines.len() < 2 {
+        anyhow::bail!("A tournament needs at least two engines");
+    }
+
+    let mut records = Vec::new();
+    let mut matches = Vec::new();
+    let mut eliminated = vec![None; config.engines.len()];
+    match config.format {
+        TournamentFormat::Swiss { rounds } => {
+            run_swiss(config, rounds, &mut records, &mut matches)?;
+        }
+        TournamentFormat::SingleElimination | TournamentFormat::DoubleElimination => {
+            let lives = config.format.lives().unwrap_or(1);
+            eliminated = run_elimination(config, lives, &mut records, &mut matches)?;
+        }
+        TournamentFormat::RoundRobin | TournamentFormat::Gauntlet => {
+            let pairs = pairings(config.engines.len(), config.format);
+            play_pairs(config, &pairs, None, &mut records)?;
+        }
+    }
+    for pairing in &mut records {
+        pairing.elo = EloEstimate::from_counts(pairing.a_wins, pairing.draws, pairing.b_wins);
+    }
+
+    let standings = standings(config, &records, &matches, &eliminated);
+
+    Ok(TournamentReport {
+        format: config.format,
+        engines: config.engines.clone(),
+        board_setups: config.board_setups.iter().map(|b| b.to_string()).collect(),
+        games_per_pairing: config.games_per_pairing,
+        random_opening_plies: config.random_opening_plies,
+        pairings: records,
+        matches,
+        standings,
+    })
+}
+
+/// Play one game between engines `a` and `b`.
+/// `Some(true)` when engine A won, `None` for a draw.
+fn play_game(
+    config: &TournamentConfig,
+    board_setup: BoardSetupType,
+    a: usize,
+    b: usize,
+    a_is_p1: bool,
+) -> anyhow::Result<Option<bool>> {
+    let (p1_spec, p2_spec) = if a_is_p1 {
+        (&config.engines[a], &config.engines[b])
+    } else {
+        (&config.engines[b], &config.engines[a])
+    };
+    let p1 = p1_spec.create_ai(PlayerId::Player1);
+    let p2 = p2_spec.create_ai(PlayerId::Player2);
+
+    let mut game = Game::new(board_setup.create_board());
+    game.perspective_mode = PerspectiveMode::Fixed(PlayerId::Player1);
+    let mut rng = StdRng::from_entropy();
+    play_random_opening(&mut game, config.random_opening_plies, &mut rng);
+    let (winner, _, _, _) = run_game_silent(&mut game, &p1, &p2, true, None, &mut rng, None)?;
+
+    let a_player = if a_is_p1 {
+        PlayerId::Player1
+    } else {
+        PlayerId::Player2
+    };
+    Ok(winner.map(|w| w == a_player))
+}
+
+/// Play every pair on every board setup, appending one record per pair and
+/// board. Returns each pair's game points over all boards.
+fn play_pairs(
+    config: &TournamentConfig,
+    pairs: &[(usize, usize)],
+    round: Option<usize>,
+    records: &mut Vec<PairingResult>,
+) -> anyhow::Result<Vec<(f64, f64)>> {
+    let first = records.len();
+    let mut jobs = Vec::new();
+    for &board_setup in &config.board_setups {
+        for &(a, b) in pairs {
+            let record = records.len();
+            records.push(PairingResult {
+                round,
+                engine_a: config.engines[a].name.clone(),
+                engine_b: config.engines[b].name.clone(),
+                board_setup: board_setup.to_string(),
+                a_wins: 0,
+                draws: 0,
+                b_wins: 0,
+                elo: None,
+            });
+            for game in 0..config.games_per_pairing {
+                jobs.push(GameJob {
+                    record,
+                    board_setup,
+                    a,
+                    b,
+                    a_is_p1: game % 2 == 0,
+                });
+            }
+        }
+    }
+
+    let label = round.map_or("Tournament".to_string(), |r| format!("Round {}", r));
+    let total = jobs.len();
+    let completed = AtomicUsize::new(0);
+    let play = |job: &GameJob| -> anyhow::Result<(usize, Option<bool>)> {
+        let a_won = play_game(config, job.board_setup, job.a, job.b, job.a_is_p1)?;
+        let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+        eprint!("\r{}: {}/{} games", label, done, total);
+        Ok((job.record, a_won))
+    };
+
+    let outcomes: Vec<_> = if config.use_parallel {
+        jobs.par_iter().map(play).collect()
+    } else {
+        jobs.iter().map(play).collect()
+    };
+    eprintln!();
+
+    for outcome in outcomes {
+        let (record, a_won) = outcome?;
+        let pairing = &mut records[record];
+        match a_won {
+            Some(true) => pairing.a_wins += 1,
+            Some(false) => pairing.b_wins += 1,
+            None => pairing.draws += 1,
+        }
+    }
+
+    // Records are laid out board by board, pair by pair
+    let mut points = vec![(0.0, 0.0); pairs.len()];
+    for (i, record) in records[first..].iter().enumerate() {
+        let (a, b) = record.points();
+        points[i % pairs.len()].0 += a;
+        points[i % pairs.len()].1 += b;
+    }
+    Ok(points)
+}
+
+fn run_swiss(
+    config: &TournamentConfig,
+    rounds: usize,
+    records: &mut Vec<PairingResult>,
+    matches: &mut Vec<MatchResult>,
+) -> anyhow::Result<()> {
+    let n = config.engines.len();
+    let mut match_points = vec![0.0; n];
+    let mut game_points = vec![0.0; n];
+    let mut met = HashSet::new();
+    let mut had_bye = vec![false; n];
+
+    for round in 1..=rounds {
+        let (pairs, bye) = swiss_pairings(&match_points, &game_points, &met, &had_bye);
+        if let Some(e) = bye {
+            had_bye[e] = true;
+            match_points[e] += 1.0;
+            matches.push(bye_match(config, round, e));
+        }
+
+        let points = play_pairs(config, &pairs, Some(round), records)?;
+        for (&(a, b), &(pa, pb)) in pairs.iter().zip(&points) {
+            met.insert((a.min(b), a.max(b)));
+            game_points[a] += pa;
+            game_points[b] += pb;
+            let winner = if pa > pb {
+                match_points[a] += 1.0;
+                Some(a)
+            } else if pb > pa {
+                match_points[b] += 1.0;
+                Some(b)
+            } else {
+                match_points[a] += 0.5;
+                match_points[b] += 0.5;
+                None
+            };
+            matches.push(MatchResult {
+                round,
+                engine_a: config.engines[a].name.clone(),
+                engine_b: Some(config.engines[b].name.clone()),
+                a_points: pa,
+                b_points: pb,
+                playoff: None,
+                winner: winner.map(|w| config.engines[w].name.clone()),
+                bracket: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn bye_match(config: &TournamentConfig, round: usize, engine: usize) -> MatchResult {
+    MatchResult {
+        round,
+        engine_a: config.engines[engine].name.clone(),
+        engine_b: None,
+        a_points: 0.0,
+        b_points: 0.0,
+        playoff: None,
+        winner: Some(config.engines[engine].name.clone()),
+        bracket: None,
+    }
+}
+
+/// Decide a drawn elimination match by single playoff games on the first
+/// board setup, colors alternating, until one is won. If all
+/// [`MAX_PLAYOFF_GAMES`] are drawn, A advances.
+/// Returns (A wins, draws, B wins).
+fn playoff(config: &TournamentConfig, a: usize, b: usize) -> anyhow::Result<(usize, usize, usize)> {
+    let board_setup = config.board_setups[0];
+    let mut draws = 0;
+    for game in 0..MAX_PLAYOFF_GAMES {
+        match play_game(config, board_setup, a, b, game % 2 == 0)? {
+            Some(true) => return Ok((1, draws, 0)),
+            Some(false) => return Ok((0, draws, 1)),
+            None => draws += 1,
+        }
+    }
+    Ok((0, draws, 0))
+}
+
+/// Play rounds until one engine is left; returns each engine's elimination round
+fn run_elimination(
+    config: &TournamentConfig,
+    lives: usize,
+    records: &mut Vec<PairingResult>,
+    matches: &mut Vec<MatchResult>,
+) -> anyhow::Result<Vec<Option<usize>>> {
+    let n = config.engines.len();
+    let mut losses = vec![0; n];
+    let mut eliminated = vec![None; n];
+    let mut had_bye = vec![false; n];
+
+    let mut round = 0;
+    loop {
+        let alive: Vec<usize> = (0..n).filter(|&e| losses[e] < lives).collect();
+        if alive.len() < 2 {
+            break;
+        }
+        round += 1;
+
+        let (pairs, byes) = elimination_pairings(&alive, &losses, &had_bye);
+        for &e in &byes {
+            had_bye[e] = true;
+            matches.push(bye_match(config, round, e));
+        }
+
+        let points = play_pairs(config, &pairs, Some(round), records)?;
+        let play_off = |&((a, b), (pa, pb)): &((usize, usize), (f64, f64))| {
+            if pa == pb {
+                playoff(config, a, b).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let results: Vec<_> = pairs.iter().copied().zip(points.iter().copied()).collect();
+        let playoffs: Vec<anyhow::Result<_>> = if config.use_parallel {
+            results.par_iter().map(play_off).collect()
+        } else {
+            results.iter().map(play_off).collect()
+        };
+
+        for (((a, b), (pa, pb)), playoff) in results.into_iter().zip(playoffs) {
+            let playoff = playoff?;
+            // Pairs put the better seed (or the winners bracket engine in a
+            // grand final) first, so A also wins a playoff that stayed drawn
+            let a_advances = match playoff {
+                Some((_, _, b_wins)) => b_wins == 0,
+                None => pa > pb,
+            };
+            let (winner, loser) = if a_advances { (a, b) } else { (b, a) };
+            let bracket = (lives > 1).then(|| {
+                match (losses[a] == 0, losses[b] == 0) {
+                    (true, true) => "winners",
+                    (false, false) => "losers",
+                    _ => "final",
+                }
+                .to_string()
+            });
+            losses[loser] += 1;
+            if losses[loser] == lives {
+                eliminated[loser] = Some(round);
+            }
+            matches.push(MatchResult {
+                round,
+                engine_a: config.engines[a].name.clone(),
+                engine_b: Some(config.engines[b].name.clone()),
+                a_points: pa,
+                b_points: pb,
+                playoff,
+                winner: Some(config.engines[winner].name.clone()),
+                bracket,
+            });
+        }
+    }
+    Ok(eliminated)
+}
+
+/// Per-engine totals over all pairings, best first. Ties are broken by
+/// Sonneborn-Berger; Swiss ranks by match points, Buchholz, then game points,
+/// and elimination by how far each engine got.
+fn standings(
+    config: &TournamentConfig,
+    pairings: &[PairingResult],
+    matches: &[MatchResult],
+    eliminated: &[Option<usize>],
+) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = config
+        .engines
+        .iter()
+        .enumerate()
+        .map(|(i, e)| Standing {
+            name: e.name.clone(),
+            games: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            points: 0.0,
+            sonneborn_berger: 0.0,
+            match_points: None,
+            buchholz: None,
+            eliminated_in_round: if config.format.lives().is_some() {
+                eliminated[i]
+            } else {
+                None
+            },
+        })
+        .collect();
+
+    for p in pairings {
+        for (name, wins, losses) in [
+            (&p.engine_a, p.a_wins, p.b_wins),
+            (&p.engine_b, p.b_wins, p.a_wins),
+        ] {
+            if let Some(s) = standings.iter_mut().find(|s| &s.name == name) {
+                s.games += wins + p.draws + losses;
+                s.wins += wins;
+                s.draws += p.draws;
+                s.losses += losses;
+                s.points += wins as f64 + p.draws as f64 / 2.0;
+            }
+        }
+    }
+
+    let points_of = |standings: &[Standing], name: &str| {
+        standings
+            .iter()
+            .find(|s| s.name == name)
+            .map_or(0.0, |s| s.points)
+    };
+    let mut sonneborn_berger = vec![0.0; standings.len()];
+    for p in pairings {
+        let (a_points, b_points) = p.points();
+        for (i, s) in standings.iter().enumerate() {
+            if s.name == p.engine_a {
+                sonneborn_berger[i] += a_points * points_of(&standings, &p.engine_b);
+            } else if s.name == p.engine_b {
+                sonneborn_berger[i] += b_points * points_of(&standings, &p.engine_a);
+            }
+        }
+    }
+    for (s, sb) in standings.iter_mut().zip(sonneborn_berger) {
+        s.sonneborn_berger = sb;
+    }
+
+    if let TournamentFormat::Swiss { .. } = config.format {
+        for s in standings.iter_mut() {
+            let mut match_points = 0.0;
+            for m in matches {
+                if m.winner.as_ref() == Some(&s.name) {
+                    match_points += 1.0;
+                } else if m.winner.is_none()
+                    && (m.engine_a == s.name || m.engine_b.as_ref() == Some(&s.name))
+                {
+                    match_points += 0.5;
+                }
+            }
+            s.match_points = Some(match_points);
+        }
+        let buchholz: Vec<f64> = standings
+            .iter()
+            .map(|s| {
+                matches
+                    .iter()
+                    .filter_map(|m| match &m.engine_b {
+                        Some(b) if m.engine_a == s.name => Some(b),
+                        Some(b) if *b == s.name => Some(&m.engine_a),
+                        _ => None,
+                    })
+                    .filter_map(|opponent| standings.iter().find(|o| &o.name == opponent))
+                    .filter_map(|o| o.match_points)
+                    .sum()
+            })
+            .collect();
+        for (s, b) in standings.iter_mut().zip(buchholz) {
+            s.buchholz = Some(b);
+        }
+    }
+
+    let by_points = |a: &Standing, b: &Standing| {
+        b.points
+            .total_cmp(&a.points)
+            .then(b.sonneborn_berger.total_cmp(&a.sonneborn_berger))
+    };
+    match config.format {
+        TournamentFormat::Swiss { .. } => standings.sort_by(|a, b| {
+            let mp = |s: &Standing| s.match_points.unwrap_or(0.0);
+            let bh = |s: &Standing| s.buchholz.unwrap_or(0.0);
+            mp(b)
+                .total_cmp(&mp(a))
+                .then(bh(b).total_cmp(&bh(a)))
+                .then(by_points(a, b))
+        }),
+        TournamentFormat::SingleElimination | TournamentFormat::DoubleElimination => {
+            // Still in (the winner) first, then the later an engine went out
+            standings.sort_by(|a, b| {
+                let out = |s: &Standing| s.eliminated_in_round.unwrap_or(usize::MAX);
+                out(b).cmp(&out(a)).then(by_points(a, b))
+            })
+        }
+        TournamentFormat::RoundRobin | TournamentFormat::Gauntlet => standings.sort_by(by_points),
+    }
+    standings
+}
+
+impl TournamentReport {
+    /// Cross-table cell for `row` vs `col`: (wins, draws, losses) from `row`'s
+    /// point of view, summed over `board_setup` (or every board if `None`).
+    /// `None` when the two engines did not meet.
+    pub fn cross_cell(
+        &self,
+        row: &str,
+        col: &str,
+        board_setup: Option<&str>,
+    ) -> Option<(usize, usize, usize)> {
+        let mut cell = None;
+        for p in &self.pairings {
+            if board_setup.is_some_and(|b| b != p.board_setup) {
+                continue;
+            }
+            let (w, l) = if p.engine_a == row && p.engine_b == col {
+                (p.a_wins, p.b_wins)
+            } else if p.engine_a == col && p.engine_b == row {
+                (p.b_wins, p.a_wins)
+            } else {
+                continue;
+            };
+            let (cw, cd, cl) = cell.unwrap_or((0, 0, 0));
+            cell = Some((cw + w, cd + p.draws, cl + l));
+        }
+        cell
+    }
+}
+
+/// Print the cross-table for each board setup (plus a combined one) and the standings
+pub fn print_report(report: &TournamentReport) {
+    let names: Vec<&str> = report.engines.iter().map(|e| e.name.as_str()).collect();
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0).max(8);
+
+    let mut tables: Vec<Option<&str>> = report
+        .board_setups
+        .iter()
+        .map(|b| Some(b.as_str()))
+        .collect();
+    if report.board_setups.len() > 1 {
+        tables.push(None);
+    }
+
+    if report.format.is_round_based() {
+        print_rounds(report);
+        // Cross-tables of a large field are mostly empty
+        tables.clear();
+    }
+
+    for board in tables {
+        println!();
+        println!("--- {} ---", board.unwrap_or("All boards"));
+        print!("{:<width$}", "", width = width);
+        for name in &names {
+            print!(" | {:>width$}", name, width = width);
+        }
+        println!();
+        for row in &names {
+            print!("{:<width$}", row, width = width);
+            for col in &names {
+                let text = if row == col {
+                    "-".to_string()
+                } else {
+                    match report.cross_cell(row, col, board) {
+                        Some((w, d, l)) => format!("{}-{}-{}", w, d, l),
+                        None => String::new(),
+                    }
+                };
+                print!(" | {:>width$}", text, width = width);
+            }
+            println!();
+        }
+    }
+
+    println!();
+    println!("=== Standings (W-D-L) ===");
+    for (rank, s) in report.standings.iter().enumerate() {
+        let pct = if s.games > 0 {
+            s.points / s.games as f64 * 100.0
+        } else {
+            0.0
+        };
+        let tiebreak = match report.format {
+            TournamentFormat::Swiss { .. } => format!(
+                "MP {:.1}  Buchholz {:.1}",
+                s.match_points.unwrap_or(0.0),
+                s.buchholz.unwrap_or(0.0)
+            ),
+            TournamentFormat::SingleElimination | TournamentFormat::DoubleElimination => {
+                match s.eliminated_in_round {
+                    Some(round) => format!("out in round {}", round),
+                    None => "winner".to_string(),
+                }
+            }
+            TournamentFormat::RoundRobin | TournamentFormat::Gauntlet => {
+                format!("SB {:.2}", s.sonneborn_berger)
+            }
+        };
+        println!(
+            "{:>2}. {:<width$} {:>5.1}/{:<4} ({:.1}%)  {}-{}-{}  {}",
+            rank + 1,
+            s.name,
+            s.points,
+            s.games,
+            pct,
+            s.wins,
+            s.draws,
+            s.losses,
+            tiebreak,
+            width = width
+        );
+    }
+}
+
+fn print_rounds(report: &TournamentReport) {
+    let mut round = 0;
+    for m in &report.matches {
+        if m.round != round {
+            round = m.round;
+            println!();
+            println!("--- Round {} ---", round);
+        }
+        let Some(engine_b) = &m.engine_b else {
+            println!("  {} (bye)", m.engine_a);
+            continue;
+        };
+        let mut line = format!(
+            "  {} {:.1}-{:.1} {}",
+            m.engine_a, m.a_points, m.b_points, engine_b
+        );
+        if let Some((a, d, b)) = m.playoff {
+            line.push_str(&format!(", playoff {}-{}-{}", a, d, b));
+        }
+        match &m.winner {
+            Some(winner) => line.push_str(&format!(" -> {}", winner)),
+            None => line.push_str(" (drawn)"),
+        }
+        if let Some(bracket) = &m.bracket {
+            line.push_str(&format!(" [{}]", bracket));
+        }
+        println!("{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairings_and_spec_parsing() {
+        assert_eq!(
+            pairings(3, TournamentFormat::RoundRobin),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+        assert_eq!(
+            pairings(3, TournamentFormat::Gauntlet),
+            vec![(0, 1), (0, 2)]
+        );
+
+        let spec = EngineSpec::parse("nn:Light:NeuralNetwork:models/a.onnx").unwrap();
+        assert_eq!(spec.strength, AIStrength::Light);
+        assert_eq!(spec.evaluator, "NeuralNetwork");
+        assert_eq!(spec.model_path.as_deref(), Some("models/a.onnx"));
+        assert_eq!(EngineSpec::parse("hc").unwrap().evaluator, "Handcrafted");
+        assert!(EngineSpec::parse(":Strong").is_err());
+    }
+
+    #[test]
+    fn test_swiss_pairings_avoid_rematches() {
+        // 0 and 1 lead and already met, 4 sits out as the lowest ranked
+        let match_points = [1.0, 1.0, 0.0, 0.0, 0.0];
+        let game_points = [2.0, 1.5, 0.5, 0.0, 0.0];
+        let met = HashSet::from([(0, 1), (2, 3)]);
+        let (pairs, bye) = swiss_pairings(&match_points, &game_points, &met, &[false; 5]);
+        assert_eq!(pairs, vec![(0, 2), (1, 3)]);
+        assert_eq!(bye, Some(4));
+
+        let (_, bye) = swiss_pairings(
+            &match_points,
+            &game_points,
+            &met,
+            &[false, false, false, false, true],
+        );
+        assert_eq!(bye, Some(3));
+        assert_eq!(default_swiss_rounds(16), 4);
+        assert_eq!(default_swiss_rounds(17), 5);
+    }
+
+    #[test]
+    fn test_double_elimination_brackets() {
+        let none = [false; 8];
+        // First round: best seed against worst
+        let (pairs, byes) = elimination_pairings(&[0, 1, 2, 3, 4, 5, 6, 7], &[0; 8], &none);
+        assert_eq!(pairs, vec![(0, 7), (1, 6), (2, 5), (3, 4)]);
+        assert!(byes.is_empty());
+
+        // Winners bracket champion waits while the losers bracket plays down
+        let losses = [0, 1, 2, 1, 2, 2, 1, 2];
+        let (pairs, byes) = elimination_pairings(&[0, 1, 3, 6], &losses, &none);
+        assert_eq!(pairs, vec![(3, 6)]);
+        assert_eq!(byes, vec![0, 1]);
+
+        // Grand final once both brackets are down to one engine
+        let (pairs, byes) = elimination_pairings(&[0, 3], &losses, &none);
+        assert_eq!(pairs, vec![(0, 3)]);
+        assert!(byes.is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+//! Installation self-test
+//!
+//! Quick checks that a build works end to end: board setups, move generation,
+//! search, config loading and (when compiled with `ml`) loading an ONNX model.
+//! Run with `cargo run --release -- selftest`, or `--features full` to verify
+//! the ML path as well.
+
+use crate::core::PlayerId;
+use crate::logic::legal_moves;
+use crate::ml::model_registry::ModelRegistry;
+use crate::ml::nn_evaluator::NNEvaluator;
+use crate::player::ai::config::AIConfig;
+use crate::player::ai::evaluator::Evaluator;
+use crate::player::ai::{AIStrength, AlphaBetaAI};
+use crate::selfplay::BoardSetupType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Not applicable to this build or environment (e.g. no model on disk)
+    Skip,
+    Fail,
+}
+
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+const SETUPS: [BoardSetupType; 6] = [
+    BoardSetupType::StandardMixed,
+    BoardSetupType::ReversedMixed,
+    BoardSetupType::ShogiOnly,
+    BoardSetupType::ChessOnly,
+    BoardSetupType::Fair,
+    BoardSetupType::ReversedFair,
+];
+
+/// Run every check. The caller decides how to report failures.
+pub fn run_checks() -> Vec<CheckResult> {
+    vec![check_config(), check_setups(), check_search(), check_ml()]
+}
+
+fn check_config() -> CheckResult {
+    let config = AIConfig::get();
+    CheckResult::new(
+        "config",
+        CheckStatus::Pass,
+        format!(
+            "evaluator={}, max depth {}/{} (Light/Strong)",
+            config.evaluation.evaluator_type,
+            config.search.max_depth(AIStrength::Light),
+            config.search.max_depth(AIStrength::Strong)
+        ),
+    )
+}
+
+fn check_setups() -> CheckResult {
+    for setup in SETUPS {
+        let board = setup.create_board();
+        let moves = legal_moves(&board, PlayerId::Player1);
+        if moves.is_empty() {
+            return CheckResult::new(
+                "setups",
+                CheckStatus::Fail,
+                format!("{} has no legal moves for Player1", setup),
+            );
+        }
+    }
+    CheckResult::new(
+        "setups",
+        CheckStatus::Pass,
+        format!("{} setups generate legal moves", SETUPS.len()),
+    )
+}
+
+fn check_search() -> CheckResult {
+    let board = BoardSetupType::ShogiOnly.create_board();
+    let ai = AlphaBetaAI::new(PlayerId::Player1, "SelfTest", AIStrength::Light, None, true);
+    match ai.search_fixed_depth(&board, 2) {
+        Some(mv) if legal_moves(&board, PlayerId::Player1).contains(&mv) => CheckResult::new(
+            "search",
+            CheckStatus::Pass,
+            format!("depth 2 with {}", ai.evaluator_name()),
+        ),
+        Some(mv) => CheckResult::new(
+            "search",
+            CheckStatus::Fail,
+            format!("illegal best move {:?}", mv),
+        ),
+        None => CheckResult::new("search", CheckStatus::Fail, "no move returned"),
+    }
+}
+
+/// Load the configured (or first discovered) model and evaluate a position
+fn check_ml() -> CheckResult {
+    if !crate::ml::ML_ENABLED {
+        let config = AIConfig::get();
+        let detail = if config.evaluation.evaluator_type == "NeuralNetwork" {
+            crate::ml::ML_UNAVAILABLE_MESSAGE.to_string()
+        } else {
+            "compiled without ml (rebuild with --features full)".to_string()
+        };
+        return CheckResult::new("ml", CheckStatus::Skip, detail);
+    }
+
+    let model_path = AIConfig::get()
+        .evaluation
+        .nn_model_path
+        .clone()
+        .filter(|path| std::path::Path::new(path).exists())
+        .or_else(|| {
+            let mut registry = ModelRegistry::new();
+            registry.discover_models("models").ok()?;
+            let mut models = registry.list();
+            models.sort_by(|a, b| a.name.cmp(&b.name));
+            models.first().map(|m| m.path.to_string_lossy().to_string())
+        });
+
+    let Some(path) = model_path else {
+        return CheckResult::new("ml", CheckStatus::Skip, "no model found in models/");
+    };
+
+    // Loading refuses models for another feature layout (see check_model_version)
+    match NNEvaluator::load_silent(&path) {
+        Ok(mut evaluator) => {
+            let board = BoardSetupType::ShogiOnly.create_board();
+            let score = evaluator.evaluate(&board);
+            CheckResult::new(
+                "ml",
+                CheckStatus::Pass,
+                format!("{} evaluates the initial position as {}", path, score),
+            )
+        }
+        Err(e) => CheckResult::new("ml", CheckStatus::Fail, format!("{}: {}", path, e)),
+    }
+}
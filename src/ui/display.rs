@@ -1,5 +1,12 @@
-use crate::core::{Board, PlayerId, Position};
-use crossterm::{cursor, execute, style::Stylize, terminal};
+use crate::core::{Board, PieceKind, PlayerId, Position};
+use crate::player::ai::eval::EvalBreakdown;
+use crate::player::clock::ClockFace;
+use crate::player::EngineView;
+use crate::ui::glyphs::{glyph_style, hand_label, piece_label, GlyphStyle};
+use crate::ui::theme::{layered, Theme};
+use crossterm::style::{Color, Stylize};
+use crossterm::{cursor, execute, terminal};
+use std::collections::HashMap;
 use std::io::stdout;
 
 pub struct DisplayState {
@@ -12,6 +19,26 @@ pub struct DisplayState {
     pub last_move: Option<crate::core::Move>,
     pub perspective: PlayerId,
     pub show_cursor: bool,
+    /// Evaluation heatmap: each piece's positional value for its owner
+    /// (see [`EvalBreakdown::positional_map`]); shades the squares and
+    /// prints the value under each piece
+    pub heatmap: Option<HashMap<Position, i32>>,
+    /// Moves played so far in notation, listed beside the board
+    pub move_list: Vec<String>,
+    /// How many of the latest moves the list is scrolled back past
+    pub move_list_scroll: usize,
+    /// The opposing engine's last search: an evaluation bar beside the board
+    /// and its depth, score, nodes and PV under it
+    pub engine: Option<EngineView>,
+    /// Both clocks of a timed game, on the line above the board
+    pub clocks: Option<ClockFace>,
+    /// Mark the pieces of `perspective` that the opponent attacks
+    pub show_threats: bool,
+    /// A suggested move, its squares marked like the squares to move to
+    pub hint: Option<crate::core::Move>,
+    /// Blindfold play: the squares, markers and move list without the pieces
+    /// on the board or in hand
+    pub blindfold: bool,
 }
 
 impl Default for DisplayState {
@@ -26,6 +53,14 @@ impl Default for DisplayState {
             last_move: None,
             perspective: PlayerId::default(),
             show_cursor: true, // Default to showing cursor
+            heatmap: None,
+            move_list: Vec::new(),
+            move_list_scroll: 0,
+            engine: None,
+            clocks: None,
+            show_threats: false,
+            hint: None,
+            blindfold: false,
         }
     }
 }
@@ -36,7 +71,78 @@ impl DisplayState {
     }
 }
 
+/// How the board fits the terminal, picked again on every redraw so a
+/// resized window gets the layout that fits it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Two lines per rank, the second for padding and heatmap values
+    Wide,
+    /// One line per rank, the heatmap as colours only
+    Compact,
+    /// Not even the compact board fits: only a message is drawn
+    TooSmall,
+}
+
+/// Lines printed under the board by the callers (the key help)
+const FOOTER_ROWS: u16 = 2;
+/// Narrowest move list worth drawing beside the board
+const MOVE_LIST_WIDTH: u16 = 12;
+
+/// The terminal's columns and rows, unlimited when it cannot be measured
+/// (e.g. output to a pipe), so everything is drawn as before
+fn terminal_size() -> (u16, u16) {
+    terminal::size().unwrap_or((u16::MAX, u16::MAX))
+}
+
+/// Lines of the ranks, from the first to the last
+fn rank_rows(board: &Board, state: &DisplayState, layout: Layout) -> u16 {
+    match layout {
+        Layout::Wide => board.height as u16 * CELL_HEIGHT - 1 + state.heatmap.is_some() as u16,
+        Layout::Compact | Layout::TooSmall => board.height as u16,
+    }
+}
+
+/// Columns and rows `layout` needs for the board, hands, engine lines and
+/// the key help under them (not the panels beside the board)
+fn needed_size(board: &Board, state: &DisplayState, layout: Layout) -> (u16, u16) {
+    let width = BOARD_LEFT + board.width as u16 * CELL_WIDTH + 1;
+    let hands: u16 = [PlayerId::Player1, PlayerId::Player2]
+        .into_iter()
+        .map(|player| 2 + hand_items(board, player).is_some() as u16)
+        .sum();
+    let engine = state
+        .engine
+        .as_ref()
+        .map_or(0, |engine| 1 + !engine.pv.is_empty() as u16);
+    let height = BOARD_TOP + rank_rows(board, state, layout) + 1 + hands + engine + FOOTER_ROWS;
+    (width, height)
+}
+
+/// The widest layout that fits a terminal of `size` (columns, rows)
+pub fn layout_for(board: &Board, state: &DisplayState, size: (u16, u16)) -> Layout {
+    let (columns, rows) = size;
+    [Layout::Wide, Layout::Compact]
+        .into_iter()
+        .find(|&layout| {
+            let (width, height) = needed_size(board, state, layout);
+            width <= columns && height <= rows
+        })
+        .unwrap_or(Layout::TooSmall)
+}
+
+/// The layout [`render_board`] uses in the terminal as it is now
+pub fn current_layout(board: &Board, state: &DisplayState) -> Layout {
+    layout_for(board, state, terminal_size())
+}
+
 pub fn render_board(board: &Board, state: &DisplayState) {
+    let _timer = crate::profile::timer(crate::profile::Subsystem::Render);
+    crate::crash::record_render(board, state.status_msg.as_deref());
+    // Detect before clearing so the width probe leaves no trace
+    let glyphs = glyph_style();
+    let theme = Theme::get();
+    let size = terminal_size();
+    let layout = layout_for(board, state, size);
     let mut out = stdout();
 
     // 画面クリア（スクロール防止）
@@ -47,12 +153,35 @@ pub fn render_board(board: &Board, state: &DisplayState) {
     )
     .unwrap();
 
+    if layout == Layout::TooSmall {
+        // 最小の盤面も入らないので、大きさの案内だけ出す
+        let (width, height) = needed_size(board, state, Layout::Compact);
+        if let Some(msg) = &state.status_msg {
+            print!("{}\r\n", theme.status.apply(msg));
+        }
+        print!(
+            "{}\r\n",
+            crate::trf!(
+                "Terminal too small: {}x{}, the board needs {}x{}. Enlarge the window.",
+                "端末が小さすぎます: {}x{} (盤面には {}x{} 必要)。ウィンドウを広げてください。",
+                size.0,
+                size.1,
+                width,
+                height
+            )
+        );
+        return;
+    }
+
     print!("=== Unified Board Game Engine ===\r\n");
     if let Some(msg) = &state.status_msg {
-        print!("{}\r\n", msg.clone().bold().yellow());
+        print!("{}\r\n", theme.status.apply(msg));
     } else {
         print!("\r\n");
     }
+    if let Some(clocks) = &state.clocks {
+        print_clocks(clocks);
+    }
     print!("\r\n");
 
     // X軸ラベル
@@ -69,6 +198,7 @@ pub fn render_board(board: &Board, state: &DisplayState) {
 
     print!("   +{}+\r\n", "----".repeat(board.width));
 
+    let (checked, threatened) = danger_squares(board, state);
     for i in 0..board.height {
         let y = if state.perspective == PlayerId::Player1 {
             i
@@ -92,66 +222,77 @@ pub fn render_board(board: &Board, state: &DisplayState) {
             let is_selected = state.selected == Some(pos);
             let is_highlight = state.highlights.contains(&pos);
 
+            // 目隠しでは駒を描かず、持ち主の色・王手・当たりの印も出さない
+            let piece = piece.filter(|_| !state.blindfold);
+            // Always two columns wide
             let char_str = if let Some(p) = piece {
-                p.display_char().to_string()
+                piece_label(p.kind, glyphs)
             } else {
-                ".".to_string()
+                " .".to_string()
             };
 
-            let is_shogi = piece.map(|p| p.is_shogi).unwrap_or(false);
-
-            let is_last_move = if let Some(mv) = &state.last_move {
-                match mv {
-                    crate::core::Move::Normal { from, to, .. } => *from == pos || *to == pos,
-                    crate::core::Move::Drop { to, .. } => *to == pos,
-                }
-            } else {
-                false
+            let touches = |mv: &crate::core::Move| match mv {
+                crate::core::Move::Normal { from, to, .. } => *from == pos || *to == pos,
+                crate::core::Move::Drop { to, .. } => *to == pos,
             };
+            let is_last_move = state.last_move.as_ref().is_some_and(touches);
+            let is_hint = state.hint.as_ref().is_some_and(touches);
 
-            let (prefix, suffix) = if is_cursor {
-                ("[", "]")
+            let (prefix, suffix, marked) = if is_cursor {
+                ('[', ']', Some(theme.cursor))
             } else if is_selected {
-                ("|", "|")
+                ('|', '|', Some(theme.selected))
             } else if is_highlight {
-                ("(", ")")
+                ('(', ')', Some(theme.highlight))
+            } else if is_hint {
+                ('>', '<', Some(theme.highlight))
             } else if is_last_move {
-                ("{", "}")
+                let (open, close) = theme.last_move_marker;
+                (open, close, Some(theme.last_move))
+            } else if checked.contains(&pos) {
+                ('!', '!', None)
+            } else if threatened.contains(&pos) {
+                ('*', '*', None)
             } else {
-                (" ", " ")
+                (' ', ' ', None)
             };
 
-            let cell_text = if is_shogi {
-                format!("{}{}{}", prefix, char_str, suffix)
-            } else {
-                format!("{} {}{}", prefix, char_str, suffix)
-            };
+            let cell_text = format!("{}{}{}", prefix, char_str, suffix);
 
-            if is_cursor {
-                print!("{}", cell_text.yellow());
-            } else if is_selected {
-                print!("{}", cell_text.blue());
-            } else if is_highlight {
-                print!("{}", cell_text.green());
-            } else if is_last_move {
-                print!("{}", cell_text.red());
-            } else if let Some(p) = piece {
-                if p.owner == PlayerId::Player1 {
-                    print!("{}", cell_text.cyan());
-                } else {
-                    print!("{}", cell_text.magenta());
-                }
-            } else {
-                print!("{}", cell_text);
+            // 駒の持ち主の色の上に、王手・当たり、カーソルなどの色の順に重ねる
+            let mut style = piece.map_or_else(Default::default, |p| theme.player(p.owner));
+            if threatened.contains(&pos) {
+                style = layered(style, theme.threatened);
+            }
+            if checked.contains(&pos) {
+                style = layered(style, theme.check);
+            }
+            if let Some(marked) = marked {
+                style = layered(style, marked);
+            }
+            match heat(state, pos).filter(|_| theme.colors) {
+                Some(value) => print!("{}", style.apply(cell_text).on(heat_color(value))),
+                None => print!("{}", style.apply(cell_text)),
             }
         }
         print!("|\r\n");
 
-        // --- Line 2: Vertical Padding ---
-        if i < board.height - 1 {
+        // --- Line 2: Vertical Padding (heatmap values) ---
+        if layout == Layout::Wide && (i < board.height - 1 || state.heatmap.is_some()) {
             print!("   |");
-            for _ in 0..board.width {
-                print!("    ");
+            for j in 0..board.width {
+                let x = if state.perspective == PlayerId::Player1 {
+                    j
+                } else {
+                    board.width - 1 - j
+                };
+                match heat(state, Position::new(x, y)) {
+                    Some(value) if theme.colors => {
+                        print!("{}", heat_label(value).on(heat_color(value)))
+                    }
+                    Some(value) => print!("{}", heat_label(value)),
+                    None => print!("    "),
+                }
             }
             print!("|\r\n");
         }
@@ -159,33 +300,367 @@ pub fn render_board(board: &Board, state: &DisplayState) {
     print!("   +{}+\r\n", "----".repeat(board.width));
 
     // 持ち駒表示
-    render_hands(board, state);
+    render_hands(board, state, glyphs);
+
+    render_engine(board, state, layout, size.0);
+    render_move_list(board, state, layout, size.0);
 }
 
-fn render_hands(board: &Board, state: &DisplayState) {
+/// Kings in check, and the pieces of `state.perspective` the opponent
+/// attacks when [`DisplayState::show_threats`] is on
+fn danger_squares(board: &Board, state: &DisplayState) -> (Vec<Position>, Vec<Position>) {
+    if state.blindfold {
+        return (Vec::new(), Vec::new());
+    }
+    let checked = [PlayerId::Player1, PlayerId::Player2]
+        .into_iter()
+        .filter(|&player| crate::logic::is_in_check(board, player))
+        .filter_map(|player| board.find_king(player))
+        .collect();
+    let threatened = if state.show_threats {
+        crate::logic::attacked_pieces(board, state.perspective)
+    } else {
+        Vec::new()
+    };
+    (checked, threatened)
+}
+
+/// Centipawns at which the evaluation bar is about three quarters full
+const EVAL_BAR_SCALE: f64 = 600.0;
+/// Moves of the engine's PV shown under the board
+const ENGINE_PV_MOVES: usize = 8;
+
+/// Rows of the evaluation bar filled for `state.perspective`, out of `rows`
+fn eval_bar_fill(score: i32, perspective: PlayerId, rows: usize) -> usize {
+    let score = if perspective == PlayerId::Player1 {
+        score
+    } else {
+        -score
+    };
+    // Logistic, so a won position fills the bar without hiding small edges
+    let share = 1.0 / (1.0 + (-(score as f64) * 3f64.ln() / EVAL_BAR_SCALE).exp());
+    (share * rows as f64).round() as usize
+}
+
+fn render_engine(board: &Board, state: &DisplayState, layout: Layout, columns: u16) {
+    let Some(engine) = &state.engine else {
+        return;
+    };
+    print!(
+        "{}: depth {} | eval {:+} (Player1's view) | {} nodes\r\n",
+        engine.name, engine.depth, engine.score, engine.nodes
+    );
+    if !engine.pv.is_empty() {
+        let pv: Vec<&str> = engine
+            .pv
+            .iter()
+            .take(ENGINE_PV_MOVES)
+            .map(String::as_str)
+            .collect();
+        print!("PV: {}\r\n", pv.join(" "));
+    }
+
+    // The bar, from the bottom in the colour of the side at the bottom
+    let rows = move_list_rows(board, state, layout);
+    let filled = eval_bar_fill(engine.score, state.perspective, rows);
+    let left = BOARD_LEFT + board.width as u16 * CELL_WIDTH + 2;
+    if left.saturating_add(2) > columns {
+        return;
+    }
+    let mut out = stdout();
+    let _ = execute!(out, cursor::SavePosition);
+    for row in 0..rows {
+        let _ = execute!(out, cursor::MoveTo(left, BOARD_TOP + row as u16));
+        let theme = Theme::get();
+        if row >= rows - filled {
+            print!("{}", theme.eval_filled.apply("  "));
+        } else {
+            print!("{}", theme.eval_empty.apply("  "));
+        }
+    }
+    let _ = execute!(out, cursor::RestorePosition);
+}
+
+/// Line of the clocks, between the status line and the file labels
+const CLOCK_ROW: u16 = 2;
+
+/// Both clocks, the running one highlighted
+fn print_clocks(clocks: &ClockFace) {
     for player in [PlayerId::Player1, PlayerId::Player2] {
-        print!("{:?} Hand:\r\n", player);
-        if let Some(hand) = board.hand.get(&player) {
-            let mut items: Vec<_> = hand.iter().filter(|(_, &c)| c > 0).collect();
-            items.sort_by_key(|(k, _)| format!("{:?}", k));
+        let label = format!(" {:?} {} ", player, clocks.time(player));
+        if clocks.running == Some(player) {
+            print!("{}", Theme::get().active.apply(label));
+        } else {
+            print!("{}", label);
+        }
+        print!("  ");
+    }
+}
+
+/// Redraws just the clocks over the last [`render_board`], so they keep
+/// ticking while the board waits for the opponent
+pub fn render_clocks(clocks: &ClockFace) {
+    let mut out = stdout();
+    let _ = execute!(
+        out,
+        cursor::SavePosition,
+        cursor::MoveTo(0, CLOCK_ROW),
+        terminal::Clear(terminal::ClearType::CurrentLine)
+    );
+    print_clocks(clocks);
+    let _ = execute!(out, cursor::RestorePosition);
+}
+
+/// Lines of the move list beside the board, one move each
+fn move_list_rows(board: &Board, state: &DisplayState, layout: Layout) -> usize {
+    rank_rows(board, state, layout) as usize
+}
+
+/// Scrolls the move list `lines` back (negative: towards the latest move),
+/// no further than its first move
+pub fn scroll_move_list(board: &Board, state: &mut DisplayState, lines: isize) {
+    let layout = current_layout(board, state);
+    scroll_move_list_in(board, state, layout, lines);
+}
+
+fn scroll_move_list_in(board: &Board, state: &mut DisplayState, layout: Layout, lines: isize) {
+    let max = state
+        .move_list
+        .len()
+        .saturating_sub(move_list_rows(board, state, layout));
+    state.move_list_scroll = state.move_list_scroll.saturating_add_signed(lines).min(max);
+}
 
-            if items.is_empty() {
+/// The numbered moves shown beside the board, latest last
+fn visible_moves(board: &Board, state: &DisplayState, layout: Layout) -> Vec<String> {
+    let end = state.move_list.len().saturating_sub(state.move_list_scroll);
+    let start = end.saturating_sub(move_list_rows(board, state, layout));
+    (start..end)
+        .map(|i| format!("{:3} {}", i + 1, state.move_list[i]))
+        .collect()
+}
+
+fn render_move_list(board: &Board, state: &DisplayState, layout: Layout, columns: u16) {
+    // Right of the evaluation bar when there is one
+    let left =
+        BOARD_LEFT + board.width as u16 * CELL_WIDTH + if state.engine.is_some() { 6 } else { 3 };
+    if state.move_list.is_empty() || left.saturating_add(MOVE_LIST_WIDTH) > columns {
+        return;
+    }
+    let mut out = stdout();
+    let header = if state.move_list_scroll > 0 {
+        format!("Moves ({} more below)", state.move_list_scroll)
+    } else {
+        "Moves".to_string()
+    };
+    let _ = execute!(
+        out,
+        cursor::SavePosition,
+        cursor::MoveTo(left, BOARD_TOP - 2)
+    );
+    print!("{}", header.bold());
+    for (row, line) in visible_moves(board, state, layout).into_iter().enumerate() {
+        let _ = execute!(out, cursor::MoveTo(left, BOARD_TOP + row as u16));
+        print!("{}", line);
+    }
+    let _ = execute!(out, cursor::RestorePosition);
+}
+
+fn heat(state: &DisplayState, pos: Position) -> Option<i32> {
+    if state.blindfold {
+        return None;
+    }
+    state.heatmap.as_ref()?.get(&pos).copied()
+}
+
+/// Green where the evaluator likes the piece for its owner, red where it does not
+fn heat_color(value: i32) -> Color {
+    match value {
+        v if v >= 60 => Color::Green,
+        v if v >= 15 => Color::DarkGreen,
+        v if v <= -60 => Color::Red,
+        v if v <= -15 => Color::DarkRed,
+        _ => Color::DarkGrey,
+    }
+}
+
+/// Four columns wide, like a cell
+fn heat_label(value: i32) -> String {
+    format!("{:>+4}", value.clamp(-999, 999))
+}
+
+/// Legend line for the heatmap colors
+pub fn heatmap_legend() -> String {
+    if !Theme::get().colors {
+        return "Heatmap: positional value for the owner under each piece".to_string();
+    }
+    format!(
+        "Heatmap (positional value for the owner): {} {} {} {} {}",
+        " >=60 ".on(heat_color(60)),
+        " >=15 ".on(heat_color(15)),
+        " ~0 ".on(heat_color(0)),
+        " <=-15 ".on(heat_color(-15)),
+        " <=-60 ".on(heat_color(-60)),
+    )
+}
+
+/// Plain text heatmap grid for non-interactive output: every piece with its
+/// positional value for its owner, Player2's pieces marked with `v`
+pub fn heatmap_lines(board: &Board, breakdown: &EvalBreakdown) -> Vec<String> {
+    let glyphs = glyph_style();
+    let mut lines = Vec::with_capacity(board.height + 1);
+    let mut header = "   ".to_string();
+    for x in 0..board.width {
+        header.push_str(&format!("{:>8}", x + 1));
+    }
+    lines.push(header);
+    for y in 0..board.height {
+        let mut line = format!("{:2} ", y + 1);
+        for x in 0..board.width {
+            let pos = Position::new(x, y);
+            match (board.get_piece(pos), breakdown.squares.get(&pos)) {
+                (Some(piece), Some(c)) => {
+                    let owner = if piece.owner == PlayerId::Player1 {
+                        ' '
+                    } else {
+                        'v'
+                    };
+                    line.push_str(&format!(
+                        " {}{}{}",
+                        owner,
+                        piece_label(piece.kind, glyphs),
+                        heat_label(c.positional())
+                    ));
+                }
+                _ => line.push_str("       ."),
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `player`'s pieces in hand in display order, `None` if they have no hand
+pub fn hand_items(board: &Board, player: PlayerId) -> Option<Vec<(PieceKind, usize)>> {
+    let hand = board.hand.get(&player)?;
+    let mut items: Vec<_> = hand
+        .iter()
+        .filter(|(_, &c)| c > 0)
+        .map(|(&k, &c)| (k, c))
+        .collect();
+    items.sort_by_key(|(k, _)| format!("{:?}", k));
+    Some(items)
+}
+
+fn hand_item_label(kind: PieceKind, count: usize, glyphs: GlyphStyle) -> String {
+    format!("[{} x{}]", hand_label(kind, glyphs), count)
+}
+
+// Screen layout of `render_board`, for `click_target`: the title, status,
+// blank and file label lines and the top border come before the first rank,
+// every rank takes two lines (pieces, then padding; one in the compact
+// layout) and every file four columns after the rank label and the left border
+const BOARD_TOP: u16 = 5;
+const BOARD_LEFT: u16 = 4;
+const CELL_WIDTH: u16 = 4;
+const CELL_HEIGHT: u16 = 2;
+
+/// What a mouse click hit on the screen [`render_board`] drew
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickTarget {
+    Square(Position),
+    /// The piece at this index of [`hand_items`]
+    Hand(PlayerId, usize),
+}
+
+/// The square or piece in hand at terminal cell (`column`, `row`), for a
+/// board drawn with `state`
+pub fn click_target(
+    board: &Board,
+    state: &DisplayState,
+    column: u16,
+    row: u16,
+) -> Option<ClickTarget> {
+    let layout = current_layout(board, state);
+    click_target_in(board, state, layout, column, row)
+}
+
+fn click_target_in(
+    board: &Board,
+    state: &DisplayState,
+    layout: Layout,
+    column: u16,
+    row: u16,
+) -> Option<ClickTarget> {
+    let line_height = match layout {
+        Layout::Wide => CELL_HEIGHT,
+        Layout::Compact => 1,
+        Layout::TooSmall => return None,
+    };
+    let ranks = rank_rows(board, state, layout);
+    if (BOARD_TOP..BOARD_TOP + ranks).contains(&row) && column >= BOARD_LEFT {
+        let i = ((row - BOARD_TOP) / line_height) as usize;
+        let j = ((column - BOARD_LEFT) / CELL_WIDTH) as usize;
+        if i >= board.height || j >= board.width {
+            return None;
+        }
+        let (x, y) = if state.perspective == PlayerId::Player1 {
+            (j, i)
+        } else {
+            (board.width - 1 - j, board.height - 1 - i)
+        };
+        return Some(ClickTarget::Square(Position::new(x, y)));
+    }
+
+    // Below the bottom border, as `render_hands` prints them
+    let glyphs = glyph_style();
+    let mut line = BOARD_TOP + ranks;
+    for player in [PlayerId::Player1, PlayerId::Player2] {
+        line += 1; // "PlayerN Hand:"
+        if let Some(items) = hand_items(board, player) {
+            line += 1;
+            if line == row && !state.blindfold {
+                // After the two-space indent, each label is followed by a space
+                let mut left = 2;
+                for (index, (kind, count)) in items.into_iter().enumerate() {
+                    let width = hand_item_label(kind, count, glyphs)
+                        .chars()
+                        .map(|c| if c.is_ascii() { 1 } else { 2 })
+                        .sum::<u16>();
+                    if (left..left + width).contains(&column) {
+                        return Some(ClickTarget::Hand(player, index));
+                    }
+                    left += width + 1;
+                }
+                return None;
+            }
+        }
+        line += 1; // blank
+    }
+    None
+}
+
+fn render_hands(board: &Board, state: &DisplayState, glyphs: GlyphStyle) {
+    for player in [PlayerId::Player1, PlayerId::Player2] {
+        print!("{:?} Hand:\r\n", player);
+        if let Some(items) = hand_items(board, player) {
+            if state.blindfold {
+                print!("  {}\r\n", crate::i18n::tr("(hidden)", "(目隠し中)"));
+            } else if items.is_empty() {
                 print!("  (None)\r\n");
             } else {
                 print!("  ");
-                for (i, (&kind, &count)) in items.iter().enumerate() {
+                for (i, &(kind, count)) in items.iter().enumerate() {
                     let is_selected_hand =
                         state.hand_mode && player == state.perspective && state.hand_index == i;
 
-                    let char_str = kind.display_char();
-                    let label = format!("[{} x{}]", char_str, count);
+                    let label = hand_item_label(kind, count, glyphs);
 
+                    let theme = Theme::get();
                     if is_selected_hand {
-                        print!("{} ", label.black().on_yellow());
-                    } else if player == PlayerId::Player1 {
-                        print!("{} ", label.cyan());
+                        print!("{} ", theme.active.apply(label));
                     } else {
-                        print!("{} ", label.magenta());
+                        print!("{} ", theme.player(player).apply(label));
                     }
                 }
                 print!("\r\n");
@@ -194,3 +669,144 @@ fn render_hands(board: &Board, state: &DisplayState) {
         print!("\r\n");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_target() {
+        let mut board = crate::core::setup::BoardSetupType::ShogiOnly.create_board();
+        for player in [PlayerId::Player1, PlayerId::Player2] {
+            board
+                .hand
+                .entry(player)
+                .or_default()
+                .insert(PieceKind::S_Pawn, 2);
+        }
+        let mut state = DisplayState::default();
+
+        // Top-left cell, both lines of it, and the one right of it
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Wide, 4, 5),
+            Some(ClickTarget::Square(Position::new(0, 0)))
+        );
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Wide, 7, 6),
+            Some(ClickTarget::Square(Position::new(0, 0)))
+        );
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Wide, 8, 5),
+            Some(ClickTarget::Square(Position::new(1, 0)))
+        );
+        assert_eq!(click_target_in(&board, &state, Layout::Wide, 2, 5), None);
+        // Flipped for Player2
+        state.perspective = PlayerId::Player2;
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Wide, 4, 5),
+            Some(ClickTarget::Square(Position::new(8, 8)))
+        );
+
+        // 9 ranks end on line 21 and the border is 22, then each hand takes
+        // a header, its pieces and a blank line
+        assert_eq!(click_target_in(&board, &state, Layout::Wide, 4, 22), None);
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Wide, 2, 24),
+            Some(ClickTarget::Hand(PlayerId::Player1, 0))
+        );
+        assert_eq!(click_target_in(&board, &state, Layout::Wide, 2, 25), None);
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Wide, 3, 27),
+            Some(ClickTarget::Hand(PlayerId::Player2, 0))
+        );
+        assert_eq!(click_target_in(&board, &state, Layout::Wide, 0, 27), None);
+    }
+
+    #[test]
+    fn test_layout_for_size() {
+        let board = crate::core::setup::BoardSetupType::ShogiOnly.create_board();
+        let state = DisplayState::default();
+        // 9 files of 4 columns after the rank label and border; 9 ranks of
+        // two lines (one compact) between the header and the hands
+        assert_eq!(needed_size(&board, &state, Layout::Wide), (41, 29));
+        assert_eq!(needed_size(&board, &state, Layout::Compact), (41, 21));
+        assert_eq!(layout_for(&board, &state, (80, 29)), Layout::Wide);
+        assert_eq!(layout_for(&board, &state, (80, 28)), Layout::Compact);
+        assert_eq!(layout_for(&board, &state, (80, 20)), Layout::TooSmall);
+        assert_eq!(layout_for(&board, &state, (40, 40)), Layout::TooSmall);
+
+        // One line per rank when compact
+        assert_eq!(
+            click_target_in(&board, &state, Layout::Compact, 4, 6),
+            Some(ClickTarget::Square(Position::new(0, 1)))
+        );
+        assert_eq!(
+            click_target_in(&board, &state, Layout::TooSmall, 4, 5),
+            None
+        );
+    }
+
+    #[test]
+    fn test_danger_squares() {
+        use crate::core::Piece;
+
+        let mut board = crate::core::setup::BoardSetupType::ShogiOnly.create_board();
+        board.pieces.clear();
+        let mut place =
+            |x, y, kind, owner| board.place_piece(Position::new(x, y), Piece::new(kind, owner));
+        place(4, 8, PieceKind::S_King, PlayerId::Player1);
+        place(4, 0, PieceKind::S_King, PlayerId::Player2);
+        place(4, 4, PieceKind::S_Rook, PlayerId::Player2);
+        place(0, 4, PieceKind::S_Gold, PlayerId::Player1);
+        place(8, 8, PieceKind::S_Pawn, PlayerId::Player1);
+
+        // The rook checks down the file and attacks the gold along the rank
+        let mut state = DisplayState::default();
+        assert_eq!(
+            danger_squares(&board, &state),
+            (vec![Position::new(4, 8)], Vec::new())
+        );
+        state.show_threats = true;
+        assert_eq!(danger_squares(&board, &state).1, vec![Position::new(0, 4)]);
+        state.perspective = PlayerId::Player2;
+        assert_eq!(danger_squares(&board, &state).1, Vec::new());
+
+        // Blindfold play gives nothing away
+        state.blindfold = true;
+        state.perspective = PlayerId::Player1;
+        assert_eq!(danger_squares(&board, &state), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_eval_bar_fill() {
+        assert_eq!(eval_bar_fill(0, PlayerId::Player1, 17), 9);
+        // Three quarters at the scale, from either side
+        assert_eq!(eval_bar_fill(600, PlayerId::Player1, 16), 12);
+        assert_eq!(eval_bar_fill(600, PlayerId::Player2, 16), 4);
+        assert_eq!(eval_bar_fill(100_000, PlayerId::Player1, 17), 17);
+        assert_eq!(eval_bar_fill(-100_000, PlayerId::Player1, 17), 0);
+    }
+
+    #[test]
+    fn test_move_list_scroll() {
+        let board = crate::core::setup::BoardSetupType::ShogiOnly.create_board();
+        let mut state = DisplayState {
+            move_list: (1..=20).map(|i| format!("m{}", i)).collect(),
+            ..Default::default()
+        };
+
+        // 17 lines beside a 9x9 board, the latest move last
+        let lines = visible_moves(&board, &state, Layout::Wide);
+        assert_eq!(lines.len(), 17);
+        assert_eq!(lines[0], "  4 m4");
+        assert_eq!(lines[16], " 20 m20");
+
+        scroll_move_list_in(&board, &mut state, Layout::Wide, 10);
+        assert_eq!(state.move_list_scroll, 3);
+        assert_eq!(visible_moves(&board, &state, Layout::Wide)[0], "  1 m1");
+        scroll_move_list_in(&board, &mut state, Layout::Wide, -1);
+        assert_eq!(state.move_list_scroll, 2);
+        scroll_move_list_in(&board, &mut state, Layout::Wide, -5);
+        assert_eq!(state.move_list_scroll, 0);
+    }
+}
@@ -0,0 +1,403 @@
+//! 盤面エディタ
+//!
+//! 駒を置く・取り除く、持ち駒と手番を決めるなどして作った局面から、そのまま
+//! 対局や検討を始めたり、盤面ファイル (`server --board` で読める設定文字列) と
+//! 棋譜の初期局面として書き出したりする。
+
+use crate::core::setup::{
+    parse_hand_token, parse_piece_token, setup_to_strings, to_sfen, HAND_PREFIX,
+};
+use crate::core::{Board, PieceKind, PlayerId, Position};
+use crate::game::Game;
+use crate::i18n::{player_name, tr};
+use crate::player::ai::alpha_beta::{AIStrength, AlphaBetaAI};
+use crate::player::{EngineView, PlayerController};
+use crate::ui::display::{click_target, render_board, ClickTarget, DisplayState};
+use crossterm::event::{self, Event, KeyCode, MouseButton, MouseEventKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 書き出した盤面ファイルの置き場所
+pub const POSITION_DIR: &str = "positions";
+
+/// 検討の探索の上限
+const ANALYSIS_DEPTH: usize = 8;
+const ANALYSIS_TIME: Duration = Duration::from_secs(5);
+
+/// Position finished in the editor, to be played
+pub struct EditedPosition {
+    pub board: Board,
+    /// Setup the position was edited from
+    pub setup_name: String,
+    pub side_to_move: PlayerId,
+}
+
+pub struct BoardEditor {
+    board: Board,
+    /// The setup as chosen, restored with `r`
+    original: Board,
+    setup_name: String,
+    side_to_move: PlayerId,
+    cursor: Position,
+    /// Piece or hand being typed after `:`
+    typed: Option<String>,
+    message: Option<String>,
+    /// The last analysis, until the position changes
+    analysis: Option<EngineView>,
+}
+
+/// Why `board` cannot be played with `side_to_move` to move, if it cannot
+pub fn position_problem(board: &Board, side_to_move: PlayerId) -> Option<String> {
+    for player in [PlayerId::Player1, PlayerId::Player2] {
+        if board.find_king(player).is_none() {
+            return Some(crate::trf!(
+                "{} has no king",
+                "{}の玉がありません",
+                player_name(player)
+            ));
+        }
+    }
+    let waiting = side_to_move.opponent();
+    if crate::logic::is_in_check(board, waiting) {
+        return Some(crate::trf!(
+            "{} is in check but not to move",
+            "手番でない{}に王手がかかっています",
+            player_name(waiting)
+        ));
+    }
+    None
+}
+
+impl BoardEditor {
+    pub fn new(board: Board, setup_name: String) -> Self {
+        BoardEditor {
+            original: board.clone(),
+            board,
+            setup_name,
+            side_to_move: PlayerId::Player1,
+            cursor: Position::default(),
+            typed: None,
+            message: None,
+            analysis: None,
+        }
+    }
+
+    /// Applies what was typed after `:`: a piece in setup notation placed at
+    /// the cursor (`P`, `+r`, `cQ`, `@x`), `.` to empty the square, or a
+    /// `hand` row that replaces both hands (`hand 2P g cn`)
+    pub fn apply(&mut self, text: &str) -> Result<(), String> {
+        let mut words = text.split_whitespace();
+        match words.next() {
+            None => {}
+            Some(HAND_PREFIX) => {
+                let mut hand = Vec::new();
+                for word in words {
+                    let (piece, count) = parse_hand_token(word, true, true)
+                        .ok_or_else(|| crate::trf!("Unknown piece: {}", "不明な駒: {}", word))?;
+                    if matches!(piece.kind, PieceKind::S_King | PieceKind::C_King) {
+                        return Err(
+                            tr("A king cannot be in hand", "玉は持ち駒にできません").to_string()
+                        );
+                    }
+                    hand.push((piece, count));
+                }
+                for pieces in self.board.hand.values_mut() {
+                    pieces.clear();
+                }
+                for (piece, count) in hand {
+                    for _ in 0..count {
+                        self.board.add_to_hand(piece.owner, piece.unpromoted_kind());
+                    }
+                }
+            }
+            Some(".") => {
+                self.board.remove_piece(self.cursor);
+            }
+            Some(token) => {
+                let piece = parse_piece_token(token)
+                    .ok_or_else(|| crate::trf!("Unknown piece: {}", "不明な駒: {}", token))?;
+                self.board.place_piece(self.cursor, piece);
+            }
+        }
+        self.analysis = None;
+        Ok(())
+    }
+
+    /// The edited board, hashed for the side to move
+    fn position(&self) -> Board {
+        let mut board = self.board.clone();
+        board.last_move = None;
+        board.zobrist_hash = crate::logic::ZobristHasher::compute_hash(&board, self.side_to_move);
+        board.history = vec![board.zobrist_hash];
+        board
+    }
+
+    fn analyze(&mut self) {
+        let board = self.position();
+        let engine = AlphaBetaAI::new(
+            self.side_to_move,
+            "Analysis",
+            AIStrength::Strong,
+            None,
+            true,
+        );
+        engine.search_with_limits(&board, ANALYSIS_DEPTH, ANALYSIS_TIME);
+        self.analysis = engine
+            .last_search(&board)
+            .map(|search| EngineView::new("Analysis", &search, &board, self.side_to_move));
+        if self.analysis.is_none() {
+            self.message = Some(crate::trf!(
+                "{} has no legal move",
+                "{}に指せる手がありません",
+                player_name(self.side_to_move)
+            ));
+        }
+    }
+
+    /// Writes the position as a setup file (see [`POSITION_DIR`]) and as a
+    /// kifu with no moves that starts from it. The SFEN, if the position has
+    /// one, goes in the setup file's comments too.
+    fn export(&self) -> anyhow::Result<(PathBuf, PathBuf, Option<String>)> {
+        let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let sfen = to_sfen(&self.board, self.side_to_move);
+
+        std::fs::create_dir_all(POSITION_DIR)?;
+        let position_path =
+            Path::new(POSITION_DIR).join(format!("{}_{}.txt", self.setup_name, stamp));
+        // `#` 行は盤面ファイルを読むときに無視される
+        let mut text = format!(
+            "# setup: {}\n# side to move: {:?}\n",
+            self.setup_name, self.side_to_move
+        );
+        if let Some(sfen) = &sfen {
+            text.push_str(&format!("# sfen: {}\n", sfen));
+        }
+        for row in setup_to_strings(&self.board) {
+            text.push_str(&row);
+            text.push('\n');
+        }
+        crate::util::atomic_write(&position_path, text)?;
+
+        std::fs::create_dir_all("kifu")?;
+        let kifu_path = Path::new("kifu").join(format!("edited_{}.json", stamp));
+        let game = Game::from_position(
+            self.board.clone(),
+            self.setup_name.clone(),
+            self.side_to_move,
+        );
+        crate::util::atomic_write_json(&kifu_path, &game.kifu(), false)?;
+        Ok((position_path, kifu_path, sfen))
+    }
+
+    /// What the board is drawn with, also to hit-test clicks against
+    fn display_state(&self) -> DisplayState {
+        DisplayState {
+            cursor: self.cursor,
+            perspective: PlayerId::Player1,
+            engine: self.analysis.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn render(&self) {
+        let mut status = crate::trf!(
+            "Board editor ({}) | {} to move",
+            "盤面エディタ ({}) | {}の手番",
+            self.setup_name,
+            player_name(self.side_to_move)
+        );
+        if let Some(text) = &self.typed {
+            status = format!("{} | {}: {}_", status, tr("Piece", "駒"), text);
+        } else if let Some(message) = &self.message {
+            status = format!("{} | {}", status, message);
+        }
+        let state = DisplayState {
+            status_msg: Some(status),
+            ..self.display_state()
+        };
+        render_board(&self.board, &state);
+        if self.typed.is_some() {
+            print!(
+                "{}\r\n",
+                tr(
+                    "[Enter]: Apply | [Esc]: Cancel | e.g. P, +r, cQ (at the cursor), . (empty), hand 2P g cn",
+                    "[Enter]: 決定 | [Esc]: やめる | 例: P, +r, cQ (カーソルの升), . (空ける), hand 2P g cn"
+                )
+            );
+        } else {
+            print!(
+                "{}\r\n",
+                tr(
+                    "[Arrows/Click]: Cursor | [:]: Piece or hand | [x/Del]: Remove | [t]: Side to move | [c]: Clear | [r]: Reset",
+                    "[矢印/クリック]: カーソル | [:]: 駒・持ち駒 | [x/Del]: 取り除く | [t]: 手番 | [c]: 全消去 | [r]: 元に戻す"
+                )
+            );
+            print!(
+                "{}\r\n",
+                tr(
+                    "[a]: Analyze | [w]: Export | [Enter]: Play | [q]: Quit",
+                    "[a]: 検討 | [w]: 書き出し | [Enter]: 対局 | [q]: 終了"
+                )
+            );
+        }
+    }
+
+    /// Edits until the position is played (`Some`) or the editor is left
+    pub fn run(mut self) -> anyhow::Result<Option<EditedPosition>> {
+        let _mouse = crate::ui::MouseCapture::enable();
+        loop {
+            self.render();
+
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let code = match event::read()? {
+                Event::Key(key) => key.code,
+                Event::Mouse(mouse) if self.typed.is_none() => {
+                    if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+                        let state = self.display_state();
+                        if let Some(ClickTarget::Square(pos)) =
+                            click_target(&self.board, &state, mouse.column, mouse.row)
+                        {
+                            self.cursor = pos;
+                        }
+                    }
+                    continue;
+                }
+                // 次の描画で端末の大きさに合う配置を選び直す
+                Event::Resize(..) => continue,
+                _ => continue,
+            };
+
+            if let Some(text) = self.typed.as_mut() {
+                // 駒・持ち駒の文字入力
+                match code {
+                    KeyCode::Char(c) => text.push(c),
+                    KeyCode::Backspace => {
+                        text.pop();
+                    }
+                    KeyCode::Esc => self.typed = None,
+                    KeyCode::Enter => {
+                        let text = self.typed.take().unwrap_or_default();
+                        if let Err(e) = self.apply(&text) {
+                            self.message = Some(e);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            self.message = None;
+            match code {
+                KeyCode::Up => self.cursor.y = self.cursor.y.saturating_sub(1),
+                KeyCode::Down if self.cursor.y + 1 < self.board.height => self.cursor.y += 1,
+                KeyCode::Left => self.cursor.x = self.cursor.x.saturating_sub(1),
+                KeyCode::Right if self.cursor.x + 1 < self.board.width => self.cursor.x += 1,
+                KeyCode::Char(':') | KeyCode::Char('/') => self.typed = Some(String::new()),
+                KeyCode::Char('x') | KeyCode::Delete | KeyCode::Backspace => {
+                    self.board.remove_piece(self.cursor);
+                    self.analysis = None;
+                }
+                KeyCode::Char('t') => {
+                    self.side_to_move = self.side_to_move.opponent();
+                    self.analysis = None;
+                }
+                KeyCode::Char('c') => {
+                    self.board.pieces.clear();
+                    for pieces in self.board.hand.values_mut() {
+                        pieces.clear();
+                    }
+                    self.analysis = None;
+                }
+                KeyCode::Char('r') => {
+                    self.board = self.original.clone();
+                    self.analysis = None;
+                }
+                KeyCode::Char('a') => match position_problem(&self.board, self.side_to_move) {
+                    Some(problem) => self.message = Some(problem),
+                    None => {
+                        self.message = Some(tr("Analyzing...", "検討中...").to_string());
+                        self.render();
+                        self.message = None;
+                        self.analyze();
+                    }
+                },
+                KeyCode::Char('w') => {
+                    self.message = Some(match self.export() {
+                        Ok((position, kifu, sfen)) => {
+                            let saved = crate::trf!(
+                                "Saved {} and {}",
+                                "{} と {} に保存しました",
+                                position.display(),
+                                kifu.display()
+                            );
+                            let sfen = match sfen {
+                                Some(sfen) => format!("SFEN: {}", sfen),
+                                None => tr(
+                                    "No SFEN: only 9x9 boards of shogi pieces have one",
+                                    "SFEN なし: 将棋駒だけの 9x9 の局面しか書けません",
+                                )
+                                .to_string(),
+                            };
+                            format!("{} | {}", saved, sfen)
+                        }
+                        Err(e) => crate::trf!("Export failed: {}", "書き出しに失敗: {}", e),
+                    });
+                }
+                KeyCode::Enter => match position_problem(&self.board, self.side_to_move) {
+                    Some(problem) => self.message = Some(problem),
+                    None => {
+                        return Ok(Some(EditedPosition {
+                            board: self.position(),
+                            setup_name: self.setup_name,
+                            side_to_move: self.side_to_move,
+                        }))
+                    }
+                },
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::setup::BoardSetupType;
+
+    #[test]
+    fn test_edit_position() {
+        let mut editor = BoardEditor::new(
+            BoardSetupType::ShogiOnly.create_board(),
+            "ShogiOnly".to_string(),
+        );
+        editor.cursor = Position::new(4, 4);
+        editor.apply("+r").unwrap();
+        let piece = editor.board.get_piece(Position::new(4, 4)).unwrap();
+        assert_eq!(
+            (piece.kind, piece.owner),
+            (PieceKind::S_ProRook, PlayerId::Player2)
+        );
+        editor.apply("cQ").unwrap();
+        assert_eq!(
+            editor.board.get_piece(Position::new(4, 4)).unwrap().kind,
+            PieceKind::C_Queen
+        );
+        editor.apply(".").unwrap();
+        assert!(editor.board.get_piece(Position::new(4, 4)).is_none());
+
+        editor.apply("hand 2P g").unwrap();
+        assert_eq!(editor.board.hand[&PlayerId::Player1][&PieceKind::S_Pawn], 2);
+        assert_eq!(editor.board.hand[&PlayerId::Player2][&PieceKind::S_Gold], 1);
+        assert!(editor.apply("hand K").is_err());
+        assert!(editor.apply("zz").is_err());
+        assert_eq!(editor.board.hand[&PlayerId::Player1][&PieceKind::S_Pawn], 2);
+
+        assert_eq!(position_problem(&editor.board, PlayerId::Player1), None);
+        let king = editor.board.find_king(PlayerId::Player2).unwrap();
+        editor.board.remove_piece(king);
+        assert!(position_problem(&editor.board, PlayerId::Player1).is_some());
+    }
+}
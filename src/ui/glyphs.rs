@@ -0,0 +1,226 @@
+//! Piece glyph sets for the board display
+//!
+//! Every board cell is two terminal columns wide. Kanji fill that on terminals
+//! that render CJK as double-width; elsewhere the grid falls apart, so we
+//! switch to two-letter romaji or single ASCII letters instead.
+//!
+//! The style comes from the `AHO_GLYPHS` environment variable or the `glyphs`
+//! key of the theme file (see [`crate::ui::theme`]), either of them `cjk`,
+//! `romaji`, `ascii` or `auto`, and is otherwise detected on the first board
+//! render by printing a kanji and measuring how far the cursor moved.
+
+use crate::core::PieceKind;
+use crossterm::{cursor, execute, terminal};
+use once_cell::sync::OnceCell;
+use std::io::{stdout, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphStyle {
+    /// 歩, 飛, ... (needs double-width CJK rendering)
+    Cjk,
+    /// FU, HI, ... (needs only ASCII)
+    Romaji,
+    /// P, R, +R, ... (single letters, promoted with `+`, chess pieces with
+    /// `c` as in setup strings)
+    Ascii,
+}
+
+impl GlyphStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "cjk" => Some(GlyphStyle::Cjk),
+            "romaji" => Some(GlyphStyle::Romaji),
+            "ascii" => Some(GlyphStyle::Ascii),
+            _ => None,
+        }
+    }
+}
+
+static STYLE: OnceCell<GlyphStyle> = OnceCell::new();
+
+/// The glyph style in use, detecting it on first call
+pub fn glyph_style() -> GlyphStyle {
+    *STYLE.get_or_init(detect_glyph_style)
+}
+
+fn detect_glyph_style() -> GlyphStyle {
+    if let Some(style) = std::env::var("AHO_GLYPHS")
+        .ok()
+        .and_then(|v| GlyphStyle::from_name(&v))
+        .or(crate::ui::theme::Theme::get().glyphs)
+    {
+        return style;
+    }
+
+    if !locale_is_utf8() {
+        return GlyphStyle::Ascii;
+    }
+
+    match probe_cjk_width() {
+        Some(1) => GlyphStyle::Romaji,
+        // Width 2, or the terminal did not answer: keep the original layout
+        _ => GlyphStyle::Cjk,
+    }
+}
+
+/// Windows consoles do not set a locale in the environment; assume UTF-8 there
+fn locale_is_utf8() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|v| !v.is_empty());
+    match locale {
+        Some(v) => {
+            let v = v.to_ascii_lowercase();
+            v.contains("utf-8") || v.contains("utf8")
+        }
+        // Nothing set: most modern terminals are UTF-8 anyway
+        None => true,
+    }
+}
+
+/// Columns the cursor advances when printing a kanji (needs raw mode for the
+/// cursor position report). The probe is erased afterwards.
+fn probe_cjk_width() -> Option<u16> {
+    let was_raw = terminal::is_raw_mode_enabled().unwrap_or(false);
+    if !was_raw {
+        terminal::enable_raw_mode().ok()?;
+    }
+
+    let measure = || -> Option<u16> {
+        let mut out = stdout();
+        let (start, row) = cursor::position().ok()?;
+        print!("歩");
+        out.flush().ok()?;
+        let (end, _) = cursor::position().ok()?;
+        execute!(
+            out,
+            cursor::MoveTo(start, row),
+            terminal::Clear(terminal::ClearType::UntilNewLine)
+        )
+        .ok()?;
+        end.checked_sub(start)
+    };
+    let width = measure();
+
+    if !was_raw {
+        terminal::disable_raw_mode().ok();
+    }
+    width
+}
+
+/// Two-column label for a piece on the board
+pub fn piece_label(kind: PieceKind, style: GlyphStyle) -> String {
+    if let PieceKind::Custom(i) = kind {
+        return custom_label(i, style);
+    }
+    if !kind.is_shogi() {
+        // Chess pieces are ASCII letters in every style, told apart from
+        // shogi letters by the `c` of setup strings when those are letters too
+        let prefix = if style == GlyphStyle::Ascii { 'c' } else { ' ' };
+        return format!("{}{}", prefix, kind.display_char());
+    }
+    match style {
+        GlyphStyle::Cjk => kind.display_char().to_string(),
+        GlyphStyle::Romaji => romaji(kind).to_string(),
+        GlyphStyle::Ascii => ascii(kind).to_string(),
+    }
+}
+
+/// Label for a piece in hand (no padding)
+pub fn hand_label(kind: PieceKind, style: GlyphStyle) -> String {
+    piece_label(kind, style).trim_start().to_string()
+}
+
+/// pieces.toml pieces: their display char, or the symbol (`+` when promoted)
+fn custom_label(index: u8, style: GlyphStyle) -> String {
+    let Some(def) = crate::core::fairy::def(index) else {
+        return " ?".to_string();
+    };
+    match style {
+        GlyphStyle::Cjk if !def.display_char.is_ascii() => def.display_char.to_string(),
+        GlyphStyle::Cjk => format!(" {}", def.display_char),
+        GlyphStyle::Romaji | GlyphStyle::Ascii => {
+            let promoted = crate::core::fairy::unpromoted(index).is_some();
+            format!("{}{}", if promoted { '+' } else { ' ' }, def.symbol)
+        }
+    }
+}
+
+fn romaji(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::S_King => "OU",
+        PieceKind::S_Rook => "HI",
+        PieceKind::S_Bishop => "KA",
+        PieceKind::S_Gold => "KI",
+        PieceKind::S_Silver => "GI",
+        PieceKind::S_Knight => "KE",
+        PieceKind::S_Lance => "KY",
+        PieceKind::S_Pawn => "FU",
+        PieceKind::S_ProRook => "RY",
+        PieceKind::S_ProBishop => "UM",
+        PieceKind::S_ProSilver => "NG",
+        PieceKind::S_ProKnight => "NK",
+        PieceKind::S_ProLance => "NY",
+        PieceKind::S_ProPawn => "TO",
+        _ => "??",
+    }
+}
+
+fn ascii(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::S_King => " K",
+        PieceKind::S_Rook => " R",
+        PieceKind::S_Bishop => " B",
+        PieceKind::S_Gold => " G",
+        PieceKind::S_Silver => " S",
+        PieceKind::S_Knight => " N",
+        PieceKind::S_Lance => " L",
+        PieceKind::S_Pawn => " P",
+        PieceKind::S_ProRook => "+R",
+        PieceKind::S_ProBishop => "+B",
+        PieceKind::S_ProSilver => "+S",
+        PieceKind::S_ProKnight => "+N",
+        PieceKind::S_ProLance => "+L",
+        PieceKind::S_ProPawn => "+P",
+        _ => " ?",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_fill_two_columns() {
+        let kinds = [
+            PieceKind::S_King,
+            PieceKind::S_ProPawn,
+            PieceKind::S_ProLance,
+            PieceKind::C_Queen,
+            PieceKind::C_Pawn,
+        ];
+        for kind in kinds {
+            for style in [GlyphStyle::Romaji, GlyphStyle::Ascii] {
+                let label = piece_label(kind, style);
+                assert_eq!(label.len(), 2, "{:?} {:?} -> {:?}", kind, style, label);
+                assert!(label.is_ascii());
+            }
+        }
+        // One double-width kanji
+        assert_eq!(
+            piece_label(PieceKind::S_Pawn, GlyphStyle::Cjk)
+                .chars()
+                .count(),
+            1
+        );
+        assert_eq!(piece_label(PieceKind::C_Pawn, GlyphStyle::Cjk), " P");
+        assert_eq!(hand_label(PieceKind::S_Rook, GlyphStyle::Ascii), "R");
+        assert_eq!(piece_label(PieceKind::S_ProPawn, GlyphStyle::Ascii), "+P");
+        assert_eq!(piece_label(PieceKind::C_Pawn, GlyphStyle::Ascii), "cP");
+        assert_eq!(GlyphStyle::from_name("ROMAJI"), Some(GlyphStyle::Romaji));
+    }
+}
@@ -1,11 +1,37 @@
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode};
 use crossterm::{execute, terminal};
+use rayon::prelude::*;
 use serde::Deserialize;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Width of the loading progress bar, in characters
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+/// Widest text inside the selector box, in terminal columns
+const MAX_LIST_WIDTH: usize = 116;
+
+/// Most file rows shown at once
+const MAX_VISIBLE_ROWS: usize = 20;
+
+/// Lines of the box besides the file rows
+const FRAME_ROWS: usize = 9;
+
+/// Text width and file rows of a box that fits the terminal. The borders and
+/// padding take 5 columns, and the last column stays free so no line wraps.
+fn list_size() -> (usize, usize) {
+    let (cols, rows) = terminal::size().map_or((80, 24), |(w, h)| (w as usize, h as usize));
+    let width = cols.saturating_sub(6).min(MAX_LIST_WIDTH);
+    let visible_rows = rows
+        .saturating_sub(FRAME_ROWS + 1)
+        .clamp(1, MAX_VISIBLE_ROWS);
+    (width, visible_rows)
+}
 
 /// Lightweight kifu metadata for file selection
 #[derive(Debug, Clone)]
@@ -17,6 +43,10 @@ pub struct KifuFileInfo {
     pub player2: String,
     pub move_count: usize,
     pub timestamp: String,
+    /// `None` for kifus saved before results were recorded, or unfinished games
+    pub result: Option<crate::game::KifuResult>,
+    /// Evaluator and model version, empty if the kifu does not say
+    pub evaluator: String,
 }
 
 /// Minimal structure for fast metadata parsing
@@ -26,6 +56,12 @@ struct KifuMetadata {
     player1_name: String,
     player2_name: String,
     moves: Vec<serde_json::Value>, // Don't parse full moves, just count
+    #[serde(default)]
+    result: Option<crate::game::KifuResult>,
+    #[serde(default)]
+    evaluator: String,
+    #[serde(default)]
+    model_version: Option<String>,
 }
 
 pub struct KifuSelector {
@@ -38,16 +74,8 @@ pub struct KifuSelector {
 impl KifuSelector {
     /// Scan directories for kifu files (recursively)
     pub fn scan_directories(dirs: &[PathBuf]) -> Result<Self> {
-        let mut files = Vec::new();
-
-        for dir in dirs {
-            if !dir.exists() {
-                continue;
-            }
-
-            // Recursively scan for .json files
-            Self::scan_directory_recursive(dir, &mut files)?;
-        }
+        let paths = kifu_paths(dirs)?;
+        let mut files = load_metadata(&paths);
 
         // Sort by timestamp (newest first)
         files.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
@@ -56,102 +84,21 @@ impl KifuSelector {
             files,
             selected_index: 0,
             scroll_offset: 0,
-            visible_rows: 20,  // Increased from 10 to 20
+            visible_rows: MAX_VISIBLE_ROWS,
         })
     }
 
-    /// Try to extract timestamp from filename (e.g., "selfplay_results_20260107_214954.json")
-    fn extract_timestamp_from_filename(filename: &str) -> Option<String> {
-        if !filename.contains("_") {
-            return None;
-        }
-
-        let parts: Vec<&str> = filename.split('_').collect();
-        if parts.len() < 3 {
-            return None;
-        }
-
-        let date_str = parts[parts.len() - 2];
-        let time_str = parts[parts.len() - 1].trim_end_matches(".json");
-
-        // Format: "20260107" -> "2026-01-07", "214954" -> "21:49:54"
-        if date_str.len() == 8 && time_str.len() == 6 {
-            Some(format!(
-                "{}-{}-{} {}:{}:{}",
-                &date_str[0..4],
-                &date_str[4..6],
-                &date_str[6..8],
-                &time_str[0..2],
-                &time_str[2..4],
-                &time_str[4..6]
-            ))
-        } else if date_str.len() == 8 {
-            // Date only
-            Some(format!(
-                "{}-{}-{}",
-                &date_str[0..4],
-                &date_str[4..6],
-                &date_str[6..8]
-            ))
-        } else {
-            Some(format!("{} {}", date_str, time_str))
-        }
-    }
-
-    /// Try to extract timestamp from parent directory path (e.g., "selfplay_kifu/ShogiOnly/20260107_214954/")
-    fn extract_timestamp_from_path(path: &Path) -> Option<String> {
-        // Check parent directory for timestamp pattern
-        if let Some(parent) = path.parent() {
-            if let Some(dir_name) = parent.file_name().and_then(|n| n.to_str()) {
-                // Try to extract from directory name
-                if let Some(ts) = Self::extract_timestamp_from_filename(dir_name) {
-                    return Some(ts);
-                }
-
-                // Check if it's just a date (YYYYMMDD)
-                if dir_name.len() == 8 && dir_name.chars().all(|c| c.is_ascii_digit()) {
-                    return Some(format!(
-                        "{}-{}-{}",
-                        &dir_name[0..4],
-                        &dir_name[4..6],
-                        &dir_name[6..8]
-                    ));
-                }
-
-                // Check if it contains underscores (YYYYMMDD_HHMMSS)
-                if dir_name.contains('_') {
-                    let parts: Vec<&str> = dir_name.split('_').collect();
-                    for part in parts {
-                        if part.len() == 8 && part.chars().all(|c| c.is_ascii_digit()) {
-                            // Found date part
-                            return Some(format!(
-                                "{}-{}-{}",
-                                &part[0..4],
-                                &part[4..6],
-                                &part[6..8]
-                            ));
-                        }
-                    }
-                }
-            }
-        }
-        None
-    }
-
     /// Recursively scan a directory for JSON kifu files
-    fn scan_directory_recursive(dir: &Path, files: &mut Vec<KifuFileInfo>) -> Result<()> {
+    fn scan_directory_recursive(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
         for entry in fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
 
             if path.is_dir() {
                 // Recursively scan subdirectories
-                Self::scan_directory_recursive(&path, files)?;
+                Self::scan_directory_recursive(&path, paths)?;
             } else if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                // Extract metadata from JSON files
-                if let Ok(info) = Self::extract_metadata(&path) {
-                    files.push(info);
-                }
+                paths.push(path);
             }
         }
         Ok(())
@@ -159,8 +106,9 @@ impl KifuSelector {
 
     /// Extract metadata without loading full moves
     fn extract_metadata(path: &Path) -> Result<KifuFileInfo> {
-        let content = fs::read_to_string(path)?;
-        let metadata: KifuMetadata = serde_json::from_str(&content)?;
+        let Some(metadata) = crate::util::read_json_or_skip::<KifuMetadata>(path)? else {
+            anyhow::bail!("{} is corrupt", path.display());
+        };
 
         // Extract timestamp from filename (e.g., "selfplay_results_20260107_214954.json")
         let filename = path
@@ -205,31 +153,49 @@ impl KifuSelector {
             player2: metadata.player2_name,
             move_count: metadata.moves.len(),
             timestamp,
+            result: metadata.result,
+            evaluator: match metadata.model_version {
+                Some(version) => format!("{} {}", metadata.evaluator, version),
+                None => metadata.evaluator,
+            },
         })
     }
 
-    /// Render the file selection UI
-    pub fn render(&self) -> Result<()> {
+    /// Render the file selection UI, `width` columns of text wide
+    pub fn render(&self, width: usize) -> Result<()> {
         execute!(
             io::stdout(),
             terminal::Clear(terminal::ClearType::All),
             crossterm::cursor::MoveTo(0, 0)
         )?;
 
-        println!("╔══════════════════════════════════════════════════════════════════════════════════════════╗\r");
-        println!("║                              Kifu Replay - Select File                                     ║\r");
-        println!("╠══════════════════════════════════════════════════════════════════════════════════════════╣\r");
-        println!("║                                                                                            ║\r");
+        let rule = "═".repeat(width + 3);
+        let row = |text: &str| println!("║  {} ║\r", pad(text, width));
+
+        let title = "Kifu Replay - Select File";
+        let indent = width.saturating_sub(title.width()) / 2;
+        println!("╔{}╗\r", rule);
+        row(&format!("{}{}", " ".repeat(indent), title));
+        println!("╠{}╣\r", rule);
+        row("");
 
         if self.files.is_empty() {
-            println!("║  No kifu files found in:                                                               ║\r");
-            println!("║    - kifu/                                                                             ║\r");
-            println!("║    - selfplay_results/                                                                 ║\r");
-            println!("║    - selfplay_kifu/                                                                    ║\r");
-            println!("║                                                                                            ║\r");
-            println!("║  Generate some games first!                                                            ║\r");
-            println!("║                                                                                            ║\r");
+            row("No kifu files found in:");
+            row("  - kifu/");
+            row("  - selfplay_results/");
+            row("  - selfplay_kifu/");
+            row("");
+            row("Generate some games first!");
+            row("");
         } else {
+            row(&format!(
+                "  {} | {} | {} | {} | Evaluator",
+                pad("Date", 19),
+                pad("Setup", 12),
+                pad("Players (moves)", 30),
+                pad("Result", 22)
+            ));
+
             // Calculate visible range
             let start = self.scroll_offset;
             let end = (start + self.visible_rows).min(self.files.len());
@@ -238,41 +204,50 @@ impl KifuSelector {
                 let file = &self.files[i];
                 let cursor = if i == self.selected_index { "▶" } else { " " };
 
-                // Format: "▶ 2026-01-07 21:49:54 | ... "
+                // Format: "▶ 2026-01-07 21:49:54 | Fair | A vs B (63) | P1 (Checkmate) | NNUE v3"
+                let players = format!(
+                    "{} vs {} ({})",
+                    truncate(&file.player1, 10),
+                    truncate(&file.player2, 10),
+                    file.move_count
+                );
+                let evaluator = if file.evaluator.is_empty() {
+                    "-"
+                } else {
+                    &file.evaluator
+                };
                 let display = format!(
-                    "{} {} | {} | {} vs {} ({} moves)",
+                    "{} {} | {} | {} | {} | {}",
                     cursor,
-                    file.timestamp,
-                    truncate(&file.board_setup, 15),
-                    truncate(&file.player1, 12),
-                    truncate(&file.player2, 12),
-                    file.move_count
+                    pad(&file.timestamp, 19),
+                    pad(&file.board_setup, 12),
+                    pad(&players, 30),
+                    pad(&result_label(file.result.as_ref()), 22),
+                    evaluator
                 );
 
-                println!("║  {:<90} ║\r", truncate(&display, 90));
+                row(&display);
             }
 
             // Fill remaining visible rows
             for _ in (end - start)..self.visible_rows {
-                println!(
-                    "║                                                                                            ║\r"
-                );
+                row("");
             }
         }
 
-        println!("║                                                                                            ║\r");
-        println!("╠══════════════════════════════════════════════════════════════════════════════════════════╣\r");
+        row("");
+        println!("╠{}╣\r", rule);
 
         if self.files.is_empty() {
-            println!("║  [q] Back to Main Menu                                                                     ║\r");
+            row("[q] Back to Main Menu");
         } else {
-            println!(
-                "║  {} files | [↑/↓] Navigate | [Enter] Select | [q] Back                                  ║\r",
+            row(&format!(
+                "{} files | [↑/↓] Navigate | [Enter] Select | [q] Back",
                 self.files.len()
-            );
+            ));
         }
 
-        println!("╚══════════════════════════════════════════════════════════════════════════════════════════╝\r");
+        println!("╚{}╝\r", rule);
 
         Ok(())
     }
@@ -283,22 +258,18 @@ impl KifuSelector {
             if let Event::Key(key) = event::read()? {
                 match key.code {
                     KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
-                    KeyCode::Enter => {
-                        if !self.files.is_empty() {
-                            return Ok(Some(self.files[self.selected_index].path.clone()));
-                        }
+                    KeyCode::Enter if !self.files.is_empty() => {
+                        return Ok(Some(self.files[self.selected_index].path.clone()));
                     }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if self.selected_index > 0 {
-                            self.selected_index -= 1;
-                            self.update_scroll();
-                        }
+                    KeyCode::Up | KeyCode::Char('k') if self.selected_index > 0 => {
+                        self.selected_index -= 1;
+                        self.update_scroll();
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if self.selected_index < self.files.len().saturating_sub(1) {
-                            self.selected_index += 1;
-                            self.update_scroll();
-                        }
+                    KeyCode::Down | KeyCode::Char('j')
+                        if self.selected_index < self.files.len().saturating_sub(1) =>
+                    {
+                        self.selected_index += 1;
+                        self.update_scroll();
                     }
                     _ => {}
                 }
@@ -321,7 +292,11 @@ impl KifuSelector {
     /// Run the selector loop
     pub fn run(&mut self) -> Result<Option<PathBuf>> {
         loop {
-            self.render()?;
+            // Measured every time, so resizing the window redraws to fit
+            let (width, visible_rows) = list_size();
+            self.visible_rows = visible_rows;
+            self.update_scroll();
+            self.render(width)?;
 
             match self.handle_input() {
                 Ok(Some(path)) => {
@@ -348,14 +323,166 @@ impl KifuSelector {
     }
 }
 
+/// Extract the metadata of many kifu files concurrently on the rayon pool,
+/// skipping files that are not kifu (run manifests, reports, ...). Results
+/// keep the order of `paths`. While parsing takes longer than a moment, a
+/// progress bar is drawn on the current line.
+pub fn load_metadata(paths: &[PathBuf]) -> Vec<KifuFileInfo> {
+    let done = AtomicUsize::new(0);
+    let finished = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut drawn = false;
+            loop {
+                std::thread::sleep(Duration::from_millis(100));
+                if finished.load(Ordering::Relaxed) {
+                    break;
+                }
+                draw_progress(done.load(Ordering::Relaxed), paths.len());
+                drawn = true;
+            }
+            if drawn {
+                draw_progress(paths.len(), paths.len());
+                print!("\r\n");
+                io::stdout().flush().ok();
+            }
+        });
+
+        let files = paths
+            .par_iter()
+            .filter_map(|path| {
+                let info = KifuSelector::extract_metadata(path).ok();
+                done.fetch_add(1, Ordering::Relaxed);
+                info
+            })
+            .collect();
+        finished.store(true, Ordering::Relaxed);
+        files
+    })
+}
+
+fn draw_progress(done: usize, total: usize) {
+    let filled = (done * PROGRESS_BAR_WIDTH).checked_div(total).unwrap_or(0);
+    print!(
+        "\rLoading kifu [{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_BAR_WIDTH - filled),
+        done,
+        total
+    );
+    io::stdout().flush().ok();
+}
+
+/// JSON files under `dirs` (recursively); directories that do not exist are
+/// skipped. Not every file is necessarily a kifu.
+pub fn kifu_paths(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for dir in dirs {
+        if dir.exists() {
+            KifuSelector::scan_directory_recursive(dir, &mut paths)?;
+        }
+    }
+    Ok(paths)
+}
+
+/// Winner and ending, e.g. `P1 (Checkmate)` or `Draw (Repetition)`; `?` if unknown
+fn result_label(result: Option<&crate::game::KifuResult>) -> String {
+    use crate::core::PlayerId;
+
+    let Some(result) = result else {
+        return "?".to_string();
+    };
+    let winner = match result.winner {
+        Some(PlayerId::Player1) => "P1",
+        Some(PlayerId::Player2) => "P2",
+        None => "Draw",
+    };
+    format!("{} ({})", winner, result.reason)
+}
+
 /// Truncate string to max length (respects UTF-8 character boundaries)
-fn truncate(s: &str, max_len: usize) -> String {
-    let char_count: usize = s.chars().count();
-    if char_count <= max_len {
-        s.to_string()
-    } else {
-        // Take max_len - 1 characters and append ellipsis
-        let truncated: String = s.chars().take(max_len.saturating_sub(1)).collect();
-        format!("{}…", truncated)
+/// `s` cut to `max_width` terminal columns, ending in `…` when cut
+fn truncate(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    // Room for the ellipsis
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in s.chars() {
+        width += c.width().unwrap_or(0);
+        if width + 1 > max_width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// `s` cut or padded with spaces to exactly `width` columns
+fn pad(s: &str, width: usize) -> String {
+    let mut padded = truncate(s, width);
+    let used = padded.width();
+    padded.push_str(&" ".repeat(width.saturating_sub(used)));
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_metadata_keeps_order_and_skips_non_kifu() {
+        let dir = std::env::temp_dir().join(format!("aho_selector_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<PathBuf> = (0..50)
+            .map(|i| dir.join(format!("game_{:04}.json", i)))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            let content = if i == 7 {
+                r#"{"run_id": "not a kifu"}"#.to_string()
+            } else if i == 9 {
+                r#"{"board_setup": "Fair", "player1_name": "p9", "player2_name": "q", "moves": [1, 2, 3],
+                    "evaluator": "NNUE", "model_version": "v3",
+                    "result": {"winner": "Player2", "reason": "Checkmate"}}"#
+                    .to_string()
+            } else {
+                format!(
+                    r#"{{"board_setup": "Fair", "player1_name": "p{}", "player2_name": "q", "moves": [1, 2, 3]}}"#,
+                    i
+                )
+            };
+            fs::write(path, content).unwrap();
+        }
+
+        let files = load_metadata(&paths);
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(files.len(), 49);
+        let expected: Vec<String> = (0..50)
+            .filter(|&i| i != 7)
+            .map(|i| format!("p{}", i))
+            .collect();
+        let names: Vec<String> = files.iter().map(|f| f.player1.clone()).collect();
+        assert_eq!(names, expected);
+        assert!(files.iter().all(|f| f.move_count == 3));
+
+        // Older kifus have no result or evaluator
+        assert_eq!(result_label(files[0].result.as_ref()), "?");
+        assert_eq!(files[0].evaluator, "");
+        assert_eq!(result_label(files[8].result.as_ref()), "P2 (Checkmate)");
+        assert_eq!(files[8].evaluator, "NNUE v3");
+    }
+
+    #[test]
+    fn test_pad_by_display_width() {
+        // Kanji take two columns each
+        assert_eq!(pad("先手", 6), "先手  ");
+        assert_eq!(pad("abc", 2), "a…");
+        // A kanji that would only half fit leaves a space
+        assert_eq!(pad("先手番の人", 6), "先手… ");
+        assert_eq!(pad("先手番の人", 6).width(), 6);
     }
 }
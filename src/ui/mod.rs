@@ -7,11 +7,55 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 pub mod display;
+pub mod editor;
+pub mod glyphs;
 pub mod kifu_selector;
 pub mod selection;
+pub mod settings;
+pub mod theme;
+
+/// Mouse reporting for as long as it is alive, so squares can be clicked
+pub struct MouseCapture;
+
+impl MouseCapture {
+    pub fn enable() -> Self {
+        let _ = execute!(io::stdout(), event::EnableMouseCapture);
+        MouseCapture
+    }
+}
+
+impl Drop for MouseCapture {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), event::DisableMouseCapture);
+    }
+}
+
+/// Chooses the display language: `flag` (`--lang`), else `AHO_LANG`, else
+/// the theme file's `lang`, else the locale (see [`crate::i18n`])
+pub fn init_lang(flag: Option<&str>) -> anyhow::Result<()> {
+    use crate::i18n::{set_lang, Lang};
+
+    if let Some(name) = flag {
+        let lang = Lang::from_name(name)
+            .ok_or_else(|| anyhow::anyhow!("unknown language '{}' (ja or en)", name))?;
+        set_lang(lang);
+    } else if let Some(lang) = std::env::var("AHO_LANG")
+        .ok()
+        .and_then(|v| Lang::from_name(&v))
+        .or(settings::Settings::user().lang)
+    {
+        set_lang(lang);
+    }
+    Ok(())
+}
 
 pub async fn read_input_raw(default: &str, prompt: &str) -> anyhow::Result<String> {
-    print!("{}: (Default: {}) \r\n> ", prompt, default);
+    print!(
+        "{}: ({}: {}) \r\n> ",
+        prompt,
+        crate::i18n::tr("Default", "既定"),
+        default
+    );
     io::stdout().flush()?;
 
     let mut input = String::new();
@@ -32,12 +76,10 @@ pub async fn read_input_raw(default: &str, prompt: &str) -> anyhow::Result<Strin
                         print!("{}", c);
                         io::stdout().flush()?;
                     }
-                    KeyCode::Backspace => {
-                        if !input.is_empty() {
-                            input.pop();
-                            print!("\u{0008} \u{0008}");
-                            io::stdout().flush()?;
-                        }
+                    KeyCode::Backspace if !input.is_empty() => {
+                        input.pop();
+                        print!("\u{0008} \u{0008}");
+                        io::stdout().flush()?;
                     }
                     KeyCode::Esc => {
                         return Err(anyhow::anyhow!("Canceled"));
@@ -49,6 +91,35 @@ pub async fn read_input_raw(default: &str, prompt: &str) -> anyhow::Result<Strin
     }
 }
 
+/// "Rules of this variant": the rules in force for `board`, until a key is pressed
+pub fn show_rules(board: &crate::core::Board) -> anyhow::Result<()> {
+    execute!(
+        io::stdout(),
+        terminal::Clear(terminal::ClearType::All),
+        crossterm::cursor::MoveTo(0, 0)
+    )?;
+    print!(
+        "{}\r\n\r\n",
+        crate::i18n::tr(
+            "=== Rules of this variant ===",
+            "=== このバリアントのルール ==="
+        )
+    );
+    for line in crate::logic::rules::RulesInForce::for_board(board).describe() {
+        print!("{}\r\n", line);
+    }
+    print!(
+        "\r\n{}\r\n",
+        crate::i18n::tr("Press any key to return", "何かキーを押すと戻ります")
+    );
+    io::stdout().flush()?;
+    loop {
+        if let Event::Key(_) = event::read()? {
+            return Ok(());
+        }
+    }
+}
+
 pub fn select_kifu_file(dir: &str) -> anyhow::Result<Option<PathBuf>> {
     use std::fs;
 
@@ -141,10 +212,10 @@ pub fn select_kifu_file(dir: &str) -> anyhow::Result<Option<PathBuf>> {
                     KeyCode::Up | KeyCode::Char('k') => {
                         selected_index = selected_index.saturating_sub(1);
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if selected_index < files_with_labels.len() - 1 {
-                            selected_index += 1;
-                        }
+                    KeyCode::Down | KeyCode::Char('j')
+                        if selected_index < files_with_labels.len() - 1 =>
+                    {
+                        selected_index += 1;
                     }
                     KeyCode::Enter => {
                         return Ok(Some(files_with_labels[selected_index].1.clone()));
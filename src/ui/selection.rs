@@ -1,17 +1,27 @@
+use crate::core::handicap::Handicap;
 use crate::core::{Board, PlayerId};
 use crate::game::PerspectiveMode;
+use crate::i18n::{player_name, tr};
 use crate::player::PlayerController;
 use crossterm::event::{self, Event, KeyCode};
 use std::time::Duration;
 
-pub fn create_player_controllers(
-    choice: &str,
-    model_path: Option<String>,
-) -> anyhow::Result<(
+/// Player 1 controller, Player 2 controller, and the board perspective to use
+type PlayerSetup = (
     Box<dyn PlayerController>,
     Box<dyn PlayerController>,
     PerspectiveMode,
-)> {
+);
+
+/// Players of local play menu entry `choice`, the AIs with `evaluator_type`
+pub fn create_player_controllers(
+    choice: &str,
+    evaluator_type: &str,
+    model_path: Option<String>,
+) -> anyhow::Result<PlayerSetup> {
+    // Only AIs facing a human vary their openings
+    let opening_randomness = &crate::player::ai::config::AIConfig::get().opening_randomness;
+    let swindle = &crate::player::ai::config::AIConfig::get().swindle;
     match choice {
         "1" => Ok((
             Box::new(crate::player::TuiController::new(
@@ -29,13 +39,18 @@ pub fn create_player_controllers(
                 PlayerId::Player1,
                 "Player1",
             )),
-            Box::new(crate::player::ai::AlphaBetaAI::new(
-                PlayerId::Player2,
-                "AlphaBeta-Light",
-                crate::player::ai::AIStrength::Light,
-                model_path,
-                false,
-            )),
+            Box::new(
+                crate::player::ai::AlphaBetaAI::with_evaluator_type(
+                    PlayerId::Player2,
+                    "AlphaBeta-Light",
+                    crate::player::ai::AIStrength::Light,
+                    evaluator_type,
+                    model_path,
+                    false,
+                )
+                .with_opening_randomness(opening_randomness)
+                .with_swindle(swindle),
+            ),
             PerspectiveMode::Fixed(PlayerId::Player1),
         )),
         "3" => Ok((
@@ -43,47 +58,113 @@ pub fn create_player_controllers(
                 PlayerId::Player1,
                 "Player1",
             )),
-            Box::new(crate::player::ai::AlphaBetaAI::new(
-                PlayerId::Player2,
-                "AlphaBeta-Strong",
-                crate::player::ai::AIStrength::Strong,
-                model_path.clone(),
-                false,
-            )),
+            Box::new(
+                crate::player::ai::AlphaBetaAI::with_evaluator_type(
+                    PlayerId::Player2,
+                    "AlphaBeta-Strong",
+                    crate::player::ai::AIStrength::Strong,
+                    evaluator_type,
+                    model_path.clone(),
+                    false,
+                )
+                .with_opening_randomness(opening_randomness)
+                .with_swindle(swindle),
+            ),
             PerspectiveMode::Fixed(PlayerId::Player1),
         )),
-        "4" => Ok((
-            Box::new(crate::player::ai::AlphaBetaAI::new(
+        "6" => Ok((
+            Box::new(crate::player::TuiController::new(
                 PlayerId::Player1,
-                "AlphaBeta-Strong-1",
-                crate::player::ai::AIStrength::Strong,
-                model_path.clone(),
-                false,
-            )),
-            Box::new(crate::player::ai::AlphaBetaAI::new(
-                PlayerId::Player2,
-                "AlphaBeta-Strong-2",
-                crate::player::ai::AIStrength::Strong,
-                model_path,
-                false,
+                "Player1",
             )),
+            Box::new(
+                crate::player::ai::adaptive::AdaptiveAI::with_evaluator_type(
+                    PlayerId::Player2,
+                    "AlphaBeta-Adaptive",
+                    &crate::player::ai::config::AIConfig::get().adaptive_difficulty,
+                    evaluator_type,
+                    model_path,
+                    false,
+                ),
+            ),
+            PerspectiveMode::Fixed(PlayerId::Player1),
+        )),
+        "4" => Ok((
+            Box::new(
+                crate::player::ai::AlphaBetaAI::with_evaluator_type(
+                    PlayerId::Player1,
+                    "AlphaBeta-Strong-1",
+                    crate::player::ai::AIStrength::Strong,
+                    evaluator_type,
+                    model_path.clone(),
+                    false,
+                )
+                .with_swindle(swindle),
+            ),
+            Box::new(
+                crate::player::ai::AlphaBetaAI::with_evaluator_type(
+                    PlayerId::Player2,
+                    "AlphaBeta-Strong-2",
+                    crate::player::ai::AIStrength::Strong,
+                    evaluator_type,
+                    model_path,
+                    false,
+                )
+                .with_swindle(swindle),
+            ),
             PerspectiveMode::Fixed(PlayerId::Player1),
         )),
         _ => Err(anyhow::anyhow!("Invalid selection")),
     }
 }
 
-pub fn select_model() -> anyhow::Result<Option<String>> {
+/// One human playing both sides against a shared blitz clock
+pub fn create_blitz_controllers(config: crate::player::clock::BlitzConfig) -> PlayerSetup {
+    use crate::player::clock::BlitzClock;
+    use crate::player::TuiController;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let clock = Rc::new(RefCell::new(BlitzClock::new(config)));
+    (
+        Box::new(TuiController::new(PlayerId::Player1, "Player1").with_clock(Rc::clone(&clock))),
+        Box::new(TuiController::new(PlayerId::Player2, "Player2").with_clock(clock)),
+        PerspectiveMode::AutoFlip,
+    )
+}
+
+/// A model from `models/` for the `evaluator_type` evaluator, `None` for
+/// the one in ai_config.json
+pub fn select_model(evaluator_type: &str) -> anyhow::Result<Option<String>> {
     use crate::ml::model_registry::ModelRegistry;
     use std::io::Write;
 
+    // NNUE runs without ONNX Runtime
+    if !crate::ml::ML_ENABLED && evaluator_type != "NNUE" {
+        println!("\r\n[!] {}\r", crate::ml::ML_UNAVAILABLE_MESSAGE);
+        std::thread::sleep(Duration::from_secs(2));
+        return Ok(None);
+    }
+
     let mut registry = ModelRegistry::new();
     registry.discover_models("models")?;
 
     let models = registry.list();
     if models.is_empty() {
-        println!("\r\n[!] No ML models found in 'models/' directory.\r");
-        println!("Using default model if configured in ai_config.json.\r");
+        println!(
+            "\r\n[!] {}\r",
+            tr(
+                "No ML models found in 'models/' directory.",
+                "'models/' にモデルがありません。"
+            )
+        );
+        println!(
+            "{}\r",
+            tr(
+                "Using default model if configured in ai_config.json.",
+                "ai_config.json に設定があれば既定のモデルを使います。"
+            )
+        );
         std::thread::sleep(Duration::from_secs(2));
         return Ok(None);
     }
@@ -94,7 +175,16 @@ pub fn select_model() -> anyhow::Result<Option<String>> {
 
     let mut selected_idx = 0;
 
-    print!("\r\nSelect ML Model (Use ↑/↓ and Enter):\r\n");
+    print!(
+        "\r\n{}\r\n",
+        tr(
+            "Select ML Model (Use ↑/↓ and Enter):",
+            "ML モデルを選んでください (↑/↓ と Enter):"
+        )
+    );
+
+    // Lines must not wrap, or moving the cursor back up goes wrong
+    let width = crossterm::terminal::size().map_or(80, |(w, _)| w as usize);
 
     loop {
         // Render list
@@ -105,7 +195,19 @@ pub fn select_model() -> anyhow::Result<Option<String>> {
                 .as_ref()
                 .map(|v| format!(" (v{})", v))
                 .unwrap_or_default();
-            print!("\r\x1B[K{}{}{}\r\n", prefix, model.name, version);
+            // Training provenance from models/manifest.json
+            let provenance = model
+                .manifest
+                .as_ref()
+                .map(|entry| entry.summary())
+                .filter(|summary| !summary.is_empty())
+                .map(|summary| format!(" - {}", summary))
+                .unwrap_or_default();
+            let line: String = format!("{}{}{}{}", prefix, model.name, version, provenance)
+                .chars()
+                .take(width.saturating_sub(1))
+                .collect();
+            print!("\r\x1B[K{}\r\n", line);
         }
 
         std::io::stdout().flush()?;
@@ -117,14 +219,10 @@ pub fn select_model() -> anyhow::Result<Option<String>> {
             if let Event::Key(key) = event::read()? {
                 match key.code {
                     KeyCode::Up => {
-                        if selected_idx > 0 {
-                            selected_idx -= 1;
-                        }
+                        selected_idx = selected_idx.saturating_sub(1);
                     }
-                    KeyCode::Down => {
-                        if selected_idx < models.len() - 1 {
-                            selected_idx += 1;
-                        }
+                    KeyCode::Down if selected_idx < models.len() - 1 => {
+                        selected_idx += 1;
                     }
                     KeyCode::Enter => {
                         // Clear the menu display area
@@ -152,10 +250,14 @@ pub fn select_model() -> anyhow::Result<Option<String>> {
     }
 }
 
-fn ask_hand_config(player_name: &str) -> anyhow::Result<Option<bool>> {
+fn ask_hand_config(player: PlayerId) -> anyhow::Result<Option<bool>> {
     print!(
-        "\r\nEnable held pieces (mochigoma) for {}? (y: Yes, n: No, Enter: Default): ",
-        player_name
+        "\r\n{}",
+        crate::trf!(
+            "Enable held pieces (mochigoma) for {}? (y: Yes, n: No, Enter: Default): ",
+            "{}の持ち駒を使いますか? (y: はい, n: いいえ, Enter: 既定): ",
+            player_name(player)
+        )
     );
     use std::io::Write;
     std::io::stdout().flush()?;
@@ -165,15 +267,15 @@ fn ask_hand_config(player_name: &str) -> anyhow::Result<Option<bool>> {
             if let Event::Key(key) = event::read()? {
                 match key.code {
                     KeyCode::Char('y') => {
-                        println!("Yes\r");
+                        println!("{}\r", tr("Yes", "はい"));
                         return Ok(Some(true));
                     }
                     KeyCode::Char('n') => {
-                        println!("No\r");
+                        println!("{}\r", tr("No", "いいえ"));
                         return Ok(Some(false));
                     }
                     KeyCode::Enter | KeyCode::Char('d') => {
-                        println!("Default\r");
+                        println!("{}\r", tr("Default", "既定"));
                         return Ok(None);
                     }
                     KeyCode::Char('q') => return Err(anyhow::anyhow!("Canceled")),
@@ -184,14 +286,28 @@ fn ask_hand_config(player_name: &str) -> anyhow::Result<Option<bool>> {
     }
 }
 
-pub fn select_board_setup() -> anyhow::Result<(Board, String)> {
-    print!("\r\nSelect board setup:\r\n");
-    print!("1. Shogi (P1) vs Chess (P2)\r\n");
-    print!("2. Chess (P1) vs Shogi (P2)\r\n");
-    print!("3. Shogi vs Shogi\r\n");
-    print!("4. Chess vs Chess\r\n");
-    print!("5. Fair (Mixed Shogi/Chess)\r\n");
-    print!("6. Reversed Fair\r\n");
+/// The chosen board, its setup name, the Chess960 seed if the player chose a
+/// shuffled start, and the handicap Player1 gives, if any
+pub fn select_board_setup() -> anyhow::Result<(Board, String, Option<u64>, Option<Handicap>)> {
+    print!(
+        "\r\n{}\r\n",
+        tr("Select board setup:", "盤面を選んでください:")
+    );
+    print!(
+        "1. {}\r\n",
+        tr("Shogi (P1) vs Chess (P2)", "将棋 (先手) vs チェス (後手)")
+    );
+    print!(
+        "2. {}\r\n",
+        tr("Chess (P1) vs Shogi (P2)", "チェス (先手) vs 将棋 (後手)")
+    );
+    print!("3. {}\r\n", tr("Shogi vs Shogi", "将棋 vs 将棋"));
+    print!("4. {}\r\n", tr("Chess vs Chess", "チェス vs チェス"));
+    print!(
+        "5. {}\r\n",
+        tr("Fair (Mixed Shogi/Chess)", "Fair (将棋とチェスの混合)")
+    );
+    print!("6. {}\r\n", tr("Reversed Fair", "Fair (先後入れ替え)"));
 
     let b_choice = loop {
         if event::poll(Duration::from_millis(100))? {
@@ -215,69 +331,121 @@ pub fn select_board_setup() -> anyhow::Result<(Board, String)> {
     let (p1_hand, p2_hand) = match b_choice {
         "3" | "5" | "6" => (Some(true), Some(true)), // Shogi/Fair modes always enable hands
         "4" => (Some(false), Some(false)),           // ChessOnly always disables hands
-        _ => (ask_hand_config("Player 1")?, ask_hand_config("Player 2")?),
+        _ => (
+            ask_hand_config(PlayerId::Player1)?,
+            ask_hand_config(PlayerId::Player2)?,
+        ),
     };
 
-    Ok(match b_choice {
+    use crate::core::setup;
+    let (map, p1_shogi, p2_shogi, name) = match b_choice {
         "1" => (
-            crate::core::setup::setup_from_strings(
-                &crate::core::setup::get_standard_mixed_setup(),
-                true,
-                false,
-                p1_hand,
-                p2_hand,
-            ),
-            "StandardMixed".to_string(),
+            setup::get_standard_mixed_setup(),
+            true,
+            false,
+            "StandardMixed",
         ),
         "2" => (
-            crate::core::setup::setup_from_strings(
-                &crate::core::setup::get_reversed_mixed_setup(),
-                false,
-                true,
-                p1_hand,
-                p2_hand,
-            ),
-            "ReversedMixed".to_string(),
-        ),
-        "3" => (
-            crate::core::setup::setup_from_strings(
-                &crate::core::setup::get_shogi_setup(),
-                true,
-                true,
-                p1_hand,
-                p2_hand,
-            ),
-            "ShogiOnly".to_string(),
-        ),
-        "4" => (
-            crate::core::setup::setup_from_strings(
-                &crate::core::setup::get_chess_setup(),
-                false,
-                false,
-                p1_hand,
-                p2_hand,
-            ),
-            "ChessOnly".to_string(),
-        ),
-        "5" => (
-            crate::core::setup::setup_from_strings(
-                &crate::core::setup::get_fair_setup(),
-                true,
-                true,
-                p1_hand,
-                p2_hand,
-            ),
-            "Fair".to_string(),
+            setup::get_reversed_mixed_setup(),
+            false,
+            true,
+            "ReversedMixed",
         ),
-        _ => (
-            crate::core::setup::setup_from_strings(
-                &crate::core::setup::get_reversed_fair_setup(),
-                true,
-                true,
-                p1_hand,
-                p2_hand,
-            ),
-            "ReversedFair".to_string(),
-        ),
-    })
+        "3" => (setup::get_shogi_setup(), true, true, "ShogiOnly"),
+        "4" => (setup::get_chess_setup(), false, false, "ChessOnly"),
+        "5" => (setup::get_fair_setup(), true, true, "Fair"),
+        _ => (setup::get_reversed_fair_setup(), true, true, "ReversedFair"),
+    };
+
+    // Every setup but Shogi vs Shogi has a chess back rank to shuffle
+    let chess960_seed = if b_choice != "3" && ask_chess960()? {
+        Some(rand::random::<u64>())
+    } else {
+        None
+    };
+    let rows = match chess960_seed {
+        Some(seed) => setup::chess960_setup(&map, seed),
+        None => map.iter().map(|row| row.to_string()).collect(),
+    };
+    let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+    let mut board = setup::setup_from_strings(&rows, p1_shogi, p2_shogi, p1_hand, p2_hand);
+    let handicap = ask_handicap(&board)?;
+    if let Some(handicap) = handicap {
+        handicap.apply(&mut board)?;
+    }
+    Ok((board, name.to_string(), chess960_seed, handicap))
+}
+
+/// The handicaps Player1's pieces allow, as a numbered menu (0 plays even)
+fn ask_handicap(board: &Board) -> anyhow::Result<Option<Handicap>> {
+    let choices: Vec<Handicap> = Handicap::ALL
+        .into_iter()
+        .filter(|h| h.applies_to(board))
+        .collect();
+    print!(
+        "\r\n{}\r\n",
+        tr("Handicap given by Player 1:", "先手の駒落ち:")
+    );
+    print!("0. {}\r\n", tr("None (Enter)", "なし (Enter)"));
+    for (i, handicap) in choices.iter().enumerate() {
+        print!("{}. {}\r\n", i + 1, handicap.label());
+    }
+    use std::io::Write;
+    std::io::stdout().flush()?;
+
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('0') | KeyCode::Enter => {
+                        println!("{}\r", tr("None", "なし"));
+                        return Ok(None);
+                    }
+                    KeyCode::Char('q') => return Err(anyhow::anyhow!("Canceled")),
+                    KeyCode::Char(c) => {
+                        let chosen = match c.to_digit(10) {
+                            Some(d) if d >= 1 => choices.get(d as usize - 1),
+                            _ => None,
+                        };
+                        if let Some(&handicap) = chosen {
+                            println!("{}\r", handicap.label());
+                            return Ok(Some(handicap));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn ask_chess960() -> anyhow::Result<bool> {
+    print!(
+        "\r\n{}",
+        tr(
+            "Chess960 start (shuffled chess back rank)? (y: Yes, n/Enter: No): ",
+            "Chess960 (チェスの後列をシャッフル) にしますか? (y: はい, n/Enter: いいえ): "
+        )
+    );
+    use std::io::Write;
+    std::io::stdout().flush()?;
+
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('y') => {
+                        println!("{}\r", tr("Yes", "はい"));
+                        return Ok(true);
+                    }
+                    KeyCode::Char('n') | KeyCode::Enter => {
+                        println!("{}\r", tr("No", "いいえ"));
+                        return Ok(false);
+                    }
+                    KeyCode::Char('q') => return Err(anyhow::anyhow!("Canceled")),
+                    _ => {}
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,425 @@
+//! ユーザー設定
+//!
+//! 毎回入力し直さなくてよいように、メインメニューの設定画面で選んだ値
+//! (サーバーの待ち受けアドレスと接続先・AI の強さ・評価関数・テーマ・視点・ネットワーク
+//! 対戦で名乗る名前) と、メインメニューの `l` で切り替えた表示言語をユーザーの
+//! 設定ファイルに保存する。場所は `AHO_SETTINGS`、なければ
+//! `$XDG_CONFIG_HOME/aho-ai/settings.toml` (`~/.config/aho-ai/settings.toml`、
+//! Windows では `%APPDATA%\aho-ai\settings.toml`)。
+//!
+//! ```toml
+//! bind_address = "0.0.0.0:8080"
+//! connect_address = "192.168.1.10:8080"
+//! ai_strength = "adaptive"
+//! evaluator = "NNUE"
+//! theme = "high-contrast"
+//! perspective = "player1"
+//! player_name = "alice"
+//! lang = "ja"
+//! ```
+
+use crate::core::PlayerId;
+use crate::game::PerspectiveMode;
+use crate::i18n::{tr, Lang};
+use crate::player::ai::AIStrength;
+use crate::ui::theme::ThemeBase;
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::{execute, terminal};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Address the menu's server listens on when none is saved
+pub const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Server the menu's client connects to when none is saved
+pub const DEFAULT_CONNECT_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Evaluators that can be saved (see [`crate::player::ai::AlphaBetaAI::with_evaluator_type`])
+pub const EVALUATORS: [&str; 3] = ["Handcrafted", "NeuralNetwork", "NNUE"];
+
+/// The AI picked with Enter in the local play menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AiLevel {
+    Light,
+    #[default]
+    Strong,
+    Adaptive,
+}
+
+impl AiLevel {
+    /// The local play menu entry for a human against this AI
+    pub fn menu_choice(self) -> &'static str {
+        match self {
+            AiLevel::Light => "2",
+            AiLevel::Strong => "3",
+            AiLevel::Adaptive => "6",
+        }
+    }
+
+    /// The fixed strength closest to this level, for self-play
+    pub fn strength(self) -> AIStrength {
+        match self {
+            AiLevel::Light => AIStrength::Light,
+            AiLevel::Strong | AiLevel::Adaptive => AIStrength::Strong,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            AiLevel::Light => AiLevel::Strong,
+            AiLevel::Strong => AiLevel::Adaptive,
+            AiLevel::Adaptive => AiLevel::Light,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AiLevel::Light => tr("Light", "弱"),
+            AiLevel::Strong => tr("Strong", "強"),
+            AiLevel::Adaptive => tr("Adaptive", "相手に合わせる"),
+        }
+    }
+}
+
+/// Whose side local games are seen from, instead of each mode's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PerspectiveSetting {
+    /// The side to move at the bottom
+    AutoFlip,
+    Player1,
+    Player2,
+}
+
+impl PerspectiveSetting {
+    pub fn mode(self) -> PerspectiveMode {
+        match self {
+            PerspectiveSetting::AutoFlip => PerspectiveMode::AutoFlip,
+            PerspectiveSetting::Player1 => PerspectiveMode::Fixed(PlayerId::Player1),
+            PerspectiveSetting::Player2 => PerspectiveMode::Fixed(PlayerId::Player2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub bind_address: String,
+    pub connect_address: String,
+    pub ai_strength: AiLevel,
+    /// One of [`EVALUATORS`], `None` for the one in ai_config.json
+    pub evaluator: Option<String>,
+    /// Base theme when there is no theme file, `None` for the usual one
+    pub theme: Option<ThemeBase>,
+    /// `None` for each mode's own (e.g. flipping in human vs human)
+    pub perspective: Option<PerspectiveSetting>,
+    /// Name given to the server's lobby in network games
+    pub player_name: String,
+    /// Language of menus and messages, `None` to follow the locale
+    pub lang: Option<Lang>,
+    /// The one address of older settings files, read into both of the above
+    #[serde(rename = "server_address", skip_serializing)]
+    legacy_address: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            connect_address: DEFAULT_CONNECT_ADDRESS.to_string(),
+            ai_strength: AiLevel::default(),
+            evaluator: None,
+            theme: None,
+            perspective: None,
+            player_name: crate::network::client::DEFAULT_PLAYER_NAME.to_string(),
+            lang: None,
+            legacy_address: None,
+        }
+    }
+}
+
+/// The user's settings file (see the module docs), `None` without a home
+pub fn settings_path() -> Option<PathBuf> {
+    let var = |key: &str| std::env::var_os(key).filter(|v| !v.is_empty());
+    if let Some(path) = var("AHO_SETTINGS") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| var("APPDATA").map(PathBuf::from))?;
+    Some(config_dir.join("aho-ai").join("settings.toml"))
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut settings: Settings = toml::from_str(&std::fs::read_to_string(path)?)?;
+        if let Some(addr) = settings.legacy_address.take() {
+            settings.bind_address = addr.clone();
+            settings.connect_address = addr;
+        }
+        if let Some(evaluator) = &settings.evaluator {
+            if !EVALUATORS.contains(&evaluator.as_str()) {
+                anyhow::bail!("unknown evaluator '{}'", evaluator);
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        crate::util::atomic_write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The saved settings, or the defaults when there are none yet
+    pub fn user() -> Self {
+        let Some(path) = settings_path().filter(|p| p.exists()) else {
+            return Settings::default();
+        };
+        Settings::load(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to load {}: {}", path.display(), e);
+            eprintln!("Using the default settings");
+            Settings::default()
+        })
+    }
+
+    /// Keeps `lang` for the next start, e.g. after the main menu's `l`
+    pub fn save_lang(lang: Lang) -> anyhow::Result<()> {
+        let path = settings_path().ok_or_else(|| anyhow::anyhow!("no home directory"))?;
+        let mut settings = Settings::user();
+        settings.lang = Some(lang);
+        settings.save(&path)
+    }
+
+    /// The evaluator the menus' AIs use
+    pub fn evaluator_type(&self) -> String {
+        self.evaluator.clone().unwrap_or_else(|| {
+            crate::player::ai::config::AIConfig::get()
+                .evaluation
+                .evaluator_type
+                .clone()
+        })
+    }
+
+    fn next_evaluator(&mut self) {
+        let index = self
+            .evaluator
+            .as_ref()
+            .and_then(|e| EVALUATORS.iter().position(|name| name == e));
+        self.evaluator = match index {
+            None => Some(EVALUATORS[0].to_string()),
+            Some(i) => EVALUATORS.get(i + 1).map(|name| name.to_string()),
+        };
+    }
+
+    fn next_theme(&mut self) {
+        self.theme = match self.theme {
+            None => Some(ThemeBase::Default),
+            Some(ThemeBase::Default) => Some(ThemeBase::HighContrast),
+            Some(ThemeBase::HighContrast) => Some(ThemeBase::Monochrome),
+            Some(ThemeBase::Monochrome) => None,
+        };
+    }
+
+    fn next_perspective(&mut self) {
+        self.perspective = match self.perspective {
+            None => Some(PerspectiveSetting::AutoFlip),
+            Some(PerspectiveSetting::AutoFlip) => Some(PerspectiveSetting::Player1),
+            Some(PerspectiveSetting::Player1) => Some(PerspectiveSetting::Player2),
+            Some(PerspectiveSetting::Player2) => None,
+        };
+    }
+}
+
+/// How a setting's value is shown in the settings screen
+fn value_label<T: Serialize>(value: &Option<T>, unset: &'static str) -> String {
+    match value {
+        // kebab-case の名前をそのまま見せる
+        Some(value) => serde_json::to_value(value)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        None => unset.to_string(),
+    }
+}
+
+/// The settings screen of the main menu: every change is saved at once
+pub async fn run_settings() -> anyhow::Result<()> {
+    let Some(path) = settings_path() else {
+        print!(
+            "\r\n{}\r\n",
+            tr(
+                "No home directory to keep settings in (set AHO_SETTINGS)",
+                "設定を保存するホームディレクトリがありません (AHO_SETTINGS を設定してください)"
+            )
+        );
+        std::thread::sleep(Duration::from_secs(2));
+        return Ok(());
+    };
+    let mut settings = Settings::user();
+    let mut message: Option<String> = None;
+
+    loop {
+        execute!(
+            io::stdout(),
+            terminal::Clear(terminal::ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        )?;
+        print!("=== {} ===\r\n", tr("Settings", "設定"));
+        print!("{}\r\n\r\n", path.display());
+        print!(
+            "1. {}: {}\r\n",
+            tr("Server address to listen on", "サーバーの待ち受けアドレス"),
+            settings.bind_address
+        );
+        print!(
+            "2. {}: {}\r\n",
+            tr("Server to connect to", "接続先のサーバー"),
+            settings.connect_address
+        );
+        print!(
+            "3. {}: {}\r\n",
+            tr("AI strength", "AI の強さ"),
+            settings.ai_strength.label()
+        );
+        print!(
+            "4. {}: {}\r\n",
+            tr("Evaluator", "評価関数"),
+            settings
+                .evaluator
+                .as_deref()
+                .unwrap_or(tr("ai_config.json", "ai_config.json の設定"))
+        );
+        print!(
+            "5. {}: {}\r\n",
+            tr("Theme", "テーマ"),
+            value_label(
+                &settings.theme,
+                tr("theme file or default", "テーマファイルか既定")
+            )
+        );
+        print!(
+            "6. {}: {}\r\n",
+            tr("Perspective", "視点"),
+            value_label(&settings.perspective, tr("per game mode", "対局の種類ごと"))
+        );
+        print!(
+            "7. {}: {}\r\n",
+            tr("Player name (network)", "名前 (ネットワーク対戦)"),
+            settings.player_name
+        );
+        print!(
+            "\r\n{}\r\n",
+            tr(
+                "[1-7]: Change | [q/Esc]: Back (the theme applies from the next start)",
+                "[1-7]: 変更 | [q/Esc]: 戻る (テーマは次回の起動から)"
+            )
+        );
+        if let Some(message) = message.take() {
+            print!("{}\r\n", message);
+        }
+        io::stdout().flush()?;
+
+        let code = loop {
+            if event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    break key.code;
+                }
+            }
+        };
+        match code {
+            KeyCode::Char(key @ ('1' | '2')) => {
+                let (address, prompt) = if key == '1' {
+                    (
+                        &mut settings.bind_address,
+                        tr("Server address to listen on", "サーバーの待ち受けアドレス"),
+                    )
+                } else {
+                    (
+                        &mut settings.connect_address,
+                        tr("Server to connect to", "接続先のサーバー"),
+                    )
+                };
+                print!("\r\n");
+                match crate::ui::read_input_raw(address, prompt).await {
+                    Ok(addr) => *address = addr.trim().to_string(),
+                    // Esc で入力をやめる
+                    Err(_) => continue,
+                }
+            }
+            KeyCode::Char('3') => settings.ai_strength = settings.ai_strength.next(),
+            KeyCode::Char('4') => settings.next_evaluator(),
+            KeyCode::Char('5') => settings.next_theme(),
+            KeyCode::Char('6') => settings.next_perspective(),
+            KeyCode::Char('7') => {
+                print!("\r\n");
+                match crate::ui::read_input_raw(&settings.player_name, tr("Name", "名前")).await {
+                    Ok(name) if !name.trim().is_empty() => {
+                        settings.player_name = name.trim().to_string()
+                    }
+                    _ => continue,
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            _ => continue,
+        }
+        if let Err(e) = settings.save(&path) {
+            message = Some(crate::trf!("Save failed: {}", "保存に失敗: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_file() {
+        let dir = std::env::temp_dir().join(format!("aho_settings_{}", std::process::id()));
+        let path = dir.join("settings.toml");
+        let mut settings = Settings {
+            ai_strength: AiLevel::Adaptive,
+            perspective: Some(PerspectiveSetting::Player2),
+            lang: Some(Lang::Ja),
+            ..Default::default()
+        };
+        settings.next_evaluator();
+        settings.next_theme();
+        settings.next_theme();
+        settings.save(&path).unwrap();
+
+        let loaded = Settings::load(&path).unwrap();
+        assert_eq!(loaded, settings);
+        assert_eq!(loaded.evaluator.as_deref(), Some("Handcrafted"));
+        assert_eq!(loaded.theme, Some(ThemeBase::HighContrast));
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("lang = \"ja\""));
+        assert_eq!(loaded.ai_strength.menu_choice(), "6");
+        assert_eq!(
+            loaded.perspective.map(PerspectiveSetting::mode),
+            Some(PerspectiveMode::Fixed(PlayerId::Player2))
+        );
+
+        // Missing keys keep their defaults; unknown values are refused
+        std::fs::write(&path, "ai_strength = \"light\"\n").unwrap();
+        let partial = Settings::load(&path).unwrap();
+        assert_eq!(partial.bind_address, DEFAULT_BIND_ADDRESS);
+        assert_eq!(partial.connect_address, DEFAULT_CONNECT_ADDRESS);
+        assert_eq!(partial.ai_strength.strength(), AIStrength::Light);
+        // The single address of older files is taken for both
+        std::fs::write(&path, "server_address = \"10.0.0.2:9000\"\n").unwrap();
+        let legacy = Settings::load(&path).unwrap();
+        assert_eq!(legacy.bind_address, "10.0.0.2:9000");
+        assert_eq!(legacy.connect_address, "10.0.0.2:9000");
+        std::fs::write(&path, "evaluator = \"Random\"\n").unwrap();
+        assert!(Settings::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,352 @@
+//! Colours and markers of the board display, user-tweakable through
+//! `theme.toml` or `theme.json`
+//!
+//! `base` picks a built-in theme (`default`, `high-contrast`, or `monochrome`
+//! for terminals without colour) and the other keys replace its entries. A
+//! style is a list of words: a colour (`cyan`, `dark_grey`, `#ff8800`), a
+//! background colour after `on_` (`on_yellow`) and attributes (`bold`, `dim`,
+//! `italic`, `underline`, `reverse`). With `colors = false` every colour is
+//! dropped and only the attributes are kept. Without a file the theme is
+//! the one saved in the settings (see [`crate::ui::settings`]), else
+//! `monochrome` when `NO_COLOR` is set and `default` otherwise. `glyphs` picks
+//! the piece labels (see [`crate::ui::glyphs`]), e.g. `ascii` for fonts whose
+//! kanji break the grid.
+//!
+//! ```toml
+//! base = "high-contrast"
+//! glyphs = "ascii"
+//! player2 = "bold red"
+//! last_move = "bold underline"
+//! last_move_marker = "<>"
+//! ```
+
+use crate::core::PlayerId;
+use crate::ui::glyphs::GlyphStyle;
+use crossterm::style::{Attribute, Color, ContentStyle};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Files looked up in the working directory, in order
+pub const THEME_FILES: [&str; 2] = ["theme.toml", "theme.json"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeBase {
+    #[default]
+    Default,
+    HighContrast,
+    Monochrome,
+}
+
+/// A theme file as written: the base theme and the entries it replaces
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeFile {
+    pub base: ThemeBase,
+    pub colors: Option<bool>,
+    /// Player1's pieces, on the board and in hand
+    pub player1: Option<String>,
+    pub player2: Option<String>,
+    pub cursor: Option<String>,
+    /// The piece picked up to move
+    pub selected: Option<String>,
+    /// Squares the picked piece can move or be dropped to
+    pub highlight: Option<String>,
+    pub last_move: Option<String>,
+    /// The status line above the board
+    pub status: Option<String>,
+    /// The running clock and the chosen piece in hand
+    pub active: Option<String>,
+    /// Filled and empty parts of the evaluation bar
+    pub eval_filled: Option<String>,
+    pub eval_empty: Option<String>,
+    /// The king in check
+    pub check: Option<String>,
+    /// Pieces the opponent attacks, when threat hints are on
+    pub threatened: Option<String>,
+    /// Two characters around the squares of the last move
+    pub last_move_marker: Option<String>,
+    /// `cjk`, `romaji`, `ascii` or `auto`
+    pub glyphs: Option<String>,
+}
+
+impl ThemeBase {
+    /// The built-in theme as a theme file with every entry written
+    fn preset(self) -> ThemeFile {
+        let style = |text: &str| Some(text.to_string());
+        match self {
+            ThemeBase::Default => ThemeFile {
+                base: self,
+                colors: Some(true),
+                player1: style("cyan"),
+                player2: style("magenta"),
+                cursor: style("yellow"),
+                selected: style("blue"),
+                highlight: style("green"),
+                last_move: style("red"),
+                status: style("bold yellow"),
+                active: style("black on_yellow"),
+                eval_filled: style("on_white"),
+                eval_empty: style("on_dark_grey"),
+                check: style("bold white on_red"),
+                threatened: style("on_dark_red"),
+                last_move_marker: style("{}"),
+                glyphs: None,
+            },
+            ThemeBase::HighContrast => ThemeFile {
+                base: self,
+                colors: Some(true),
+                player1: style("bold white"),
+                player2: style("bold yellow"),
+                cursor: style("black on_white"),
+                selected: style("black on_cyan"),
+                highlight: style("black on_green"),
+                last_move: style("bold white on_red"),
+                status: style("bold white"),
+                active: style("black on_white"),
+                eval_filled: style("on_white"),
+                eval_empty: style("on_black"),
+                check: style("bold white on_red"),
+                threatened: style("bold black on_magenta"),
+                last_move_marker: style("<>"),
+                glyphs: None,
+            },
+            // 色がなくても、属性と括弧で区別できるようにする
+            ThemeBase::Monochrome => ThemeFile {
+                base: self,
+                colors: Some(false),
+                player1: style("bold"),
+                player2: style("underline"),
+                cursor: style("reverse"),
+                selected: style("reverse"),
+                highlight: style("bold"),
+                last_move: style("bold"),
+                status: style("bold"),
+                active: style("reverse"),
+                eval_filled: style("reverse"),
+                eval_empty: style(""),
+                check: style("bold reverse"),
+                threatened: style("italic"),
+                last_move_marker: style("{}"),
+                glyphs: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// `false` drops every colour (see the module docs)
+    pub colors: bool,
+    pub player1: ContentStyle,
+    pub player2: ContentStyle,
+    pub cursor: ContentStyle,
+    pub selected: ContentStyle,
+    pub highlight: ContentStyle,
+    pub last_move: ContentStyle,
+    pub status: ContentStyle,
+    pub active: ContentStyle,
+    pub eval_filled: ContentStyle,
+    pub eval_empty: ContentStyle,
+    pub check: ContentStyle,
+    pub threatened: ContentStyle,
+    pub last_move_marker: (char, char),
+    /// Piece labels, `None` to detect them
+    pub glyphs: Option<GlyphStyle>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from_file(&ThemeFile::default()).expect("built-in themes parse")
+    }
+}
+
+fn parse_color(word: &str) -> anyhow::Result<Color> {
+    if let Some(hex) = word.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not a #rrggbb colour", word))?;
+        return Ok(Color::Rgb {
+            r: (value >> 16) as u8,
+            g: (value >> 8) as u8,
+            b: value as u8,
+        });
+    }
+    Color::try_from(word).map_err(|_| anyhow::anyhow!("unknown colour '{}'", word))
+}
+
+/// A style as written in a theme (see the module docs)
+pub fn parse_style(text: &str) -> anyhow::Result<ContentStyle> {
+    let mut style = ContentStyle::new();
+    for word in text.split_whitespace() {
+        let attribute = match word.to_ascii_lowercase().as_str() {
+            "bold" => Some(Attribute::Bold),
+            "dim" => Some(Attribute::Dim),
+            "italic" => Some(Attribute::Italic),
+            "underline" => Some(Attribute::Underlined),
+            "reverse" => Some(Attribute::Reverse),
+            _ => None,
+        };
+        match (attribute, word.strip_prefix("on_")) {
+            (Some(attribute), _) => style.attributes.set(attribute),
+            (None, Some(background)) => style.background_color = Some(parse_color(background)?),
+            (None, None) => style.foreground_color = Some(parse_color(word)?),
+        }
+    }
+    Ok(style)
+}
+
+static THEME: Lazy<Theme> = Lazy::new(|| {
+    let Some(path) = THEME_FILES.iter().map(Path::new).find(|p| p.exists()) else {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let base = crate::ui::settings::Settings::user().theme;
+        return Theme::preset(base.unwrap_or(if no_color {
+            ThemeBase::Monochrome
+        } else {
+            ThemeBase::Default
+        }));
+    };
+    crate::util::load_config::<ThemeFile>(path)
+        .and_then(|file| Theme::from_file(&file))
+        .unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to load {}: {}", path.display(), e);
+            eprintln!("Using the default theme");
+            Theme::default()
+        })
+});
+
+impl Theme {
+    /// The theme from the theme file (loaded once)
+    pub fn get() -> &'static Theme {
+        &THEME
+    }
+
+    pub fn preset(base: ThemeBase) -> Theme {
+        Theme::from_file(&ThemeFile {
+            base,
+            ..Default::default()
+        })
+        .expect("built-in themes parse")
+    }
+
+    /// `file`'s base theme with its entries replaced
+    pub fn from_file(file: &ThemeFile) -> anyhow::Result<Theme> {
+        let preset = file.base.preset();
+        let colors = file.colors.or(preset.colors).unwrap_or(true);
+        let style = |written: &Option<String>, default: &Option<String>| {
+            let text = written
+                .as_deref()
+                .or(default.as_deref())
+                .unwrap_or_default();
+            let mut style = parse_style(text)?;
+            if !colors {
+                style.foreground_color = None;
+                style.background_color = None;
+            }
+            anyhow::Ok(style)
+        };
+
+        let marker = file
+            .last_move_marker
+            .as_deref()
+            .or(preset.last_move_marker.as_deref())
+            .unwrap_or_default();
+        let last_move_marker = match marker.chars().collect::<Vec<_>>()[..] {
+            [open, close] => (open, close),
+            _ => anyhow::bail!("last_move_marker must be two characters, not '{}'", marker),
+        };
+
+        let glyphs = match file.glyphs.as_deref() {
+            None | Some("auto") => None,
+            Some(name) => Some(
+                GlyphStyle::from_name(name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown glyphs '{}'", name))?,
+            ),
+        };
+
+        Ok(Theme {
+            colors,
+            player1: style(&file.player1, &preset.player1)?,
+            player2: style(&file.player2, &preset.player2)?,
+            cursor: style(&file.cursor, &preset.cursor)?,
+            selected: style(&file.selected, &preset.selected)?,
+            highlight: style(&file.highlight, &preset.highlight)?,
+            last_move: style(&file.last_move, &preset.last_move)?,
+            status: style(&file.status, &preset.status)?,
+            active: style(&file.active, &preset.active)?,
+            eval_filled: style(&file.eval_filled, &preset.eval_filled)?,
+            eval_empty: style(&file.eval_empty, &preset.eval_empty)?,
+            check: style(&file.check, &preset.check)?,
+            threatened: style(&file.threatened, &preset.threatened)?,
+            last_move_marker,
+            glyphs,
+        })
+    }
+
+    pub fn player(&self, player: PlayerId) -> ContentStyle {
+        match player {
+            PlayerId::Player1 => self.player1,
+            PlayerId::Player2 => self.player2,
+        }
+    }
+}
+
+/// `top` over `base`: its colours where it has them, and both attributes
+pub fn layered(base: ContentStyle, top: ContentStyle) -> ContentStyle {
+    let mut style = base;
+    style.foreground_color = top.foreground_color.or(base.foreground_color);
+    style.background_color = top.background_color.or(base.background_color);
+    style.attributes.extend(top.attributes);
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_file() {
+        let file: ThemeFile = toml::from_str(
+            "base = \"high-contrast\"\nplayer2 = \"bold #ff8800 on_black\"\n\
+             last_move_marker = \"<>\"\nglyphs = \"ascii\"\n",
+        )
+        .unwrap();
+        let theme = Theme::from_file(&file).unwrap();
+        assert_eq!(
+            theme.player2.foreground_color,
+            Some(Color::Rgb {
+                r: 0xff,
+                g: 0x88,
+                b: 0
+            })
+        );
+        assert_eq!(theme.player2.background_color, Some(Color::Black));
+        assert!(theme.player2.attributes.has(Attribute::Bold));
+        assert_eq!(
+            theme.player1,
+            Theme::preset(ThemeBase::HighContrast).player1
+        );
+        assert_eq!(theme.last_move_marker, ('<', '>'));
+        assert_eq!(theme.glyphs, Some(GlyphStyle::Ascii));
+        assert_eq!(Theme::default().glyphs, None);
+
+        let mono = Theme::preset(ThemeBase::Monochrome);
+        assert!(!mono.colors);
+        assert_ne!(mono.player1, mono.player2);
+        let stripped: ThemeFile =
+            serde_json::from_str(r#"{"colors": false, "cursor": "red reverse"}"#).unwrap();
+        let stripped = Theme::from_file(&stripped).unwrap();
+        assert_eq!(stripped.cursor.foreground_color, None);
+        assert!(stripped.cursor.attributes.has(Attribute::Reverse));
+
+        assert!(parse_style("bold purple").is_err());
+        assert!(toml::from_str::<ThemeFile>("base = \"neon\"").is_err());
+        let bad_marker = ThemeFile {
+            last_move_marker: Some("{".into()),
+            ..Default::default()
+        };
+        assert!(Theme::from_file(&bad_marker).is_err());
+    }
+}
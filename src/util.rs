@@ -0,0 +1,131 @@
+//! File helpers shared by everything that saves results, kifu and caches,
+//! and the clock the engine times itself with
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Monotonic clock for search time limits and profiling; the std clock is not
+/// available in browsers, so wasm32 builds read `performance.now()` instead
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
+
+/// Write `contents` to `path` so that readers (and a later run after a crash)
+/// see either the previous file or the complete new one, never a truncated
+/// file: the data goes to a temporary file in the same directory, is synced to
+/// disk, and then renamed over `path`.
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let tmp = temp_path(path);
+    let result = write_synced(&tmp, contents.as_ref()).and_then(|()| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        std::fs::remove_file(&tmp).ok();
+        return result;
+    }
+
+    // Make the rename itself durable (not possible for directories on Windows)
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Ok(dir) = std::fs::File::open(dir) {
+            dir.sync_all().ok();
+        }
+    }
+    Ok(())
+}
+
+/// [`atomic_write`] of a value as JSON
+pub fn atomic_write_json<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+    pretty: bool,
+) -> anyhow::Result<()> {
+    let json = if pretty {
+        serde_json::to_vec_pretty(value)?
+    } else {
+        serde_json::to_vec(value)?
+    };
+    atomic_write(path, json)?;
+    Ok(())
+}
+
+/// Read a JSON file this program wrote. A file that is cut off or not JSON at
+/// all (left behind by a run killed before writes were atomic) is skipped with
+/// a warning and gives `Ok(None)`; valid JSON of the wrong shape is an error.
+pub fn read_json_or_skip<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Option<T>> {
+    let content = std::fs::read_to_string(path)?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.is_eof() || e.is_syntax() => {
+            eprintln!(
+                "Warning: skipping corrupt file {} ({})\r",
+                path.display(),
+                e
+            );
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A settings file the user writes: TOML for `.toml` files, JSON otherwise
+pub fn load_config<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let text = std::fs::read_to_string(path)?;
+    let value = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(&text)?
+    } else {
+        serde_json::from_str(&text)?
+    };
+    Ok(value)
+}
+
+/// `.{name}.{pid}.{n}.tmp` next to `path`, unique across threads and processes
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atomic_write_replaces_and_skips_corrupt() {
+        let dir = std::env::temp_dir().join(format!("aho_util_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("value.json");
+
+        atomic_write_json(&path, &vec![1, 2], false).unwrap();
+        atomic_write_json(&path, &vec![3], true).unwrap();
+        let value: Option<Vec<i32>> = read_json_or_skip(&path).unwrap();
+        let leftovers = std::fs::read_dir(&dir).unwrap().count();
+
+        std::fs::write(&path, "[1, 2").unwrap();
+        let truncated: Option<Vec<i32>> = read_json_or_skip(&path).unwrap();
+        std::fs::write(&path, "{\"a\": 1}").unwrap();
+        let wrong_shape = read_json_or_skip::<Vec<i32>>(&path);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(value, Some(vec![3]));
+        assert_eq!(leftovers, 1, "no temporary files left behind");
+        assert_eq!(truncated, None);
+        assert!(wrong_shape.is_err());
+    }
+}
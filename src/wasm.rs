@@ -0,0 +1,99 @@
+//! Browser bindings (`wasm` feature)
+//!
+//! Boards, moves and search results cross the boundary as JSON in the same
+//! format as kifu files and network play, so a front end can keep the board
+//! it gets back and pass it to the next call. Players are `"Player1"` or
+//! `"Player2"`. Errors become JavaScript exceptions.
+
+use crate::core::{Board, Move, PlayerId};
+use wasm_bindgen::prelude::*;
+
+fn parse_player(player: &str) -> anyhow::Result<PlayerId> {
+    serde_json::from_value(serde_json::Value::String(player.to_string()))
+        .map_err(|_| anyhow::anyhow!("unknown player '{}' (Player1 or Player2)", player))
+}
+
+fn new_board_json(setup: &str) -> anyhow::Result<String> {
+    let setup = crate::BoardSetupType::from_name(setup)
+        .ok_or_else(|| anyhow::anyhow!("unknown board setup '{}'", setup))?;
+    Ok(serde_json::to_string(&setup.create_board())?)
+}
+
+fn legal_moves_json(board: &str, player: &str) -> anyhow::Result<String> {
+    let board: Board = serde_json::from_str(board)?;
+    let moves = crate::logic::legal_moves(&board, parse_player(player)?);
+    Ok(serde_json::to_string(&moves)?)
+}
+
+fn apply_move_json(board: &str, mv: &str, player: &str) -> anyhow::Result<String> {
+    let board: Board = serde_json::from_str(board)?;
+    let mv: Move = serde_json::from_str(mv)?;
+    let player = parse_player(player)?;
+    anyhow::ensure!(
+        crate::logic::legal_moves(&board, player).contains(&mv),
+        "{} is not a legal move for {:?}",
+        mv,
+        player
+    );
+    Ok(serde_json::to_string(&crate::logic::apply_move(
+        &board, &mv, player,
+    ))?)
+}
+
+fn search_json(board: &str, player: &str, depth: usize) -> anyhow::Result<String> {
+    let board: Board = serde_json::from_str(board)?;
+    let result = crate::search(&board, parse_player(player)?, depth);
+    Ok(serde_json::to_string(&result)?)
+}
+
+fn to_js(result: anyhow::Result<String>) -> Result<String, JsError> {
+    result.map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Board JSON of a named setup ("ShogiOnly", "ChessOnly", "Fair", ...)
+#[wasm_bindgen(js_name = newBoard)]
+pub fn new_board(setup: &str) -> Result<String, JsError> {
+    to_js(new_board_json(setup))
+}
+
+/// JSON array of the legal moves of `player` on `board`
+#[wasm_bindgen(js_name = legalMoves)]
+pub fn legal_moves(board: &str, player: &str) -> Result<String, JsError> {
+    to_js(legal_moves_json(board, player))
+}
+
+/// Board JSON after `player` plays the move `mv` (JSON); illegal moves are refused
+#[wasm_bindgen(js_name = applyMove)]
+pub fn apply_move(board: &str, mv: &str, player: &str) -> Result<String, JsError> {
+    to_js(apply_move_json(board, mv, player))
+}
+
+/// Search `board` for `player` to `depth` plies: JSON with `best_move`,
+/// `score` (for `player`), `depth`, `pv` and `nodes`, or `null` when
+/// `player` has no legal move
+#[wasm_bindgen]
+pub fn search(board: &str, player: &str, depth: usize) -> Result<String, JsError> {
+    to_js(search_json(board, player, depth))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let board = new_board_json("ShogiOnly").unwrap();
+        let moves: Vec<Move> =
+            serde_json::from_str(&legal_moves_json(&board, "Player1").unwrap()).unwrap();
+        assert_eq!(moves.len(), 30);
+
+        let mv = serde_json::to_string(&moves[0]).unwrap();
+        let after = apply_move_json(&board, &mv, "Player1").unwrap();
+        assert!(apply_move_json(&after, &mv, "Player1").is_err());
+        assert!(legal_moves_json(&after, "Player3").is_err());
+
+        let result: serde_json::Value =
+            serde_json::from_str(&search_json(&after, "Player2", 1).unwrap()).unwrap();
+        assert!(result["best_move"].is_object());
+    }
+}